@@ -0,0 +1,190 @@
+use anyhow::{Context, Result};
+use reqwest::Client;
+use serde_json::json;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::ai::util::strip_think_tags;
+use crate::ai_batch::downscale::read_image_base64_downscaled;
+
+const AESTHETIC_PROMPT: &str = r#"Rate the aesthetic quality of this image on a scale from 1 to 10, where 1 is very poor and 10 is exceptional. Consider composition, lighting, color harmony, detail, and overall visual appeal. Reply with only the number."#;
+
+/// Ask a vision model for a 1–10 aesthetic score of an image, downscaled so
+/// its longest side is at most `max_dimension` when set.
+pub async fn score_aesthetic(
+    client: &Client,
+    endpoint: &str,
+    model: &str,
+    image_path: &Path,
+    max_dimension: Option<u32>,
+) -> Result<f64> {
+    let image_b64 = read_image_base64_downscaled(image_path, max_dimension)?;
+
+    let body = json!({
+        "model": model,
+        "prompt": AESTHETIC_PROMPT,
+        "images": [image_b64],
+        "stream": false,
+        "options": {
+            "num_predict": 256,
+            "temperature": 0.2,
+        },
+    });
+
+    let url = format!("{}/api/generate", endpoint);
+    let resp = client
+        .post(&url)
+        .timeout(Duration::from_secs(120))
+        .json(&body)
+        .send()
+        .await
+        .with_context(|| format!("Cannot connect to Ollama at {} for scoring", endpoint))?;
+
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let text = resp.text().await.unwrap_or_default();
+        anyhow::bail!("Ollama returned {} for aesthetic scoring: {}", status, text);
+    }
+
+    let json: serde_json::Value = resp
+        .json()
+        .await
+        .context("Failed to parse Ollama response")?;
+    let raw = json.get("response").and_then(|v| v.as_str()).unwrap_or("");
+
+    parse_score(raw).with_context(|| {
+        let excerpt: String = raw.chars().take(200).collect();
+        format!("No 1-10 score found in model response: {}", excerpt)
+    })
+}
+
+/// Pull a single 1–10 score out of free-form model output. An explicit
+/// "N/10" or "N out of 10" wins; otherwise the first number in range that
+/// isn't part of a "1-10" style scale description.
+fn parse_score(text: &str) -> Option<f64> {
+    let text = strip_think_tags(text);
+    let numbers = find_numbers(&text);
+
+    let mut excluded = vec![false; numbers.len()];
+    for (i, &(start, end, _)) in numbers.iter().enumerate() {
+        let before = text[..start].trim_end().to_lowercase();
+        if before.ends_with('/') || before.ends_with("out of") {
+            excluded[i] = true;
+        }
+        if let Some(&(next_start, _, _)) = numbers.get(i + 1) {
+            let between = text[end..next_start].trim().to_lowercase();
+            if matches!(between.as_str(), "-" | "–" | "—" | "to") {
+                excluded[i] = true;
+                excluded[i + 1] = true;
+            }
+        }
+    }
+
+    let in_range = |v: f64| (1.0..=10.0).contains(&v);
+    let explicit = numbers.iter().enumerate().find(|&(i, &(_, end, value))| {
+        let after = text[end..].trim_start().to_lowercase();
+        !excluded[i]
+            && in_range(value)
+            && (after.starts_with("/10")
+                || after.starts_with("/ 10")
+                || after.starts_with("out of 10"))
+    });
+    if let Some((_, &(_, _, value))) = explicit {
+        return Some(value);
+    }
+
+    numbers
+        .iter()
+        .zip(&excluded)
+        .find(|(&(_, _, value), &skip)| !skip && in_range(value))
+        .map(|(&(_, _, value), _)| value)
+}
+
+/// `(start, end, value)` of every unsigned decimal number in `text`.
+fn find_numbers(text: &str) -> Vec<(usize, usize, f64)> {
+    let bytes = text.as_bytes();
+    let mut numbers = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if !bytes[i].is_ascii_digit() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            i += 1;
+        }
+        if i + 1 < bytes.len() && bytes[i] == b'.' && bytes[i + 1].is_ascii_digit() {
+            i += 1;
+            while i < bytes.len() && bytes[i].is_ascii_digit() {
+                i += 1;
+            }
+        }
+        if let Ok(value) = text[start..i].parse::<f64>() {
+            numbers.push((start, i, value));
+        }
+    }
+    numbers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_http::MockServer;
+
+    #[test]
+    fn test_parse_bare_number() {
+        assert_eq!(parse_score("7"), Some(7.0));
+        assert_eq!(parse_score("  8.5\n"), Some(8.5));
+    }
+
+    #[test]
+    fn test_parse_explicit_out_of_ten_wins() {
+        assert_eq!(
+            parse_score("The 2 figures are well placed. Overall: 6/10."),
+            Some(6.0)
+        );
+        assert_eq!(parse_score("I'd say 7 out of 10"), Some(7.0));
+        assert_eq!(parse_score("**Score: 9 / 10**"), Some(9.0));
+    }
+
+    #[test]
+    fn test_parse_skips_scale_description() {
+        assert_eq!(
+            parse_score("On a scale of 1-10, I would give this image a 7."),
+            Some(7.0)
+        );
+        assert_eq!(parse_score("From 1 to 10: 4"), Some(4.0));
+    }
+
+    #[test]
+    fn test_parse_ignores_think_blocks_and_out_of_range() {
+        assert_eq!(
+            parse_score("<think>Maybe a 3? No, the lighting is great.</think>Rating: 8"),
+            Some(8.0)
+        );
+        assert_eq!(parse_score("Rendered at 1024 pixels, score 5"), Some(5.0));
+        assert_eq!(parse_score("I cannot rate this image."), None);
+        assert_eq!(parse_score("0"), None);
+    }
+
+    #[tokio::test]
+    async fn test_score_aesthetic_reads_generate_response() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("img.png");
+        std::fs::write(&path, b"not really a png").unwrap();
+
+        let server = MockServer::start(vec![
+            json!({ "response": "Sure! Aesthetic score: 7.5/10", "done": true }).to_string(),
+        ])
+        .await;
+        let score = score_aesthetic(&Client::new(), &server.endpoint, "llava", &path, None)
+            .await
+            .unwrap();
+        assert_eq!(score, 7.5);
+
+        let requests = server.requests();
+        assert_eq!(requests[0].path, "/api/generate");
+        assert!(requests[0].body.contains(r#""model":"llava""#));
+    }
+}
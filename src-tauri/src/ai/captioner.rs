@@ -4,20 +4,76 @@ use serde_json::json;
 use std::path::Path;
 use std::time::Duration;
 
+use crate::ai::util::strip_think_tags;
+use crate::ai_batch::downscale::read_image_base64_downscaled;
+
 const CAPTION_PROMPT: &str = r#"Describe this image in 1-2 sentences. Focus on the main subject, art style, composition, lighting, and mood. Be specific and concise. Do not start with "This image shows" or "The image depicts". Just describe what you see directly."#;
 
+const IDEA_PROMPT: &str = r#"Use this image as inspiration for a new picture. Write a single short creative idea (one sentence, under 40 words) for an image that captures its subject, mood and style. Do not describe the image literally and do not add any preamble. Reply with only the idea."#;
+
 /// Generate a descriptive caption for an image using Ollama's vision model.
 pub async fn caption_image(
     client: &Client,
     endpoint: &str,
     model: &str,
     image_path: &Path,
+    max_dimension: Option<u32>,
+) -> Result<String> {
+    let raw = describe_image(
+        client,
+        endpoint,
+        model,
+        image_path,
+        max_dimension,
+        CAPTION_PROMPT,
+    )
+    .await?;
+
+    // Strip <think>...</think> blocks from reasoning models
+    let caption = strip_think_tags(&raw).trim().to_string();
+
+    if caption.is_empty() {
+        anyhow::bail!("Ollama returned empty caption");
+    }
+
+    Ok(caption)
+}
+
+/// Turn a reference image into a short idea suitable as pipeline input.
+pub async fn idea_from_image(
+    client: &Client,
+    endpoint: &str,
+    model: &str,
+    image_path: &Path,
+    max_dimension: Option<u32>,
+) -> Result<String> {
+    let raw = describe_image(
+        client,
+        endpoint,
+        model,
+        image_path,
+        max_dimension,
+        IDEA_PROMPT,
+    )
+    .await?;
+    parse_idea(&raw).context("Ollama returned an empty idea")
+}
+
+/// Send an image, downscaled so its longest side is at most `max_dimension`
+/// when set, to a vision model with `prompt` and return its raw reply.
+async fn describe_image(
+    client: &Client,
+    endpoint: &str,
+    model: &str,
+    image_path: &Path,
+    max_dimension: Option<u32>,
+    prompt: &str,
 ) -> Result<String> {
-    let image_b64 = read_image_base64(image_path)?;
+    let image_b64 = read_image_base64_downscaled(image_path, max_dimension)?;
 
     let body = json!({
         "model": model,
-        "prompt": CAPTION_PROMPT,
+        "prompt": prompt,
         "images": [image_b64],
         "stream": false,
         "options": {
@@ -46,46 +102,73 @@ pub async fn caption_image(
         .json()
         .await
         .context("Failed to parse Ollama response")?;
-    let raw = json.get("response").and_then(|v| v.as_str()).unwrap_or("");
-
-    // Strip <think>...</think> blocks from reasoning models
-    let caption = strip_think_tags(raw).trim().to_string();
-
-    if caption.is_empty() {
-        anyhow::bail!("Ollama returned empty caption");
-    }
-
-    Ok(caption)
+    Ok(json
+        .get("response")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string())
 }
 
-/// Strip `<think>...</think>` blocks emitted by reasoning models
-fn strip_think_tags(text: &str) -> String {
-    let mut result = text.to_string();
-    while let Some(start) = result.find("<think>") {
-        if let Some(end) = result[start..].find("</think>") {
-            result = format!("{}{}", &result[..start], &result[start + end + 8..]);
-        } else {
-            result = result[..start].to_string();
+/// Clean a model's idea reply: drop reasoning, an "Idea:" label, wrapping
+/// quotes or bold markers, and fold the rest onto one line.
+fn parse_idea(raw: &str) -> Option<String> {
+    let text = strip_think_tags(raw);
+    let mut idea = text.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    let lower = idea.to_lowercase();
+    for label in ["**idea:**", "idea:", "**idea**:"] {
+        if lower.starts_with(label) {
+            idea = idea[label.len()..].trim_start().to_string();
             break;
         }
     }
-    result
-}
+    let idea = idea
+        .trim_matches(|c: char| c == '"' || c == '*' || c == '\u{201c}' || c == '\u{201d}')
+        .trim();
 
-fn read_image_base64(path: &Path) -> Result<String> {
-    let bytes = std::fs::read(path)
-        .with_context(|| format!("Failed to read image at {}", path.display()))?;
-    Ok(base64::Engine::encode(
-        &base64::engine::general_purpose::STANDARD,
-        &bytes,
-    ))
+    (!idea.is_empty()).then(|| idea.to_string())
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use crate::mock_http::MockServer;
+
     #[test]
     fn test_caption_prompt_not_empty() {
         assert!(!super::CAPTION_PROMPT.is_empty());
         assert!(super::CAPTION_PROMPT.len() > 50);
     }
+
+    #[test]
+    fn test_parse_idea_strips_label_quotes_and_thinking() {
+        assert_eq!(
+            parse_idea("<think>hmm</think>\nIdea: \"A lighthouse keeper's cat\nwatching a storm\"")
+                .as_deref(),
+            Some("A lighthouse keeper's cat watching a storm")
+        );
+        assert_eq!(
+            parse_idea("**Idea:** a neon koi pond at dusk").as_deref(),
+            Some("a neon koi pond at dusk")
+        );
+        assert!(parse_idea("<think>only thoughts</think>  ").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_idea_from_image_uses_idea_prompt() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("ref.png");
+        std::fs::write(&path, b"png").unwrap();
+        let reply = serde_json::json!({"response": "Idea: a fox made of autumn leaves"});
+        let server = MockServer::start(vec![reply.to_string()]).await;
+
+        let idea = idea_from_image(&Client::new(), &server.endpoint, "llava", &path, None)
+            .await
+            .unwrap();
+
+        assert_eq!(idea, "a fox made of autumn leaves");
+        let requests = server.requests();
+        assert_eq!(requests[0].path, "/api/generate");
+        assert!(requests[0].body.contains("creative idea"));
+    }
 }
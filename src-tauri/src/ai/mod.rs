@@ -1,2 +1,4 @@
+pub mod aesthetic;
 pub mod captioner;
 pub mod tagger;
+pub mod util;
@@ -4,6 +4,9 @@ use serde_json::json;
 use std::path::Path;
 use std::time::Duration;
 
+use crate::ai::util::strip_think_tags;
+use crate::ai_batch::downscale::read_image_base64_downscaled;
+
 const TAG_SYSTEM_PROMPT: &str = r#"You are an image tagging assistant. Analyze the provided image and return a JSON array of relevant tags. Each tag should be a single word or short phrase (2-3 words max) that describes a key visual element, style, subject, or mood in the image.
 
 Return ONLY a JSON array of strings. Example: ["portrait", "fantasy", "dark lighting", "woman", "medieval", "oil painting"]
@@ -16,15 +19,17 @@ Return between 5 and 15 tags. Focus on:
 - Composition (close-up, wide shot, symmetrical)
 - Notable elements (fire, water, armor, flowers)"#;
 
-/// Auto-tag an image using Ollama's vision model.
+/// Auto-tag an image using Ollama's vision model, downscaled so its longest
+/// side is at most `max_dimension` when set.
 /// Returns a list of tag strings.
 pub async fn tag_image(
     client: &Client,
     endpoint: &str,
     model: &str,
     image_path: &Path,
+    max_dimension: Option<u32>,
 ) -> Result<Vec<String>> {
-    let image_b64 = read_image_base64(image_path)?;
+    let image_b64 = read_image_base64_downscaled(image_path, max_dimension)?;
 
     let body = json!({
         "model": model,
@@ -115,21 +120,6 @@ fn parse_tags(response: &str) -> Result<Vec<String>> {
     Ok(tags)
 }
 
-/// Strip `<think>...</think>` blocks emitted by reasoning models
-fn strip_think_tags(text: &str) -> String {
-    let mut result = text.to_string();
-    while let Some(start) = result.find("<think>") {
-        if let Some(end) = result[start..].find("</think>") {
-            result = format!("{}{}", &result[..start], &result[start + end + 8..]);
-        } else {
-            // No closing tag — strip from <think> to end
-            result = result[..start].to_string();
-            break;
-        }
-    }
-    result
-}
-
 /// Try parsing as a JSON object and extracting an array from a "tags" key
 fn try_extract_tags_from_object(text: &str) -> Option<Vec<String>> {
     let val: serde_json::Value = serde_json::from_str(text).ok()?;
@@ -190,15 +180,6 @@ fn clean_tags(tags: Vec<String>) -> Vec<String> {
         .collect()
 }
 
-fn read_image_base64(path: &Path) -> Result<String> {
-    let bytes = std::fs::read(path)
-        .with_context(|| format!("Failed to read image at {}", path.display()))?;
-    Ok(base64::Engine::encode(
-        &base64::engine::general_purpose::STANDARD,
-        &bytes,
-    ))
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,30 @@
+/// Strip `<think>...</think>` blocks that reasoning models emit. An unclosed
+/// `<think>` drops everything after it.
+pub fn strip_think_tags(text: &str) -> String {
+    let mut result = text.to_string();
+    while let Some(start) = result.find("<think>") {
+        if let Some(end) = result[start..].find("</think>") {
+            result = format!("{}{}", &result[..start], &result[start + end + 8..]);
+        } else {
+            result = result[..start].to_string();
+            break;
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_think_tags() {
+        assert_eq!(strip_think_tags("<think>hmm</think>answer"), "answer");
+        assert_eq!(
+            strip_think_tags("a<think>x</think>b<think>y</think>c"),
+            "abc"
+        );
+        assert_eq!(strip_think_tags("answer<think>never closed"), "answer");
+        assert_eq!(strip_think_tags("plain"), "plain");
+    }
+}
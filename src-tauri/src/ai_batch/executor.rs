@@ -303,9 +303,17 @@ async fn process_tag(
     image_path: &std::path::Path,
     image_id: &str,
 ) -> Result<()> {
-    let tags = tagger::tag_image(&state.http_client, endpoint, model, image_path)
-        .await
-        .context("Tagging failed")?;
+    let max_dimension = state.config_snapshot()?.hardware.vision_max_dimension();
+    let _gpu = state.exclusive_gpu().await;
+    let tags = tagger::tag_image(
+        &state.http_client,
+        endpoint,
+        model,
+        image_path,
+        max_dimension,
+    )
+    .await
+    .context("Tagging failed")?;
 
     let conn = state.db.lock().map_err(|e| anyhow::anyhow!("{}", e))?;
     for tag_name in &tags {
@@ -321,12 +329,24 @@ async fn process_caption(
     image_path: &std::path::Path,
     image_id: &str,
 ) -> Result<()> {
-    let caption = captioner::caption_image(&state.http_client, endpoint, model, image_path)
-        .await
-        .context("Captioning failed")?;
+    let max_dimension = state.config_snapshot()?.hardware.vision_max_dimension();
+    let _gpu = state.exclusive_gpu().await;
+    let caption = captioner::caption_image(
+        &state.http_client,
+        endpoint,
+        model,
+        image_path,
+        max_dimension,
+    )
+    .await
+    .context("Captioning failed")?;
 
     let conn = state.db.lock().map_err(|e| anyhow::anyhow!("{}", e))?;
     db::images::update_image_caption(&conn, image_id, &caption, false)
         .context("Failed to save caption")?;
     Ok(())
 }
+
+#[cfg(test)]
+#[path = "executor_test.rs"]
+mod tests;
@@ -0,0 +1,52 @@
+use super::*;
+use crate::db::images::tests::make_test_image;
+use crate::mock_http::MockServer;
+use crate::types::config::AppConfig;
+use std::sync::Arc;
+
+async fn tag_while_generating(exclusive_gpu: bool) -> (bool, MockServer) {
+    let conn = db::open_memory_database().unwrap();
+    db::images::insert_image(&conn, &make_test_image("img-1")).unwrap();
+    let mut config = AppConfig::default();
+    config.hardware.exclusive_gpu = exclusive_gpu;
+    let state = Arc::new(AppState::new(conn, config));
+
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("img-1.png");
+    std::fs::write(&path, b"png").unwrap();
+    let reply = serde_json::json!({"response": r#"["cat"]"#});
+    let server = MockServer::start(vec![reply.to_string()]).await;
+
+    // Held the way the queue executor holds it during a generation
+    let generating = state.gpu_lock.lock().await;
+    let task = tokio::spawn({
+        let state = state.clone();
+        let endpoint = server.endpoint.clone();
+        async move { process_tag(&state, &endpoint, "llava", &path, "img-1").await }
+    });
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let finished_while_generating = task.is_finished();
+    if exclusive_gpu {
+        assert!(server.requests().is_empty(), "model called mid-generation");
+    }
+
+    drop(generating);
+    task.await.unwrap().unwrap();
+    let conn = state.db.lock().unwrap();
+    let tags = db::tags::get_image_tags(&conn, "img-1").unwrap();
+    assert_eq!(tags[0].name, "cat");
+    (finished_while_generating, server)
+}
+
+#[tokio::test]
+async fn test_exclusive_gpu_tagging_waits_for_generation() {
+    let (finished_while_generating, server) = tag_while_generating(true).await;
+    assert!(!finished_while_generating);
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[tokio::test]
+async fn test_tagging_runs_alongside_generation_by_default() {
+    let (finished_while_generating, _server) = tag_while_generating(false).await;
+    assert!(finished_while_generating);
+}
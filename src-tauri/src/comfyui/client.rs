@@ -1,16 +1,16 @@
 use anyhow::{Context, Result};
-use futures::StreamExt;
 use reqwest::Client;
 use serde_json::Value;
 use std::time::Duration;
 
-use crate::types::generation::{GenerationStatus, GenerationStatusKind};
-
-fn normalize_endpoint(endpoint: &str) -> &str {
+pub(crate) fn normalize_endpoint(endpoint: &str) -> &str {
     endpoint.trim_end_matches('/')
 }
 
-async fn ensure_success(resp: reqwest::Response, action: &str) -> Result<reqwest::Response> {
+pub(super) async fn ensure_success(
+    resp: reqwest::Response,
+    action: &str,
+) -> Result<reqwest::Response> {
     if resp.status().is_success() {
         return Ok(resp);
     }
@@ -20,12 +20,6 @@ async fn ensure_success(resp: reqwest::Response, action: &str) -> Result<reqwest
     anyhow::bail!("ComfyUI returned {} for {}: {}", status, action, body);
 }
 
-#[derive(Debug, Clone)]
-pub struct ProgressUpdate {
-    pub current_step: u32,
-    pub total_steps: u32,
-}
-
 pub async fn check_health(client: &Client, endpoint: &str) -> Result<bool> {
     let endpoint = normalize_endpoint(endpoint);
     let url = format!("{}/system_stats", endpoint);
@@ -166,9 +160,31 @@ pub async fn get_history(
         status: status_str.to_string(),
         completed,
         image_filenames,
+        // `prompt` is [number, prompt_id, graph, extra_data, outputs]
+        prompt_graph: entry.pointer("/prompt/2").cloned(),
     }))
 }
 
+/// Fetch the full `/object_info` node catalogue (every installed node class
+/// with its input spec). This is a large response; callers should cache it.
+pub async fn get_object_info(client: &Client, endpoint: &str) -> Result<Value> {
+    let endpoint = normalize_endpoint(endpoint);
+    let url = format!("{}/object_info", endpoint);
+
+    let resp = client
+        .get(&url)
+        .timeout(Duration::from_secs(30))
+        .send()
+        .await
+        .context("Failed to fetch ComfyUI object_info")?;
+
+    let resp = ensure_success(resp, "object_info").await?;
+
+    resp.json()
+        .await
+        .context("Failed to parse ComfyUI object_info response")
+}
+
 pub async fn get_image(
     client: &Client,
     endpoint: &str,
@@ -210,39 +226,6 @@ pub async fn get_image(
     Ok(bytes.to_vec())
 }
 
-pub async fn get_queue_status(client: &Client, endpoint: &str) -> Result<QueueStatus> {
-    let endpoint = normalize_endpoint(endpoint);
-    let url = format!("{}/queue", endpoint);
-
-    let resp = client
-        .get(&url)
-        .timeout(Duration::from_secs(5))
-        .send()
-        .await
-        .context("Failed to fetch ComfyUI queue status")?;
-
-    let resp = ensure_success(resp, "queue status").await?;
-
-    let json: Value = resp
-        .json()
-        .await
-        .context("Failed to parse ComfyUI queue response")?;
-
-    let running = json
-        .get("queue_running")
-        .and_then(|v| v.as_array())
-        .map(|a| a.len() as u32)
-        .unwrap_or(0);
-
-    let pending = json
-        .get("queue_pending")
-        .and_then(|v| v.as_array())
-        .map(|a| a.len() as u32)
-        .unwrap_or(0);
-
-    Ok(QueueStatus { running, pending })
-}
-
 pub async fn free_memory(client: &Client, endpoint: &str, unload_models: bool) -> Result<()> {
     let endpoint = normalize_endpoint(endpoint);
     let url = format!("{}/free", endpoint);
@@ -278,201 +261,6 @@ pub async fn interrupt(client: &Client, endpoint: &str) -> Result<()> {
     Ok(())
 }
 
-fn gen_status_failed(prompt_id: &str, error: &str) -> GenerationStatus {
-    GenerationStatus {
-        prompt_id: prompt_id.to_string(),
-        status: GenerationStatusKind::Failed,
-        progress: None,
-        current_step: None,
-        total_steps: None,
-        image_filenames: None,
-        error: Some(error.to_string()),
-    }
-}
-
-async fn fetch_completed_status(
-    client: &Client,
-    endpoint: &str,
-    prompt_id: &str,
-) -> Result<GenerationStatus> {
-    if let Some(history) = get_history(client, endpoint, prompt_id).await? {
-        let filenames: Vec<String> = history
-            .image_filenames
-            .iter()
-            .map(|r| r.filename.clone())
-            .collect();
-        Ok(GenerationStatus {
-            prompt_id: prompt_id.to_string(),
-            status: if history.completed {
-                GenerationStatusKind::Completed
-            } else {
-                GenerationStatusKind::Failed
-            },
-            progress: Some(1.0),
-            current_step: None,
-            total_steps: None,
-            image_filenames: if filenames.is_empty() {
-                None
-            } else {
-                Some(filenames)
-            },
-            error: if !history.completed {
-                Some("Generation failed".to_string())
-            } else {
-                None
-            },
-        })
-    } else {
-        Ok(gen_status_failed(
-            prompt_id,
-            "No history found after generation",
-        ))
-    }
-}
-
-/// Poll history until the prompt completes or fails (fallback when WS unavailable)
-pub async fn wait_for_completion(
-    client: &Client,
-    endpoint: &str,
-    prompt_id: &str,
-    poll_interval: Duration,
-    timeout: Duration,
-) -> Result<GenerationStatus> {
-    let endpoint = normalize_endpoint(endpoint);
-    let start = std::time::Instant::now();
-    loop {
-        if start.elapsed() > timeout {
-            return Ok(gen_status_failed(prompt_id, "Generation timed out"));
-        }
-        if let Some(history) = get_history(client, endpoint, prompt_id).await? {
-            if history.completed {
-                return fetch_completed_status(client, endpoint, prompt_id).await;
-            } else if history.status == "error" {
-                return Ok(gen_status_failed(prompt_id, "ComfyUI generation failed"));
-            }
-        }
-        tokio::time::sleep(poll_interval).await;
-    }
-}
-
-/// Wait for completion using ComfyUI's WebSocket for real-time step progress.
-/// Calls `on_progress` for each sampling step. Falls back to polling on WS failure.
-pub async fn wait_for_completion_ws<F>(
-    client: &Client,
-    endpoint: &str,
-    prompt_id: &str,
-    client_id: &str,
-    timeout: Duration,
-    mut on_progress: F,
-) -> Result<GenerationStatus>
-where
-    F: FnMut(ProgressUpdate),
-{
-    let endpoint = normalize_endpoint(endpoint);
-    let ws_url = format!(
-        "{}/ws?clientId={}",
-        endpoint
-            .replace("http://", "ws://")
-            .replace("https://", "wss://"),
-        client_id
-    );
-    let (mut ws, _) = match tokio_tungstenite::connect_async(&ws_url).await {
-        Ok(c) => c,
-        Err(e) => {
-            eprintln!("[comfyui] WS failed: {}, falling back to polling", e);
-            return wait_for_completion(
-                client,
-                endpoint,
-                prompt_id,
-                Duration::from_secs(2),
-                timeout,
-            )
-            .await;
-        }
-    };
-
-    let start = std::time::Instant::now();
-    let mut our_msg_count: usize = 0;
-    const MAX_OUR_MESSAGES: usize = 10_000;
-    let mut total_msg_count: usize = 0;
-    const MAX_TOTAL_MESSAGES: usize = 50_000;
-
-    while let Ok(Some(msg)) = tokio::time::timeout(Duration::from_secs(30), ws.next()).await {
-        total_msg_count += 1;
-        if total_msg_count > MAX_TOTAL_MESSAGES {
-            eprintln!(
-                "[comfyui] WS exceeded {} total message limit (busy shared instance?), falling back to polling",
-                MAX_TOTAL_MESSAGES
-            );
-            break;
-        }
-        if start.elapsed() > timeout {
-            return Ok(gen_status_failed(prompt_id, "Generation timed out"));
-        }
-        let text = match msg {
-            Ok(m) if m.is_text() => m.into_text().unwrap_or_default(),
-            Ok(_) => continue,
-            Err(_) => break,
-        };
-        let json: Value = match serde_json::from_str(&text) {
-            Ok(j) => j,
-            Err(_) => continue,
-        };
-        let msg_type = json.get("type").and_then(|v| v.as_str()).unwrap_or("");
-        let data = json.get("data");
-        let pid = data
-            .and_then(|d| d.get("prompt_id"))
-            .and_then(|v| v.as_str());
-        if pid.is_some() && pid != Some(prompt_id) {
-            continue;
-        }
-        // Only count messages for our prompt toward the per-prompt limit
-        if pid == Some(prompt_id) {
-            our_msg_count += 1;
-            if our_msg_count > MAX_OUR_MESSAGES {
-                eprintln!(
-                    "[comfyui] Prompt {} exceeded {} message limit, falling back to polling",
-                    prompt_id, MAX_OUR_MESSAGES
-                );
-                break;
-            }
-        }
-        match msg_type {
-            "progress" => {
-                if let Some(d) = data {
-                    let val = d.get("value").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
-                    let max = d.get("max").and_then(|v| v.as_u64()).unwrap_or(1) as u32;
-                    on_progress(ProgressUpdate {
-                        current_step: val,
-                        total_steps: max,
-                    });
-                }
-            }
-            "executing"
-                if data
-                    .and_then(|d| d.get("node"))
-                    .map(|v| v.is_null())
-                    .unwrap_or(false) =>
-            {
-                return fetch_completed_status(client, endpoint, prompt_id).await;
-            }
-            "execution_error" => {
-                let err = data
-                    .and_then(|d| d.get("exception_message"))
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown error");
-                return Ok(gen_status_failed(
-                    prompt_id,
-                    &format!("ComfyUI error: {}", err),
-                ));
-            }
-            _ => {}
-        }
-    }
-    // WS closed unexpectedly — fall back to polling
-    wait_for_completion(client, endpoint, prompt_id, Duration::from_secs(2), timeout).await
-}
-
 #[derive(Debug, Clone)]
 pub struct ImageRef {
     pub filename: String,
@@ -485,13 +273,8 @@ pub struct PromptHistory {
     pub status: String,
     pub completed: bool,
     pub image_filenames: Vec<ImageRef>,
-}
-
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct QueueStatus {
-    pub running: u32,
-    pub pending: u32,
+    /// The API-format graph ComfyUI actually executed.
+    pub prompt_graph: Option<Value>,
 }
 
 #[cfg(test)]
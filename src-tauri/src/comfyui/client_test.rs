@@ -85,13 +85,19 @@ fn test_image_ref_struct() {
     assert_eq!(img.filename, "test.png");
 }
 
-#[test]
-fn test_queue_status_serialization() {
-    let status = QueueStatus {
-        running: 1,
-        pending: 3,
-    };
-    let json = serde_json::to_string(&status).unwrap();
-    assert!(json.contains("\"running\":1"));
-    assert!(json.contains("\"pending\":3"));
+#[tokio::test]
+async fn test_get_history_keeps_executed_graph() {
+    let body = serde_json::json!({"p1": {
+        "prompt": [3, "p1", {"5": {"class_type": "KSampler", "inputs": {"sampler_name": "dpmpp_2m"}}}, {}, ["9"]],
+        "status": {"status_str": "success", "completed": true},
+        "outputs": {}
+    }});
+    let server = crate::mock_http::MockServer::start(vec![body.to_string()]).await;
+
+    let history = get_history(&Client::new(), &server.endpoint, "p1")
+        .await
+        .unwrap()
+        .unwrap();
+    let graph = history.prompt_graph.unwrap();
+    assert_eq!(graph["5"]["inputs"]["sampler_name"], "dpmpp_2m");
 }
@@ -0,0 +1,207 @@
+use anyhow::{Context, Result};
+use futures::StreamExt;
+use reqwest::Client;
+use serde_json::Value;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::client::normalize_endpoint;
+
+/// Stream ComfyUI's console to `on_line` until `stop` is set or the socket
+/// closes. Newer ComfyUI builds push terminal output as `logs` messages once
+/// a client subscribes; older ones only send `execution_*` events, which are
+/// rendered as lines too.
+pub async fn tail_logs<F>(
+    client: &Client,
+    endpoint: &str,
+    stop: Arc<AtomicBool>,
+    mut on_line: F,
+) -> Result<()>
+where
+    F: FnMut(String),
+{
+    let endpoint = normalize_endpoint(endpoint);
+    let client_id = uuid::Uuid::new_v4().to_string();
+    let ws_url = format!(
+        "{}/ws?clientId={}",
+        endpoint
+            .replace("http://", "ws://")
+            .replace("https://", "wss://"),
+        client_id
+    );
+    let (mut ws, _) = tokio_tungstenite::connect_async(&ws_url)
+        .await
+        .with_context(|| format!("Failed to open ComfyUI WebSocket at {}", ws_url))?;
+
+    // Best-effort: builds without the log endpoint still send execution events
+    let subscribe = client
+        .patch(format!("{}/internal/logs/subscribe", endpoint))
+        .timeout(Duration::from_secs(5))
+        .json(&serde_json::json!({ "enabled": true, "clientId": client_id }))
+        .send()
+        .await
+        .and_then(|resp| resp.error_for_status());
+    if let Err(e) = subscribe {
+        eprintln!("[comfyui] Log subscription unavailable: {}", e);
+    }
+
+    while !stop.load(Ordering::Relaxed) {
+        let msg = match tokio::time::timeout(Duration::from_secs(1), ws.next()).await {
+            Ok(Some(Ok(m))) => m,
+            Ok(Some(Err(_))) | Ok(None) => break,
+            Err(_) => continue, // read timeout — re-check the stop flag
+        };
+        if !msg.is_text() {
+            continue;
+        }
+        let text = msg.into_text().unwrap_or_default();
+        for line in log_lines_from_ws_message(&text) {
+            on_line(line);
+        }
+    }
+    Ok(())
+}
+
+/// Turn one ComfyUI WebSocket message into console lines. Messages that
+/// carry no log-worthy information (status, progress, previews) yield none.
+pub fn log_lines_from_ws_message(text: &str) -> Vec<String> {
+    let Ok(json) = serde_json::from_str::<Value>(text) else {
+        return Vec::new();
+    };
+    let msg_type = json.get("type").and_then(|v| v.as_str()).unwrap_or("");
+    let data = json.get("data").cloned().unwrap_or(Value::Null);
+    let str_field = |key: &str| data.get(key).and_then(|v| v.as_str()).unwrap_or("?");
+
+    match msg_type {
+        "logs" => data
+            .get("entries")
+            .and_then(|v| v.as_array())
+            .map(|entries| {
+                entries
+                    .iter()
+                    .filter_map(|e| e.get("m").and_then(|m| m.as_str()))
+                    .flat_map(str::lines)
+                    .map(|l| l.trim_end().to_string())
+                    .filter(|l| !l.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default(),
+        "execution_start" => vec![format!(
+            "Execution started (prompt {})",
+            str_field("prompt_id")
+        )],
+        "execution_cached" => {
+            let nodes: Vec<&str> = data
+                .get("nodes")
+                .and_then(|v| v.as_array())
+                .map(|a| a.iter().filter_map(|n| n.as_str()).collect())
+                .unwrap_or_default();
+            if nodes.is_empty() {
+                Vec::new()
+            } else {
+                vec![format!("Cached nodes: {}", nodes.join(", "))]
+            }
+        }
+        "executing" => match data.get("node").and_then(|v| v.as_str()) {
+            Some(node) => vec![format!("Executing node {}", node)],
+            None => Vec::new(),
+        },
+        "execution_success" => vec![format!(
+            "Execution finished (prompt {})",
+            str_field("prompt_id")
+        )],
+        "execution_interrupted" => {
+            vec![format!(
+                "Execution interrupted at node {}",
+                str_field("node_id")
+            )]
+        }
+        "execution_error" => {
+            let mut lines = vec![format!(
+                "Error in node {} ({}): {}",
+                str_field("node_id"),
+                str_field("node_type"),
+                str_field("exception_message").trim()
+            )];
+            if let Some(tb) = data.get("traceback").and_then(|v| v.as_array()) {
+                lines.extend(
+                    tb.iter()
+                        .filter_map(|l| l.as_str())
+                        .flat_map(str::lines)
+                        .map(|l| l.trim_end().to_string())
+                        .filter(|l| !l.is_empty()),
+                );
+            }
+            lines
+        }
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_logs_message_splits_entries_into_lines() {
+        let msg = r#"{"type": "logs", "data": {"entries": [
+            {"t": "2026-01-15T10:00:00", "m": "got prompt\n"},
+            {"t": "2026-01-15T10:00:01", "m": "model_type EPS\nUsing pytorch attention\n\n"}
+        ], "size": {"cols": 120, "rows": 40}}}"#;
+        assert_eq!(
+            log_lines_from_ws_message(msg),
+            vec!["got prompt", "model_type EPS", "Using pytorch attention"]
+        );
+    }
+
+    #[test]
+    fn test_execution_error_includes_traceback() {
+        let msg = r#"{"type": "execution_error", "data": {
+            "prompt_id": "abc", "node_id": "5", "node_type": "KSampler",
+            "exception_message": "CUDA out of memory\n",
+            "traceback": ["Traceback (most recent call last):\n", "  File \"nodes.py\", line 1\n"]
+        }}"#;
+        assert_eq!(
+            log_lines_from_ws_message(msg),
+            vec![
+                "Error in node 5 (KSampler): CUDA out of memory",
+                "Traceback (most recent call last):",
+                "  File \"nodes.py\", line 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_execution_lifecycle_messages() {
+        let lines = |m: &str| log_lines_from_ws_message(m);
+        assert_eq!(
+            lines(r#"{"type": "execution_start", "data": {"prompt_id": "abc"}}"#),
+            vec!["Execution started (prompt abc)"]
+        );
+        assert_eq!(
+            lines(
+                r#"{"type": "execution_cached", "data": {"nodes": ["1", "2"], "prompt_id": "abc"}}"#
+            ),
+            vec!["Cached nodes: 1, 2"]
+        );
+        assert_eq!(
+            lines(r#"{"type": "executing", "data": {"node": "5", "prompt_id": "abc"}}"#),
+            vec!["Executing node 5"]
+        );
+        assert!(lines(r#"{"type": "executing", "data": {"node": null}}"#).is_empty());
+    }
+
+    #[test]
+    fn test_noise_and_garbage_yield_no_lines() {
+        assert!(log_lines_from_ws_message(
+            r#"{"type": "progress", "data": {"value": 3, "max": 20}}"#
+        )
+        .is_empty());
+        assert!(log_lines_from_ws_message(
+            r#"{"type": "status", "data": {"status": {"exec_info": {"queue_remaining": 0}}}}"#
+        )
+        .is_empty());
+        assert!(log_lines_from_ws_message("not json").is_empty());
+    }
+}
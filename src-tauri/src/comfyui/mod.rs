@@ -1,3 +1,13 @@
 pub mod client;
+pub mod completion;
+pub mod logs;
 pub mod models;
+pub mod object_info;
+pub mod preview;
+pub mod prompt_queue;
+pub mod retry;
+pub mod smoke;
+pub mod system_stats;
+pub mod upload;
+pub mod vram;
 pub mod workflow;
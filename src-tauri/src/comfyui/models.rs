@@ -3,9 +3,7 @@ use reqwest::Client;
 use serde_json::Value;
 use std::time::Duration;
 
-fn normalize_endpoint(endpoint: &str) -> &str {
-    endpoint.trim_end_matches('/')
-}
+use super::client::normalize_endpoint;
 
 /// Discover available checkpoints from ComfyUI via /object_info endpoint.
 /// This queries the CheckpointLoaderSimple node to find which checkpoints are installed.
@@ -118,6 +116,74 @@ pub async fn list_schedulers(client: &Client, endpoint: &str) -> Result<Vec<Stri
     Ok(schedulers)
 }
 
+/// Discover installed LoRAs from ComfyUI's LoraLoader node
+pub async fn list_loras(client: &Client, endpoint: &str) -> Result<Vec<String>> {
+    let endpoint = normalize_endpoint(endpoint);
+    let url = format!("{}/object_info/LoraLoader", endpoint);
+
+    let resp = client
+        .get(&url)
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await
+        .context("Failed to fetch LoraLoader info from ComfyUI")?;
+
+    if !resp.status().is_success() {
+        return Ok(Vec::new());
+    }
+
+    let json: Value = resp
+        .json()
+        .await
+        .context("Failed to parse LoraLoader object_info")?;
+
+    let loras = json
+        .pointer("/LoraLoader/input/required/lora_name/0")
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(loras)
+}
+
+/// Discover installed ControlNet models from ComfyUI's ControlNetLoader node
+pub async fn list_controlnets(client: &Client, endpoint: &str) -> Result<Vec<String>> {
+    let endpoint = normalize_endpoint(endpoint);
+    let url = format!("{}/object_info/ControlNetLoader", endpoint);
+
+    let resp = client
+        .get(&url)
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await
+        .context("Failed to fetch ControlNetLoader info from ComfyUI")?;
+
+    if !resp.status().is_success() {
+        return Ok(Vec::new());
+    }
+
+    let json: Value = resp
+        .json()
+        .await
+        .context("Failed to parse ControlNetLoader object_info")?;
+
+    let controlnets = json
+        .pointer("/ControlNetLoader/input/required/control_net_name/0")
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(controlnets)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -211,4 +277,52 @@ mod tests {
 
         assert!(checkpoints.is_empty());
     }
+
+    #[tokio::test]
+    async fn test_list_loras() {
+        let server = crate::mock_http::MockServer::start(vec![r#"{
+            "LoraLoader": {"input": {"required": {
+                "lora_name": [["detail.safetensors", "style/ink.safetensors"]],
+                "strength_model": ["FLOAT", {"default": 1.0}]
+            }}}
+        }"#
+        .to_string()])
+        .await;
+
+        let loras = list_loras(&Client::new(), &server.endpoint).await.unwrap();
+        assert_eq!(loras, vec!["detail.safetensors", "style/ink.safetensors"]);
+        assert_eq!(server.requests()[0].path, "/object_info/LoraLoader");
+
+        // No LoraLoader (404) means no LoRAs rather than an error
+        assert!(list_loras(&Client::new(), &server.endpoint)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_list_controlnets() {
+        let server = crate::mock_http::MockServer::start(vec![r#"{
+            "ControlNetLoader": {"input": {"required": {
+                "control_net_name": [["control_canny.pth", "sdxl/depth.safetensors"]]
+            }}}
+        }"#
+        .to_string()])
+        .await;
+
+        let controlnets = list_controlnets(&Client::new(), &server.endpoint)
+            .await
+            .unwrap();
+        assert_eq!(
+            controlnets,
+            vec!["control_canny.pth", "sdxl/depth.safetensors"]
+        );
+        assert_eq!(server.requests()[0].path, "/object_info/ControlNetLoader");
+
+        // ControlNet support not installed (404) means an empty list
+        assert!(list_controlnets(&Client::new(), &server.endpoint)
+            .await
+            .unwrap()
+            .is_empty());
+    }
 }
@@ -0,0 +1,104 @@
+use anyhow::Result;
+use serde_json::Value;
+use std::sync::Arc;
+
+use super::client;
+use crate::state::AppState;
+
+/// Return ComfyUI's `/object_info`, fetching it only on first use, after the
+/// endpoint changes, or when `refresh` is set (e.g. new custom nodes installed).
+pub async fn get_cached(state: &AppState, endpoint: &str, refresh: bool) -> Result<Arc<Value>> {
+    if !refresh {
+        let cache = state
+            .object_info_cache
+            .lock()
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        if let Some((cached_endpoint, info)) = cache.as_ref() {
+            if cached_endpoint == endpoint {
+                return Ok(info.clone());
+            }
+        }
+    }
+
+    // Fetch without holding the lock; a concurrent duplicate fetch is harmless
+    let info = Arc::new(client::get_object_info(&state.http_client, endpoint).await?);
+    let mut cache = state
+        .object_info_cache
+        .lock()
+        .map_err(|e| anyhow::anyhow!("{}", e))?;
+    *cache = Some((endpoint.to_string(), info.clone()));
+    Ok(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::comfyui::workflow;
+    use crate::mock_http::MockServer;
+    use crate::types::config::AppConfig;
+
+    fn object_info_body() -> String {
+        serde_json::json!({
+            "KSampler": {
+                "input": {
+                    "required": {
+                        "seed": ["INT", {}],
+                        "steps": ["INT", {}],
+                        "model": ["MODEL"]
+                    }
+                }
+            },
+            "SaveImage": {
+                "input": { "required": { "images": ["IMAGE"] } }
+            }
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_object_info_fetched_once_and_cached() {
+        let server = MockServer::start(vec![object_info_body(), object_info_body()]).await;
+        let state = AppState::new(
+            crate::db::open_memory_database().unwrap(),
+            AppConfig::default(),
+        );
+
+        let first = get_cached(&state, &server.endpoint, false).await.unwrap();
+        let second = get_cached(&state, &server.endpoint, false).await.unwrap();
+        assert!(first.get("KSampler").is_some());
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(server.requests().len(), 1);
+        assert_eq!(server.requests()[0].path, "/object_info");
+
+        get_cached(&state, &server.endpoint, true).await.unwrap();
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[test]
+    fn test_validate_flags_unknown_class_type() {
+        let info: Value = serde_json::from_str(&object_info_body()).unwrap();
+        let wf = serde_json::json!({
+            "1": { "class_type": "KSampler", "inputs": { "seed": 1, "steps": 20, "model": ["2", 0] } },
+            "2": { "class_type": "FancyCustomLoader", "inputs": {} },
+            "3": { "class_type": "SaveImage", "inputs": {} }
+        });
+
+        let problems = workflow::validate_against(&info, &wf);
+        assert_eq!(
+            problems,
+            vec![
+                "Node 2: unknown node type 'FancyCustomLoader'",
+                "Node 3 (SaveImage): missing required input 'images'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_accepts_complete_workflow() {
+        let info: Value = serde_json::from_str(&object_info_body()).unwrap();
+        let wf = serde_json::json!({
+            "9": { "class_type": "SaveImage", "inputs": { "images": ["8", 0] } }
+        });
+        assert!(workflow::validate_against(&info, &wf).is_empty());
+    }
+}
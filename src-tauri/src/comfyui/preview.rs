@@ -0,0 +1,62 @@
+/// Binary WS event type ComfyUI uses for sampler previews.
+const WS_PREVIEW_IMAGE: u32 = 1;
+
+/// A sampler preview decoded from a binary WebSocket frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreviewImage {
+    pub mime: &'static str,
+    pub bytes: Vec<u8>,
+}
+
+/// Decode ComfyUI's binary preview frame: a big-endian u32 event type, a
+/// u32 image format (1 = JPEG, 2 = PNG), then the image bytes. Other event
+/// types and truncated frames give `None`.
+pub fn decode_preview_frame(frame: &[u8]) -> Option<PreviewImage> {
+    let event = u32::from_be_bytes(frame.get(0..4)?.try_into().ok()?);
+    let format = u32::from_be_bytes(frame.get(4..8)?.try_into().ok()?);
+    if event != WS_PREVIEW_IMAGE {
+        return None;
+    }
+    let mime = match format {
+        1 => "image/jpeg",
+        2 => "image/png",
+        _ => return None,
+    };
+    let bytes = frame.get(8..).filter(|b| !b.is_empty())?.to_vec();
+    Some(PreviewImage { mime, bytes })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_preview_frame() {
+        let jpeg = [0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10];
+        let mut frame = vec![0, 0, 0, 1, 0, 0, 0, 1];
+        frame.extend_from_slice(&jpeg);
+        assert_eq!(
+            decode_preview_frame(&frame),
+            Some(PreviewImage {
+                mime: "image/jpeg",
+                bytes: jpeg.to_vec(),
+            })
+        );
+
+        let png = [0x89, b'P', b'N', b'G'];
+        let mut frame = vec![0, 0, 0, 1, 0, 0, 0, 2];
+        frame.extend_from_slice(&png);
+        assert_eq!(decode_preview_frame(&frame).unwrap().mime, "image/png");
+    }
+
+    #[test]
+    fn test_decode_preview_frame_rejects_other_frames() {
+        // Not a preview event
+        assert!(decode_preview_frame(&[0, 0, 0, 2, 0, 0, 0, 1, 0xFF]).is_none());
+        // Unknown image format
+        assert!(decode_preview_frame(&[0, 0, 0, 1, 0, 0, 0, 9, 0xFF]).is_none());
+        // Header only, or truncated header
+        assert!(decode_preview_frame(&[0, 0, 0, 1, 0, 0, 0, 1]).is_none());
+        assert!(decode_preview_frame(&[0, 0, 0, 1]).is_none());
+    }
+}
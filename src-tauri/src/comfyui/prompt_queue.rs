@@ -0,0 +1,163 @@
+use anyhow::{Context, Result};
+use reqwest::Client;
+use serde_json::Value;
+use std::time::Duration;
+
+use super::client::{ensure_success, interrupt, normalize_endpoint};
+
+pub async fn get_queue_status(client: &Client, endpoint: &str) -> Result<QueueStatus> {
+    let json = fetch_queue(client, endpoint).await?;
+
+    let running = json
+        .get("queue_running")
+        .and_then(|v| v.as_array())
+        .map(|a| a.len() as u32)
+        .unwrap_or(0);
+
+    let pending = json
+        .get("queue_pending")
+        .and_then(|v| v.as_array())
+        .map(|a| a.len() as u32)
+        .unwrap_or(0);
+
+    Ok(QueueStatus { running, pending })
+}
+
+async fn fetch_queue(client: &Client, endpoint: &str) -> Result<Value> {
+    let endpoint = normalize_endpoint(endpoint);
+    let url = format!("{}/queue", endpoint);
+
+    let resp = client
+        .get(&url)
+        .timeout(Duration::from_secs(5))
+        .send()
+        .await
+        .context("Failed to fetch ComfyUI queue status")?;
+
+    let resp = ensure_success(resp, "queue status").await?;
+
+    resp.json()
+        .await
+        .context("Failed to parse ComfyUI queue response")
+}
+
+/// Whether ComfyUI is executing `prompt_id` right now. Each `queue_running`
+/// entry is `[number, prompt_id, graph, ...]`.
+pub async fn is_prompt_running(client: &Client, endpoint: &str, prompt_id: &str) -> Result<bool> {
+    let json = fetch_queue(client, endpoint).await?;
+    Ok(json
+        .get("queue_running")
+        .and_then(|v| v.as_array())
+        .is_some_and(|running| {
+            running
+                .iter()
+                .any(|item| item.get(1).and_then(|id| id.as_str()) == Some(prompt_id))
+        }))
+}
+
+/// Remove a pending prompt from ComfyUI's queue. A prompt that has already
+/// started is unaffected; see [`cancel_prompt`].
+pub async fn delete_queued(client: &Client, endpoint: &str, prompt_id: &str) -> Result<()> {
+    let endpoint = normalize_endpoint(endpoint);
+    let url = format!("{}/queue", endpoint);
+    let resp = client
+        .post(&url)
+        .timeout(Duration::from_secs(5))
+        .json(&delete_queued_body(prompt_id))
+        .send()
+        .await
+        .context("Failed to delete prompt from ComfyUI queue")?;
+    ensure_success(resp, "queue delete").await?;
+    Ok(())
+}
+
+fn delete_queued_body(prompt_id: &str) -> Value {
+    serde_json::json!({ "delete": [prompt_id] })
+}
+
+/// Stop `prompt_id` without touching anyone else's prompts: a pending one is
+/// deleted from the queue, and only when it is the one executing does this
+/// fall back to `/interrupt`, which stops whatever is running. When the
+/// queue can't be read the prompt is assumed to be running.
+pub async fn cancel_prompt(client: &Client, endpoint: &str, prompt_id: &str) -> Result<()> {
+    let running = is_prompt_running(client, endpoint, prompt_id)
+        .await
+        .unwrap_or(true);
+    if running {
+        interrupt(client, endpoint).await
+    } else {
+        delete_queued(client, endpoint, prompt_id).await
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueStatus {
+    pub running: u32,
+    pub pending: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue_status_serialization() {
+        let status = QueueStatus {
+            running: 1,
+            pending: 3,
+        };
+        let json = serde_json::to_string(&status).unwrap();
+        assert!(json.contains("\"running\":1"));
+        assert!(json.contains("\"pending\":3"));
+    }
+
+    #[test]
+    fn test_delete_queued_body() {
+        assert_eq!(
+            delete_queued_body("prompt-abc"),
+            serde_json::json!({"delete": ["prompt-abc"]})
+        );
+    }
+
+    fn queue_with_running(prompt_id: &str) -> String {
+        serde_json::json!({
+            "queue_running": [[7, prompt_id, {}, {}, ["9"]]],
+            "queue_pending": [[8, "mine", {}, {}, ["9"]]]
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_cancel_pending_prompt_deletes_it_from_queue() {
+        let server = crate::mock_http::MockServer::start(vec![
+            queue_with_running("someone-else"),
+            "{}".to_string(),
+        ])
+        .await;
+
+        cancel_prompt(&Client::new(), &server.endpoint, "mine")
+            .await
+            .unwrap();
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests.iter().all(|r| r.path != "/interrupt"));
+        assert_eq!(requests[1].path, "/queue");
+        let body: Value = serde_json::from_str(&requests[1].body).unwrap();
+        assert_eq!(body, serde_json::json!({"delete": ["mine"]}));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_running_prompt_interrupts() {
+        let server =
+            crate::mock_http::MockServer::start(vec![queue_with_running("mine"), "{}".to_string()])
+                .await;
+
+        cancel_prompt(&Client::new(), &server.endpoint, "mine")
+            .await
+            .unwrap();
+        let requests = server.requests();
+        assert_eq!(requests[1].path, "/interrupt");
+        assert!(requests[1].body.is_empty());
+    }
+}
@@ -0,0 +1,208 @@
+//! Retries for ComfyUI calls that fail because the server is briefly
+//! unreachable (restarting, busy loading a model). Anything ComfyUI actually
+//! answered — an HTTP error status, node_errors — fails straight away.
+
+use anyhow::Result;
+use reqwest::Client;
+use serde_json::Value;
+use std::future::Future;
+use std::time::Duration;
+
+use super::client::{self, PromptHistory};
+
+/// Delay before the first retry; doubles on each further attempt.
+pub const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+/// Upper bound for the delay between two attempts.
+pub const RETRY_MAX_DELAY: Duration = Duration::from_secs(5);
+
+/// True when `err` came from failing to reach ComfyUI at all: a refused or
+/// reset connection, or a timeout.
+pub fn is_transient(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            return e.is_connect() || e.is_timeout();
+        }
+        if let Some(e) = cause.downcast_ref::<std::io::Error>() {
+            return matches!(
+                e.kind(),
+                std::io::ErrorKind::ConnectionRefused
+                    | std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+                    | std::io::ErrorKind::TimedOut
+            );
+        }
+        false
+    })
+}
+
+fn backoff_delay(attempt: u32, base: Duration, cap: Duration) -> Duration {
+    base.saturating_mul(2u32.saturating_pow(attempt)).min(cap)
+}
+
+/// Run `op` until it succeeds, fails with a non-transient error, or has been
+/// retried `max_retries` times.
+pub async fn retry_with_backoff<T, F, Fut>(
+    max_retries: u32,
+    base: Duration,
+    cap: Duration,
+    mut op: F,
+) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        match op().await {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < max_retries && is_transient(&e) => {
+                let delay = backoff_delay(attempt, base, cap);
+                eprintln!(
+                    "[comfyui] Call failed ({:#}), retrying in {:?} ({}/{})",
+                    e,
+                    delay,
+                    attempt + 1,
+                    max_retries
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+async fn with_retries<T, F, Fut>(max_retries: u32, op: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    retry_with_backoff(max_retries, RETRY_BASE_DELAY, RETRY_MAX_DELAY, op).await
+}
+
+/// [`client::queue_prompt`], retried on connection failures.
+pub async fn queue_prompt(
+    http: &Client,
+    endpoint: &str,
+    workflow: &Value,
+    client_id: &str,
+    max_retries: u32,
+) -> Result<String> {
+    with_retries(max_retries, || {
+        client::queue_prompt(http, endpoint, workflow, client_id)
+    })
+    .await
+}
+
+/// [`client::get_history`], retried on connection failures.
+pub async fn get_history(
+    http: &Client,
+    endpoint: &str,
+    prompt_id: &str,
+    max_retries: u32,
+) -> Result<Option<PromptHistory>> {
+    with_retries(max_retries, || {
+        client::get_history(http, endpoint, prompt_id)
+    })
+    .await
+}
+
+/// [`client::get_image`], retried on connection failures.
+pub async fn get_image(
+    http: &Client,
+    endpoint: &str,
+    filename: &str,
+    subfolder: &str,
+    img_type: &str,
+    max_retries: u32,
+) -> Result<Vec<u8>> {
+    with_retries(max_retries, || {
+        client::get_image(http, endpoint, filename, subfolder, img_type)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_http::MockServer;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    const FAST: Duration = Duration::from_millis(1);
+
+    fn refused() -> anyhow::Error {
+        anyhow::Error::new(std::io::Error::from(std::io::ErrorKind::ConnectionRefused))
+            .context("Cannot connect to ComfyUI")
+    }
+
+    #[tokio::test]
+    async fn test_retries_transient_failures_until_success() {
+        let calls = AtomicU32::new(0);
+        let result = retry_with_backoff(3, FAST, FAST, || async {
+            let n = calls.fetch_add(1, Ordering::SeqCst);
+            if n < 2 {
+                Err(refused())
+            } else {
+                Ok("prompt-1")
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(result, "prompt-1");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_retries() {
+        let calls = AtomicU32::new(0);
+        let result: Result<()> = retry_with_backoff(2, FAST, FAST, || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(refused())
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_does_not_retry_answered_errors() {
+        let calls = AtomicU32::new(0);
+        let result: Result<()> = retry_with_backoff(3, FAST, FAST, || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            anyhow::bail!("ComfyUI workflow has node errors: {{}}")
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // An HTTP 4xx from a live server is an answer, not a connection failure
+        let server = MockServer::start(vec![]).await;
+        let err = get_image(&Client::new(), &server.endpoint, "a.png", "", "output", 3)
+            .await
+            .unwrap_err();
+        assert!(!is_transient(&err));
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_refused_connection_is_transient() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        let err = client::get_history(&Client::new(), &endpoint, "p1")
+            .await
+            .unwrap_err();
+        assert!(is_transient(&err));
+    }
+
+    #[test]
+    fn test_backoff_delay_doubles_up_to_cap() {
+        let base = Duration::from_millis(500);
+        let cap = Duration::from_secs(5);
+        assert_eq!(backoff_delay(0, base, cap), Duration::from_millis(500));
+        assert_eq!(backoff_delay(1, base, cap), Duration::from_secs(1));
+        assert_eq!(backoff_delay(3, base, cap), Duration::from_secs(4));
+        assert_eq!(backoff_delay(4, base, cap), cap);
+        assert_eq!(backoff_delay(40, base, cap), cap);
+    }
+}
@@ -0,0 +1,222 @@
+use anyhow::{Context, Result};
+use reqwest::Client;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+use super::client::{self, ImageRef, PromptHistory};
+use super::{completion, models, workflow};
+use crate::types::generation::{GenerationRequest, GenerationStatusKind};
+
+const SMOKE_SIZE: u32 = 64;
+const SMOKE_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SmokeTestResult {
+    pub passed: bool,
+    pub elapsed_ms: u64,
+    pub checkpoint: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Generate a single 64x64, 1-step image with the first installed checkpoint
+/// to confirm ComfyUI can actually produce output. The image is fetched to
+/// prove it exists and then discarded; nothing is written to the gallery.
+pub async fn run_smoke_test(client: &Client, endpoint: &str) -> SmokeTestResult {
+    let start = Instant::now();
+    let mut checkpoint = None;
+    let outcome = run_inner(client, endpoint, &mut checkpoint).await;
+    SmokeTestResult {
+        passed: outcome.is_ok(),
+        elapsed_ms: start.elapsed().as_millis() as u64,
+        checkpoint,
+        error: outcome.err().map(|e| format!("{:#}", e)),
+    }
+}
+
+async fn run_inner(
+    client: &Client,
+    endpoint: &str,
+    checkpoint_out: &mut Option<String>,
+) -> Result<()> {
+    let checkpoint = models::list_checkpoints(client, endpoint)
+        .await?
+        .into_iter()
+        .next()
+        .context("ComfyUI has no checkpoints installed")?;
+    *checkpoint_out = Some(checkpoint.clone());
+
+    let (workflow_json, _) = workflow::build_txt2img(&smoke_request(checkpoint));
+    let client_id = uuid::Uuid::new_v4().to_string();
+    let prompt_id = client::queue_prompt(client, endpoint, &workflow_json, &client_id).await?;
+
+    let status = completion::wait_for_completion(
+        client,
+        endpoint,
+        &prompt_id,
+        Duration::from_millis(500),
+        SMOKE_TIMEOUT,
+    )
+    .await?;
+    if status.status != GenerationStatusKind::Completed {
+        anyhow::bail!(status
+            .error
+            .unwrap_or_else(|| "Generation did not complete".to_string()));
+    }
+
+    let history = client::get_history(client, endpoint, &prompt_id).await?;
+    let image = check_history(history.as_ref())?;
+    let bytes = client::get_image(
+        client,
+        endpoint,
+        &image.filename,
+        &image.subfolder,
+        &image.img_type,
+    )
+    .await?;
+    if bytes.is_empty() {
+        anyhow::bail!("ComfyUI returned an empty image for {}", image.filename);
+    }
+    Ok(())
+}
+
+fn smoke_request(checkpoint: String) -> GenerationRequest {
+    GenerationRequest {
+        positive_prompt: "a red circle".to_string(),
+        negative_prompt: String::new(),
+        checkpoint,
+        width: SMOKE_SIZE,
+        height: SMOKE_SIZE,
+        steps: 1,
+        cfg_scale: 1.0,
+        sampler: "euler".to_string(),
+        scheduler: "normal".to_string(),
+        seed: 0,
+        batch_size: 1,
+        denoise: 1.0,
+        init_image: None,
+        loras: Vec::new(),
+        hires: None,
+        controlnet: None,
+        custom_workflow: None,
+    }
+}
+
+/// Decide whether a finished prompt's history counts as a pass: it must have
+/// completed successfully and produced at least one image.
+pub fn check_history(history: Option<&PromptHistory>) -> Result<&ImageRef> {
+    let history = history.context("ComfyUI has no history for the smoke test prompt")?;
+    if history.status == "error" {
+        anyhow::bail!("ComfyUI reported an error running the smoke test");
+    }
+    if !history.completed {
+        anyhow::bail!("Smoke test prompt did not complete");
+    }
+    history
+        .image_filenames
+        .first()
+        .context("Smoke test completed but produced no image")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_http::MockServer;
+
+    fn history(status: &str, completed: bool, images: &[&str]) -> PromptHistory {
+        PromptHistory {
+            status: status.to_string(),
+            completed,
+            image_filenames: images
+                .iter()
+                .map(|f| ImageRef {
+                    filename: f.to_string(),
+                    subfolder: String::new(),
+                    img_type: "output".to_string(),
+                })
+                .collect(),
+            prompt_graph: None,
+        }
+    }
+
+    #[test]
+    fn test_successful_history_passes() {
+        let h = history("success", true, &["VisionForge_00001_.png"]);
+        assert_eq!(
+            check_history(Some(&h)).unwrap().filename,
+            "VisionForge_00001_.png"
+        );
+    }
+
+    #[test]
+    fn test_failed_histories_fail() {
+        assert!(check_history(None).is_err());
+        assert!(check_history(Some(&history("error", false, &[]))).is_err());
+        assert!(check_history(Some(&history("success", false, &["a.png"]))).is_err());
+        let err = check_history(Some(&history("success", true, &[]))).unwrap_err();
+        assert!(err.to_string().contains("no image"));
+    }
+
+    fn history_body(prompt_id: &str, status: &str, completed: bool, images: bool) -> String {
+        let outputs = if images {
+            serde_json::json!({"9": {"images": [
+                {"filename": "VisionForge_00001_.png", "subfolder": "", "type": "output"}
+            ]}})
+        } else {
+            serde_json::json!({})
+        };
+        serde_json::json!({ prompt_id: {
+            "status": {"status_str": status, "completed": completed},
+            "outputs": outputs,
+        }})
+        .to_string()
+    }
+
+    fn checkpoints_body() -> String {
+        serde_json::json!({"CheckpointLoaderSimple": {"input": {"required": {
+            "ckpt_name": [["tiny.safetensors", "big.safetensors"]]
+        }}}})
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_smoke_test_passes_against_mocked_success() {
+        let done = history_body("p1", "success", true, true);
+        let server = MockServer::start(vec![
+            checkpoints_body(),
+            r#"{"prompt_id": "p1"}"#.to_string(),
+            done.clone(),
+            done.clone(),
+            done,
+            "PNGDATA".to_string(),
+        ])
+        .await;
+
+        let result = run_smoke_test(&Client::new(), &server.endpoint).await;
+        assert!(result.passed, "{:?}", result.error);
+        assert_eq!(result.checkpoint.as_deref(), Some("tiny.safetensors"));
+
+        let requests = server.requests();
+        let prompt = &requests[1].body;
+        assert!(prompt.contains(r#""width":64"#));
+        assert!(prompt.contains(r#""steps":1"#));
+        assert_eq!(
+            requests.last().unwrap().path.split('?').next(),
+            Some("/view")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_smoke_test_fails_against_mocked_error_history() {
+        let server = MockServer::start(vec![
+            checkpoints_body(),
+            r#"{"prompt_id": "p1"}"#.to_string(),
+            history_body("p1", "error", false, false),
+        ])
+        .await;
+
+        let result = run_smoke_test(&Client::new(), &server.endpoint).await;
+        assert!(!result.passed);
+        assert!(result.error.is_some());
+    }
+}
@@ -0,0 +1,144 @@
+use anyhow::{Context, Result};
+use reqwest::Client;
+use serde_json::Value;
+use std::time::Duration;
+
+use super::client::{ensure_success, normalize_endpoint};
+
+/// Fetch `/system_stats`: VRAM of the first device and the runtime versions.
+/// Only an unreachable server or a non-JSON reply is an error; anything
+/// ComfyUI leaves out comes back as `None`.
+pub async fn get_system_stats(client: &Client, endpoint: &str) -> Result<SystemStats> {
+    let endpoint = normalize_endpoint(endpoint);
+    let url = format!("{}/system_stats", endpoint);
+
+    let resp = client
+        .get(&url)
+        .timeout(Duration::from_secs(5))
+        .send()
+        .await
+        .context("Failed to fetch ComfyUI system stats")?;
+
+    let resp = ensure_success(resp, "system stats").await?;
+
+    let json: Value = resp
+        .json()
+        .await
+        .context("Failed to parse ComfyUI system stats")?;
+    Ok(parse_system_stats(&json))
+}
+
+fn parse_system_stats(json: &Value) -> SystemStats {
+    let device = json.pointer("/devices/0");
+    let device_str = |key: &str| {
+        device
+            .and_then(|d| d.get(key))
+            .and_then(|v| v.as_str())
+            .map(String::from)
+    };
+    let device_u64 = |key: &str| device.and_then(|d| d.get(key)).and_then(|v| v.as_u64());
+    let system_str = |key: &str| {
+        json.pointer(&format!("/system/{}", key))
+            .and_then(|v| v.as_str())
+            .map(String::from)
+    };
+
+    let pytorch_version = system_str("pytorch_version");
+    let cuda_version = pytorch_version.as_deref().and_then(cuda_from_torch_version);
+    SystemStats {
+        device_name: device_str("name"),
+        device_type: device_str("type"),
+        vram_total: device_u64("vram_total"),
+        vram_free: device_u64("vram_free"),
+        comfyui_version: system_str("comfyui_version"),
+        pytorch_version,
+        cuda_version,
+    }
+}
+
+/// CUDA version from a torch build tag: "2.5.1+cu124" → "12.4".
+fn cuda_from_torch_version(version: &str) -> Option<String> {
+    let digits = version.split_once("+cu")?.1;
+    if digits.len() < 2 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let (major, minor) = digits.split_at(digits.len() - 1);
+    Some(format!("{}.{}", major, minor))
+}
+
+/// GPU and runtime details from ComfyUI's `/system_stats`. Device fields
+/// describe the first device; VRAM is in bytes.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemStats {
+    pub device_name: Option<String>,
+    pub device_type: Option<String>,
+    pub vram_total: Option<u64>,
+    pub vram_free: Option<u64>,
+    pub comfyui_version: Option<String>,
+    pub pytorch_version: Option<String>,
+    pub cuda_version: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_system_stats() {
+        let json: Value = serde_json::from_str(
+            r#"{
+            "system": {
+                "os": "posix",
+                "ram_total": 67108864000,
+                "comfyui_version": "0.3.10",
+                "python_version": "3.12.3",
+                "pytorch_version": "2.5.1+cu124",
+                "embedded_python": false
+            },
+            "devices": [{
+                "name": "cuda:0 NVIDIA GeForce RTX 4090 : cudaMallocAsync",
+                "type": "cuda",
+                "index": 0,
+                "vram_total": 25393692672,
+                "vram_free": 23012345678,
+                "torch_vram_total": 0,
+                "torch_vram_free": 0
+            }]
+        }"#,
+        )
+        .unwrap();
+
+        let stats = parse_system_stats(&json);
+        assert_eq!(
+            stats.device_name.as_deref(),
+            Some("cuda:0 NVIDIA GeForce RTX 4090 : cudaMallocAsync")
+        );
+        assert_eq!(stats.device_type.as_deref(), Some("cuda"));
+        assert_eq!(stats.vram_total, Some(25393692672));
+        assert_eq!(stats.vram_free, Some(23012345678));
+        assert_eq!(stats.comfyui_version.as_deref(), Some("0.3.10"));
+        assert_eq!(stats.pytorch_version.as_deref(), Some("2.5.1+cu124"));
+        assert_eq!(stats.cuda_version.as_deref(), Some("12.4"));
+    }
+
+    #[test]
+    fn test_parse_system_stats_tolerates_missing_fields() {
+        let json: Value =
+            serde_json::from_str(r#"{"system": {"pytorch_version": "2.5.1+cpu"}, "devices": []}"#)
+                .unwrap();
+        let stats = parse_system_stats(&json);
+        assert_eq!(stats.pytorch_version.as_deref(), Some("2.5.1+cpu"));
+        assert_eq!(
+            stats,
+            SystemStats {
+                pytorch_version: stats.pytorch_version.clone(),
+                ..SystemStats::default()
+            }
+        );
+        assert_eq!(
+            parse_system_stats(&serde_json::json!({})),
+            SystemStats::default()
+        );
+    }
+}
@@ -0,0 +1,90 @@
+use anyhow::{Context, Result};
+use reqwest::Client;
+use serde_json::Value;
+use std::time::Duration;
+
+use super::client::{ensure_success, normalize_endpoint};
+
+/// Upload image bytes to ComfyUI's input folder (`POST /upload/image`) so a
+/// `LoadImage` node can read them. Returns the name ComfyUI stored the file
+/// under, which may differ from `filename` if that name was taken.
+pub async fn upload_image(
+    client: &Client,
+    endpoint: &str,
+    filename: &str,
+    bytes: &[u8],
+) -> Result<String> {
+    let endpoint = normalize_endpoint(endpoint);
+    let url = format!("{}/upload/image", endpoint);
+
+    let boundary = format!("visionforge-{}", uuid::Uuid::new_v4().simple());
+    let mut body = format!(
+        "--{b}\r\nContent-Disposition: form-data; name=\"type\"\r\n\r\ninput\r\n\
+         --{b}\r\nContent-Disposition: form-data; name=\"image\"; filename=\"{f}\"\r\n\
+         Content-Type: application/octet-stream\r\n\r\n",
+        b = boundary,
+        f = filename.replace('"', "")
+    )
+    .into_bytes();
+    body.extend_from_slice(bytes);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+
+    let resp = client
+        .post(&url)
+        .timeout(Duration::from_secs(60))
+        .header(
+            reqwest::header::CONTENT_TYPE,
+            format!("multipart/form-data; boundary={}", boundary),
+        )
+        .body(body)
+        .send()
+        .await
+        .with_context(|| {
+            format!(
+                "Cannot connect to ComfyUI at {} — is the service running?",
+                endpoint
+            )
+        })?;
+    let resp = ensure_success(resp, "image upload").await?;
+
+    let json: Value = resp
+        .json()
+        .await
+        .context("Failed to parse ComfyUI upload response")?;
+    let name = json
+        .get("name")
+        .and_then(|v| v.as_str())
+        .context("ComfyUI upload response has no name")?;
+    match json.get("subfolder").and_then(|v| v.as_str()) {
+        Some(subfolder) if !subfolder.is_empty() => Ok(format!("{}/{}", subfolder, name)),
+        _ => Ok(name.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_upload_image_returns_stored_name() {
+        let server = crate::mock_http::MockServer::start(vec![
+            r#"{"name": "cat (1).png", "subfolder": "", "type": "input"}"#.to_string(),
+            r#"{"name": "cat.png", "subfolder": "refine", "type": "input"}"#.to_string(),
+        ])
+        .await;
+
+        let name = upload_image(&Client::new(), &server.endpoint, "cat.png", b"png bytes")
+            .await
+            .unwrap();
+        assert_eq!(name, "cat (1).png");
+        let nested = upload_image(&Client::new(), &server.endpoint, "cat.png", b"png bytes")
+            .await
+            .unwrap();
+        assert_eq!(nested, "refine/cat.png");
+
+        let request = &server.requests()[0];
+        assert_eq!(request.path, "/upload/image");
+        assert!(request.body.contains(r#"name="image"; filename="cat.png""#));
+        assert!(request.body.contains("png bytes"));
+    }
+}
@@ -0,0 +1,222 @@
+use serde::Serialize;
+
+use super::system_stats::SystemStats;
+use crate::types::generation::GenerationRequest;
+
+const GIB: u64 = 1024 * 1024 * 1024;
+const MEGAPIXEL: u64 = 1024 * 1024;
+
+/// Fraction of free VRAM a request may take and still count as a safe fit;
+/// the rest covers fragmentation and what the heuristic misses.
+const SAFE_FRACTION_PERCENT: u64 = 85;
+
+/// Checkpoint architecture, which sets the weights' size and the cost of
+/// each sampled pixel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ModelFamily {
+    Sd15,
+    Sdxl,
+}
+
+impl ModelFamily {
+    /// Family from a checkpoint profile's `base_model` ("SDXL", "Pony", …)
+    /// or, without one, from the checkpoint filename. Unrecognized names
+    /// are taken as SD 1.5.
+    pub fn guess(base_model: Option<&str>, checkpoint: &str) -> Self {
+        let name = base_model.unwrap_or(checkpoint).to_lowercase();
+        if ["xl", "pony", "illustrious"]
+            .iter()
+            .any(|marker| name.contains(marker))
+        {
+            Self::Sdxl
+        } else {
+            Self::Sd15
+        }
+    }
+
+    /// fp16 UNet, text encoders and VAE.
+    fn weights_bytes(self) -> u64 {
+        match self {
+            Self::Sd15 => 2 * GIB + GIB / 2,
+            Self::Sdxl => 7 * GIB,
+        }
+    }
+
+    /// Sampling and VAE-decode memory per output megapixel per image.
+    fn bytes_per_megapixel(self) -> u64 {
+        match self {
+            Self::Sd15 => 2 * GIB,
+            Self::Sdxl => 2 * GIB + GIB / 2,
+        }
+    }
+
+    fn controlnet_bytes(self) -> u64 {
+        match self {
+            Self::Sd15 => GIB + GIB / 2,
+            Self::Sdxl => 2 * GIB + GIB / 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FitVerdict {
+    LikelyFits,
+    /// More than the safe share of free VRAM, but within the card's total:
+    /// may work once other models are unloaded, or spill into slow shared
+    /// memory.
+    Risky,
+    WontFit,
+    /// ComfyUI didn't report VRAM (CPU or unknown device).
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FitEstimate {
+    pub verdict: FitVerdict,
+    pub family: ModelFamily,
+    pub estimated_bytes: u64,
+    pub vram_free: Option<u64>,
+    pub vram_total: Option<u64>,
+}
+
+/// Rough peak VRAM for `request`: model weights plus a per-megapixel cost
+/// for every image in the batch at the saved size (the hires size when a
+/// second pass runs), plus LoRAs and a ControlNet. Meant for a heads-up
+/// before queueing, not as an exact figure.
+pub fn estimate_vram_bytes(request: &GenerationRequest, family: ModelFamily) -> u64 {
+    let (width, height) = request.output_size();
+    let pixels = width as u64 * height as u64 * request.batch_size.max(1) as u64;
+    let activations = pixels * family.bytes_per_megapixel() / MEGAPIXEL;
+    let loras = request.loras.len() as u64 * (GIB / 4);
+    let controlnet = if request.controlnet.is_some() {
+        family.controlnet_bytes()
+    } else {
+        0
+    };
+    family.weights_bytes() + activations + loras + controlnet
+}
+
+/// Compare the estimate for `request` with the VRAM ComfyUI reports.
+pub fn estimate_fit(
+    request: &GenerationRequest,
+    family: ModelFamily,
+    stats: &SystemStats,
+) -> FitEstimate {
+    let estimated_bytes = estimate_vram_bytes(request, family);
+    let verdict = match stats.vram_free {
+        None => FitVerdict::Unknown,
+        Some(free) if estimated_bytes * 100 <= free * SAFE_FRACTION_PERCENT => {
+            FitVerdict::LikelyFits
+        }
+        Some(free) if estimated_bytes <= stats.vram_total.unwrap_or(free) => FitVerdict::Risky,
+        Some(_) => FitVerdict::WontFit,
+    };
+    FitEstimate {
+        verdict,
+        family,
+        estimated_bytes,
+        vram_free: stats.vram_free,
+        vram_total: stats.vram_total,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::generation::HiresFix;
+
+    fn request(checkpoint: &str, width: u32, height: u32, batch_size: u32) -> GenerationRequest {
+        GenerationRequest {
+            positive_prompt: "a cat".to_string(),
+            negative_prompt: String::new(),
+            checkpoint: checkpoint.to_string(),
+            width,
+            height,
+            steps: 25,
+            cfg_scale: 7.0,
+            sampler: "euler".to_string(),
+            scheduler: "normal".to_string(),
+            seed: -1,
+            batch_size,
+            denoise: 1.0,
+            init_image: None,
+            loras: Vec::new(),
+            hires: None,
+            controlnet: None,
+            custom_workflow: None,
+        }
+    }
+
+    fn stats(free_gib: u64, total_gib: u64) -> SystemStats {
+        SystemStats {
+            vram_free: Some(free_gib * GIB),
+            vram_total: Some(total_gib * GIB),
+            ..SystemStats::default()
+        }
+    }
+
+    #[test]
+    fn test_family_guess() {
+        assert_eq!(
+            ModelFamily::guess(Some("SDXL 1.0"), "x.safetensors"),
+            ModelFamily::Sdxl
+        );
+        assert_eq!(
+            ModelFamily::guess(None, "juggernautXL_v9.safetensors"),
+            ModelFamily::Sdxl
+        );
+        assert_eq!(
+            ModelFamily::guess(Some("SD1.5"), "someXL.safetensors"),
+            ModelFamily::Sd15
+        );
+        assert_eq!(
+            ModelFamily::guess(None, "dreamshaper_8.safetensors"),
+            ModelFamily::Sd15
+        );
+    }
+
+    #[test]
+    fn test_large_sdxl_request_wont_fit_low_free_vram() {
+        let req = request("sd_xl_base.safetensors", 1536, 1536, 4);
+        let fit = estimate_fit(&req, ModelFamily::Sdxl, &stats(6, 8));
+        assert_eq!(fit.verdict, FitVerdict::WontFit);
+        assert!(fit.estimated_bytes > 8 * GIB);
+        assert_eq!(fit.vram_free, Some(6 * GIB));
+    }
+
+    #[test]
+    fn test_small_request_fits_and_tight_one_is_risky() {
+        let small = request("dreamshaper_8.safetensors", 512, 768, 1);
+        assert_eq!(
+            estimate_fit(&small, ModelFamily::Sd15, &stats(20, 24)).verdict,
+            FitVerdict::LikelyFits
+        );
+
+        // About 10 GiB: over the safe share of 10 free, under the 24 total
+        let tight = request("sd_xl_base.safetensors", 1024, 1024, 1);
+        let estimate = estimate_vram_bytes(&tight, ModelFamily::Sdxl);
+        assert!(estimate > 9 * GIB && estimate <= 10 * GIB);
+        assert_eq!(
+            estimate_fit(&tight, ModelFamily::Sdxl, &stats(10, 24)).verdict,
+            FitVerdict::Risky
+        );
+    }
+
+    #[test]
+    fn test_hires_counts_upscaled_size_and_missing_stats_is_unknown() {
+        let mut req = request("dreamshaper_8.safetensors", 512, 512, 1);
+        let base = estimate_vram_bytes(&req, ModelFamily::Sd15);
+        req.hires = Some(HiresFix {
+            upscale_by: 2.0,
+            steps: 10,
+            denoise: 0.5,
+        });
+        assert!(estimate_vram_bytes(&req, ModelFamily::Sd15) > base);
+
+        let fit = estimate_fit(&req, ModelFamily::Sd15, &SystemStats::default());
+        assert_eq!(fit.verdict, FitVerdict::Unknown);
+    }
+}
@@ -0,0 +1,218 @@
+use anyhow::Result;
+use rand::Rng;
+use serde_json::{json, Value};
+
+use crate::types::generation::{CustomWorkflow, GenerationRequest};
+
+mod nodes;
+
+use nodes::apply_loras;
+pub(crate) use nodes::{apply_controlnet, apply_hires};
+pub use nodes::{build_img2img, build_txt2img_controlnet, build_txt2img_hires};
+
+/// Build a txt2img workflow for ComfyUI from generation settings.
+/// Returns (workflow_json, actual_seed). When request.seed is -1 (random),
+/// a random seed is generated and returned so it can be stored with the image.
+pub fn build_txt2img(request: &GenerationRequest) -> (Value, i64) {
+    let seed = resolve_seed(request.seed, &mut rand::rng());
+
+    let mut workflow = json!({
+        "1": {
+            "class_type": "CheckpointLoaderSimple",
+            "inputs": {
+                "ckpt_name": request.checkpoint
+            }
+        },
+        "2": {
+            "class_type": "EmptyLatentImage",
+            "inputs": {
+                "width": request.width,
+                "height": request.height,
+                "batch_size": request.batch_size
+            }
+        },
+        "3": {
+            "class_type": "CLIPTextEncode",
+            "inputs": {
+                "text": request.positive_prompt,
+                "clip": ["1", 1]
+            }
+        },
+        "4": {
+            "class_type": "CLIPTextEncode",
+            "inputs": {
+                "text": request.negative_prompt,
+                "clip": ["1", 1]
+            }
+        },
+        "5": {
+            "class_type": "KSampler",
+            "inputs": {
+                "seed": seed,
+                "steps": request.steps,
+                "cfg": request.cfg_scale,
+                "sampler_name": request.sampler,
+                "scheduler": request.scheduler,
+                "denoise": request.denoise,
+                "model": ["1", 0],
+                "positive": ["3", 0],
+                "negative": ["4", 0],
+                "latent_image": ["2", 0]
+            }
+        },
+        "6": {
+            "class_type": "VAEDecode",
+            "inputs": {
+                "samples": ["5", 0],
+                "vae": ["1", 2]
+            }
+        },
+        "7": {
+            "class_type": "SaveImage",
+            "inputs": {
+                "filename_prefix": "VisionForge",
+                "images": ["6", 0]
+            }
+        }
+    });
+    apply_loras(&mut workflow, &request.loras);
+
+    (workflow, seed)
+}
+
+/// Prepare a user-supplied API-format workflow: the request's prompts and
+/// resolved seed (a random one for -1) are written into the nodes `custom`
+/// names and everything else is left as uploaded. Bails, naming the node,
+/// when a referenced node is missing or lacks the input it should receive.
+pub fn build_custom(request: &GenerationRequest, custom: &CustomWorkflow) -> Result<(Value, i64)> {
+    let mut workflow = custom.prompt_graph.clone();
+    if !workflow.is_object() {
+        anyhow::bail!("Custom workflow must be a JSON object of nodes (ComfyUI's API format)");
+    }
+    let seed = resolve_seed(request.seed, &mut rand::rng());
+
+    set_node_input(
+        &mut workflow,
+        &custom.prompt_node_id,
+        &["text"],
+        json!(request.positive_prompt),
+        "positive prompt",
+    )?;
+    if let Some(negative_id) = &custom.negative_node_id {
+        set_node_input(
+            &mut workflow,
+            negative_id,
+            &["text"],
+            json!(request.negative_prompt),
+            "negative prompt",
+        )?;
+    }
+    set_node_input(
+        &mut workflow,
+        &custom.seed_node_id,
+        &["seed", "noise_seed"],
+        json!(seed),
+        "seed",
+    )?;
+    Ok((workflow, seed))
+}
+
+/// Overwrite the first of `inputs` that node `id` already has; `what` names
+/// the value in errors.
+fn set_node_input(
+    workflow: &mut Value,
+    id: &str,
+    inputs: &[&str],
+    value: Value,
+    what: &str,
+) -> Result<()> {
+    let Some(node) = workflow.get_mut(id) else {
+        anyhow::bail!("Custom workflow has no node \"{}\" for the {}", id, what);
+    };
+    let class_type = node["class_type"]
+        .as_str()
+        .unwrap_or("unknown type")
+        .to_string();
+    let fields = node.get_mut("inputs").and_then(Value::as_object_mut);
+    let Some((fields, name)) = fields.and_then(|fields| {
+        let name = inputs.iter().find(|name| fields.contains_key(**name))?;
+        Some((fields, *name))
+    }) else {
+        anyhow::bail!(
+            "Node \"{}\" ({}) has no '{}' input to take the {}",
+            id,
+            class_type,
+            inputs.join("' or '"),
+            what
+        );
+    };
+    fields.insert(name.to_string(), value);
+    Ok(())
+}
+
+fn next_node_id(workflow: &Value) -> u64 {
+    workflow
+        .as_object()
+        .and_then(|nodes| nodes.keys().filter_map(|id| id.parse::<u64>().ok()).max())
+        .map_or(1, |max| max + 1)
+}
+
+/// ComfyUI requires seed >= 0; a negative seed (-1) means "pick one at random".
+pub fn resolve_seed(seed: i64, rng: &mut impl Rng) -> i64 {
+    if seed < 0 {
+        rng.random_range(0..i64::MAX)
+    } else {
+        seed
+    }
+}
+
+/// The seeds a batch or sweep of `count` images starting from `base_seed`
+/// will consume: the resolved base followed by consecutive values, wrapping
+/// within ComfyUI's non-negative range.
+pub fn preview_seeds(base_seed: i64, count: u32, rng: &mut impl Rng) -> Vec<i64> {
+    let base = resolve_seed(base_seed, rng);
+    (0..count as i64)
+        .map(|i| base.wrapping_add(i) & i64::MAX)
+        .collect()
+}
+
+/// Check a workflow against ComfyUI's `/object_info`. Returns one message per
+/// node whose class_type isn't installed or that lacks a required input;
+/// an empty list means the workflow looks submittable.
+pub fn validate_against(object_info: &Value, workflow: &Value) -> Vec<String> {
+    let Some(nodes) = workflow.as_object() else {
+        return vec!["Workflow must be a JSON object of nodes".to_string()];
+    };
+
+    let mut node_ids: Vec<&String> = nodes.keys().collect();
+    node_ids.sort_by_key(|id| (id.parse::<u64>().unwrap_or(u64::MAX), id.as_str()));
+
+    let mut problems = Vec::new();
+    for id in node_ids {
+        let node = &nodes[id];
+        let Some(class_type) = node.get("class_type").and_then(|v| v.as_str()) else {
+            problems.push(format!("Node {}: missing class_type", id));
+            continue;
+        };
+        let Some(spec) = object_info.get(class_type) else {
+            problems.push(format!("Node {}: unknown node type '{}'", id, class_type));
+            continue;
+        };
+
+        let inputs = node.get("inputs").and_then(|v| v.as_object());
+        if let Some(required) = spec.pointer("/input/required").and_then(|v| v.as_object()) {
+            for name in required.keys() {
+                if !inputs.is_some_and(|i| i.contains_key(name)) {
+                    problems.push(format!(
+                        "Node {} ({}): missing required input '{}'",
+                        id, class_type, name
+                    ));
+                }
+            }
+        }
+    }
+    problems
+}
+
+#[cfg(test)]
+pub(crate) mod tests;
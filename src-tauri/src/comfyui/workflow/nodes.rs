@@ -0,0 +1,209 @@
+use anyhow::Result;
+use serde_json::{json, Value};
+
+use super::{build_txt2img, next_node_id};
+use crate::types::generation::{GenerationRequest, LoraSpec};
+
+/// Chain a `LoraLoader` per LoRA after the checkpoint loader (node "1") and
+/// point the KSampler's model and both text encoders' clip at the last one.
+/// LoRA nodes take ids from 11 up, clear of the base and img2img nodes.
+pub(super) fn apply_loras(workflow: &mut Value, loras: &[LoraSpec]) {
+    let mut source = "1".to_string();
+    for (i, lora) in loras.iter().enumerate() {
+        let id = (11 + i).to_string();
+        workflow[id.as_str()] = json!({
+            "class_type": "LoraLoader",
+            "inputs": {
+                "lora_name": lora.filename,
+                "strength_model": lora.model_weight,
+                "strength_clip": lora.clip_weight,
+                "model": [source, 0],
+                "clip": [source, 1]
+            }
+        });
+        source = id;
+    }
+    if loras.is_empty() {
+        return;
+    }
+
+    workflow["5"]["inputs"]["model"] = json!([source, 0]);
+    workflow["3"]["inputs"]["clip"] = json!([source, 1]);
+    workflow["4"]["inputs"]["clip"] = json!([source, 1]);
+}
+
+/// Build an img2img workflow: the txt2img graph with its empty latent
+/// replaced by `init_image_filename` (a file already uploaded to ComfyUI's
+/// input folder), scaled and center-cropped to the requested size, then
+/// VAE-encoded. At denoise 1.0 the init image would be fully noised away, so
+/// the plain txt2img graph is returned instead.
+pub fn build_img2img(
+    request: &GenerationRequest,
+    init_image_filename: &str,
+    denoise: f64,
+) -> (Value, i64) {
+    let (mut workflow, seed) = build_txt2img(request);
+    if denoise >= 1.0 {
+        return (workflow, seed);
+    }
+
+    workflow["8"] = json!({
+        "class_type": "LoadImage",
+        "inputs": {
+            "image": init_image_filename
+        }
+    });
+    workflow["9"] = json!({
+        "class_type": "ImageScale",
+        "inputs": {
+            "upscale_method": "lanczos",
+            "width": request.width,
+            "height": request.height,
+            "crop": "center",
+            "image": ["8", 0]
+        }
+    });
+    workflow["2"] = json!({
+        "class_type": "VAEEncode",
+        "inputs": {
+            "pixels": ["9", 0],
+            "vae": ["1", 2]
+        }
+    });
+    if request.batch_size > 1 {
+        workflow["10"] = json!({
+            "class_type": "RepeatLatentBatch",
+            "inputs": {
+                "samples": ["2", 0],
+                "amount": request.batch_size
+            }
+        });
+        workflow["5"]["inputs"]["latent_image"] = json!(["10", 0]);
+    }
+    workflow["5"]["inputs"]["denoise"] = json!(denoise);
+
+    (workflow, seed)
+}
+
+/// Build a txt2img workflow with a hires-fix pass: the first KSampler's
+/// latent is upscaled by `upscale_by` and refined by a second KSampler
+/// before decoding. An upscale of 1.0 or less would just resample at the
+/// same size, so it is rejected.
+pub fn build_txt2img_hires(
+    request: &GenerationRequest,
+    upscale_by: f64,
+    hires_steps: u32,
+    hires_denoise: f64,
+) -> Result<(Value, i64)> {
+    let (mut workflow, seed) = build_txt2img(request);
+    apply_hires(&mut workflow, upscale_by, hires_steps, hires_denoise)?;
+    Ok((workflow, seed))
+}
+
+/// Insert a `LatentUpscaleBy` after KSampler "5" and a second KSampler
+/// (same model, seed and conditioning) feeding the VAEDecode. The new nodes
+/// take the next free ids so they never collide with LoRA or img2img nodes.
+pub(crate) fn apply_hires(
+    workflow: &mut Value,
+    upscale_by: f64,
+    steps: u32,
+    denoise: f64,
+) -> Result<()> {
+    if upscale_by.is_nan() || upscale_by <= 1.0 {
+        anyhow::bail!("Hires upscale must be greater than 1, got {}", upscale_by);
+    }
+
+    let upscale_id = next_node_id(workflow);
+    let sampler_id = (upscale_id + 1).to_string();
+    let upscale_id = upscale_id.to_string();
+
+    workflow[upscale_id.as_str()] = json!({
+        "class_type": "LatentUpscaleBy",
+        "inputs": {
+            "upscale_method": "nearest-exact",
+            "scale_by": upscale_by,
+            "samples": ["5", 0]
+        }
+    });
+    let mut second_pass = workflow["5"].clone();
+    second_pass["inputs"]["steps"] = json!(steps);
+    second_pass["inputs"]["denoise"] = json!(denoise);
+    second_pass["inputs"]["latent_image"] = json!([upscale_id, 0]);
+    workflow[sampler_id.as_str()] = second_pass;
+    workflow["6"]["inputs"]["samples"] = json!([sampler_id, 0]);
+    Ok(())
+}
+
+/// Build a txt2img workflow guided by a ControlNet: `control_image_filename`
+/// (already uploaded to ComfyUI's input folder) is fed through
+/// `controlnet_name` into both the positive and negative conditioning.
+/// A strength of 0 keeps the nodes in place; ControlNetApply then passes the
+/// conditioning through unchanged.
+pub fn build_txt2img_controlnet(
+    request: &GenerationRequest,
+    control_image_filename: &str,
+    controlnet_name: &str,
+    strength: f64,
+) -> (Value, i64) {
+    let (mut workflow, seed) = build_txt2img(request);
+    apply_controlnet(
+        &mut workflow,
+        control_image_filename,
+        controlnet_name,
+        strength,
+    );
+    (workflow, seed)
+}
+
+/// Insert `LoadImage` and `ControlNetLoader` nodes plus one `ControlNetApply`
+/// per conditioning, and point every KSampler's positive/negative at the
+/// apply nodes. Call it after [`apply_hires`] so the second pass is rewired
+/// too; the new nodes take the next free ids.
+pub(crate) fn apply_controlnet(
+    workflow: &mut Value,
+    control_image_filename: &str,
+    controlnet_name: &str,
+    strength: f64,
+) {
+    let first = next_node_id(workflow);
+    let [image_id, loader_id, positive_id, negative_id] =
+        [first, first + 1, first + 2, first + 3].map(|id| id.to_string());
+
+    workflow[image_id.as_str()] = json!({
+        "class_type": "LoadImage",
+        "inputs": {
+            "image": control_image_filename
+        }
+    });
+    workflow[loader_id.as_str()] = json!({
+        "class_type": "ControlNetLoader",
+        "inputs": {
+            "control_net_name": controlnet_name
+        }
+    });
+    for (id, conditioning) in [(&positive_id, "3"), (&negative_id, "4")] {
+        workflow[id.as_str()] = json!({
+            "class_type": "ControlNetApply",
+            "inputs": {
+                "strength": strength,
+                "conditioning": [conditioning, 0],
+                "control_net": [loader_id, 0],
+                "image": [image_id, 0]
+            }
+        });
+    }
+
+    let Some(nodes) = workflow.as_object_mut() else {
+        return;
+    };
+    for node in nodes.values_mut() {
+        if node["class_type"] == "KSampler" {
+            node["inputs"]["positive"] = json!([positive_id, 0]);
+            node["inputs"]["negative"] = json!([negative_id, 0]);
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "nodes_test.rs"]
+mod tests;
@@ -0,0 +1,234 @@
+use super::*;
+use crate::comfyui::workflow::tests::make_request;
+use crate::comfyui::workflow::validate_against;
+
+#[test]
+fn test_img2img_encodes_init_image() {
+    let mut req = make_request();
+    req.batch_size = 2;
+    let (workflow, _seed) = build_img2img(&req, "refine_me.png", 0.45);
+
+    assert_eq!(workflow["8"]["class_type"], "LoadImage");
+    assert_eq!(workflow["8"]["inputs"]["image"], "refine_me.png");
+    assert_eq!(workflow["2"]["class_type"], "VAEEncode");
+    assert_eq!(workflow["2"]["inputs"]["vae"], json!(["1", 2]));
+    assert_eq!(workflow["10"]["inputs"]["samples"], json!(["2", 0]));
+    assert_eq!(workflow["5"]["inputs"]["latent_image"], json!(["10", 0]));
+    assert_eq!(workflow["5"]["inputs"]["denoise"], 0.45);
+    let has_empty_latent = workflow
+        .as_object()
+        .unwrap()
+        .values()
+        .any(|n| n["class_type"] == "EmptyLatentImage");
+    assert!(!has_empty_latent);
+}
+
+#[test]
+fn test_img2img_resolves_random_seed() {
+    let mut req = make_request();
+    req.seed = -1;
+    let (workflow, actual_seed) = build_img2img(&req, "refine_me.png", 0.5);
+    assert!(actual_seed >= 0, "Random seed should be non-negative");
+    assert_eq!(workflow["5"]["inputs"]["seed"], actual_seed);
+}
+
+#[test]
+fn test_img2img_scales_init_image_to_requested_size() {
+    // A square init image refined into a 512x768 portrait
+    let (workflow, _seed) = build_img2img(&make_request(), "square.png", 0.6);
+    let scale = &workflow["9"];
+    assert_eq!(scale["class_type"], "ImageScale");
+    assert_eq!(scale["inputs"]["width"], 512);
+    assert_eq!(scale["inputs"]["height"], 768);
+    assert_eq!(scale["inputs"]["crop"], "center");
+    assert_eq!(workflow["2"]["inputs"]["pixels"], json!(["9", 0]));
+    assert_eq!(workflow["5"]["inputs"]["latent_image"], json!(["2", 0]));
+}
+
+#[test]
+fn test_img2img_at_full_denoise_is_txt2img() {
+    let req = make_request();
+    let (img2img, _) = build_img2img(&req, "ignored.png", 1.0);
+    let (txt2img, _) = build_txt2img(&req);
+    assert_eq!(img2img, txt2img);
+}
+
+#[test]
+fn test_stacked_loras_rewire_model_and_clip() {
+    let mut req = make_request();
+    req.loras = vec![
+        LoraSpec {
+            filename: "detail.safetensors".to_string(),
+            model_weight: 0.8,
+            clip_weight: 0.6,
+        },
+        LoraSpec {
+            filename: "style.safetensors".to_string(),
+            model_weight: 0.5,
+            clip_weight: 1.0,
+        },
+    ];
+    let (workflow, _seed) = build_txt2img(&req);
+
+    let first = &workflow["11"];
+    assert_eq!(first["class_type"], "LoraLoader");
+    assert_eq!(first["inputs"]["lora_name"], "detail.safetensors");
+    assert_eq!(first["inputs"]["strength_model"], 0.8);
+    assert_eq!(first["inputs"]["strength_clip"], 0.6);
+    assert_eq!(first["inputs"]["model"], json!(["1", 0]));
+    assert_eq!(first["inputs"]["clip"], json!(["1", 1]));
+
+    let second = &workflow["12"];
+    assert_eq!(second["inputs"]["lora_name"], "style.safetensors");
+    assert_eq!(second["inputs"]["model"], json!(["11", 0]));
+    assert_eq!(second["inputs"]["clip"], json!(["11", 1]));
+
+    assert_eq!(workflow["5"]["inputs"]["model"], json!(["12", 0]));
+    assert_eq!(workflow["3"]["inputs"]["clip"], json!(["12", 1]));
+    assert_eq!(workflow["4"]["inputs"]["clip"], json!(["12", 1]));
+    // The VAE still comes straight from the checkpoint
+    assert_eq!(workflow["6"]["inputs"]["vae"], json!(["1", 2]));
+
+    // Settings recovered from the graph follow the chain back to the checkpoint
+    let meta = crate::gallery::png_metadata::parse_comfyui_graph(&workflow).unwrap();
+    assert_eq!(
+        meta.checkpoint.as_deref(),
+        Some("dreamshaper_8.safetensors")
+    );
+}
+
+#[test]
+fn test_no_loras_adds_no_nodes() {
+    let (workflow, _seed) = build_txt2img(&make_request());
+    assert_eq!(workflow.as_object().unwrap().len(), 7);
+    assert_eq!(workflow["5"]["inputs"]["model"], json!(["1", 0]));
+}
+
+fn sampler_ids(workflow: &Value) -> Vec<String> {
+    let mut ids: Vec<String> = workflow
+        .as_object()
+        .unwrap()
+        .iter()
+        .filter(|(_, node)| node["class_type"] == "KSampler")
+        .map(|(id, _)| id.clone())
+        .collect();
+    ids.sort();
+    ids
+}
+
+#[test]
+fn test_hires_adds_upscale_and_second_sampler() {
+    let (workflow, seed) = build_txt2img_hires(&make_request(), 1.5, 12, 0.45).unwrap();
+
+    assert_eq!(sampler_ids(&workflow), ["5", "9"]);
+    let upscale = &workflow["8"];
+    assert_eq!(upscale["class_type"], "LatentUpscaleBy");
+    assert_eq!(upscale["inputs"]["scale_by"], 1.5);
+    assert_eq!(upscale["inputs"]["samples"], json!(["5", 0]));
+
+    let second = &workflow["9"]["inputs"];
+    assert_eq!(second["latent_image"], json!(["8", 0]));
+    assert_eq!(second["steps"], 12);
+    assert_eq!(second["denoise"], 0.45);
+    assert_eq!(second["seed"], seed);
+    assert_eq!(second["positive"], json!(["3", 0]));
+    assert_eq!(second["negative"], json!(["4", 0]));
+    assert_eq!(workflow["6"]["inputs"]["samples"], json!(["9", 0]));
+
+    // The first pass is untouched
+    assert_eq!(workflow["5"]["inputs"]["steps"], 25);
+    assert_eq!(workflow["5"]["inputs"]["latent_image"], json!(["2", 0]));
+}
+
+#[test]
+fn test_hires_rejects_no_upscale() {
+    assert!(build_txt2img_hires(&make_request(), 1.0, 12, 0.45).is_err());
+    assert!(build_txt2img_hires(&make_request(), 0.5, 12, 0.45).is_err());
+    assert!(build_txt2img_hires(&make_request(), f64::NAN, 12, 0.45).is_err());
+}
+
+#[test]
+fn test_hires_second_pass_uses_lora_model_and_free_ids() {
+    let mut req = make_request();
+    req.loras = vec![LoraSpec {
+        filename: "detail.safetensors".to_string(),
+        model_weight: 0.8,
+        clip_weight: 0.6,
+    }];
+    let (mut workflow, _) = build_img2img(&req, "init.png", 0.5);
+    apply_hires(&mut workflow, 2.0, 10, 0.5).unwrap();
+
+    assert_eq!(workflow["12"]["class_type"], "LatentUpscaleBy");
+    assert_eq!(workflow["13"]["inputs"]["model"], json!(["11", 0]));
+    assert_eq!(workflow["6"]["inputs"]["samples"], json!(["13", 0]));
+    // The img2img nodes are still wired into the first pass
+    assert_eq!(workflow["8"]["class_type"], "LoadImage");
+    assert_eq!(workflow["5"]["inputs"]["latent_image"], json!(["2", 0]));
+}
+
+#[test]
+fn test_controlnet_rewires_conditioning_through_apply_nodes() {
+    let (workflow, seed) =
+        build_txt2img_controlnet(&make_request(), "pose.png", "control_openpose.pth", 0.8);
+    assert_eq!(seed, 12345);
+
+    assert_eq!(workflow["8"]["class_type"], "LoadImage");
+    assert_eq!(workflow["8"]["inputs"]["image"], "pose.png");
+    assert_eq!(workflow["9"]["class_type"], "ControlNetLoader");
+    assert_eq!(
+        workflow["9"]["inputs"]["control_net_name"],
+        "control_openpose.pth"
+    );
+    for (apply, conditioning) in [("10", "3"), ("11", "4")] {
+        let node = &workflow[apply];
+        assert_eq!(node["class_type"], "ControlNetApply");
+        assert_eq!(node["inputs"]["conditioning"], json!([conditioning, 0]));
+        assert_eq!(node["inputs"]["control_net"], json!(["9", 0]));
+        assert_eq!(node["inputs"]["image"], json!(["8", 0]));
+        assert_eq!(node["inputs"]["strength"], 0.8);
+    }
+    assert_eq!(workflow["5"]["inputs"]["positive"], json!(["10", 0]));
+    assert_eq!(workflow["5"]["inputs"]["negative"], json!(["11", 0]));
+}
+
+#[test]
+fn test_controlnet_zero_strength_keeps_graph_valid() {
+    let (workflow, _) = build_txt2img_controlnet(&make_request(), "pose.png", "cn.pth", 0.0);
+    assert_eq!(workflow["10"]["inputs"]["strength"], 0.0);
+    assert_eq!(workflow["5"]["inputs"]["positive"], json!(["10", 0]));
+
+    let object_info = json!({
+        "CheckpointLoaderSimple": {"input": {"required": {"ckpt_name": []}}},
+        "EmptyLatentImage": {"input": {"required": {"width": [], "height": [], "batch_size": []}}},
+        "CLIPTextEncode": {"input": {"required": {"text": [], "clip": []}}},
+        "KSampler": {"input": {"required": {"positive": [], "negative": [], "latent_image": []}}},
+        "VAEDecode": {"input": {"required": {"samples": [], "vae": []}}},
+        "SaveImage": {"input": {"required": {"images": []}}},
+        "LoadImage": {"input": {"required": {"image": []}}},
+        "ControlNetLoader": {"input": {"required": {"control_net_name": []}}},
+        "ControlNetApply": {"input": {"required": {
+            "conditioning": [], "control_net": [], "image": [], "strength": []
+        }}}
+    });
+    assert!(validate_against(&object_info, &workflow).is_empty());
+}
+
+#[test]
+fn test_controlnet_after_hires_rewires_both_samplers() {
+    let mut req = make_request();
+    req.loras = vec![LoraSpec {
+        filename: "detail.safetensors".to_string(),
+        model_weight: 0.8,
+        clip_weight: 0.6,
+    }];
+    let (mut workflow, _) = build_txt2img_hires(&req, 1.5, 12, 0.45).unwrap();
+    apply_controlnet(&mut workflow, "depth.png", "control_depth.pth", 1.0);
+
+    // LoRA is 11, hires takes 12 and 13, ControlNet 14..=17
+    assert_eq!(workflow["14"]["class_type"], "LoadImage");
+    assert_eq!(workflow["16"]["inputs"]["conditioning"], json!(["3", 0]));
+    for sampler in ["5", "13"] {
+        assert_eq!(workflow[sampler]["inputs"]["positive"], json!(["16", 0]));
+        assert_eq!(workflow[sampler]["inputs"]["negative"], json!(["17", 0]));
+    }
+}
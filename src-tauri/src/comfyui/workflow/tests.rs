@@ -0,0 +1,212 @@
+use super::*;
+
+pub(crate) fn make_request() -> GenerationRequest {
+    GenerationRequest {
+        positive_prompt: "masterpiece, best quality, a cat".to_string(),
+        negative_prompt: "lowres, blurry".to_string(),
+        checkpoint: "dreamshaper_8.safetensors".to_string(),
+        width: 512,
+        height: 768,
+        steps: 25,
+        cfg_scale: 7.5,
+        sampler: "dpmpp_2m".to_string(),
+        scheduler: "karras".to_string(),
+        seed: 12345,
+        batch_size: 1,
+        denoise: 1.0,
+        init_image: None,
+        loras: Vec::new(),
+        hires: None,
+        controlnet: None,
+        custom_workflow: None,
+    }
+}
+
+#[test]
+fn test_build_txt2img_has_all_nodes() {
+    let (workflow, _seed) = build_txt2img(&make_request());
+    assert!(workflow.get("1").is_some()); // CheckpointLoader
+    assert!(workflow.get("2").is_some()); // EmptyLatentImage
+    assert!(workflow.get("3").is_some()); // CLIPTextEncode positive
+    assert!(workflow.get("4").is_some()); // CLIPTextEncode negative
+    assert!(workflow.get("5").is_some()); // KSampler
+    assert!(workflow.get("6").is_some()); // VAEDecode
+    assert!(workflow.get("7").is_some()); // SaveImage
+}
+
+#[test]
+fn test_checkpoint_loader() {
+    let (workflow, _seed) = build_txt2img(&make_request());
+    let node = &workflow["1"];
+    assert_eq!(node["class_type"], "CheckpointLoaderSimple");
+    assert_eq!(node["inputs"]["ckpt_name"], "dreamshaper_8.safetensors");
+}
+
+#[test]
+fn test_ksampler_settings() {
+    let (workflow, seed) = build_txt2img(&make_request());
+    let node = &workflow["5"];
+    assert_eq!(node["class_type"], "KSampler");
+    assert_eq!(node["inputs"]["seed"], 12345);
+    assert_eq!(seed, 12345);
+    assert_eq!(node["inputs"]["steps"], 25);
+    assert_eq!(node["inputs"]["cfg"], 7.5);
+    assert_eq!(node["inputs"]["sampler_name"], "dpmpp_2m");
+    assert_eq!(node["inputs"]["scheduler"], "karras");
+    assert_eq!(node["inputs"]["denoise"], 1.0);
+}
+
+#[test]
+fn test_random_seed_when_negative() {
+    let mut req = make_request();
+    req.seed = -1;
+    let (workflow, actual_seed) = build_txt2img(&req);
+    assert!(actual_seed >= 0, "Random seed should be non-negative");
+    assert_eq!(workflow["5"]["inputs"]["seed"], actual_seed);
+}
+
+#[test]
+fn test_preview_seeds_fixed_base() {
+    let seeds = preview_seeds(100, 4, &mut rand::rng());
+    assert_eq!(seeds, vec![100, 101, 102, 103]);
+    assert_eq!(
+        preview_seeds(i64::MAX, 2, &mut rand::rng()),
+        vec![i64::MAX, 0]
+    );
+}
+
+#[test]
+fn test_preview_seeds_random_base_is_deterministic_with_seeded_rng() {
+    use rand::SeedableRng;
+    let a = preview_seeds(-1, 3, &mut rand::rngs::StdRng::seed_from_u64(1));
+    let b = preview_seeds(-1, 3, &mut rand::rngs::StdRng::seed_from_u64(1));
+    assert_eq!(a, b);
+    assert!(a[0] >= 0);
+    assert_eq!(a[1], (a[0] + 1) & i64::MAX);
+}
+
+#[test]
+fn test_clip_text_encode() {
+    let (workflow, _seed) = build_txt2img(&make_request());
+    let positive = &workflow["3"];
+    assert_eq!(
+        positive["inputs"]["text"],
+        "masterpiece, best quality, a cat"
+    );
+    assert_eq!(positive["inputs"]["clip"], json!(["1", 1]));
+
+    let negative = &workflow["4"];
+    assert_eq!(negative["inputs"]["text"], "lowres, blurry");
+}
+
+#[test]
+fn test_empty_latent_image() {
+    let (workflow, _seed) = build_txt2img(&make_request());
+    let node = &workflow["2"];
+    assert_eq!(node["inputs"]["width"], 512);
+    assert_eq!(node["inputs"]["height"], 768);
+    assert_eq!(node["inputs"]["batch_size"], 1);
+}
+
+#[test]
+fn test_node_connections() {
+    let (workflow, _seed) = build_txt2img(&make_request());
+
+    // KSampler connects to checkpoint model, positive, negative, latent
+    assert_eq!(workflow["5"]["inputs"]["model"], json!(["1", 0]));
+    assert_eq!(workflow["5"]["inputs"]["positive"], json!(["3", 0]));
+    assert_eq!(workflow["5"]["inputs"]["negative"], json!(["4", 0]));
+    assert_eq!(workflow["5"]["inputs"]["latent_image"], json!(["2", 0]));
+
+    // VAEDecode connects to KSampler output and checkpoint VAE
+    assert_eq!(workflow["6"]["inputs"]["samples"], json!(["5", 0]));
+    assert_eq!(workflow["6"]["inputs"]["vae"], json!(["1", 2]));
+
+    // SaveImage connects to VAEDecode output
+    assert_eq!(workflow["7"]["inputs"]["images"], json!(["6", 0]));
+}
+
+#[test]
+fn test_save_image_prefix() {
+    let (workflow, _seed) = build_txt2img(&make_request());
+    assert_eq!(workflow["7"]["inputs"]["filename_prefix"], "VisionForge");
+}
+
+#[test]
+fn test_workflow_is_valid_json() {
+    let (workflow, _seed) = build_txt2img(&make_request());
+    let json_str = serde_json::to_string(&workflow).unwrap();
+    assert!(json_str.len() > 100);
+    // Can re-parse
+    let _: Value = serde_json::from_str(&json_str).unwrap();
+}
+
+fn custom_graph() -> Value {
+    json!({
+        "4": {"class_type": "CheckpointLoaderSimple", "inputs": {"ckpt_name": "flux.safetensors"}},
+        "6": {"class_type": "CLIPTextEncode", "inputs": {"text": "placeholder", "clip": ["4", 1]}},
+        "7": {"class_type": "CLIPTextEncode", "inputs": {"text": "", "clip": ["4", 1]}},
+        "3": {"class_type": "KSamplerAdvanced", "inputs": {"noise_seed": 0, "steps": 30}},
+        "9": {"class_type": "SaveImage", "inputs": {"images": ["8", 0]}}
+    })
+}
+
+fn custom_workflow() -> CustomWorkflow {
+    CustomWorkflow {
+        prompt_graph: custom_graph(),
+        prompt_node_id: "6".to_string(),
+        negative_node_id: Some("7".to_string()),
+        seed_node_id: "3".to_string(),
+    }
+}
+
+#[test]
+fn test_custom_workflow_injects_prompts_and_seed() {
+    let (workflow, seed) = build_custom(&make_request(), &custom_workflow()).unwrap();
+    assert_eq!(seed, 12345);
+    assert_eq!(
+        workflow["6"]["inputs"]["text"],
+        "masterpiece, best quality, a cat"
+    );
+    assert_eq!(workflow["7"]["inputs"]["text"], "lowres, blurry");
+    assert_eq!(workflow["3"]["inputs"]["noise_seed"], 12345);
+
+    // Everything else is queued as uploaded
+    assert_eq!(workflow["3"]["inputs"]["steps"], 30);
+    assert_eq!(workflow["6"]["inputs"]["clip"], json!(["4", 1]));
+    assert_eq!(workflow["4"], custom_graph()["4"]);
+    assert_eq!(workflow.as_object().unwrap().len(), 5);
+}
+
+#[test]
+fn test_custom_workflow_randomizes_seed() {
+    let mut req = make_request();
+    req.seed = -1;
+    let mut custom = custom_workflow();
+    custom.negative_node_id = None;
+    let (workflow, seed) = build_custom(&req, &custom).unwrap();
+    assert!(seed >= 0);
+    assert_eq!(workflow["3"]["inputs"]["noise_seed"], seed);
+    assert_eq!(workflow["7"]["inputs"]["text"], "");
+}
+
+#[test]
+fn test_custom_workflow_rejects_missing_nodes_and_inputs() {
+    let mut custom = custom_workflow();
+    custom.prompt_node_id = "42".to_string();
+    let err = build_custom(&make_request(), &custom).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("no node \"42\" for the positive prompt"));
+
+    let mut custom = custom_workflow();
+    custom.seed_node_id = "6".to_string();
+    let err = build_custom(&make_request(), &custom).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Node \"6\" (CLIPTextEncode) has no 'seed' or 'noise_seed' input"));
+
+    let mut custom = custom_workflow();
+    custom.prompt_graph = json!([]);
+    assert!(build_custom(&make_request(), &custom).is_err());
+}
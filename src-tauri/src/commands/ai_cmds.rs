@@ -1,24 +1,25 @@
-use crate::ai::{captioner, tagger};
+use std::path::PathBuf;
+
+use crate::ai::{aesthetic, captioner, tagger};
 use crate::db;
 use crate::gallery::storage;
 use crate::state::AppState;
+use crate::types::config::AppConfig;
 
-#[tauri::command]
-pub async fn tag_image(
-    state: tauri::State<'_, AppState>,
-    image_id: String,
-) -> Result<Vec<String>, String> {
-    let config = state.config_snapshot().map_err(|e| e.to_string())?;
-    let endpoint = config.ollama.endpoint.clone();
-    let model = config.models.tagger.clone();
-
+/// Locate an image's file on disk, checking the configured output
+/// directory first and the default one second.
+fn resolve_image_path(
+    state: &AppState,
+    config: &AppConfig,
+    image_id: &str,
+) -> Result<PathBuf, String> {
     let image_path = {
         let conn = state.db.lock().map_err(|e| e.to_string())?;
-        let image = db::images::get_image(&conn, &image_id)
+        let image = db::images::get_image(&conn, image_id)
             .map_err(|e| format!("{:#}", e))?
             .ok_or_else(|| format!("Image {} not found", image_id))?;
 
-        let path = storage::get_image_path_for(&config, &image.filename);
+        let path = storage::get_image_path_for(config, &image.filename);
         if path.exists() {
             path
         } else {
@@ -29,10 +30,33 @@ pub async fn tag_image(
     if !image_path.exists() {
         return Err(format!("Image file not found: {}", image_path.display()));
     }
+    Ok(image_path)
+}
 
-    let tags = tagger::tag_image(&state.http_client, &endpoint, &model, &image_path)
+#[tauri::command]
+pub async fn tag_image(
+    state: tauri::State<'_, AppState>,
+    image_id: String,
+) -> Result<Vec<String>, String> {
+    let config = state.config_snapshot().map_err(|e| e.to_string())?;
+    let endpoint = config.ollama.endpoint.clone();
+    let model = config.models.tagger.clone();
+
+    let image_path = resolve_image_path(&state, &config, &image_id)?;
+    let max_dimension = config.hardware.vision_max_dimension();
+
+    let tags = {
+        let _gpu = state.exclusive_gpu().await;
+        tagger::tag_image(
+            &state.http_client,
+            &endpoint,
+            &model,
+            &image_path,
+            max_dimension,
+        )
         .await
-        .map_err(|e| format!("Tagging failed: {:#}", e))?;
+        .map_err(|e| format!("Tagging failed: {:#}", e))?
+    };
 
     // Save tags to database
     {
@@ -54,27 +78,21 @@ pub async fn caption_image(
     let endpoint = config.ollama.endpoint.clone();
     let model = config.models.captioner.clone();
 
-    let image_path = {
-        let conn = state.db.lock().map_err(|e| e.to_string())?;
-        let image = db::images::get_image(&conn, &image_id)
-            .map_err(|e| format!("{:#}", e))?
-            .ok_or_else(|| format!("Image {} not found", image_id))?;
-
-        let path = storage::get_image_path_for(&config, &image.filename);
-        if path.exists() {
-            path
-        } else {
-            storage::get_image_path(&image.filename)
-        }
-    };
-
-    if !image_path.exists() {
-        return Err(format!("Image file not found: {}", image_path.display()));
-    }
+    let image_path = resolve_image_path(&state, &config, &image_id)?;
+    let max_dimension = config.hardware.vision_max_dimension();
 
-    let caption = captioner::caption_image(&state.http_client, &endpoint, &model, &image_path)
+    let caption = {
+        let _gpu = state.exclusive_gpu().await;
+        captioner::caption_image(
+            &state.http_client,
+            &endpoint,
+            &model,
+            &image_path,
+            max_dimension,
+        )
         .await
-        .map_err(|e| format!("Captioning failed: {:#}", e))?;
+        .map_err(|e| format!("Captioning failed: {:#}", e))?
+    };
 
     // Save caption to database (AI-generated, not user-edited)
     {
@@ -85,3 +103,39 @@ pub async fn caption_image(
 
     Ok(caption)
 }
+
+/// Score an image's aesthetics 1–10 with a vision model (the captioner by
+/// default) and store the result for gallery sorting.
+#[tauri::command]
+pub async fn score_aesthetic(
+    state: tauri::State<'_, AppState>,
+    image_id: String,
+    model: Option<String>,
+) -> Result<f64, String> {
+    let config = state.config_snapshot().map_err(|e| e.to_string())?;
+    let endpoint = config.ollama.endpoint.clone();
+    let model = model.unwrap_or_else(|| config.models.captioner.clone());
+
+    let image_path = resolve_image_path(&state, &config, &image_id)?;
+    let max_dimension = config.hardware.vision_max_dimension();
+
+    let score = {
+        let _gpu = state.exclusive_gpu().await;
+        aesthetic::score_aesthetic(
+            &state.http_client,
+            &endpoint,
+            &model,
+            &image_path,
+            max_dimension,
+        )
+        .await
+        .map_err(|e| format!("Aesthetic scoring failed: {:#}", e))?
+    };
+
+    {
+        let conn = state.db.lock().map_err(|e| e.to_string())?;
+        db::images::set_aesthetic_score(&conn, &image_id, score).map_err(|e| format!("{:#}", e))?;
+    }
+
+    Ok(score)
+}
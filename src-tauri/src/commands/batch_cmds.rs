@@ -0,0 +1,75 @@
+use crate::db;
+use crate::state::AppState;
+use crate::types::gallery::BatchUpdate;
+
+/// Move many images to the trash in one call. Unknown ids are reported,
+/// not fatal.
+#[tauri::command]
+pub async fn delete_images(
+    state: tauri::State<'_, AppState>,
+    ids: Vec<String>,
+) -> Result<BatchUpdate, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    db::images_batch::soft_delete_many(&conn, &ids)
+        .map_err(|e| format!("Failed to delete images: {:#}", e))
+}
+
+/// Rate a keyboard-selected range of images at once. Returns how many were
+/// rated; unknown ids are skipped, as in [`rate_images`].
+#[tauri::command]
+pub async fn rate_range(
+    state: tauri::State<'_, AppState>,
+    ids: Vec<String>,
+    rating: Option<u32>,
+) -> Result<u32, String> {
+    let threshold = state
+        .config_snapshot()
+        .map_err(|e| e.to_string())?
+        .gallery
+        .auto_favorite_rating;
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    db::images_batch::update_rating_many(&conn, &ids, rating, threshold)
+        .map(|result| result.updated.len() as u32)
+        .map_err(|e| format!("Failed to rate images: {:#}", e))
+}
+
+/// Rate many images in one call, honouring the auto-favorite threshold.
+/// Unknown ids are reported, not fatal.
+#[tauri::command]
+pub async fn rate_images(
+    state: tauri::State<'_, AppState>,
+    ids: Vec<String>,
+    rating: Option<u32>,
+) -> Result<BatchUpdate, String> {
+    let threshold = state
+        .config_snapshot()
+        .map_err(|e| e.to_string())?
+        .gallery
+        .auto_favorite_rating;
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    db::images_batch::update_rating_many(&conn, &ids, rating, threshold)
+        .map_err(|e| format!("Failed to rate images: {:#}", e))
+}
+
+/// Set or clear the favorite flag on many images in one call.
+#[tauri::command]
+pub async fn favorite_images(
+    state: tauri::State<'_, AppState>,
+    ids: Vec<String>,
+    favorite: bool,
+) -> Result<BatchUpdate, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    db::images_batch::update_favorite_many(&conn, &ids, favorite)
+        .map_err(|e| format!("Failed to update favorites: {:#}", e))
+}
+
+#[tauri::command]
+pub async fn reattribute_checkpoint(
+    state: tauri::State<'_, AppState>,
+    ids: Vec<String>,
+    new_checkpoint: String,
+) -> Result<u32, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    db::images_batch::reattribute_checkpoint(&conn, &ids, &new_checkpoint)
+        .map_err(|e| format!("Failed to reattribute checkpoint: {:#}", e))
+}
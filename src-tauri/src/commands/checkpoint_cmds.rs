@@ -1,6 +1,11 @@
 use crate::db;
+use crate::pipeline::knowledge;
+use crate::pipeline::prompts::CheckpointContext;
 use crate::state::AppState;
-use crate::types::checkpoints::{CheckpointObservation, CheckpointProfile, PromptTerm};
+use crate::types::checkpoints::{
+    CheckpointObservation, CheckpointProfile, ProfileSuggestion, PromptTerm,
+};
+use crate::types::generation::GenerationRequest;
 
 #[tauri::command]
 pub async fn upsert_checkpoint(
@@ -22,6 +27,29 @@ pub async fn get_checkpoint(
         .map_err(|e| format!("Failed to get checkpoint: {:#}", e))
 }
 
+/// Generation settings pre-filled from the checkpoint's preferences, or
+/// `None` when it has no profile.
+#[tauri::command]
+pub async fn get_checkpoint_defaults(
+    state: tauri::State<'_, AppState>,
+    filename: String,
+) -> Result<Option<GenerationRequest>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    db::checkpoints::get_default_request(&conn, &filename)
+        .map_err(|e| format!("Failed to get checkpoint defaults: {:#}", e))
+}
+
+#[tauri::command]
+pub async fn set_checkpoint_sample_image(
+    state: tauri::State<'_, AppState>,
+    filename: String,
+    image_id: String,
+) -> Result<(), String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    db::checkpoints::set_checkpoint_sample_image(&conn, &filename, &image_id)
+        .map_err(|e| format!("Failed to set sample image: {:#}", e))
+}
+
 #[tauri::command]
 pub async fn list_checkpoint_profiles(
     state: tauri::State<'_, AppState>,
@@ -61,6 +89,17 @@ pub async fn add_checkpoint_observation(
         .map_err(|e| format!("Failed to add observation: {:#}", e))
 }
 
+/// Copy an image's note onto its checkpoint as a user observation.
+#[tauri::command]
+pub async fn promote_note_to_observation(
+    state: tauri::State<'_, AppState>,
+    image_id: String,
+) -> Result<CheckpointObservation, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    db::checkpoints::promote_image_note(&conn, &image_id)
+        .map_err(|e| format!("Failed to promote note: {:#}", e))
+}
+
 #[tauri::command]
 pub async fn get_checkpoint_observations(
     state: tauri::State<'_, AppState>,
@@ -80,3 +119,35 @@ pub async fn get_checkpoint_context(
     db::checkpoints::get_checkpoint_context(&conn, &filename)
         .map_err(|e| format!("Failed to get checkpoint context: {:#}", e))
 }
+
+/// Have the judge model propose updated strengths and weaknesses from the
+/// checkpoint's observations and terms. Returns the suggestion only; the
+/// frontend saves it through `upsert_checkpoint` once the user accepts.
+#[tauri::command]
+pub async fn suggest_checkpoint_profile(
+    state: tauri::State<'_, AppState>,
+    filename: String,
+) -> Result<ProfileSuggestion, String> {
+    let config = state.config_snapshot().map_err(|e| e.to_string())?;
+    let context = {
+        let conn = state.db.lock().map_err(|e| e.to_string())?;
+        knowledge::summary_context(&conn, &filename)
+            .map_err(|e| format!("Failed to gather checkpoint data: {:#}", e))?
+    };
+    knowledge::suggest_profile(&state.http_client, &config, &context)
+        .await
+        .map_err(|e| format!("Failed to summarize checkpoint: {:#}", e))
+}
+
+/// The checkpoint context exactly as the Prompt Engineer is given it. Unknown
+/// checkpoints get the same defaults the stage falls back to.
+#[tauri::command]
+pub async fn preview_pipeline_context(
+    state: tauri::State<'_, AppState>,
+    filename: String,
+) -> Result<CheckpointContext, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    knowledge::pipeline_context(&conn, &filename)
+        .map(Option::unwrap_or_default)
+        .map_err(|e| format!("Failed to build pipeline context: {:#}", e))
+}
@@ -1,6 +1,13 @@
-use crate::comfyui::{client, models, workflow};
+use crate::comfyui::{
+    client, logs, models, object_info, prompt_queue, smoke, system_stats, vram, workflow,
+};
+use crate::queue::prepare;
 use crate::state::AppState;
 use crate::types::generation::{GenerationRequest, GenerationStatus, GenerationStatusKind};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tauri::Emitter;
 
 #[tauri::command]
 pub async fn check_comfyui_health(state: tauri::State<'_, AppState>) -> Result<bool, String> {
@@ -57,16 +64,46 @@ pub async fn get_comfyui_schedulers(
 }
 
 #[tauri::command]
-pub async fn queue_generation(
+pub async fn get_comfyui_loras(state: tauri::State<'_, AppState>) -> Result<Vec<String>, String> {
+    let endpoint = {
+        let config = state.config.read().map_err(|e| e.to_string())?;
+        config.comfyui.endpoint.clone()
+    };
+
+    models::list_loras(&state.http_client, &endpoint)
+        .await
+        .map_err(|e| format!("{:#}", e))
+}
+
+#[tauri::command]
+pub async fn get_comfyui_controlnets(
     state: tauri::State<'_, AppState>,
-    request: GenerationRequest,
-) -> Result<GenerationStatus, String> {
+) -> Result<Vec<String>, String> {
     let endpoint = {
         let config = state.config.read().map_err(|e| e.to_string())?;
         config.comfyui.endpoint.clone()
     };
 
-    let (workflow_json, _actual_seed) = workflow::build_txt2img(&request);
+    models::list_controlnets(&state.http_client, &endpoint)
+        .await
+        .map_err(|e| format!("{:#}", e))
+}
+
+#[tauri::command]
+pub async fn queue_generation(
+    state: tauri::State<'_, AppState>,
+    request: GenerationRequest,
+) -> Result<GenerationStatus, String> {
+    let config = state.config_snapshot().map_err(|e| e.to_string())?;
+    let endpoint = config.comfyui.endpoint.clone();
+    request
+        .validate(config.generation.max_dimension)
+        .map_err(|e| format!("Invalid generation request: {:#}", e))?;
+
+    let (workflow_json, actual_seed) =
+        prepare::build_workflow(&state.http_client, &config, &request)
+            .await
+            .map_err(|e| format!("{:#}", e))?;
     let client_id = uuid::Uuid::new_v4().to_string();
 
     let prompt_id = client::queue_prompt(&state.http_client, &endpoint, &workflow_json, &client_id)
@@ -81,6 +118,7 @@ pub async fn queue_generation(
         total_steps: None,
         image_filenames: None,
         error: None,
+        seed: Some(actual_seed),
     })
 }
 
@@ -118,6 +156,7 @@ pub async fn get_generation_status(
                         Some(filenames)
                     },
                     error: None,
+                    seed: None,
                 })
             } else if h.status == "error" {
                 Ok(GenerationStatus {
@@ -128,6 +167,7 @@ pub async fn get_generation_status(
                     total_steps: None,
                     image_filenames: None,
                     error: Some("ComfyUI generation failed".to_string()),
+                    seed: None,
                 })
             } else {
                 Ok(GenerationStatus {
@@ -138,6 +178,7 @@ pub async fn get_generation_status(
                     total_steps: None,
                     image_filenames: None,
                     error: None,
+                    seed: None,
                 })
             }
         }
@@ -149,6 +190,7 @@ pub async fn get_generation_status(
             total_steps: None,
             image_filenames: None,
             error: None,
+            seed: None,
         }),
     }
 }
@@ -156,17 +198,57 @@ pub async fn get_generation_status(
 #[tauri::command]
 pub async fn get_comfyui_queue_status(
     state: tauri::State<'_, AppState>,
-) -> Result<client::QueueStatus, String> {
+) -> Result<prompt_queue::QueueStatus, String> {
     let endpoint = {
         let config = state.config.read().map_err(|e| e.to_string())?;
         config.comfyui.endpoint.clone()
     };
 
-    client::get_queue_status(&state.http_client, &endpoint)
+    prompt_queue::get_queue_status(&state.http_client, &endpoint)
         .await
         .map_err(|e| format!("{:#}", e))
 }
 
+/// VRAM and runtime versions reported by ComfyUI.
+#[tauri::command]
+pub async fn get_comfyui_system_stats(
+    state: tauri::State<'_, AppState>,
+) -> Result<system_stats::SystemStats, String> {
+    let endpoint = {
+        let config = state.config.read().map_err(|e| e.to_string())?;
+        config.comfyui.endpoint.clone()
+    };
+
+    system_stats::get_system_stats(&state.http_client, &endpoint)
+        .await
+        .map_err(|e| format!("{:#}", e))
+}
+
+/// Heads-up before queueing: whether `request` is likely to fit in the VRAM
+/// ComfyUI reports free, from a rough per-model and per-pixel estimate.
+#[tauri::command]
+pub async fn estimate_fit(
+    state: tauri::State<'_, AppState>,
+    request: GenerationRequest,
+) -> Result<vram::FitEstimate, String> {
+    let endpoint = {
+        let config = state.config.read().map_err(|e| e.to_string())?;
+        config.comfyui.endpoint.clone()
+    };
+    let base_model = {
+        let conn = state.db.lock().map_err(|e| e.to_string())?;
+        crate::db::checkpoints::get_checkpoint(&conn, &request.checkpoint)
+            .map_err(|e| format!("Failed to load checkpoint profile: {:#}", e))?
+            .and_then(|profile| profile.base_model)
+    };
+
+    let stats = system_stats::get_system_stats(&state.http_client, &endpoint)
+        .await
+        .map_err(|e| format!("{:#}", e))?;
+    let family = vram::ModelFamily::guess(base_model.as_deref(), &request.checkpoint);
+    Ok(vram::estimate_fit(&request, family, &stats))
+}
+
 #[tauri::command]
 pub async fn free_comfyui_memory(
     state: tauri::State<'_, AppState>,
@@ -193,3 +275,104 @@ pub async fn interrupt_comfyui(state: tauri::State<'_, AppState>) -> Result<(),
         .await
         .map_err(|e| format!("{:#}", e))
 }
+
+/// Validate a ComfyUI API-format workflow against the server's installed
+/// nodes. `/object_info` is fetched once and cached; pass `refresh` after
+/// installing custom nodes.
+#[tauri::command]
+pub async fn validate_workflow(
+    state: tauri::State<'_, AppState>,
+    workflow: serde_json::Value,
+    refresh: Option<bool>,
+) -> Result<Vec<String>, String> {
+    let endpoint = {
+        let config = state.config.read().map_err(|e| e.to_string())?;
+        config.comfyui.endpoint.clone()
+    };
+
+    let info = object_info::get_cached(&state, &endpoint, refresh.unwrap_or(false))
+        .await
+        .map_err(|e| format!("{:#}", e))?;
+
+    Ok(workflow::validate_against(&info, &workflow))
+}
+
+/// Generate a throwaway 64x64, 1-step image to check that ComfyUI works end
+/// to end. Failures are reported in the result rather than as an error.
+#[tauri::command]
+pub async fn smoke_test(
+    state: tauri::State<'_, AppState>,
+) -> Result<smoke::SmokeTestResult, String> {
+    let endpoint = {
+        let config = state.config.read().map_err(|e| e.to_string())?;
+        config.comfyui.endpoint.clone()
+    };
+
+    Ok(smoke::run_smoke_test(&state.http_client, &endpoint).await)
+}
+
+const MAX_SEED_PREVIEW: u32 = 1000;
+
+/// Seeds a batch of `count` images from `request` would use, for display
+/// before queueing. A random (-1) seed is resolved to a concrete base here.
+#[tauri::command]
+pub async fn preview_seeds(request: GenerationRequest, count: u32) -> Result<Vec<i64>, String> {
+    if count > MAX_SEED_PREVIEW {
+        return Err(format!(
+            "Can preview at most {} seeds, got {}",
+            MAX_SEED_PREVIEW, count
+        ));
+    }
+    Ok(workflow::preview_seeds(
+        request.seed,
+        count,
+        &mut rand::rng(),
+    ))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ComfyUiLogEvent {
+    pub line: String,
+}
+
+/// Start streaming ComfyUI's console as `comfyui:log` events. Replaces any
+/// tail that is already running; stop it with `stop_comfyui_log_tail`.
+#[tauri::command]
+pub async fn start_comfyui_log_tail(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let endpoint = {
+        let config = state.config.read().map_err(|e| e.to_string())?;
+        config.comfyui.endpoint.clone()
+    };
+
+    let stop = Arc::new(AtomicBool::new(false));
+    {
+        let mut current = state.comfyui_log_tail.lock().map_err(|e| e.to_string())?;
+        if let Some(previous) = current.replace(stop.clone()) {
+            previous.store(true, Ordering::Relaxed);
+        }
+    }
+
+    let client = state.http_client.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = logs::tail_logs(&client, &endpoint, stop, |line| {
+            let _ = app_handle.emit("comfyui:log", ComfyUiLogEvent { line });
+        })
+        .await;
+        if let Err(e) = result {
+            eprintln!("[comfyui] Log tail ended: {:#}", e);
+        }
+    });
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn stop_comfyui_log_tail(state: tauri::State<'_, AppState>) -> Result<(), String> {
+    let mut current = state.comfyui_log_tail.lock().map_err(|e| e.to_string())?;
+    if let Some(stop) = current.take() {
+        stop.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
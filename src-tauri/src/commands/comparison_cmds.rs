@@ -1,6 +1,8 @@
 use crate::db;
+use crate::gallery::variation;
 use crate::state::AppState;
-use crate::types::comparison::Comparison;
+use crate::types::comparison::{Comparison, ComparisonSide};
+use crate::types::generation::GenerationRequest;
 
 #[tauri::command]
 pub async fn create_comparison(
@@ -64,3 +66,16 @@ pub async fn delete_comparison(
     db::comparisons::delete_comparison(&conn, &id)
         .map_err(|e| format!("Failed to delete comparison: {:#}", e))
 }
+
+/// The generation request of the comparison's chosen image, ready to load
+/// into the generate form or enqueue.
+#[tauri::command]
+pub async fn continue_from_winner(
+    state: tauri::State<'_, AppState>,
+    comparison_id: String,
+    which: ComparisonSide,
+) -> Result<GenerationRequest, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    variation::continue_from_winner(&conn, &comparison_id, which)
+        .map_err(|e| format!("Failed to load comparison winner: {:#}", e))
+}
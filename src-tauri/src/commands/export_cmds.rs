@@ -1,13 +1,21 @@
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use tauri::Emitter;
+
+use super::gallery_cmds::attach_tags;
 use crate::db;
-use crate::gallery::export;
+use crate::gallery::{export, export_folder, provenance};
 use crate::state::AppState;
-use crate::types::gallery::GalleryFilter;
+use crate::types::config::AppConfig;
+use crate::types::gallery::{GalleryFilter, ImageEntry, ProvenanceBundle, SidecarFormat};
 
 #[tauri::command]
 pub async fn export_images(
+    app_handle: tauri::AppHandle,
     state: tauri::State<'_, AppState>,
     image_ids: Vec<String>,
     output_path: String,
+    include_images: Option<bool>,
 ) -> Result<(), String> {
     // Validate export path BEFORE doing any work
     let validated_path = export::validate_export_path(&output_path)
@@ -22,6 +30,7 @@ pub async fn export_images(
                 images.push(img);
             }
         }
+        attach_tags(&conn, &mut images)?;
         images
     };
 
@@ -29,15 +38,24 @@ pub async fn export_images(
         return Err("No images found to export".to_string());
     }
 
-    export::create_export_bundle_with_config(&images, &validated_path, Some(&config))
-        .map_err(|e| format!("Failed to create export: {:#}", e))
+    write_bundle(
+        app_handle,
+        &state,
+        images,
+        validated_path,
+        config,
+        include_images.unwrap_or(true),
+    )
+    .await
 }
 
 #[tauri::command]
 pub async fn export_gallery(
+    app_handle: tauri::AppHandle,
     state: tauri::State<'_, AppState>,
     filter: GalleryFilter,
     output_path: String,
+    include_images: Option<bool>,
 ) -> Result<u32, String> {
     // Validate export path BEFORE doing any work
     let validated_path = export::validate_export_path(&output_path)
@@ -46,8 +64,10 @@ pub async fn export_gallery(
     let config = state.config_snapshot().map_err(|e| e.to_string())?;
     let images = {
         let conn = state.db.lock().map_err(|e| e.to_string())?;
-        db::images::list_images(&conn, &filter)
-            .map_err(|e| format!("Failed to query images: {:#}", e))?
+        let mut images = db::image_filter::list_images(&conn, &filter)
+            .map_err(|e| format!("Failed to query images: {:#}", e))?;
+        attach_tags(&conn, &mut images)?;
+        images
     };
 
     if images.is_empty() {
@@ -55,8 +75,116 @@ pub async fn export_gallery(
     }
 
     let count = images.len() as u32;
-    export::create_export_bundle_with_config(&images, &validated_path, Some(&config))
-        .map_err(|e| format!("Failed to create export: {:#}", e))?;
+    write_bundle(
+        app_handle,
+        &state,
+        images,
+        validated_path,
+        config,
+        include_images.unwrap_or(true),
+    )
+    .await?;
 
     Ok(count)
 }
+
+/// Copy the originals of the given images into an existing folder, with an
+/// optional sidecar per image and a `manifest.csv`. Emits `export:progress`
+/// and honours `cancel_export`. Returns how many images were copied.
+#[tauri::command]
+pub async fn export_folder(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    image_ids: Vec<String>,
+    output_dir: String,
+    sidecar_format: Option<SidecarFormat>,
+) -> Result<u32, String> {
+    let dir = export::validate_export_dir(&output_dir)
+        .map_err(|e| format!("Invalid export folder: {:#}", e))?;
+
+    let config = state.config_snapshot().map_err(|e| e.to_string())?;
+    let images = {
+        let conn = state.db.lock().map_err(|e| e.to_string())?;
+        let mut images = Vec::new();
+        for id in &image_ids {
+            if let Some(img) = db::images::get_image(&conn, id).map_err(|e| format!("{:#}", e))? {
+                images.push(img);
+            }
+        }
+        attach_tags(&conn, &mut images)?;
+        images
+    };
+
+    if images.is_empty() {
+        return Err("No images found to export".to_string());
+    }
+
+    state.export_cancelled.store(false, Ordering::Relaxed);
+    let cancelled = state.export_cancelled.clone();
+    tokio::task::spawn_blocking(move || {
+        export_folder::export_to_folder(
+            &images,
+            &dir,
+            &config,
+            sidecar_format.unwrap_or_default(),
+            &cancelled,
+            |event| {
+                let _ = app_handle.emit("export:progress", event);
+            },
+        )
+    })
+    .await
+    .map_err(|e| format!("Export task panicked: {}", e))?
+    .map(|copied| copied as u32)
+    .map_err(|e| format!("Failed to export to folder: {:#}", e))
+}
+
+/// Stop the running export before its next image. The partial file is
+/// removed and the export command returns an error.
+#[tauri::command]
+pub async fn cancel_export(state: tauri::State<'_, AppState>) -> Result<(), String> {
+    state.export_cancelled.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Write the bundle off the async runtime, emitting `export:progress` after
+/// each image.
+async fn write_bundle(
+    app_handle: tauri::AppHandle,
+    state: &AppState,
+    images: Vec<ImageEntry>,
+    path: PathBuf,
+    config: AppConfig,
+    include_images: bool,
+) -> Result<(), String> {
+    state.export_cancelled.store(false, Ordering::Relaxed);
+    let cancelled = state.export_cancelled.clone();
+    tokio::task::spawn_blocking(move || {
+        export::write_export_bundle(
+            &images,
+            &path,
+            Some(&config),
+            include_images,
+            &cancelled,
+            |event| {
+                let _ = app_handle.emit("export:progress", event);
+            },
+        )
+    })
+    .await
+    .map_err(|e| format!("Export task panicked: {}", e))?
+    .map_err(|e| format!("Failed to create export: {:#}", e))
+}
+
+/// Everything recorded about how one image was made, as a single JSON
+/// bundle for sharing.
+#[tauri::command]
+pub async fn export_provenance(
+    state: tauri::State<'_, AppState>,
+    image_id: String,
+) -> Result<ProvenanceBundle, String> {
+    let config = state.config_snapshot().map_err(|e| e.to_string())?;
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    provenance::build_provenance(&conn, &config, &image_id)
+        .map_err(|e| format!("Failed to export provenance: {:#}", e))
+}
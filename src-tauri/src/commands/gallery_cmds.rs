@@ -1,31 +1,167 @@
 use crate::db;
-use crate::gallery::storage;
+use crate::gallery::{deletion, import, storage, triage, variation};
 use crate::state::AppState;
-use crate::types::gallery::{GalleryFilter, ImageEntry};
+use crate::types::gallery::{
+    GalleryFilter, ImageCaption, ImageEntry, ImageLineage, ImportFailure, ImportSummary, NewImages,
+    RecentChoices, TermCount,
+};
+use crate::types::generation::PartialGenerationRequest;
 
 #[tauri::command]
 pub async fn get_gallery_images(
     state: tauri::State<'_, AppState>,
     filter: GalleryFilter,
 ) -> Result<Vec<ImageEntry>, String> {
+    let filter = {
+        let config = state.config.read().map_err(|e| e.to_string())?;
+        filter.with_defaults(
+            config.gallery.default_page_size,
+            config.gallery.default_sort.as_ref(),
+        )
+    };
     let conn = state.db.lock().map_err(|e| e.to_string())?;
-    let mut images = db::images::list_images(&conn, &filter)
+    let mut images = db::image_filter::list_images(&conn, &filter)
         .map_err(|e| format!("Failed to load gallery: {:#}", e))?;
+    attach_tags(&conn, &mut images)?;
+    Ok(images)
+}
 
-    // Batch load tags (replaces N+1 per-image queries)
+/// Batch load tags for a page of images (replaces N+1 per-image queries).
+pub(crate) fn attach_tags(
+    conn: &rusqlite::Connection,
+    images: &mut [ImageEntry],
+) -> Result<(), String> {
     let image_ids: Vec<String> = images.iter().map(|i| i.id.clone()).collect();
-    let tag_map = db::tags::get_tags_for_images(&conn, &image_ids)
+    let tag_map = db::tags::get_tags_for_images(conn, &image_ids)
         .map_err(|e| format!("Failed to load tags: {:#}", e))?;
 
-    for img in &mut images {
+    for img in images.iter_mut() {
         if let Some(tags) = tag_map.get(&img.id) {
             if !tags.is_empty() {
                 img.tags = Some(tags.clone());
             }
         }
     }
+    Ok(())
+}
 
-    Ok(images)
+/// Images created since `since`, or since the last visit marked with
+/// `mark_gallery_seen` when omitted, plus how many there are. Before the
+/// first visit every image counts as new.
+#[tauri::command]
+pub async fn get_new_images(
+    state: tauri::State<'_, AppState>,
+    since: Option<String>,
+    limit: Option<u32>,
+) -> Result<NewImages, String> {
+    let filter = {
+        let config = state.config.read().map_err(|e| e.to_string())?;
+        GalleryFilter {
+            since: since.or_else(|| config.gallery.last_seen_at.clone()),
+            limit,
+            ..GalleryFilter::default()
+        }
+        .with_defaults(
+            config.gallery.default_page_size,
+            config.gallery.default_sort.as_ref(),
+        )
+    };
+    let since = filter.since.clone();
+
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let count = db::image_filter::count_images(&conn, &filter)
+        .map_err(|e| format!("Failed to count new images: {:#}", e))?;
+    let mut images = db::image_filter::list_images(&conn, &filter)
+        .map_err(|e| format!("Failed to load new images: {:#}", e))?;
+    attach_tags(&conn, &mut images)?;
+    Ok(NewImages {
+        since,
+        count,
+        images,
+    })
+}
+
+/// Record now as the last gallery visit and return the timestamp.
+#[tauri::command]
+pub async fn mark_gallery_seen(state: tauri::State<'_, AppState>) -> Result<String, String> {
+    let now = chrono::Utc::now().to_rfc3339();
+    let mut config = state.config.write().map_err(|e| e.to_string())?;
+    let mut updated = config.clone();
+    updated.gallery.last_seen_at = Some(now.clone());
+    crate::config::manager::save_config_to_disk(&updated)
+        .map_err(|e| format!("Failed to save last visit: {:#}", e))?;
+    *config = updated;
+    Ok(now)
+}
+
+/// Total compute units (steps × pixels × batch) across images matching the filter.
+#[tauri::command]
+pub async fn get_total_compute(
+    state: tauri::State<'_, AppState>,
+    filter: GalleryFilter,
+) -> Result<i64, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    db::image_filter::total_compute(&conn, &filter)
+        .map_err(|e| format!("Failed to compute total cost: {:#}", e))
+}
+
+/// Checkpoints used in the gallery with their image counts, for filter menus.
+#[tauri::command]
+pub async fn list_gallery_checkpoints(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<(String, u32)>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    db::images::distinct_checkpoints(&conn)
+        .map_err(|e| format!("Failed to list gallery checkpoints: {:#}", e))
+}
+
+/// The most recently used checkpoints, samplers, schedulers and sizes, for
+/// quick-picks on the generate form.
+#[tauri::command]
+pub async fn get_recent_choices(
+    state: tauri::State<'_, AppState>,
+) -> Result<RecentChoices, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    db::recent_choices::recent_choices(&conn)
+        .map_err(|e| format!("Failed to load recent choices: {:#}", e))
+}
+
+/// The most used prompt terms among images matching `filter`, for the
+/// prompt builder's palette.
+#[tauri::command]
+pub async fn get_term_frequencies(
+    state: tauri::State<'_, AppState>,
+    filter: GalleryFilter,
+    limit: Option<usize>,
+) -> Result<Vec<TermCount>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    db::term_frequencies::term_frequencies(&conn, &filter, limit.unwrap_or(50))
+        .map_err(|e| format!("Failed to count prompt terms: {:#}", e))
+}
+
+/// Copy PNGs made by other tools into the gallery, reading their A1111 or
+/// ComfyUI metadata. A file that can't be imported is reported and skipped.
+#[tauri::command]
+pub async fn import_images(
+    state: tauri::State<'_, AppState>,
+    paths: Vec<String>,
+) -> Result<ImportSummary, String> {
+    let config = state.config_snapshot().map_err(|e| e.to_string())?;
+    let mut summary = ImportSummary::default();
+    for path in paths {
+        let result = {
+            let conn = state.db.lock().map_err(|e| e.to_string())?;
+            import::import_png(&conn, &config, std::path::Path::new(&path))
+        };
+        match result {
+            Ok(image) => summary.imported.push(image.id),
+            Err(e) => summary.failed.push(ImportFailure {
+                path,
+                error: format!("{:#}", e),
+            }),
+        }
+    }
+    Ok(summary)
 }
 
 #[tauri::command]
@@ -60,31 +196,16 @@ pub async fn restore_image(state: tauri::State<'_, AppState>, id: String) -> Res
     db::images::restore_image(&conn, &id).map_err(|e| format!("Failed to restore image: {:#}", e))
 }
 
+/// Remove an image and its files for good. Refused in safe mode.
 #[tauri::command]
 pub async fn permanently_delete_image(
     state: tauri::State<'_, AppState>,
     id: String,
 ) -> Result<(), String> {
     let config = state.config_snapshot().map_err(|e| e.to_string())?;
-
-    // Get filename before deleting from DB
-    let image = {
-        let conn = state.db.lock().map_err(|e| e.to_string())?;
-        let image = db::images::get_image(&conn, &id)
-            .map_err(|e| format!("Failed to get image: {:#}", e))?;
-
-        db::images::permanently_delete_image(&conn, &id)
-            .map_err(|e| format!("Failed to permanently delete image: {:#}", e))?;
-        image
-    };
-
-    // Delete files from disk
-    if let Some(img) = image {
-        storage::delete_image_files_for(&config, &img.filename)
-            .map_err(|e| format!("DB row deleted but file cleanup failed: {:#}", e))?;
-    }
-
-    Ok(())
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    deletion::permanently_delete(&conn, &config, &id)
+        .map_err(|e| format!("Failed to permanently delete image: {:#}", e))
 }
 
 #[tauri::command]
@@ -93,8 +214,13 @@ pub async fn update_image_rating(
     id: String,
     rating: Option<u32>,
 ) -> Result<(), String> {
+    let threshold = state
+        .config_snapshot()
+        .map_err(|e| e.to_string())?
+        .gallery
+        .auto_favorite_rating;
     let conn = state.db.lock().map_err(|e| e.to_string())?;
-    db::images::update_image_rating(&conn, &id, rating)
+    db::images::update_image_rating_with_auto_favorite(&conn, &id, rating, threshold)
         .map_err(|e| format!("Failed to update rating: {:#}", e))
 }
 
@@ -109,6 +235,33 @@ pub async fn update_image_favorite(
         .map_err(|e| format!("Failed to update favorite: {:#}", e))
 }
 
+/// Rate, favorite and tag an image in one call for keyboard triage. Omitted
+/// fields are left unchanged. Returns the updated image with its tags.
+#[tauri::command]
+pub async fn quick_triage(
+    state: tauri::State<'_, AppState>,
+    id: String,
+    rating: Option<u32>,
+    favorite: Option<bool>,
+    add_tags: Option<Vec<String>>,
+) -> Result<ImageEntry, String> {
+    let threshold = state
+        .config_snapshot()
+        .map_err(|e| e.to_string())?
+        .gallery
+        .auto_favorite_rating;
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    triage::quick_triage(
+        &conn,
+        &id,
+        rating,
+        favorite,
+        &add_tags.unwrap_or_default(),
+        threshold,
+    )
+    .map_err(|e| format!("Failed to triage image: {:#}", e))
+}
+
 #[tauri::command]
 pub async fn update_caption(
     state: tauri::State<'_, AppState>,
@@ -121,38 +274,47 @@ pub async fn update_caption(
 }
 
 #[tauri::command]
-pub async fn update_image_note(
+pub async fn add_image_caption(
     state: tauri::State<'_, AppState>,
-    id: String,
-    note: String,
-) -> Result<(), String> {
+    image_id: String,
+    caption: String,
+    source: Option<String>,
+) -> Result<i64, String> {
     let conn = state.db.lock().map_err(|e| e.to_string())?;
-    db::images::update_image_note(&conn, &id, &note)
-        .map_err(|e| format!("Failed to update note: {:#}", e))
+    let source = source.unwrap_or_else(|| "user".to_string());
+    db::captions::add_image_caption(&conn, &image_id, &caption, &source)
+        .map_err(|e| format!("Failed to add caption: {:#}", e))
 }
 
 #[tauri::command]
-pub async fn add_tag(
+pub async fn list_image_captions(
     state: tauri::State<'_, AppState>,
     image_id: String,
-    tag: String,
-    source: String,
+) -> Result<Vec<ImageCaption>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    db::captions::list_image_captions(&conn, &image_id)
+        .map_err(|e| format!("Failed to list captions: {:#}", e))
+}
+
+#[tauri::command]
+pub async fn delete_image_caption(
+    state: tauri::State<'_, AppState>,
+    caption_id: i64,
 ) -> Result<(), String> {
     let conn = state.db.lock().map_err(|e| e.to_string())?;
-    db::tags::add_image_tag(&conn, &image_id, &tag, &source, None)
-        .map_err(|e| format!("Failed to add tag: {:#}", e))?;
-    Ok(())
+    db::captions::delete_image_caption(&conn, caption_id)
+        .map_err(|e| format!("Failed to delete caption: {:#}", e))
 }
 
 #[tauri::command]
-pub async fn remove_tag(
+pub async fn update_image_note(
     state: tauri::State<'_, AppState>,
-    image_id: String,
-    tag_id: i64,
+    id: String,
+    note: String,
 ) -> Result<(), String> {
     let conn = state.db.lock().map_err(|e| e.to_string())?;
-    db::tags::remove_image_tag(&conn, &image_id, tag_id)
-        .map_err(|e| format!("Failed to remove tag: {:#}", e))
+    db::images::update_image_note(&conn, &id, &note)
+        .map_err(|e| format!("Failed to update note: {:#}", e))
 }
 
 #[tauri::command]
@@ -169,6 +331,28 @@ pub async fn get_image_lineage(
     Ok(image.and_then(|img| img.pipeline_log))
 }
 
+/// Images this one was derived from (variation or img2img source, nearest
+/// first) and those derived from it.
+#[tauri::command]
+pub async fn get_image_derivation(
+    state: tauri::State<'_, AppState>,
+    image_id: String,
+) -> Result<ImageLineage, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    db::lineage::get_lineage(&conn, &image_id)
+        .map_err(|e| format!("Failed to get image lineage: {:#}", e))
+}
+
+#[tauri::command]
+pub async fn variation(
+    state: tauri::State<'_, AppState>,
+    image_id: String,
+    overrides: PartialGenerationRequest,
+) -> Result<String, String> {
+    variation::create_variation(&state, &image_id, &overrides)
+        .map_err(|e| format!("Failed to queue variation: {:#}", e))
+}
+
 #[tauri::command]
 pub async fn get_image_file_path(
     state: tauri::State<'_, AppState>,
@@ -188,21 +372,23 @@ pub async fn get_image_file_path(
     Err(format!("Image file not found: {}", filename))
 }
 
+/// Directory holding the original of image `id`, for "show in folder".
+/// Checks the configured image directory, then the default one.
 #[tauri::command]
-pub async fn get_thumbnail_file_path(
+pub async fn get_image_dir_path(
     state: tauri::State<'_, AppState>,
-    filename: String,
+    id: String,
 ) -> Result<String, String> {
+    let filename = {
+        let conn = state.db.lock().map_err(|e| e.to_string())?;
+        db::images::get_image(&conn, &id)
+            .map_err(|e| format!("Failed to load image: {:#}", e))?
+            .ok_or_else(|| format!("Image not found: {}", id))?
+            .filename
+    };
     storage::validate_filename(&filename).map_err(|e| format!("Invalid filename: {:#}", e))?;
     let config = state.config_snapshot().map_err(|e| e.to_string())?;
-    let path = storage::get_thumbnail_path_for(&config, &filename);
-    if path.exists() {
-        return Ok(path.to_string_lossy().to_string());
-    }
-    // Fallback to default path for images saved before config change
-    let fallback = storage::get_thumbnail_path(&filename);
-    if fallback.exists() {
-        return Ok(fallback.to_string_lossy().to_string());
-    }
-    Err(format!("Thumbnail not found for: {}", filename))
+    storage::locate_original_dir(&config, &filename)
+        .map(|dir| dir.to_string_lossy().to_string())
+        .ok_or_else(|| format!("Image file not found: {}", filename))
 }
@@ -1,5 +1,6 @@
 pub mod ai_batch_cmds;
 pub mod ai_cmds;
+pub mod batch_cmds;
 pub mod checkpoint_cmds;
 pub mod comfyui_cmds;
 pub mod comparison_cmds;
@@ -8,4 +9,7 @@ pub mod export_cmds;
 pub mod gallery_cmds;
 pub mod pipeline_cmds;
 pub mod queue_cmds;
+pub mod search_cmds;
 pub mod seed_cmds;
+pub mod tag_cmds;
+pub mod thumbnail_cmds;
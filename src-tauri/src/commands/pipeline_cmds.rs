@@ -1,12 +1,19 @@
 use std::sync::atomic::Ordering;
 
+use crate::ai::captioner;
 use crate::db;
+use crate::pipeline::edits;
 use crate::pipeline::engine::{self, PipelineInput};
 use crate::pipeline::engine_streaming;
-use crate::pipeline::ollama;
-use crate::pipeline::prompts::CheckpointContext;
+use crate::pipeline::knowledge;
+use crate::pipeline::prompt_templates;
+use crate::pipeline::prompts::{CheckpointContext, PromptTemplates};
+use crate::pipeline::single_stage;
+use crate::pipeline::{ollama, thinking};
+use crate::queue::manager;
 use crate::state::AppState;
-use crate::types::pipeline::PipelineResult;
+use crate::types::generation::PartialGenerationRequest;
+use crate::types::pipeline::{PipelineResult, PromptPair, PromptTemplateInfo, SavedPrompt};
 
 #[tauri::command]
 pub async fn run_full_pipeline(
@@ -25,27 +32,14 @@ pub async fn run_full_pipeline(
         cfg.clone()
     };
 
-    // Build checkpoint context if a checkpoint is specified
-    let checkpoint_context = if let Some(ref ckpt) = checkpoint {
-        let ctx = {
-            let conn = state.db.lock().map_err(|e| e.to_string())?;
-            db::checkpoints::get_checkpoint_context(&conn, ckpt)
-                .map_err(|e| format!("Failed to load checkpoint context: {}", e))?
-        };
-        if ctx.is_empty() {
-            None
-        } else {
-            Some(parse_checkpoint_context_string(&ctx, ckpt))
-        }
-    } else {
-        None
-    };
+    let checkpoint_context = load_checkpoint_context(&state, checkpoint.as_deref())?;
 
     let input = PipelineInput {
         idea,
         num_concepts: num_concepts.clamp(1, 10),
         auto_approve,
         checkpoint_context,
+        prompt_templates: load_prompt_templates(&state)?,
     };
 
     let cancelled = state.pipeline_cancelled.clone();
@@ -60,6 +54,112 @@ pub async fn run_full_pipeline(
     .map_err(|e| format!("{:#}", e))
 }
 
+/// Headless "just make it" path: run the full streaming pipeline, then enqueue
+/// a generation job with the final prompts. Returns the queue job ID.
+#[tauri::command]
+pub async fn idea_to_image(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    idea: String,
+    checkpoint: String,
+    settings: Option<PartialGenerationRequest>,
+) -> Result<String, String> {
+    state.pipeline_cancelled.store(false, Ordering::Relaxed);
+
+    let config = state.config_snapshot().map_err(|e| e.to_string())?;
+    let checkpoint_context = load_checkpoint_context(&state, Some(&checkpoint))?;
+
+    let input = PipelineInput {
+        idea,
+        num_concepts: 3,
+        auto_approve: true,
+        checkpoint_context,
+        prompt_templates: load_prompt_templates(&state)?,
+    };
+
+    let cancelled = state.pipeline_cancelled.clone();
+    let result = engine_streaming::run_pipeline_streaming(
+        &state.http_client,
+        &config,
+        input,
+        app_handle,
+        cancelled,
+    )
+    .await
+    .map_err(|e| format!("{:#}", e))?;
+
+    let job = manager::job_from_pipeline(&result, &checkpoint, &settings.unwrap_or_default())
+        .map_err(|e| format!("Failed to build queue job: {:#}", e))?;
+    manager::add_job(&state, job).map_err(|e| format!("Failed to add job to queue: {:#}", e))
+}
+
+/// Record hand edits to the final prompts on `result` and keep a
+/// `saved_prompts` copy. Use the returned result as the job's pipeline log so
+/// the generated image keeps both the model's prompts and the edit.
+#[tauri::command]
+pub async fn record_prompt_edits(
+    state: tauri::State<'_, AppState>,
+    mut result: PipelineResult,
+    positive_prompt: String,
+    negative_prompt: String,
+) -> Result<PipelineResult, String> {
+    let edited = PromptPair {
+        positive: positive_prompt,
+        negative: negative_prompt,
+    };
+    edits::apply_user_edits(&mut result, edited.clone()).map_err(|e| format!("{:#}", e))?;
+
+    if let Some(original) = engine::get_final_prompts(&result) {
+        if result.user_edits.as_ref().is_some_and(|e| e.prompt_edited) {
+            let conn = state.db.lock().map_err(|e| e.to_string())?;
+            db::saved_prompts::insert_saved_prompt(
+                &conn,
+                result.run_id.as_deref(),
+                &original,
+                &edited,
+            )
+            .map_err(|e| format!("Failed to save edited prompt: {:#}", e))?;
+        }
+    }
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn list_saved_prompts(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<SavedPrompt>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    db::saved_prompts::list_saved_prompts(&conn)
+        .map_err(|e| format!("Failed to list saved prompts: {:#}", e))
+}
+
+/// Suggest a pipeline idea from a reference image using a vision model (the
+/// captioner by default). The text is meant for `run_full_pipeline`.
+#[tauri::command]
+pub async fn idea_from_image(
+    state: tauri::State<'_, AppState>,
+    image_path: String,
+    model: Option<String>,
+) -> Result<String, String> {
+    let config = state.config_snapshot().map_err(|e| e.to_string())?;
+    let model = model.unwrap_or_else(|| config.models.captioner.clone());
+
+    let path = std::path::PathBuf::from(&image_path);
+    if !path.is_file() {
+        return Err(format!("Reference image not found: {}", image_path));
+    }
+
+    captioner::idea_from_image(
+        &state.http_client,
+        &config.ollama.endpoint,
+        &model,
+        &path,
+        config.hardware.vision_max_dimension(),
+    )
+    .await
+    .map_err(|e| format!("Failed to get idea from image: {:#}", e))
+}
+
 #[tauri::command]
 pub async fn run_pipeline_stage(
     state: tauri::State<'_, AppState>,
@@ -68,16 +168,45 @@ pub async fn run_pipeline_stage(
     model: String,
     checkpoint_context: Option<String>,
 ) -> Result<String, String> {
-    let endpoint = {
-        let config = state.config.read().map_err(|e| e.to_string())?;
-        config.ollama.endpoint.clone()
-    };
+    let config = state.config_snapshot().map_err(|e| e.to_string())?;
+    let ctx = checkpoint_context.map(|s| knowledge::parse_checkpoint_context(&s, "unknown"));
+    let templates = load_prompt_templates(&state)?;
 
-    let ctx = checkpoint_context.map(|s| parse_checkpoint_context_string(&s, "unknown"));
+    single_stage::run_single_stage(
+        &state.http_client,
+        &config,
+        &stage,
+        &model,
+        &input,
+        ctx,
+        &templates,
+    )
+    .await
+    .map_err(|e| format!("{:#}", e))
+}
 
-    engine::run_single_stage(&state.http_client, &endpoint, &stage, &model, &input, ctx)
-        .await
-        .map_err(|e| format!("{:#}", e))
+/// Tune a hand-written prompt for `checkpoint_filename` with only the
+/// Prompt Engineer stage, using the checkpoint's stored profile as context.
+/// Nothing is queued or saved.
+#[tauri::command]
+pub async fn tune_prompt(
+    state: tauri::State<'_, AppState>,
+    prompt: String,
+    checkpoint_filename: String,
+) -> Result<PromptPair, String> {
+    let config = state.config_snapshot().map_err(|e| e.to_string())?;
+    let checkpoint_context = load_checkpoint_context(&state, Some(&checkpoint_filename))?;
+    let templates = load_prompt_templates(&state)?;
+
+    single_stage::tune_prompt(
+        &state.http_client,
+        &config,
+        &prompt,
+        checkpoint_context,
+        &templates,
+    )
+    .await
+    .map_err(|e| format!("{:#}", e))
 }
 
 #[tauri::command]
@@ -116,7 +245,7 @@ pub async fn get_thinking_models(state: tauri::State<'_, AppState>) -> Result<Ve
     let model_names: Vec<String> = all_models.into_iter().map(|m| m.name).collect();
 
     let mut thinking =
-        ollama::detect_thinking_models(&state.http_client, &endpoint, &model_names).await;
+        thinking::detect_thinking_models(&state.http_client, &endpoint, &model_names).await;
 
     // Merge in user-configured custom thinking models (only if installed)
     for custom in &custom_thinking {
@@ -148,39 +277,64 @@ pub async fn cancel_pipeline(state: tauri::State<'_, AppState>) -> Result<(), St
     Ok(())
 }
 
-fn parse_checkpoint_context_string(context_str: &str, checkpoint: &str) -> CheckpointContext {
-    // Try JSON first (new format)
-    if let Ok(ctx) = serde_json::from_str::<CheckpointContext>(context_str) {
-        return ctx;
-    }
-
-    // Fall back to line-based parsing (legacy format)
-    let mut ctx = CheckpointContext {
-        checkpoint_name: checkpoint.to_string(),
-        ..Default::default()
+/// Built-in system prompt and any stored override for every stage.
+#[tauri::command]
+pub async fn get_prompt_templates(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<PromptTemplateInfo>, String> {
+    let mut overrides = {
+        let conn = state.db.lock().map_err(|e| e.to_string())?;
+        db::prompt_templates::load_prompt_templates(&conn)
+            .map_err(|e| format!("Failed to load prompt templates: {:#}", e))?
     };
 
-    for line in context_str.lines() {
-        let line = line.trim();
-        if let Some(rest) = line.strip_prefix("Checkpoint: ") {
-            ctx.checkpoint_name = rest.to_string();
-        } else if let Some(rest) = line.strip_prefix("Base model: ") {
-            ctx.base_model = rest.to_string();
-        } else if let Some(rest) = line.strip_prefix("Strengths: ") {
-            ctx.strengths = rest.to_string();
-        } else if let Some(rest) = line.strip_prefix("Weaknesses: ") {
-            ctx.weaknesses = rest.to_string();
-        } else if let Some(rest) = line.strip_prefix("Notes: ") {
-            ctx.checkpoint_notes = rest.to_string();
-        } else if line.starts_with("Known terms:") {
-            ctx.term_list = String::new();
-        } else if line.starts_with("- ") {
-            if !ctx.term_list.is_empty() {
-                ctx.term_list.push('\n');
-            }
-            ctx.term_list.push_str(line);
-        }
+    Ok(prompt_templates::TEMPLATE_STAGES
+        .iter()
+        .map(|stage| PromptTemplateInfo {
+            stage: stage.to_string(),
+            default_template: prompt_templates::default_template(stage)
+                .unwrap_or_default()
+                .to_string(),
+            override_template: overrides.remove(*stage),
+        })
+        .collect())
+}
+
+/// Override a stage's system prompt. `None` or a blank template restores
+/// the built-in one.
+#[tauri::command]
+pub async fn set_prompt_template(
+    state: tauri::State<'_, AppState>,
+    stage: String,
+    template: Option<String>,
+) -> Result<(), String> {
+    if prompt_templates::default_template(&stage).is_none() {
+        return Err(format!("Unknown pipeline stage: {}", stage));
     }
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    match template.filter(|t| !t.trim().is_empty()) {
+        Some(t) => db::prompt_templates::set_prompt_template(&conn, &stage, &t),
+        None => db::prompt_templates::reset_prompt_template(&conn, &stage),
+    }
+    .map_err(|e| format!("Failed to save prompt template: {:#}", e))
+}
 
-    ctx
+fn load_prompt_templates(state: &AppState) -> Result<PromptTemplates, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    db::prompt_templates::load_prompt_templates(&conn)
+        .map(PromptTemplates::new)
+        .map_err(|e| format!("Failed to load prompt templates: {:#}", e))
+}
+
+/// Load the stored profile for `checkpoint` as Prompt Engineer context, if any.
+fn load_checkpoint_context(
+    state: &AppState,
+    checkpoint: Option<&str>,
+) -> Result<Option<CheckpointContext>, String> {
+    let Some(ckpt) = checkpoint else {
+        return Ok(None);
+    };
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    knowledge::pipeline_context(&conn, ckpt)
+        .map_err(|e| format!("Failed to load checkpoint context: {}", e))
 }
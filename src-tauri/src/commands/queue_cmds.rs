@@ -1,14 +1,20 @@
 use crate::db;
 use crate::queue::manager;
+use crate::queue::sweep;
 use crate::state::AppState;
-use crate::types::queue::{QueueJob, QueuePriority};
+use crate::types::generation::GenerationRequest;
+use crate::types::queue::{QueueJob, QueuePriority, VariableSweep};
 
 #[tauri::command]
 pub async fn add_to_queue(
     state: tauri::State<'_, AppState>,
     job: QueueJob,
+    priority: Option<QueuePriority>,
+    position: Option<usize>,
 ) -> Result<String, String> {
-    manager::add_job(&state, job).map_err(|e| format!("Failed to add job to queue: {:#}", e))
+    let priority = priority.unwrap_or_else(|| job.priority.clone());
+    manager::add_job_at(&state, job, priority, position)
+        .map_err(|e| format!("Failed to add job to queue: {:#}", e))
 }
 
 #[tauri::command]
@@ -31,9 +37,40 @@ pub async fn cancel_queue_job(
     state: tauri::State<'_, AppState>,
     job_id: String,
 ) -> Result<(), String> {
-    manager::cancel_job(&state, &job_id)
-        .await
-        .map_err(|e| format!("Failed to cancel job: {:#}", e))
+    manager::cancel_job(&state, &job_id).map_err(|e| format!("Failed to cancel job: {:#}", e))
+}
+
+#[tauri::command]
+pub async fn cancel_group(
+    state: tauri::State<'_, AppState>,
+    group_id: String,
+    abort_generating: Option<bool>,
+) -> Result<u32, String> {
+    manager::cancel_group(&state, &group_id, abort_generating.unwrap_or(false))
+        .map_err(|e| format!("Failed to cancel group: {:#}", e))
+}
+
+/// Queue one job per value of `variable` (sampler, scheduler, cfg, steps)
+/// with everything else taken from `base`.
+#[tauri::command]
+pub async fn enqueue_variable_sweep(
+    state: tauri::State<'_, AppState>,
+    base: GenerationRequest,
+    variable: String,
+    values: Vec<serde_json::Value>,
+) -> Result<VariableSweep, String> {
+    sweep::enqueue_variable_sweep(&state, &base, &variable, &values)
+        .map_err(|e| format!("Failed to enqueue sweep: {:#}", e))
+}
+
+#[tauri::command]
+pub async fn relink_result(
+    state: tauri::State<'_, AppState>,
+    job_id: String,
+    image_id: String,
+) -> Result<(), String> {
+    manager::relink_result(&state, &job_id, &image_id)
+        .map_err(|e| format!("Failed to relink result image: {:#}", e))
 }
 
 #[tauri::command]
@@ -0,0 +1,99 @@
+use crate::db;
+use crate::gallery::duplicates;
+use crate::pipeline::ollama;
+use crate::state::AppState;
+use crate::types::gallery::ImageEntry;
+
+/// Images that look like `id`, closest first, with their hash distance.
+#[tauri::command]
+pub async fn find_similar_images(
+    state: tauri::State<'_, AppState>,
+    id: String,
+    max_distance: Option<u32>,
+) -> Result<Vec<(ImageEntry, u32)>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    db::phash::find_similar(
+        &conn,
+        &id,
+        max_distance.unwrap_or(duplicates::DEFAULT_MAX_DISTANCE),
+    )
+    .map_err(|e| format!("Failed to find similar images: {:#}", e))
+}
+
+/// Scan the whole gallery for groups of near-identical images, hashing any
+/// saved before hashes were recorded first.
+#[tauri::command]
+pub async fn find_duplicates(
+    state: tauri::State<'_, AppState>,
+    max_distance: Option<u32>,
+) -> Result<Vec<Vec<ImageEntry>>, String> {
+    let config = state.config_snapshot().map_err(|e| e.to_string())?;
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    duplicates::backfill_hashes(&conn, &config)
+        .map_err(|e| format!("Failed to hash gallery images: {:#}", e))?;
+    duplicates::find_duplicate_groups(
+        &conn,
+        max_distance.unwrap_or(duplicates::DEFAULT_MAX_DISTANCE),
+    )
+    .map_err(|e| format!("Failed to find duplicates: {:#}", e))
+}
+
+/// Embed the prompt of every image that doesn't have an embedding yet.
+/// Returns how many were embedded; stops at the first Ollama failure.
+#[tauri::command]
+pub async fn index_prompt_embeddings(state: tauri::State<'_, AppState>) -> Result<u32, String> {
+    let config = state.config_snapshot().map_err(|e| e.to_string())?;
+    let missing = {
+        let conn = state.db.lock().map_err(|e| e.to_string())?;
+        db::semantic_search::list_images_missing_embedding(&conn)
+            .map_err(|e| format!("Failed to list images: {:#}", e))?
+    };
+
+    let mut indexed = 0;
+    for (image_id, prompt) in missing {
+        let embedding = ollama::embed(
+            &state.http_client,
+            &config.ollama.endpoint,
+            &config.models.embedder,
+            &prompt,
+        )
+        .await
+        .map_err(|e| format!("Failed to embed prompt of {}: {:#}", image_id, e))?;
+
+        let conn = state.db.lock().map_err(|e| e.to_string())?;
+        db::semantic_search::set_prompt_embedding(&conn, &image_id, &embedding)
+            .map_err(|e| format!("Failed to store embedding: {:#}", e))?;
+        indexed += 1;
+    }
+    Ok(indexed)
+}
+
+/// Reindex gallery search from scratch, for databases whose full-text index
+/// has drifted from the images table. Returns how many images are indexed.
+#[tauri::command]
+pub async fn rebuild_search_index(state: tauri::State<'_, AppState>) -> Result<u32, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    db::maintenance::rebuild_fts(&conn)
+        .map_err(|e| format!("Failed to rebuild search index: {:#}", e))
+}
+
+#[tauri::command]
+pub async fn semantic_search(
+    state: tauri::State<'_, AppState>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<ImageEntry>, String> {
+    let config = state.config_snapshot().map_err(|e| e.to_string())?;
+    let query_embedding = ollama::embed(
+        &state.http_client,
+        &config.ollama.endpoint,
+        &config.models.embedder,
+        &query,
+    )
+    .await
+    .map_err(|e| format!("Failed to embed search query: {:#}", e))?;
+
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    db::semantic_search::semantic_search(&conn, &query_embedding, limit.unwrap_or(50))
+        .map_err(|e| format!("Semantic search failed: {:#}", e))
+}
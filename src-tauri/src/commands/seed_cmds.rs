@@ -20,6 +20,19 @@ pub async fn get_seed(
     db::seeds::get_seed(&conn, id).map_err(|e| format!("Failed to get seed: {:#}", e))
 }
 
+/// Link a gallery image as the seed's sample; `get_seed` then reports its
+/// filename for the thumbnail.
+#[tauri::command]
+pub async fn set_sample_image(
+    state: tauri::State<'_, AppState>,
+    seed_id: i64,
+    image_id: String,
+) -> Result<(), String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    db::seeds::set_seed_sample_image(&conn, seed_id, &image_id)
+        .map_err(|e| format!("Failed to set seed sample image: {:#}", e))
+}
+
 #[tauri::command]
 pub async fn list_seeds(
     state: tauri::State<'_, AppState>,
@@ -0,0 +1,103 @@
+use crate::db;
+use crate::state::AppState;
+use crate::types::gallery::{TagChangeSummary, TagEntry, TagImplication};
+
+#[tauri::command]
+pub async fn add_tag(
+    state: tauri::State<'_, AppState>,
+    image_id: String,
+    tag: String,
+    source: String,
+) -> Result<(), String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    db::tags::add_image_tag(&conn, &image_id, &tag, &source, None)
+        .map_err(|e| format!("Failed to add tag: {:#}", e))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn remove_tag(
+    state: tauri::State<'_, AppState>,
+    image_id: String,
+    tag_id: i64,
+) -> Result<(), String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    db::tags::remove_image_tag(&conn, &image_id, tag_id)
+        .map_err(|e| format!("Failed to remove tag: {:#}", e))
+}
+
+/// Rename a tag everywhere it is used. With `dry_run` only the affected
+/// image and seed counts are returned, so the UI can confirm first.
+#[tauri::command]
+pub async fn rename_tag(
+    state: tauri::State<'_, AppState>,
+    tag_id: i64,
+    new_name: String,
+    dry_run: Option<bool>,
+) -> Result<TagChangeSummary, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    db::tags::rename_tag(&conn, tag_id, &new_name, dry_run.unwrap_or(false))
+        .map_err(|e| format!("Failed to rename tag: {:#}", e))
+}
+
+/// Every tag with how many images use it, most used first, for the tag
+/// cloud. Soft-deleted images only count with `include_deleted`.
+#[tauri::command]
+pub async fn get_tag_counts(
+    state: tauri::State<'_, AppState>,
+    include_deleted: Option<bool>,
+) -> Result<Vec<(TagEntry, u32)>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    db::tags::list_tags_with_counts(&conn, include_deleted.unwrap_or(false))
+        .map_err(|e| format!("Failed to count tags: {:#}", e))
+}
+
+/// Merge `source_tag_id` into `target_tag_id`. `dry_run` works as for
+/// [`rename_tag`].
+#[tauri::command]
+pub async fn merge_tags(
+    state: tauri::State<'_, AppState>,
+    source_tag_id: i64,
+    target_tag_id: i64,
+    dry_run: Option<bool>,
+) -> Result<TagChangeSummary, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    db::tags::merge_tags(
+        &conn,
+        source_tag_id,
+        target_tag_id,
+        dry_run.unwrap_or(false),
+    )
+    .map_err(|e| format!("Failed to merge tags: {:#}", e))
+}
+
+#[tauri::command]
+pub async fn add_tag_implication(
+    state: tauri::State<'_, AppState>,
+    tag: String,
+    implied_tag: String,
+) -> Result<(), String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    db::tag_implications::add_tag_implication(&conn, &tag, &implied_tag)
+        .map_err(|e| format!("Failed to add tag implication: {:#}", e))
+}
+
+#[tauri::command]
+pub async fn remove_tag_implication(
+    state: tauri::State<'_, AppState>,
+    tag_id: i64,
+    implied_tag_id: i64,
+) -> Result<(), String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    db::tag_implications::remove_tag_implication(&conn, tag_id, implied_tag_id)
+        .map_err(|e| format!("Failed to remove tag implication: {:#}", e))
+}
+
+#[tauri::command]
+pub async fn list_tag_implications(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<TagImplication>, String> {
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    db::tag_implications::list_tag_implications(&conn)
+        .map_err(|e| format!("Failed to list tag implications: {:#}", e))
+}
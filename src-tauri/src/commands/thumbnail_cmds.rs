@@ -0,0 +1,47 @@
+use tauri::Emitter;
+
+use crate::db;
+use crate::gallery::{storage, thumbnails};
+use crate::state::AppState;
+use crate::types::gallery::ThumbnailRegenSummary;
+
+#[tauri::command]
+pub async fn get_thumbnail_file_path(
+    state: tauri::State<'_, AppState>,
+    filename: String,
+) -> Result<String, String> {
+    storage::validate_filename(&filename).map_err(|e| format!("Invalid filename: {:#}", e))?;
+    let config = state.config_snapshot().map_err(|e| e.to_string())?;
+    // Older thumbnails may predate a directory or format change
+    storage::thumbnail_candidates(&config, &filename)
+        .into_iter()
+        .find(|path| path.exists())
+        .map(|path| path.to_string_lossy().to_string())
+        .ok_or_else(|| format!("Thumbnail not found for: {}", filename))
+}
+
+/// Re-render every thumbnail at `size`, or the configured size when omitted,
+/// streaming `thumbnails:progress`. Images whose original is gone are
+/// skipped and listed in the summary's `errors`.
+#[tauri::command]
+pub async fn regenerate_thumbnails(
+    app_handle: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    size: Option<u32>,
+) -> Result<ThumbnailRegenSummary, String> {
+    let config = state.config_snapshot().map_err(|e| e.to_string())?;
+    let filenames = {
+        let conn = state.db.lock().map_err(|e| e.to_string())?;
+        db::images::list_all_filenames(&conn)
+            .map_err(|e| format!("Failed to list images: {:#}", e))?
+    };
+
+    tokio::task::spawn_blocking(move || {
+        thumbnails::regenerate_thumbnails(&config, &filenames, size, |event| {
+            let _ = app_handle.emit("thumbnails:progress", event);
+        })
+    })
+    .await
+    .map_err(|e| format!("Thumbnail task panicked: {}", e))?
+    .map_err(|e| format!("Failed to regenerate thumbnails: {:#}", e))
+}
@@ -1 +1,2 @@
 pub mod manager;
+mod toml_config;
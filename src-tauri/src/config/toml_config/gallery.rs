@@ -0,0 +1,37 @@
+use crate::types::gallery::GallerySortField;
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub(super) struct TomlGallery {
+    #[serde(default)]
+    pub(super) auto_favorite_rating: u32,
+    #[serde(default)]
+    pub(super) auto_rate_from_fidelity: bool,
+    #[serde(default)]
+    pub(super) safe_mode: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(super) last_seen_at: Option<String>,
+    #[serde(default = "default_page_size")]
+    pub(super) default_page_size: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(super) default_sort: Option<GallerySortField>,
+    #[serde(default)]
+    pub(super) trash_retention_days: u32,
+}
+
+impl Default for TomlGallery {
+    fn default() -> Self {
+        Self {
+            auto_favorite_rating: 0,
+            auto_rate_from_fidelity: false,
+            safe_mode: false,
+            last_seen_at: None,
+            default_page_size: default_page_size(),
+            default_sort: None,
+            trash_retention_days: 0,
+        }
+    }
+}
+
+fn default_page_size() -> u32 {
+    crate::types::gallery::DEFAULT_PAGE_SIZE
+}
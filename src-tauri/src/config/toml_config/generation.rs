@@ -0,0 +1,24 @@
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub(super) struct TomlGeneration {
+    #[serde(default = "default_max_dimension")]
+    pub(super) max_dimension: u32,
+    #[serde(default = "default_max_job_retries")]
+    pub(super) max_job_retries: u32,
+}
+
+impl Default for TomlGeneration {
+    fn default() -> Self {
+        Self {
+            max_dimension: default_max_dimension(),
+            max_job_retries: default_max_job_retries(),
+        }
+    }
+}
+
+fn default_max_dimension() -> u32 {
+    crate::types::generation::MAX_DIMENSION
+}
+
+fn default_max_job_retries() -> u32 {
+    1
+}
@@ -0,0 +1,77 @@
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub(super) struct TomlHardware {
+    #[serde(default = "default_cooldown")]
+    pub(super) cooldown_seconds: u32,
+    #[serde(default)]
+    pub(super) cooldown_jitter_secs: u32,
+    #[serde(default)]
+    pub(super) adaptive_cooldown: bool,
+    #[serde(default = "default_max_consecutive")]
+    pub(super) max_consecutive_generations: u32,
+    #[serde(default)]
+    pub(super) enable_ha_power_monitoring: bool,
+    #[serde(default = "default_ha_entity")]
+    pub(super) ha_entity_id: String,
+    #[serde(default = "default_ha_watts")]
+    pub(super) ha_max_watts: u32,
+    #[serde(default = "default_ha_url")]
+    pub(super) ha_url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(super) ha_token: Option<String>,
+    #[serde(default = "default_batch_downscale")]
+    pub(super) ai_batch_downscale: Option<bool>,
+    #[serde(default = "default_batch_max_dim")]
+    pub(super) ai_batch_max_dimension: Option<u32>,
+    #[serde(default = "default_thumbnail_concurrency")]
+    pub(super) thumbnail_concurrency: u32,
+    #[serde(default)]
+    pub(super) exclusive_gpu: bool,
+}
+
+fn default_batch_downscale() -> Option<bool> {
+    Some(true)
+}
+
+fn default_batch_max_dim() -> Option<u32> {
+    Some(1024)
+}
+
+fn default_thumbnail_concurrency() -> u32 {
+    4
+}
+
+impl Default for TomlHardware {
+    fn default() -> Self {
+        Self {
+            cooldown_seconds: default_cooldown(),
+            cooldown_jitter_secs: 0,
+            adaptive_cooldown: false,
+            max_consecutive_generations: default_max_consecutive(),
+            enable_ha_power_monitoring: false,
+            ha_entity_id: default_ha_entity(),
+            ha_max_watts: default_ha_watts(),
+            ha_url: default_ha_url(),
+            ha_token: None,
+            ai_batch_downscale: default_batch_downscale(),
+            ai_batch_max_dimension: default_batch_max_dim(),
+            thumbnail_concurrency: default_thumbnail_concurrency(),
+            exclusive_gpu: false,
+        }
+    }
+}
+
+fn default_cooldown() -> u32 {
+    30
+}
+fn default_max_consecutive() -> u32 {
+    5
+}
+fn default_ha_entity() -> String {
+    "sensor.gpu_power_draw".to_string()
+}
+fn default_ha_watts() -> u32 {
+    180
+}
+fn default_ha_url() -> String {
+    "http://homeassistant.local:8123".to_string()
+}
@@ -0,0 +1,301 @@
+mod gallery;
+mod generation;
+mod hardware;
+mod models;
+mod pipeline;
+mod storage;
+
+use crate::types::config::{AppConfig, LlmBackend};
+use gallery::TomlGallery;
+use generation::TomlGeneration;
+use hardware::TomlHardware;
+use models::TomlModels;
+use pipeline::{TomlPipeline, TomlStageTuning};
+use storage::TomlStorage;
+
+// TOML-compatible config structure (snake_case keys for TOML convention)
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub(super) struct TomlConfig {
+    #[serde(default)]
+    comfyui: TomlComfyUi,
+    #[serde(default)]
+    ollama: TomlOllama,
+    #[serde(default)]
+    models: TomlModels,
+    #[serde(default)]
+    pipeline: TomlPipeline,
+    #[serde(default)]
+    hardware: TomlHardware,
+    #[serde(default)]
+    presets: std::collections::HashMap<String, TomlPreset>,
+    #[serde(default)]
+    storage: TomlStorage,
+    #[serde(default)]
+    gallery: TomlGallery,
+    #[serde(default)]
+    generation: TomlGeneration,
+    #[serde(default)]
+    stage_tuning: TomlStageTuning,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct TomlComfyUi {
+    #[serde(default = "default_comfyui_endpoint")]
+    endpoint: String,
+    #[serde(default = "default_comfyui_max_retries")]
+    max_retries: u32,
+}
+
+impl Default for TomlComfyUi {
+    fn default() -> Self {
+        Self {
+            endpoint: default_comfyui_endpoint(),
+            max_retries: default_comfyui_max_retries(),
+        }
+    }
+}
+
+fn default_comfyui_max_retries() -> u32 {
+    3
+}
+
+fn default_comfyui_endpoint() -> String {
+    "http://localhost:8188".to_string()
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct TomlOllama {
+    #[serde(default = "default_ollama_endpoint")]
+    endpoint: String,
+    #[serde(default)]
+    backend: LlmBackend,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    api_key: Option<String>,
+}
+
+impl Default for TomlOllama {
+    fn default() -> Self {
+        Self {
+            endpoint: default_ollama_endpoint(),
+            backend: LlmBackend::default(),
+            api_key: None,
+        }
+    }
+}
+
+fn default_ollama_endpoint() -> String {
+    "http://localhost:11434".to_string()
+}
+
+pub(super) fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct TomlPreset {
+    steps: u32,
+    cfg: f64,
+    width: u32,
+    height: u32,
+    sampler: String,
+    scheduler: String,
+}
+
+impl TomlConfig {
+    pub(super) fn into_app_config(self) -> AppConfig {
+        use crate::types::config::*;
+
+        let mut presets = std::collections::HashMap::new();
+        for (name, p) in self.presets {
+            presets.insert(
+                name,
+                QualityPreset {
+                    steps: p.steps,
+                    cfg: p.cfg,
+                    width: p.width,
+                    height: p.height,
+                    sampler: p.sampler,
+                    scheduler: p.scheduler,
+                },
+            );
+        }
+
+        // Ensure default presets exist
+        let defaults = AppConfig::default();
+        for (name, preset) in defaults.presets {
+            presets.entry(name).or_insert(preset);
+        }
+
+        AppConfig {
+            comfyui: ComfyUiConfig {
+                endpoint: self.comfyui.endpoint,
+                max_retries: self.comfyui.max_retries,
+            },
+            ollama: OllamaConfig {
+                endpoint: self.ollama.endpoint,
+                backend: self.ollama.backend,
+                api_key: self.ollama.api_key,
+            },
+            models: ModelAssignments {
+                ideator: self.models.ideator,
+                composer: self.models.composer,
+                judge: self.models.judge,
+                prompt_engineer: self.models.prompt_engineer,
+                reviewer: self.models.reviewer,
+                tagger: self.models.tagger,
+                captioner: self.models.captioner,
+                embedder: self.models.embedder,
+                thinking_overrides: self.models.thinking_overrides,
+                custom_thinking_models: self.models.custom_thinking_models,
+                fallback_model: self.models.fallback_model,
+            },
+            pipeline: PipelineSettings {
+                enable_ideator: self.pipeline.enable_ideator,
+                enable_composer: self.pipeline.enable_composer,
+                enable_judge: self.pipeline.enable_judge,
+                enable_prompt_engineer: self.pipeline.enable_prompt_engineer,
+                enable_reviewer: self.pipeline.enable_reviewer,
+                auto_approve: self.pipeline.auto_approve,
+                capture_raw: self.pipeline.capture_raw,
+                reviewer_fail_mode: self.pipeline.reviewer_fail_mode,
+                max_retries: self.pipeline.max_retries,
+                max_review_iterations: self.pipeline.max_review_iterations,
+                auto_tag_on_complete: self.pipeline.auto_tag_on_complete,
+            },
+            hardware: HardwareSettings {
+                cooldown_seconds: self.hardware.cooldown_seconds,
+                cooldown_jitter_secs: self.hardware.cooldown_jitter_secs,
+                adaptive_cooldown: self.hardware.adaptive_cooldown,
+                max_consecutive_generations: self.hardware.max_consecutive_generations,
+                enable_ha_power_monitoring: self.hardware.enable_ha_power_monitoring,
+                ha_entity_id: self.hardware.ha_entity_id,
+                ha_max_watts: self.hardware.ha_max_watts,
+                ha_url: self.hardware.ha_url,
+                ha_token: self.hardware.ha_token,
+                ai_batch_downscale: self.hardware.ai_batch_downscale,
+                ai_batch_max_dimension: self.hardware.ai_batch_max_dimension,
+                thumbnail_concurrency: self.hardware.thumbnail_concurrency,
+                exclusive_gpu: self.hardware.exclusive_gpu,
+            },
+            storage: crate::types::config::StorageSettings {
+                image_directory: self.storage.image_directory,
+                embed_metadata: self.storage.embed_metadata,
+                backup_interval_hours: self.storage.backup_interval_hours,
+                backup_keep: self.storage.backup_keep,
+                thumbnail_size: self.storage.thumbnail_size,
+                thumbnail_format: self.storage.thumbnail_format,
+            },
+            gallery: GallerySettings {
+                auto_favorite_rating: self.gallery.auto_favorite_rating,
+                auto_rate_from_fidelity: self.gallery.auto_rate_from_fidelity,
+                safe_mode: self.gallery.safe_mode,
+                last_seen_at: self.gallery.last_seen_at,
+                default_page_size: self.gallery.default_page_size,
+                default_sort: self.gallery.default_sort,
+                trash_retention_days: self.gallery.trash_retention_days,
+            },
+            generation: crate::types::config::GenerationLimits {
+                max_dimension: self.generation.max_dimension,
+                max_job_retries: self.generation.max_job_retries,
+            },
+            stage_tuning: self.stage_tuning.into_tuning(),
+            presets,
+        }
+    }
+
+    pub(super) fn from_app_config(config: &AppConfig) -> Self {
+        let mut presets = std::collections::HashMap::new();
+        for (name, p) in &config.presets {
+            presets.insert(
+                name.clone(),
+                TomlPreset {
+                    steps: p.steps,
+                    cfg: p.cfg,
+                    width: p.width,
+                    height: p.height,
+                    sampler: p.sampler.clone(),
+                    scheduler: p.scheduler.clone(),
+                },
+            );
+        }
+
+        TomlConfig {
+            comfyui: TomlComfyUi {
+                endpoint: config.comfyui.endpoint.clone(),
+                max_retries: config.comfyui.max_retries,
+            },
+            ollama: TomlOllama {
+                endpoint: config.ollama.endpoint.clone(),
+                backend: config.ollama.backend,
+                api_key: config.ollama.api_key.clone(),
+            },
+            models: TomlModels {
+                ideator: config.models.ideator.clone(),
+                composer: config.models.composer.clone(),
+                judge: config.models.judge.clone(),
+                prompt_engineer: config.models.prompt_engineer.clone(),
+                reviewer: config.models.reviewer.clone(),
+                tagger: config.models.tagger.clone(),
+                captioner: config.models.captioner.clone(),
+                embedder: config.models.embedder.clone(),
+                thinking_overrides: config.models.thinking_overrides.clone(),
+                custom_thinking_models: config.models.custom_thinking_models.clone(),
+                fallback_model: config.models.fallback_model.clone(),
+            },
+            pipeline: TomlPipeline {
+                enable_ideator: config.pipeline.enable_ideator,
+                enable_composer: config.pipeline.enable_composer,
+                enable_judge: config.pipeline.enable_judge,
+                enable_prompt_engineer: config.pipeline.enable_prompt_engineer,
+                enable_reviewer: config.pipeline.enable_reviewer,
+                auto_approve: config.pipeline.auto_approve,
+                capture_raw: config.pipeline.capture_raw,
+                reviewer_fail_mode: config.pipeline.reviewer_fail_mode,
+                max_retries: config.pipeline.max_retries,
+                max_review_iterations: config.pipeline.max_review_iterations,
+                auto_tag_on_complete: config.pipeline.auto_tag_on_complete,
+            },
+            hardware: TomlHardware {
+                cooldown_seconds: config.hardware.cooldown_seconds,
+                cooldown_jitter_secs: config.hardware.cooldown_jitter_secs,
+                adaptive_cooldown: config.hardware.adaptive_cooldown,
+                max_consecutive_generations: config.hardware.max_consecutive_generations,
+                enable_ha_power_monitoring: config.hardware.enable_ha_power_monitoring,
+                ha_entity_id: config.hardware.ha_entity_id.clone(),
+                ha_max_watts: config.hardware.ha_max_watts,
+                ha_url: config.hardware.ha_url.clone(),
+                ha_token: config.hardware.ha_token.clone(),
+                ai_batch_downscale: config.hardware.ai_batch_downscale,
+                ai_batch_max_dimension: config.hardware.ai_batch_max_dimension,
+                thumbnail_concurrency: config.hardware.thumbnail_concurrency,
+                exclusive_gpu: config.hardware.exclusive_gpu,
+            },
+            storage: TomlStorage {
+                image_directory: config.storage.image_directory.clone(),
+                embed_metadata: config.storage.embed_metadata,
+                backup_interval_hours: config.storage.backup_interval_hours,
+                backup_keep: config.storage.backup_keep,
+                thumbnail_size: config.storage.thumbnail_size,
+                thumbnail_format: config.storage.thumbnail_format,
+            },
+            gallery: TomlGallery {
+                auto_favorite_rating: config.gallery.auto_favorite_rating,
+                auto_rate_from_fidelity: config.gallery.auto_rate_from_fidelity,
+                safe_mode: config.gallery.safe_mode,
+                last_seen_at: config.gallery.last_seen_at.clone(),
+                default_page_size: config.gallery.default_page_size,
+                default_sort: config.gallery.default_sort.clone(),
+                trash_retention_days: config.gallery.trash_retention_days,
+            },
+            generation: TomlGeneration {
+                max_dimension: config.generation.max_dimension,
+                max_job_retries: config.generation.max_job_retries,
+            },
+            stage_tuning: TomlStageTuning::from_tuning(&config.stage_tuning),
+            presets,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,68 @@
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub(super) struct TomlModels {
+    #[serde(default = "default_ideator")]
+    pub(super) ideator: String,
+    #[serde(default = "default_composer")]
+    pub(super) composer: String,
+    #[serde(default = "default_judge")]
+    pub(super) judge: String,
+    #[serde(default = "default_prompt_engineer")]
+    pub(super) prompt_engineer: String,
+    #[serde(default = "default_reviewer")]
+    pub(super) reviewer: String,
+    #[serde(default = "default_tagger")]
+    pub(super) tagger: String,
+    #[serde(default = "default_captioner")]
+    pub(super) captioner: String,
+    #[serde(default = "default_embedder")]
+    pub(super) embedder: String,
+    #[serde(default)]
+    pub(super) thinking_overrides: std::collections::HashMap<String, bool>,
+    #[serde(default)]
+    pub(super) custom_thinking_models: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(super) fallback_model: Option<String>,
+}
+
+impl Default for TomlModels {
+    fn default() -> Self {
+        Self {
+            ideator: default_ideator(),
+            composer: default_composer(),
+            judge: default_judge(),
+            prompt_engineer: default_prompt_engineer(),
+            reviewer: default_reviewer(),
+            tagger: default_tagger(),
+            captioner: default_captioner(),
+            embedder: default_embedder(),
+            thinking_overrides: std::collections::HashMap::new(),
+            custom_thinking_models: Vec::new(),
+            fallback_model: None,
+        }
+    }
+}
+
+fn default_ideator() -> String {
+    "mistral:7b".to_string()
+}
+fn default_composer() -> String {
+    "llama3.1:8b".to_string()
+}
+fn default_judge() -> String {
+    "qwen2.5:7b".to_string()
+}
+fn default_prompt_engineer() -> String {
+    "mistral:7b".to_string()
+}
+fn default_reviewer() -> String {
+    "qwen2.5:7b".to_string()
+}
+fn default_tagger() -> String {
+    "llava:7b".to_string()
+}
+fn default_captioner() -> String {
+    "llava:7b".to_string()
+}
+fn default_embedder() -> String {
+    "nomic-embed-text".to_string()
+}
@@ -0,0 +1,124 @@
+use super::default_true;
+use crate::types::config::{PipelineStageTuning, ReviewerFailMode, StageSampling};
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub(super) struct TomlPipeline {
+    #[serde(default = "default_true")]
+    pub(super) enable_ideator: bool,
+    #[serde(default = "default_true")]
+    pub(super) enable_composer: bool,
+    #[serde(default = "default_true")]
+    pub(super) enable_judge: bool,
+    #[serde(default = "default_true")]
+    pub(super) enable_prompt_engineer: bool,
+    #[serde(default)]
+    pub(super) enable_reviewer: bool,
+    #[serde(default)]
+    pub(super) auto_approve: bool,
+    #[serde(default)]
+    pub(super) capture_raw: bool,
+    #[serde(default)]
+    pub(super) reviewer_fail_mode: ReviewerFailMode,
+    #[serde(default = "default_max_retries")]
+    pub(super) max_retries: u32,
+    #[serde(default = "default_max_review_iterations")]
+    pub(super) max_review_iterations: u32,
+    #[serde(default)]
+    pub(super) auto_tag_on_complete: bool,
+}
+
+impl Default for TomlPipeline {
+    fn default() -> Self {
+        Self {
+            enable_ideator: true,
+            enable_composer: true,
+            enable_judge: true,
+            enable_prompt_engineer: true,
+            enable_reviewer: false,
+            auto_approve: false,
+            capture_raw: false,
+            reviewer_fail_mode: ReviewerFailMode::default(),
+            max_retries: default_max_retries(),
+            max_review_iterations: default_max_review_iterations(),
+            auto_tag_on_complete: false,
+        }
+    }
+}
+
+fn default_max_retries() -> u32 {
+    crate::types::config::DEFAULT_MAX_RETRIES
+}
+
+fn default_max_review_iterations() -> u32 {
+    1
+}
+
+/// A stage table that is present replaces that stage's defaults entirely,
+/// so a key left out means the model's own default.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub(super) struct TomlStageTuning {
+    pub(super) ideator: TomlStageSampling,
+    pub(super) composer: TomlStageSampling,
+    pub(super) judge: TomlStageSampling,
+    pub(super) prompt_engineer: TomlStageSampling,
+    pub(super) reviewer: TomlStageSampling,
+}
+
+impl Default for TomlStageTuning {
+    fn default() -> Self {
+        Self::from_tuning(&PipelineStageTuning::default())
+    }
+}
+
+impl TomlStageTuning {
+    pub(super) fn from_tuning(tuning: &PipelineStageTuning) -> Self {
+        Self {
+            ideator: tuning.ideator.into(),
+            composer: tuning.composer.into(),
+            judge: tuning.judge.into(),
+            prompt_engineer: tuning.prompt_engineer.into(),
+            reviewer: tuning.reviewer.into(),
+        }
+    }
+
+    pub(super) fn into_tuning(self) -> PipelineStageTuning {
+        PipelineStageTuning {
+            ideator: self.ideator.into(),
+            composer: self.composer.into(),
+            judge: self.judge.into(),
+            prompt_engineer: self.prompt_engineer.into(),
+            reviewer: self.reviewer.into(),
+        }
+    }
+}
+
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+pub(super) struct TomlStageSampling {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(super) temperature: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(super) top_p: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(super) top_k: Option<u32>,
+}
+
+impl From<StageSampling> for TomlStageSampling {
+    fn from(s: StageSampling) -> Self {
+        Self {
+            temperature: s.temperature,
+            top_p: s.top_p,
+            top_k: s.top_k,
+        }
+    }
+}
+
+impl From<TomlStageSampling> for StageSampling {
+    fn from(s: TomlStageSampling) -> Self {
+        Self {
+            temperature: s.temperature,
+            top_p: s.top_p,
+            top_k: s.top_k,
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use super::default_true;
+use crate::types::config::ThumbnailFormat;
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub(super) struct TomlStorage {
+    #[serde(default)]
+    pub(super) image_directory: String,
+    #[serde(default = "default_true")]
+    pub(super) embed_metadata: bool,
+    #[serde(default = "default_backup_interval_hours")]
+    pub(super) backup_interval_hours: u32,
+    #[serde(default = "default_backup_keep")]
+    pub(super) backup_keep: u32,
+    #[serde(default = "default_thumbnail_size")]
+    pub(super) thumbnail_size: u32,
+    #[serde(default)]
+    pub(super) thumbnail_format: ThumbnailFormat,
+}
+
+impl Default for TomlStorage {
+    fn default() -> Self {
+        Self {
+            image_directory: String::new(),
+            embed_metadata: true,
+            backup_interval_hours: default_backup_interval_hours(),
+            backup_keep: default_backup_keep(),
+            thumbnail_size: default_thumbnail_size(),
+            thumbnail_format: ThumbnailFormat::default(),
+        }
+    }
+}
+
+fn default_thumbnail_size() -> u32 {
+    256
+}
+
+fn default_backup_interval_hours() -> u32 {
+    24
+}
+
+fn default_backup_keep() -> u32 {
+    7
+}
@@ -0,0 +1,318 @@
+use super::*;
+use crate::types::config::{PipelineStageTuning, ReviewerFailMode, ThumbnailFormat};
+use crate::types::gallery::GallerySortField;
+
+#[test]
+fn test_default_config_serializes() {
+    let config = AppConfig::default();
+    let toml_config = TomlConfig::from_app_config(&config);
+    let serialized = toml::to_string_pretty(&toml_config).unwrap();
+    assert!(serialized.contains("[comfyui]"));
+    assert!(serialized.contains("[ollama]"));
+    assert!(serialized.contains("[models]"));
+    assert!(serialized.contains("[pipeline]"));
+    assert!(serialized.contains("[hardware]"));
+}
+
+#[test]
+fn test_config_roundtrip() {
+    let config = AppConfig::default();
+    let toml_config = TomlConfig::from_app_config(&config);
+    let serialized = toml::to_string_pretty(&toml_config).unwrap();
+    let deserialized: TomlConfig = toml::from_str(&serialized).unwrap();
+    let roundtripped = deserialized.into_app_config();
+
+    assert_eq!(roundtripped.comfyui.endpoint, config.comfyui.endpoint);
+    assert_eq!(roundtripped.ollama.endpoint, config.ollama.endpoint);
+    assert_eq!(roundtripped.models.ideator, config.models.ideator);
+    assert_eq!(
+        roundtripped.pipeline.enable_ideator,
+        config.pipeline.enable_ideator
+    );
+    assert_eq!(
+        roundtripped.hardware.cooldown_seconds,
+        config.hardware.cooldown_seconds
+    );
+    assert_eq!(roundtripped.presets.len(), config.presets.len());
+}
+
+#[test]
+fn test_gallery_auto_favorite_roundtrip() {
+    let mut config = AppConfig::default();
+    assert_eq!(config.gallery.auto_favorite_rating, 0);
+    config.gallery.auto_favorite_rating = 5;
+
+    let serialized = toml::to_string_pretty(&TomlConfig::from_app_config(&config)).unwrap();
+    assert!(serialized.contains("[gallery]"));
+    let roundtripped = toml::from_str::<TomlConfig>(&serialized)
+        .unwrap()
+        .into_app_config();
+    assert_eq!(roundtripped.gallery.auto_favorite_rating, 5);
+    assert!(!roundtripped.gallery.safe_mode);
+}
+
+#[test]
+fn test_gallery_safe_mode_roundtrip() {
+    let mut config = AppConfig::default();
+    config.gallery.safe_mode = true;
+
+    let serialized = toml::to_string_pretty(&TomlConfig::from_app_config(&config)).unwrap();
+    assert!(serialized.contains("safe_mode = true"));
+    let roundtripped = toml::from_str::<TomlConfig>(&serialized)
+        .unwrap()
+        .into_app_config();
+    assert!(roundtripped.gallery.safe_mode);
+}
+
+#[test]
+fn test_thumbnail_settings_roundtrip() {
+    let mut config = AppConfig::default();
+    assert_eq!(config.storage.thumbnail_size, 256);
+    assert_eq!(config.storage.thumbnail_format, ThumbnailFormat::Jpeg);
+    config.storage.thumbnail_size = 512;
+    config.storage.thumbnail_format = ThumbnailFormat::WebP;
+
+    let serialized = toml::to_string_pretty(&TomlConfig::from_app_config(&config)).unwrap();
+    assert!(serialized.contains("thumbnail_format = \"webp\""));
+    let roundtripped = toml::from_str::<TomlConfig>(&serialized)
+        .unwrap()
+        .into_app_config();
+    assert_eq!(roundtripped.storage.thumbnail_size, 512);
+    assert_eq!(roundtripped.storage.thumbnail_format, ThumbnailFormat::WebP);
+}
+
+#[test]
+fn test_trash_retention_roundtrip() {
+    let mut config = AppConfig::default();
+    assert_eq!(config.gallery.trash_retention_days, 0);
+    config.gallery.trash_retention_days = 30;
+
+    let serialized = toml::to_string_pretty(&TomlConfig::from_app_config(&config)).unwrap();
+    assert!(serialized.contains("trash_retention_days = 30"));
+    let roundtripped = toml::from_str::<TomlConfig>(&serialized)
+        .unwrap()
+        .into_app_config();
+    assert_eq!(roundtripped.gallery.trash_retention_days, 30);
+}
+
+#[test]
+fn test_gallery_last_seen_roundtrip() {
+    let mut config = AppConfig::default();
+    let serialized = toml::to_string_pretty(&TomlConfig::from_app_config(&config)).unwrap();
+    assert!(!serialized.contains("last_seen_at"));
+
+    config.gallery.last_seen_at = Some("2026-03-01T09:30:00+00:00".to_string());
+    let serialized = toml::to_string_pretty(&TomlConfig::from_app_config(&config)).unwrap();
+    let roundtripped = toml::from_str::<TomlConfig>(&serialized)
+        .unwrap()
+        .into_app_config();
+    assert_eq!(
+        roundtripped.gallery.last_seen_at.as_deref(),
+        Some("2026-03-01T09:30:00+00:00")
+    );
+}
+
+#[test]
+fn test_gallery_defaults_roundtrip() {
+    let mut config = AppConfig::default();
+    assert_eq!(config.gallery.default_page_size, 50);
+    assert!(config.gallery.default_sort.is_none());
+    config.gallery.default_page_size = 100;
+    config.gallery.default_sort = Some(GallerySortField::Rating);
+
+    let serialized = toml::to_string_pretty(&TomlConfig::from_app_config(&config)).unwrap();
+    assert!(serialized.contains("default_page_size = 100"));
+    assert!(serialized.contains("default_sort = \"rating\""));
+    let roundtripped = toml::from_str::<TomlConfig>(&serialized)
+        .unwrap()
+        .into_app_config();
+    assert_eq!(roundtripped.gallery.default_page_size, 100);
+    assert!(matches!(
+        roundtripped.gallery.default_sort,
+        Some(GallerySortField::Rating)
+    ));
+}
+
+#[test]
+fn test_reviewer_fail_mode_roundtrip() {
+    let mut config = AppConfig::default();
+    assert_eq!(
+        config.pipeline.reviewer_fail_mode,
+        ReviewerFailMode::ApproveOnError
+    );
+    config.pipeline.reviewer_fail_mode = ReviewerFailMode::RetryOnError;
+
+    let serialized = toml::to_string_pretty(&TomlConfig::from_app_config(&config)).unwrap();
+    assert!(serialized.contains(r#"reviewer_fail_mode = "retryOnError""#));
+    let roundtripped = toml::from_str::<TomlConfig>(&serialized)
+        .unwrap()
+        .into_app_config();
+    assert_eq!(
+        roundtripped.pipeline.reviewer_fail_mode,
+        ReviewerFailMode::RetryOnError
+    );
+}
+
+#[test]
+fn test_llm_backend_roundtrip() {
+    let mut config = AppConfig::default();
+    assert_eq!(config.ollama.backend, LlmBackend::Ollama);
+    config.ollama.endpoint = "http://localhost:1234".to_string();
+    config.ollama.backend = LlmBackend::OpenAiCompatible;
+    config.ollama.api_key = Some("sk-local".to_string());
+
+    let serialized = toml::to_string_pretty(&TomlConfig::from_app_config(&config)).unwrap();
+    assert!(serialized.contains(r#"backend = "openAiCompatible""#));
+    let roundtripped = toml::from_str::<TomlConfig>(&serialized)
+        .unwrap()
+        .into_app_config();
+    assert_eq!(roundtripped.ollama.backend, LlmBackend::OpenAiCompatible);
+    assert_eq!(roundtripped.ollama.api_key.as_deref(), Some("sk-local"));
+}
+
+#[test]
+fn test_adaptive_cooldown_roundtrip() {
+    let mut config = AppConfig::default();
+    assert!(!config.hardware.adaptive_cooldown);
+    config.hardware.adaptive_cooldown = true;
+
+    let serialized = toml::to_string_pretty(&TomlConfig::from_app_config(&config)).unwrap();
+    let roundtripped = toml::from_str::<TomlConfig>(&serialized)
+        .unwrap()
+        .into_app_config();
+    assert!(roundtripped.hardware.adaptive_cooldown);
+}
+
+#[test]
+fn test_ha_connection_roundtrip() {
+    let mut config = AppConfig::default();
+    assert_eq!(config.hardware.ha_url, "http://homeassistant.local:8123");
+    assert_eq!(config.hardware.ha_token, None);
+    config.hardware.ha_url = "http://10.0.0.5:8123".to_string();
+    config.hardware.ha_token = Some("ha-token".to_string());
+
+    let serialized = toml::to_string_pretty(&TomlConfig::from_app_config(&config)).unwrap();
+    let roundtripped = toml::from_str::<TomlConfig>(&serialized)
+        .unwrap()
+        .into_app_config();
+    assert_eq!(roundtripped.hardware.ha_url, "http://10.0.0.5:8123");
+    assert_eq!(roundtripped.hardware.ha_token.as_deref(), Some("ha-token"));
+}
+
+#[test]
+fn test_pipeline_max_retries_roundtrip() {
+    let mut config = AppConfig::default();
+    assert_eq!(config.pipeline.max_retries, 2);
+    config.pipeline.max_retries = 0;
+
+    let serialized = toml::to_string_pretty(&TomlConfig::from_app_config(&config)).unwrap();
+    assert!(serialized.contains("max_retries = 0"));
+    let roundtripped = toml::from_str::<TomlConfig>(&serialized)
+        .unwrap()
+        .into_app_config();
+    assert_eq!(roundtripped.pipeline.max_retries, 0);
+}
+
+#[test]
+fn test_pipeline_max_review_iterations_roundtrip() {
+    let mut config = AppConfig::default();
+    assert_eq!(config.pipeline.max_review_iterations, 1);
+    config.pipeline.max_review_iterations = 3;
+
+    let serialized = toml::to_string_pretty(&TomlConfig::from_app_config(&config)).unwrap();
+    assert!(serialized.contains("max_review_iterations = 3"));
+    let roundtripped = toml::from_str::<TomlConfig>(&serialized)
+        .unwrap()
+        .into_app_config();
+    assert_eq!(roundtripped.pipeline.max_review_iterations, 3);
+}
+
+#[test]
+fn test_pipeline_auto_tag_roundtrip() {
+    let mut config = AppConfig::default();
+    assert!(!config.pipeline.auto_tag_on_complete);
+    config.pipeline.auto_tag_on_complete = true;
+
+    let serialized = toml::to_string_pretty(&TomlConfig::from_app_config(&config)).unwrap();
+    assert!(serialized.contains("auto_tag_on_complete = true"));
+    let roundtripped = toml::from_str::<TomlConfig>(&serialized)
+        .unwrap()
+        .into_app_config();
+    assert!(roundtripped.pipeline.auto_tag_on_complete);
+}
+
+#[test]
+fn test_comfyui_max_retries_roundtrip() {
+    let mut config = AppConfig::default();
+    assert_eq!(config.comfyui.max_retries, 3);
+    config.comfyui.max_retries = 0;
+
+    let serialized = toml::to_string_pretty(&TomlConfig::from_app_config(&config)).unwrap();
+    let roundtripped = toml::from_str::<TomlConfig>(&serialized)
+        .unwrap()
+        .into_app_config();
+    assert_eq!(roundtripped.comfyui.max_retries, 0);
+}
+
+#[test]
+fn test_generation_max_dimension_roundtrip() {
+    let mut config = AppConfig::default();
+    assert_eq!(config.generation.max_dimension, 4096);
+    config.generation.max_dimension = 2048;
+
+    let serialized = toml::to_string_pretty(&TomlConfig::from_app_config(&config)).unwrap();
+    assert!(serialized.contains("[generation]"));
+    let roundtripped = toml::from_str::<TomlConfig>(&serialized)
+        .unwrap()
+        .into_app_config();
+    assert_eq!(roundtripped.generation.max_dimension, 2048);
+}
+
+#[test]
+fn test_generation_max_job_retries_roundtrip() {
+    let mut config = AppConfig::default();
+    assert_eq!(config.generation.max_job_retries, 1);
+    config.generation.max_job_retries = 3;
+
+    let serialized = toml::to_string_pretty(&TomlConfig::from_app_config(&config)).unwrap();
+    let roundtripped = toml::from_str::<TomlConfig>(&serialized)
+        .unwrap()
+        .into_app_config();
+    assert_eq!(roundtripped.generation.max_job_retries, 3);
+}
+
+#[test]
+fn test_stage_tuning_roundtrip_and_defaults() {
+    let mut config = AppConfig::default();
+    config.stage_tuning.ideator.top_p = Some(0.95);
+    config.stage_tuning.judge.top_k = Some(20);
+
+    let serialized = toml::to_string_pretty(&TomlConfig::from_app_config(&config)).unwrap();
+    assert!(serialized.contains("[stage_tuning.ideator]"));
+    let roundtripped = toml::from_str::<TomlConfig>(&serialized)
+        .unwrap()
+        .into_app_config();
+    assert_eq!(roundtripped.stage_tuning, config.stage_tuning);
+
+    // An older config without the section gets the stage defaults
+    let defaults = toml::from_str::<TomlConfig>("[comfyui]\n")
+        .unwrap()
+        .into_app_config();
+    assert_eq!(defaults.stage_tuning, PipelineStageTuning::default());
+    assert_eq!(defaults.stage_tuning.ideator.temperature, Some(0.9));
+}
+
+#[test]
+fn test_partial_toml_uses_defaults() {
+    let partial = r#"
+[comfyui]
+endpoint = "http://myhost:8188"
+"#;
+    let toml_config: TomlConfig = toml::from_str(partial).unwrap();
+    let config = toml_config.into_app_config();
+
+    assert_eq!(config.comfyui.endpoint, "http://myhost:8188");
+    assert_eq!(config.ollama.endpoint, "http://localhost:11434");
+    assert_eq!(config.models.ideator, "mistral:7b");
+    assert!(config.pipeline.enable_ideator);
+}
@@ -0,0 +1,125 @@
+use anyhow::{Context, Result};
+use rusqlite::{Connection, DatabaseName};
+use std::path::{Path, PathBuf};
+
+use crate::config::manager;
+
+const BACKUP_PREFIX: &str = "gallery-";
+const BACKUP_EXTENSION: &str = "db";
+
+pub fn backups_dir() -> PathBuf {
+    manager::data_dir().join("backups")
+}
+
+/// Copy the live database at `db_path` into `dir` using SQLite's online
+/// backup API, then delete all but the `keep` newest backups. Returns the
+/// new backup's path.
+pub fn backup_database(db_path: &Path, dir: &Path, keep: usize) -> Result<PathBuf> {
+    // A separate connection, so the app's shared one is never blocked
+    let conn = Connection::open(db_path)
+        .with_context(|| format!("Failed to open {} for backup", db_path.display()))?;
+    backup_to(&conn, dir, keep)
+}
+
+/// Back up `conn` into a timestamped file in `dir` and prune old backups.
+pub fn backup_to(conn: &Connection, dir: &Path, keep: usize) -> Result<PathBuf> {
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create backup dir {}", dir.display()))?;
+
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S%3f");
+    let path = dir.join(format!("{}{}.{}", BACKUP_PREFIX, stamp, BACKUP_EXTENSION));
+    conn.backup(DatabaseName::Main, &path, None)
+        .with_context(|| format!("Failed to back up database to {}", path.display()))?;
+
+    prune_backups(dir, keep)?;
+    Ok(path)
+}
+
+/// Backups in `dir`, oldest first. The timestamped names sort chronologically.
+pub fn list_backups(dir: &Path) -> Result<Vec<PathBuf>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to read backup dir {}", dir.display()))
+        }
+    };
+
+    let mut backups: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| is_backup_file(path))
+        .collect();
+    backups.sort();
+    Ok(backups)
+}
+
+/// Delete all but the `keep` newest backups. Returns the deleted paths.
+pub fn prune_backups(dir: &Path, keep: usize) -> Result<Vec<PathBuf>> {
+    let backups = list_backups(dir)?;
+    let excess = backups.len().saturating_sub(keep);
+    let mut removed = Vec::new();
+    for path in backups.into_iter().take(excess) {
+        std::fs::remove_file(&path)
+            .with_context(|| format!("Failed to delete old backup {}", path.display()))?;
+        removed.push(path);
+    }
+    Ok(removed)
+}
+
+fn is_backup_file(path: &Path) -> bool {
+    let name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default();
+    name.starts_with(BACKUP_PREFIX)
+        && path.extension().and_then(|e| e.to_str()) == Some(BACKUP_EXTENSION)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    #[test]
+    fn test_backup_is_a_valid_database() {
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = tmp.path().join("gallery.db");
+        let conn = db::open_database(&db_path).unwrap();
+        db::images::insert_image(&conn, &db::images::tests::make_test_image("img-1")).unwrap();
+
+        let backup = backup_database(&db_path, &tmp.path().join("backups"), 3).unwrap();
+
+        let restored = Connection::open(&backup).unwrap();
+        let integrity: String = restored
+            .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(integrity, "ok");
+        assert!(db::images::get_image(&restored, "img-1").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_prune_keeps_newest_backups() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        for stamp in [
+            "20260101-000000000",
+            "20260102-000000000",
+            "20260103-000000000",
+        ] {
+            std::fs::write(dir.join(format!("gallery-{}.db", stamp)), b"").unwrap();
+        }
+        std::fs::write(dir.join("notes.txt"), b"not a backup").unwrap();
+
+        let removed = prune_backups(dir, 2).unwrap();
+
+        assert_eq!(removed, vec![dir.join("gallery-20260101-000000000.db")]);
+        assert_eq!(
+            list_backups(dir).unwrap(),
+            vec![
+                dir.join("gallery-20260102-000000000.db"),
+                dir.join("gallery-20260103-000000000.db"),
+            ]
+        );
+        assert!(dir.join("notes.txt").exists());
+    }
+}
@@ -0,0 +1,80 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Manager};
+
+use super::backup;
+use crate::state::AppState;
+
+/// How often the task wakes to check whether a backup is due.
+const CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// True when no backup in `dir` is younger than `interval`.
+fn backup_due(dir: &Path, interval: Duration) -> Result<bool> {
+    let newest = backup::list_backups(dir)?.pop();
+    let Some(newest) = newest else {
+        return Ok(true);
+    };
+    let modified = std::fs::metadata(&newest)
+        .and_then(|m| m.modified())
+        .with_context(|| format!("Failed to stat {}", newest.display()))?;
+    let age = SystemTime::now()
+        .duration_since(modified)
+        .unwrap_or_default();
+    Ok(age >= interval)
+}
+
+/// Spawn the periodic backup task. `storage.backupIntervalHours` is re-read
+/// on every check, so changing it (or setting 0 to disable) needs no restart.
+pub fn spawn(app_handle: AppHandle, db_path: PathBuf) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            if let Err(e) = run_once(&app_handle, &db_path).await {
+                eprintln!("[backup] {:#}", e);
+            }
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
+
+async fn run_once(app_handle: &AppHandle, db_path: &Path) -> Result<()> {
+    let Some(state) = app_handle.try_state::<AppState>() else {
+        return Ok(());
+    };
+    let storage = state.config_snapshot()?.storage;
+    if storage.backup_interval_hours == 0 {
+        return Ok(());
+    }
+
+    let dir = backup::backups_dir();
+    let interval = Duration::from_secs(storage.backup_interval_hours as u64 * 3600);
+    if !backup_due(&dir, interval)? {
+        return Ok(());
+    }
+
+    let db_path = db_path.to_path_buf();
+    let keep = storage.backup_keep.max(1) as usize;
+    let path = tokio::task::spawn_blocking(move || backup::backup_database(&db_path, &dir, keep))
+        .await
+        .context("Backup task panicked")??;
+    eprintln!("[backup] Saved database backup to {}", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    #[test]
+    fn test_backup_due_follows_newest_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        assert!(backup_due(dir, Duration::from_secs(3600)).unwrap());
+
+        let conn = db::open_memory_database().unwrap();
+        backup::backup_to(&conn, dir, 5).unwrap();
+        assert!(!backup_due(dir, Duration::from_secs(3600)).unwrap());
+        assert!(backup_due(dir, Duration::ZERO).unwrap());
+    }
+}
@@ -0,0 +1,143 @@
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::types::gallery::ImageCaption;
+
+/// Store an alternate caption for an image. Returns the new caption's ID.
+pub fn add_image_caption(
+    conn: &Connection,
+    image_id: &str,
+    caption: &str,
+    source: &str,
+) -> Result<i64> {
+    let caption = caption.trim();
+    if caption.is_empty() {
+        anyhow::bail!("Caption cannot be empty");
+    }
+    conn.execute(
+        "INSERT INTO image_captions (image_id, caption, source) VALUES (?1, ?2, ?3)",
+        params![image_id, caption, source],
+    )
+    .context("Failed to add image caption")?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn delete_image_caption(conn: &Connection, caption_id: i64) -> Result<()> {
+    conn.execute(
+        "DELETE FROM image_captions WHERE id = ?1",
+        params![caption_id],
+    )
+    .context("Failed to delete image caption")?;
+    Ok(())
+}
+
+/// The primary caption (if set) followed by alternates, oldest first.
+pub fn list_image_captions(conn: &Connection, image_id: &str) -> Result<Vec<ImageCaption>> {
+    let primary: Option<(Option<String>, bool, Option<String>)> = conn
+        .query_row(
+            "SELECT caption, COALESCE(caption_edited, FALSE), created_at
+             FROM images WHERE id = ?1",
+            params![image_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()
+        .context("Failed to query primary caption")?;
+
+    let mut captions = Vec::new();
+    if let Some((Some(caption), edited, created_at)) = primary {
+        if !caption.is_empty() {
+            captions.push(ImageCaption {
+                id: None,
+                image_id: image_id.to_string(),
+                caption,
+                source: Some(if edited { "user" } else { "ai" }.to_string()),
+                created_at,
+                is_primary: true,
+            });
+        }
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, caption, source, created_at FROM image_captions
+             WHERE image_id = ?1 ORDER BY created_at, id",
+        )
+        .context("Failed to prepare list_image_captions query")?;
+
+    let rows = stmt
+        .query_map(params![image_id], |row| {
+            Ok(ImageCaption {
+                id: row.get(0)?,
+                image_id: image_id.to_string(),
+                caption: row.get(1)?,
+                source: row.get(2)?,
+                created_at: row.get(3)?,
+                is_primary: false,
+            })
+        })
+        .context("Failed to execute list_image_captions query")?;
+
+    for row in rows {
+        captions.push(row.context("Failed to read image caption row")?);
+    }
+    Ok(captions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::db::images::tests::make_test_image;
+
+    fn setup() -> Connection {
+        let conn = db::open_memory_database().unwrap();
+        db::images::insert_image(&conn, &make_test_image("img-001")).unwrap();
+        db::images::update_image_caption(&conn, "img-001", "A cat on a throne", false).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_alternates_listed_after_primary() {
+        let conn = setup();
+        add_image_caption(&conn, "img-001", "a cat, throne, regal", "user").unwrap();
+        add_image_caption(
+            &conn,
+            "img-001",
+            "A regal feline seated on a gilded throne",
+            "ai",
+        )
+        .unwrap();
+
+        let captions = list_image_captions(&conn, "img-001").unwrap();
+        assert_eq!(captions.len(), 3);
+        assert!(captions[0].is_primary);
+        assert_eq!(captions[0].id, None);
+        assert_eq!(captions[0].caption, "A cat on a throne");
+        assert_eq!(captions[1].caption, "a cat, throne, regal");
+        assert_eq!(captions[1].source.as_deref(), Some("user"));
+        assert_eq!(
+            captions[2].caption,
+            "A regal feline seated on a gilded throne"
+        );
+        assert!(!captions[2].is_primary);
+    }
+
+    #[test]
+    fn test_delete_alternate_keeps_primary() {
+        let conn = setup();
+        let id = add_image_caption(&conn, "img-001", "alt", "user").unwrap();
+        delete_image_caption(&conn, id).unwrap();
+
+        let captions = list_image_captions(&conn, "img-001").unwrap();
+        assert_eq!(captions.len(), 1);
+        assert!(captions[0].is_primary);
+    }
+
+    #[test]
+    fn test_rejects_empty_and_unknown_image() {
+        let conn = setup();
+        assert!(add_image_caption(&conn, "img-001", "   ", "user").is_err());
+        assert!(add_image_caption(&conn, "missing", "alt", "user").is_err());
+        assert!(add_image_caption(&conn, "img-001", "alt", "robot").is_err());
+    }
+}
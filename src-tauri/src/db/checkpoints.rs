@@ -4,6 +4,7 @@ use rusqlite::{params, Connection};
 use crate::types::checkpoints::{
     CheckpointObservation, CheckpointProfile, ObservationSource, PromptTerm, TermStrength,
 };
+use crate::types::generation::{GenerationRequest, GenerationSettings, PartialGenerationRequest};
 
 pub fn upsert_checkpoint(conn: &Connection, profile: &CheckpointProfile) -> Result<i64> {
     let strengths_json = profile
@@ -64,11 +65,13 @@ pub fn upsert_checkpoint(conn: &Connection, profile: &CheckpointProfile) -> Resu
 pub fn get_checkpoint(conn: &Connection, filename: &str) -> Result<Option<CheckpointProfile>> {
     let mut stmt = conn
         .prepare(
-            "SELECT id, filename, display_name, base_model, created_at,
-                    strengths, weaknesses, preferred_cfg, cfg_range_low,
-                    cfg_range_high, preferred_sampler, preferred_scheduler,
-                    optimal_resolution, notes
-             FROM checkpoints WHERE filename = ?1",
+            "SELECT c.id, c.filename, c.display_name, c.base_model, c.created_at,
+                    c.strengths, c.weaknesses, c.preferred_cfg, c.cfg_range_low,
+                    c.cfg_range_high, c.preferred_sampler, c.preferred_scheduler,
+                    c.optimal_resolution, c.notes, c.sample_image_id, i.filename
+             FROM checkpoints c
+             LEFT JOIN images i ON c.sample_image_id = i.id
+             WHERE c.filename = ?1",
         )
         .context("Failed to prepare get_checkpoint query")?;
 
@@ -82,14 +85,79 @@ pub fn get_checkpoint(conn: &Connection, filename: &str) -> Result<Option<Checkp
     }
 }
 
+/// A generation request pre-filled from the checkpoint's preferred cfg,
+/// sampler, scheduler and resolution. Anything the profile leaves unset gets
+/// the queue's defaults; prompts are left empty. `None` when there is no
+/// profile for `filename`.
+pub fn get_default_request(conn: &Connection, filename: &str) -> Result<Option<GenerationRequest>> {
+    let Some(profile) = get_checkpoint(conn, filename)? else {
+        return Ok(None);
+    };
+    let (width, height) = profile
+        .optimal_resolution
+        .as_deref()
+        .and_then(parse_resolution)
+        .unzip();
+    let mut settings = GenerationSettings::for_checkpoint(profile.filename);
+    settings.apply(&PartialGenerationRequest {
+        width,
+        height,
+        cfg_scale: profile.preferred_cfg,
+        sampler: profile.preferred_sampler,
+        scheduler: profile.preferred_scheduler,
+        ..Default::default()
+    });
+    Ok(Some(settings.into_request(String::new(), String::new())))
+}
+
+/// Parse an `optimal_resolution` such as "512x768" (also "512 x 768" or
+/// "512×768") into width and height. Zero or unparseable sizes give `None`.
+pub fn parse_resolution(value: &str) -> Option<(u32, u32)> {
+    let (w, h) = value.trim().split_once(['x', 'X', '×'])?;
+    let w: u32 = w.trim().parse().ok()?;
+    let h: u32 = h.trim().parse().ok()?;
+    (w > 0 && h > 0).then_some((w, h))
+}
+
+/// Link a gallery image as the checkpoint's representative sample.
+pub fn set_checkpoint_sample_image(
+    conn: &Connection,
+    filename: &str,
+    image_id: &str,
+) -> Result<()> {
+    let image_exists: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM images WHERE id = ?1)",
+            params![image_id],
+            |row| row.get(0),
+        )
+        .context("Failed to look up sample image")?;
+    if !image_exists {
+        anyhow::bail!("Image {} not found", image_id);
+    }
+
+    let updated = conn
+        .execute(
+            "UPDATE checkpoints SET sample_image_id = ?1 WHERE filename = ?2",
+            params![image_id, filename],
+        )
+        .context("Failed to set checkpoint sample image")?;
+    if updated == 0 {
+        anyhow::bail!("Checkpoint {} not found", filename);
+    }
+    Ok(())
+}
+
 pub fn list_checkpoints(conn: &Connection) -> Result<Vec<CheckpointProfile>> {
     let mut stmt = conn
         .prepare(
-            "SELECT id, filename, display_name, base_model, created_at,
-                    strengths, weaknesses, preferred_cfg, cfg_range_low,
-                    cfg_range_high, preferred_sampler, preferred_scheduler,
-                    optimal_resolution, notes
-             FROM checkpoints ORDER BY filename",
+            "SELECT c.id, c.filename, c.display_name, c.base_model, c.created_at,
+                    c.strengths, c.weaknesses, c.preferred_cfg, c.cfg_range_low,
+                    c.cfg_range_high, c.preferred_sampler, c.preferred_scheduler,
+                    c.optimal_resolution, c.notes, c.sample_image_id, i.filename
+             FROM checkpoints c
+             LEFT JOIN images i ON c.sample_image_id = i.id
+             ORDER BY c.filename",
         )
         .context("Failed to prepare list_checkpoints query")?;
 
@@ -165,6 +233,53 @@ pub fn add_observation(conn: &Connection, obs: &CheckpointObservation) -> Result
     Ok(conn.last_insert_rowid())
 }
 
+/// Turn image `image_id`'s note into a user observation on the checkpoint
+/// it was generated with, creating a bare profile for that checkpoint if
+/// there is none yet. The note itself is left on the image.
+pub fn promote_image_note(conn: &Connection, image_id: &str) -> Result<CheckpointObservation> {
+    let image = super::images::get_image(conn, image_id)?
+        .with_context(|| format!("Image {} not found", image_id))?;
+    let note = image
+        .user_note
+        .as_deref()
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+        .with_context(|| format!("Image {} has no note to promote", image_id))?;
+    let checkpoint = image
+        .checkpoint
+        .as_deref()
+        .filter(|c| !c.is_empty())
+        .with_context(|| format!("Image {} has no checkpoint", image_id))?;
+
+    let tx = conn
+        .unchecked_transaction()
+        .context("Failed to start note promotion")?;
+    tx.execute(
+        "INSERT INTO checkpoints (filename) VALUES (?1) ON CONFLICT(filename) DO NOTHING",
+        params![checkpoint],
+    )
+    .context("Failed to create checkpoint profile")?;
+    let checkpoint_id: i64 = tx
+        .query_row(
+            "SELECT id FROM checkpoints WHERE filename = ?1",
+            params![checkpoint],
+            |row| row.get(0),
+        )
+        .context("Failed to look up checkpoint")?;
+
+    let mut observation = CheckpointObservation {
+        id: None,
+        checkpoint_id,
+        observation: note.to_string(),
+        source: ObservationSource::User,
+        comparison_id: None,
+        created_at: None,
+    };
+    observation.id = Some(add_observation(&tx, &observation)?);
+    tx.commit().context("Failed to commit note promotion")?;
+    Ok(observation)
+}
+
 pub fn get_observations(
     conn: &Connection,
     checkpoint_id: i64,
@@ -259,150 +374,11 @@ fn row_to_profile(row: &rusqlite::Row) -> rusqlite::Result<CheckpointProfile> {
         preferred_scheduler: row.get(11)?,
         optimal_resolution: row.get(12)?,
         notes: row.get(13)?,
+        sample_image_id: row.get(14)?,
+        sample_filename: row.get(15)?,
     })
 }
 
 #[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::db;
-
-    fn setup() -> Connection {
-        db::open_memory_database().unwrap()
-    }
-
-    fn make_profile() -> CheckpointProfile {
-        CheckpointProfile {
-            id: None,
-            filename: "dreamshaper_8.safetensors".to_string(),
-            display_name: Some("DreamShaper v8".to_string()),
-            base_model: Some("SD 1.5".to_string()),
-            created_at: None,
-            strengths: Some(vec![
-                "photorealism".to_string(),
-                "cinematic lighting".to_string(),
-            ]),
-            weaknesses: Some(vec!["text rendering".to_string()]),
-            preferred_cfg: Some(7.5),
-            cfg_range_low: Some(6.0),
-            cfg_range_high: Some(9.0),
-            preferred_sampler: Some("dpmpp_2m".to_string()),
-            preferred_scheduler: Some("karras".to_string()),
-            optimal_resolution: Some("512x768".to_string()),
-            notes: Some("Good all-around checkpoint".to_string()),
-        }
-    }
-
-    #[test]
-    fn test_upsert_and_get() {
-        let conn = setup();
-        let id = upsert_checkpoint(&conn, &make_profile()).unwrap();
-        assert!(id > 0);
-
-        let profile = get_checkpoint(&conn, "dreamshaper_8.safetensors")
-            .unwrap()
-            .unwrap();
-        assert_eq!(profile.display_name.unwrap(), "DreamShaper v8");
-        assert_eq!(profile.strengths.unwrap().len(), 2);
-    }
-
-    #[test]
-    fn test_upsert_updates_existing() {
-        let conn = setup();
-        upsert_checkpoint(&conn, &make_profile()).unwrap();
-
-        let updated = CheckpointProfile {
-            notes: Some("Updated notes".to_string()),
-            ..make_profile()
-        };
-        upsert_checkpoint(&conn, &updated).unwrap();
-
-        let all = list_checkpoints(&conn).unwrap();
-        assert_eq!(all.len(), 1);
-        assert_eq!(all[0].notes.as_deref(), Some("Updated notes"));
-    }
-
-    #[test]
-    fn test_prompt_terms() {
-        let conn = setup();
-        let cp_id = upsert_checkpoint(&conn, &make_profile()).unwrap();
-
-        add_prompt_term(
-            &conn,
-            &PromptTerm {
-                id: None,
-                checkpoint_id: cp_id,
-                term: "cinematic lighting".to_string(),
-                effect: "Strong volumetric light".to_string(),
-                strength: TermStrength::Strong,
-                example_image_id: None,
-                created_at: None,
-            },
-        )
-        .unwrap();
-
-        let terms = get_prompt_terms(&conn, cp_id).unwrap();
-        assert_eq!(terms.len(), 1);
-        assert_eq!(terms[0].term, "cinematic lighting");
-    }
-
-    #[test]
-    fn test_observations() {
-        let conn = setup();
-        let cp_id = upsert_checkpoint(&conn, &make_profile()).unwrap();
-
-        add_observation(
-            &conn,
-            &CheckpointObservation {
-                id: None,
-                checkpoint_id: cp_id,
-                observation: "Great for portraits".to_string(),
-                source: ObservationSource::User,
-                comparison_id: None,
-                created_at: None,
-            },
-        )
-        .unwrap();
-
-        let obs = get_observations(&conn, cp_id).unwrap();
-        assert_eq!(obs.len(), 1);
-        assert_eq!(obs[0].observation, "Great for portraits");
-    }
-
-    #[test]
-    fn test_checkpoint_context_string() {
-        let conn = setup();
-        let cp_id = upsert_checkpoint(&conn, &make_profile()).unwrap();
-        add_prompt_term(
-            &conn,
-            &PromptTerm {
-                id: None,
-                checkpoint_id: cp_id,
-                term: "cinematic lighting".to_string(),
-                effect: "Produces volumetric rays".to_string(),
-                strength: TermStrength::Strong,
-                example_image_id: None,
-                created_at: None,
-            },
-        )
-        .unwrap();
-
-        let ctx = get_checkpoint_context(&conn, "dreamshaper_8.safetensors").unwrap();
-        assert!(ctx.contains("DreamShaper v8"));
-        assert!(ctx.contains("photorealism"));
-        assert!(ctx.contains("cinematic lighting"));
-    }
-
-    #[test]
-    fn test_get_nonexistent_checkpoint() {
-        let conn = setup();
-        assert!(get_checkpoint(&conn, "nope.safetensors").unwrap().is_none());
-    }
-
-    #[test]
-    fn test_empty_context_for_unknown_checkpoint() {
-        let conn = setup();
-        let ctx = get_checkpoint_context(&conn, "unknown.safetensors").unwrap();
-        assert!(ctx.is_empty());
-    }
-}
+#[path = "checkpoints_test.rs"]
+mod tests;
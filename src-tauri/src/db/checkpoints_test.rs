@@ -0,0 +1,286 @@
+use super::*;
+use crate::db;
+
+fn setup() -> Connection {
+    db::open_memory_database().unwrap()
+}
+
+fn make_profile() -> CheckpointProfile {
+    CheckpointProfile {
+        id: None,
+        filename: "dreamshaper_8.safetensors".to_string(),
+        display_name: Some("DreamShaper v8".to_string()),
+        base_model: Some("SD 1.5".to_string()),
+        created_at: None,
+        strengths: Some(vec![
+            "photorealism".to_string(),
+            "cinematic lighting".to_string(),
+        ]),
+        weaknesses: Some(vec!["text rendering".to_string()]),
+        preferred_cfg: Some(7.5),
+        cfg_range_low: Some(6.0),
+        cfg_range_high: Some(9.0),
+        preferred_sampler: Some("dpmpp_2m".to_string()),
+        preferred_scheduler: Some("karras".to_string()),
+        optimal_resolution: Some("512x768".to_string()),
+        notes: Some("Good all-around checkpoint".to_string()),
+        sample_image_id: None,
+        sample_filename: None,
+    }
+}
+
+#[test]
+fn test_upsert_and_get() {
+    let conn = setup();
+    let id = upsert_checkpoint(&conn, &make_profile()).unwrap();
+    assert!(id > 0);
+
+    let profile = get_checkpoint(&conn, "dreamshaper_8.safetensors")
+        .unwrap()
+        .unwrap();
+    assert_eq!(profile.display_name.unwrap(), "DreamShaper v8");
+    assert_eq!(profile.strengths.unwrap().len(), 2);
+}
+
+#[test]
+fn test_upsert_updates_existing() {
+    let conn = setup();
+    upsert_checkpoint(&conn, &make_profile()).unwrap();
+
+    let updated = CheckpointProfile {
+        notes: Some("Updated notes".to_string()),
+        ..make_profile()
+    };
+    upsert_checkpoint(&conn, &updated).unwrap();
+
+    let all = list_checkpoints(&conn).unwrap();
+    assert_eq!(all.len(), 1);
+    assert_eq!(all[0].notes.as_deref(), Some("Updated notes"));
+}
+
+#[test]
+fn test_prompt_terms() {
+    let conn = setup();
+    let cp_id = upsert_checkpoint(&conn, &make_profile()).unwrap();
+
+    add_prompt_term(
+        &conn,
+        &PromptTerm {
+            id: None,
+            checkpoint_id: cp_id,
+            term: "cinematic lighting".to_string(),
+            effect: "Strong volumetric light".to_string(),
+            strength: TermStrength::Strong,
+            example_image_id: None,
+            created_at: None,
+        },
+    )
+    .unwrap();
+
+    let terms = get_prompt_terms(&conn, cp_id).unwrap();
+    assert_eq!(terms.len(), 1);
+    assert_eq!(terms[0].term, "cinematic lighting");
+}
+
+#[test]
+fn test_observations() {
+    let conn = setup();
+    let cp_id = upsert_checkpoint(&conn, &make_profile()).unwrap();
+
+    add_observation(
+        &conn,
+        &CheckpointObservation {
+            id: None,
+            checkpoint_id: cp_id,
+            observation: "Great for portraits".to_string(),
+            source: ObservationSource::User,
+            comparison_id: None,
+            created_at: None,
+        },
+    )
+    .unwrap();
+
+    let obs = get_observations(&conn, cp_id).unwrap();
+    assert_eq!(obs.len(), 1);
+    assert_eq!(obs[0].observation, "Great for portraits");
+}
+
+#[test]
+fn test_checkpoint_context_string() {
+    let conn = setup();
+    let cp_id = upsert_checkpoint(&conn, &make_profile()).unwrap();
+    add_prompt_term(
+        &conn,
+        &PromptTerm {
+            id: None,
+            checkpoint_id: cp_id,
+            term: "cinematic lighting".to_string(),
+            effect: "Produces volumetric rays".to_string(),
+            strength: TermStrength::Strong,
+            example_image_id: None,
+            created_at: None,
+        },
+    )
+    .unwrap();
+
+    let ctx = get_checkpoint_context(&conn, "dreamshaper_8.safetensors").unwrap();
+    assert!(ctx.contains("DreamShaper v8"));
+    assert!(ctx.contains("photorealism"));
+    assert!(ctx.contains("cinematic lighting"));
+}
+
+#[test]
+fn test_get_nonexistent_checkpoint() {
+    let conn = setup();
+    assert!(get_checkpoint(&conn, "nope.safetensors").unwrap().is_none());
+}
+
+#[test]
+fn test_empty_context_for_unknown_checkpoint() {
+    let conn = setup();
+    let ctx = get_checkpoint_context(&conn, "unknown.safetensors").unwrap();
+    assert!(ctx.is_empty());
+}
+
+#[test]
+fn test_set_and_get_sample_image() {
+    let conn = setup();
+    upsert_checkpoint(&conn, &make_profile()).unwrap();
+    db::images::insert_image(&conn, &db::images::tests::make_test_image("img-001")).unwrap();
+
+    set_checkpoint_sample_image(&conn, "dreamshaper_8.safetensors", "img-001").unwrap();
+
+    let profile = get_checkpoint(&conn, "dreamshaper_8.safetensors")
+        .unwrap()
+        .unwrap();
+    assert_eq!(profile.sample_image_id.as_deref(), Some("img-001"));
+    assert_eq!(profile.sample_filename.as_deref(), Some("img-001.png"));
+    assert_eq!(
+        list_checkpoints(&conn).unwrap()[0]
+            .sample_filename
+            .as_deref(),
+        Some("img-001.png")
+    );
+
+    // Re-upserting the profile keeps the sample
+    upsert_checkpoint(&conn, &make_profile()).unwrap();
+    let profile = get_checkpoint(&conn, "dreamshaper_8.safetensors")
+        .unwrap()
+        .unwrap();
+    assert_eq!(profile.sample_image_id.as_deref(), Some("img-001"));
+}
+
+#[test]
+fn test_set_sample_image_rejects_unknown_image_or_checkpoint() {
+    let conn = setup();
+    upsert_checkpoint(&conn, &make_profile()).unwrap();
+    db::images::insert_image(&conn, &db::images::tests::make_test_image("img-001")).unwrap();
+
+    assert!(set_checkpoint_sample_image(&conn, "dreamshaper_8.safetensors", "nope").is_err());
+    assert!(set_checkpoint_sample_image(&conn, "missing.safetensors", "img-001").is_err());
+}
+
+#[test]
+fn test_promote_image_note_creates_observation() {
+    let conn = setup();
+    let mut image = db::images::tests::make_test_image("img-001");
+    image.user_note = Some("  Hands come out mangled above CFG 8 ".to_string());
+    db::images::insert_image(&conn, &image).unwrap();
+
+    // No profile exists yet; promotion creates one for the image's checkpoint
+    let observation = promote_image_note(&conn, "img-001").unwrap();
+    let profile = get_checkpoint(&conn, "dreamshaper_8.safetensors")
+        .unwrap()
+        .unwrap();
+    assert_eq!(observation.checkpoint_id, profile.id.unwrap());
+
+    let stored = get_observations(&conn, profile.id.unwrap()).unwrap();
+    assert_eq!(stored.len(), 1);
+    assert_eq!(stored[0].id, observation.id);
+    assert_eq!(stored[0].observation, "Hands come out mangled above CFG 8");
+    assert!(matches!(stored[0].source, ObservationSource::User));
+
+    // An existing profile is reused, not duplicated
+    promote_image_note(&conn, "img-001").unwrap();
+    assert_eq!(list_checkpoints(&conn).unwrap().len(), 1);
+    assert_eq!(
+        get_observations(&conn, profile.id.unwrap()).unwrap().len(),
+        2
+    );
+}
+
+#[test]
+fn test_promote_image_note_requires_note_and_checkpoint() {
+    let conn = setup();
+    db::images::insert_image(&conn, &db::images::tests::make_test_image("img-bare")).unwrap();
+    let mut no_checkpoint = db::images::tests::make_test_image("img-nockpt");
+    no_checkpoint.user_note = Some("nice".to_string());
+    no_checkpoint.checkpoint = None;
+    db::images::insert_image(&conn, &no_checkpoint).unwrap();
+
+    assert!(promote_image_note(&conn, "img-bare").is_err());
+    assert!(promote_image_note(&conn, "img-nockpt").is_err());
+    assert!(promote_image_note(&conn, "missing").is_err());
+    assert!(list_checkpoints(&conn).unwrap().is_empty());
+}
+
+#[test]
+fn test_default_request_uses_profile_preferences() {
+    let conn = setup();
+    let mut profile = make_profile();
+    profile.preferred_cfg = Some(5.0);
+    profile.preferred_sampler = Some("euler_ancestral".to_string());
+    profile.optimal_resolution = Some("832x1216".to_string());
+    upsert_checkpoint(&conn, &profile).unwrap();
+
+    let request = get_default_request(&conn, "dreamshaper_8.safetensors")
+        .unwrap()
+        .unwrap();
+    assert_eq!(request.checkpoint, "dreamshaper_8.safetensors");
+    assert_eq!((request.width, request.height), (832, 1216));
+    assert_eq!(request.cfg_scale, 5.0);
+    assert_eq!(request.sampler, "euler_ancestral");
+    assert_eq!(request.scheduler, "karras");
+    assert!(request.positive_prompt.is_empty());
+}
+
+#[test]
+fn test_default_request_falls_back_to_global_defaults() {
+    let conn = setup();
+    let mut profile = make_profile();
+    profile.preferred_cfg = None;
+    profile.preferred_sampler = None;
+    profile.preferred_scheduler = None;
+    profile.optimal_resolution = Some("portrait".to_string());
+    upsert_checkpoint(&conn, &profile).unwrap();
+
+    let request = get_default_request(&conn, "dreamshaper_8.safetensors")
+        .unwrap()
+        .unwrap();
+    assert_eq!((request.width, request.height), (512, 768));
+    assert_eq!(request.cfg_scale, 7.5);
+    assert_eq!(request.sampler, "dpmpp_2m");
+    assert_eq!(request.scheduler, "karras");
+    assert_eq!(request.steps, 25);
+    assert_eq!(request.seed, -1);
+
+    assert!(get_default_request(&conn, "unknown.safetensors")
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn test_parse_resolution() {
+    assert_eq!(parse_resolution("512x768"), Some((512, 768)));
+    assert_eq!(parse_resolution(" 1024 X 1024 "), Some((1024, 1024)));
+    assert_eq!(parse_resolution("832×1216"), Some((832, 1216)));
+
+    assert_eq!(parse_resolution(""), None);
+    assert_eq!(parse_resolution("512"), None);
+    assert_eq!(parse_resolution("512x"), None);
+    assert_eq!(parse_resolution("x768"), None);
+    assert_eq!(parse_resolution("0x768"), None);
+    assert_eq!(parse_resolution("512x768x2"), None);
+    assert_eq!(parse_resolution("-512x768"), None);
+    assert_eq!(parse_resolution("wide"), None);
+}
@@ -19,6 +19,33 @@ pub fn insert_comparison(conn: &Connection, comparison: &Comparison) -> Result<(
     Ok(())
 }
 
+/// Insert a comparison whose second image does not exist yet (e.g. a queued
+/// variation). `image_b_id` is filled in later via `set_comparison_image_b`.
+pub fn insert_pending_comparison(
+    conn: &Connection,
+    id: &str,
+    image_a_id: &str,
+    variable_changed: &str,
+) -> Result<()> {
+    conn.execute(
+        "INSERT INTO comparisons (id, image_a_id, image_b_id, variable_changed)
+         VALUES (?1, ?2, NULL, ?3)",
+        params![id, image_a_id, variable_changed],
+    )
+    .context("Failed to insert pending comparison")?;
+    Ok(())
+}
+
+/// Fill in the second image of a pending comparison. No-op if it is already set.
+pub fn set_comparison_image_b(conn: &Connection, id: &str, image_b_id: &str) -> Result<()> {
+    conn.execute(
+        "UPDATE comparisons SET image_b_id = ?1 WHERE id = ?2 AND image_b_id IS NULL",
+        params![image_b_id, id],
+    )
+    .context("Failed to set comparison image")?;
+    Ok(())
+}
+
 pub fn get_comparison(conn: &Connection, id: &str) -> Result<Option<Comparison>> {
     let mut stmt = conn
         .prepare(
@@ -101,7 +128,7 @@ fn row_to_comparison(row: &rusqlite::Row) -> rusqlite::Result<Comparison> {
     Ok(Comparison {
         id: row.get(0)?,
         image_a_id: row.get(1)?,
-        image_b_id: row.get(2)?,
+        image_b_id: row.get::<_, Option<String>>(2)?.unwrap_or_default(),
         variable_changed: row.get(3)?,
         note: row.get(4)?,
         created_at: row.get(5)?,
@@ -113,6 +140,7 @@ mod tests {
     use super::*;
     use crate::db;
     use crate::db::images;
+    use crate::db::images::tests::make_test_image;
     use crate::types::gallery::ImageEntry;
 
     fn setup() -> Connection {
@@ -121,30 +149,8 @@ mod tests {
 
     fn insert_test_image(conn: &Connection, id: &str, checkpoint: &str) {
         let img = ImageEntry {
-            id: id.to_string(),
-            filename: format!("{}.png", id),
-            created_at: "2026-01-15T10:00:00".to_string(),
             checkpoint: Some(checkpoint.to_string()),
-            positive_prompt: None,
-            negative_prompt: None,
-            original_idea: None,
-            width: None,
-            height: None,
-            steps: None,
-            cfg_scale: None,
-            sampler: None,
-            scheduler: None,
-            seed: None,
-            pipeline_log: None,
-            selected_concept: None,
-            auto_approved: false,
-            caption: None,
-            caption_edited: false,
-            rating: None,
-            favorite: false,
-            deleted: false,
-            user_note: None,
-            tags: None,
+            ..make_test_image(id)
         };
         images::insert_image(conn, &img).unwrap();
     }
@@ -268,4 +274,21 @@ mod tests {
         delete_comparison(&conn, "cmp-1").unwrap();
         assert!(get_comparison(&conn, "cmp-1").unwrap().is_none());
     }
+
+    #[test]
+    fn test_pending_comparison_fills_image_b() {
+        let conn = setup();
+        insert_test_image(&conn, "img-a", "ds");
+        insert_test_image(&conn, "img-b", "ds");
+
+        insert_pending_comparison(&conn, "cmp-1", "img-a", "sampler").unwrap();
+        let pending = get_comparison(&conn, "cmp-1").unwrap().unwrap();
+        assert_eq!(pending.image_a_id, "img-a");
+        assert!(pending.image_b_id.is_empty());
+
+        set_comparison_image_b(&conn, "cmp-1", "img-b").unwrap();
+        let done = get_comparison(&conn, "cmp-1").unwrap().unwrap();
+        assert_eq!(done.image_b_id, "img-b");
+        assert_eq!(done.variable_changed, "sampler");
+    }
 }
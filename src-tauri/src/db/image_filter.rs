@@ -0,0 +1,233 @@
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+
+use super::images::row_to_image;
+use super::search;
+use crate::types::gallery::{
+    GalleryFilter, GallerySortField, ImageEntry, SortOrder, DEFAULT_PAGE_SIZE,
+};
+
+pub fn list_images(conn: &Connection, filter: &GalleryFilter) -> Result<Vec<ImageEntry>> {
+    let FilterSql {
+        where_clause,
+        params: mut param_values,
+        next_idx,
+        fts_param,
+    } = build_filter_conditions(conn, filter);
+
+    let sort_dir = match filter.sort_order {
+        Some(SortOrder::Asc) => "ASC",
+        _ => "DESC",
+    };
+    let order_by = match (&filter.sort_by, fts_param) {
+        // bm25 rank: lower is more relevant, so best matches come first
+        (None | Some(GallerySortField::Relevance), Some(p)) => format!(
+            "(SELECT rank FROM images_fts WHERE images_fts MATCH ?{} AND image_id = images.id)",
+            p
+        ),
+        (Some(GallerySortField::Rating), _) => format!("rating {}", sort_dir),
+        (Some(GallerySortField::AestheticScore), _) => format!("aesthetic_score {}", sort_dir),
+        (Some(GallerySortField::Random), _) => "RANDOM()".to_string(),
+        (Some(GallerySortField::DeletedAt), _) => format!("deleted_at {}", sort_dir),
+        _ => format!("created_at {}", sort_dir),
+    };
+
+    let limit = filter.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let offset = filter.offset.unwrap_or(0);
+
+    let sql = format!(
+        "SELECT id, filename, created_at, positive_prompt, negative_prompt,
+                original_idea, checkpoint, width, height, steps, cfg_scale,
+                sampler, scheduler, seed, pipeline_log, selected_concept,
+                auto_approved, caption, caption_edited, rating, favorite,
+                deleted, user_note, compute_cost, source, aesthetic_score,
+            pipeline_run_id, denoise, settings_mismatch, parent_image_id, deleted_at
+         FROM images WHERE {} ORDER BY {} LIMIT ?{} OFFSET ?{}",
+        where_clause,
+        order_by,
+        next_idx,
+        next_idx + 1
+    );
+
+    param_values.push(Box::new(limit));
+    param_values.push(Box::new(offset));
+
+    let params_ref: Vec<&dyn rusqlite::types::ToSql> =
+        param_values.iter().map(|p| p.as_ref()).collect();
+
+    let mut stmt = conn
+        .prepare(&sql)
+        .context("Failed to prepare list_images query")?;
+    let rows = stmt
+        .query_map(params_ref.as_slice(), row_to_image)
+        .context("Failed to execute list_images query")?;
+
+    let mut images = Vec::new();
+    for row in rows {
+        images.push(row.context("Failed to read image row")?);
+    }
+    Ok(images)
+}
+
+/// Sum of `compute_cost` over all images matching the filter
+/// (limit/offset/sort are ignored).
+pub fn total_compute(conn: &Connection, filter: &GalleryFilter) -> Result<i64> {
+    let FilterSql {
+        where_clause,
+        params: param_values,
+        ..
+    } = build_filter_conditions(conn, filter);
+    let sql = format!(
+        "SELECT COALESCE(SUM(compute_cost), 0) FROM images WHERE {}",
+        where_clause
+    );
+
+    let params_ref: Vec<&dyn rusqlite::types::ToSql> =
+        param_values.iter().map(|p| p.as_ref()).collect();
+
+    conn.query_row(&sql, params_ref.as_slice(), |row| row.get(0))
+        .context("Failed to compute total compute cost")
+}
+
+/// Number of images matching the filter (limit/offset/sort are ignored).
+pub fn count_images(conn: &Connection, filter: &GalleryFilter) -> Result<u32> {
+    let FilterSql {
+        where_clause,
+        params: param_values,
+        ..
+    } = build_filter_conditions(conn, filter);
+    let sql = format!("SELECT COUNT(*) FROM images WHERE {}", where_clause);
+
+    let params_ref: Vec<&dyn rusqlite::types::ToSql> =
+        param_values.iter().map(|p| p.as_ref()).collect();
+
+    conn.query_row(&sql, params_ref.as_slice(), |row| row.get(0))
+        .context("Failed to count images")
+}
+
+/// WHERE clause and its parameters for a gallery filter.
+pub(super) struct FilterSql {
+    pub(super) where_clause: String,
+    pub(super) params: Vec<Box<dyn rusqlite::types::ToSql>>,
+    next_idx: usize,
+    /// Placeholder holding the FTS5 query, when search uses the index.
+    fts_param: Option<usize>,
+}
+
+pub(super) fn build_filter_conditions(conn: &Connection, filter: &GalleryFilter) -> FilterSql {
+    let mut conditions = vec!["1=1".to_string()];
+    let mut params: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
+    let mut idx = 1;
+
+    let show_deleted = filter.show_deleted.unwrap_or(false);
+    conditions.push(format!("deleted = ?{}", idx));
+    params.push(Box::new(show_deleted));
+    idx += 1;
+
+    if let Some(ref checkpoint) = filter.checkpoint {
+        conditions.push(format!("checkpoint = ?{}", idx));
+        params.push(Box::new(checkpoint.clone()));
+        idx += 1;
+    }
+    if let Some(min_rating) = filter.min_rating {
+        conditions.push(format!("rating >= ?{}", idx));
+        params.push(Box::new(min_rating));
+        idx += 1;
+    }
+    if filter.favorite_only.unwrap_or(false) {
+        conditions.push(format!("favorite = ?{}", idx));
+        params.push(Box::new(true));
+        idx += 1;
+    }
+    if let Some(auto_approved) = filter.auto_approved {
+        conditions.push(format!("auto_approved = ?{}", idx));
+        params.push(Box::new(auto_approved));
+        idx += 1;
+    }
+    // Tag names are bound as parameters, never formatted into the SQL
+    if let Some(ref tags) = filter.tags {
+        let mut names: Vec<&str> = Vec::new();
+        for tag in tags {
+            if !names.contains(&tag.as_str()) {
+                names.push(tag);
+            }
+        }
+        if !names.is_empty() {
+            let placeholders: Vec<String> =
+                (0..names.len()).map(|i| format!("?{}", idx + i)).collect();
+            // Matching all tags means the image carries every distinct name
+            let having = if filter.tag_match_all.unwrap_or(true) {
+                format!(
+                    " GROUP BY it.image_id HAVING COUNT(DISTINCT t.id) = {}",
+                    names.len()
+                )
+            } else {
+                String::new()
+            };
+            conditions.push(format!(
+                "images.id IN (SELECT it.image_id FROM image_tags it JOIN tags t ON it.tag_id = t.id \
+                 WHERE t.name IN ({}){})",
+                placeholders.join(", "),
+                having
+            ));
+            for name in &names {
+                params.push(Box::new(name.to_string()));
+            }
+            idx += names.len();
+        }
+    }
+    if filter.untagged_only.unwrap_or(false) {
+        conditions.push(
+            "NOT EXISTS (SELECT 1 FROM image_tags it WHERE it.image_id = images.id AND it.source = 'ai')"
+                .to_string(),
+        );
+    }
+    if let Some(source) = filter.source {
+        conditions.push(format!("source = ?{}", idx));
+        params.push(Box::new(source.as_str().to_string()));
+        idx += 1;
+    }
+    if filter.uncaptioned_only.unwrap_or(false) {
+        conditions.push("(caption IS NULL OR caption = '')".to_string());
+    }
+    if let Some(ref since) = filter.since {
+        conditions.push(format!("created_at > ?{}", idx));
+        params.push(Box::new(since.clone()));
+        idx += 1;
+    }
+    let mut fts_param = None;
+    if let Some(ref search) = filter.search {
+        if search::fts_enabled(conn) {
+            // Text with nothing searchable in it filters nothing, as LIKE did
+            if let Some(query) = search::fts_query(search) {
+                conditions.push(format!(
+                    "images.id IN (SELECT image_id FROM images_fts WHERE images_fts MATCH ?{})",
+                    idx
+                ));
+                params.push(Box::new(query));
+                fts_param = Some(idx);
+                idx += 1;
+            }
+        } else {
+            let like = format!("%{}%", search);
+            conditions.push(format!(
+                "(positive_prompt LIKE ?{p} OR negative_prompt LIKE ?{p} \
+                 OR original_idea LIKE ?{p} OR caption LIKE ?{p})",
+                p = idx
+            ));
+            params.push(Box::new(like));
+            idx += 1;
+        }
+    }
+
+    FilterSql {
+        where_clause: conditions.join(" AND "),
+        params,
+        next_idx: idx,
+        fts_param,
+    }
+}
+
+#[cfg(test)]
+#[path = "image_filter_test.rs"]
+mod tests;
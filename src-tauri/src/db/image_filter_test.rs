@@ -0,0 +1,391 @@
+use super::*;
+use crate::db;
+use crate::db::images::tests::make_test_image;
+use crate::db::images::{get_image, insert_image, soft_delete_image};
+use crate::types::gallery::ImageSource;
+
+fn setup() -> Connection {
+    db::open_memory_database().unwrap()
+}
+
+#[test]
+fn test_list_default_filter() {
+    let conn = setup();
+    for i in 0..5 {
+        insert_image(&conn, &make_test_image(&format!("img-{:03}", i))).unwrap();
+    }
+    let images = list_images(&conn, &GalleryFilter::default()).unwrap();
+    assert_eq!(images.len(), 5);
+}
+
+#[test]
+fn test_list_with_checkpoint_filter() {
+    let conn = setup();
+    let mut img1 = make_test_image("img-001");
+    img1.checkpoint = Some("dreamshaper.safetensors".to_string());
+    let mut img2 = make_test_image("img-002");
+    img2.checkpoint = Some("deliberate.safetensors".to_string());
+    insert_image(&conn, &img1).unwrap();
+    insert_image(&conn, &img2).unwrap();
+
+    let filter = GalleryFilter {
+        checkpoint: Some("dreamshaper.safetensors".to_string()),
+        ..Default::default()
+    };
+    let images = list_images(&conn, &filter).unwrap();
+    assert_eq!(images.len(), 1);
+    assert_eq!(images[0].id, "img-001");
+}
+
+#[test]
+fn test_list_with_search() {
+    let conn = setup();
+    let mut img1 = make_test_image("img-001");
+    img1.positive_prompt = Some("beautiful sunset over ocean".to_string());
+    let mut img2 = make_test_image("img-002");
+    img2.positive_prompt = Some("dark forest at night".to_string());
+    insert_image(&conn, &img1).unwrap();
+    insert_image(&conn, &img2).unwrap();
+
+    let filter = GalleryFilter {
+        search: Some("sunset".to_string()),
+        ..Default::default()
+    };
+    let images = list_images(&conn, &filter).unwrap();
+    assert_eq!(images.len(), 1);
+    assert_eq!(images[0].id, "img-001");
+}
+
+fn tagged_ids(conn: &Connection, tags: &[&str], match_all: Option<bool>) -> Vec<String> {
+    let filter = GalleryFilter {
+        tags: Some(tags.iter().map(|t| t.to_string()).collect()),
+        tag_match_all: match_all,
+        ..Default::default()
+    };
+    let mut ids: Vec<String> = list_images(conn, &filter)
+        .unwrap()
+        .into_iter()
+        .map(|img| img.id)
+        .collect();
+    ids.sort();
+    ids
+}
+
+fn insert_tagged(conn: &Connection) {
+    for (id, tags) in [
+        ("img-1", &["cat", "night"][..]),
+        ("img-2", &["cat"][..]),
+        ("img-3", &["dog", "night"][..]),
+        ("img-4", &[][..]),
+    ] {
+        insert_image(conn, &make_test_image(id)).unwrap();
+        for tag in tags {
+            db::tags::add_image_tag(conn, id, tag, "user", None).unwrap();
+        }
+    }
+}
+
+#[test]
+fn test_filter_by_single_tag() {
+    let conn = setup();
+    insert_tagged(&conn);
+    assert_eq!(tagged_ids(&conn, &["cat"], None), vec!["img-1", "img-2"]);
+    assert!(tagged_ids(&conn, &["unknown"], None).is_empty());
+}
+
+#[test]
+fn test_filter_by_tags_all_and_any() {
+    let conn = setup();
+    insert_tagged(&conn);
+    assert_eq!(tagged_ids(&conn, &["cat", "night"], None), vec!["img-1"]);
+    // A repeated tag doesn't make "all" unsatisfiable
+    assert_eq!(
+        tagged_ids(&conn, &["cat", "night", "cat"], Some(true)),
+        vec!["img-1"]
+    );
+    assert_eq!(
+        tagged_ids(&conn, &["cat", "night"], Some(false)),
+        vec!["img-1", "img-2", "img-3"]
+    );
+}
+
+#[test]
+fn test_filter_by_empty_tags_is_noop() {
+    let conn = setup();
+    insert_tagged(&conn);
+    assert_eq!(tagged_ids(&conn, &[], None).len(), 4);
+    assert_eq!(tagged_ids(&conn, &[], Some(false)).len(), 4);
+}
+
+fn search_ids(conn: &Connection, search: &str) -> Vec<String> {
+    let filter = GalleryFilter {
+        search: Some(search.to_string()),
+        sort_by: Some(GallerySortField::Relevance),
+        ..Default::default()
+    };
+    list_images(conn, &filter)
+        .unwrap()
+        .into_iter()
+        .map(|img| img.id)
+        .collect()
+}
+
+fn insert_prompts(conn: &Connection, prompts: &[(&str, &str)]) {
+    for (id, prompt) in prompts {
+        let mut img = make_test_image(id);
+        img.positive_prompt = Some(prompt.to_string());
+        insert_image(conn, &img).unwrap();
+    }
+}
+
+#[test]
+fn test_full_text_search_phrases_and_prefixes() {
+    let conn = setup();
+    insert_prompts(
+        &conn,
+        &[
+            ("img-001", "a dark forest at dusk"),
+            ("img-002", "a forest, dark and misty"),
+            ("img-003", "sunshine over the sea"),
+            ("img-004", "sunset on the beach"),
+        ],
+    );
+    let mut captioned = make_test_image("img-005");
+    captioned.positive_prompt = None;
+    captioned.caption = Some("Dark forest path".to_string());
+    insert_image(&conn, &captioned).unwrap();
+
+    let mut phrase = search_ids(&conn, "\"dark forest\"");
+    phrase.sort();
+    assert_eq!(phrase, ["img-001", "img-005"]);
+
+    let mut words = search_ids(&conn, "dark forest");
+    words.sort();
+    assert_eq!(words, ["img-001", "img-002", "img-005"]);
+
+    let mut prefix = search_ids(&conn, "suns*");
+    prefix.sort();
+    assert_eq!(prefix, ["img-003", "img-004"]);
+
+    // Stray punctuation and operators are searched as text, not parsed
+    assert!(search_ids(&conn, "\"unclosed AND (").is_empty());
+}
+
+#[test]
+fn test_full_text_search_ranks_best_match_first() {
+    let conn = setup();
+    insert_prompts(
+        &conn,
+        &[
+            (
+                "img-001",
+                "portrait of a knight, castle in the background, banners",
+            ),
+            ("img-002", "castle, castle walls, castle towers"),
+        ],
+    );
+    assert_eq!(search_ids(&conn, "castle"), ["img-002", "img-001"]);
+}
+
+#[test]
+fn test_full_text_search_respects_deleted_filter() {
+    let conn = setup();
+    insert_prompts(&conn, &[("img-001", "sunset"), ("img-002", "sunset again")]);
+    soft_delete_image(&conn, "img-002").unwrap();
+
+    assert_eq!(search_ids(&conn, "sunset"), ["img-001"]);
+    let deleted = GalleryFilter {
+        search: Some("sunset".to_string()),
+        show_deleted: Some(true),
+        ..Default::default()
+    };
+    let images = list_images(&conn, &deleted).unwrap();
+    assert_eq!(images.len(), 1);
+    assert_eq!(images[0].id, "img-002");
+}
+
+#[test]
+fn test_search_falls_back_to_like_without_fts() {
+    let conn = setup();
+    crate::db::search::drop_fts(&conn);
+    insert_prompts(&conn, &[("img-001", "sunset"), ("img-002", "forest")]);
+
+    // LIKE matches substrings, as before the index existed
+    assert_eq!(search_ids(&conn, "unse"), ["img-001"]);
+    let filter = GalleryFilter {
+        search: Some("unse".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(total_compute(&conn, &filter).unwrap(), 0);
+}
+
+#[test]
+fn test_pagination() {
+    let conn = setup();
+    for i in 0..10 {
+        insert_image(&conn, &make_test_image(&format!("img-{:03}", i))).unwrap();
+    }
+
+    let page1 = list_images(
+        &conn,
+        &GalleryFilter {
+            limit: Some(3),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(page1.len(), 3);
+
+    let page2 = list_images(
+        &conn,
+        &GalleryFilter {
+            limit: Some(3),
+            offset: Some(3),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(page2.len(), 3);
+    assert_ne!(page1[0].id, page2[0].id);
+}
+
+#[test]
+fn test_total_compute_sums_matching_images() {
+    let conn = setup();
+    let mut a = make_test_image("img-001");
+    a.compute_cost = Some(512 * 768 * 25);
+    let mut b = make_test_image("img-002");
+    b.compute_cost = Some(1024 * 1024 * 30 * 2);
+    b.checkpoint = Some("other.safetensors".to_string());
+    insert_image(&conn, &a).unwrap();
+    insert_image(&conn, &b).unwrap();
+
+    let total = total_compute(&conn, &GalleryFilter::default()).unwrap();
+    assert_eq!(total, 512 * 768 * 25 + 1024 * 1024 * 30 * 2);
+
+    let filtered = total_compute(
+        &conn,
+        &GalleryFilter {
+            checkpoint: Some("other.safetensors".to_string()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(filtered, 1024 * 1024 * 30 * 2);
+}
+
+#[test]
+fn test_since_filter_returns_only_newer_images() {
+    let conn = setup();
+    for (id, created_at) in [
+        ("old", "2026-03-01T08:00:00+00:00"),
+        ("at-visit", "2026-03-01T09:00:00+00:00"),
+        ("new-1", "2026-03-01T10:00:00+00:00"),
+        ("new-2", "2026-03-02T07:30:00+00:00"),
+    ] {
+        let mut image = make_test_image(id);
+        image.created_at = created_at.to_string();
+        insert_image(&conn, &image).unwrap();
+    }
+
+    let filter = GalleryFilter {
+        since: Some("2026-03-01T09:00:00+00:00".to_string()),
+        ..Default::default()
+    };
+    let ids: Vec<String> = list_images(&conn, &filter)
+        .unwrap()
+        .into_iter()
+        .map(|i| i.id)
+        .collect();
+    assert_eq!(ids, ["new-2", "new-1"]);
+    assert_eq!(count_images(&conn, &filter).unwrap(), 2);
+
+    // The count ignores the page size
+    let page = GalleryFilter {
+        limit: Some(1),
+        ..filter
+    };
+    assert_eq!(list_images(&conn, &page).unwrap().len(), 1);
+    assert_eq!(count_images(&conn, &page).unwrap(), 2);
+    assert_eq!(count_images(&conn, &GalleryFilter::default()).unwrap(), 4);
+}
+
+#[test]
+fn test_omitted_limit_uses_configured_page_size() {
+    let conn = setup();
+    for i in 0..4 {
+        let mut image = make_test_image(&format!("img-{}", i));
+        image.created_at = format!("2026-03-01T10:00:0{}+00:00", i);
+        image.rating = Some(4 - i);
+        insert_image(&conn, &image).unwrap();
+    }
+
+    let filter = GalleryFilter::default().with_defaults(3, None);
+    assert_eq!(filter.limit, Some(3));
+    assert_eq!(list_images(&conn, &filter).unwrap().len(), 3);
+
+    // An explicit limit wins over the configured one
+    let explicit = GalleryFilter {
+        limit: Some(1),
+        ..Default::default()
+    }
+    .with_defaults(3, None);
+    assert_eq!(list_images(&conn, &explicit).unwrap().len(), 1);
+
+    // The configured sort applies only when the filter has none
+    let by_rating = GalleryFilter {
+        sort_order: Some(SortOrder::Asc),
+        ..Default::default()
+    }
+    .with_defaults(10, Some(&GallerySortField::Rating));
+    let ids: Vec<String> = list_images(&conn, &by_rating)
+        .unwrap()
+        .into_iter()
+        .map(|i| i.id)
+        .collect();
+    assert_eq!(ids, ["img-3", "img-2", "img-1", "img-0"]);
+    let searching = GalleryFilter {
+        search: Some("cat".to_string()),
+        ..Default::default()
+    }
+    .with_defaults(10, Some(&GallerySortField::Rating));
+    assert!(searching.sort_by.is_none());
+}
+
+#[test]
+fn test_total_compute_empty_is_zero() {
+    let conn = setup();
+    assert_eq!(total_compute(&conn, &GalleryFilter::default()).unwrap(), 0);
+}
+
+#[test]
+fn test_filter_by_source() {
+    let conn = setup();
+    let mut generated = make_test_image("img-pipeline");
+    generated.source = Some(ImageSource::Pipeline);
+    let mut imported = make_test_image("img-imported");
+    imported.source = Some(ImageSource::Imported);
+    insert_image(&conn, &generated).unwrap();
+    insert_image(&conn, &imported).unwrap();
+
+    assert_eq!(
+        get_image(&conn, "img-imported").unwrap().unwrap().source,
+        Some(ImageSource::Imported)
+    );
+
+    let filter = GalleryFilter {
+        source: Some(ImageSource::Pipeline),
+        ..Default::default()
+    };
+    let images = list_images(&conn, &filter).unwrap();
+    assert_eq!(images.len(), 1);
+    assert_eq!(images[0].id, "img-pipeline");
+
+    let filter = GalleryFilter {
+        source: Some(ImageSource::Imported),
+        ..Default::default()
+    };
+    let images = list_images(&conn, &filter).unwrap();
+    assert_eq!(images.len(), 1);
+    assert_eq!(images[0].id, "img-imported");
+}
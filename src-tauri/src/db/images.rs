@@ -1,7 +1,7 @@
 use anyhow::{Context, Result};
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OptionalExtension};
 
-use crate::types::gallery::{GalleryFilter, GallerySortField, ImageEntry, SortOrder};
+use crate::types::gallery::{ImageEntry, ImageSource};
 
 pub fn insert_image(conn: &Connection, image: &ImageEntry) -> Result<()> {
     conn.execute(
@@ -10,10 +10,12 @@ pub fn insert_image(conn: &Connection, image: &ImageEntry) -> Result<()> {
             original_idea, checkpoint, width, height, steps, cfg_scale,
             sampler, scheduler, seed, pipeline_log, selected_concept,
             auto_approved, caption, caption_edited, rating, favorite,
-            deleted, user_note
+            deleted, user_note, compute_cost, source, aesthetic_score,
+            pipeline_run_id, denoise, settings_mismatch, parent_image_id
         ) VALUES (
             ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11,
-            ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23
+            ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26,
+            ?27, ?28, ?29, ?30
         )",
         params![
             image.id,
@@ -39,6 +41,13 @@ pub fn insert_image(conn: &Connection, image: &ImageEntry) -> Result<()> {
             image.favorite,
             image.deleted,
             image.user_note,
+            image.compute_cost,
+            image.source.as_ref().map(|s| s.as_str()),
+            image.aesthetic_score,
+            image.pipeline_run_id,
+            image.denoise,
+            image.settings_mismatch,
+            image.parent_image_id,
         ],
     )
     .context("Failed to insert image")?;
@@ -52,7 +61,8 @@ pub fn get_image(conn: &Connection, id: &str) -> Result<Option<ImageEntry>> {
                     original_idea, checkpoint, width, height, steps, cfg_scale,
                     sampler, scheduler, seed, pipeline_log, selected_concept,
                     auto_approved, caption, caption_edited, rating, favorite,
-                    deleted, user_note
+                    deleted, user_note, compute_cost, source, aesthetic_score,
+            pipeline_run_id, denoise, settings_mismatch, parent_image_id, deleted_at
              FROM images WHERE id = ?1",
         )
         .context("Failed to prepare get_image query")?;
@@ -67,111 +77,6 @@ pub fn get_image(conn: &Connection, id: &str) -> Result<Option<ImageEntry>> {
     }
 }
 
-pub fn list_images(conn: &Connection, filter: &GalleryFilter) -> Result<Vec<ImageEntry>> {
-    let (where_clause, mut param_values, next_idx) = build_filter_conditions(filter);
-
-    let sort_col = match filter.sort_by {
-        Some(GallerySortField::Rating) => "rating",
-        Some(GallerySortField::Random) => "RANDOM()",
-        _ => "created_at",
-    };
-    let sort_dir = match filter.sort_order {
-        Some(SortOrder::Asc) => "ASC",
-        _ => "DESC",
-    };
-
-    let limit = filter.limit.unwrap_or(50);
-    let offset = filter.offset.unwrap_or(0);
-
-    let sql = format!(
-        "SELECT id, filename, created_at, positive_prompt, negative_prompt,
-                original_idea, checkpoint, width, height, steps, cfg_scale,
-                sampler, scheduler, seed, pipeline_log, selected_concept,
-                auto_approved, caption, caption_edited, rating, favorite,
-                deleted, user_note
-         FROM images WHERE {} ORDER BY {} {} LIMIT ?{} OFFSET ?{}",
-        where_clause,
-        sort_col,
-        sort_dir,
-        next_idx,
-        next_idx + 1
-    );
-
-    param_values.push(Box::new(limit));
-    param_values.push(Box::new(offset));
-
-    let params_ref: Vec<&dyn rusqlite::types::ToSql> =
-        param_values.iter().map(|p| p.as_ref()).collect();
-
-    let mut stmt = conn
-        .prepare(&sql)
-        .context("Failed to prepare list_images query")?;
-    let rows = stmt
-        .query_map(params_ref.as_slice(), row_to_image)
-        .context("Failed to execute list_images query")?;
-
-    let mut images = Vec::new();
-    for row in rows {
-        images.push(row.context("Failed to read image row")?);
-    }
-    Ok(images)
-}
-
-fn build_filter_conditions(
-    filter: &GalleryFilter,
-) -> (String, Vec<Box<dyn rusqlite::types::ToSql>>, usize) {
-    let mut conditions = vec!["1=1".to_string()];
-    let mut params: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
-    let mut idx = 1;
-
-    let show_deleted = filter.show_deleted.unwrap_or(false);
-    conditions.push(format!("deleted = ?{}", idx));
-    params.push(Box::new(show_deleted));
-    idx += 1;
-
-    if let Some(ref checkpoint) = filter.checkpoint {
-        conditions.push(format!("checkpoint = ?{}", idx));
-        params.push(Box::new(checkpoint.clone()));
-        idx += 1;
-    }
-    if let Some(min_rating) = filter.min_rating {
-        conditions.push(format!("rating >= ?{}", idx));
-        params.push(Box::new(min_rating));
-        idx += 1;
-    }
-    if filter.favorite_only.unwrap_or(false) {
-        conditions.push(format!("favorite = ?{}", idx));
-        params.push(Box::new(true));
-        idx += 1;
-    }
-    if let Some(auto_approved) = filter.auto_approved {
-        conditions.push(format!("auto_approved = ?{}", idx));
-        params.push(Box::new(auto_approved));
-        idx += 1;
-    }
-    if filter.untagged_only.unwrap_or(false) {
-        conditions.push(
-            "NOT EXISTS (SELECT 1 FROM image_tags it WHERE it.image_id = images.id AND it.source = 'ai')"
-                .to_string(),
-        );
-    }
-    if filter.uncaptioned_only.unwrap_or(false) {
-        conditions.push("(caption IS NULL OR caption = '')".to_string());
-    }
-    if let Some(ref search) = filter.search {
-        let like = format!("%{}%", search);
-        conditions.push(format!(
-            "(positive_prompt LIKE ?{p} OR negative_prompt LIKE ?{p} \
-             OR original_idea LIKE ?{p} OR caption LIKE ?{p})",
-            p = idx
-        ));
-        params.push(Box::new(like));
-        idx += 1;
-    }
-
-    (conditions.join(" AND "), params, idx)
-}
-
 pub fn update_image_rating(conn: &Connection, id: &str, rating: Option<u32>) -> Result<()> {
     conn.execute(
         "UPDATE images SET rating = ?1 WHERE id = ?2",
@@ -181,6 +86,46 @@ pub fn update_image_rating(conn: &Connection, id: &str, rating: Option<u32>) ->
     Ok(())
 }
 
+/// Set the rating and, if `auto_favorite_rating` is non-zero and the new rating
+/// meets it, also mark the image as a favorite. Never clears an existing favorite.
+pub fn update_image_rating_with_auto_favorite(
+    conn: &Connection,
+    id: &str,
+    rating: Option<u32>,
+    auto_favorite_rating: u32,
+) -> Result<()> {
+    update_image_rating(conn, id, rating)?;
+    let reaches_threshold = rating.is_some_and(|r| r >= auto_favorite_rating);
+    if auto_favorite_rating > 0 && reaches_threshold {
+        update_image_favorite(conn, id, true)?;
+    }
+    Ok(())
+}
+
+/// Set a rating only if the image has none yet. Returns whether it was set.
+pub fn set_rating_if_unrated(conn: &Connection, id: &str, rating: u32) -> Result<bool> {
+    let updated = conn
+        .execute(
+            "UPDATE images SET rating = ?1 WHERE id = ?2 AND rating IS NULL",
+            params![rating, id],
+        )
+        .context("Failed to set initial image rating")?;
+    Ok(updated > 0)
+}
+
+pub fn set_aesthetic_score(conn: &Connection, id: &str, score: f64) -> Result<()> {
+    let updated = conn
+        .execute(
+            "UPDATE images SET aesthetic_score = ?1 WHERE id = ?2",
+            params![score, id],
+        )
+        .context("Failed to update aesthetic score")?;
+    if updated == 0 {
+        anyhow::bail!("Image {} not found", id);
+    }
+    Ok(())
+}
+
 pub fn update_image_favorite(conn: &Connection, id: &str, favorite: bool) -> Result<()> {
     conn.execute(
         "UPDATE images SET favorite = ?1 WHERE id = ?2",
@@ -215,7 +160,7 @@ pub fn update_image_note(conn: &Connection, id: &str, note: &str) -> Result<()>
 
 pub fn soft_delete_image(conn: &Connection, id: &str) -> Result<()> {
     conn.execute(
-        "UPDATE images SET deleted = TRUE WHERE id = ?1",
+        "UPDATE images SET deleted = TRUE, deleted_at = CURRENT_TIMESTAMP WHERE id = ?1",
         params![id],
     )
     .context("Failed to soft-delete image")?;
@@ -224,19 +169,107 @@ pub fn soft_delete_image(conn: &Connection, id: &str) -> Result<()> {
 
 pub fn restore_image(conn: &Connection, id: &str) -> Result<()> {
     conn.execute(
-        "UPDATE images SET deleted = FALSE WHERE id = ?1",
+        "UPDATE images SET deleted = FALSE, deleted_at = NULL WHERE id = ?1",
         params![id],
     )
     .context("Failed to restore image")?;
     Ok(())
 }
 
+/// Delete every image that has been in the trash for more than
+/// `retention_days` days. Returns the purged filenames so the caller can
+/// remove the files. 0 days disables purging.
+pub fn purge_expired_trash(conn: &Connection, retention_days: u32) -> Result<Vec<String>> {
+    if retention_days == 0 {
+        return Ok(Vec::new());
+    }
+    let cutoff = format!("-{} days", retention_days);
+    let tx = conn
+        .unchecked_transaction()
+        .context("Failed to start trash purge transaction")?;
+    let filenames = {
+        let mut stmt = tx
+            .prepare(
+                "SELECT filename FROM images
+                 WHERE deleted = TRUE AND deleted_at < datetime('now', ?1)",
+            )
+            .context("Failed to prepare expired trash query")?;
+        let rows = stmt
+            .query_map(params![cutoff], |row| row.get::<_, String>(0))
+            .context("Failed to execute expired trash query")?;
+        let mut filenames = Vec::new();
+        for row in rows {
+            filenames.push(row.context("Failed to read expired trash row")?);
+        }
+        filenames
+    };
+    tx.execute(
+        "DELETE FROM images WHERE deleted = TRUE AND deleted_at < datetime('now', ?1)",
+        params![cutoff],
+    )
+    .context("Failed to purge expired trash")?;
+    tx.commit().context("Failed to commit trash purge")?;
+    Ok(filenames)
+}
+
 pub fn permanently_delete_image(conn: &Connection, id: &str) -> Result<()> {
     conn.execute("DELETE FROM images WHERE id = ?1", params![id])
         .context("Failed to permanently delete image")?;
     Ok(())
 }
 
+/// Checkpoints used by non-deleted images with their image counts, most used
+/// first.
+pub fn distinct_checkpoints(conn: &Connection) -> Result<Vec<(String, u32)>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT checkpoint, COUNT(*) FROM images
+             WHERE deleted = 0 AND checkpoint IS NOT NULL AND checkpoint != ''
+             GROUP BY checkpoint
+             ORDER BY COUNT(*) DESC, checkpoint",
+        )
+        .context("Failed to prepare distinct checkpoints query")?;
+
+    let rows = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .context("Failed to execute distinct checkpoints query")?;
+
+    let mut checkpoints = Vec::new();
+    for row in rows {
+        checkpoints.push(row.context("Failed to read checkpoint row")?);
+    }
+    Ok(checkpoints)
+}
+
+/// ID of the image stored under `filename`, if any.
+pub fn find_image_id_by_filename(conn: &Connection, filename: &str) -> Result<Option<String>> {
+    conn.query_row(
+        "SELECT id FROM images WHERE filename = ?1",
+        params![filename],
+        |row| row.get(0),
+    )
+    .optional()
+    .context("Failed to look up image by filename")
+}
+
+/// Filenames of every image row, including soft-deleted ones (their files
+/// stay on disk until permanently deleted).
+pub fn list_all_filenames(conn: &Connection) -> Result<Vec<String>> {
+    let mut stmt = conn
+        .prepare("SELECT filename FROM images ORDER BY created_at")
+        .context("Failed to prepare list_all_filenames query")?;
+
+    let rows = stmt
+        .query_map([], |row| row.get(0))
+        .context("Failed to execute list_all_filenames query")?;
+
+    let mut filenames = Vec::new();
+    for row in rows {
+        filenames.push(row.context("Failed to read filename row")?);
+    }
+    Ok(filenames)
+}
+
 pub fn row_to_image(row: &rusqlite::Row) -> rusqlite::Result<ImageEntry> {
     Ok(ImageEntry {
         id: row.get(0)?,
@@ -262,10 +295,21 @@ pub fn row_to_image(row: &rusqlite::Row) -> rusqlite::Result<ImageEntry> {
         favorite: row.get(20)?,
         deleted: row.get(21)?,
         user_note: row.get(22)?,
+        compute_cost: row.get(23)?,
+        source: row
+            .get::<_, Option<String>>(24)?
+            .as_deref()
+            .and_then(ImageSource::from_str),
+        aesthetic_score: row.get(25)?,
+        pipeline_run_id: row.get(26)?,
+        denoise: row.get(27)?,
+        settings_mismatch: row.get(28)?,
+        parent_image_id: row.get(29)?,
+        deleted_at: row.get(30)?,
         tags: None,
     })
 }
 
 #[cfg(test)]
 #[path = "images_test.rs"]
-mod tests;
+pub(crate) mod tests;
@@ -0,0 +1,282 @@
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+
+use super::images::update_image_rating_with_auto_favorite;
+use crate::types::gallery::BatchUpdate;
+
+/// Run `update` for each id inside one transaction, sorting ids by whether
+/// it changed a row.
+fn update_each(
+    conn: &Connection,
+    ids: &[String],
+    mut update: impl FnMut(&Connection, &str) -> Result<usize>,
+) -> Result<BatchUpdate> {
+    let tx = conn
+        .unchecked_transaction()
+        .context("Failed to start batch update transaction")?;
+    let mut result = BatchUpdate::default();
+    for id in ids {
+        if update(&tx, id)? > 0 {
+            result.updated.push(id.clone());
+        } else {
+            result.not_found.push(id.clone());
+        }
+    }
+    tx.commit().context("Failed to commit batch update")?;
+    Ok(result)
+}
+
+/// Move many images to the trash in one transaction.
+pub fn soft_delete_many(conn: &Connection, ids: &[String]) -> Result<BatchUpdate> {
+    update_each(conn, ids, |tx, id| {
+        tx.execute(
+            "UPDATE images SET deleted = TRUE, deleted_at = CURRENT_TIMESTAMP WHERE id = ?1",
+            params![id],
+        )
+        .context("Failed to soft-delete image")
+    })
+}
+
+/// Rate many images in one transaction, applying the auto-favorite
+/// threshold to each via [`update_image_rating_with_auto_favorite`].
+pub fn update_rating_many(
+    conn: &Connection,
+    ids: &[String],
+    rating: Option<u32>,
+    auto_favorite_rating: u32,
+) -> Result<BatchUpdate> {
+    update_each(conn, ids, |tx, id| {
+        let exists: bool = tx
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM images WHERE id = ?1)",
+                params![id],
+                |row| row.get(0),
+            )
+            .context("Failed to look up image")?;
+        if !exists {
+            return Ok(0);
+        }
+        update_image_rating_with_auto_favorite(tx, id, rating, auto_favorite_rating)?;
+        Ok(1)
+    })
+}
+
+/// Set or clear the favorite flag on many images in one transaction.
+pub fn update_favorite_many(
+    conn: &Connection,
+    ids: &[String],
+    favorite: bool,
+) -> Result<BatchUpdate> {
+    update_each(conn, ids, |tx, id| {
+        tx.execute(
+            "UPDATE images SET favorite = ?1 WHERE id = ?2",
+            params![favorite, id],
+        )
+        .context("Failed to update image favorite")
+    })
+}
+
+/// Re-label many images with `checkpoint` in one transaction. Fails without
+/// changing anything if any id is unknown. Checkpoints whose sample image was
+/// moved away lose it, since it no longer shows their output. Comparison and
+/// gallery views read `images.checkpoint` directly, so they follow the change.
+pub fn reattribute_checkpoint(conn: &Connection, ids: &[String], checkpoint: &str) -> Result<u32> {
+    let checkpoint = checkpoint.trim();
+    if checkpoint.is_empty() {
+        anyhow::bail!("Checkpoint name cannot be empty");
+    }
+
+    let tx = conn
+        .unchecked_transaction()
+        .context("Failed to start reattribution transaction")?;
+    let mut updated = 0;
+    for id in ids {
+        let changed = tx
+            .execute(
+                "UPDATE images SET checkpoint = ?1 WHERE id = ?2",
+                params![checkpoint, id],
+            )
+            .context("Failed to update image checkpoint")?;
+        if changed == 0 {
+            anyhow::bail!("Image {} not found", id);
+        }
+        updated += 1;
+
+        tx.execute(
+            "UPDATE checkpoints SET sample_image_id = NULL
+             WHERE sample_image_id = ?1 AND filename != ?2",
+            params![id, checkpoint],
+        )
+        .context("Failed to clear stale checkpoint sample image")?;
+    }
+    tx.commit().context("Failed to commit reattribution")?;
+    Ok(updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::db::images::tests::make_test_image;
+    use crate::db::images::{get_image, insert_image, update_image_favorite};
+
+    fn setup() -> Connection {
+        db::open_memory_database().unwrap()
+    }
+
+    #[test]
+    fn test_update_rating_many_rates_range_and_auto_favorites() {
+        let conn = setup();
+        for id in ["img-001", "img-002", "img-003", "img-004"] {
+            insert_image(&conn, &make_test_image(id)).unwrap();
+        }
+        let ids: Vec<String> = ["img-001", "img-002", "img-003"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        let result = update_rating_many(&conn, &ids, Some(5), 4).unwrap();
+        assert_eq!(result.updated, ids);
+        for id in &ids {
+            let image = get_image(&conn, id).unwrap().unwrap();
+            assert_eq!(image.rating, Some(5));
+            assert!(image.favorite);
+        }
+        let untouched = get_image(&conn, "img-004").unwrap().unwrap();
+        assert_eq!(untouched.rating, None);
+        assert!(!untouched.favorite);
+    }
+
+    fn mixed_batch(conn: &Connection) -> Vec<String> {
+        insert_image(conn, &make_test_image("img-001")).unwrap();
+        insert_image(conn, &make_test_image("img-002")).unwrap();
+        vec![
+            "img-001".to_string(),
+            "missing".to_string(),
+            "img-002".to_string(),
+        ]
+    }
+
+    fn expected_batch() -> BatchUpdate {
+        BatchUpdate {
+            updated: vec!["img-001".to_string(), "img-002".to_string()],
+            not_found: vec!["missing".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_soft_delete_many_reports_unknown_ids() {
+        let conn = setup();
+        let ids = mixed_batch(&conn);
+
+        assert_eq!(soft_delete_many(&conn, &ids).unwrap(), expected_batch());
+        for id in ["img-001", "img-002"] {
+            let image = get_image(&conn, id).unwrap().unwrap();
+            assert!(image.deleted);
+            assert!(image.deleted_at.is_some());
+        }
+    }
+
+    #[test]
+    fn test_update_rating_many_reports_unknown_ids() {
+        let conn = setup();
+        let ids = mixed_batch(&conn);
+        update_image_favorite(&conn, "img-002", true).unwrap();
+
+        assert_eq!(
+            update_rating_many(&conn, &ids, Some(3), 4).unwrap(),
+            expected_batch()
+        );
+        let first = get_image(&conn, "img-001").unwrap().unwrap();
+        assert_eq!(first.rating, Some(3));
+        assert!(!first.favorite);
+        // Below the threshold an existing favorite is kept
+        assert!(get_image(&conn, "img-002").unwrap().unwrap().favorite);
+
+        update_rating_many(&conn, &ids, Some(5), 4).unwrap();
+        assert!(get_image(&conn, "img-001").unwrap().unwrap().favorite);
+    }
+
+    #[test]
+    fn test_update_favorite_many_reports_unknown_ids() {
+        let conn = setup();
+        let ids = mixed_batch(&conn);
+
+        assert_eq!(
+            update_favorite_many(&conn, &ids, true).unwrap(),
+            expected_batch()
+        );
+        assert!(get_image(&conn, "img-001").unwrap().unwrap().favorite);
+        assert!(get_image(&conn, "img-002").unwrap().unwrap().favorite);
+    }
+
+    #[test]
+    fn test_reattribute_checkpoint_moves_all_images() {
+        let conn = setup();
+        for id in ["img-a", "img-b", "img-other"] {
+            insert_image(&conn, &make_test_image(id)).unwrap();
+        }
+        let old = make_test_image("x").checkpoint.unwrap();
+        conn.execute(
+            "INSERT INTO checkpoints (filename) VALUES (?1)",
+            params![old],
+        )
+        .unwrap();
+        db::checkpoints::set_checkpoint_sample_image(&conn, &old, "img-a").unwrap();
+        let comparison = crate::types::comparison::Comparison {
+            id: "cmp-1".to_string(),
+            image_a_id: "img-a".to_string(),
+            image_b_id: "img-b".to_string(),
+            variable_changed: "seed".to_string(),
+            note: None,
+            created_at: None,
+        };
+        db::comparisons::insert_comparison(&conn, &comparison).unwrap();
+
+        let ids = vec!["img-a".to_string(), "img-b".to_string()];
+        assert_eq!(
+            reattribute_checkpoint(&conn, &ids, "juggernaut_xl.safetensors").unwrap(),
+            2
+        );
+
+        for id in &ids {
+            let image = get_image(&conn, id).unwrap().unwrap();
+            assert_eq!(
+                image.checkpoint.as_deref(),
+                Some("juggernaut_xl.safetensors")
+            );
+        }
+        let other = get_image(&conn, "img-other").unwrap().unwrap();
+        assert_eq!(other.checkpoint, Some(old.clone()));
+
+        // Per-checkpoint views follow the images
+        assert!(
+            db::comparisons::list_comparisons_for_checkpoint(&conn, &old)
+                .unwrap()
+                .is_empty()
+        );
+        let moved =
+            db::comparisons::list_comparisons_for_checkpoint(&conn, "juggernaut_xl.safetensors")
+                .unwrap();
+        assert_eq!(moved.len(), 1);
+        let profile = db::checkpoints::get_checkpoint(&conn, &old)
+            .unwrap()
+            .unwrap();
+        assert!(profile.sample_image_id.is_none());
+    }
+
+    #[test]
+    fn test_reattribute_checkpoint_is_all_or_nothing() {
+        let conn = setup();
+        insert_image(&conn, &make_test_image("img-a")).unwrap();
+        let ids = vec!["img-a".to_string(), "missing".to_string()];
+
+        assert!(reattribute_checkpoint(&conn, &ids, "other.safetensors").is_err());
+        assert!(reattribute_checkpoint(&conn, &ids[..1], "  ").is_err());
+        let image = get_image(&conn, "img-a").unwrap().unwrap();
+        assert_eq!(
+            image.checkpoint.as_deref(),
+            Some("dreamshaper_8.safetensors")
+        );
+    }
+}
@@ -1,5 +1,7 @@
 use super::*;
 use crate::db;
+use crate::db::image_filter::list_images;
+use crate::types::gallery::{GalleryFilter, GallerySortField};
 
 fn setup() -> Connection {
     db::open_memory_database().unwrap()
@@ -21,6 +23,8 @@ pub fn make_test_image(id: &str) -> ImageEntry {
         sampler: Some("dpmpp_2m".to_string()),
         scheduler: Some("karras".to_string()),
         seed: Some(12345),
+        denoise: None,
+        settings_mismatch: None,
         pipeline_log: None,
         selected_concept: Some(2),
         auto_approved: false,
@@ -30,6 +34,12 @@ pub fn make_test_image(id: &str) -> ImageEntry {
         favorite: false,
         deleted: false,
         user_note: None,
+        compute_cost: None,
+        aesthetic_score: None,
+        pipeline_run_id: None,
+        parent_image_id: None,
+        deleted_at: None,
+        source: None,
         tags: None,
     }
 }
@@ -53,84 +63,6 @@ fn test_get_nonexistent() {
     assert!(get_image(&conn, "nope").unwrap().is_none());
 }
 
-#[test]
-fn test_list_default_filter() {
-    let conn = setup();
-    for i in 0..5 {
-        insert_image(&conn, &make_test_image(&format!("img-{:03}", i))).unwrap();
-    }
-    let images = list_images(&conn, &GalleryFilter::default()).unwrap();
-    assert_eq!(images.len(), 5);
-}
-
-#[test]
-fn test_list_with_checkpoint_filter() {
-    let conn = setup();
-    let mut img1 = make_test_image("img-001");
-    img1.checkpoint = Some("dreamshaper.safetensors".to_string());
-    let mut img2 = make_test_image("img-002");
-    img2.checkpoint = Some("deliberate.safetensors".to_string());
-    insert_image(&conn, &img1).unwrap();
-    insert_image(&conn, &img2).unwrap();
-
-    let filter = GalleryFilter {
-        checkpoint: Some("dreamshaper.safetensors".to_string()),
-        ..Default::default()
-    };
-    let images = list_images(&conn, &filter).unwrap();
-    assert_eq!(images.len(), 1);
-    assert_eq!(images[0].id, "img-001");
-}
-
-#[test]
-fn test_list_with_search() {
-    let conn = setup();
-    let mut img1 = make_test_image("img-001");
-    img1.positive_prompt = Some("beautiful sunset over ocean".to_string());
-    let mut img2 = make_test_image("img-002");
-    img2.positive_prompt = Some("dark forest at night".to_string());
-    insert_image(&conn, &img1).unwrap();
-    insert_image(&conn, &img2).unwrap();
-
-    let filter = GalleryFilter {
-        search: Some("sunset".to_string()),
-        ..Default::default()
-    };
-    let images = list_images(&conn, &filter).unwrap();
-    assert_eq!(images.len(), 1);
-    assert_eq!(images[0].id, "img-001");
-}
-
-#[test]
-fn test_pagination() {
-    let conn = setup();
-    for i in 0..10 {
-        insert_image(&conn, &make_test_image(&format!("img-{:03}", i))).unwrap();
-    }
-
-    let page1 = list_images(
-        &conn,
-        &GalleryFilter {
-            limit: Some(3),
-            ..Default::default()
-        },
-    )
-    .unwrap();
-    assert_eq!(page1.len(), 3);
-
-    let page2 = list_images(
-        &conn,
-        &GalleryFilter {
-            limit: Some(3),
-            offset: Some(3),
-            ..Default::default()
-        },
-    )
-    .unwrap();
-    assert_eq!(page2.len(), 3);
-    assert_ne!(page1[0].id, page2[0].id);
-}
-
 #[test]
 fn test_soft_delete_and_restore() {
     let conn = setup();
@@ -227,3 +159,208 @@ fn test_permanent_delete() {
     permanently_delete_image(&conn, "img-001").unwrap();
     assert!(get_image(&conn, "img-001").unwrap().is_none());
 }
+
+#[test]
+fn test_rating_at_threshold_sets_favorite() {
+    let conn = setup();
+    insert_image(&conn, &make_test_image("img-001")).unwrap();
+    insert_image(&conn, &make_test_image("img-002")).unwrap();
+
+    update_image_rating_with_auto_favorite(&conn, "img-001", Some(5), 4).unwrap();
+    update_image_rating_with_auto_favorite(&conn, "img-002", Some(4), 4).unwrap();
+
+    assert!(get_image(&conn, "img-001").unwrap().unwrap().favorite);
+    assert!(get_image(&conn, "img-002").unwrap().unwrap().favorite);
+}
+
+#[test]
+fn test_rating_below_threshold_keeps_favorite_unchanged() {
+    let conn = setup();
+    insert_image(&conn, &make_test_image("img-001")).unwrap();
+    insert_image(&conn, &make_test_image("img-002")).unwrap();
+    update_image_favorite(&conn, "img-002", true).unwrap();
+
+    update_image_rating_with_auto_favorite(&conn, "img-001", Some(3), 5).unwrap();
+    update_image_rating_with_auto_favorite(&conn, "img-002", Some(1), 5).unwrap();
+
+    let low = get_image(&conn, "img-001").unwrap().unwrap();
+    assert_eq!(low.rating, Some(3));
+    assert!(!low.favorite);
+    // Lower ratings never unfavorite
+    assert!(get_image(&conn, "img-002").unwrap().unwrap().favorite);
+}
+
+#[test]
+fn test_auto_favorite_disabled_at_zero() {
+    let conn = setup();
+    insert_image(&conn, &make_test_image("img-001")).unwrap();
+    update_image_rating_with_auto_favorite(&conn, "img-001", Some(5), 0).unwrap();
+    assert!(!get_image(&conn, "img-001").unwrap().unwrap().favorite);
+}
+
+#[test]
+fn test_purge_expired_trash_only_removes_old_trash() {
+    let conn = setup();
+    for id in ["old-trash", "new-trash", "old-kept"] {
+        insert_image(&conn, &make_test_image(id)).unwrap();
+    }
+    soft_delete_image(&conn, "old-trash").unwrap();
+    soft_delete_image(&conn, "new-trash").unwrap();
+    conn.execute(
+        "UPDATE images SET deleted_at = '2020-01-01 00:00:00' WHERE id = 'old-trash'",
+        [],
+    )
+    .unwrap();
+    conn.execute(
+        "UPDATE images SET created_at = '2020-01-01T00:00:00' WHERE id = 'old-kept'",
+        [],
+    )
+    .unwrap();
+
+    assert!(purge_expired_trash(&conn, 0).unwrap().is_empty());
+    assert_eq!(
+        purge_expired_trash(&conn, 30).unwrap(),
+        vec!["old-trash.png".to_string()]
+    );
+    assert!(get_image(&conn, "old-trash").unwrap().is_none());
+    assert!(get_image(&conn, "new-trash").unwrap().unwrap().deleted);
+    assert!(get_image(&conn, "old-kept").unwrap().is_some());
+}
+
+#[test]
+fn test_soft_delete_records_deleted_at_and_restore_clears_it() {
+    let conn = setup();
+    insert_image(&conn, &make_test_image("img-001")).unwrap();
+    assert!(get_image(&conn, "img-001")
+        .unwrap()
+        .unwrap()
+        .deleted_at
+        .is_none());
+
+    soft_delete_image(&conn, "img-001").unwrap();
+    let trashed = get_image(&conn, "img-001").unwrap().unwrap();
+    assert!(trashed.deleted);
+    let deleted_at = trashed.deleted_at.unwrap();
+    assert!(chrono::NaiveDateTime::parse_from_str(&deleted_at, "%Y-%m-%d %H:%M:%S").is_ok());
+
+    restore_image(&conn, "img-001").unwrap();
+    let restored = get_image(&conn, "img-001").unwrap().unwrap();
+    assert!(!restored.deleted);
+    assert!(restored.deleted_at.is_none());
+}
+
+#[test]
+fn test_trash_sorts_by_deleted_at() {
+    let conn = setup();
+    for (id, deleted_at) in [
+        ("img-001", "2026-03-01 09:00:00"),
+        ("img-002", "2026-03-03 09:00:00"),
+        ("img-003", "2026-03-02 09:00:00"),
+    ] {
+        insert_image(&conn, &make_test_image(id)).unwrap();
+        soft_delete_image(&conn, id).unwrap();
+        conn.execute(
+            "UPDATE images SET deleted_at = ?1 WHERE id = ?2",
+            params![deleted_at, id],
+        )
+        .unwrap();
+    }
+
+    let filter = GalleryFilter {
+        show_deleted: Some(true),
+        sort_by: Some(GallerySortField::DeletedAt),
+        ..Default::default()
+    };
+    let ids: Vec<String> = list_images(&conn, &filter)
+        .unwrap()
+        .into_iter()
+        .map(|img| img.id)
+        .collect();
+    assert_eq!(ids, vec!["img-002", "img-003", "img-001"]);
+}
+
+#[test]
+fn test_list_all_filenames_includes_deleted() {
+    let conn = setup();
+    insert_image(&conn, &make_test_image("img-001")).unwrap();
+    insert_image(&conn, &make_test_image("img-002")).unwrap();
+    soft_delete_image(&conn, "img-002").unwrap();
+
+    let mut filenames = list_all_filenames(&conn).unwrap();
+    filenames.sort();
+    assert_eq!(filenames, vec!["img-001.png", "img-002.png"]);
+}
+
+#[test]
+fn test_aesthetic_score_storage_and_sort() {
+    let conn = setup();
+    insert_image(&conn, &make_test_image("img-low")).unwrap();
+    insert_image(&conn, &make_test_image("img-high")).unwrap();
+    insert_image(&conn, &make_test_image("img-unscored")).unwrap();
+
+    set_aesthetic_score(&conn, "img-low", 3.5).unwrap();
+    set_aesthetic_score(&conn, "img-high", 8.0).unwrap();
+    assert!(set_aesthetic_score(&conn, "missing", 5.0).is_err());
+
+    assert_eq!(
+        get_image(&conn, "img-high")
+            .unwrap()
+            .unwrap()
+            .aesthetic_score,
+        Some(8.0)
+    );
+
+    let filter = GalleryFilter {
+        sort_by: Some(GallerySortField::AestheticScore),
+        ..Default::default()
+    };
+    let ids: Vec<String> = list_images(&conn, &filter)
+        .unwrap()
+        .into_iter()
+        .map(|i| i.id)
+        .collect();
+    assert_eq!(ids, vec!["img-high", "img-low", "img-unscored"]);
+}
+
+#[test]
+fn test_distinct_checkpoints_counts_live_images() {
+    let conn = setup();
+    for (id, checkpoint) in [
+        ("img-1", "dreamshaper_8.safetensors"),
+        ("img-2", "juggernaut_xl.safetensors"),
+        ("img-3", "juggernaut_xl.safetensors"),
+        ("img-4", "juggernaut_xl.safetensors"),
+        ("img-5", "dreamshaper_8.safetensors"),
+    ] {
+        let mut image = make_test_image(id);
+        image.checkpoint = Some(checkpoint.to_string());
+        insert_image(&conn, &image).unwrap();
+    }
+    soft_delete_image(&conn, "img-4").unwrap();
+
+    assert_eq!(
+        distinct_checkpoints(&conn).unwrap(),
+        vec![
+            ("dreamshaper_8.safetensors".to_string(), 2),
+            ("juggernaut_xl.safetensors".to_string(), 2),
+        ]
+    );
+    soft_delete_image(&conn, "img-1").unwrap();
+    assert_eq!(
+        distinct_checkpoints(&conn).unwrap()[0],
+        ("juggernaut_xl.safetensors".to_string(), 2)
+    );
+}
+
+#[test]
+fn test_find_image_id_by_filename() {
+    let conn = setup();
+    insert_image(&conn, &make_test_image("img-1")).unwrap();
+    assert_eq!(
+        find_image_id_by_filename(&conn, "img-1.png")
+            .unwrap()
+            .as_deref(),
+        Some("img-1")
+    );
+    assert_eq!(find_image_id_by_filename(&conn, "nope.png").unwrap(), None);
+}
@@ -0,0 +1,167 @@
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+
+use super::images::{get_image, row_to_image};
+use crate::types::gallery::{ImageEntry, ImageLineage};
+
+/// The images `id` was derived from through `parent_image_id`, nearest
+/// first. Stops at a parent that no longer exists, and at a cycle.
+pub fn image_ancestors(conn: &Connection, id: &str) -> Result<Vec<ImageEntry>> {
+    let mut seen = vec![id.to_string()];
+    let mut ancestors = Vec::new();
+    let mut next = get_image(conn, id)?.and_then(|image| image.parent_image_id);
+    while let Some(parent_id) = next {
+        if seen.contains(&parent_id) {
+            break;
+        }
+        let Some(parent) = get_image(conn, &parent_id)? else {
+            break;
+        };
+        seen.push(parent_id);
+        next = parent.parent_image_id.clone();
+        ancestors.push(parent);
+    }
+    Ok(ancestors)
+}
+
+/// Where image `id` came from and what was made from it: its ancestor
+/// chain nearest first (see [`image_ancestors`]) and its non-deleted
+/// immediate children, oldest first.
+pub fn get_lineage(conn: &Connection, id: &str) -> Result<ImageLineage> {
+    let ancestors = image_ancestors(conn, id)?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, filename, created_at, positive_prompt, negative_prompt,
+                    original_idea, checkpoint, width, height, steps, cfg_scale,
+                    sampler, scheduler, seed, pipeline_log, selected_concept,
+                    auto_approved, caption, caption_edited, rating, favorite,
+                    deleted, user_note, compute_cost, source, aesthetic_score,
+                    pipeline_run_id, denoise, settings_mismatch, parent_image_id, deleted_at
+             FROM images WHERE parent_image_id = ?1 AND deleted = 0
+             ORDER BY created_at, rowid",
+        )
+        .context("Failed to prepare child images query")?;
+    let rows = stmt
+        .query_map(params![id], row_to_image)
+        .context("Failed to execute child images query")?;
+    let mut children = Vec::new();
+    for row in rows {
+        children.push(row.context("Failed to read child image row")?);
+    }
+
+    Ok(ImageLineage {
+        ancestors,
+        children,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::db::images::tests::make_test_image;
+    use crate::db::images::{insert_image, soft_delete_image};
+
+    fn setup() -> Connection {
+        db::open_memory_database().unwrap()
+    }
+
+    #[test]
+    fn test_image_ancestors_nearest_first() {
+        let conn = setup();
+        for (id, parent) in [
+            ("img-a", None),
+            ("img-b", Some("img-a")),
+            ("img-c", Some("img-b")),
+        ] {
+            let mut image = make_test_image(id);
+            image.parent_image_id = parent.map(str::to_string);
+            insert_image(&conn, &image).unwrap();
+        }
+
+        let ids = |id: &str| -> Vec<String> {
+            image_ancestors(&conn, id)
+                .unwrap()
+                .into_iter()
+                .map(|img| img.id)
+                .collect()
+        };
+        assert_eq!(ids("img-c"), vec!["img-b", "img-a"]);
+        assert!(ids("img-a").is_empty());
+        assert!(ids("missing").is_empty());
+
+        conn.execute(
+            "UPDATE images SET parent_image_id = 'img-c' WHERE id = 'img-a'",
+            [],
+        )
+        .unwrap();
+        assert_eq!(ids("img-c"), vec!["img-b", "img-a"]);
+    }
+
+    #[test]
+    fn test_parent_link_is_a_foreign_key() {
+        let conn = setup();
+        insert_image(&conn, &make_test_image("img-a")).unwrap();
+        let mut child = make_test_image("img-b");
+        child.parent_image_id = Some("img-a".to_string());
+        insert_image(&conn, &child).unwrap();
+
+        let mut orphan = make_test_image("img-orphan");
+        orphan.parent_image_id = Some("gone".to_string());
+        assert!(insert_image(&conn, &orphan).is_err());
+
+        conn.execute("DELETE FROM images WHERE id = 'img-a'", [])
+            .unwrap();
+        let parent: Option<String> = conn
+            .query_row(
+                "SELECT parent_image_id FROM images WHERE id = 'img-b'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(parent, None);
+    }
+
+    #[test]
+    fn test_get_lineage_three_generations() {
+        let conn = setup();
+        for (id, created_at, parent) in [
+            ("gen-1", "2026-01-10T10:00:00", None),
+            ("gen-2", "2026-01-11T10:00:00", Some("gen-1")),
+            ("gen-3a", "2026-01-12T10:00:00", Some("gen-2")),
+            ("gen-3b", "2026-01-13T10:00:00", Some("gen-2")),
+        ] {
+            let mut image = make_test_image(id);
+            image.created_at = created_at.to_string();
+            image.parent_image_id = parent.map(str::to_string);
+            insert_image(&conn, &image).unwrap();
+        }
+        let ids =
+            |images: Vec<ImageEntry>| -> Vec<String> { images.into_iter().map(|i| i.id).collect() };
+
+        let lineage = get_lineage(&conn, "gen-2").unwrap();
+        assert_eq!(ids(lineage.ancestors), vec!["gen-1"]);
+        assert_eq!(ids(lineage.children), vec!["gen-3a", "gen-3b"]);
+
+        let lineage = get_lineage(&conn, "gen-3b").unwrap();
+        assert_eq!(ids(lineage.ancestors), vec!["gen-2", "gen-1"]);
+        assert!(lineage.children.is_empty());
+
+        let lineage = get_lineage(&conn, "gen-1").unwrap();
+        assert!(lineage.ancestors.is_empty());
+        assert_eq!(ids(lineage.children), vec!["gen-2"]);
+
+        // Deleted children drop out; a deleted parent still links the chain
+        soft_delete_image(&conn, "gen-3a").unwrap();
+        soft_delete_image(&conn, "gen-1").unwrap();
+        assert_eq!(
+            ids(get_lineage(&conn, "gen-2").unwrap().children),
+            vec!["gen-3b"]
+        );
+        assert_eq!(
+            ids(get_lineage(&conn, "gen-3b").unwrap().ancestors),
+            vec!["gen-2", "gen-1"]
+        );
+    }
+}
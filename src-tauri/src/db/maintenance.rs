@@ -0,0 +1,63 @@
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+
+use super::search;
+
+/// Repopulate the full-text index from `images`. Databases that had rows
+/// before the FTS triggers existed can drift from the index; a rebuild
+/// reindexes every row from scratch.
+/// Returns how many images are indexed afterwards.
+pub fn rebuild_fts(conn: &Connection) -> Result<u32> {
+    if !search::fts_enabled(conn) {
+        anyhow::bail!("Full-text search is not available in this SQLite build");
+    }
+    conn.execute_batch(search::FTS_REBUILD)
+        .context("Failed to rebuild full-text index")?;
+    conn.query_row("SELECT COUNT(*) FROM images", [], |row| row.get(0))
+        .context("Failed to count indexed images")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    fn hits(conn: &Connection, query: &str) -> i64 {
+        conn.query_row(
+            "SELECT COUNT(*) FROM images_fts WHERE images_fts MATCH ?1",
+            [query],
+            |row| row.get(0),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_rebuild_indexes_rows_inserted_without_triggers() {
+        let conn = db::open_memory_database().unwrap();
+        conn.execute(
+            "INSERT INTO images (id, filename, positive_prompt) VALUES ('a', 'a.png', 'misty lake')",
+            [],
+        )
+        .unwrap();
+        // An old database: rows written while the insert trigger was missing
+        conn.execute_batch("DROP TRIGGER images_fts_insert;")
+            .unwrap();
+        conn.execute(
+            "INSERT INTO images (id, filename, caption) VALUES ('b', 'b.png', 'a red fox')",
+            [],
+        )
+        .unwrap();
+        assert_eq!(hits(&conn, "fox"), 0);
+
+        assert_eq!(rebuild_fts(&conn).unwrap(), 2);
+        assert_eq!(hits(&conn, "fox"), 1);
+        assert_eq!(hits(&conn, "misty"), 1);
+    }
+
+    #[test]
+    fn test_rebuild_without_index_fails() {
+        let conn = db::open_memory_database().unwrap();
+        search::drop_fts(&conn);
+        assert!(rebuild_fts(&conn).is_err());
+    }
+}
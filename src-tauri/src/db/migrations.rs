@@ -1,11 +1,47 @@
 use anyhow::{Context, Result};
 use rusqlite::Connection;
 
+use super::schema::*;
+
+/// Every schema change, in order. Append new steps at the end; never edit
+/// or renumber one that has shipped.
+const MIGRATIONS: &[(u32, &str)] = &[
+    (1, SCHEMA_V1),
+    (2, MIGRATION_V2),
+    (3, MIGRATION_V3),
+    (4, MIGRATION_V4),
+    (5, MIGRATION_V5),
+    (6, MIGRATION_V6),
+    (7, MIGRATION_V7),
+    (8, MIGRATION_V8),
+    (9, MIGRATION_V9),
+    (10, MIGRATION_V10),
+    (11, MIGRATION_V11),
+    (12, MIGRATION_V12),
+    (13, MIGRATION_V13),
+    (14, MIGRATION_V14),
+    (15, MIGRATION_V15),
+    (16, MIGRATION_V16),
+    (17, MIGRATION_V17),
+    (18, MIGRATION_V18),
+    (19, MIGRATION_V19),
+    (20, MIGRATION_V20),
+    (21, MIGRATION_V21),
+];
+
 /// Current schema version
 #[allow(dead_code)]
-const CURRENT_VERSION: u32 = 2;
+const CURRENT_VERSION: u32 = MIGRATIONS[MIGRATIONS.len() - 1].0;
 
 pub fn run(conn: &Connection) -> Result<()> {
+    apply(conn, MIGRATIONS)?;
+    super::search::ensure_fts(conn)
+}
+
+/// Apply every step newer than the stored version. Each step and its
+/// version bump commit together, so a failed step leaves the database at
+/// the previous version, ready to retry.
+fn apply(conn: &Connection, migrations: &[(u32, &str)]) -> Result<()> {
     // Ensure the migrations tracking table exists
     conn.execute_batch(
         "CREATE TABLE IF NOT EXISTS schema_version (
@@ -16,17 +52,15 @@ pub fn run(conn: &Connection) -> Result<()> {
     .context("Failed to create schema_version table")?;
 
     let current = get_current_version(conn);
-
-    if current < 1 {
-        conn.execute_batch(SCHEMA_V1)
-            .context("Failed to apply migration v1")?;
-        set_version(conn, 1)?;
-    }
-
-    if current < 2 {
-        conn.execute_batch(MIGRATION_V2)
-            .context("Failed to apply migration v2")?;
-        set_version(conn, 2)?;
+    for &(version, sql) in migrations.iter().filter(|(v, _)| *v > current) {
+        let tx = conn
+            .unchecked_transaction()
+            .with_context(|| format!("Failed to start migration v{}", version))?;
+        tx.execute_batch(sql)
+            .with_context(|| format!("Failed to apply migration v{}", version))?;
+        set_version(&tx, version)?;
+        tx.commit()
+            .with_context(|| format!("Failed to commit migration v{}", version))?;
     }
 
     Ok(())
@@ -50,178 +84,6 @@ fn set_version(conn: &Connection, version: u32) -> Result<()> {
     Ok(())
 }
 
-const SCHEMA_V1: &str = r#"
--- ============================================
--- Core Gallery
--- ============================================
-
-CREATE TABLE IF NOT EXISTS images (
-    id              TEXT PRIMARY KEY,
-    filename        TEXT NOT NULL,
-    created_at      DATETIME DEFAULT CURRENT_TIMESTAMP,
-    positive_prompt TEXT,
-    negative_prompt TEXT,
-    original_idea   TEXT,
-    checkpoint      TEXT,
-    width           INTEGER,
-    height          INTEGER,
-    steps           INTEGER,
-    cfg_scale       REAL,
-    sampler         TEXT,
-    scheduler       TEXT,
-    seed            INTEGER,
-    pipeline_log    TEXT,
-    selected_concept INTEGER,
-    auto_approved   BOOLEAN DEFAULT FALSE,
-    caption         TEXT,
-    caption_edited  BOOLEAN DEFAULT FALSE,
-    rating          INTEGER,
-    favorite        BOOLEAN DEFAULT FALSE,
-    deleted         BOOLEAN DEFAULT FALSE,
-    user_note       TEXT
-);
-
--- ============================================
--- Tags (shared across images and seeds)
--- ============================================
-
-CREATE TABLE IF NOT EXISTS tags (
-    id    INTEGER PRIMARY KEY AUTOINCREMENT,
-    name  TEXT UNIQUE NOT NULL
-);
-
-CREATE TABLE IF NOT EXISTS image_tags (
-    image_id    TEXT REFERENCES images(id) ON DELETE CASCADE,
-    tag_id      INTEGER REFERENCES tags(id),
-    source      TEXT CHECK(source IN ('ai', 'user')),
-    confidence  REAL,
-    PRIMARY KEY (image_id, tag_id)
-);
-
--- ============================================
--- Seed Library
--- ============================================
-
-CREATE TABLE IF NOT EXISTS seeds (
-    id              INTEGER PRIMARY KEY AUTOINCREMENT,
-    seed_value      INTEGER NOT NULL,
-    comment         TEXT NOT NULL,
-    checkpoint      TEXT,
-    sample_image_id TEXT REFERENCES images(id),
-    created_at      DATETIME DEFAULT CURRENT_TIMESTAMP
-);
-
-CREATE TABLE IF NOT EXISTS seed_tags (
-    seed_id  INTEGER REFERENCES seeds(id) ON DELETE CASCADE,
-    tag_id   INTEGER REFERENCES tags(id),
-    PRIMARY KEY (seed_id, tag_id)
-);
-
-CREATE TABLE IF NOT EXISTS seed_checkpoint_notes (
-    seed_id         INTEGER REFERENCES seeds(id) ON DELETE CASCADE,
-    checkpoint      TEXT NOT NULL,
-    note            TEXT NOT NULL,
-    sample_image_id TEXT REFERENCES images(id),
-    PRIMARY KEY (seed_id, checkpoint)
-);
-
--- ============================================
--- Checkpoint Knowledge Database
--- ============================================
-
-CREATE TABLE IF NOT EXISTS checkpoints (
-    id                  INTEGER PRIMARY KEY AUTOINCREMENT,
-    filename            TEXT UNIQUE NOT NULL,
-    display_name        TEXT,
-    base_model          TEXT,
-    created_at          DATETIME DEFAULT CURRENT_TIMESTAMP,
-    strengths           TEXT,
-    weaknesses          TEXT,
-    preferred_cfg       REAL,
-    cfg_range_low       REAL,
-    cfg_range_high      REAL,
-    preferred_sampler   TEXT,
-    preferred_scheduler TEXT,
-    optimal_resolution  TEXT,
-    notes               TEXT
-);
-
-CREATE TABLE IF NOT EXISTS checkpoint_prompt_terms (
-    id              INTEGER PRIMARY KEY AUTOINCREMENT,
-    checkpoint_id   INTEGER REFERENCES checkpoints(id) ON DELETE CASCADE,
-    term            TEXT NOT NULL,
-    effect          TEXT NOT NULL,
-    strength        TEXT CHECK(strength IN ('strong', 'moderate', 'weak', 'broken')),
-    example_image_id TEXT REFERENCES images(id),
-    created_at      DATETIME DEFAULT CURRENT_TIMESTAMP
-);
-
-CREATE TABLE IF NOT EXISTS checkpoint_observations (
-    id              INTEGER PRIMARY KEY AUTOINCREMENT,
-    checkpoint_id   INTEGER REFERENCES checkpoints(id) ON DELETE CASCADE,
-    observation     TEXT NOT NULL,
-    source          TEXT CHECK(source IN ('user', 'ab_comparison', 'pipeline_note', 'auto_rating')),
-    comparison_id   TEXT,
-    created_at      DATETIME DEFAULT CURRENT_TIMESTAMP
-);
-
--- ============================================
--- A/B Comparisons
--- ============================================
-
-CREATE TABLE IF NOT EXISTS comparisons (
-    id              TEXT PRIMARY KEY,
-    image_a_id      TEXT REFERENCES images(id),
-    image_b_id      TEXT REFERENCES images(id),
-    variable_changed TEXT NOT NULL,
-    note            TEXT,
-    created_at      DATETIME DEFAULT CURRENT_TIMESTAMP
-);
-
--- ============================================
--- Smart Queue (persistent)
--- ============================================
-
-CREATE TABLE IF NOT EXISTS queue_jobs (
-    id              TEXT PRIMARY KEY,
-    priority        INTEGER DEFAULT 1,
-    status          TEXT CHECK(status IN ('pending', 'generating', 'completed', 'failed', 'cancelled')),
-    positive_prompt TEXT NOT NULL,
-    negative_prompt TEXT NOT NULL,
-    settings_json   TEXT NOT NULL,
-    pipeline_log    TEXT,
-    original_idea   TEXT,
-    linked_comparison_id TEXT,
-    created_at      DATETIME DEFAULT CURRENT_TIMESTAMP,
-    started_at      DATETIME,
-    completed_at    DATETIME,
-    result_image_id TEXT REFERENCES images(id)
-);
-
--- ============================================
--- Indexes
--- ============================================
-
-CREATE INDEX IF NOT EXISTS idx_images_checkpoint ON images(checkpoint);
-CREATE INDEX IF NOT EXISTS idx_images_seed ON images(seed);
-CREATE INDEX IF NOT EXISTS idx_images_created ON images(created_at);
-CREATE INDEX IF NOT EXISTS idx_images_rating ON images(rating);
-CREATE INDEX IF NOT EXISTS idx_images_deleted ON images(deleted);
-CREATE INDEX IF NOT EXISTS idx_images_favorite ON images(favorite);
-CREATE INDEX IF NOT EXISTS idx_images_created_deleted ON images(created_at, deleted);
-CREATE INDEX IF NOT EXISTS idx_image_tags_image_id ON image_tags(image_id);
-CREATE INDEX IF NOT EXISTS idx_image_tags_tag_id ON image_tags(tag_id);
-CREATE INDEX IF NOT EXISTS idx_checkpoint_terms_checkpoint ON checkpoint_prompt_terms(checkpoint_id);
-CREATE INDEX IF NOT EXISTS idx_queue_status ON queue_jobs(status, priority);
-CREATE INDEX IF NOT EXISTS idx_seeds_value ON seeds(seed_value);
-CREATE INDEX IF NOT EXISTS idx_seeds_checkpoint ON seeds(checkpoint);
-"#;
-
-const MIGRATION_V2: &str = r#"
-ALTER TABLE queue_jobs ADD COLUMN selected_concept INTEGER;
-ALTER TABLE queue_jobs ADD COLUMN auto_approved BOOLEAN DEFAULT FALSE;
-"#;
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -253,6 +115,99 @@ mod tests {
         assert_eq!(get_current_version(&conn), CURRENT_VERSION);
     }
 
+    fn applied_versions(conn: &Connection) -> Vec<u32> {
+        conn.prepare("SELECT version FROM schema_version ORDER BY version")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_migration_versions_are_ascending() {
+        assert!(MIGRATIONS.windows(2).all(|w| w[0].0 < w[1].0));
+        assert_eq!(MIGRATIONS[0].0, 1);
+    }
+
+    #[test]
+    fn test_bare_tables_get_every_migration_once() {
+        // A database from before schema_version existed: just the v1 tables
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+        conn.execute_batch(SCHEMA_V1).unwrap();
+
+        run(&conn).unwrap();
+        let all: Vec<u32> = MIGRATIONS.iter().map(|(v, _)| *v).collect();
+        assert_eq!(applied_versions(&conn), all);
+
+        // A second run has nothing left to apply (re-running an ALTER TABLE
+        // ADD COLUMN step would fail)
+        run(&conn).unwrap();
+        assert_eq!(applied_versions(&conn), all);
+    }
+
+    #[test]
+    fn test_only_pending_migrations_apply() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+        apply(&conn, &MIGRATIONS[..MIGRATIONS.len() - 1]).unwrap();
+        assert_eq!(get_current_version(&conn), CURRENT_VERSION - 1);
+
+        run(&conn).unwrap();
+        assert_eq!(get_current_version(&conn), CURRENT_VERSION);
+        assert_eq!(applied_versions(&conn).len(), MIGRATIONS.len());
+    }
+
+    #[test]
+    fn test_v21_keeps_live_parents_and_drops_dangling_ones() {
+        use crate::db::images::{insert_image, tests::make_test_image};
+
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+        apply(&conn, &MIGRATIONS[..20]).unwrap();
+        insert_image(&conn, &make_test_image("img-a")).unwrap();
+        for (id, parent) in [("img-b", "img-a"), ("img-orphan", "gone")] {
+            let mut image = make_test_image(id);
+            image.parent_image_id = Some(parent.to_string());
+            insert_image(&conn, &image).unwrap();
+        }
+
+        run(&conn).unwrap();
+        let parent = |id: &str| -> Option<String> {
+            conn.query_row(
+                "SELECT parent_image_id FROM images WHERE id = ?1",
+                [id],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+        assert_eq!(parent("img-b").as_deref(), Some("img-a"));
+        assert_eq!(parent("img-orphan"), None);
+    }
+
+    #[test]
+    fn test_failed_migration_rolls_back_with_its_version() {
+        let conn = Connection::open_in_memory().unwrap();
+        let steps: &[(u32, &str)] = &[
+            (1, "CREATE TABLE a (id INTEGER);"),
+            (
+                2,
+                "CREATE TABLE b (id INTEGER); INSERT INTO missing VALUES (1);",
+            ),
+        ];
+        assert!(apply(&conn, steps).is_err());
+        assert_eq!(applied_versions(&conn), vec![1]);
+        let b_exists: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE name = 'b'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(b_exists, 0, "the failed step's table is rolled back");
+    }
+
     #[test]
     fn test_all_tables_created() {
         let conn = Connection::open_in_memory().unwrap();
@@ -272,13 +227,17 @@ mod tests {
             "checkpoint_prompt_terms",
             "checkpoints",
             "comparisons",
+            "image_captions",
             "image_tags",
             "images",
+            "prompt_templates",
             "queue_jobs",
+            "saved_prompts",
             "schema_version",
             "seed_checkpoint_notes",
             "seed_tags",
             "seeds",
+            "tag_implications",
             "tags",
         ];
 
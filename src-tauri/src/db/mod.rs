@@ -1,10 +1,27 @@
+pub mod backup;
+pub mod backup_schedule;
+pub mod captions;
 pub mod checkpoints;
 pub mod comparisons;
+pub mod embeddings;
+pub mod image_filter;
 pub mod images;
+pub mod images_batch;
+pub mod lineage;
+pub mod maintenance;
 pub mod migrations;
+pub mod phash;
+pub mod prompt_templates;
 pub mod queue;
+pub mod recent_choices;
+pub mod saved_prompts;
+mod schema;
+pub mod search;
 pub mod seeds;
+pub mod semantic_search;
+pub mod tag_implications;
 pub mod tags;
+pub mod term_frequencies;
 
 use anyhow::{Context, Result};
 use rusqlite::Connection;
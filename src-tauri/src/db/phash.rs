@@ -0,0 +1,140 @@
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+
+use super::images::get_image;
+use crate::types::gallery::ImageEntry;
+
+/// Store the perceptual hash computed when the image was saved.
+pub fn set_phash(conn: &Connection, id: &str, hash: u64) -> Result<()> {
+    conn.execute(
+        "UPDATE images SET phash = ?1 WHERE id = ?2",
+        params![hash.to_be_bytes().to_vec(), id],
+    )
+    .context("Failed to store perceptual hash")?;
+    Ok(())
+}
+
+fn phash_from_blob(blob: &[u8]) -> Option<u64> {
+    blob.try_into().ok().map(u64::from_be_bytes)
+}
+
+/// Ids and hashes of every non-deleted image that has a perceptual hash.
+pub fn list_phashes(conn: &Connection) -> Result<Vec<(String, u64)>> {
+    let mut stmt = conn
+        .prepare("SELECT id, phash FROM images WHERE deleted = 0 AND phash IS NOT NULL")
+        .context("Failed to prepare phash query")?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, Vec<u8>>(1)?))
+        })
+        .context("Failed to execute phash query")?;
+
+    let mut hashes = Vec::new();
+    for row in rows {
+        let (id, blob) = row.context("Failed to read phash row")?;
+        if let Some(hash) = phash_from_blob(&blob) {
+            hashes.push((id, hash));
+        }
+    }
+    Ok(hashes)
+}
+
+/// Ids and filenames of non-deleted images saved before hashing existed.
+pub fn list_unhashed_images(conn: &Connection) -> Result<Vec<(String, String)>> {
+    let mut stmt = conn
+        .prepare("SELECT id, filename FROM images WHERE deleted = 0 AND phash IS NULL")
+        .context("Failed to prepare unhashed images query")?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .context("Failed to execute unhashed images query")?;
+
+    let mut images = Vec::new();
+    for row in rows {
+        images.push(row.context("Failed to read unhashed image row")?);
+    }
+    Ok(images)
+}
+
+/// Non-deleted images whose perceptual hash is within `max_distance` bits of
+/// image `id`'s, closest first, each with its distance. Fails if `id` is
+/// unknown or has no hash yet.
+pub fn find_similar(
+    conn: &Connection,
+    id: &str,
+    max_distance: u32,
+) -> Result<Vec<(ImageEntry, u32)>> {
+    let blob: Option<Vec<u8>> = conn
+        .query_row(
+            "SELECT phash FROM images WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+        )
+        .optional()
+        .context("Failed to look up image hash")?
+        .with_context(|| format!("Image {} not found", id))?;
+    let target = blob
+        .as_deref()
+        .and_then(phash_from_blob)
+        .with_context(|| format!("Image {} has no perceptual hash", id))?;
+
+    let mut matches: Vec<(String, u32)> = list_phashes(conn)?
+        .into_iter()
+        .filter(|(other, _)| other != id)
+        .map(|(other, hash)| (other, (target ^ hash).count_ones()))
+        .filter(|(_, distance)| *distance <= max_distance)
+        .collect();
+    matches.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+
+    let mut similar = Vec::new();
+    for (other, distance) in matches {
+        if let Some(image) = get_image(conn, &other)? {
+            similar.push((image, distance));
+        }
+    }
+    Ok(similar)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::db::images::tests::make_test_image;
+    use crate::db::images::{insert_image, soft_delete_image};
+
+    fn setup() -> Connection {
+        db::open_memory_database().unwrap()
+    }
+
+    #[test]
+    fn test_find_similar_within_distance() {
+        let conn = setup();
+        for id in ["img-001", "img-002", "img-003", "img-004", "img-005"] {
+            insert_image(&conn, &make_test_image(id)).unwrap();
+        }
+        set_phash(&conn, "img-001", 0xFFFF_0000_FFFF_0000).unwrap();
+        set_phash(&conn, "img-002", 0xFFFF_0000_FFFF_0000).unwrap();
+        set_phash(&conn, "img-003", 0xFFFF_0000_FFFF_0007).unwrap();
+        set_phash(&conn, "img-004", 0x0000_FFFF_0000_FFFF).unwrap();
+        set_phash(&conn, "img-005", 0xFFFF_0000_FFFF_0001).unwrap();
+        soft_delete_image(&conn, "img-005").unwrap();
+
+        let similar: Vec<(String, u32)> = find_similar(&conn, "img-001", 5)
+            .unwrap()
+            .into_iter()
+            .map(|(image, distance)| (image.id, distance))
+            .collect();
+        assert_eq!(
+            similar,
+            vec![("img-002".to_string(), 0), ("img-003".to_string(), 3)]
+        );
+
+        assert_eq!(list_unhashed_images(&conn).unwrap(), Vec::new());
+        insert_image(&conn, &make_test_image("img-006")).unwrap();
+        assert!(find_similar(&conn, "img-006", 5).is_err());
+        assert!(find_similar(&conn, "missing", 5).is_err());
+        assert_eq!(
+            list_unhashed_images(&conn).unwrap(),
+            vec![("img-006".to_string(), "img-006.png".to_string())]
+        );
+    }
+}
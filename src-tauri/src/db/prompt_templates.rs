@@ -0,0 +1,77 @@
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+use std::collections::HashMap;
+
+/// All stored system-prompt overrides, keyed by stage.
+pub fn load_prompt_templates(conn: &Connection) -> Result<HashMap<String, String>> {
+    let mut stmt = conn
+        .prepare("SELECT stage, template FROM prompt_templates")
+        .context("Failed to prepare prompt templates query")?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .context("Failed to execute prompt templates query")?;
+
+    let mut templates = HashMap::new();
+    for row in rows {
+        let (stage, template): (String, String) =
+            row.context("Failed to read prompt template row")?;
+        templates.insert(stage, template);
+    }
+    Ok(templates)
+}
+
+/// Store (or replace) the override for a stage.
+pub fn set_prompt_template(conn: &Connection, stage: &str, template: &str) -> Result<()> {
+    if template.trim().is_empty() {
+        anyhow::bail!("Prompt template cannot be empty");
+    }
+    conn.execute(
+        "INSERT INTO prompt_templates (stage, template, updated_at)
+         VALUES (?1, ?2, CURRENT_TIMESTAMP)
+         ON CONFLICT(stage) DO UPDATE SET
+            template = excluded.template, updated_at = excluded.updated_at",
+        params![stage, template],
+    )
+    .with_context(|| format!("Failed to save prompt template for {}", stage))?;
+    Ok(())
+}
+
+/// Drop a stage's override so the built-in prompt is used again.
+pub fn reset_prompt_template(conn: &Connection, stage: &str) -> Result<()> {
+    conn.execute(
+        "DELETE FROM prompt_templates WHERE stage = ?1",
+        params![stage],
+    )
+    .context("Failed to reset prompt template")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    #[test]
+    fn test_set_replace_and_reset() {
+        let conn = db::open_memory_database().unwrap();
+        set_prompt_template(&conn, "ideator", "First {num_concepts}").unwrap();
+        set_prompt_template(&conn, "ideator", "Second {num_concepts}").unwrap();
+        set_prompt_template(&conn, "judge", "Rank {count}").unwrap();
+
+        let templates = load_prompt_templates(&conn).unwrap();
+        assert_eq!(templates.len(), 2);
+        assert_eq!(templates["ideator"], "Second {num_concepts}");
+
+        reset_prompt_template(&conn, "ideator").unwrap();
+        let templates = load_prompt_templates(&conn).unwrap();
+        assert!(!templates.contains_key("ideator"));
+        assert!(templates.contains_key("judge"));
+    }
+
+    #[test]
+    fn test_rejects_blank_and_unknown_stage() {
+        let conn = db::open_memory_database().unwrap();
+        assert!(set_prompt_template(&conn, "ideator", "  ").is_err());
+        assert!(set_prompt_template(&conn, "upscaler", "x").is_err());
+    }
+}
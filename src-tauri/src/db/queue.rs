@@ -1,15 +1,56 @@
 use anyhow::{Context, Result};
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OptionalExtension};
 
 use crate::types::queue::{QueueJob, QueueJobStatus, QueuePriority};
 
 pub fn insert_job(conn: &Connection, job: &QueueJob) -> Result<()> {
+    insert_with_sort_order(conn, job, None)
+}
+
+/// Insert a job at `position` among the pending jobs of its priority
+/// (0 = next to run within that priority). Positions past the end append.
+pub fn insert_job_at(conn: &Connection, job: &QueueJob, position: usize) -> Result<()> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT sort_order FROM queue_jobs
+             WHERE status = 'pending' AND priority = ?1
+             ORDER BY sort_order ASC, created_at ASC",
+        )
+        .context("Failed to prepare sort order query")?;
+    let orders = stmt
+        .query_map(params![job.priority.as_i32()], |row| {
+            row.get::<_, Option<f64>>(0)
+        })
+        .context("Failed to execute sort order query")?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to read sort order")?;
+
+    let before = position
+        .checked_sub(1)
+        .and_then(|i| orders.get(i).copied().flatten());
+    let after = orders.get(position).copied().flatten();
+    let sort_order = match (before, after) {
+        (Some(b), Some(a)) => Some((a + b) / 2.0),
+        (None, Some(a)) => Some(a - 1.0),
+        _ => None, // append
+    };
+    insert_with_sort_order(conn, job, sort_order)
+}
+
+/// `sort_order` of `None` places the job after every existing one.
+fn insert_with_sort_order(
+    conn: &Connection,
+    job: &QueueJob,
+    sort_order: Option<f64>,
+) -> Result<()> {
     conn.execute(
         "INSERT INTO queue_jobs (
             id, priority, status, positive_prompt, negative_prompt,
             settings_json, pipeline_log, original_idea, selected_concept,
-            auto_approved, linked_comparison_id
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            auto_approved, linked_comparison_id, group_id, pipeline_run_id,
+            parent_image_id, sort_order
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14,
+            COALESCE(?15, (SELECT COALESCE(MAX(sort_order), 0) + 1 FROM queue_jobs)))",
         params![
             job.id,
             job.priority.as_i32(),
@@ -22,6 +63,10 @@ pub fn insert_job(conn: &Connection, job: &QueueJob) -> Result<()> {
             job.selected_concept,
             job.auto_approved,
             job.linked_comparison_id,
+            job.group_id,
+            job.pipeline_run_id,
+            job.parent_image_id,
+            sort_order,
         ],
     )
     .context("Failed to insert queue job")?;
@@ -34,7 +79,8 @@ pub fn get_job(conn: &Connection, id: &str) -> Result<Option<QueueJob>> {
             "SELECT id, priority, status, positive_prompt, negative_prompt,
                     settings_json, pipeline_log, original_idea, selected_concept,
                     auto_approved, linked_comparison_id,
-                    created_at, started_at, completed_at, result_image_id
+                    created_at, started_at, completed_at, result_image_id, group_id,
+                    pipeline_run_id, parent_image_id, retry_count
              FROM queue_jobs WHERE id = ?1",
         )
         .context("Failed to prepare get_job query")?;
@@ -55,7 +101,8 @@ pub fn list_jobs(conn: &Connection) -> Result<Vec<QueueJob>> {
             "SELECT id, priority, status, positive_prompt, negative_prompt,
                     settings_json, pipeline_log, original_idea, selected_concept,
                     auto_approved, linked_comparison_id,
-                    created_at, started_at, completed_at, result_image_id
+                    created_at, started_at, completed_at, result_image_id, group_id,
+                    pipeline_run_id, parent_image_id, retry_count
              FROM queue_jobs
              ORDER BY
                 CASE status
@@ -66,6 +113,7 @@ pub fn list_jobs(conn: &Connection) -> Result<Vec<QueueJob>> {
                     WHEN 'cancelled' THEN 4
                 END,
                 priority ASC,
+                sort_order ASC,
                 created_at ASC",
         )
         .context("Failed to prepare list_jobs query")?;
@@ -87,10 +135,11 @@ pub fn get_pending_jobs(conn: &Connection) -> Result<Vec<QueueJob>> {
             "SELECT id, priority, status, positive_prompt, negative_prompt,
                     settings_json, pipeline_log, original_idea, selected_concept,
                     auto_approved, linked_comparison_id,
-                    created_at, started_at, completed_at, result_image_id
+                    created_at, started_at, completed_at, result_image_id, group_id,
+                    pipeline_run_id, parent_image_id, retry_count
              FROM queue_jobs
              WHERE status = 'pending'
-             ORDER BY priority ASC, created_at ASC",
+             ORDER BY priority ASC, sort_order ASC, created_at ASC",
         )
         .context("Failed to prepare get_pending_jobs query")?;
 
@@ -126,6 +175,20 @@ pub fn update_job_status(conn: &Connection, id: &str, status: &QueueJobStatus) -
     Ok(())
 }
 
+/// Move a job from pending to generating. Returns false, changing nothing,
+/// when the job is no longer pending or no longer exists.
+pub fn claim_pending_job(conn: &Connection, id: &str) -> Result<bool> {
+    let now = chrono::Utc::now().to_rfc3339();
+    let claimed = conn
+        .execute(
+            "UPDATE queue_jobs SET status = 'generating', started_at = ?1
+             WHERE id = ?2 AND status = 'pending'",
+            params![now, id],
+        )
+        .context("Failed to claim queue job")?;
+    Ok(claimed > 0)
+}
+
 pub fn set_job_result_image(conn: &Connection, job_id: &str, image_id: &str) -> Result<()> {
     conn.execute(
         "UPDATE queue_jobs SET result_image_id = ?1 WHERE id = ?2",
@@ -182,6 +245,96 @@ pub fn is_job_cancelled(conn: &Connection, id: &str) -> Result<bool> {
     Ok(status == "cancelled")
 }
 
+/// Cancel every pending job in a group in one transaction. Returns how many
+/// were cancelled; jobs already generating are left alone.
+pub fn cancel_pending_in_group(conn: &Connection, group_id: &str) -> Result<u32> {
+    let tx = conn
+        .unchecked_transaction()
+        .context("Failed to start group cancel transaction")?;
+    let now = chrono::Utc::now().to_rfc3339();
+    let count = tx
+        .execute(
+            "UPDATE queue_jobs SET status = 'cancelled', completed_at = ?1
+             WHERE group_id = ?2 AND status = 'pending'",
+            params![now, group_id],
+        )
+        .context("Failed to cancel group jobs")?;
+    tx.commit().context("Failed to commit group cancel")?;
+    Ok(count as u32)
+}
+
+/// IDs of a group's jobs that are currently generating.
+pub fn generating_jobs_in_group(conn: &Connection, group_id: &str) -> Result<Vec<String>> {
+    let mut stmt = conn
+        .prepare("SELECT id FROM queue_jobs WHERE group_id = ?1 AND status = 'generating'")
+        .context("Failed to prepare generating group jobs query")?;
+    let rows = stmt
+        .query_map(params![group_id], |row| row.get(0))
+        .context("Failed to execute generating group jobs query")?;
+
+    let mut ids = Vec::new();
+    for row in rows {
+        ids.push(row.context("Failed to read job id")?);
+    }
+    Ok(ids)
+}
+
+/// Result image of the earliest-completed job in a group other than
+/// `exclude_job_id`, if any has finished yet.
+pub fn first_group_result(
+    conn: &Connection,
+    group_id: &str,
+    exclude_job_id: &str,
+) -> Result<Option<String>> {
+    conn.query_row(
+        "SELECT result_image_id FROM queue_jobs
+         WHERE group_id = ?1 AND id != ?2 AND status = 'completed'
+               AND result_image_id IS NOT NULL
+         ORDER BY completed_at ASC, id ASC LIMIT 1",
+        params![group_id, exclude_job_id],
+        |row| row.get(0),
+    )
+    .optional()
+    .context("Failed to query first group result")
+}
+
+/// After a failed attempt, put the job back in the queue if it has been
+/// retried fewer than `max_retries` times, bumping its `retry_count`, and
+/// return the new count. Once retries are spent the job is marked failed and
+/// `None` returned. A cancelled job is left cancelled and never retried, and
+/// a job that already has a `result_image_id` (a batch that partly saved) is
+/// failed rather than regenerated, so its saved images aren't duplicated.
+pub fn retry_or_fail_job(conn: &Connection, id: &str, max_retries: u32) -> Result<Option<u32>> {
+    let requeued = conn
+        .execute(
+            "UPDATE queue_jobs
+             SET status = 'pending', retry_count = retry_count + 1, started_at = NULL
+             WHERE id = ?1 AND status IN ('pending', 'generating') AND retry_count < ?2
+               AND result_image_id IS NULL",
+            params![id, max_retries],
+        )
+        .context("Failed to requeue job for retry")?;
+    if requeued > 0 {
+        let count = conn
+            .query_row(
+                "SELECT retry_count FROM queue_jobs WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .context("Failed to read job retry count")?;
+        return Ok(Some(count));
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    conn.execute(
+        "UPDATE queue_jobs SET status = 'failed', completed_at = ?1
+         WHERE id = ?2 AND status IN ('pending', 'generating')",
+        params![now, id],
+    )
+    .context("Failed to mark job failed")?;
+    Ok(None)
+}
+
 pub fn requeue_interrupted_jobs(conn: &Connection) -> Result<u32> {
     let count = conn
         .execute(
@@ -231,165 +384,13 @@ fn row_to_job(row: &rusqlite::Row) -> rusqlite::Result<QueueJob> {
         started_at: row.get(12)?,
         completed_at: row.get(13)?,
         result_image_id: row.get(14)?,
+        group_id: row.get(15)?,
+        pipeline_run_id: row.get(16)?,
+        parent_image_id: row.get(17)?,
+        retry_count: row.get(18)?,
     })
 }
 
 #[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::db;
-
-    fn setup() -> Connection {
-        db::open_memory_database().unwrap()
-    }
-
-    fn make_job(id: &str, priority: QueuePriority) -> QueueJob {
-        QueueJob {
-            id: id.to_string(),
-            priority,
-            status: QueueJobStatus::Pending,
-            positive_prompt: "a cat".to_string(),
-            negative_prompt: "lowres".to_string(),
-            settings_json: r#"{"steps":20}"#.to_string(),
-            pipeline_log: None,
-            original_idea: Some("cat".to_string()),
-            selected_concept: Some(1),
-            auto_approved: false,
-            linked_comparison_id: None,
-            created_at: None,
-            started_at: None,
-            completed_at: None,
-            result_image_id: None,
-        }
-    }
-
-    #[test]
-    fn test_insert_and_get() {
-        let conn = setup();
-        let job = make_job("job-1", QueuePriority::Normal);
-        insert_job(&conn, &job).unwrap();
-
-        let retrieved = get_job(&conn, "job-1").unwrap().unwrap();
-        assert_eq!(retrieved.positive_prompt, "a cat");
-        assert_eq!(retrieved.priority, QueuePriority::Normal);
-        assert_eq!(retrieved.status, QueueJobStatus::Pending);
-        assert_eq!(retrieved.selected_concept, Some(1));
-        assert!(!retrieved.auto_approved);
-    }
-
-    #[test]
-    fn test_pending_jobs_sorted_by_priority() {
-        let conn = setup();
-        insert_job(&conn, &make_job("low-1", QueuePriority::Low)).unwrap();
-        insert_job(&conn, &make_job("high-1", QueuePriority::High)).unwrap();
-        insert_job(&conn, &make_job("normal-1", QueuePriority::Normal)).unwrap();
-
-        let pending = get_pending_jobs(&conn).unwrap();
-        assert_eq!(pending.len(), 3);
-        assert_eq!(pending[0].id, "high-1");
-        assert_eq!(pending[1].id, "normal-1");
-        assert_eq!(pending[2].id, "low-1");
-    }
-
-    #[test]
-    fn test_update_status() {
-        let conn = setup();
-        insert_job(&conn, &make_job("job-1", QueuePriority::Normal)).unwrap();
-
-        update_job_status(&conn, "job-1", &QueueJobStatus::Generating).unwrap();
-        let job = get_job(&conn, "job-1").unwrap().unwrap();
-        assert_eq!(job.status, QueueJobStatus::Generating);
-        assert!(job.started_at.is_some());
-
-        update_job_status(&conn, "job-1", &QueueJobStatus::Completed).unwrap();
-        let job = get_job(&conn, "job-1").unwrap().unwrap();
-        assert_eq!(job.status, QueueJobStatus::Completed);
-        assert!(job.completed_at.is_some());
-    }
-
-    #[test]
-    fn test_cancel_pending_job() {
-        let conn = setup();
-        insert_job(&conn, &make_job("job-1", QueuePriority::Normal)).unwrap();
-        let prev = cancel_job(&conn, "job-1").unwrap();
-        assert_eq!(prev, "pending");
-
-        let job = get_job(&conn, "job-1").unwrap().unwrap();
-        assert_eq!(job.status, QueueJobStatus::Cancelled);
-    }
-
-    #[test]
-    fn test_cancel_generating_job() {
-        let conn = setup();
-        insert_job(&conn, &make_job("job-1", QueuePriority::Normal)).unwrap();
-        update_job_status(&conn, "job-1", &QueueJobStatus::Generating).unwrap();
-
-        let prev = cancel_job(&conn, "job-1").unwrap();
-        assert_eq!(prev, "generating");
-
-        let job = get_job(&conn, "job-1").unwrap().unwrap();
-        assert_eq!(job.status, QueueJobStatus::Cancelled);
-    }
-
-    #[test]
-    fn test_cancel_completed_fails() {
-        let conn = setup();
-        insert_job(&conn, &make_job("job-1", QueuePriority::Normal)).unwrap();
-        update_job_status(&conn, "job-1", &QueueJobStatus::Completed).unwrap();
-
-        let result = cancel_job(&conn, "job-1");
-        assert!(result.is_err());
-    }
-
-    #[test]
-    fn test_is_job_cancelled() {
-        let conn = setup();
-        insert_job(&conn, &make_job("job-1", QueuePriority::Normal)).unwrap();
-        assert!(!is_job_cancelled(&conn, "job-1").unwrap());
-
-        cancel_job(&conn, "job-1").unwrap();
-        assert!(is_job_cancelled(&conn, "job-1").unwrap());
-    }
-
-    #[test]
-    fn test_requeue_interrupted() {
-        let conn = setup();
-        insert_job(&conn, &make_job("job-1", QueuePriority::Normal)).unwrap();
-        update_job_status(&conn, "job-1", &QueueJobStatus::Generating).unwrap();
-
-        let count = requeue_interrupted_jobs(&conn).unwrap();
-        assert_eq!(count, 1);
-
-        let job = get_job(&conn, "job-1").unwrap().unwrap();
-        assert_eq!(job.status, QueueJobStatus::Pending);
-        // Requeued jobs retain their original priority
-        assert_eq!(job.priority, QueuePriority::Normal);
-    }
-
-    #[test]
-    fn test_update_priority() {
-        let conn = setup();
-        insert_job(&conn, &make_job("job-1", QueuePriority::Low)).unwrap();
-        update_job_priority(&conn, "job-1", &QueuePriority::High).unwrap();
-
-        let job = get_job(&conn, "job-1").unwrap().unwrap();
-        assert_eq!(job.priority, QueuePriority::High);
-    }
-
-    #[test]
-    fn test_set_result_image() {
-        let conn = setup();
-        // Insert a test image to satisfy foreign key
-        conn.execute(
-            "INSERT INTO images (id, filename) VALUES ('img-001', 'test.png')",
-            [],
-        )
-        .unwrap();
-
-        insert_job(&conn, &make_job("job-1", QueuePriority::Normal)).unwrap();
-        set_job_result_image(&conn, "job-1", "img-001").unwrap();
-
-        let job = get_job(&conn, "job-1").unwrap().unwrap();
-        assert_eq!(job.result_image_id.unwrap(), "img-001");
-    }
-}
+#[path = "queue_test.rs"]
+pub(crate) mod tests;
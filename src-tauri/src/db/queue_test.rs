@@ -0,0 +1,339 @@
+use super::*;
+use crate::db;
+
+fn setup() -> Connection {
+    db::open_memory_database().unwrap()
+}
+
+pub(crate) fn make_job(id: &str, priority: QueuePriority) -> QueueJob {
+    QueueJob {
+        id: id.to_string(),
+        priority,
+        status: QueueJobStatus::Pending,
+        positive_prompt: "a cat".to_string(),
+        negative_prompt: "lowres".to_string(),
+        settings_json: r#"{"steps":20}"#.to_string(),
+        pipeline_log: None,
+        original_idea: Some("cat".to_string()),
+        selected_concept: Some(1),
+        auto_approved: false,
+        linked_comparison_id: None,
+        group_id: None,
+        pipeline_run_id: None,
+        parent_image_id: None,
+        retry_count: 0,
+        created_at: None,
+        started_at: None,
+        completed_at: None,
+        result_image_id: None,
+    }
+}
+
+#[test]
+fn test_insert_and_get() {
+    let conn = setup();
+    let job = make_job("job-1", QueuePriority::Normal);
+    insert_job(&conn, &job).unwrap();
+
+    let retrieved = get_job(&conn, "job-1").unwrap().unwrap();
+    assert_eq!(retrieved.positive_prompt, "a cat");
+    assert_eq!(retrieved.priority, QueuePriority::Normal);
+    assert_eq!(retrieved.status, QueueJobStatus::Pending);
+    assert_eq!(retrieved.selected_concept, Some(1));
+    assert!(!retrieved.auto_approved);
+}
+
+#[test]
+fn test_pending_jobs_sorted_by_priority() {
+    let conn = setup();
+    insert_job(&conn, &make_job("low-1", QueuePriority::Low)).unwrap();
+    insert_job(&conn, &make_job("high-1", QueuePriority::High)).unwrap();
+    insert_job(&conn, &make_job("normal-1", QueuePriority::Normal)).unwrap();
+
+    let pending = get_pending_jobs(&conn).unwrap();
+    assert_eq!(pending.len(), 3);
+    assert_eq!(pending[0].id, "high-1");
+    assert_eq!(pending[1].id, "normal-1");
+    assert_eq!(pending[2].id, "low-1");
+}
+
+#[test]
+fn test_update_status() {
+    let conn = setup();
+    insert_job(&conn, &make_job("job-1", QueuePriority::Normal)).unwrap();
+
+    update_job_status(&conn, "job-1", &QueueJobStatus::Generating).unwrap();
+    let job = get_job(&conn, "job-1").unwrap().unwrap();
+    assert_eq!(job.status, QueueJobStatus::Generating);
+    assert!(job.started_at.is_some());
+
+    update_job_status(&conn, "job-1", &QueueJobStatus::Completed).unwrap();
+    let job = get_job(&conn, "job-1").unwrap().unwrap();
+    assert_eq!(job.status, QueueJobStatus::Completed);
+    assert!(job.completed_at.is_some());
+}
+
+#[test]
+fn test_cancel_pending_job() {
+    let conn = setup();
+    insert_job(&conn, &make_job("job-1", QueuePriority::Normal)).unwrap();
+    let prev = cancel_job(&conn, "job-1").unwrap();
+    assert_eq!(prev, "pending");
+
+    let job = get_job(&conn, "job-1").unwrap().unwrap();
+    assert_eq!(job.status, QueueJobStatus::Cancelled);
+}
+
+#[test]
+fn test_cancel_generating_job() {
+    let conn = setup();
+    insert_job(&conn, &make_job("job-1", QueuePriority::Normal)).unwrap();
+    update_job_status(&conn, "job-1", &QueueJobStatus::Generating).unwrap();
+
+    let prev = cancel_job(&conn, "job-1").unwrap();
+    assert_eq!(prev, "generating");
+
+    let job = get_job(&conn, "job-1").unwrap().unwrap();
+    assert_eq!(job.status, QueueJobStatus::Cancelled);
+}
+
+#[test]
+fn test_cancel_completed_fails() {
+    let conn = setup();
+    insert_job(&conn, &make_job("job-1", QueuePriority::Normal)).unwrap();
+    update_job_status(&conn, "job-1", &QueueJobStatus::Completed).unwrap();
+
+    let result = cancel_job(&conn, "job-1");
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_is_job_cancelled() {
+    let conn = setup();
+    insert_job(&conn, &make_job("job-1", QueuePriority::Normal)).unwrap();
+    assert!(!is_job_cancelled(&conn, "job-1").unwrap());
+
+    cancel_job(&conn, "job-1").unwrap();
+    assert!(is_job_cancelled(&conn, "job-1").unwrap());
+}
+
+#[test]
+fn test_requeue_interrupted() {
+    let conn = setup();
+    insert_job(&conn, &make_job("job-1", QueuePriority::Normal)).unwrap();
+    update_job_status(&conn, "job-1", &QueueJobStatus::Generating).unwrap();
+
+    let count = requeue_interrupted_jobs(&conn).unwrap();
+    assert_eq!(count, 1);
+
+    let job = get_job(&conn, "job-1").unwrap().unwrap();
+    assert_eq!(job.status, QueueJobStatus::Pending);
+    // Requeued jobs retain their original priority
+    assert_eq!(job.priority, QueuePriority::Normal);
+}
+
+#[test]
+fn test_update_priority() {
+    let conn = setup();
+    insert_job(&conn, &make_job("job-1", QueuePriority::Low)).unwrap();
+    update_job_priority(&conn, "job-1", &QueuePriority::High).unwrap();
+
+    let job = get_job(&conn, "job-1").unwrap().unwrap();
+    assert_eq!(job.priority, QueuePriority::High);
+}
+
+#[test]
+fn test_set_result_image() {
+    let conn = setup();
+    // Insert a test image to satisfy foreign key
+    conn.execute(
+        "INSERT INTO images (id, filename) VALUES ('img-001', 'test.png')",
+        [],
+    )
+    .unwrap();
+
+    insert_job(&conn, &make_job("job-1", QueuePriority::Normal)).unwrap();
+    set_job_result_image(&conn, "job-1", "img-001").unwrap();
+
+    let job = get_job(&conn, "job-1").unwrap().unwrap();
+    assert_eq!(job.result_image_id.unwrap(), "img-001");
+}
+
+#[test]
+fn test_cancel_pending_in_group_leaves_other_groups() {
+    let conn = setup();
+    for (id, group) in [
+        ("sweep-a-1", "sweep-a"),
+        ("sweep-a-2", "sweep-a"),
+        ("sweep-a-3", "sweep-a"),
+        ("sweep-b-1", "sweep-b"),
+    ] {
+        let mut job = make_job(id, QueuePriority::Normal);
+        job.group_id = Some(group.to_string());
+        insert_job(&conn, &job).unwrap();
+    }
+    insert_job(&conn, &make_job("loose", QueuePriority::Normal)).unwrap();
+    update_job_status(&conn, "sweep-a-1", &QueueJobStatus::Generating).unwrap();
+
+    assert_eq!(cancel_pending_in_group(&conn, "sweep-a").unwrap(), 2);
+
+    let status = |id: &str| get_job(&conn, id).unwrap().unwrap().status;
+    assert_eq!(status("sweep-a-1"), QueueJobStatus::Generating);
+    assert_eq!(status("sweep-a-2"), QueueJobStatus::Cancelled);
+    assert_eq!(status("sweep-a-3"), QueueJobStatus::Cancelled);
+    assert_eq!(status("sweep-b-1"), QueueJobStatus::Pending);
+    assert_eq!(status("loose"), QueueJobStatus::Pending);
+    assert_eq!(
+        generating_jobs_in_group(&conn, "sweep-a").unwrap(),
+        vec!["sweep-a-1"]
+    );
+}
+
+#[test]
+fn test_first_group_result() {
+    let conn = setup();
+    for id in ["job-1", "job-2", "job-3"] {
+        let mut job = make_job(id, QueuePriority::Normal);
+        job.group_id = Some("sweep".to_string());
+        insert_job(&conn, &job).unwrap();
+    }
+    assert_eq!(first_group_result(&conn, "sweep", "job-2").unwrap(), None);
+
+    for (job_id, image_id) in [("job-1", "img-1"), ("job-2", "img-2")] {
+        crate::db::images::insert_image(
+            &conn,
+            &crate::db::images::tests::make_test_image(image_id),
+        )
+        .unwrap();
+        update_job_status(&conn, job_id, &QueueJobStatus::Completed).unwrap();
+        set_job_result_image(&conn, job_id, image_id).unwrap();
+    }
+
+    assert_eq!(
+        first_group_result(&conn, "sweep", "job-2")
+            .unwrap()
+            .as_deref(),
+        Some("img-1")
+    );
+    assert_eq!(
+        first_group_result(&conn, "sweep", "job-1")
+            .unwrap()
+            .as_deref(),
+        Some("img-2")
+    );
+}
+
+#[test]
+fn test_insert_job_at_position_within_priority() {
+    let conn = setup();
+    for id in ["n-1", "n-2", "n-3"] {
+        insert_job(&conn, &make_job(id, QueuePriority::Normal)).unwrap();
+    }
+    insert_job(&conn, &make_job("h-1", QueuePriority::High)).unwrap();
+
+    insert_job_at(&conn, &make_job("n-front", QueuePriority::Normal), 0).unwrap();
+    insert_job_at(&conn, &make_job("n-mid", QueuePriority::Normal), 2).unwrap();
+    insert_job_at(&conn, &make_job("n-end", QueuePriority::Normal), 99).unwrap();
+    insert_job_at(&conn, &make_job("h-front", QueuePriority::High), 0).unwrap();
+
+    let ids: Vec<String> = get_pending_jobs(&conn)
+        .unwrap()
+        .into_iter()
+        .map(|j| j.id)
+        .collect();
+    assert_eq!(
+        ids,
+        vec!["h-front", "h-1", "n-front", "n-1", "n-mid", "n-2", "n-3", "n-end"]
+    );
+}
+
+#[test]
+fn test_retry_or_fail_requeues_and_increments() {
+    let conn = setup();
+    insert_job(&conn, &make_job("job-1", QueuePriority::Normal)).unwrap();
+    update_job_status(&conn, "job-1", &QueueJobStatus::Generating).unwrap();
+
+    assert_eq!(retry_or_fail_job(&conn, "job-1", 2).unwrap(), Some(1));
+    let job = get_job(&conn, "job-1").unwrap().unwrap();
+    assert_eq!(job.status, QueueJobStatus::Pending);
+    assert_eq!(job.retry_count, 1);
+    assert!(job.started_at.is_none());
+
+    update_job_status(&conn, "job-1", &QueueJobStatus::Generating).unwrap();
+    assert_eq!(retry_or_fail_job(&conn, "job-1", 2).unwrap(), Some(2));
+    assert_eq!(get_job(&conn, "job-1").unwrap().unwrap().retry_count, 2);
+}
+
+#[test]
+fn test_retry_or_fail_marks_failed_when_exhausted() {
+    let conn = setup();
+    insert_job(&conn, &make_job("job-1", QueuePriority::Normal)).unwrap();
+    update_job_status(&conn, "job-1", &QueueJobStatus::Generating).unwrap();
+    assert_eq!(retry_or_fail_job(&conn, "job-1", 1).unwrap(), Some(1));
+
+    update_job_status(&conn, "job-1", &QueueJobStatus::Generating).unwrap();
+    assert_eq!(retry_or_fail_job(&conn, "job-1", 1).unwrap(), None);
+    let job = get_job(&conn, "job-1").unwrap().unwrap();
+    assert_eq!(job.status, QueueJobStatus::Failed);
+    assert_eq!(job.retry_count, 1);
+    assert!(job.completed_at.is_some());
+
+    // No retries configured: fails straight away
+    insert_job(&conn, &make_job("job-2", QueuePriority::Normal)).unwrap();
+    assert_eq!(retry_or_fail_job(&conn, "job-2", 0).unwrap(), None);
+    assert_eq!(
+        get_job(&conn, "job-2").unwrap().unwrap().status,
+        QueueJobStatus::Failed
+    );
+}
+
+#[test]
+fn test_retry_or_fail_leaves_cancelled_jobs_alone() {
+    let conn = setup();
+    insert_job(&conn, &make_job("job-1", QueuePriority::Normal)).unwrap();
+    update_job_status(&conn, "job-1", &QueueJobStatus::Generating).unwrap();
+    cancel_job(&conn, "job-1").unwrap();
+
+    assert_eq!(retry_or_fail_job(&conn, "job-1", 3).unwrap(), None);
+    let job = get_job(&conn, "job-1").unwrap().unwrap();
+    assert_eq!(job.status, QueueJobStatus::Cancelled);
+    assert_eq!(job.retry_count, 0);
+}
+
+#[test]
+fn test_retry_or_fail_does_not_requeue_partially_saved_batch() {
+    let conn = setup();
+    crate::db::images::insert_image(&conn, &crate::db::images::tests::make_test_image("img-1"))
+        .unwrap();
+    insert_job(&conn, &make_job("job-1", QueuePriority::Normal)).unwrap();
+    update_job_status(&conn, "job-1", &QueueJobStatus::Generating).unwrap();
+    set_job_result_image(&conn, "job-1", "img-1").unwrap();
+
+    assert_eq!(retry_or_fail_job(&conn, "job-1", 3).unwrap(), None);
+    let job = get_job(&conn, "job-1").unwrap().unwrap();
+    assert_eq!(job.status, QueueJobStatus::Failed);
+    assert_eq!(job.retry_count, 0);
+    assert_eq!(job.result_image_id.as_deref(), Some("img-1"));
+}
+
+#[test]
+fn test_claim_pending_job_only_claims_pending() {
+    let conn = setup();
+    insert_job(&conn, &make_job("job-1", QueuePriority::Normal)).unwrap();
+    insert_job(&conn, &make_job("job-2", QueuePriority::Normal)).unwrap();
+    cancel_job(&conn, "job-2").unwrap();
+
+    assert!(claim_pending_job(&conn, "job-1").unwrap());
+    let job = get_job(&conn, "job-1").unwrap().unwrap();
+    assert_eq!(job.status, QueueJobStatus::Generating);
+    assert!(job.started_at.is_some());
+    // Already generating
+    assert!(!claim_pending_job(&conn, "job-1").unwrap());
+
+    assert!(!claim_pending_job(&conn, "job-2").unwrap());
+    assert_eq!(
+        get_job(&conn, "job-2").unwrap().unwrap().status,
+        QueueJobStatus::Cancelled
+    );
+    assert!(!claim_pending_job(&conn, "no-such-job").unwrap());
+}
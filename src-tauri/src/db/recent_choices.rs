@@ -0,0 +1,145 @@
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+
+use crate::types::gallery::{Dimensions, RecentChoices};
+
+/// How many values of each kind `recent_choices` returns.
+const RECENT_CHOICE_LIMIT: u32 = 5;
+
+/// The distinct checkpoints, samplers, schedulers and dimensions of the most
+/// recent non-deleted images, newest first, for quick-picks on the generate
+/// form.
+pub fn recent_choices(conn: &Connection) -> Result<RecentChoices> {
+    let dimensions = {
+        let mut stmt = conn
+            .prepare(
+                "SELECT width, height FROM images
+                 WHERE deleted = 0 AND width IS NOT NULL AND height IS NOT NULL
+                 GROUP BY width, height
+                 ORDER BY MAX(created_at) DESC, MAX(rowid) DESC
+                 LIMIT ?1",
+            )
+            .context("Failed to prepare recent dimensions query")?;
+        let rows = stmt
+            .query_map([RECENT_CHOICE_LIMIT], |row| {
+                Ok(Dimensions {
+                    width: row.get(0)?,
+                    height: row.get(1)?,
+                })
+            })
+            .context("Failed to execute recent dimensions query")?;
+        let mut dimensions = Vec::new();
+        for row in rows {
+            dimensions.push(row.context("Failed to read dimensions row")?);
+        }
+        dimensions
+    };
+
+    Ok(RecentChoices {
+        checkpoints: recent_distinct(conn, "checkpoint")?,
+        samplers: recent_distinct(conn, "sampler")?,
+        schedulers: recent_distinct(conn, "scheduler")?,
+        dimensions,
+    })
+}
+
+/// Distinct non-empty values of a text column, most recently used first.
+/// `column` is always one of the fixed names above, never user input.
+fn recent_distinct(conn: &Connection, column: &str) -> Result<Vec<String>> {
+    let sql = format!(
+        "SELECT {col} FROM images
+         WHERE deleted = 0 AND {col} IS NOT NULL AND {col} != ''
+         GROUP BY {col}
+         ORDER BY MAX(created_at) DESC, MAX(rowid) DESC
+         LIMIT ?1",
+        col = column
+    );
+    let mut stmt = conn
+        .prepare(&sql)
+        .with_context(|| format!("Failed to prepare recent {} query", column))?;
+    let rows = stmt
+        .query_map([RECENT_CHOICE_LIMIT], |row| row.get(0))
+        .with_context(|| format!("Failed to execute recent {} query", column))?;
+
+    let mut values = Vec::new();
+    for row in rows {
+        values.push(row.with_context(|| format!("Failed to read recent {} row", column))?);
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::db::images::tests::make_test_image;
+    use crate::db::images::{insert_image, soft_delete_image};
+
+    fn setup() -> Connection {
+        db::open_memory_database().unwrap()
+    }
+
+    #[test]
+    fn test_recent_choices_newest_first() {
+        let conn = setup();
+        assert_eq!(recent_choices(&conn).unwrap(), RecentChoices::default());
+
+        for (id, created_at, checkpoint, sampler, size) in [
+            (
+                "img-1",
+                "2026-01-10T10:00:00",
+                "a.safetensors",
+                "euler",
+                512,
+            ),
+            (
+                "img-2",
+                "2026-01-11T10:00:00",
+                "b.safetensors",
+                "dpmpp_2m",
+                768,
+            ),
+            (
+                "img-3",
+                "2026-01-12T10:00:00",
+                "a.safetensors",
+                "euler",
+                1024,
+            ),
+            ("img-4", "2026-01-13T10:00:00", "c.safetensors", "ddim", 640),
+        ] {
+            let mut image = make_test_image(id);
+            image.created_at = created_at.to_string();
+            image.checkpoint = Some(checkpoint.to_string());
+            image.sampler = Some(sampler.to_string());
+            image.scheduler = Some("karras".to_string());
+            image.width = Some(size);
+            image.height = Some(size);
+            insert_image(&conn, &image).unwrap();
+        }
+        // The newest image is deleted, so its settings don't count
+        soft_delete_image(&conn, "img-4").unwrap();
+
+        let recent = recent_choices(&conn).unwrap();
+        assert_eq!(recent.checkpoints, vec!["a.safetensors", "b.safetensors"]);
+        assert_eq!(recent.samplers, vec!["euler", "dpmpp_2m"]);
+        assert_eq!(recent.schedulers, vec!["karras"]);
+        assert_eq!(
+            recent.dimensions,
+            vec![
+                Dimensions {
+                    width: 1024,
+                    height: 1024
+                },
+                Dimensions {
+                    width: 768,
+                    height: 768
+                },
+                Dimensions {
+                    width: 512,
+                    height: 512
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,101 @@
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+
+use crate::types::pipeline::{PromptPair, SavedPrompt};
+
+/// Record a hand edit of a pipeline's final prompts. Returns the new row id.
+pub fn insert_saved_prompt(
+    conn: &Connection,
+    pipeline_run_id: Option<&str>,
+    original: &PromptPair,
+    edited: &PromptPair,
+) -> Result<i64> {
+    conn.execute(
+        "INSERT INTO saved_prompts
+            (pipeline_run_id, original_positive, original_negative,
+             positive_prompt, negative_prompt)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            pipeline_run_id,
+            original.positive,
+            original.negative,
+            edited.positive,
+            edited.negative
+        ],
+    )
+    .context("Failed to insert saved prompt")?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Saved prompts, newest first.
+pub fn list_saved_prompts(conn: &Connection) -> Result<Vec<SavedPrompt>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, pipeline_run_id, original_positive, original_negative,
+                    positive_prompt, negative_prompt, created_at
+             FROM saved_prompts
+             ORDER BY created_at DESC, id DESC",
+        )
+        .context("Failed to prepare list_saved_prompts query")?;
+
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(SavedPrompt {
+                id: row.get(0)?,
+                pipeline_run_id: row.get(1)?,
+                original: PromptPair {
+                    positive: row.get(2)?,
+                    negative: row.get(3)?,
+                },
+                edited: PromptPair {
+                    positive: row.get(4)?,
+                    negative: row.get(5)?,
+                },
+                created_at: row.get(6)?,
+            })
+        })
+        .context("Failed to execute list_saved_prompts query")?;
+
+    let mut prompts = Vec::new();
+    for row in rows {
+        prompts.push(row.context("Failed to read saved prompt row")?);
+    }
+    Ok(prompts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    fn pair(positive: &str, negative: &str) -> PromptPair {
+        PromptPair {
+            positive: positive.to_string(),
+            negative: negative.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_insert_and_list_saved_prompts() {
+        let conn = db::open_memory_database().unwrap();
+        let first = insert_saved_prompt(
+            &conn,
+            Some("run-001"),
+            &pair("cat", "blurry"),
+            &pair("cat, gold crown", "blurry"),
+        )
+        .unwrap();
+        let second =
+            insert_saved_prompt(&conn, None, &pair("dog", ""), &pair("corgi", "")).unwrap();
+
+        let saved = list_saved_prompts(&conn).unwrap();
+        assert_eq!(
+            saved.iter().map(|p| p.id).collect::<Vec<_>>(),
+            vec![second, first]
+        );
+        assert_eq!(saved[1].pipeline_run_id.as_deref(), Some("run-001"));
+        assert_eq!(saved[1].original.positive, "cat");
+        assert_eq!(saved[1].edited.positive, "cat, gold crown");
+        assert!(saved[0].pipeline_run_id.is_none());
+    }
+}
@@ -0,0 +1,347 @@
+//! SQL for each schema version, applied in order by `migrations::run`.
+
+pub(super) const SCHEMA_V1: &str = r#"
+-- ============================================
+-- Core Gallery
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS images (
+    id              TEXT PRIMARY KEY,
+    filename        TEXT NOT NULL,
+    created_at      DATETIME DEFAULT CURRENT_TIMESTAMP,
+    positive_prompt TEXT,
+    negative_prompt TEXT,
+    original_idea   TEXT,
+    checkpoint      TEXT,
+    width           INTEGER,
+    height          INTEGER,
+    steps           INTEGER,
+    cfg_scale       REAL,
+    sampler         TEXT,
+    scheduler       TEXT,
+    seed            INTEGER,
+    pipeline_log    TEXT,
+    selected_concept INTEGER,
+    auto_approved   BOOLEAN DEFAULT FALSE,
+    caption         TEXT,
+    caption_edited  BOOLEAN DEFAULT FALSE,
+    rating          INTEGER,
+    favorite        BOOLEAN DEFAULT FALSE,
+    deleted         BOOLEAN DEFAULT FALSE,
+    user_note       TEXT
+);
+
+-- ============================================
+-- Tags (shared across images and seeds)
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS tags (
+    id    INTEGER PRIMARY KEY AUTOINCREMENT,
+    name  TEXT UNIQUE NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS image_tags (
+    image_id    TEXT REFERENCES images(id) ON DELETE CASCADE,
+    tag_id      INTEGER REFERENCES tags(id),
+    source      TEXT CHECK(source IN ('ai', 'user')),
+    confidence  REAL,
+    PRIMARY KEY (image_id, tag_id)
+);
+
+-- ============================================
+-- Seed Library
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS seeds (
+    id              INTEGER PRIMARY KEY AUTOINCREMENT,
+    seed_value      INTEGER NOT NULL,
+    comment         TEXT NOT NULL,
+    checkpoint      TEXT,
+    sample_image_id TEXT REFERENCES images(id),
+    created_at      DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS seed_tags (
+    seed_id  INTEGER REFERENCES seeds(id) ON DELETE CASCADE,
+    tag_id   INTEGER REFERENCES tags(id),
+    PRIMARY KEY (seed_id, tag_id)
+);
+
+CREATE TABLE IF NOT EXISTS seed_checkpoint_notes (
+    seed_id         INTEGER REFERENCES seeds(id) ON DELETE CASCADE,
+    checkpoint      TEXT NOT NULL,
+    note            TEXT NOT NULL,
+    sample_image_id TEXT REFERENCES images(id),
+    PRIMARY KEY (seed_id, checkpoint)
+);
+
+-- ============================================
+-- Checkpoint Knowledge Database
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS checkpoints (
+    id                  INTEGER PRIMARY KEY AUTOINCREMENT,
+    filename            TEXT UNIQUE NOT NULL,
+    display_name        TEXT,
+    base_model          TEXT,
+    created_at          DATETIME DEFAULT CURRENT_TIMESTAMP,
+    strengths           TEXT,
+    weaknesses          TEXT,
+    preferred_cfg       REAL,
+    cfg_range_low       REAL,
+    cfg_range_high      REAL,
+    preferred_sampler   TEXT,
+    preferred_scheduler TEXT,
+    optimal_resolution  TEXT,
+    notes               TEXT
+);
+
+CREATE TABLE IF NOT EXISTS checkpoint_prompt_terms (
+    id              INTEGER PRIMARY KEY AUTOINCREMENT,
+    checkpoint_id   INTEGER REFERENCES checkpoints(id) ON DELETE CASCADE,
+    term            TEXT NOT NULL,
+    effect          TEXT NOT NULL,
+    strength        TEXT CHECK(strength IN ('strong', 'moderate', 'weak', 'broken')),
+    example_image_id TEXT REFERENCES images(id),
+    created_at      DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS checkpoint_observations (
+    id              INTEGER PRIMARY KEY AUTOINCREMENT,
+    checkpoint_id   INTEGER REFERENCES checkpoints(id) ON DELETE CASCADE,
+    observation     TEXT NOT NULL,
+    source          TEXT CHECK(source IN ('user', 'ab_comparison', 'pipeline_note', 'auto_rating')),
+    comparison_id   TEXT,
+    created_at      DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+-- ============================================
+-- A/B Comparisons
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS comparisons (
+    id              TEXT PRIMARY KEY,
+    image_a_id      TEXT REFERENCES images(id),
+    image_b_id      TEXT REFERENCES images(id),
+    variable_changed TEXT NOT NULL,
+    note            TEXT,
+    created_at      DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+-- ============================================
+-- Smart Queue (persistent)
+-- ============================================
+
+CREATE TABLE IF NOT EXISTS queue_jobs (
+    id              TEXT PRIMARY KEY,
+    priority        INTEGER DEFAULT 1,
+    status          TEXT CHECK(status IN ('pending', 'generating', 'completed', 'failed', 'cancelled')),
+    positive_prompt TEXT NOT NULL,
+    negative_prompt TEXT NOT NULL,
+    settings_json   TEXT NOT NULL,
+    pipeline_log    TEXT,
+    original_idea   TEXT,
+    linked_comparison_id TEXT,
+    created_at      DATETIME DEFAULT CURRENT_TIMESTAMP,
+    started_at      DATETIME,
+    completed_at    DATETIME,
+    result_image_id TEXT REFERENCES images(id)
+);
+
+-- ============================================
+-- Indexes
+-- ============================================
+
+CREATE INDEX IF NOT EXISTS idx_images_checkpoint ON images(checkpoint);
+CREATE INDEX IF NOT EXISTS idx_images_seed ON images(seed);
+CREATE INDEX IF NOT EXISTS idx_images_created ON images(created_at);
+CREATE INDEX IF NOT EXISTS idx_images_rating ON images(rating);
+CREATE INDEX IF NOT EXISTS idx_images_deleted ON images(deleted);
+CREATE INDEX IF NOT EXISTS idx_images_favorite ON images(favorite);
+CREATE INDEX IF NOT EXISTS idx_images_created_deleted ON images(created_at, deleted);
+CREATE INDEX IF NOT EXISTS idx_image_tags_image_id ON image_tags(image_id);
+CREATE INDEX IF NOT EXISTS idx_image_tags_tag_id ON image_tags(tag_id);
+CREATE INDEX IF NOT EXISTS idx_checkpoint_terms_checkpoint ON checkpoint_prompt_terms(checkpoint_id);
+CREATE INDEX IF NOT EXISTS idx_queue_status ON queue_jobs(status, priority);
+CREATE INDEX IF NOT EXISTS idx_seeds_value ON seeds(seed_value);
+CREATE INDEX IF NOT EXISTS idx_seeds_checkpoint ON seeds(checkpoint);
+"#;
+
+pub(super) const MIGRATION_V2: &str = r#"
+ALTER TABLE queue_jobs ADD COLUMN selected_concept INTEGER;
+ALTER TABLE queue_jobs ADD COLUMN auto_approved BOOLEAN DEFAULT FALSE;
+"#;
+
+// compute_cost = steps × width × height × batch. Existing rows predate batch
+// tracking, so they are backfilled assuming a batch of 1.
+pub(super) const MIGRATION_V3: &str = r#"
+ALTER TABLE images ADD COLUMN compute_cost INTEGER;
+UPDATE images SET compute_cost = steps * width * height
+    WHERE steps IS NOT NULL AND width IS NOT NULL AND height IS NOT NULL;
+"#;
+
+// Tag implications ("corgi" implies "dog"). SQLite can't alter a CHECK
+// constraint, so image_tags is rebuilt to allow the new 'implied' source.
+pub(super) const MIGRATION_V4: &str = r#"
+CREATE TABLE IF NOT EXISTS tag_implications (
+    tag_id          INTEGER REFERENCES tags(id) ON DELETE CASCADE,
+    implied_tag_id  INTEGER REFERENCES tags(id) ON DELETE CASCADE,
+    PRIMARY KEY (tag_id, implied_tag_id),
+    CHECK (tag_id != implied_tag_id)
+);
+
+CREATE TABLE image_tags_v4 (
+    image_id    TEXT REFERENCES images(id) ON DELETE CASCADE,
+    tag_id      INTEGER REFERENCES tags(id),
+    source      TEXT CHECK(source IN ('ai', 'user', 'implied')),
+    confidence  REAL,
+    PRIMARY KEY (image_id, tag_id)
+);
+INSERT INTO image_tags_v4 (image_id, tag_id, source, confidence)
+    SELECT image_id, tag_id, source, confidence FROM image_tags;
+DROP TABLE image_tags;
+ALTER TABLE image_tags_v4 RENAME TO image_tags;
+CREATE INDEX IF NOT EXISTS idx_image_tags_image_id ON image_tags(image_id);
+CREATE INDEX IF NOT EXISTS idx_image_tags_tag_id ON image_tags(tag_id);
+CREATE INDEX IF NOT EXISTS idx_tag_implications_implied ON tag_implications(implied_tag_id);
+"#;
+
+// Image source. Existing rows with a pipeline log or idea came from the
+// pipeline; everything else was generated from manual prompts.
+pub(super) const MIGRATION_V5: &str = r#"
+ALTER TABLE images ADD COLUMN source TEXT
+    CHECK(source IN ('pipeline', 'manual', 'imported', 'variation'));
+UPDATE images SET source = CASE
+    WHEN pipeline_log IS NOT NULL OR original_idea IS NOT NULL THEN 'pipeline'
+    ELSE 'manual'
+END;
+CREATE INDEX IF NOT EXISTS idx_images_source ON images(source);
+"#;
+
+// Prompt embeddings for semantic search: little-endian f32s, filled lazily.
+pub(super) const MIGRATION_V6: &str = r#"
+ALTER TABLE images ADD COLUMN prompt_embedding BLOB;
+"#;
+
+pub(super) const MIGRATION_V7: &str = r#"
+ALTER TABLE checkpoints ADD COLUMN sample_image_id TEXT
+    REFERENCES images(id) ON DELETE SET NULL;
+"#;
+
+pub(super) const MIGRATION_V8: &str = r#"
+ALTER TABLE queue_jobs ADD COLUMN group_id TEXT;
+CREATE INDEX IF NOT EXISTS idx_queue_group ON queue_jobs(group_id);
+"#;
+
+// Alternate captions; the primary stays in images.caption.
+pub(super) const MIGRATION_V9: &str = r#"
+CREATE TABLE IF NOT EXISTS image_captions (
+    id          INTEGER PRIMARY KEY AUTOINCREMENT,
+    image_id    TEXT NOT NULL REFERENCES images(id) ON DELETE CASCADE,
+    caption     TEXT NOT NULL,
+    source      TEXT CHECK(source IN ('ai', 'user')),
+    created_at  DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS idx_image_captions_image ON image_captions(image_id);
+"#;
+
+// Vision-model aesthetic score (1-10), sortable in the gallery.
+pub(super) const MIGRATION_V10: &str = r#"
+ALTER TABLE images ADD COLUMN aesthetic_score REAL;
+CREATE INDEX IF NOT EXISTS idx_images_aesthetic ON images(aesthetic_score);
+"#;
+
+// Trace image -> job -> pipeline run.
+pub(super) const MIGRATION_V11: &str = r#"
+ALTER TABLE queue_jobs ADD COLUMN pipeline_run_id TEXT;
+ALTER TABLE images ADD COLUMN pipeline_run_id TEXT;
+CREATE INDEX IF NOT EXISTS idx_images_pipeline_run ON images(pipeline_run_id);
+"#;
+
+// Every image generated before this was txt2img at full denoise.
+pub(super) const MIGRATION_V12: &str = r#"
+ALTER TABLE images ADD COLUMN denoise REAL;
+UPDATE images SET denoise = 1.0 WHERE source IS NOT 'imported';
+"#;
+
+// User overrides of the pipeline stage system prompts.
+pub(super) const MIGRATION_V13: &str = r#"
+CREATE TABLE IF NOT EXISTS prompt_templates (
+    stage       TEXT PRIMARY KEY CHECK(stage IN
+                    ('ideator', 'composer', 'judge', 'prompt_engineer', 'reviewer')),
+    template    TEXT NOT NULL,
+    updated_at  DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+"#;
+
+// Hand edits to the Prompt Engineer's output, kept alongside the original.
+pub(super) const MIGRATION_V14: &str = r#"
+CREATE TABLE IF NOT EXISTS saved_prompts (
+    id                  INTEGER PRIMARY KEY AUTOINCREMENT,
+    pipeline_run_id     TEXT,
+    original_positive   TEXT NOT NULL,
+    original_negative   TEXT NOT NULL,
+    positive_prompt     TEXT NOT NULL,
+    negative_prompt     TEXT NOT NULL,
+    created_at          DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS idx_saved_prompts_run ON saved_prompts(pipeline_run_id);
+"#;
+
+// Differences between requested and actually-run KSampler settings.
+pub(super) const MIGRATION_V15: &str = r#"
+ALTER TABLE images ADD COLUMN settings_mismatch TEXT;
+"#;
+
+// Explicit order of jobs within a priority. Fractional so a job can be
+// placed between two others without renumbering; existing jobs keep their
+// insertion order.
+pub(super) const MIGRATION_V16: &str = r#"
+ALTER TABLE queue_jobs ADD COLUMN sort_order REAL;
+UPDATE queue_jobs SET sort_order = rowid;
+"#;
+
+// Variations remember the image they were made from.
+pub(super) const MIGRATION_V17: &str = r#"
+ALTER TABLE queue_jobs ADD COLUMN parent_image_id TEXT;
+ALTER TABLE images ADD COLUMN parent_image_id TEXT;
+CREATE INDEX IF NOT EXISTS idx_images_parent ON images(parent_image_id);
+"#;
+
+// 64-bit perceptual hash (big-endian) for near-duplicate detection.
+pub(super) const MIGRATION_V18: &str = r#"
+ALTER TABLE images ADD COLUMN phash BLOB;
+"#;
+
+// When an image was moved to the trash, for purging after the retention
+// period. Images already in the trash start their period now.
+pub(super) const MIGRATION_V19: &str = r#"
+ALTER TABLE images ADD COLUMN deleted_at DATETIME;
+UPDATE images SET deleted_at = CURRENT_TIMESTAMP WHERE deleted = TRUE;
+"#;
+
+// How many times a failed job was requeued, bounded by the
+// `generation.max_job_retries` config.
+pub(super) const MIGRATION_V20: &str = r#"
+ALTER TABLE queue_jobs ADD COLUMN retry_count INTEGER NOT NULL DEFAULT 0;
+"#;
+
+// Parent links become foreign keys, so purging a parent clears its
+// variations' link instead of leaving it dangling. SQLite can't add a
+// constraint to a column, so each one is replaced by a constrained copy;
+// links to images that are already gone are dropped.
+pub(super) const MIGRATION_V21: &str = r#"
+DROP INDEX IF EXISTS idx_images_parent;
+ALTER TABLE images ADD COLUMN parent_ref TEXT REFERENCES images(id) ON DELETE SET NULL;
+UPDATE images SET parent_ref = parent_image_id
+    WHERE parent_image_id IN (SELECT id FROM images);
+ALTER TABLE images DROP COLUMN parent_image_id;
+ALTER TABLE images RENAME COLUMN parent_ref TO parent_image_id;
+CREATE INDEX IF NOT EXISTS idx_images_parent ON images(parent_image_id);
+
+ALTER TABLE queue_jobs ADD COLUMN parent_ref TEXT REFERENCES images(id) ON DELETE SET NULL;
+UPDATE queue_jobs SET parent_ref = parent_image_id
+    WHERE parent_image_id IN (SELECT id FROM images);
+ALTER TABLE queue_jobs DROP COLUMN parent_image_id;
+ALTER TABLE queue_jobs RENAME COLUMN parent_ref TO parent_image_id;
+"#;
@@ -0,0 +1,267 @@
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+
+// Full-text index over the searchable image text, kept in sync by triggers.
+// Rows are tied to images by `image_id` rather than rowid: `images` has a
+// TEXT primary key, so VACUUM is free to renumber its rowids.
+const FTS_SCHEMA: &str = r#"
+CREATE VIRTUAL TABLE IF NOT EXISTS images_fts USING fts5(
+    image_id UNINDEXED, positive_prompt, negative_prompt, original_idea, caption
+);
+
+CREATE TRIGGER IF NOT EXISTS images_fts_insert AFTER INSERT ON images BEGIN
+    INSERT INTO images_fts (image_id, positive_prompt, negative_prompt, original_idea, caption)
+    VALUES (new.id, new.positive_prompt, new.negative_prompt, new.original_idea, new.caption);
+END;
+
+CREATE TRIGGER IF NOT EXISTS images_fts_delete AFTER DELETE ON images BEGIN
+    DELETE FROM images_fts WHERE image_id = old.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS images_fts_update
+AFTER UPDATE OF id, positive_prompt, negative_prompt, original_idea, caption ON images BEGIN
+    DELETE FROM images_fts WHERE image_id = old.id;
+    INSERT INTO images_fts (image_id, positive_prompt, negative_prompt, original_idea, caption)
+    VALUES (new.id, new.positive_prompt, new.negative_prompt, new.original_idea, new.caption);
+END;
+"#;
+
+const DROP_FTS: &str = "DROP TRIGGER IF EXISTS images_fts_insert;
+     DROP TRIGGER IF EXISTS images_fts_delete;
+     DROP TRIGGER IF EXISTS images_fts_update;
+     DROP TABLE IF EXISTS images_fts;";
+
+/// Reindex every image from scratch.
+pub(super) const FTS_REBUILD: &str = "DELETE FROM images_fts;
+     INSERT INTO images_fts (image_id, positive_prompt, negative_prompt, original_idea, caption)
+     SELECT id, positive_prompt, negative_prompt, original_idea, caption FROM images;";
+
+/// Create the full-text index and its triggers when this SQLite has FTS5,
+/// indexing existing images the first time. An index from older versions,
+/// keyed by rowid, is replaced. Without FTS5 this does nothing and gallery
+/// search keeps using LIKE, which is why it isn't a versioned migration.
+pub fn ensure_fts(conn: &Connection) -> Result<()> {
+    let has_fts5: bool = conn
+        .query_row(
+            "SELECT sqlite_compileoption_used('ENABLE_FTS5')",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap_or(false);
+    if !has_fts5 {
+        return Ok(());
+    }
+
+    let existed = fts_keyed_by_image_id(conn);
+    let tx = conn
+        .unchecked_transaction()
+        .context("Failed to start full-text index setup")?;
+    if !existed {
+        tx.execute_batch(DROP_FTS)
+            .context("Failed to drop old full-text index")?;
+    }
+    tx.execute_batch(FTS_SCHEMA)
+        .context("Failed to create full-text index")?;
+    if !existed {
+        tx.execute_batch(FTS_REBUILD)
+            .context("Failed to build full-text index")?;
+    }
+    tx.commit()
+        .context("Failed to commit full-text index setup")?;
+    Ok(())
+}
+
+/// Whether the full-text index exists on this database.
+pub fn fts_enabled(conn: &Connection) -> bool {
+    conn.query_row(
+        "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'images_fts'",
+        [],
+        |_| Ok(()),
+    )
+    .is_ok()
+}
+
+/// Whether the index exists in its current form, with an `image_id` column.
+fn fts_keyed_by_image_id(conn: &Connection) -> bool {
+    conn.query_row(
+        "SELECT 1 FROM pragma_table_info('images_fts') WHERE name = 'image_id'",
+        [],
+        |_| Ok(()),
+    )
+    .is_ok()
+}
+
+/// Remove the index and triggers, leaving the database as it would be
+/// without FTS5.
+#[cfg(test)]
+pub(crate) fn drop_fts(conn: &Connection) {
+    conn.execute_batch(DROP_FTS).unwrap();
+}
+
+/// Turn gallery search text into an FTS5 query. Double-quoted phrases
+/// ("dark forest") are kept as phrases, and a trailing `*` makes a word a
+/// prefix query (suns*). Every other word is quoted, so punctuation and FTS
+/// operators in the text can't cause syntax errors. Words are ANDed.
+/// Returns `None` when nothing searchable is left.
+pub fn fts_query(search: &str) -> Option<String> {
+    let mut terms = Vec::new();
+    let mut rest = search.trim();
+    while !rest.is_empty() {
+        if let Some(after_quote) = rest.strip_prefix('"') {
+            // A phrase runs to the closing quote, or to the end if unbalanced
+            let (phrase, tail) = after_quote.split_once('"').unwrap_or((after_quote, ""));
+            push_term(&mut terms, phrase, false);
+            rest = tail.trim_start();
+        } else {
+            let end = rest
+                .find(|c: char| c.is_whitespace() || c == '"')
+                .unwrap_or(rest.len());
+            let word = &rest[..end];
+            match word.strip_suffix('*') {
+                Some(stem) => push_term(&mut terms, stem.trim_end_matches('*'), true),
+                None => push_term(&mut terms, word, false),
+            }
+            rest = rest[end..].trim_start();
+        }
+    }
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+fn push_term(terms: &mut Vec<String>, text: &str, prefix: bool) {
+    // Only letters and digits are indexed; a term without any would match
+    // nothing or be a syntax error
+    if !text.chars().any(char::is_alphanumeric) {
+        return;
+    }
+    let quoted = format!("\"{}\"", text.replace('"', "\"\""));
+    terms.push(if prefix { quoted + "*" } else { quoted });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    #[test]
+    fn test_fts_query_words_phrases_and_prefixes() {
+        assert_eq!(fts_query("sunset").as_deref(), Some(r#""sunset""#));
+        assert_eq!(
+            fts_query(r#"  "dark forest"  suns* "#).as_deref(),
+            Some(r#""dark forest" "suns"*"#)
+        );
+        assert_eq!(
+            fts_query(r#"cat's NOT "unclosed phrase"#).as_deref(),
+            Some(r#""cat's" "NOT" "unclosed phrase""#)
+        );
+        assert!(fts_query("").is_none());
+        assert!(fts_query(r#"* "" -"#).is_none());
+    }
+
+    fn hits(conn: &Connection, query: &str) -> i64 {
+        conn.query_row(
+            "SELECT COUNT(*) FROM images_fts WHERE images_fts MATCH ?1",
+            [query],
+            |row| row.get(0),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_ensure_fts_indexes_existing_images_once() {
+        let conn = db::open_memory_database().unwrap();
+        assert!(fts_enabled(&conn));
+        drop_fts(&conn);
+        assert!(!fts_enabled(&conn));
+
+        conn.execute(
+            "INSERT INTO images (id, filename, positive_prompt) VALUES ('a', 'a.png', 'misty lake')",
+            [],
+        )
+        .unwrap();
+        ensure_fts(&conn).unwrap();
+        assert_eq!(hits(&conn, "misty"), 1);
+
+        // Running setup again must not index the row a second time
+        ensure_fts(&conn).unwrap();
+        assert_eq!(hits(&conn, "misty"), 1);
+    }
+
+    #[test]
+    fn test_triggers_follow_updates_and_deletes() {
+        let conn = db::open_memory_database().unwrap();
+        conn.execute(
+            "INSERT INTO images (id, filename, caption) VALUES ('a', 'a.png', 'a red fox')",
+            [],
+        )
+        .unwrap();
+        assert_eq!(hits(&conn, "fox"), 1);
+
+        conn.execute(
+            "UPDATE images SET caption = 'a grey wolf' WHERE id = 'a'",
+            [],
+        )
+        .unwrap();
+        assert_eq!(hits(&conn, "fox"), 0);
+        assert_eq!(hits(&conn, "wolf"), 1);
+
+        conn.execute("DELETE FROM images WHERE id = 'a'", [])
+            .unwrap();
+        assert_eq!(hits(&conn, "wolf"), 0);
+    }
+
+    fn matching_ids(conn: &Connection, query: &str) -> Vec<String> {
+        let mut stmt = conn
+            .prepare(
+                "SELECT images.id FROM images WHERE images.id IN \
+                 (SELECT image_id FROM images_fts WHERE images_fts MATCH ?1)",
+            )
+            .unwrap();
+        stmt.query_map([query], |row| row.get(0))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_index_survives_renumbered_rowids() {
+        let conn = db::open_memory_database().unwrap();
+        for (id, caption) in [("a", "a red fox"), ("b", "a grey wolf")] {
+            conn.execute(
+                "INSERT INTO images (id, filename, caption) VALUES (?1, ?1, ?2)",
+                [id, caption],
+            )
+            .unwrap();
+        }
+        // What VACUUM may do to a table without an INTEGER PRIMARY KEY
+        conn.execute_batch(
+            "UPDATE images SET rowid = rowid + 100;
+             UPDATE images SET rowid = CASE id WHEN 'a' THEN 2 ELSE 1 END;",
+        )
+        .unwrap();
+
+        assert_eq!(matching_ids(&conn, "fox"), ["a"]);
+        assert_eq!(matching_ids(&conn, "wolf"), ["b"]);
+    }
+
+    #[test]
+    fn test_ensure_fts_replaces_rowid_keyed_index() {
+        let conn = db::open_memory_database().unwrap();
+        conn.execute(
+            "INSERT INTO images (id, filename, caption) VALUES ('a', 'a.png', 'a red fox')",
+            [],
+        )
+        .unwrap();
+        drop_fts(&conn);
+        conn.execute_batch(
+            "CREATE VIRTUAL TABLE images_fts USING fts5(
+                 positive_prompt, negative_prompt, original_idea, caption,
+                 content = 'images', content_rowid = 'rowid'
+             );",
+        )
+        .unwrap();
+
+        ensure_fts(&conn).unwrap();
+        assert!(fts_keyed_by_image_id(&conn));
+        assert_eq!(matching_ids(&conn, "fox"), ["a"]);
+    }
+}
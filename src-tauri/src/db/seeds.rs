@@ -22,8 +22,11 @@ pub fn insert_seed(conn: &Connection, seed: &SeedEntry) -> Result<i64> {
 pub fn get_seed(conn: &Connection, id: i64) -> Result<Option<SeedEntry>> {
     let mut stmt = conn
         .prepare(
-            "SELECT id, seed_value, comment, checkpoint, sample_image_id, created_at
-             FROM seeds WHERE id = ?1",
+            "SELECT s.id, s.seed_value, s.comment, s.checkpoint, s.sample_image_id, s.created_at,
+                    i.filename
+             FROM seeds s
+             LEFT JOIN images i ON s.sample_image_id = i.id
+             WHERE s.id = ?1",
         )
         .context("Failed to prepare get_seed query")?;
 
@@ -79,8 +82,10 @@ pub fn list_seeds(conn: &Connection, filter: &SeedFilter) -> Result<Vec<SeedEntr
 
     let where_clause = conditions.join(" AND ");
     let sql = format!(
-        "SELECT s.id, s.seed_value, s.comment, s.checkpoint, s.sample_image_id, s.created_at
+        "SELECT s.id, s.seed_value, s.comment, s.checkpoint, s.sample_image_id, s.created_at,
+                i.filename
          FROM seeds s
+         LEFT JOIN images i ON s.sample_image_id = i.id
          WHERE {}
          ORDER BY s.created_at DESC",
         where_clause
@@ -103,6 +108,31 @@ pub fn list_seeds(conn: &Connection, filter: &SeedFilter) -> Result<Vec<SeedEntr
     Ok(seeds)
 }
 
+/// Link a gallery image as the seed's sample.
+pub fn set_seed_sample_image(conn: &Connection, seed_id: i64, image_id: &str) -> Result<()> {
+    let image_exists: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM images WHERE id = ?1)",
+            params![image_id],
+            |row| row.get(0),
+        )
+        .context("Failed to look up sample image")?;
+    if !image_exists {
+        anyhow::bail!("Image {} not found", image_id);
+    }
+
+    let updated = conn
+        .execute(
+            "UPDATE seeds SET sample_image_id = ?1 WHERE id = ?2",
+            params![image_id, seed_id],
+        )
+        .context("Failed to set seed sample image")?;
+    if updated == 0 {
+        anyhow::bail!("Seed {} not found", seed_id);
+    }
+    Ok(())
+}
+
 pub fn delete_seed(conn: &Connection, id: i64) -> Result<()> {
     conn.execute("DELETE FROM seed_tags WHERE seed_id = ?1", params![id])
         .context("Failed to remove seed tag associations")?;
@@ -187,192 +217,10 @@ fn row_to_seed(row: &rusqlite::Row) -> rusqlite::Result<SeedEntry> {
         sample_image_id: row.get(4)?,
         created_at: row.get(5)?,
         tags: None,
+        sample_filename: row.get(6)?,
     })
 }
 
 #[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::db;
-
-    fn setup() -> Connection {
-        db::open_memory_database().unwrap()
-    }
-
-    fn make_test_seed() -> SeedEntry {
-        SeedEntry {
-            id: None,
-            seed_value: 12345,
-            comment: "Strong center composition".to_string(),
-            checkpoint: Some("dreamshaper_8.safetensors".to_string()),
-            sample_image_id: None,
-            created_at: None,
-            tags: None,
-        }
-    }
-
-    #[test]
-    fn test_insert_and_get() {
-        let conn = setup();
-        let seed = make_test_seed();
-        let id = insert_seed(&conn, &seed).unwrap();
-
-        let retrieved = get_seed(&conn, id).unwrap().unwrap();
-        assert_eq!(retrieved.seed_value, 12345);
-        assert_eq!(retrieved.comment, "Strong center composition");
-        assert_eq!(retrieved.checkpoint.unwrap(), "dreamshaper_8.safetensors");
-    }
-
-    #[test]
-    fn test_list_seeds_no_filter() {
-        let conn = setup();
-        insert_seed(&conn, &make_test_seed()).unwrap();
-        insert_seed(
-            &conn,
-            &SeedEntry {
-                seed_value: 99999,
-                comment: "Chaotic multi-element".to_string(),
-                ..make_test_seed()
-            },
-        )
-        .unwrap();
-
-        let seeds = list_seeds(&conn, &SeedFilter::default()).unwrap();
-        assert_eq!(seeds.len(), 2);
-    }
-
-    #[test]
-    fn test_list_seeds_with_checkpoint_filter() {
-        let conn = setup();
-        insert_seed(&conn, &make_test_seed()).unwrap();
-        insert_seed(
-            &conn,
-            &SeedEntry {
-                checkpoint: Some("deliberate.safetensors".to_string()),
-                ..make_test_seed()
-            },
-        )
-        .unwrap();
-
-        let filter = SeedFilter {
-            checkpoint: Some("dreamshaper_8.safetensors".to_string()),
-            ..Default::default()
-        };
-        let seeds = list_seeds(&conn, &filter).unwrap();
-        assert_eq!(seeds.len(), 1);
-    }
-
-    #[test]
-    fn test_list_seeds_with_search() {
-        let conn = setup();
-        insert_seed(&conn, &make_test_seed()).unwrap();
-        insert_seed(
-            &conn,
-            &SeedEntry {
-                comment: "Portrait framing".to_string(),
-                ..make_test_seed()
-            },
-        )
-        .unwrap();
-
-        let filter = SeedFilter {
-            search: Some("center".to_string()),
-            ..Default::default()
-        };
-        let seeds = list_seeds(&conn, &filter).unwrap();
-        assert_eq!(seeds.len(), 1);
-    }
-
-    #[test]
-    fn test_delete_seed() {
-        let conn = setup();
-        let id = insert_seed(&conn, &make_test_seed()).unwrap();
-        delete_seed(&conn, id).unwrap();
-
-        let result = get_seed(&conn, id).unwrap();
-        assert!(result.is_none());
-    }
-
-    #[test]
-    fn test_seed_tags() {
-        let conn = setup();
-        let seed_id = insert_seed(&conn, &make_test_seed()).unwrap();
-
-        let tag_id = add_seed_tag(&conn, seed_id, "portrait").unwrap();
-        add_seed_tag(&conn, seed_id, "symmetric").unwrap();
-
-        // Filter by tag
-        let filter = SeedFilter {
-            tags: Some(vec!["portrait".to_string()]),
-            ..Default::default()
-        };
-        let seeds = list_seeds(&conn, &filter).unwrap();
-        assert_eq!(seeds.len(), 1);
-
-        remove_seed_tag(&conn, seed_id, tag_id).unwrap();
-
-        let filter2 = SeedFilter {
-            tags: Some(vec!["portrait".to_string()]),
-            ..Default::default()
-        };
-        let seeds2 = list_seeds(&conn, &filter2).unwrap();
-        assert_eq!(seeds2.len(), 0);
-    }
-
-    #[test]
-    fn test_checkpoint_notes() {
-        let conn = setup();
-        let seed_id = insert_seed(&conn, &make_test_seed()).unwrap();
-
-        add_checkpoint_note(
-            &conn,
-            &SeedCheckpointNote {
-                seed_id,
-                checkpoint: "dreamshaper_8.safetensors".to_string(),
-                note: "Great for portraits".to_string(),
-                sample_image_id: None,
-            },
-        )
-        .unwrap();
-
-        add_checkpoint_note(
-            &conn,
-            &SeedCheckpointNote {
-                seed_id,
-                checkpoint: "deliberate.safetensors".to_string(),
-                note: "More abstract results".to_string(),
-                sample_image_id: None,
-            },
-        )
-        .unwrap();
-
-        let notes = get_checkpoint_notes(&conn, seed_id).unwrap();
-        assert_eq!(notes.len(), 2);
-        assert_eq!(notes[0].checkpoint, "deliberate.safetensors");
-        assert_eq!(notes[1].checkpoint, "dreamshaper_8.safetensors");
-    }
-
-    #[test]
-    fn test_checkpoint_note_upsert() {
-        let conn = setup();
-        let seed_id = insert_seed(&conn, &make_test_seed()).unwrap();
-
-        let note = SeedCheckpointNote {
-            seed_id,
-            checkpoint: "dreamshaper_8.safetensors".to_string(),
-            note: "Original note".to_string(),
-            sample_image_id: None,
-        };
-        add_checkpoint_note(&conn, &note).unwrap();
-
-        let updated = SeedCheckpointNote {
-            note: "Updated note".to_string(),
-            ..note
-        };
-        add_checkpoint_note(&conn, &updated).unwrap();
-
-        let notes = get_checkpoint_notes(&conn, seed_id).unwrap();
-        assert_eq!(notes.len(), 1);
-        assert_eq!(notes[0].note, "Updated note");
-    }
-}
+#[path = "seeds_test.rs"]
+mod tests;
@@ -0,0 +1,224 @@
+use super::*;
+use crate::db;
+use crate::db::images::tests::make_test_image;
+
+fn setup() -> Connection {
+    db::open_memory_database().unwrap()
+}
+
+fn make_test_seed() -> SeedEntry {
+    SeedEntry {
+        id: None,
+        seed_value: 12345,
+        comment: "Strong center composition".to_string(),
+        checkpoint: Some("dreamshaper_8.safetensors".to_string()),
+        sample_image_id: None,
+        created_at: None,
+        tags: None,
+        sample_filename: None,
+    }
+}
+
+#[test]
+fn test_insert_and_get() {
+    let conn = setup();
+    let seed = make_test_seed();
+    let id = insert_seed(&conn, &seed).unwrap();
+
+    let retrieved = get_seed(&conn, id).unwrap().unwrap();
+    assert_eq!(retrieved.seed_value, 12345);
+    assert_eq!(retrieved.comment, "Strong center composition");
+    assert_eq!(retrieved.checkpoint.unwrap(), "dreamshaper_8.safetensors");
+}
+
+#[test]
+fn test_list_seeds_no_filter() {
+    let conn = setup();
+    insert_seed(&conn, &make_test_seed()).unwrap();
+    insert_seed(
+        &conn,
+        &SeedEntry {
+            seed_value: 99999,
+            comment: "Chaotic multi-element".to_string(),
+            ..make_test_seed()
+        },
+    )
+    .unwrap();
+
+    let seeds = list_seeds(&conn, &SeedFilter::default()).unwrap();
+    assert_eq!(seeds.len(), 2);
+}
+
+#[test]
+fn test_list_seeds_with_checkpoint_filter() {
+    let conn = setup();
+    insert_seed(&conn, &make_test_seed()).unwrap();
+    insert_seed(
+        &conn,
+        &SeedEntry {
+            checkpoint: Some("deliberate.safetensors".to_string()),
+            ..make_test_seed()
+        },
+    )
+    .unwrap();
+
+    let filter = SeedFilter {
+        checkpoint: Some("dreamshaper_8.safetensors".to_string()),
+        ..Default::default()
+    };
+    let seeds = list_seeds(&conn, &filter).unwrap();
+    assert_eq!(seeds.len(), 1);
+}
+
+#[test]
+fn test_list_seeds_with_search() {
+    let conn = setup();
+    insert_seed(&conn, &make_test_seed()).unwrap();
+    insert_seed(
+        &conn,
+        &SeedEntry {
+            comment: "Portrait framing".to_string(),
+            ..make_test_seed()
+        },
+    )
+    .unwrap();
+
+    let filter = SeedFilter {
+        search: Some("center".to_string()),
+        ..Default::default()
+    };
+    let seeds = list_seeds(&conn, &filter).unwrap();
+    assert_eq!(seeds.len(), 1);
+}
+
+#[test]
+fn test_delete_seed() {
+    let conn = setup();
+    let id = insert_seed(&conn, &make_test_seed()).unwrap();
+    delete_seed(&conn, id).unwrap();
+
+    let result = get_seed(&conn, id).unwrap();
+    assert!(result.is_none());
+}
+
+#[test]
+fn test_seed_tags() {
+    let conn = setup();
+    let seed_id = insert_seed(&conn, &make_test_seed()).unwrap();
+
+    let tag_id = add_seed_tag(&conn, seed_id, "portrait").unwrap();
+    add_seed_tag(&conn, seed_id, "symmetric").unwrap();
+
+    // Filter by tag
+    let filter = SeedFilter {
+        tags: Some(vec!["portrait".to_string()]),
+        ..Default::default()
+    };
+    let seeds = list_seeds(&conn, &filter).unwrap();
+    assert_eq!(seeds.len(), 1);
+
+    remove_seed_tag(&conn, seed_id, tag_id).unwrap();
+
+    let filter2 = SeedFilter {
+        tags: Some(vec!["portrait".to_string()]),
+        ..Default::default()
+    };
+    let seeds2 = list_seeds(&conn, &filter2).unwrap();
+    assert_eq!(seeds2.len(), 0);
+}
+
+#[test]
+fn test_checkpoint_notes() {
+    let conn = setup();
+    let seed_id = insert_seed(&conn, &make_test_seed()).unwrap();
+
+    add_checkpoint_note(
+        &conn,
+        &SeedCheckpointNote {
+            seed_id,
+            checkpoint: "dreamshaper_8.safetensors".to_string(),
+            note: "Great for portraits".to_string(),
+            sample_image_id: None,
+        },
+    )
+    .unwrap();
+
+    add_checkpoint_note(
+        &conn,
+        &SeedCheckpointNote {
+            seed_id,
+            checkpoint: "deliberate.safetensors".to_string(),
+            note: "More abstract results".to_string(),
+            sample_image_id: None,
+        },
+    )
+    .unwrap();
+
+    let notes = get_checkpoint_notes(&conn, seed_id).unwrap();
+    assert_eq!(notes.len(), 2);
+    assert_eq!(notes[0].checkpoint, "deliberate.safetensors");
+    assert_eq!(notes[1].checkpoint, "dreamshaper_8.safetensors");
+}
+
+#[test]
+fn test_checkpoint_note_upsert() {
+    let conn = setup();
+    let seed_id = insert_seed(&conn, &make_test_seed()).unwrap();
+
+    let note = SeedCheckpointNote {
+        seed_id,
+        checkpoint: "dreamshaper_8.safetensors".to_string(),
+        note: "Original note".to_string(),
+        sample_image_id: None,
+    };
+    add_checkpoint_note(&conn, &note).unwrap();
+
+    let updated = SeedCheckpointNote {
+        note: "Updated note".to_string(),
+        ..note
+    };
+    add_checkpoint_note(&conn, &updated).unwrap();
+
+    let notes = get_checkpoint_notes(&conn, seed_id).unwrap();
+    assert_eq!(notes.len(), 1);
+    assert_eq!(notes[0].note, "Updated note");
+}
+
+#[test]
+fn test_set_sample_image_joins_filename() {
+    let conn = setup();
+    db::images::insert_image(&conn, &make_test_image("img-001")).unwrap();
+    let id = insert_seed(&conn, &make_test_seed()).unwrap();
+    assert!(get_seed(&conn, id)
+        .unwrap()
+        .unwrap()
+        .sample_filename
+        .is_none());
+
+    set_seed_sample_image(&conn, id, "img-001").unwrap();
+
+    let seed = get_seed(&conn, id).unwrap().unwrap();
+    assert_eq!(seed.sample_image_id.as_deref(), Some("img-001"));
+    let expected = make_test_image("img-001").filename;
+    assert_eq!(seed.sample_filename.as_deref(), Some(expected.as_str()));
+    let listed = list_seeds(&conn, &SeedFilter::default()).unwrap();
+    assert_eq!(
+        listed[0].sample_filename.as_deref(),
+        Some(expected.as_str())
+    );
+}
+
+#[test]
+fn test_set_sample_image_rejects_unknown_ids() {
+    let conn = setup();
+    db::images::insert_image(&conn, &make_test_image("img-001")).unwrap();
+    let id = insert_seed(&conn, &make_test_seed()).unwrap();
+
+    assert!(set_seed_sample_image(&conn, id, "missing").is_err());
+    assert!(set_seed_sample_image(&conn, id + 1, "img-001").is_err());
+    assert!(get_seed(&conn, id)
+        .unwrap()
+        .unwrap()
+        .sample_image_id
+        .is_none());
+}
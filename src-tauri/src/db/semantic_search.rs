@@ -0,0 +1,127 @@
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+
+use super::images::row_to_image;
+use crate::types::gallery::ImageEntry;
+
+pub fn set_prompt_embedding(conn: &Connection, id: &str, embedding: &[f32]) -> Result<()> {
+    conn.execute(
+        "UPDATE images SET prompt_embedding = ?1 WHERE id = ?2",
+        params![super::embeddings::encode(embedding), id],
+    )
+    .context("Failed to store prompt embedding")?;
+    Ok(())
+}
+
+/// (id, positive_prompt) of images that have a prompt but no embedding yet.
+pub fn list_images_missing_embedding(conn: &Connection) -> Result<Vec<(String, String)>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, positive_prompt FROM images
+             WHERE prompt_embedding IS NULL
+               AND positive_prompt IS NOT NULL AND positive_prompt != ''
+             ORDER BY created_at",
+        )
+        .context("Failed to prepare missing embeddings query")?;
+
+    let rows = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .context("Failed to execute missing embeddings query")?;
+
+    let mut missing = Vec::new();
+    for row in rows {
+        missing.push(row.context("Failed to read image row")?);
+    }
+    Ok(missing)
+}
+
+/// Non-deleted images ranked by cosine similarity between their prompt
+/// embedding and `query_embedding`, best first. Images without an embedding,
+/// or with one from a model of a different dimension, are left out.
+pub fn semantic_search(
+    conn: &Connection,
+    query_embedding: &[f32],
+    limit: usize,
+) -> Result<Vec<ImageEntry>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, filename, created_at, positive_prompt, negative_prompt,
+                    original_idea, checkpoint, width, height, steps, cfg_scale,
+                    sampler, scheduler, seed, pipeline_log, selected_concept,
+                    auto_approved, caption, caption_edited, rating, favorite,
+                    deleted, user_note, compute_cost, source, aesthetic_score,
+                    pipeline_run_id, denoise, settings_mismatch, parent_image_id, deleted_at,
+                    prompt_embedding
+             FROM images WHERE deleted = FALSE AND prompt_embedding IS NOT NULL",
+        )
+        .context("Failed to prepare semantic_search query")?;
+
+    let rows = stmt
+        .query_map([], |row| {
+            let embedding: Vec<u8> = row.get(31)?;
+            Ok((row_to_image(row)?, embedding))
+        })
+        .context("Failed to execute semantic_search query")?;
+
+    let mut scored = Vec::new();
+    for row in rows {
+        let (image, bytes) = row.context("Failed to read image row")?;
+        let embedding = super::embeddings::decode(&bytes);
+        if let Some(score) = super::embeddings::cosine_similarity(query_embedding, &embedding) {
+            scored.push((score, image));
+        }
+    }
+
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    Ok(scored
+        .into_iter()
+        .take(limit)
+        .map(|(_, image)| image)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::db::images::insert_image;
+    use crate::db::images::tests::make_test_image;
+
+    fn setup() -> Connection {
+        db::open_memory_database().unwrap()
+    }
+
+    #[test]
+    fn test_semantic_search_ranks_closer_prompt_first() {
+        let conn = setup();
+        for id in ["img-autumn", "img-space", "img-unindexed"] {
+            insert_image(&conn, &make_test_image(id)).unwrap();
+        }
+        // Tiny fixed "embeddings": axis 0 ≈ cozy/autumn, axis 2 ≈ sci-fi
+        set_prompt_embedding(&conn, "img-autumn", &[0.9, 0.3, 0.1]).unwrap();
+        set_prompt_embedding(&conn, "img-space", &[0.1, 0.2, 0.95]).unwrap();
+
+        let query = [1.0, 0.2, 0.0];
+        let results = semantic_search(&conn, &query, 10).unwrap();
+        let ids: Vec<&str> = results.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(ids, vec!["img-autumn", "img-space"]);
+
+        let top = semantic_search(&conn, &query, 1).unwrap();
+        assert_eq!(top.len(), 1);
+        assert_eq!(top[0].id, "img-autumn");
+    }
+
+    #[test]
+    fn test_list_images_missing_embedding() {
+        let conn = setup();
+        insert_image(&conn, &make_test_image("img-001")).unwrap();
+        insert_image(&conn, &make_test_image("img-002")).unwrap();
+        set_prompt_embedding(&conn, "img-001", &[1.0, 0.0]).unwrap();
+
+        let missing = list_images_missing_embedding(&conn).unwrap();
+        assert_eq!(
+            missing,
+            vec![("img-002".to_string(), "a cat on a throne".to_string())]
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+
+use super::tags::get_or_create_tag;
+use crate::types::gallery::TagImplication;
+
+/// Record that tagging with `tag_name` should also apply `implied_name`.
+/// Rejects self-implications and anything that would create a cycle.
+pub fn add_tag_implication(conn: &Connection, tag_name: &str, implied_name: &str) -> Result<()> {
+    let tag_id = get_or_create_tag(conn, tag_name)?;
+    let implied_id = get_or_create_tag(conn, implied_name)?;
+
+    if tag_id == implied_id {
+        anyhow::bail!("A tag cannot imply itself");
+    }
+    if get_implied_tag_ids(conn, implied_id)?.contains(&tag_id) {
+        anyhow::bail!(
+            "'{}' already implies '{}'; adding the reverse would create a cycle",
+            implied_name.trim(),
+            tag_name.trim()
+        );
+    }
+
+    conn.execute(
+        "INSERT OR IGNORE INTO tag_implications (tag_id, implied_tag_id) VALUES (?1, ?2)",
+        params![tag_id, implied_id],
+    )
+    .context("Failed to add tag implication")?;
+    Ok(())
+}
+
+pub fn remove_tag_implication(conn: &Connection, tag_id: i64, implied_tag_id: i64) -> Result<()> {
+    conn.execute(
+        "DELETE FROM tag_implications WHERE tag_id = ?1 AND implied_tag_id = ?2",
+        params![tag_id, implied_tag_id],
+    )
+    .context("Failed to remove tag implication")?;
+    Ok(())
+}
+
+pub fn list_tag_implications(conn: &Connection) -> Result<Vec<TagImplication>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT ti.tag_id, t.name, ti.implied_tag_id, it.name
+             FROM tag_implications ti
+             JOIN tags t ON ti.tag_id = t.id
+             JOIN tags it ON ti.implied_tag_id = it.id
+             ORDER BY t.name, it.name",
+        )
+        .context("Failed to prepare list_tag_implications query")?;
+
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(TagImplication {
+                tag_id: row.get(0)?,
+                tag_name: row.get(1)?,
+                implied_tag_id: row.get(2)?,
+                implied_tag_name: row.get(3)?,
+            })
+        })
+        .context("Failed to execute list_tag_implications query")?;
+
+    let mut implications = Vec::new();
+    for row in rows {
+        implications.push(row.context("Failed to read tag implication row")?);
+    }
+    Ok(implications)
+}
+
+/// All tags transitively implied by `tag_id` (not including itself).
+pub fn get_implied_tag_ids(conn: &Connection, tag_id: i64) -> Result<Vec<i64>> {
+    // UNION (not UNION ALL) stops the recursion on already-visited tags,
+    // so a cycle that slipped in via direct SQL can't loop forever.
+    let mut stmt = conn
+        .prepare(
+            "WITH RECURSIVE implied(id) AS (
+                SELECT implied_tag_id FROM tag_implications WHERE tag_id = ?1
+                UNION
+                SELECT ti.implied_tag_id FROM tag_implications ti
+                JOIN implied ON ti.tag_id = implied.id
+             )
+             SELECT id FROM implied WHERE id != ?1",
+        )
+        .context("Failed to prepare implied tags query")?;
+
+    let rows = stmt
+        .query_map(params![tag_id], |row| row.get(0))
+        .context("Failed to execute implied tags query")?;
+
+    let mut ids = Vec::new();
+    for row in rows {
+        ids.push(row.context("Failed to read implied tag row")?);
+    }
+    Ok(ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::db::images::tests::make_test_image;
+    use crate::db::tags::{add_image_tag, get_image_tags};
+
+    fn setup() -> Connection {
+        let conn = db::open_memory_database().unwrap();
+        db::images::insert_image(&conn, &make_test_image("img-001")).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_tagging_applies_implied_tags() {
+        let conn = setup();
+        add_tag_implication(&conn, "corgi", "dog").unwrap();
+        add_tag_implication(&conn, "dog", "animal").unwrap();
+
+        add_image_tag(&conn, "img-001", "corgi", "user", None).unwrap();
+
+        let tags = get_image_tags(&conn, "img-001").unwrap();
+        let names: Vec<&str> = tags.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["animal", "corgi", "dog"]);
+
+        let dog = tags.iter().find(|t| t.name == "dog").unwrap();
+        assert_eq!(dog.source.as_deref(), Some("implied"));
+    }
+
+    #[test]
+    fn test_implied_tag_does_not_override_explicit_tag() {
+        let conn = setup();
+        add_tag_implication(&conn, "corgi", "dog").unwrap();
+        add_image_tag(&conn, "img-001", "dog", "user", None).unwrap();
+        add_image_tag(&conn, "img-001", "corgi", "ai", Some(0.9)).unwrap();
+
+        let tags = get_image_tags(&conn, "img-001").unwrap();
+        let dog = tags.iter().find(|t| t.name == "dog").unwrap();
+        assert_eq!(dog.source.as_deref(), Some("user"));
+    }
+
+    #[test]
+    fn test_cycle_is_rejected() {
+        let conn = setup();
+        add_tag_implication(&conn, "corgi", "dog").unwrap();
+        add_tag_implication(&conn, "dog", "animal").unwrap();
+
+        assert!(add_tag_implication(&conn, "animal", "corgi").is_err());
+        assert!(add_tag_implication(&conn, "dog", "dog").is_err());
+        assert_eq!(list_tag_implications(&conn).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_remove_tag_implication() {
+        let conn = setup();
+        add_tag_implication(&conn, "corgi", "dog").unwrap();
+        let imp = list_tag_implications(&conn).unwrap().remove(0);
+        assert_eq!(imp.tag_name, "corgi");
+        assert_eq!(imp.implied_tag_name, "dog");
+
+        remove_tag_implication(&conn, imp.tag_id, imp.implied_tag_id).unwrap();
+        assert!(list_tag_implications(&conn).unwrap().is_empty());
+    }
+}
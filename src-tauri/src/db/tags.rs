@@ -1,7 +1,7 @@
 use anyhow::{Context, Result};
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OptionalExtension};
 
-use crate::types::gallery::TagEntry;
+use crate::types::gallery::{TagChangeSummary, TagEntry};
 
 pub fn get_or_create_tag(conn: &Connection, name: &str) -> Result<i64> {
     let normalized = name.trim().to_lowercase();
@@ -69,11 +69,55 @@ pub fn list_all_tags(conn: &Connection) -> Result<Vec<TagEntry>> {
     Ok(tags)
 }
 
+/// Every tag with the number of images carrying it, most used first. Tags
+/// only attached to seeds count zero. Soft-deleted images are counted only
+/// with `include_deleted`.
+pub fn list_tags_with_counts(
+    conn: &Connection,
+    include_deleted: bool,
+) -> Result<Vec<(TagEntry, u32)>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.id, t.name, COUNT(i.id) AS uses
+             FROM tags t
+             LEFT JOIN image_tags it ON it.tag_id = t.id
+             LEFT JOIN images i ON i.id = it.image_id AND (?1 OR i.deleted = 0)
+             GROUP BY t.id
+             ORDER BY uses DESC, t.name",
+        )
+        .context("Failed to prepare tag count query")?;
+
+    let rows = stmt
+        .query_map(params![include_deleted], |row| {
+            Ok((
+                TagEntry {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    source: None,
+                    confidence: None,
+                },
+                row.get(2)?,
+            ))
+        })
+        .context("Failed to execute tag count query")?;
+
+    let mut tags = Vec::new();
+    for row in rows {
+        tags.push(row.context("Failed to read tag count row")?);
+    }
+    Ok(tags)
+}
+
 pub fn delete_tag(conn: &Connection, tag_id: i64) -> Result<()> {
     conn.execute("DELETE FROM image_tags WHERE tag_id = ?1", params![tag_id])
         .context("Failed to remove tag associations")?;
     conn.execute("DELETE FROM seed_tags WHERE tag_id = ?1", params![tag_id])
         .context("Failed to remove seed tag associations")?;
+    conn.execute(
+        "DELETE FROM tag_implications WHERE tag_id = ?1 OR implied_tag_id = ?1",
+        params![tag_id],
+    )
+    .context("Failed to remove tag implications")?;
     conn.execute("DELETE FROM tags WHERE id = ?1", params![tag_id])
         .context("Failed to delete tag")?;
     Ok(())
@@ -95,6 +139,16 @@ pub fn add_image_tag(
     )
     .context("Failed to add image tag")?;
 
+    // Implied tags never replace an existing explicit tag on the image
+    for implied_id in super::tag_implications::get_implied_tag_ids(conn, tag_id)? {
+        conn.execute(
+            "INSERT OR IGNORE INTO image_tags (image_id, tag_id, source, confidence)
+             VALUES (?1, ?2, 'implied', NULL)",
+            params![image_id, implied_id],
+        )
+        .context("Failed to add implied image tag")?;
+    }
+
     Ok(tag_id)
 }
 
@@ -210,135 +264,104 @@ pub fn search_tags(conn: &Connection, query: &str) -> Result<Vec<TagEntry>> {
     Ok(tags)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::db;
-    use crate::db::images;
-    use crate::types::gallery::ImageEntry;
-
-    fn setup() -> Connection {
-        db::open_memory_database().unwrap()
-    }
-
-    fn insert_test_image(conn: &Connection, id: &str) {
-        let img = ImageEntry {
-            id: id.to_string(),
-            filename: format!("{}.png", id),
-            created_at: "2026-01-15T10:00:00".to_string(),
-            positive_prompt: None,
-            negative_prompt: None,
-            original_idea: None,
-            checkpoint: None,
-            width: None,
-            height: None,
-            steps: None,
-            cfg_scale: None,
-            sampler: None,
-            scheduler: None,
-            seed: None,
-            pipeline_log: None,
-            selected_concept: None,
-            auto_approved: false,
-            caption: None,
-            caption_edited: false,
-            rating: None,
-            favorite: false,
-            deleted: false,
-            user_note: None,
-            tags: None,
-        };
-        images::insert_image(conn, &img).unwrap();
-    }
-
-    #[test]
-    fn test_get_or_create_tag() {
-        let conn = setup();
-        let id1 = get_or_create_tag(&conn, "portrait").unwrap();
-        let id2 = get_or_create_tag(&conn, "portrait").unwrap();
-        assert_eq!(id1, id2);
-
-        let id3 = get_or_create_tag(&conn, "  Portrait  ").unwrap();
-        assert_eq!(id1, id3);
-    }
-
-    #[test]
-    fn test_get_tag_by_name() {
-        let conn = setup();
-        get_or_create_tag(&conn, "landscape").unwrap();
-
-        let tag = get_tag_by_name(&conn, "landscape").unwrap().unwrap();
-        assert_eq!(tag.name, "landscape");
-
-        let none = get_tag_by_name(&conn, "nonexistent").unwrap();
-        assert!(none.is_none());
+/// Rename a tag in place. Every image and seed carrying it follows along,
+/// so the summary reports how many are affected. With `dry_run` nothing is
+/// written. Renaming onto another existing tag is rejected; use
+/// [`merge_tags`] for that.
+pub fn rename_tag(
+    conn: &Connection,
+    tag_id: i64,
+    new_name: &str,
+    dry_run: bool,
+) -> Result<TagChangeSummary> {
+    let normalized = new_name.trim().to_lowercase();
+    if normalized.is_empty() {
+        anyhow::bail!("Tag name cannot be empty");
     }
-
-    #[test]
-    fn test_list_all_tags() {
-        let conn = setup();
-        get_or_create_tag(&conn, "portrait").unwrap();
-        get_or_create_tag(&conn, "landscape").unwrap();
-        get_or_create_tag(&conn, "anime").unwrap();
-
-        let tags = list_all_tags(&conn).unwrap();
-        assert_eq!(tags.len(), 3);
-        assert_eq!(tags[0].name, "anime");
-        assert_eq!(tags[1].name, "landscape");
-        assert_eq!(tags[2].name, "portrait");
+    let summary = tag_usage(conn, tag_id)?;
+    if let Some(existing) = get_tag_by_name(conn, &normalized)? {
+        if existing.id != tag_id {
+            anyhow::bail!("Tag '{}' already exists; merge instead", normalized);
+        }
     }
-
-    #[test]
-    fn test_add_and_get_image_tags() {
-        let conn = setup();
-        insert_test_image(&conn, "img-001");
-
-        add_image_tag(&conn, "img-001", "cat", "ai", Some(0.95)).unwrap();
-        add_image_tag(&conn, "img-001", "throne", "user", None).unwrap();
-
-        let tags = get_image_tags(&conn, "img-001").unwrap();
-        assert_eq!(tags.len(), 2);
-
-        let cat_tag = tags.iter().find(|t| t.name == "cat").unwrap();
-        assert_eq!(cat_tag.source.as_deref(), Some("ai"));
-        assert!((cat_tag.confidence.unwrap() - 0.95).abs() < 0.01);
+    if !dry_run {
+        conn.execute(
+            "UPDATE tags SET name = ?1 WHERE id = ?2",
+            params![normalized, tag_id],
+        )
+        .context("Failed to rename tag")?;
     }
+    Ok(summary)
+}
 
-    #[test]
-    fn test_remove_image_tag() {
-        let conn = setup();
-        insert_test_image(&conn, "img-001");
-
-        let tag_id = add_image_tag(&conn, "img-001", "cat", "user", None).unwrap();
-        remove_image_tag(&conn, "img-001", tag_id).unwrap();
-
-        let tags = get_image_tags(&conn, "img-001").unwrap();
-        assert_eq!(tags.len(), 0);
+/// Fold `source_id` into `target_id`: its images, seeds and implications
+/// move to the target and the source tag is deleted. Images and seeds that
+/// already had both keep their target row. The summary counts everything
+/// tagged with the source; with `dry_run` nothing is written.
+pub fn merge_tags(
+    conn: &Connection,
+    source_id: i64,
+    target_id: i64,
+    dry_run: bool,
+) -> Result<TagChangeSummary> {
+    if source_id == target_id {
+        anyhow::bail!("Cannot merge a tag into itself");
     }
-
-    #[test]
-    fn test_delete_tag() {
-        let conn = setup();
-        insert_test_image(&conn, "img-001");
-
-        let tag_id = add_image_tag(&conn, "img-001", "cat", "user", None).unwrap();
-        delete_tag(&conn, tag_id).unwrap();
-
-        let tags = get_image_tags(&conn, "img-001").unwrap();
-        assert_eq!(tags.len(), 0);
-
-        let all_tags = list_all_tags(&conn).unwrap();
-        assert_eq!(all_tags.len(), 0);
+    let summary = tag_usage(conn, source_id)?;
+    tag_usage(conn, target_id)?;
+    if dry_run {
+        return Ok(summary);
     }
 
-    #[test]
-    fn test_search_tags() {
-        let conn = setup();
-        get_or_create_tag(&conn, "portrait").unwrap();
-        get_or_create_tag(&conn, "landscape portrait").unwrap();
-        get_or_create_tag(&conn, "anime").unwrap();
+    let tx = conn
+        .unchecked_transaction()
+        .context("Failed to start merge transaction")?;
+    tx.execute(
+        "INSERT OR IGNORE INTO image_tags (image_id, tag_id, source, confidence)
+         SELECT image_id, ?2, source, confidence FROM image_tags WHERE tag_id = ?1",
+        params![source_id, target_id],
+    )
+    .context("Failed to move image tags")?;
+    tx.execute(
+        "INSERT OR IGNORE INTO seed_tags (seed_id, tag_id)
+         SELECT seed_id, ?2 FROM seed_tags WHERE tag_id = ?1",
+        params![source_id, target_id],
+    )
+    .context("Failed to move seed tags")?;
+    tx.execute(
+        "INSERT OR IGNORE INTO tag_implications (tag_id, implied_tag_id)
+         SELECT ?2, implied_tag_id FROM tag_implications
+         WHERE tag_id = ?1 AND implied_tag_id != ?2
+         UNION
+         SELECT tag_id, ?2 FROM tag_implications
+         WHERE implied_tag_id = ?1 AND tag_id != ?2",
+        params![source_id, target_id],
+    )
+    .context("Failed to move tag implications")?;
+    delete_tag(&tx, source_id)?;
+    tx.commit().context("Failed to commit tag merge")?;
+    Ok(summary)
+}
 
-        let results = search_tags(&conn, "port").unwrap();
-        assert_eq!(results.len(), 2);
-    }
+/// How many images and seeds carry a tag. Errors if the tag doesn't exist.
+fn tag_usage(conn: &Connection, tag_id: i64) -> Result<TagChangeSummary> {
+    conn.query_row(
+        "SELECT (SELECT COUNT(*) FROM image_tags WHERE tag_id = ?1),
+                (SELECT COUNT(*) FROM seed_tags WHERE tag_id = ?1)
+         FROM tags WHERE id = ?1",
+        params![tag_id],
+        |row| {
+            Ok(TagChangeSummary {
+                images: row.get(0)?,
+                seeds: row.get(1)?,
+            })
+        },
+    )
+    .optional()
+    .context("Failed to count tag usage")?
+    .with_context(|| format!("Tag {} not found", tag_id))
 }
+
+#[cfg(test)]
+#[path = "tags_test.rs"]
+mod tests;
@@ -0,0 +1,265 @@
+use super::*;
+use crate::db;
+use crate::db::images;
+use crate::db::images::tests::make_test_image;
+
+fn setup() -> Connection {
+    db::open_memory_database().unwrap()
+}
+
+fn insert_test_image(conn: &Connection, id: &str) {
+    images::insert_image(conn, &make_test_image(id)).unwrap();
+}
+
+#[test]
+fn test_get_or_create_tag() {
+    let conn = setup();
+    let id1 = get_or_create_tag(&conn, "portrait").unwrap();
+    let id2 = get_or_create_tag(&conn, "portrait").unwrap();
+    assert_eq!(id1, id2);
+
+    let id3 = get_or_create_tag(&conn, "  Portrait  ").unwrap();
+    assert_eq!(id1, id3);
+}
+
+#[test]
+fn test_get_tag_by_name() {
+    let conn = setup();
+    get_or_create_tag(&conn, "landscape").unwrap();
+
+    let tag = get_tag_by_name(&conn, "landscape").unwrap().unwrap();
+    assert_eq!(tag.name, "landscape");
+
+    let none = get_tag_by_name(&conn, "nonexistent").unwrap();
+    assert!(none.is_none());
+}
+
+#[test]
+fn test_list_all_tags() {
+    let conn = setup();
+    get_or_create_tag(&conn, "portrait").unwrap();
+    get_or_create_tag(&conn, "landscape").unwrap();
+    get_or_create_tag(&conn, "anime").unwrap();
+
+    let tags = list_all_tags(&conn).unwrap();
+    assert_eq!(tags.len(), 3);
+    assert_eq!(tags[0].name, "anime");
+    assert_eq!(tags[1].name, "landscape");
+    assert_eq!(tags[2].name, "portrait");
+}
+
+#[test]
+fn test_add_and_get_image_tags() {
+    let conn = setup();
+    insert_test_image(&conn, "img-001");
+
+    add_image_tag(&conn, "img-001", "cat", "ai", Some(0.95)).unwrap();
+    add_image_tag(&conn, "img-001", "throne", "user", None).unwrap();
+
+    let tags = get_image_tags(&conn, "img-001").unwrap();
+    assert_eq!(tags.len(), 2);
+
+    let cat_tag = tags.iter().find(|t| t.name == "cat").unwrap();
+    assert_eq!(cat_tag.source.as_deref(), Some("ai"));
+    assert!((cat_tag.confidence.unwrap() - 0.95).abs() < 0.01);
+}
+
+#[test]
+fn test_remove_image_tag() {
+    let conn = setup();
+    insert_test_image(&conn, "img-001");
+
+    let tag_id = add_image_tag(&conn, "img-001", "cat", "user", None).unwrap();
+    remove_image_tag(&conn, "img-001", tag_id).unwrap();
+
+    let tags = get_image_tags(&conn, "img-001").unwrap();
+    assert_eq!(tags.len(), 0);
+}
+
+#[test]
+fn test_delete_tag() {
+    let conn = setup();
+    insert_test_image(&conn, "img-001");
+
+    let tag_id = add_image_tag(&conn, "img-001", "cat", "user", None).unwrap();
+    delete_tag(&conn, tag_id).unwrap();
+
+    let tags = get_image_tags(&conn, "img-001").unwrap();
+    assert_eq!(tags.len(), 0);
+
+    let all_tags = list_all_tags(&conn).unwrap();
+    assert_eq!(all_tags.len(), 0);
+}
+
+#[test]
+fn test_search_tags() {
+    let conn = setup();
+    get_or_create_tag(&conn, "portrait").unwrap();
+    get_or_create_tag(&conn, "landscape portrait").unwrap();
+    get_or_create_tag(&conn, "anime").unwrap();
+
+    let results = search_tags(&conn, "port").unwrap();
+    assert_eq!(results.len(), 2);
+}
+
+fn tagged_seed(conn: &Connection, tag: &str) -> i64 {
+    conn.execute(
+        "INSERT INTO seeds (seed_value, comment) VALUES (42, 'test')",
+        [],
+    )
+    .unwrap();
+    let seed_id = conn.last_insert_rowid();
+    db::seeds::add_seed_tag(conn, seed_id, tag).unwrap();
+    seed_id
+}
+
+#[test]
+fn test_rename_tag_dry_run_counts_without_changing() {
+    let conn = setup();
+    insert_test_image(&conn, "img-001");
+    insert_test_image(&conn, "img-002");
+    let tag_id = add_image_tag(&conn, "img-001", "kitty", "user", None).unwrap();
+    add_image_tag(&conn, "img-002", "kitty", "ai", Some(0.8)).unwrap();
+    tagged_seed(&conn, "kitty");
+
+    let preview = rename_tag(&conn, tag_id, "Cat", true).unwrap();
+    assert_eq!(
+        preview,
+        TagChangeSummary {
+            images: 2,
+            seeds: 1
+        }
+    );
+    assert!(get_tag_by_name(&conn, "cat").unwrap().is_none());
+    assert_eq!(get_image_tags(&conn, "img-001").unwrap()[0].name, "kitty");
+
+    let applied = rename_tag(&conn, tag_id, "Cat", false).unwrap();
+    assert_eq!(applied, preview);
+    assert_eq!(get_image_tags(&conn, "img-002").unwrap()[0].name, "cat");
+}
+
+#[test]
+fn test_rename_tag_onto_existing_name_errors() {
+    let conn = setup();
+    let kitty = get_or_create_tag(&conn, "kitty").unwrap();
+    get_or_create_tag(&conn, "cat").unwrap();
+    assert!(rename_tag(&conn, kitty, "cat", true).is_err());
+    assert!(rename_tag(&conn, kitty, "  ", false).is_err());
+    assert!(rename_tag(&conn, 999, "dog", true).is_err());
+    // Case-only rename of the same tag is fine
+    assert!(rename_tag(&conn, kitty, "KITTY", false).is_ok());
+}
+
+#[test]
+fn test_merge_tags_dry_run_counts_without_changing() {
+    let conn = setup();
+    insert_test_image(&conn, "img-001");
+    insert_test_image(&conn, "img-002");
+    let kitty = add_image_tag(&conn, "img-001", "kitty", "user", None).unwrap();
+    add_image_tag(&conn, "img-002", "kitty", "user", None).unwrap();
+    let cat = add_image_tag(&conn, "img-002", "cat", "user", None).unwrap();
+    tagged_seed(&conn, "kitty");
+
+    let preview = merge_tags(&conn, kitty, cat, true).unwrap();
+    assert_eq!(
+        preview,
+        TagChangeSummary {
+            images: 2,
+            seeds: 1
+        }
+    );
+    assert!(get_tag_by_name(&conn, "kitty").unwrap().is_some());
+    assert_eq!(get_image_tags(&conn, "img-001").unwrap()[0].id, kitty);
+    assert_eq!(get_image_tags(&conn, "img-002").unwrap().len(), 2);
+}
+
+#[test]
+fn test_merge_tags_moves_images_seeds_and_implications() {
+    let conn = setup();
+    insert_test_image(&conn, "img-001");
+    insert_test_image(&conn, "img-002");
+    let kitty = add_image_tag(&conn, "img-001", "kitty", "user", None).unwrap();
+    add_image_tag(&conn, "img-002", "kitty", "user", None).unwrap();
+    let cat = add_image_tag(&conn, "img-002", "cat", "user", None).unwrap();
+    let seed_id = tagged_seed(&conn, "kitty");
+    db::tag_implications::add_tag_implication(&conn, "kitty", "animal").unwrap();
+
+    let summary = merge_tags(&conn, kitty, cat, false).unwrap();
+    assert_eq!(
+        summary,
+        TagChangeSummary {
+            images: 2,
+            seeds: 1
+        }
+    );
+
+    assert!(get_tag_by_name(&conn, "kitty").unwrap().is_none());
+    for image_id in ["img-001", "img-002"] {
+        let tags = get_image_tags(&conn, image_id).unwrap();
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0].id, cat);
+    }
+    let seed_tag: i64 = conn
+        .query_row(
+            "SELECT tag_id FROM seed_tags WHERE seed_id = ?1",
+            params![seed_id],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(seed_tag, cat);
+
+    let implications = db::tag_implications::list_tag_implications(&conn).unwrap();
+    assert_eq!(implications.len(), 1);
+    assert_eq!(implications[0].tag_name, "cat");
+    assert_eq!(implications[0].implied_tag_name, "animal");
+
+    assert!(merge_tags(&conn, cat, cat, true).is_err());
+    assert!(merge_tags(&conn, cat, 999, true).is_err());
+}
+
+#[test]
+fn test_list_tags_with_counts() {
+    let conn = setup();
+    insert_test_image(&conn, "img-001");
+    insert_test_image(&conn, "img-002");
+    insert_test_image(&conn, "img-003");
+    add_image_tag(&conn, "img-001", "landscape", "user", None).unwrap();
+    add_image_tag(&conn, "img-002", "landscape", "user", None).unwrap();
+    add_image_tag(&conn, "img-003", "landscape", "user", None).unwrap();
+    add_image_tag(&conn, "img-001", "portrait", "user", None).unwrap();
+    images::soft_delete_image(&conn, "img-003").unwrap();
+
+    let seed_id = db::seeds::insert_seed(
+        &conn,
+        &crate::types::seeds::SeedEntry {
+            id: None,
+            seed_value: 42,
+            comment: "tagged seed".to_string(),
+            checkpoint: None,
+            sample_image_id: None,
+            created_at: None,
+            tags: None,
+            sample_filename: None,
+        },
+    )
+    .unwrap();
+    db::seeds::add_seed_tag(&conn, seed_id, "seed-only").unwrap();
+
+    let counts: Vec<(String, u32)> = list_tags_with_counts(&conn, false)
+        .unwrap()
+        .into_iter()
+        .map(|(tag, count)| (tag.name, count))
+        .collect();
+    assert_eq!(
+        counts,
+        vec![
+            ("landscape".to_string(), 2),
+            ("portrait".to_string(), 1),
+            ("seed-only".to_string(), 0),
+        ]
+    );
+
+    let with_deleted = list_tags_with_counts(&conn, true).unwrap();
+    assert_eq!(with_deleted[0].0.name, "landscape");
+    assert_eq!(with_deleted[0].1, 3);
+}
@@ -0,0 +1,114 @@
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+
+use super::image_filter::{build_filter_conditions, FilterSql};
+use crate::types::gallery::{GalleryFilter, TermCount};
+
+/// The most used comma-separated terms in the positive prompts of images
+/// matching the filter, most frequent first (ties alphabetical). Terms are
+/// normalized so `(Masterpiece:1.2)` and `masterpiece` count together.
+pub fn term_frequencies(
+    conn: &Connection,
+    filter: &GalleryFilter,
+    limit: usize,
+) -> Result<Vec<TermCount>> {
+    let FilterSql {
+        where_clause,
+        params: param_values,
+        ..
+    } = build_filter_conditions(conn, filter);
+    let sql = format!(
+        "SELECT positive_prompt FROM images WHERE {} AND positive_prompt IS NOT NULL",
+        where_clause
+    );
+    let params_ref: Vec<&dyn rusqlite::types::ToSql> =
+        param_values.iter().map(|p| p.as_ref()).collect();
+
+    let mut stmt = conn
+        .prepare(&sql)
+        .context("Failed to prepare term frequency query")?;
+    let rows = stmt
+        .query_map(params_ref.as_slice(), |row| row.get::<_, String>(0))
+        .context("Failed to execute term frequency query")?;
+
+    let mut counts: std::collections::HashMap<String, u32> = std::collections::HashMap::new();
+    for row in rows {
+        let prompt = row.context("Failed to read prompt row")?;
+        for term in prompt.split(',').filter_map(normalize_term) {
+            *counts.entry(term).or_default() += 1;
+        }
+    }
+
+    let mut terms: Vec<TermCount> = counts
+        .into_iter()
+        .map(|(term, count)| TermCount { term, count })
+        .collect();
+    terms.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.term.cmp(&b.term)));
+    terms.truncate(limit);
+    Ok(terms)
+}
+
+/// Lowercase a prompt term and strip emphasis brackets and a trailing
+/// `:weight`. `None` when nothing is left.
+fn normalize_term(raw: &str) -> Option<String> {
+    let mut term = raw
+        .trim()
+        .trim_matches(|c| matches!(c, '(' | ')' | '[' | ']'));
+    if let Some((text, weight)) = term.rsplit_once(':') {
+        if weight.trim().parse::<f64>().is_ok() {
+            term = text;
+        }
+    }
+    let term = term
+        .trim_matches(|c| matches!(c, '(' | ')' | '[' | ']'))
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    (!term.is_empty()).then_some(term)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::db::images::tests::make_test_image;
+    use crate::db::images::{insert_image, soft_delete_image};
+
+    fn setup() -> Connection {
+        db::open_memory_database().unwrap()
+    }
+
+    #[test]
+    fn test_term_frequencies_rank_common_terms_first() {
+        let conn = setup();
+        for (id, prompt) in [
+            ("img-1", "masterpiece, cat on throne, gold crown"),
+            ("img-2", "(Masterpiece:1.2), dog in garden"),
+            ("img-3", "masterpiece,  Cat on  throne , rare sparkle"),
+            ("img-4", "masterpiece, deleted only"),
+        ] {
+            let mut image = make_test_image(id);
+            image.positive_prompt = Some(prompt.to_string());
+            insert_image(&conn, &image).unwrap();
+        }
+        soft_delete_image(&conn, "img-4").unwrap();
+
+        let terms = term_frequencies(&conn, &GalleryFilter::default(), 10).unwrap();
+        assert_eq!(
+            terms[0],
+            TermCount {
+                term: "masterpiece".to_string(),
+                count: 3
+            }
+        );
+        assert_eq!(terms[1].term, "cat on throne");
+        assert_eq!(terms[1].count, 2);
+        let rare = terms.iter().find(|t| t.term == "rare sparkle").unwrap();
+        assert_eq!(rare.count, 1);
+        assert!(!terms.iter().any(|t| t.term == "deleted only"));
+
+        let top = term_frequencies(&conn, &GalleryFilter::default(), 1).unwrap();
+        assert_eq!(top.len(), 1);
+    }
+}
@@ -0,0 +1,108 @@
+use anyhow::Result;
+use rusqlite::Connection;
+
+use crate::db;
+use crate::types::pipeline::PipelineResult;
+
+/// Map a reviewer fidelity score (0–100) to stars. Tops out at 4 so that
+/// 5 stars stays a human judgement.
+pub fn fidelity_to_stars(score: u32) -> u32 {
+    match score {
+        80.. => 4,
+        60..=79 => 3,
+        40..=59 => 2,
+        _ => 1,
+    }
+}
+
+/// The reviewer's fidelity score recorded in a job's serialized pipeline log.
+pub fn fidelity_from_pipeline_log(pipeline_log: &str) -> Option<u32> {
+    let result: PipelineResult = serde_json::from_str(pipeline_log).ok()?;
+    result.stages.reviewer?.fidelity_score
+}
+
+/// Give an image an initial rating from its pipeline's fidelity score,
+/// unless it has already been rated. Returns the rating applied, if any.
+pub fn apply_fidelity_rating(
+    conn: &Connection,
+    image_id: &str,
+    pipeline_log: &str,
+) -> Result<Option<u32>> {
+    let Some(score) = fidelity_from_pipeline_log(pipeline_log) else {
+        return Ok(None);
+    };
+    let stars = fidelity_to_stars(score);
+    let applied = db::images::set_rating_if_unrated(conn, image_id, stars)?;
+    Ok(applied.then_some(stars))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::images::tests::make_test_image;
+    use crate::pipeline::engine::tests::make_test_result;
+    use crate::types::pipeline::ReviewerOutput;
+
+    fn log_with_fidelity(score: u32) -> String {
+        let mut result = make_test_result();
+        result.stages.reviewer = Some(ReviewerOutput {
+            approved: true,
+            issues: None,
+            suggested_positive: None,
+            suggested_negative: None,
+            fidelity_score: Some(score),
+            duration_ms: 100,
+            model: "qwen2.5:7b".to_string(),
+            parse_retries: 0,
+            raw_response: None,
+        });
+        serde_json::to_string(&result).unwrap()
+    }
+
+    #[test]
+    fn test_fidelity_to_stars() {
+        assert_eq!(fidelity_to_stars(100), 4);
+        assert_eq!(fidelity_to_stars(80), 4);
+        assert_eq!(fidelity_to_stars(79), 3);
+        assert_eq!(fidelity_to_stars(60), 3);
+        assert_eq!(fidelity_to_stars(45), 2);
+        assert_eq!(fidelity_to_stars(39), 1);
+        assert_eq!(fidelity_to_stars(0), 1);
+    }
+
+    #[test]
+    fn test_apply_rates_unrated_image() {
+        let conn = db::open_memory_database().unwrap();
+        db::images::insert_image(&conn, &make_test_image("img-001")).unwrap();
+
+        let applied = apply_fidelity_rating(&conn, "img-001", &log_with_fidelity(85)).unwrap();
+        assert_eq!(applied, Some(4));
+        let image = db::images::get_image(&conn, "img-001").unwrap().unwrap();
+        assert_eq!(image.rating, Some(4));
+    }
+
+    #[test]
+    fn test_apply_keeps_existing_user_rating() {
+        let conn = db::open_memory_database().unwrap();
+        db::images::insert_image(&conn, &make_test_image("img-001")).unwrap();
+        db::images::update_image_rating(&conn, "img-001", Some(2)).unwrap();
+
+        let applied = apply_fidelity_rating(&conn, "img-001", &log_with_fidelity(95)).unwrap();
+        assert_eq!(applied, None);
+        let image = db::images::get_image(&conn, "img-001").unwrap().unwrap();
+        assert_eq!(image.rating, Some(2));
+    }
+
+    #[test]
+    fn test_no_reviewer_score_leaves_image_unrated() {
+        let conn = db::open_memory_database().unwrap();
+        db::images::insert_image(&conn, &make_test_image("img-001")).unwrap();
+        let log = serde_json::to_string(&make_test_result()).unwrap();
+
+        assert_eq!(apply_fidelity_rating(&conn, "img-001", &log).unwrap(), None);
+        assert_eq!(
+            apply_fidelity_rating(&conn, "img-001", "not json").unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+
+use crate::db;
+use crate::gallery::storage;
+use crate::types::config::AppConfig;
+
+/// Fail when `gallery.safe_mode` is on. Every command that destroys images
+/// for good (rather than moving them to the trash) checks this first.
+pub fn ensure_destructive_allowed(config: &AppConfig) -> Result<()> {
+    if config.gallery.safe_mode {
+        anyhow::bail!("Safe mode is on; permanent deletes are disabled");
+    }
+    Ok(())
+}
+
+/// Remove an image's row and its files. Refused in safe mode; moving the
+/// image to the trash still works there.
+pub fn permanently_delete(conn: &Connection, config: &AppConfig, id: &str) -> Result<()> {
+    ensure_destructive_allowed(config)?;
+
+    // Get filename before deleting from DB
+    let image = db::images::get_image(conn, id).context("Failed to get image")?;
+    db::images::permanently_delete_image(conn, id)?;
+
+    if let Some(img) = image {
+        storage::delete_image_files_for(config, &img.filename)
+            .context("DB row deleted but file cleanup failed")?;
+    }
+    Ok(())
+}
+
+/// Permanently delete images that have sat in the trash longer than
+/// `gallery.trash_retention_days`, files included. Skipped in safe mode.
+/// A file that can't be removed is logged; its row is gone either way.
+/// Returns how many images were purged.
+pub fn purge_expired_trash(conn: &Connection, config: &AppConfig) -> Result<usize> {
+    if ensure_destructive_allowed(config).is_err() {
+        return Ok(0);
+    }
+    let filenames = db::images::purge_expired_trash(conn, config.gallery.trash_retention_days)?;
+    for filename in &filenames {
+        if let Err(e) = storage::delete_image_files_for(config, filename) {
+            eprintln!(
+                "[gallery] Failed to remove purged image {}: {:#}",
+                filename, e
+            );
+        }
+    }
+    Ok(filenames.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::images::tests::make_test_image;
+
+    fn setup(safe_mode: bool) -> (tempfile::TempDir, AppConfig, Connection) {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = AppConfig::default();
+        config.storage.image_directory = tmp.path().to_string_lossy().to_string();
+        config.gallery.safe_mode = safe_mode;
+        let conn = db::open_memory_database().unwrap();
+        db::images::insert_image(&conn, &make_test_image("img-1")).unwrap();
+        let originals = storage::originals_dir_for(&config);
+        std::fs::create_dir_all(&originals).unwrap();
+        std::fs::write(originals.join("img-1.png"), b"png bytes").unwrap();
+        (tmp, config, conn)
+    }
+
+    #[test]
+    fn test_safe_mode_rejects_permanent_delete() {
+        let (_tmp, config, conn) = setup(true);
+
+        let err = permanently_delete(&conn, &config, "img-1").unwrap_err();
+        assert!(err.to_string().contains("Safe mode"));
+        assert!(db::images::get_image(&conn, "img-1").unwrap().is_some());
+        assert!(storage::get_image_path_for(&config, "img-1.png").exists());
+
+        // Moving to the trash is still allowed
+        db::images::soft_delete_image(&conn, "img-1").unwrap();
+        assert!(
+            db::images::get_image(&conn, "img-1")
+                .unwrap()
+                .unwrap()
+                .deleted
+        );
+    }
+
+    fn age_trash(conn: &Connection, id: &str, days: u32) {
+        conn.execute(
+            "UPDATE images SET deleted_at = datetime('now', ?1) WHERE id = ?2",
+            rusqlite::params![format!("-{} days", days), id],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_purge_expired_trash_removes_old_files() {
+        let (_tmp, mut config, conn) = setup(false);
+        config.gallery.trash_retention_days = 30;
+        db::images::soft_delete_image(&conn, "img-1").unwrap();
+        age_trash(&conn, "img-1", 31);
+
+        assert_eq!(purge_expired_trash(&conn, &config).unwrap(), 1);
+        assert!(db::images::get_image(&conn, "img-1").unwrap().is_none());
+        assert!(!storage::get_image_path_for(&config, "img-1.png").exists());
+    }
+
+    #[test]
+    fn test_purge_expired_trash_skipped_in_safe_mode() {
+        let (_tmp, mut config, conn) = setup(true);
+        config.gallery.trash_retention_days = 30;
+        db::images::soft_delete_image(&conn, "img-1").unwrap();
+        age_trash(&conn, "img-1", 31);
+
+        assert_eq!(purge_expired_trash(&conn, &config).unwrap(), 0);
+        assert!(db::images::get_image(&conn, "img-1").unwrap().is_some());
+        assert!(storage::get_image_path_for(&config, "img-1.png").exists());
+    }
+
+    #[test]
+    fn test_permanent_delete_removes_row_and_files() {
+        let (_tmp, config, conn) = setup(false);
+
+        permanently_delete(&conn, &config, "img-1").unwrap();
+        assert!(db::images::get_image(&conn, "img-1").unwrap().is_none());
+        assert!(!storage::get_image_path_for(&config, "img-1.png").exists());
+    }
+}
@@ -0,0 +1,137 @@
+use anyhow::Result;
+use rusqlite::Connection;
+
+use crate::db;
+use crate::gallery::storage;
+use crate::types::config::AppConfig;
+use crate::types::gallery::ImageEntry;
+
+/// Hash distance at or below which two images count as near-duplicates.
+/// Re-rolls with a nudged seed usually land within a few bits.
+pub const DEFAULT_MAX_DISTANCE: u32 = 5;
+
+/// Hash images saved before perceptual hashing existed, reading their
+/// originals from disk. Images whose file is missing or unreadable are
+/// skipped. Returns how many were hashed.
+pub fn backfill_hashes(conn: &Connection, config: &AppConfig) -> Result<usize> {
+    let mut hashed = 0;
+    for (id, filename) in db::phash::list_unhashed_images(conn)? {
+        let Some(path) = storage::locate_original(config, &filename) else {
+            continue;
+        };
+        let hash = std::fs::read(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| storage::perceptual_hash(&bytes));
+        match hash {
+            Ok(hash) => {
+                db::phash::set_phash(conn, &id, hash)?;
+                hashed += 1;
+            }
+            Err(e) => eprintln!("[gallery] Could not hash {}: {:#}", filename, e),
+        }
+    }
+    Ok(hashed)
+}
+
+/// Groups of near-duplicate images across the gallery. Images are grouped
+/// transitively: A and C share a group when both are close to B. Groups are
+/// ordered largest first; images within a group oldest first.
+pub fn find_duplicate_groups(conn: &Connection, max_distance: u32) -> Result<Vec<Vec<ImageEntry>>> {
+    let hashes = db::phash::list_phashes(conn)?;
+    let mut parent: Vec<usize> = (0..hashes.len()).collect();
+    for i in 0..hashes.len() {
+        for j in (i + 1)..hashes.len() {
+            if storage::hash_distance(hashes[i].1, hashes[j].1) <= max_distance {
+                let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+                parent[a.max(b)] = a.min(b);
+            }
+        }
+    }
+
+    let mut members: std::collections::BTreeMap<usize, Vec<&str>> = Default::default();
+    for (i, (id, _)) in hashes.iter().enumerate() {
+        let group = root(&mut parent, i);
+        members.entry(group).or_default().push(id);
+    }
+
+    let mut groups = Vec::new();
+    for ids in members.into_values().filter(|ids| ids.len() > 1) {
+        let mut images = Vec::new();
+        for id in ids {
+            if let Some(image) = db::images::get_image(conn, id)? {
+                images.push(image);
+            }
+        }
+        images.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+        groups.push(images);
+    }
+    groups.sort_by_key(|images| std::cmp::Reverse(images.len()));
+    Ok(groups)
+}
+
+fn root(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::images::tests::make_test_image;
+
+    fn insert_hashed(conn: &Connection, id: &str, hash: u64) {
+        db::images::insert_image(conn, &make_test_image(id)).unwrap();
+        db::phash::set_phash(conn, id, hash).unwrap();
+    }
+
+    #[test]
+    fn test_groups_near_duplicates_transitively() {
+        let conn = db::open_memory_database().unwrap();
+        insert_hashed(&conn, "a", 0b0000);
+        insert_hashed(&conn, "b", 0b0111);
+        insert_hashed(&conn, "c", 0b0111_1111);
+        insert_hashed(&conn, "d", u64::MAX);
+        insert_hashed(&conn, "e", u64::MAX ^ 1);
+        insert_hashed(&conn, "lonely", 0xF0F0_F0F0_0000_0000);
+
+        let groups: Vec<Vec<String>> = find_duplicate_groups(&conn, 4)
+            .unwrap()
+            .into_iter()
+            .map(|g| {
+                let mut ids: Vec<String> = g.into_iter().map(|i| i.id).collect();
+                ids.sort();
+                ids
+            })
+            .collect();
+        assert_eq!(groups, vec![vec!["a", "b", "c"], vec!["d", "e"]]);
+    }
+
+    #[test]
+    fn test_backfill_hashes_reads_originals() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = AppConfig::default();
+        config.storage.image_directory = tmp.path().to_string_lossy().to_string();
+        let conn = db::open_memory_database().unwrap();
+
+        let mut png = std::io::Cursor::new(Vec::new());
+        image::RgbImage::new(16, 16)
+            .write_to(&mut png, image::ImageFormat::Png)
+            .unwrap();
+        storage::save_image_from_bytes_with_config(&config, png.get_ref(), "old.png").unwrap();
+        db::images::insert_image(&conn, &make_test_image("old")).unwrap();
+        db::images::insert_image(&conn, &make_test_image("gone")).unwrap();
+
+        assert_eq!(backfill_hashes(&conn, &config).unwrap(), 1);
+        assert_eq!(
+            db::phash::list_phashes(&conn).unwrap(),
+            vec![("old".to_string(), 0)]
+        );
+        assert_eq!(
+            db::phash::list_unhashed_images(&conn).unwrap(),
+            vec![("gone".to_string(), "gone.png".to_string())]
+        );
+    }
+}
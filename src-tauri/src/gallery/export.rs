@@ -1,6 +1,8 @@
 use anyhow::{Context, Result};
+use serde::Serialize;
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use zip::write::FileOptions;
 use zip::ZipWriter;
 
@@ -11,8 +13,8 @@ use crate::types::gallery::ImageEntry;
 /// Export manifest entry — included in the ZIP as JSON.
 #[derive(Debug, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
-struct ManifestEntry {
-    filename: String,
+pub(super) struct ManifestEntry {
+    pub(super) filename: String,
     positive_prompt: Option<String>,
     negative_prompt: Option<String>,
     original_idea: Option<String>,
@@ -26,6 +28,62 @@ struct ManifestEntry {
     seed: Option<i64>,
     rating: Option<u32>,
     caption: Option<String>,
+    /// Tag names, from the image's pre-fetched `tags`.
+    tags: Vec<String>,
+}
+
+impl ManifestEntry {
+    pub(super) fn from_image(image: &ImageEntry) -> Self {
+        Self {
+            filename: image.filename.clone(),
+            positive_prompt: image.positive_prompt.clone(),
+            negative_prompt: image.negative_prompt.clone(),
+            original_idea: image.original_idea.clone(),
+            checkpoint: image.checkpoint.clone(),
+            width: image.width,
+            height: image.height,
+            steps: image.steps,
+            cfg_scale: image.cfg_scale,
+            sampler: image.sampler.clone(),
+            scheduler: image.scheduler.clone(),
+            seed: image.seed,
+            rating: image.rating,
+            caption: image.caption.clone(),
+            tags: image
+                .tags
+                .iter()
+                .flatten()
+                .map(|tag| tag.name.clone())
+                .collect(),
+        }
+    }
+}
+
+/// Per-image progress payload, emitted as `export:progress`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportProgressEvent {
+    pub done: usize,
+    pub total: usize,
+}
+
+/// Validate a folder to export into: absolute, no `..`, and an existing
+/// directory. Its contents are left alone apart from the exported files.
+pub fn validate_export_dir(output_dir: &str) -> Result<PathBuf> {
+    if output_dir.is_empty() {
+        anyhow::bail!("Export folder is empty");
+    }
+    let path = PathBuf::from(output_dir);
+    if !path.is_absolute() {
+        anyhow::bail!("Export folder must be absolute, got: {}", path.display());
+    }
+    if path.to_string_lossy().contains("..") {
+        anyhow::bail!("Export folder must not contain '..': {}", path.display());
+    }
+    if !path.is_dir() {
+        anyhow::bail!("Export folder does not exist: {}", path.display());
+    }
+    Ok(path)
 }
 
 /// Validate that an export output path is safe to write to.
@@ -69,66 +127,101 @@ pub fn validate_export_path(output_path: &str) -> Result<std::path::PathBuf> {
 }
 
 /// Create a ZIP bundle containing the specified images and a JSON manifest.
-/// Returns the path to the created ZIP file.
+/// Tags come from each image's `tags`, so attach them before exporting.
 pub fn create_export_bundle(images: &[ImageEntry], output_path: &Path) -> Result<()> {
-    create_export_bundle_with_config(images, output_path, None)
+    create_export_bundle_with_config(images, output_path, None, true)
 }
 
+/// With `include_images` false only `manifest.json` and `manifest.csv` are
+/// written, for when the settings are wanted without the PNGs.
 pub fn create_export_bundle_with_config(
     images: &[ImageEntry],
     output_path: &Path,
     config: Option<&AppConfig>,
+    include_images: bool,
 ) -> Result<()> {
-    let file = std::fs::File::create(output_path)
-        .with_context(|| format!("Failed to create export file at {}", output_path.display()))?;
+    write_export_bundle(
+        images,
+        output_path,
+        config,
+        include_images,
+        &AtomicBool::new(false),
+        |_| {},
+    )
+}
+
+/// Write the bundle one image at a time, reporting progress after each and
+/// checking `cancelled` before the next. The archive is built beside
+/// `output_path` and only moved into place once finalized, so a cancelled
+/// or failed export leaves no file behind.
+pub fn write_export_bundle(
+    images: &[ImageEntry],
+    output_path: &Path,
+    config: Option<&AppConfig>,
+    include_images: bool,
+    cancelled: &AtomicBool,
+    on_progress: impl FnMut(ExportProgressEvent),
+) -> Result<()> {
+    let partial = partial_path(output_path);
+    let written = write_zip(
+        images,
+        &partial,
+        config,
+        include_images,
+        cancelled,
+        on_progress,
+    );
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&partial);
+        return Err(e);
+    }
+    std::fs::rename(&partial, output_path).with_context(|| {
+        format!(
+            "Failed to move finished export to {}",
+            output_path.display()
+        )
+    })
+}
+
+/// `export.zip` is written as `export.zip.part` until it's complete.
+fn partial_path(output_path: &Path) -> PathBuf {
+    let mut name = output_path.as_os_str().to_os_string();
+    name.push(".part");
+    PathBuf::from(name)
+}
+
+fn write_zip(
+    images: &[ImageEntry],
+    path: &Path,
+    config: Option<&AppConfig>,
+    include_images: bool,
+    cancelled: &AtomicBool,
+    mut on_progress: impl FnMut(ExportProgressEvent),
+) -> Result<()> {
+    let file = std::fs::File::create(path)
+        .with_context(|| format!("Failed to create export file at {}", path.display()))?;
 
     let mut zip = ZipWriter::new(file);
     let options = FileOptions::<()>::default().compression_method(zip::CompressionMethod::Stored);
 
     let mut manifest = Vec::new();
+    let total = images.len();
 
-    for image in images {
+    for (done, image) in images.iter().enumerate() {
+        if cancelled.load(Ordering::Relaxed) {
+            anyhow::bail!("Export cancelled after {} of {} images", done, total);
+        }
         storage::validate_filename(&image.filename)
             .with_context(|| format!("Unsafe gallery filename in DB: {}", image.filename))?;
 
-        let image_path = if let Some(cfg) = config {
-            let p = storage::get_image_path_for(cfg, &image.filename);
-            if p.exists() {
-                p
-            } else {
-                storage::get_image_path(&image.filename)
-            }
-        } else {
-            storage::get_image_path(&image.filename)
-        };
-
-        if image_path.exists() {
-            let image_bytes = std::fs::read(&image_path)
-                .with_context(|| format!("Failed to read {}", image_path.display()))?;
-
-            storage::validate_filename(&image.filename)
-                .with_context(|| format!("Unsafe ZIP filename: {}", image.filename))?;
-            zip.start_file(&image.filename, options)
-                .context("Failed to add file to ZIP")?;
-            zip.write_all(&image_bytes)
-                .context("Failed to write image to ZIP")?;
+        if include_images {
+            add_image_file(&mut zip, image, config, options)?;
         }
 
-        manifest.push(ManifestEntry {
-            filename: image.filename.clone(),
-            positive_prompt: image.positive_prompt.clone(),
-            negative_prompt: image.negative_prompt.clone(),
-            original_idea: image.original_idea.clone(),
-            checkpoint: image.checkpoint.clone(),
-            width: image.width,
-            height: image.height,
-            steps: image.steps,
-            cfg_scale: image.cfg_scale,
-            sampler: image.sampler.clone(),
-            scheduler: image.scheduler.clone(),
-            seed: image.seed,
-            rating: image.rating,
-            caption: image.caption.clone(),
+        manifest.push(ManifestEntry::from_image(image));
+        on_progress(ExportProgressEvent {
+            done: done + 1,
+            total,
         });
     }
 
@@ -151,14 +244,46 @@ pub fn create_export_bundle_with_config(
     Ok(())
 }
 
-fn build_csv_manifest(entries: &[ManifestEntry]) -> String {
+/// Copy an image's original into the ZIP. Missing files are skipped.
+fn add_image_file(
+    zip: &mut ZipWriter<std::fs::File>,
+    image: &ImageEntry,
+    config: Option<&AppConfig>,
+    options: FileOptions<()>,
+) -> Result<()> {
+    let image_path = if let Some(cfg) = config {
+        let p = storage::get_image_path_for(cfg, &image.filename);
+        if p.exists() {
+            p
+        } else {
+            storage::get_image_path(&image.filename)
+        }
+    } else {
+        storage::get_image_path(&image.filename)
+    };
+
+    if image_path.exists() {
+        let image_bytes = std::fs::read(&image_path)
+            .with_context(|| format!("Failed to read {}", image_path.display()))?;
+
+        storage::validate_filename(&image.filename)
+            .with_context(|| format!("Unsafe ZIP filename: {}", image.filename))?;
+        zip.start_file(&image.filename, options)
+            .context("Failed to add file to ZIP")?;
+        zip.write_all(&image_bytes)
+            .context("Failed to write image to ZIP")?;
+    }
+    Ok(())
+}
+
+pub(super) fn build_csv_manifest(entries: &[ManifestEntry]) -> String {
     let mut csv = String::from(
-        "filename,positivePrompt,negativePrompt,checkpoint,width,height,steps,cfgScale,sampler,scheduler,seed,rating,caption\n"
+        "filename,positivePrompt,negativePrompt,checkpoint,width,height,steps,cfgScale,sampler,scheduler,seed,rating,caption,tags\n"
     );
 
     for e in entries {
         csv.push_str(&format!(
-            "{},{},{},{},{},{},{},{},{},{},{},{},{}\n",
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{}\n",
             csv_escape(&e.filename),
             csv_escape(e.positive_prompt.as_deref().unwrap_or("")),
             csv_escape(e.negative_prompt.as_deref().unwrap_or("")),
@@ -172,6 +297,7 @@ fn build_csv_manifest(entries: &[ManifestEntry]) -> String {
             e.seed.map(|v| v.to_string()).unwrap_or_default(),
             e.rating.map(|v| v.to_string()).unwrap_or_default(),
             csv_escape(e.caption.as_deref().unwrap_or("")),
+            csv_escape(&e.tags.join(";")),
         ));
     }
 
@@ -187,150 +313,5 @@ fn csv_escape(s: &str) -> String {
 }
 
 #[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_csv_escape_no_special() {
-        assert_eq!(csv_escape("hello"), "hello");
-    }
-
-    #[test]
-    fn test_csv_escape_with_comma() {
-        assert_eq!(csv_escape("hello, world"), "\"hello, world\"");
-    }
-
-    #[test]
-    fn test_csv_escape_with_quotes() {
-        assert_eq!(csv_escape("say \"hi\""), "\"say \"\"hi\"\"\"");
-    }
-
-    #[test]
-    fn test_build_csv_manifest() {
-        let entries = vec![ManifestEntry {
-            filename: "test.png".to_string(),
-            positive_prompt: Some("a cat".to_string()),
-            negative_prompt: Some("lowres".to_string()),
-            original_idea: None,
-            checkpoint: Some("ds8".to_string()),
-            width: Some(512),
-            height: Some(768),
-            steps: Some(25),
-            cfg_scale: Some(7.5),
-            sampler: Some("dpmpp_2m".to_string()),
-            scheduler: Some("karras".to_string()),
-            seed: Some(42),
-            rating: Some(4),
-            caption: None,
-        }];
-        let csv = build_csv_manifest(&entries);
-        assert!(csv.contains("filename,"));
-        assert!(csv.contains("test.png"));
-        assert!(csv.contains("a cat"));
-    }
-
-    #[test]
-    fn test_create_export_bundle() {
-        let tmp = tempfile::tempdir().unwrap();
-        let zip_path = tmp.path().join("export.zip");
-
-        // Empty export (no actual image files on disk)
-        let images = vec![ImageEntry {
-            id: "img-1".to_string(),
-            filename: "nonexistent.png".to_string(),
-            created_at: "2026-01-15T10:00:00".to_string(),
-            positive_prompt: Some("a cat".to_string()),
-            negative_prompt: None,
-            original_idea: None,
-            checkpoint: None,
-            width: None,
-            height: None,
-            steps: None,
-            cfg_scale: None,
-            sampler: None,
-            scheduler: None,
-            seed: None,
-            pipeline_log: None,
-            selected_concept: None,
-            auto_approved: false,
-            caption: None,
-            caption_edited: false,
-            rating: None,
-            favorite: false,
-            deleted: false,
-            user_note: None,
-            tags: None,
-        }];
-
-        create_export_bundle(&images, &zip_path).unwrap();
-        assert!(zip_path.exists());
-
-        // Verify ZIP contains manifest
-        let file = std::fs::File::open(&zip_path).unwrap();
-        let mut archive = zip::ZipArchive::new(file).unwrap();
-        let names: Vec<String> = (0..archive.len())
-            .map(|i| archive.by_index(i).unwrap().name().to_string())
-            .collect();
-        assert!(names.contains(&"manifest.json".to_string()));
-        assert!(names.contains(&"manifest.csv".to_string()));
-    }
-
-    #[test]
-    fn test_validate_export_path_valid() {
-        let tmp = tempfile::tempdir().unwrap();
-        let path = tmp.path().join("export.zip");
-        let result = super::validate_export_path(path.to_str().unwrap());
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap(), path);
-    }
-
-    #[test]
-    fn test_validate_export_path_empty() {
-        let result = super::validate_export_path("");
-        assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("empty"));
-    }
-
-    #[test]
-    fn test_validate_export_path_relative() {
-        let result = super::validate_export_path("relative/path/export.zip");
-        assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("absolute"));
-    }
-
-    #[test]
-    fn test_validate_export_path_traversal() {
-        let result = super::validate_export_path("/tmp/../etc/evil.zip");
-        assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains(".."));
-    }
-
-    #[test]
-    fn test_validate_export_path_no_zip_extension() {
-        let result = super::validate_export_path("/tmp/export.tar.gz");
-        assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains(".zip"));
-    }
-
-    #[test]
-    fn test_validate_export_path_no_extension() {
-        let result = super::validate_export_path("/tmp/export");
-        assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains(".zip"));
-    }
-
-    #[test]
-    fn test_validate_export_path_nonexistent_parent() {
-        let result = super::validate_export_path("/nonexistent/deeply/nested/dir/export.zip");
-        assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("does not exist"));
-    }
-
-    #[test]
-    fn test_validate_export_path_zip_case_insensitive() {
-        let tmp = tempfile::tempdir().unwrap();
-        let path = tmp.path().join("export.ZIP");
-        let result = super::validate_export_path(path.to_str().unwrap());
-        assert!(result.is_ok());
-    }
-}
+#[path = "export_test.rs"]
+mod tests;
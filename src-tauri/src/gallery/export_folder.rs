@@ -0,0 +1,281 @@
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use super::export::{build_csv_manifest, ExportProgressEvent, ManifestEntry};
+use crate::gallery::storage;
+use crate::types::config::AppConfig;
+use crate::types::gallery::{ImageEntry, SidecarFormat};
+
+/// Copy each image's original into `dir`, with an optional sidecar per image
+/// and a `manifest.csv` describing the exported files. Meant for folders that
+/// get synced elsewhere or used as a training dataset, so files already in
+/// `dir` with the same name are overwritten.
+///
+/// Images whose original is missing are left out. Two images that would land
+/// on the same stem (`a.png` and `a.jpg`, or case-only differences) get
+/// `_2`, `_3`… suffixes so their sidecars don't overwrite each other; the
+/// manifest lists the names as written. Returns how many images were copied.
+pub fn export_to_folder(
+    images: &[ImageEntry],
+    dir: &Path,
+    config: &AppConfig,
+    sidecar: SidecarFormat,
+    cancelled: &AtomicBool,
+    mut on_progress: impl FnMut(ExportProgressEvent),
+) -> Result<usize> {
+    let total = images.len();
+    let mut used_stems = HashSet::new();
+    let mut manifest = Vec::new();
+
+    for (done, image) in images.iter().enumerate() {
+        if cancelled.load(Ordering::Relaxed) {
+            anyhow::bail!("Export cancelled after {} of {} images", done, total);
+        }
+        storage::validate_filename(&image.filename)
+            .with_context(|| format!("Unsafe gallery filename in DB: {}", image.filename))?;
+
+        if let Some(source) = storage::locate_original(config, &image.filename) {
+            let (stem, filename) = unique_name(&image.filename, &mut used_stems);
+            std::fs::copy(&source, dir.join(&filename))
+                .with_context(|| format!("Failed to copy {}", source.display()))?;
+
+            let mut entry = ManifestEntry::from_image(image);
+            entry.filename = filename;
+            write_sidecar(dir, &stem, image, &entry, sidecar)?;
+            manifest.push(entry);
+        }
+        on_progress(ExportProgressEvent {
+            done: done + 1,
+            total,
+        });
+    }
+
+    let csv_path = dir.join("manifest.csv");
+    std::fs::write(&csv_path, build_csv_manifest(&manifest))
+        .with_context(|| format!("Failed to write {}", csv_path.display()))?;
+    Ok(manifest.len())
+}
+
+/// Stem and filename to export `filename` under, unique among `used`
+/// regardless of case.
+fn unique_name(filename: &str, used: &mut HashSet<String>) -> (String, String) {
+    let path = Path::new(filename);
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or(filename);
+    let mut candidate = stem.to_string();
+    let mut n = 1;
+    while !used.insert(candidate.to_lowercase()) {
+        n += 1;
+        candidate = format!("{}_{}", stem, n);
+    }
+    let name = match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) => format!("{}.{}", candidate, ext),
+        None => candidate.clone(),
+    };
+    (candidate, name)
+}
+
+fn write_sidecar(
+    dir: &Path,
+    stem: &str,
+    image: &ImageEntry,
+    entry: &ManifestEntry,
+    format: SidecarFormat,
+) -> Result<()> {
+    let (path, contents) = match format {
+        SidecarFormat::None => return Ok(()),
+        SidecarFormat::A1111Txt => (dir.join(format!("{}.txt", stem)), a1111_text(image)),
+        SidecarFormat::Json => (
+            dir.join(format!("{}.json", stem)),
+            serde_json::to_string_pretty(entry).context("Failed to serialize sidecar")?,
+        ),
+    };
+    std::fs::write(&path, contents).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// The Automatic1111 `parameters` text for a gallery image. Settings the
+/// image has no record of are left out of the settings line.
+fn a1111_text(image: &ImageEntry) -> String {
+    let mut text = image.positive_prompt.clone().unwrap_or_default();
+    if let Some(negative) = image.negative_prompt.as_deref().filter(|n| !n.is_empty()) {
+        text.push_str("\nNegative prompt: ");
+        text.push_str(negative);
+    }
+
+    let mut settings = Vec::new();
+    if let Some(steps) = image.steps {
+        settings.push(format!("Steps: {}", steps));
+    }
+    if let Some(sampler) = &image.sampler {
+        settings.push(format!("Sampler: {}", sampler));
+    }
+    if let Some(scheduler) = &image.scheduler {
+        settings.push(format!("Schedule type: {}", scheduler));
+    }
+    if let Some(cfg) = image.cfg_scale {
+        settings.push(format!("CFG scale: {}", cfg));
+    }
+    if let Some(seed) = image.seed {
+        settings.push(format!("Seed: {}", seed));
+    }
+    if let (Some(width), Some(height)) = (image.width, image.height) {
+        settings.push(format!("Size: {}x{}", width, height));
+    }
+    if let Some(checkpoint) = &image.checkpoint {
+        let model = Path::new(checkpoint)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or(checkpoint);
+        settings.push(format!("Model: {}", model));
+    }
+    if !settings.is_empty() {
+        text.push('\n');
+        text.push_str(&settings.join(", "));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::images::tests::make_test_image;
+
+    fn setup() -> (tempfile::TempDir, AppConfig, std::path::PathBuf) {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = AppConfig::default();
+        config.storage.image_directory = tmp.path().join("gallery").to_string_lossy().to_string();
+        std::fs::create_dir_all(storage::originals_dir_for(&config)).unwrap();
+        let out = tmp.path().join("out");
+        std::fs::create_dir_all(&out).unwrap();
+        (tmp, config, out)
+    }
+
+    fn image_with_file(config: &AppConfig, id: &str, filename: &str) -> ImageEntry {
+        std::fs::write(storage::originals_dir_for(config).join(filename), id).unwrap();
+        ImageEntry {
+            filename: filename.to_string(),
+            ..make_test_image(id)
+        }
+    }
+
+    fn export(
+        images: &[ImageEntry],
+        out: &Path,
+        config: &AppConfig,
+        sidecar: SidecarFormat,
+    ) -> usize {
+        export_to_folder(
+            images,
+            out,
+            config,
+            sidecar,
+            &AtomicBool::new(false),
+            |_| {},
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_a1111_sidecar_content() {
+        let (_tmp, config, out) = setup();
+        let mut image = image_with_file(&config, "fox", "fox.png");
+        image.positive_prompt = Some("a fox in snow".to_string());
+        image.negative_prompt = Some("blurry".to_string());
+        image.steps = Some(28);
+        image.sampler = Some("dpmpp_2m".to_string());
+        image.scheduler = Some("karras".to_string());
+        image.cfg_scale = Some(6.5);
+        image.seed = Some(42);
+        image.width = Some(832);
+        image.height = Some(1216);
+        image.checkpoint = Some("sdxl/juggernaut.safetensors".to_string());
+
+        assert_eq!(export(&[image], &out, &config, SidecarFormat::A1111Txt), 1);
+
+        assert_eq!(std::fs::read_to_string(out.join("fox.png")).unwrap(), "fox");
+        assert_eq!(
+            std::fs::read_to_string(out.join("fox.txt")).unwrap(),
+            "a fox in snow\nNegative prompt: blurry\nSteps: 28, Sampler: dpmpp_2m, \
+             Schedule type: karras, CFG scale: 6.5, Seed: 42, Size: 832x1216, Model: juggernaut"
+        );
+        assert!(out.join("manifest.csv").exists());
+        assert!(!out.join("fox.json").exists());
+    }
+
+    #[test]
+    fn test_json_sidecar_content() {
+        let (_tmp, config, out) = setup();
+        let mut image = image_with_file(&config, "cat", "cat.png");
+        image.positive_prompt = Some("a cat".to_string());
+        image.seed = Some(7);
+
+        export(&[image], &out, &config, SidecarFormat::Json);
+
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(out.join("cat.json")).unwrap()).unwrap();
+        assert_eq!(json["filename"], "cat.png");
+        assert_eq!(json["positivePrompt"], "a cat");
+        assert_eq!(json["seed"], 7);
+        assert!(!out.join("cat.txt").exists());
+    }
+
+    #[test]
+    fn test_colliding_stems_get_suffixes() {
+        let (_tmp, config, out) = setup();
+        let images = vec![
+            image_with_file(&config, "first", "a.png"),
+            image_with_file(&config, "second", "a.jpg"),
+            image_with_file(&config, "third", "A.webp"),
+        ];
+
+        assert_eq!(export(&images, &out, &config, SidecarFormat::A1111Txt), 3);
+
+        for (file, contents) in [
+            ("a.png", "first"),
+            ("a_2.jpg", "second"),
+            ("A_3.webp", "third"),
+        ] {
+            assert_eq!(std::fs::read_to_string(out.join(file)).unwrap(), contents);
+        }
+        for sidecar in ["a.txt", "a_2.txt", "A_3.txt"] {
+            assert!(out.join(sidecar).exists(), "missing {}", sidecar);
+        }
+        let csv = std::fs::read_to_string(out.join("manifest.csv")).unwrap();
+        let names: Vec<&str> = csv
+            .lines()
+            .skip(1)
+            .map(|line| line.split(',').next().unwrap())
+            .collect();
+        assert_eq!(names, vec!["a.png", "a_2.jpg", "A_3.webp"]);
+    }
+
+    #[test]
+    fn test_missing_originals_are_left_out() {
+        let (_tmp, config, out) = setup();
+        let present = image_with_file(&config, "here", "here.png");
+        let missing = make_test_image("gone");
+
+        let mut events = Vec::new();
+        let copied = export_to_folder(
+            &[missing, present],
+            &out,
+            &config,
+            SidecarFormat::None,
+            &AtomicBool::new(false),
+            |e| events.push(e),
+        )
+        .unwrap();
+
+        assert_eq!(copied, 1);
+        assert!(!out.join("gone.png").exists());
+        assert!(!out.join("here.txt").exists());
+        assert_eq!(
+            events.last(),
+            Some(&ExportProgressEvent { done: 2, total: 2 })
+        );
+    }
+}
@@ -0,0 +1,253 @@
+use super::*;
+use crate::db::images::tests::make_test_image;
+
+#[test]
+fn test_csv_escape_no_special() {
+    assert_eq!(csv_escape("hello"), "hello");
+}
+
+#[test]
+fn test_csv_escape_with_comma() {
+    assert_eq!(csv_escape("hello, world"), "\"hello, world\"");
+}
+
+#[test]
+fn test_csv_escape_with_quotes() {
+    assert_eq!(csv_escape("say \"hi\""), "\"say \"\"hi\"\"\"");
+}
+
+#[test]
+fn test_build_csv_manifest() {
+    let entries = vec![ManifestEntry {
+        filename: "test.png".to_string(),
+        positive_prompt: Some("a cat".to_string()),
+        negative_prompt: Some("lowres".to_string()),
+        original_idea: None,
+        checkpoint: Some("ds8".to_string()),
+        width: Some(512),
+        height: Some(768),
+        steps: Some(25),
+        cfg_scale: Some(7.5),
+        sampler: Some("dpmpp_2m".to_string()),
+        scheduler: Some("karras".to_string()),
+        seed: Some(42),
+        rating: Some(4),
+        caption: None,
+        tags: vec![],
+    }];
+    let csv = build_csv_manifest(&entries);
+    assert!(csv.contains("filename,"));
+    assert!(csv.contains("test.png"));
+    assert!(csv.contains("a cat"));
+}
+
+#[test]
+fn test_create_export_bundle() {
+    let tmp = tempfile::tempdir().unwrap();
+    let zip_path = tmp.path().join("export.zip");
+
+    // Empty export (no actual image files on disk)
+    let images = vec![ImageEntry {
+        filename: "nonexistent.png".to_string(),
+        positive_prompt: Some("a cat".to_string()),
+        ..make_test_image("img-1")
+    }];
+
+    create_export_bundle(&images, &zip_path).unwrap();
+    assert!(zip_path.exists());
+
+    // Verify ZIP contains manifest
+    let file = std::fs::File::open(&zip_path).unwrap();
+    let mut archive = zip::ZipArchive::new(file).unwrap();
+    let names: Vec<String> = (0..archive.len())
+        .map(|i| archive.by_index(i).unwrap().name().to_string())
+        .collect();
+    assert!(names.contains(&"manifest.json".to_string()));
+    assert!(names.contains(&"manifest.csv".to_string()));
+}
+
+fn zip_entry_names(path: &Path) -> Vec<String> {
+    let file = std::fs::File::open(path).unwrap();
+    let mut archive = zip::ZipArchive::new(file).unwrap();
+    (0..archive.len())
+        .map(|i| archive.by_index(i).unwrap().name().to_string())
+        .collect()
+}
+
+#[test]
+fn test_manifest_only_export_skips_image_files() {
+    let tmp = tempfile::tempdir().unwrap();
+    let mut config = AppConfig::default();
+    config.storage.image_directory = tmp.path().to_string_lossy().to_string();
+    let originals = storage::originals_dir_for(&config);
+    std::fs::create_dir_all(&originals).unwrap();
+    std::fs::write(originals.join("img-1.png"), b"png bytes").unwrap();
+
+    let images = vec![crate::db::images::tests::make_test_image("img-1")];
+
+    let full = tmp.path().join("full.zip");
+    create_export_bundle_with_config(&images, &full, Some(&config), true).unwrap();
+    assert!(zip_entry_names(&full).contains(&"img-1.png".to_string()));
+
+    let manifest_only = tmp.path().join("manifest.zip");
+    create_export_bundle_with_config(&images, &manifest_only, Some(&config), false).unwrap();
+    let mut names = zip_entry_names(&manifest_only);
+    names.sort();
+    assert_eq!(names, vec!["manifest.csv", "manifest.json"]);
+}
+
+#[test]
+fn test_validate_export_path_valid() {
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("export.zip");
+    let result = super::validate_export_path(path.to_str().unwrap());
+    assert!(result.is_ok());
+    assert_eq!(result.unwrap(), path);
+}
+
+#[test]
+fn test_validate_export_path_empty() {
+    let result = super::validate_export_path("");
+    assert!(result.is_err());
+    assert!(result.unwrap_err().to_string().contains("empty"));
+}
+
+#[test]
+fn test_validate_export_path_relative() {
+    let result = super::validate_export_path("relative/path/export.zip");
+    assert!(result.is_err());
+    assert!(result.unwrap_err().to_string().contains("absolute"));
+}
+
+#[test]
+fn test_validate_export_path_traversal() {
+    let result = super::validate_export_path("/tmp/../etc/evil.zip");
+    assert!(result.is_err());
+    assert!(result.unwrap_err().to_string().contains(".."));
+}
+
+#[test]
+fn test_validate_export_path_no_zip_extension() {
+    let result = super::validate_export_path("/tmp/export.tar.gz");
+    assert!(result.is_err());
+    assert!(result.unwrap_err().to_string().contains(".zip"));
+}
+
+#[test]
+fn test_validate_export_path_no_extension() {
+    let result = super::validate_export_path("/tmp/export");
+    assert!(result.is_err());
+    assert!(result.unwrap_err().to_string().contains(".zip"));
+}
+
+#[test]
+fn test_validate_export_path_nonexistent_parent() {
+    let result = super::validate_export_path("/nonexistent/deeply/nested/dir/export.zip");
+    assert!(result.is_err());
+    assert!(result.unwrap_err().to_string().contains("does not exist"));
+}
+
+#[test]
+fn test_validate_export_path_zip_case_insensitive() {
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("export.ZIP");
+    let result = super::validate_export_path(path.to_str().unwrap());
+    assert!(result.is_ok());
+}
+
+fn three_images() -> Vec<ImageEntry> {
+    ["img-1", "img-2", "img-3"]
+        .iter()
+        .map(|id| crate::db::images::tests::make_test_image(id))
+        .collect()
+}
+
+#[test]
+fn test_export_reports_progress_per_image() {
+    let tmp = tempfile::tempdir().unwrap();
+    let zip_path = tmp.path().join("export.zip");
+
+    let mut events = Vec::new();
+    write_export_bundle(
+        &three_images(),
+        &zip_path,
+        None,
+        false,
+        &AtomicBool::new(false),
+        |event| events.push(event),
+    )
+    .unwrap();
+
+    assert_eq!(
+        events,
+        (1..=3)
+            .map(|done| ExportProgressEvent { done, total: 3 })
+            .collect::<Vec<_>>()
+    );
+    assert!(zip_entry_names(&zip_path).contains(&"manifest.json".to_string()));
+    assert!(!partial_path(&zip_path).exists());
+}
+
+#[test]
+fn test_cancelled_export_leaves_no_file() {
+    let tmp = tempfile::tempdir().unwrap();
+    let zip_path = tmp.path().join("export.zip");
+    let cancelled = AtomicBool::new(false);
+
+    let mut events = Vec::new();
+    let err = write_export_bundle(
+        &three_images(),
+        &zip_path,
+        None,
+        false,
+        &cancelled,
+        |event| {
+            events.push(event);
+            cancelled.store(true, Ordering::Relaxed);
+        },
+    )
+    .unwrap_err();
+
+    assert!(err.to_string().contains("cancelled after 1 of 3"));
+    assert_eq!(events.len(), 1);
+    assert!(!zip_path.exists());
+    assert!(!partial_path(&zip_path).exists());
+}
+
+fn tag(name: &str) -> crate::types::gallery::TagEntry {
+    crate::types::gallery::TagEntry {
+        id: 0,
+        name: name.to_string(),
+        source: None,
+        confidence: None,
+    }
+}
+
+#[test]
+fn test_manifest_includes_tags() {
+    let tmp = tempfile::tempdir().unwrap();
+    let zip_path = tmp.path().join("tags.zip");
+    let mut tagged = crate::db::images::tests::make_test_image("img-1");
+    tagged.tags = Some(vec![tag("cat"), tag("red, blue")]);
+    let untagged = crate::db::images::tests::make_test_image("img-2");
+
+    create_export_bundle_with_config(&[tagged, untagged], &zip_path, None, false).unwrap();
+
+    let file = std::fs::File::open(&zip_path).unwrap();
+    let mut archive = zip::ZipArchive::new(file).unwrap();
+    let mut read = |name: &str| {
+        let mut contents = String::new();
+        std::io::Read::read_to_string(&mut archive.by_name(name).unwrap(), &mut contents).unwrap();
+        contents
+    };
+
+    let json: serde_json::Value = serde_json::from_str(&read("manifest.json")).unwrap();
+    assert_eq!(json[0]["tags"], serde_json::json!(["cat", "red, blue"]));
+    assert_eq!(json[1]["tags"], serde_json::json!([]));
+
+    let csv = read("manifest.csv");
+    let lines: Vec<&str> = csv.lines().collect();
+    assert!(lines[0].ends_with(",caption,tags"));
+    assert!(lines[1].ends_with(",\"cat;red, blue\""), "{}", lines[1]);
+    assert!(lines[2].ends_with(','), "{}", lines[2]);
+}
@@ -0,0 +1,192 @@
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+use std::path::Path;
+
+use crate::db;
+use crate::gallery::png_chunks;
+use crate::gallery::png_metadata::{self, PngGenerationMetadata};
+use crate::gallery::storage;
+use crate::types::config::AppConfig;
+use crate::types::gallery::{ImageEntry, ImageSource};
+
+/// Copy a PNG made by another tool into the gallery. Generation settings
+/// come from its A1111 `parameters` chunk, or failing that ComfyUI's
+/// `prompt` chunk; a file with neither is imported with empty metadata.
+/// The source file is left untouched.
+pub fn import_png(conn: &Connection, config: &AppConfig, path: &Path) -> Result<ImageEntry> {
+    let bytes =
+        std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    if !png_chunks::is_png(&bytes) {
+        anyhow::bail!("{} is not a PNG", path.display());
+    }
+
+    let text = png_chunks::parse_png_text(&bytes).unwrap_or_default();
+    let meta = match (text.get("parameters"), text.get("prompt")) {
+        (Some(parameters), _) => png_metadata::parse_a1111_parameters(parameters),
+        (None, Some(prompt)) => png_metadata::parse_comfyui_prompt(prompt).unwrap_or_default(),
+        (None, None) => PngGenerationMetadata::default(),
+    };
+
+    let saved = storage::save_generated_image(config, &bytes)
+        .with_context(|| format!("Failed to copy {} into the gallery", path.display()))?;
+    let image = image_entry(saved.filename, meta);
+    if let Err(e) = db::images::insert_image(conn, &image) {
+        // Don't leave an orphaned copy behind
+        let _ = storage::delete_image_files_for(config, &image.filename);
+        return Err(e);
+    }
+    if let Some(hash) = saved.phash {
+        db::phash::set_phash(conn, &image.id, hash)?;
+    }
+    Ok(image)
+}
+
+fn image_entry(filename: String, meta: PngGenerationMetadata) -> ImageEntry {
+    ImageEntry {
+        id: uuid::Uuid::new_v4().to_string(),
+        filename,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        positive_prompt: meta.positive_prompt,
+        negative_prompt: meta.negative_prompt,
+        original_idea: None,
+        checkpoint: meta.checkpoint,
+        width: meta.width,
+        height: meta.height,
+        steps: meta.steps,
+        cfg_scale: meta.cfg_scale,
+        sampler: meta.sampler,
+        scheduler: meta.scheduler,
+        seed: meta.seed,
+        denoise: meta.denoise,
+        pipeline_log: None,
+        selected_concept: None,
+        auto_approved: false,
+        caption: None,
+        caption_edited: false,
+        rating: None,
+        favorite: false,
+        deleted: false,
+        user_note: None,
+        compute_cost: None,
+        aesthetic_score: None,
+        pipeline_run_id: None,
+        parent_image_id: None,
+        deleted_at: None,
+        settings_mismatch: None,
+        source: Some(ImageSource::Imported),
+        tags: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const A1111_PARAMETERS: &str = "a lighthouse on a cliff, storm\n\
+        Negative prompt: blurry, lowres\n\
+        Steps: 30, Sampler: DPM++ 2M Karras, CFG scale: 7.5, Seed: 1234567890, \
+        Size: 512x768, Model hash: 6ce0161689, Model: v1-5-pruned-emaonly";
+
+    fn png(text: Option<(&str, &str)>) -> Vec<u8> {
+        let mut encoded = std::io::Cursor::new(Vec::new());
+        image::RgbImage::new(8, 8)
+            .write_to(&mut encoded, image::ImageFormat::Png)
+            .unwrap();
+        let bytes = encoded.into_inner();
+        match text {
+            Some((keyword, text)) => png_chunks::insert_text_chunk(&bytes, keyword, text).unwrap(),
+            None => bytes,
+        }
+    }
+
+    struct Fixture {
+        _tmp: tempfile::TempDir,
+        config: AppConfig,
+        conn: Connection,
+        source_dir: std::path::PathBuf,
+    }
+
+    fn fixture() -> Fixture {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = AppConfig::default();
+        config.storage.image_directory = tmp.path().join("gallery").to_string_lossy().to_string();
+        let source_dir = tmp.path().join("other-tool");
+        std::fs::create_dir_all(&source_dir).unwrap();
+        Fixture {
+            _tmp: tmp,
+            config,
+            conn: db::open_memory_database().unwrap(),
+            source_dir,
+        }
+    }
+
+    impl Fixture {
+        fn import(&self, name: &str, bytes: &[u8]) -> Result<ImageEntry> {
+            let path = self.source_dir.join(name);
+            std::fs::write(&path, bytes).unwrap();
+            import_png(&self.conn, &self.config, &path)
+        }
+    }
+
+    #[test]
+    fn test_a1111_parameters_map_to_image_fields() {
+        let fx = fixture();
+        let image = fx
+            .import(
+                "00012-1234567890.png",
+                &png(Some(("parameters", A1111_PARAMETERS))),
+            )
+            .unwrap();
+
+        let stored = db::images::get_image(&fx.conn, &image.id).unwrap().unwrap();
+        assert_eq!(
+            stored.positive_prompt.as_deref(),
+            Some("a lighthouse on a cliff, storm")
+        );
+        assert_eq!(stored.negative_prompt.as_deref(), Some("blurry, lowres"));
+        assert_eq!(stored.steps, Some(30));
+        assert_eq!(stored.sampler.as_deref(), Some("DPM++ 2M Karras"));
+        assert_eq!(stored.cfg_scale, Some(7.5));
+        assert_eq!(stored.seed, Some(1_234_567_890));
+        assert_eq!((stored.width, stored.height), (Some(512), Some(768)));
+        assert_eq!(stored.checkpoint.as_deref(), Some("v1-5-pruned-emaonly"));
+        assert_eq!(stored.source, Some(ImageSource::Imported));
+
+        assert!(storage::get_image_path_for(&fx.config, &stored.filename).exists());
+        assert!(storage::get_thumbnail_path_for(&fx.config, &stored.filename).exists());
+        // The source file stays where it was
+        assert!(fx.source_dir.join("00012-1234567890.png").exists());
+    }
+
+    #[test]
+    fn test_png_without_parameters_imports_with_empty_metadata() {
+        let fx = fixture();
+        let image = fx.import("plain.png", &png(None)).unwrap();
+
+        let stored = db::images::get_image(&fx.conn, &image.id).unwrap().unwrap();
+        assert!(stored.positive_prompt.is_none());
+        assert!(stored.seed.is_none());
+        assert!(stored.checkpoint.is_none());
+        assert!(storage::get_image_path_for(&fx.config, &stored.filename).exists());
+    }
+
+    #[test]
+    fn test_comfyui_prompt_used_without_parameters() {
+        let fx = fixture();
+        let prompt = r#"{"3": {"class_type": "KSampler", "inputs": {"seed": 7, "steps": 12}}}"#;
+        let image = fx
+            .import("comfy.png", &png(Some(("prompt", prompt))))
+            .unwrap();
+        assert_eq!(image.seed, Some(7));
+        assert_eq!(image.steps, Some(12));
+    }
+
+    #[test]
+    fn test_non_png_is_rejected_without_copying() {
+        let fx = fixture();
+        assert!(fx
+            .import("photo.jpg", b"\xff\xd8\xff\xe0 not a png")
+            .is_err());
+        assert!(db::images::list_all_filenames(&fx.conn).unwrap().is_empty());
+    }
+}
@@ -1,2 +1,13 @@
+pub mod auto_rating;
+pub mod deletion;
+pub mod duplicates;
 pub mod export;
+pub mod export_folder;
+pub mod import;
+pub mod png_chunks;
+pub mod png_metadata;
+pub mod provenance;
 pub mod storage;
+pub mod thumbnails;
+pub mod triage;
+pub mod variation;
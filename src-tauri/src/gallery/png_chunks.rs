@@ -0,0 +1,209 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::Path;
+
+const PNG_SIGNATURE: &[u8; 8] = b"\x89PNG\r\n\x1a\n";
+
+/// Read the uncompressed `tEXt` and `iTXt` chunks of a PNG file.
+pub fn read_png_text(path: &Path) -> Result<HashMap<String, String>> {
+    let bytes =
+        std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    parse_png_text(&bytes).with_context(|| format!("{} is not a valid PNG", path.display()))
+}
+
+/// Text chunks keyed by keyword. Compressed chunks are skipped.
+pub fn parse_png_text(bytes: &[u8]) -> Result<HashMap<String, String>> {
+    let mut text = HashMap::new();
+    for chunk in read_chunks(bytes)? {
+        match chunk.kind {
+            b"tEXt" => {
+                if let Some((key, value)) = split_nul(chunk.data) {
+                    // tEXt is Latin-1
+                    text.insert(latin1(key), latin1(value));
+                }
+            }
+            b"iTXt" => {
+                if let Some((key, value)) = parse_itxt(chunk.data) {
+                    text.insert(key, value);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(text)
+}
+
+pub fn is_png(bytes: &[u8]) -> bool {
+    bytes.starts_with(PNG_SIGNATURE)
+}
+
+/// Copy of the PNG with every text chunk (`tEXt`, `zTXt`, `iTXt`) removed.
+/// Image data is untouched.
+pub fn strip_text_chunks(bytes: &[u8]) -> Result<Vec<u8>> {
+    let mut out = PNG_SIGNATURE.to_vec();
+    for chunk in read_chunks(bytes)? {
+        if text_keyword(&chunk).is_none() {
+            out.extend_from_slice(chunk.raw);
+        }
+    }
+    Ok(out)
+}
+
+/// Copy of the PNG with a text chunk inserted after IHDR, replacing any
+/// existing chunk with the same keyword. Written as `tEXt` when the text is
+/// Latin-1 and as uncompressed `iTXt` otherwise.
+pub fn insert_text_chunk(bytes: &[u8], keyword: &str, text: &str) -> Result<Vec<u8>> {
+    let mut out = PNG_SIGNATURE.to_vec();
+    for chunk in read_chunks(bytes)? {
+        if text_keyword(&chunk) == Some(keyword.as_bytes()) {
+            continue;
+        }
+        out.extend_from_slice(chunk.raw);
+        if chunk.kind == b"IHDR" {
+            write_text_chunk(&mut out, keyword, text);
+        }
+    }
+    Ok(out)
+}
+
+struct Chunk<'a> {
+    kind: &'a [u8],
+    data: &'a [u8],
+    /// The whole chunk: length, type, data and CRC.
+    raw: &'a [u8],
+}
+
+fn read_chunks(bytes: &[u8]) -> Result<Vec<Chunk<'_>>> {
+    if !is_png(bytes) {
+        anyhow::bail!("Missing PNG signature");
+    }
+
+    let mut chunks = Vec::new();
+    let mut pos = PNG_SIGNATURE.len();
+    while pos + 8 <= bytes.len() {
+        let len = u32::from_be_bytes([bytes[pos], bytes[pos + 1], bytes[pos + 2], bytes[pos + 3]])
+            as usize;
+        let kind = &bytes[pos + 4..pos + 8];
+        let data = bytes
+            .get(pos + 8..pos + 8 + len)
+            .context("PNG chunk runs past end of file")?;
+        let end = (pos + 12 + len).min(bytes.len()); // length + type + data + crc
+        chunks.push(Chunk {
+            kind,
+            data,
+            raw: &bytes[pos..end],
+        });
+        if kind == b"IEND" {
+            break;
+        }
+        pos = end;
+    }
+    Ok(chunks)
+}
+
+fn text_keyword<'a>(chunk: &Chunk<'a>) -> Option<&'a [u8]> {
+    match chunk.kind {
+        b"tEXt" | b"zTXt" | b"iTXt" => Some(split_nul(chunk.data).map_or(chunk.data, |(k, _)| k)),
+        _ => None,
+    }
+}
+
+fn write_text_chunk(out: &mut Vec<u8>, keyword: &str, text: &str) {
+    let mut body = Vec::new();
+    if text.chars().all(|c| (c as u32) <= 0xFF) {
+        body.extend_from_slice(b"tEXt");
+        body.extend_from_slice(keyword.as_bytes());
+        body.push(0);
+        body.extend(text.chars().map(|c| c as u8));
+    } else {
+        body.extend_from_slice(b"iTXt");
+        body.extend_from_slice(keyword.as_bytes());
+        // NUL, uncompressed, method 0, empty language and translated keyword
+        body.extend_from_slice(&[0, 0, 0, 0, 0]);
+        body.extend_from_slice(text.as_bytes());
+    }
+    out.extend_from_slice(&((body.len() - 4) as u32).to_be_bytes());
+    out.extend_from_slice(&body);
+    out.extend_from_slice(&crc32(&body).to_be_bytes());
+}
+
+/// CRC-32 (ISO-HDLC) over chunk type and data, as PNG requires.
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &b in bytes {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn split_nul(data: &[u8]) -> Option<(&[u8], &[u8])> {
+    let nul = data.iter().position(|&b| b == 0)?;
+    Some((&data[..nul], &data[nul + 1..]))
+}
+
+fn latin1(bytes: &[u8]) -> String {
+    bytes.iter().map(|&b| b as char).collect()
+}
+
+fn parse_itxt(data: &[u8]) -> Option<(String, String)> {
+    let (key, rest) = split_nul(data)?;
+    let (&compressed, rest) = rest.split_first()?;
+    if compressed != 0 {
+        return None;
+    }
+    let rest = rest.get(1..)?; // compression method
+    let (_language, rest) = split_nul(rest)?;
+    let (_translated, text) = split_nul(rest)?;
+    Some((latin1(key), String::from_utf8_lossy(text).into_owned()))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// A real 8x8 PNG with `chunks` inserted as tEXt right after IHDR.
+    pub(crate) fn png_with_text(chunks: &[(&str, &str)]) -> Vec<u8> {
+        let mut encoded = std::io::Cursor::new(Vec::new());
+        image::RgbImage::new(8, 8)
+            .write_to(&mut encoded, image::ImageFormat::Png)
+            .unwrap();
+        let png = encoded.into_inner();
+
+        let ihdr_end = 8 + 12 + 13;
+        let mut out = png[..ihdr_end].to_vec();
+        for (key, text) in chunks {
+            let mut body = b"tEXt".to_vec();
+            body.extend_from_slice(key.as_bytes());
+            body.push(0);
+            body.extend_from_slice(text.as_bytes());
+            out.extend_from_slice(&((body.len() - 4) as u32).to_be_bytes());
+            out.extend_from_slice(&body);
+            out.extend_from_slice(&crc32(&body).to_be_bytes());
+        }
+        out.extend_from_slice(&png[ihdr_end..]);
+        out
+    }
+
+    #[test]
+    fn test_insert_and_strip_text_chunks() {
+        let png = png_with_text(&[("prompt", "{}"), ("parameters", "old")]);
+
+        let tagged = insert_text_chunk(&png, "parameters", "a fox, 🦊").unwrap();
+        assert!(image::load_from_memory(&tagged).is_ok());
+        let text = parse_png_text(&tagged).unwrap();
+        assert_eq!(text["parameters"], "a fox, 🦊");
+        assert!(text.contains_key("prompt"));
+
+        let stripped = strip_text_chunks(&tagged).unwrap();
+        assert!(image::load_from_memory(&stripped).is_ok());
+        assert!(parse_png_text(&stripped).unwrap().is_empty());
+        assert!(strip_text_chunks(b"not a png").is_err());
+    }
+}
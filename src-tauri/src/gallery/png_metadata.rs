@@ -0,0 +1,245 @@
+use serde_json::Value;
+use std::path::Path;
+
+use crate::types::generation::GenerationRequest;
+
+/// Generation parameters recovered from a PNG written by another tool.
+/// Every field is optional: whatever the file doesn't record stays `None`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PngGenerationMetadata {
+    pub positive_prompt: Option<String>,
+    pub negative_prompt: Option<String>,
+    pub checkpoint: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub steps: Option<u32>,
+    pub cfg_scale: Option<f64>,
+    pub sampler: Option<String>,
+    pub scheduler: Option<String>,
+    pub seed: Option<i64>,
+    pub denoise: Option<f64>,
+}
+
+/// Reconstruct generation settings from ComfyUI's `prompt` chunk (the
+/// API-format graph). Starts at the first KSampler and follows its links to
+/// the checkpoint loader, text encoders and latent image.
+pub fn parse_comfyui_prompt(prompt_json: &str) -> Option<PngGenerationMetadata> {
+    let graph: Value = serde_json::from_str(prompt_json).ok()?;
+    parse_comfyui_graph(&graph)
+}
+
+/// Same as [`parse_comfyui_prompt`] for an already-parsed graph, such as the
+/// one ComfyUI reports in `/history`.
+pub fn parse_comfyui_graph(graph: &Value) -> Option<PngGenerationMetadata> {
+    let nodes = graph.as_object()?;
+
+    let mut sampler_ids: Vec<&String> = nodes
+        .iter()
+        .filter(|(_, node)| {
+            matches!(
+                class_type(node),
+                Some("KSampler") | Some("KSamplerAdvanced")
+            )
+        })
+        .map(|(id, _)| id)
+        .collect();
+    sampler_ids.sort_by_key(|id| id.parse::<u64>().unwrap_or(u64::MAX));
+    let sampler = &nodes[*sampler_ids.first()?];
+    let inputs = sampler.get("inputs")?;
+
+    let mut meta = PngGenerationMetadata {
+        seed: inputs
+            .get("seed")
+            .or_else(|| inputs.get("noise_seed"))
+            .and_then(Value::as_i64),
+        steps: inputs.get("steps").and_then(as_u32),
+        cfg_scale: inputs.get("cfg").and_then(Value::as_f64),
+        sampler: inputs.get("sampler_name").and_then(as_string),
+        scheduler: inputs.get("scheduler").and_then(as_string),
+        denoise: inputs.get("denoise").and_then(Value::as_f64),
+        positive_prompt: prompt_text(graph, inputs.get("positive")),
+        negative_prompt: prompt_text(graph, inputs.get("negative")),
+        ..Default::default()
+    };
+
+    if let Some(latent) = follow(graph, inputs.get("latent_image")).and_then(|n| n.get("inputs")) {
+        meta.width = latent.get("width").and_then(as_u32);
+        meta.height = latent.get("height").and_then(as_u32);
+    }
+
+    meta.checkpoint = checkpoint_upstream(graph, inputs.get("model")).or_else(|| {
+        nodes
+            .values()
+            .find_map(|n| n.pointer("/inputs/ckpt_name").and_then(as_string))
+    });
+    Some(meta)
+}
+
+/// Describe `request` in the `parameters` text format written by
+/// Automatic1111, which most image viewers and sites understand.
+pub fn format_a1111_parameters(request: &GenerationRequest) -> String {
+    let mut text = request.positive_prompt.clone();
+    if !request.negative_prompt.is_empty() {
+        text.push_str("\nNegative prompt: ");
+        text.push_str(&request.negative_prompt);
+    }
+    let model = Path::new(&request.checkpoint)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(&request.checkpoint);
+    text.push_str(&format!(
+        "\nSteps: {}, Sampler: {}, Schedule type: {}, CFG scale: {}, Seed: {}, Size: {}x{}, Model: {}",
+        request.steps,
+        request.sampler,
+        request.scheduler,
+        request.cfg_scale,
+        request.seed,
+        request.width,
+        request.height,
+        model
+    ));
+    // A1111 reports the hires pass's denoise as the denoising strength of
+    // a txt2img image
+    if request.denoise < 1.0 {
+        text.push_str(&format!(", Denoising strength: {}", request.denoise));
+    } else if let Some(hires) = &request.hires {
+        text.push_str(&format!(", Denoising strength: {}", hires.denoise));
+    }
+    if let Some(hires) = &request.hires {
+        text.push_str(&format!(
+            ", Hires upscale: {}, Hires steps: {}, Hires upscaler: Latent (nearest-exact)",
+            hires.upscale_by, hires.steps
+        ));
+    }
+    text
+}
+
+/// Read back a `parameters` text in the Automatic1111 format, as written by
+/// [`format_a1111_parameters`] and by A1111 itself. The prompt comes first,
+/// then an optional `Negative prompt:` section, then one settings line
+/// starting with `Steps:`. Unknown settings are ignored; a text without a
+/// settings line is taken as a bare prompt.
+pub fn parse_a1111_parameters(text: &str) -> PngGenerationMetadata {
+    let text = text.trim();
+    let (prompts, settings) = match text.rfind("\nSteps: ") {
+        Some(i) => (&text[..i], &text[i + 1..]),
+        None if text.starts_with("Steps: ") => ("", text),
+        None => (text, ""),
+    };
+    let (positive, negative) = match prompts.find("\nNegative prompt: ") {
+        Some(i) => (
+            &prompts[..i],
+            Some(&prompts[i + "\nNegative prompt: ".len()..]),
+        ),
+        None => match prompts.strip_prefix("Negative prompt: ") {
+            Some(negative) => ("", Some(negative)),
+            None => (prompts, None),
+        },
+    };
+
+    let mut meta = PngGenerationMetadata {
+        positive_prompt: non_empty(positive),
+        negative_prompt: negative.and_then(non_empty),
+        ..Default::default()
+    };
+    let settings = settings_pairs(settings.lines().next().unwrap_or(""));
+    let hires = settings.iter().any(|(key, _)| key.starts_with("Hires"));
+    for (key, value) in settings {
+        match key {
+            "Steps" => meta.steps = value.parse().ok(),
+            "Sampler" => meta.sampler = non_empty(value),
+            "Schedule type" => meta.scheduler = non_empty(value),
+            "CFG scale" => meta.cfg_scale = value.parse().ok(),
+            "Seed" => meta.seed = value.parse().ok(),
+            "Model" => meta.checkpoint = non_empty(value),
+            "Size" => {
+                if let Some((w, h)) = value.split_once('x') {
+                    meta.width = w.trim().parse().ok();
+                    meta.height = h.trim().parse().ok();
+                }
+            }
+            // With a hires pass this is the second pass's strength, not the
+            // image's own denoise
+            "Denoising strength" if !hires => meta.denoise = value.parse().ok(),
+            _ => {}
+        }
+    }
+    meta
+}
+
+/// Split an A1111 settings line into `key: value` pairs. Values may be
+/// double-quoted to contain commas (e.g. `Lora hashes: "a: 1, b: 2"`).
+fn settings_pairs(line: &str) -> Vec<(&str, &str)> {
+    let mut pairs = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    for (i, c) in line
+        .char_indices()
+        .chain(std::iter::once((line.len(), ',')))
+    {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                if let Some((key, value)) = line[start..i].split_once(':') {
+                    pairs.push((key.trim(), value.trim().trim_matches('"')));
+                }
+                start = (i + 1).min(line.len());
+            }
+            _ => {}
+        }
+    }
+    pairs
+}
+
+fn non_empty(text: &str) -> Option<String> {
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+fn class_type(node: &Value) -> Option<&str> {
+    node.get("class_type").and_then(Value::as_str)
+}
+
+fn as_u32(v: &Value) -> Option<u32> {
+    v.as_u64().and_then(|n| u32::try_from(n).ok())
+}
+
+fn as_string(v: &Value) -> Option<String> {
+    v.as_str().map(String::from)
+}
+
+/// Resolve a `[node_id, output_index]` link to the node it points at.
+fn follow<'a>(graph: &'a Value, link: Option<&Value>) -> Option<&'a Value> {
+    let id = link?.as_array()?.first()?;
+    let key = match id {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    graph.get(key)
+}
+
+fn prompt_text(graph: &Value, link: Option<&Value>) -> Option<String> {
+    let node = follow(graph, link)?;
+    let inputs = node.get("inputs")?;
+    inputs
+        .get("text")
+        .or_else(|| inputs.get("text_g"))
+        .and_then(as_string)
+}
+
+/// Walk model links (through LoRA loaders etc.) until a node names a checkpoint.
+fn checkpoint_upstream(graph: &Value, link: Option<&Value>) -> Option<String> {
+    let mut node = follow(graph, link)?;
+    for _ in 0..32 {
+        let inputs = node.get("inputs")?;
+        if let Some(name) = inputs.get("ckpt_name").and_then(as_string) {
+            return Some(name);
+        }
+        node = follow(graph, inputs.get("model"))?;
+    }
+    None
+}
+
+#[cfg(test)]
+#[path = "png_metadata_test.rs"]
+mod tests;
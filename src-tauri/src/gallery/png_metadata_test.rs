@@ -0,0 +1,174 @@
+use super::*;
+use crate::gallery::png_chunks::parse_png_text;
+use crate::gallery::png_chunks::tests::png_with_text;
+use crate::types::generation::HiresFix;
+
+const COMFY_PROMPT: &str = r#"{
+    "3": {"class_type": "KSampler", "inputs": {
+        "seed": 987654321, "steps": 28, "cfg": 6.5,
+        "sampler_name": "dpmpp_2m", "scheduler": "karras", "denoise": 1.0,
+        "model": ["10", 0], "positive": ["6", 0], "negative": ["7", 0],
+        "latent_image": ["5", 0]}},
+    "4": {"class_type": "CheckpointLoaderSimple",
+          "inputs": {"ckpt_name": "juggernaut_xl.safetensors"}},
+    "10": {"class_type": "LoraLoader",
+           "inputs": {"model": ["4", 0], "clip": ["4", 1], "lora_name": "detail.safetensors"}},
+    "5": {"class_type": "EmptyLatentImage",
+          "inputs": {"width": 832, "height": 1216, "batch_size": 1}},
+    "6": {"class_type": "CLIPTextEncode", "inputs": {"text": "a fox in snow", "clip": ["10", 1]}},
+    "7": {"class_type": "CLIPTextEncode", "inputs": {"text": "blurry", "clip": ["10", 1]}}
+}"#;
+
+#[test]
+fn test_extracts_settings_from_comfyui_png() {
+    let png = png_with_text(&[("prompt", COMFY_PROMPT), ("workflow", "{}")]);
+    // Still a decodable image after inserting the chunks
+    assert!(image::load_from_memory(&png).is_ok());
+
+    let text = parse_png_text(&png).unwrap();
+    let meta = parse_comfyui_prompt(&text["prompt"]).unwrap();
+
+    assert_eq!(meta.steps, Some(28));
+    assert_eq!(meta.seed, Some(987654321));
+    assert_eq!(meta.cfg_scale, Some(6.5));
+    assert_eq!(meta.sampler.as_deref(), Some("dpmpp_2m"));
+    assert_eq!(meta.scheduler.as_deref(), Some("karras"));
+    assert_eq!(
+        meta.checkpoint.as_deref(),
+        Some("juggernaut_xl.safetensors")
+    );
+    assert_eq!(meta.positive_prompt.as_deref(), Some("a fox in snow"));
+    assert_eq!(meta.negative_prompt.as_deref(), Some("blurry"));
+    assert_eq!((meta.width, meta.height), (Some(832), Some(1216)));
+}
+
+#[test]
+fn test_advanced_sampler_uses_noise_seed() {
+    let prompt = r#"{"1": {"class_type": "KSamplerAdvanced",
+        "inputs": {"noise_seed": 42, "steps": 20}}}"#;
+    let meta = parse_comfyui_prompt(prompt).unwrap();
+    assert_eq!(meta.seed, Some(42));
+    assert_eq!(meta.steps, Some(20));
+    assert!(meta.checkpoint.is_none());
+}
+
+#[test]
+fn test_non_comfyui_text_is_rejected() {
+    assert!(parse_comfyui_prompt("not json").is_none());
+    assert!(parse_comfyui_prompt(r#"{"1": {"class_type": "SaveImage"}}"#).is_none());
+    assert!(parse_png_text(b"GIF89a").is_err());
+}
+
+fn request() -> GenerationRequest {
+    GenerationRequest {
+        positive_prompt: "a fox in snow".to_string(),
+        negative_prompt: "blurry".to_string(),
+        checkpoint: "juggernaut_xl.safetensors".to_string(),
+        width: 832,
+        height: 1216,
+        steps: 28,
+        cfg_scale: 6.5,
+        sampler: "dpmpp_2m".to_string(),
+        scheduler: "karras".to_string(),
+        seed: 42,
+        batch_size: 1,
+        denoise: 1.0,
+        init_image: None,
+        loras: Vec::new(),
+        hires: None,
+        controlnet: None,
+        custom_workflow: None,
+    }
+}
+
+#[test]
+fn test_format_a1111_parameters() {
+    let mut req = request();
+    assert_eq!(
+        format_a1111_parameters(&req),
+        "a fox in snow\nNegative prompt: blurry\nSteps: 28, Sampler: dpmpp_2m, \
+         Schedule type: karras, CFG scale: 6.5, Seed: 42, Size: 832x1216, \
+         Model: juggernaut_xl"
+    );
+
+    req.negative_prompt.clear();
+    req.denoise = 0.55;
+    let text = format_a1111_parameters(&req);
+    assert!(!text.contains("Negative prompt"));
+    assert!(text.ends_with(", Denoising strength: 0.55"));
+
+    req.denoise = 1.0;
+    req.hires = Some(HiresFix {
+        upscale_by: 1.5,
+        steps: 12,
+        denoise: 0.4,
+    });
+    assert!(format_a1111_parameters(&req).ends_with(
+        "Size: 832x1216, Model: juggernaut_xl, Denoising strength: 0.4, \
+         Hires upscale: 1.5, Hires steps: 12, Hires upscaler: Latent (nearest-exact)"
+    ));
+}
+
+#[test]
+fn test_parse_a1111_parameters_reads_own_output() {
+    let mut req = request();
+    req.hires = Some(HiresFix {
+        upscale_by: 1.5,
+        steps: 12,
+        denoise: 0.4,
+    });
+    let meta = parse_a1111_parameters(&format_a1111_parameters(&req));
+    assert_eq!(
+        meta,
+        PngGenerationMetadata {
+            positive_prompt: Some("a fox in snow".to_string()),
+            negative_prompt: Some("blurry".to_string()),
+            checkpoint: Some("juggernaut_xl".to_string()),
+            width: Some(832),
+            height: Some(1216),
+            steps: Some(28),
+            cfg_scale: Some(6.5),
+            sampler: Some("dpmpp_2m".to_string()),
+            scheduler: Some("karras".to_string()),
+            seed: Some(42),
+            denoise: None,
+        }
+    );
+}
+
+#[test]
+fn test_parse_a1111_parameters_from_webui() {
+    let text = "masterpiece, a castle,\nat dusk\n\
+                Negative prompt: lowres, (worst quality:1.4)\n\
+                Steps: 30, Sampler: DPM++ 2M Karras, CFG scale: 7, Seed: 3960213546, \
+                Size: 512x768, Model hash: 6ce0161689, Model: v1-5-pruned-emaonly, \
+                Denoising strength: 0.6, Lora hashes: \"detail: a1b2, style: c3d4\", \
+                Version: v1.6.0";
+    let meta = parse_a1111_parameters(text);
+    assert_eq!(
+        meta.positive_prompt.as_deref(),
+        Some("masterpiece, a castle,\nat dusk")
+    );
+    assert_eq!(
+        meta.negative_prompt.as_deref(),
+        Some("lowres, (worst quality:1.4)")
+    );
+    assert_eq!(meta.sampler.as_deref(), Some("DPM++ 2M Karras"));
+    assert_eq!(meta.seed, Some(3_960_213_546));
+    assert_eq!(meta.cfg_scale, Some(7.0));
+    assert_eq!((meta.width, meta.height), (Some(512), Some(768)));
+    assert_eq!(meta.checkpoint.as_deref(), Some("v1-5-pruned-emaonly"));
+    assert_eq!(meta.denoise, Some(0.6));
+    assert_eq!(meta.scheduler, None);
+}
+
+#[test]
+fn test_parse_a1111_parameters_prompt_only() {
+    let meta = parse_a1111_parameters("just a prompt, no settings");
+    assert_eq!(
+        meta.positive_prompt.as_deref(),
+        Some("just a prompt, no settings")
+    );
+    assert_eq!(meta.steps, None);
+    assert_eq!(parse_a1111_parameters(""), PngGenerationMetadata::default());
+}
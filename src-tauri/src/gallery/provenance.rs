@@ -0,0 +1,116 @@
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+
+use crate::db;
+use crate::gallery::{png_chunks, storage};
+use crate::types::config::AppConfig;
+use crate::types::gallery::ProvenanceBundle;
+
+/// Gather everything recorded about how image `id` was made: its metadata,
+/// the pipeline run behind its prompts, the workflow ComfyUI ran, its tags
+/// and the images it was derived from.
+pub fn build_provenance(
+    conn: &Connection,
+    config: &AppConfig,
+    id: &str,
+) -> Result<ProvenanceBundle> {
+    let mut image =
+        db::images::get_image(conn, id)?.with_context(|| format!("Image {} not found", id))?;
+
+    // Logs written by older versions may not parse; the bundle is still
+    // useful without them
+    let pipeline = image
+        .pipeline_log
+        .take()
+        .and_then(|log| serde_json::from_str(&log).ok());
+    let workflow = embedded_workflow(config, &image.filename);
+    let tags = db::tags::get_image_tags(conn, id)?;
+    let ancestors = db::lineage::image_ancestors(conn, id)?;
+
+    Ok(ProvenanceBundle {
+        image,
+        pipeline,
+        workflow,
+        tags,
+        ancestors,
+    })
+}
+
+/// The API-format graph ComfyUI stores in its PNG `prompt` chunk. `None`
+/// when the original is gone, isn't a PNG, or had its text stripped.
+fn embedded_workflow(config: &AppConfig, filename: &str) -> Option<serde_json::Value> {
+    let path = storage::locate_original(config, filename)?;
+    let text = png_chunks::read_png_text(&path).ok()?;
+    serde_json::from_str(text.get("prompt")?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::images::tests::make_test_image;
+    use crate::pipeline::engine::tests::make_test_result;
+
+    const WORKFLOW: &str = r#"{"3": {"class_type": "KSampler", "inputs": {"seed": 42}}}"#;
+
+    fn config_in(dir: &std::path::Path) -> AppConfig {
+        let mut config = AppConfig::default();
+        config.storage.image_directory = dir.to_string_lossy().to_string();
+        config
+    }
+
+    fn comfyui_png() -> Vec<u8> {
+        let mut encoded = std::io::Cursor::new(Vec::new());
+        image::RgbImage::new(8, 8)
+            .write_to(&mut encoded, image::ImageFormat::Png)
+            .unwrap();
+        png_chunks::insert_text_chunk(&encoded.into_inner(), "prompt", WORKFLOW).unwrap()
+    }
+
+    #[test]
+    fn test_bundle_has_workflow_pipeline_tags_and_ancestors() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = config_in(tmp.path());
+        let conn = db::open_memory_database().unwrap();
+
+        let result = make_test_result();
+        db::images::insert_image(&conn, &make_test_image("img-parent")).unwrap();
+        let mut image = make_test_image("img-child");
+        image.pipeline_log = Some(serde_json::to_string(&result).unwrap());
+        image.parent_image_id = Some("img-parent".to_string());
+        db::images::insert_image(&conn, &image).unwrap();
+        db::tags::add_image_tag(&conn, "img-child", "lighthouse", "user", None).unwrap();
+        storage::save_image_from_bytes_with_config(&config, &comfyui_png(), &image.filename)
+            .unwrap();
+
+        let bundle = build_provenance(&conn, &config, "img-child").unwrap();
+        assert_eq!(
+            bundle.workflow.unwrap(),
+            serde_json::from_str::<serde_json::Value>(WORKFLOW).unwrap()
+        );
+        let pipeline = bundle.pipeline.unwrap();
+        assert_eq!(pipeline.original_idea, result.original_idea);
+        assert_eq!(
+            pipeline.stages.ideator.unwrap().output,
+            vec!["Concept A", "Concept B"]
+        );
+        assert!(pipeline.stages.composer.is_some());
+        assert!(bundle.image.pipeline_log.is_none());
+        assert_eq!(bundle.tags[0].name, "lighthouse");
+        assert_eq!(bundle.ancestors.len(), 1);
+        assert_eq!(bundle.ancestors[0].id, "img-parent");
+    }
+
+    #[test]
+    fn test_bundle_without_file_or_log() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = config_in(tmp.path());
+        let conn = db::open_memory_database().unwrap();
+        db::images::insert_image(&conn, &make_test_image("img-bare")).unwrap();
+
+        let bundle = build_provenance(&conn, &config, "img-bare").unwrap();
+        assert!(bundle.workflow.is_none());
+        assert!(bundle.pipeline.is_none());
+        assert!(bundle.tags.is_empty());
+        assert!(build_provenance(&conn, &config, "missing").is_err());
+    }
+}
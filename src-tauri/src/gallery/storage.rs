@@ -1,8 +1,12 @@
 use anyhow::{Context, Result};
+use std::io::Write;
 use std::path::{Path, PathBuf};
 
 use crate::config::manager;
-use crate::types::config::AppConfig;
+use crate::gallery::thumbnails::{MAX_THUMBNAIL_SIZE, MIN_THUMBNAIL_SIZE};
+use crate::gallery::{png_chunks, png_metadata};
+use crate::types::config::{AppConfig, ThumbnailFormat};
+use crate::types::generation::GenerationRequest;
 
 const THUMBNAIL_SIZE: u32 = 256;
 
@@ -59,29 +63,145 @@ pub fn get_image_path_for(config: &AppConfig, filename: &str) -> PathBuf {
 }
 
 /// Get the full path to a thumbnail by original filename (default dir).
+/// Thumbnails there predate configurable formats, so they're always JPEG.
 pub fn get_thumbnail_path(filename: &str) -> PathBuf {
-    let stem = Path::new(filename)
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .unwrap_or("unknown");
-    thumbnails_dir().join(format!("{}_thumb.jpg", stem))
+    thumbnail_path_in(&thumbnails_dir(), filename, ThumbnailFormat::Jpeg)
 }
 
-/// Get the full path to a thumbnail by original filename for a given config.
+/// Get the full path to a thumbnail by original filename for a given
+/// config, in its configured format.
 pub fn get_thumbnail_path_for(config: &AppConfig, filename: &str) -> PathBuf {
+    thumbnail_path_in(
+        &thumbnails_dir_for(config),
+        filename,
+        config.storage.thumbnail_format,
+    )
+}
+
+fn thumbnail_path_in(thumb_dir: &Path, filename: &str, format: ThumbnailFormat) -> PathBuf {
     let stem = Path::new(filename)
         .file_stem()
         .and_then(|s| s.to_str())
         .unwrap_or("unknown");
-    thumbnails_dir_for(config).join(format!("{}_thumb.jpg", stem))
+    thumb_dir.join(format!("{}_thumb.{}", stem, format.extension()))
+}
+
+/// Everywhere a thumbnail for `filename` may live, preferred first: the
+/// configured format and directory, a JPEG there from before the format
+/// was changed, then the default directory.
+pub fn thumbnail_candidates(config: &AppConfig, filename: &str) -> Vec<PathBuf> {
+    let mut candidates = vec![
+        get_thumbnail_path_for(config, filename),
+        thumbnail_path_in(&thumbnails_dir_for(config), filename, ThumbnailFormat::Jpeg),
+        get_thumbnail_path(filename),
+    ];
+    candidates.dedup();
+    candidates
+}
+
+/// Size and format new thumbnails are written with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThumbnailSpec {
+    pub size: u32,
+    pub format: ThumbnailFormat,
+}
+
+impl Default for ThumbnailSpec {
+    fn default() -> Self {
+        Self {
+            size: THUMBNAIL_SIZE,
+            format: ThumbnailFormat::Jpeg,
+        }
+    }
+}
+
+impl ThumbnailSpec {
+    /// The configured size (kept within the supported range) and format.
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self {
+            size: config
+                .storage
+                .thumbnail_size
+                .clamp(MIN_THUMBNAIL_SIZE, MAX_THUMBNAIL_SIZE),
+            format: config.storage.thumbnail_format,
+        }
+    }
 }
 
 /// Save raw image bytes to the originals directory and create a thumbnail.
 pub fn save_image_from_bytes(bytes: &[u8], filename: &str) -> Result<()> {
-    save_image_from_bytes_for(bytes, filename, &originals_dir(), &thumbnails_dir())
+    save_image_from_bytes_for(
+        bytes,
+        filename,
+        &originals_dir(),
+        &thumbnails_dir(),
+        ThumbnailSpec::default(),
+    )
+}
+
+/// An image written to the gallery directory by [`save_generated_image`].
+#[derive(Debug, Clone)]
+pub struct SavedImage {
+    pub filename: String,
+    /// [`perceptual_hash`] of the pixels; `None` if they couldn't be decoded.
+    pub phash: Option<u64>,
+}
+
+/// Save a ComfyUI output under a fresh local name and return that name.
+/// ComfyUI reuses output filenames (e.g. `VisionForge_00001_.png`) across
+/// runs and restarts, so its name is never used locally.
+pub fn save_generated_image(config: &AppConfig, bytes: &[u8]) -> Result<SavedImage> {
+    let orig_dir = originals_dir_for(config);
+    for _ in 0..MAX_NAME_ATTEMPTS {
+        let filename = generate_filename();
+        if !orig_dir.join(&filename).exists() {
+            save_image_from_bytes_with_config(config, bytes, &filename)?;
+            // Best-effort like the thumbnail: duplicate detection just skips it
+            let phash = match perceptual_hash(bytes) {
+                Ok(hash) => Some(hash),
+                Err(e) => {
+                    eprintln!(
+                        "[gallery] WARNING: No perceptual hash for {}: {:#}",
+                        filename, e
+                    );
+                    None
+                }
+            };
+            return Ok(SavedImage { filename, phash });
+        }
+    }
+    anyhow::bail!(
+        "Could not find an unused filename in {}",
+        orig_dir.display()
+    )
+}
+
+const MAX_NAME_ATTEMPTS: usize = 8;
+
+/// [`save_generated_image`] for a PNG produced from `request`, honouring
+/// `storage.embed_metadata`: when on, an A1111-style `parameters` chunk is
+/// added alongside ComfyUI's own; when off, every text chunk is stripped.
+/// Non-PNG bytes are saved as-is.
+pub fn save_image_with_metadata(
+    config: &AppConfig,
+    bytes: &[u8],
+    request: &GenerationRequest,
+) -> Result<SavedImage> {
+    if !png_chunks::is_png(bytes) {
+        return save_generated_image(config, bytes);
+    }
+    let bytes = if config.storage.embed_metadata {
+        let parameters = png_metadata::format_a1111_parameters(request);
+        png_chunks::insert_text_chunk(bytes, "parameters", &parameters)
+    } else {
+        png_chunks::strip_text_chunks(bytes)
+    }
+    .context("Failed to rewrite PNG metadata")?;
+    save_generated_image(config, &bytes)
 }
 
 /// Save raw image bytes using a specific config's directories.
+/// Never overwrites an existing original.
 pub fn save_image_from_bytes_with_config(
     config: &AppConfig,
     bytes: &[u8],
@@ -89,7 +209,13 @@ pub fn save_image_from_bytes_with_config(
 ) -> Result<()> {
     let orig_dir = originals_dir_for(config);
     let thumb_dir = thumbnails_dir_for(config);
-    save_image_from_bytes_for(bytes, filename, &orig_dir, &thumb_dir)
+    save_image_from_bytes_for(
+        bytes,
+        filename,
+        &orig_dir,
+        &thumb_dir,
+        ThumbnailSpec::from_config(config),
+    )
 }
 
 fn save_image_from_bytes_for(
@@ -97,6 +223,7 @@ fn save_image_from_bytes_for(
     filename: &str,
     orig_dir: &Path,
     thumb_dir: &Path,
+    thumbnail: ThumbnailSpec,
 ) -> Result<()> {
     std::fs::create_dir_all(orig_dir)
         .with_context(|| format!("Failed to create originals dir {}", orig_dir.display()))?;
@@ -104,11 +231,16 @@ fn save_image_from_bytes_for(
         .with_context(|| format!("Failed to create thumbnails dir {}", thumb_dir.display()))?;
 
     let orig_path = orig_dir.join(filename);
-    std::fs::write(&orig_path, bytes)
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&orig_path)
+        .with_context(|| format!("Failed to create image file {}", orig_path.display()))?;
+    file.write_all(bytes)
         .with_context(|| format!("Failed to write image to {}", orig_path.display()))?;
 
     // Thumbnail creation is best-effort — don't fail the image save
-    if let Err(e) = create_thumbnail_to(&orig_path, filename, thumb_dir) {
+    if let Err(e) = create_thumbnail_sized(&orig_path, filename, thumb_dir, thumbnail) {
         eprintln!(
             "[gallery] WARNING: Failed to create thumbnail for {}: {}. \
              Original image saved successfully.",
@@ -118,29 +250,82 @@ fn save_image_from_bytes_for(
     Ok(())
 }
 
-/// Create a 256px thumbnail from an original image file.
+/// Create a 256px JPEG thumbnail from an original image file.
 pub fn create_thumbnail(original_path: &Path, filename: &str) -> Result<()> {
-    create_thumbnail_to(original_path, filename, &thumbnails_dir())
+    create_thumbnail_sized(
+        original_path,
+        filename,
+        &thumbnails_dir(),
+        ThumbnailSpec::default(),
+    )
 }
 
-fn create_thumbnail_to(original_path: &Path, filename: &str, thumb_dir: &Path) -> Result<()> {
+/// Render a thumbnail fitting within `spec.size`×`spec.size` into
+/// `thumb_dir` in `spec.format`, overwriting any existing thumbnail for the
+/// same original in that format.
+pub fn create_thumbnail_sized(
+    original_path: &Path,
+    filename: &str,
+    thumb_dir: &Path,
+    spec: ThumbnailSpec,
+) -> Result<()> {
     let img = image::open(original_path)
         .with_context(|| format!("Failed to open image {}", original_path.display()))?;
 
-    let thumb = img.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE);
-    let stem = Path::new(filename)
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .unwrap_or("unknown");
-    let thumb_path = thumb_dir.join(format!("{}_thumb.jpg", stem));
+    let mut thumb = img.thumbnail(spec.size, spec.size);
+    if spec.format == ThumbnailFormat::Jpeg {
+        // JPEG has no alpha channel
+        thumb = image::DynamicImage::ImageRgb8(thumb.to_rgb8());
+    }
+    let thumb_path = thumbnail_path_in(thumb_dir, filename, spec.format);
 
     thumb
-        .save(&thumb_path)
+        .save_with_format(&thumb_path, spec.format.image_format())
         .with_context(|| format!("Failed to save thumbnail to {}", thumb_path.display()))?;
 
     Ok(())
 }
 
+/// 64-bit average hash of an image: downscaled to 8×8 grayscale, one bit per
+/// pixel brighter than the mean. Re-encodes and small edits barely change
+/// it, so the Hamming distance between two hashes measures how alike the
+/// images look.
+pub fn perceptual_hash(bytes: &[u8]) -> Result<u64> {
+    let img = image::load_from_memory(bytes).context("Failed to decode image for hashing")?;
+    let small = img
+        .resize_exact(8, 8, image::imageops::FilterType::Triangle)
+        .to_luma8();
+    let pixels: Vec<u32> = small.pixels().map(|p| u32::from(p.0[0])).collect();
+    let mean = pixels.iter().sum::<u32>() / pixels.len().max(1) as u32;
+    Ok(pixels
+        .iter()
+        .enumerate()
+        .filter(|(_, &value)| value > mean)
+        .fold(0u64, |hash, (i, _)| hash | (1 << i)))
+}
+
+/// Number of differing bits between two [`perceptual_hash`]es.
+pub fn hash_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// Find an original on disk, checking the configured directory first and then
+/// the default one (images saved before a directory change live there).
+pub fn locate_original(config: &AppConfig, filename: &str) -> Option<PathBuf> {
+    [
+        get_image_path_for(config, filename),
+        get_image_path(filename),
+    ]
+    .into_iter()
+    .find(|p| p.exists())
+}
+
+/// The directory an original actually lives in, for revealing it in the
+/// OS file manager. Same lookup order as [`locate_original`].
+pub fn locate_original_dir(config: &AppConfig, filename: &str) -> Option<PathBuf> {
+    locate_original(config, filename).and_then(|p| p.parent().map(Path::to_path_buf))
+}
+
 /// Delete both original and thumbnail files for an image.
 pub fn delete_image_files(filename: &str) -> Result<()> {
     let orig = get_image_path(filename);
@@ -174,15 +359,10 @@ pub fn delete_image_files_for(config: &AppConfig, filename: &str) -> Result<()>
         }
     }
 
-    let thumb = get_thumbnail_path_for(config, filename);
-    if thumb.exists() {
-        std::fs::remove_file(&thumb)
-            .with_context(|| format!("Failed to delete thumbnail {}", thumb.display()))?;
-    } else {
-        let fallback = get_thumbnail_path(filename);
-        if fallback.exists() {
-            std::fs::remove_file(&fallback)
-                .with_context(|| format!("Failed to delete thumbnail {}", fallback.display()))?;
+    for thumb in thumbnail_candidates(config, filename) {
+        if thumb.exists() {
+            std::fs::remove_file(&thumb)
+                .with_context(|| format!("Failed to delete thumbnail {}", thumb.display()))?;
         }
     }
 
@@ -190,79 +370,5 @@ pub fn delete_image_files_for(config: &AppConfig, filename: &str) -> Result<()>
 }
 
 #[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_generate_filename_format() {
-        let name = generate_filename();
-        // Format: YYYY-MM-DD_HH-MM-SS_xxxxxxxx.png
-        assert!(name.ends_with(".png"));
-        assert_eq!(name.len(), 32); // 10 date + 1 _ + 8 time + 1 _ + 8 uuid + 4 .png
-    }
-
-    #[test]
-    fn test_get_thumbnail_path() {
-        let thumb = get_thumbnail_path("2026-01-15_12-30-45_abc12345.png");
-        let filename = thumb.file_name().unwrap().to_str().unwrap();
-        assert_eq!(filename, "2026-01-15_12-30-45_abc12345_thumb.jpg");
-    }
-
-    #[test]
-    fn test_get_image_path() {
-        let path = get_image_path("test.png");
-        assert!(path.to_str().unwrap().contains("originals"));
-        assert!(path.to_str().unwrap().ends_with("test.png"));
-    }
-
-    #[test]
-    fn test_save_and_thumbnail() {
-        // Create a small test image in memory
-        let img = image::RgbImage::new(64, 64);
-        let mut bytes = Vec::new();
-        let encoder = image::codecs::png::PngEncoder::new(&mut bytes);
-        image::ImageEncoder::write_image(
-            encoder,
-            img.as_raw(),
-            64,
-            64,
-            image::ExtendedColorType::Rgb8,
-        )
-        .unwrap();
-
-        // Use a temp dir to avoid polluting real data dir
-        let tmp = tempfile::tempdir().unwrap();
-        let orig_path = tmp.path().join("test.png");
-        std::fs::write(&orig_path, &bytes).unwrap();
-
-        let thumb_path = tmp.path().join("test_thumb.jpg");
-        let img_loaded = image::open(&orig_path).unwrap();
-        let thumb = img_loaded.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE);
-        thumb.save(&thumb_path).unwrap();
-
-        assert!(thumb_path.exists());
-    }
-
-    #[test]
-    fn test_custom_image_dir() {
-        let mut config = AppConfig::default();
-        config.storage.image_directory = "/tmp/my-images".to_string();
-        assert_eq!(
-            originals_dir_for(&config),
-            PathBuf::from("/tmp/my-images/originals")
-        );
-        assert_eq!(
-            thumbnails_dir_for(&config),
-            PathBuf::from("/tmp/my-images/thumbnails")
-        );
-    }
-
-    #[test]
-    fn test_empty_image_dir_uses_default() {
-        let config = AppConfig::default();
-        assert!(originals_dir_for(&config)
-            .to_str()
-            .unwrap()
-            .contains(".visionforge"));
-    }
-}
+#[path = "storage_test.rs"]
+mod tests;
@@ -0,0 +1,275 @@
+use super::*;
+
+#[test]
+fn test_generate_filename_format() {
+    let name = generate_filename();
+    // Format: YYYY-MM-DD_HH-MM-SS_xxxxxxxx.png
+    assert!(name.ends_with(".png"));
+    assert_eq!(name.len(), 32); // 10 date + 1 _ + 8 time + 1 _ + 8 uuid + 4 .png
+}
+
+#[test]
+fn test_same_comfyui_filename_maps_to_distinct_local_files() {
+    let tmp = tempfile::tempdir().unwrap();
+    let mut config = AppConfig::default();
+    config.storage.image_directory = tmp.path().to_string_lossy().to_string();
+
+    // Two jobs whose ComfyUI outputs were both named VisionForge_00001_.png
+    let comfy_name = "VisionForge_00001_.png";
+    let first = save_generated_image(&config, b"first job")
+        .unwrap()
+        .filename;
+    let second = save_generated_image(&config, b"second job")
+        .unwrap()
+        .filename;
+
+    assert_ne!(first, second);
+    assert_ne!(first, comfy_name);
+    let orig_dir = originals_dir_for(&config);
+    assert_eq!(std::fs::read(orig_dir.join(&first)).unwrap(), b"first job");
+    assert_eq!(
+        std::fs::read(orig_dir.join(&second)).unwrap(),
+        b"second job"
+    );
+}
+
+#[test]
+fn test_save_never_overwrites_existing_original() {
+    let tmp = tempfile::tempdir().unwrap();
+    let mut config = AppConfig::default();
+    config.storage.image_directory = tmp.path().to_string_lossy().to_string();
+
+    save_image_from_bytes_with_config(&config, b"original", "taken.png").unwrap();
+    assert!(save_image_from_bytes_with_config(&config, b"intruder", "taken.png").is_err());
+    let path = originals_dir_for(&config).join("taken.png");
+    assert_eq!(std::fs::read(path).unwrap(), b"original");
+}
+
+fn comfyui_png() -> Vec<u8> {
+    let mut encoded = std::io::Cursor::new(Vec::new());
+    image::RgbImage::new(8, 8)
+        .write_to(&mut encoded, image::ImageFormat::Png)
+        .unwrap();
+    png_chunks::insert_text_chunk(&encoded.into_inner(), "prompt", "{}").unwrap()
+}
+
+fn saved_text(embed_metadata: bool) -> std::collections::HashMap<String, String> {
+    let tmp = tempfile::tempdir().unwrap();
+    let mut config = AppConfig::default();
+    config.storage.image_directory = tmp.path().to_string_lossy().to_string();
+    config.storage.embed_metadata = embed_metadata;
+    let request = GenerationRequest {
+        positive_prompt: "a private prompt".to_string(),
+        negative_prompt: String::new(),
+        checkpoint: "dreamshaper_8.safetensors".to_string(),
+        width: 8,
+        height: 8,
+        steps: 20,
+        cfg_scale: 7.0,
+        sampler: "euler".to_string(),
+        scheduler: "normal".to_string(),
+        seed: 1,
+        batch_size: 1,
+        denoise: 1.0,
+        init_image: None,
+        loras: Vec::new(),
+        hires: None,
+        controlnet: None,
+        custom_workflow: None,
+    };
+
+    let saved = save_image_with_metadata(&config, &comfyui_png(), &request).unwrap();
+    assert!(saved.phash.is_some());
+    png_chunks::read_png_text(&originals_dir_for(&config).join(saved.filename)).unwrap()
+}
+
+#[test]
+fn test_embed_metadata_adds_parameters_chunk() {
+    let text = saved_text(true);
+    assert!(text["parameters"].starts_with("a private prompt\nSteps: 20"));
+    assert!(text.contains_key("prompt"));
+}
+
+#[test]
+fn test_embedded_parameters_round_trip() {
+    let text = saved_text(true);
+    let meta = png_metadata::parse_a1111_parameters(&text["parameters"]);
+    assert_eq!(meta.seed, Some(1));
+    assert_eq!(meta.steps, Some(20));
+    assert_eq!(meta.positive_prompt.as_deref(), Some("a private prompt"));
+    assert_eq!(meta.checkpoint.as_deref(), Some("dreamshaper_8"));
+}
+
+#[test]
+fn test_embed_metadata_off_strips_all_text() {
+    let text = saved_text(false);
+    assert!(!text.contains_key("parameters"));
+    assert!(text.is_empty());
+}
+
+#[test]
+fn test_locate_original_dir_uses_custom_image_dir() {
+    let tmp = tempfile::tempdir().unwrap();
+    let mut config = AppConfig::default();
+    config.storage.image_directory = tmp.path().to_string_lossy().to_string();
+
+    let filename = save_generated_image(&config, b"image bytes")
+        .unwrap()
+        .filename;
+    let dir = locate_original_dir(&config, &filename).unwrap();
+    assert_eq!(dir, originals_dir_for(&config));
+    assert!(dir.starts_with(tmp.path()));
+
+    assert!(locate_original_dir(&config, "missing_00000000.png").is_none());
+}
+
+#[test]
+fn test_get_thumbnail_path() {
+    let thumb = get_thumbnail_path("2026-01-15_12-30-45_abc12345.png");
+    let filename = thumb.file_name().unwrap().to_str().unwrap();
+    assert_eq!(filename, "2026-01-15_12-30-45_abc12345_thumb.jpg");
+}
+
+fn save_with_thumbnail(format: ThumbnailFormat) -> (tempfile::TempDir, AppConfig, PathBuf) {
+    let tmp = tempfile::tempdir().unwrap();
+    let mut config = AppConfig::default();
+    config.storage.image_directory = tmp.path().to_string_lossy().to_string();
+    config.storage.thumbnail_size = 512;
+    config.storage.thumbnail_format = format;
+
+    let mut png = std::io::Cursor::new(Vec::new());
+    image::RgbaImage::new(1024, 2048)
+        .write_to(&mut png, image::ImageFormat::Png)
+        .unwrap();
+    save_image_from_bytes_with_config(&config, png.get_ref(), "tall.png").unwrap();
+    let thumb = get_thumbnail_path_for(&config, "tall.png");
+    (tmp, config, thumb)
+}
+
+#[test]
+fn test_configured_thumbnail_size_and_format() {
+    for (format, extension) in [
+        (ThumbnailFormat::Jpeg, "jpg"),
+        (ThumbnailFormat::WebP, "webp"),
+        (ThumbnailFormat::Png, "png"),
+    ] {
+        let (_tmp, _config, thumb) = save_with_thumbnail(format);
+        assert_eq!(thumb.extension().unwrap(), extension);
+        assert_eq!(image::image_dimensions(&thumb).unwrap(), (256, 512));
+        assert_eq!(
+            image::ImageFormat::from_path(&thumb).unwrap(),
+            format.image_format()
+        );
+    }
+}
+
+#[test]
+fn test_thumbnail_lookup_falls_back_to_jpeg_after_format_change() {
+    let (_tmp, mut config, jpeg) = save_with_thumbnail(ThumbnailFormat::Jpeg);
+    config.storage.thumbnail_format = ThumbnailFormat::WebP;
+
+    let candidates = thumbnail_candidates(&config, "tall.png");
+    assert!(candidates[0].ends_with("tall_thumb.webp"));
+    assert_eq!(candidates.iter().find(|p| p.exists()), Some(&jpeg));
+
+    delete_image_files_for(&config, "tall.png").unwrap();
+    assert!(!jpeg.exists());
+}
+
+#[test]
+fn test_get_image_path() {
+    let path = get_image_path("test.png");
+    assert!(path.to_str().unwrap().contains("originals"));
+    assert!(path.to_str().unwrap().ends_with("test.png"));
+}
+
+#[test]
+fn test_save_and_thumbnail() {
+    // Create a small test image in memory
+    let img = image::RgbImage::new(64, 64);
+    let mut bytes = Vec::new();
+    let encoder = image::codecs::png::PngEncoder::new(&mut bytes);
+    image::ImageEncoder::write_image(
+        encoder,
+        img.as_raw(),
+        64,
+        64,
+        image::ExtendedColorType::Rgb8,
+    )
+    .unwrap();
+
+    // Use a temp dir to avoid polluting real data dir
+    let tmp = tempfile::tempdir().unwrap();
+    let orig_path = tmp.path().join("test.png");
+    std::fs::write(&orig_path, &bytes).unwrap();
+
+    let thumb_path = tmp.path().join("test_thumb.jpg");
+    let img_loaded = image::open(&orig_path).unwrap();
+    let thumb = img_loaded.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE);
+    thumb.save(&thumb_path).unwrap();
+
+    assert!(thumb_path.exists());
+}
+
+#[test]
+fn test_custom_image_dir() {
+    let mut config = AppConfig::default();
+    config.storage.image_directory = "/tmp/my-images".to_string();
+    assert_eq!(
+        originals_dir_for(&config),
+        PathBuf::from("/tmp/my-images/originals")
+    );
+    assert_eq!(
+        thumbnails_dir_for(&config),
+        PathBuf::from("/tmp/my-images/thumbnails")
+    );
+}
+
+#[test]
+fn test_empty_image_dir_uses_default() {
+    let config = AppConfig::default();
+    assert!(originals_dir_for(&config)
+        .to_str()
+        .unwrap()
+        .contains(".visionforge"));
+}
+
+/// A 64×64 PNG whose brightness rises along x (`horizontal`) or y.
+fn gradient_png(horizontal: bool) -> Vec<u8> {
+    let img = image::GrayImage::from_fn(64, 64, |x, y| {
+        let t = if horizontal { x } else { y };
+        image::Luma([(t * 4) as u8])
+    });
+    let mut encoded = std::io::Cursor::new(Vec::new());
+    img.write_to(&mut encoded, image::ImageFormat::Png).unwrap();
+    encoded.into_inner()
+}
+
+#[test]
+fn test_identical_images_hash_to_distance_zero() {
+    let a = perceptual_hash(&gradient_png(true)).unwrap();
+    let b = perceptual_hash(&gradient_png(true)).unwrap();
+    assert_eq!(hash_distance(a, b), 0);
+
+    // Same pixels, different encoding
+    let jpeg = {
+        let img = image::load_from_memory(&gradient_png(true)).unwrap();
+        let mut encoded = std::io::Cursor::new(Vec::new());
+        img.write_to(&mut encoded, image::ImageFormat::Jpeg)
+            .unwrap();
+        encoded.into_inner()
+    };
+    assert!(hash_distance(a, perceptual_hash(&jpeg).unwrap()) <= 2);
+}
+
+#[test]
+fn test_different_images_exceed_duplicate_threshold() {
+    let horizontal = perceptual_hash(&gradient_png(true)).unwrap();
+    let vertical = perceptual_hash(&gradient_png(false)).unwrap();
+    assert!(hash_distance(horizontal, vertical) > 10);
+}
+
+#[test]
+fn test_perceptual_hash_rejects_non_images() {
+    assert!(perceptual_hash(b"not an image").is_err());
+}
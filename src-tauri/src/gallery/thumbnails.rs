@@ -0,0 +1,269 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+
+use super::storage;
+use crate::types::config::AppConfig;
+use crate::types::gallery::{ThumbnailFailure, ThumbnailRegenSummary};
+
+pub const MIN_THUMBNAIL_SIZE: u32 = 32;
+pub const MAX_THUMBNAIL_SIZE: u32 = 2048;
+
+/// Per-image progress payload, emitted as `thumbnails:progress`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThumbnailProgressEvent {
+    pub filename: String,
+    /// "regenerated", "missing" or "failed"
+    pub status: &'static str,
+    pub completed: usize,
+    pub total: usize,
+}
+
+/// Re-render the thumbnail of every given original at `size`, overwriting
+/// the existing file. Work is spread over `hardware.thumbnail_concurrency`
+/// threads; progress is reported on the calling thread in completion order.
+/// Missing originals are skipped; per-image failures are counted and listed
+/// in `errors` rather than aborting the run.
+pub fn regenerate_all(
+    config: &AppConfig,
+    filenames: &[String],
+    size: u32,
+    mut on_progress: impl FnMut(ThumbnailProgressEvent),
+) -> Result<ThumbnailRegenSummary> {
+    if !(MIN_THUMBNAIL_SIZE..=MAX_THUMBNAIL_SIZE).contains(&size) {
+        anyhow::bail!(
+            "Thumbnail size must be between {} and {} px, got {}",
+            MIN_THUMBNAIL_SIZE,
+            MAX_THUMBNAIL_SIZE,
+            size
+        );
+    }
+
+    let thumb_dir = storage::thumbnails_dir_for(config);
+    std::fs::create_dir_all(&thumb_dir)
+        .with_context(|| format!("Failed to create thumbnails dir {}", thumb_dir.display()))?;
+
+    let mut summary = ThumbnailRegenSummary {
+        total: filenames.len(),
+        ..Default::default()
+    };
+
+    let workers = (config.hardware.thumbnail_concurrency.max(1) as usize).min(filenames.len());
+    let next = AtomicUsize::new(0);
+    let (tx, rx) = mpsc::channel();
+
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            let tx = tx.clone();
+            let (next, thumb_dir) = (&next, &thumb_dir);
+            scope.spawn(move || loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(filename) = filenames.get(i) else {
+                    break;
+                };
+                let outcome = regenerate_one(config, filename, thumb_dir, size);
+                if tx.send((filename, outcome)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(tx);
+
+        for (completed, (filename, outcome)) in rx.into_iter().enumerate() {
+            let status = match outcome {
+                Ok(()) => {
+                    summary.regenerated += 1;
+                    "regenerated"
+                }
+                Err((status, error)) => {
+                    if status == "missing" {
+                        summary.skipped_missing += 1;
+                    } else {
+                        summary.failed += 1;
+                    }
+                    summary.errors.push(ThumbnailFailure {
+                        filename: filename.clone(),
+                        error,
+                    });
+                    status
+                }
+            };
+            on_progress(ThumbnailProgressEvent {
+                filename: filename.clone(),
+                status,
+                completed: completed + 1,
+                total: filenames.len(),
+            });
+        }
+    });
+
+    Ok(summary)
+}
+
+/// Re-render every thumbnail in `storage.thumbnail_format`, at `size` or
+/// else the configured `storage.thumbnail_size`, e.g. after either setting
+/// changed.
+pub fn regenerate_thumbnails(
+    config: &AppConfig,
+    filenames: &[String],
+    size: Option<u32>,
+    on_progress: impl FnMut(ThumbnailProgressEvent),
+) -> Result<ThumbnailRegenSummary> {
+    let size = size.unwrap_or_else(|| storage::ThumbnailSpec::from_config(config).size);
+    regenerate_all(config, filenames, size, on_progress)
+}
+
+/// Err carries the progress status ("missing" or "failed") and the reason.
+fn regenerate_one(
+    config: &AppConfig,
+    filename: &str,
+    thumb_dir: &Path,
+    size: u32,
+) -> std::result::Result<(), (&'static str, String)> {
+    let Some(path) = storage::locate_original(config, filename) else {
+        return Err(("missing", "Original not found".to_string()));
+    };
+    let spec = storage::ThumbnailSpec {
+        size,
+        format: config.storage.thumbnail_format,
+    };
+    storage::create_thumbnail_sized(&path, filename, thumb_dir, spec).map_err(|e| {
+        eprintln!(
+            "[gallery] Failed to regenerate thumbnail for {}: {:#}",
+            filename, e
+        );
+        ("failed", format!("{:#}", e))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::config::ThumbnailFormat;
+
+    fn write_png(path: &std::path::Path, width: u32, height: u32) {
+        image::RgbImage::new(width, height).save(path).unwrap();
+    }
+
+    fn temp_config(dir: &std::path::Path) -> AppConfig {
+        let mut config = AppConfig::default();
+        config.storage.image_directory = dir.to_string_lossy().to_string();
+        config
+    }
+
+    #[test]
+    fn test_regenerate_overwrites_thumbnail_at_new_size() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = temp_config(tmp.path());
+        let orig_dir = storage::originals_dir_for(&config);
+        let thumb_dir = storage::thumbnails_dir_for(&config);
+        std::fs::create_dir_all(&orig_dir).unwrap();
+        std::fs::create_dir_all(&thumb_dir).unwrap();
+
+        let orig = orig_dir.join("wide.png");
+        write_png(&orig, 1024, 512);
+        storage::create_thumbnail_sized(&orig, "wide.png", &thumb_dir, Default::default()).unwrap();
+        let thumb_path = storage::get_thumbnail_path_for(&config, "wide.png");
+        assert_eq!(image::image_dimensions(&thumb_path).unwrap(), (256, 128));
+
+        let mut events = Vec::new();
+        let summary =
+            regenerate_all(&config, &["wide.png".to_string()], 128, |e| events.push(e)).unwrap();
+
+        assert_eq!(summary.regenerated, 1);
+        assert_eq!(image::image_dimensions(&thumb_path).unwrap(), (128, 64));
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].status, "regenerated");
+    }
+
+    #[test]
+    fn test_regenerate_skips_missing_originals() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = temp_config(tmp.path());
+        let orig_dir = storage::originals_dir_for(&config);
+        std::fs::create_dir_all(&orig_dir).unwrap();
+        write_png(&orig_dir.join("present.png"), 64, 64);
+
+        let filenames = vec!["gone-a1b2c3d4.png".to_string(), "present.png".to_string()];
+        let mut completed = Vec::new();
+        let summary =
+            regenerate_all(&config, &filenames, 32, |e| completed.push(e.completed)).unwrap();
+
+        assert_eq!(
+            summary,
+            ThumbnailRegenSummary {
+                total: 2,
+                regenerated: 1,
+                skipped_missing: 1,
+                failed: 0,
+                errors: vec![ThumbnailFailure {
+                    filename: "gone-a1b2c3d4.png".to_string(),
+                    error: "Original not found".to_string(),
+                }],
+            }
+        );
+        assert_eq!(completed, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_regenerate_thumbnails_uses_configured_size_and_format() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = temp_config(tmp.path());
+        config.storage.thumbnail_size = 48;
+        config.storage.thumbnail_format = ThumbnailFormat::Png;
+        let orig_dir = storage::originals_dir_for(&config);
+        std::fs::create_dir_all(&orig_dir).unwrap();
+        write_png(&orig_dir.join("present.png"), 96, 64);
+
+        let filenames = vec!["present.png".to_string(), "missing.png".to_string()];
+        let mut statuses = Vec::new();
+        let summary =
+            regenerate_thumbnails(&config, &filenames, None, |e| statuses.push(e.status)).unwrap();
+
+        assert_eq!(summary.regenerated, 1);
+        assert_eq!(summary.skipped_missing, 1);
+        assert_eq!(summary.errors.len(), 1);
+        assert_eq!(summary.errors[0].filename, "missing.png");
+        statuses.sort();
+        assert_eq!(statuses, vec!["missing", "regenerated"]);
+
+        let thumb = storage::get_thumbnail_path_for(&config, "present.png");
+        assert_eq!(thumb.extension().unwrap(), "png");
+        assert_eq!(image::image_dimensions(&thumb).unwrap(), (48, 32));
+    }
+
+    #[test]
+    fn test_parallel_regenerate_completes_batch() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = temp_config(tmp.path());
+        config.hardware.thumbnail_concurrency = 3;
+        let orig_dir = storage::originals_dir_for(&config);
+        std::fs::create_dir_all(&orig_dir).unwrap();
+
+        let filenames: Vec<String> = (0..20).map(|i| format!("img-{:02}.png", i)).collect();
+        for name in &filenames {
+            write_png(&orig_dir.join(name), 64, 64);
+        }
+
+        let mut events = Vec::new();
+        let summary = regenerate_all(&config, &filenames, 32, |e| events.push(e)).unwrap();
+
+        assert_eq!(summary.regenerated, 20);
+        for name in &filenames {
+            assert!(storage::get_thumbnail_path_for(&config, name).exists());
+        }
+        let completed: Vec<usize> = events.iter().map(|e| e.completed).collect();
+        assert_eq!(completed, (1..=20).collect::<Vec<_>>());
+        assert!(events.iter().all(|e| e.total == 20));
+    }
+
+    #[test]
+    fn test_regenerate_rejects_out_of_range_size() {
+        let config = AppConfig::default();
+        assert!(regenerate_all(&config, &[], 0, |_| {}).is_err());
+        assert!(regenerate_all(&config, &[], 10_000, |_| {}).is_err());
+    }
+}
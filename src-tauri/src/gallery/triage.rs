@@ -0,0 +1,116 @@
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+
+use crate::db;
+use crate::types::gallery::ImageEntry;
+
+/// One keyboard-triage step: optionally rate, (un)favorite and add user tags
+/// to an image in a single transaction. Fields left as `None` are untouched;
+/// an explicit `favorite` wins over auto-favorite from the rating. Returns
+/// the updated image with its tags.
+pub fn quick_triage(
+    conn: &Connection,
+    image_id: &str,
+    rating: Option<u32>,
+    favorite: Option<bool>,
+    add_tags: &[String],
+    auto_favorite_rating: u32,
+) -> Result<ImageEntry> {
+    if rating.is_some_and(|r| r > 5) {
+        anyhow::bail!("Rating must be between 0 and 5");
+    }
+
+    let tx = conn
+        .unchecked_transaction()
+        .context("Failed to start triage transaction")?;
+    if db::images::get_image(&tx, image_id)?.is_none() {
+        anyhow::bail!("Image {} not found", image_id);
+    }
+
+    if rating.is_some() {
+        db::images::update_image_rating_with_auto_favorite(
+            &tx,
+            image_id,
+            rating,
+            auto_favorite_rating,
+        )?;
+    }
+    if let Some(favorite) = favorite {
+        db::images::update_image_favorite(&tx, image_id, favorite)?;
+    }
+    for tag in add_tags {
+        if tag.trim().is_empty() {
+            anyhow::bail!("Tag names cannot be empty");
+        }
+        db::tags::add_image_tag(&tx, image_id, tag, "user", None)?;
+    }
+    tx.commit().context("Failed to commit triage")?;
+
+    let mut image = db::images::get_image(conn, image_id)?
+        .with_context(|| format!("Image {} not found", image_id))?;
+    let tags = db::tags::get_image_tags(conn, image_id)?;
+    if !tags.is_empty() {
+        image.tags = Some(tags);
+    }
+    Ok(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::images::tests::make_test_image;
+
+    fn setup() -> Connection {
+        let conn = db::open_memory_database().unwrap();
+        db::images::insert_image(&conn, &make_test_image("img-001")).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_quick_triage_applies_everything_and_returns_entry() {
+        let conn = setup();
+        let tags = vec!["Portrait".to_string(), "keeper".to_string()];
+
+        let image = quick_triage(&conn, "img-001", Some(4), Some(true), &tags, 0).unwrap();
+
+        assert_eq!(image.rating, Some(4));
+        assert!(image.favorite);
+        let names: Vec<String> = image.tags.unwrap().into_iter().map(|t| t.name).collect();
+        assert_eq!(names, vec!["keeper", "portrait"]);
+
+        let stored = db::images::get_image(&conn, "img-001").unwrap().unwrap();
+        assert_eq!(stored.rating, Some(4));
+        assert!(stored.favorite);
+    }
+
+    #[test]
+    fn test_quick_triage_rolls_back_on_bad_tag() {
+        let conn = setup();
+        let tags = vec!["keeper".to_string(), "  ".to_string()];
+
+        assert!(quick_triage(&conn, "img-001", Some(5), Some(true), &tags, 0).is_err());
+
+        let stored = db::images::get_image(&conn, "img-001").unwrap().unwrap();
+        assert_eq!(stored.rating, None);
+        assert!(!stored.favorite);
+        assert!(db::tags::get_image_tags(&conn, "img-001")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_explicit_favorite_overrides_auto_favorite() {
+        let conn = setup();
+        let image = quick_triage(&conn, "img-001", Some(5), Some(false), &[], 4).unwrap();
+        assert_eq!(image.rating, Some(5));
+        assert!(!image.favorite);
+        assert!(image.tags.is_none());
+    }
+
+    #[test]
+    fn test_quick_triage_unknown_image_fails() {
+        let conn = setup();
+        assert!(quick_triage(&conn, "missing", Some(3), None, &[], 0).is_err());
+        assert!(quick_triage(&conn, "img-001", Some(9), None, &[], 0).is_err());
+    }
+}
@@ -0,0 +1,327 @@
+use anyhow::{Context, Result};
+
+use crate::db;
+use crate::state::AppState;
+use crate::types::comparison::ComparisonSide;
+use crate::types::gallery::ImageEntry;
+use crate::types::generation::{GenerationRequest, GenerationSettings, PartialGenerationRequest};
+use crate::types::queue::{QueueJob, QueueJobStatus, QueuePriority};
+
+/// Enqueue a copy of an existing image's generation with only the given
+/// overrides applied, and create a comparison linking the source image to
+/// the (future) result. Returns the new queue job ID.
+pub fn create_variation(
+    state: &AppState,
+    image_id: &str,
+    overrides: &PartialGenerationRequest,
+) -> Result<String> {
+    let conn = state.db.lock().map_err(|e| anyhow::anyhow!("{}", e))?;
+
+    let image = db::images::get_image(&conn, image_id)?
+        .with_context(|| format!("Image {} not found", image_id))?;
+
+    let changed = changed_fields(&image, overrides);
+    if changed.is_empty() {
+        anyhow::bail!(
+            "Overrides do not change any parameter of image {}",
+            image_id
+        );
+    }
+
+    let comparison_id = uuid::Uuid::new_v4().to_string();
+    let mut job = build_variation_job(&image, overrides)?;
+    job.linked_comparison_id = Some(comparison_id.clone());
+
+    db::comparisons::insert_pending_comparison(
+        &conn,
+        &comparison_id,
+        image_id,
+        &changed.join(", "),
+    )?;
+    db::queue::insert_job(&conn, &job)?;
+
+    Ok(job.id)
+}
+
+/// The full request for the image on the chosen side of a comparison, so
+/// the generate form can carry on from the better result.
+pub fn continue_from_winner(
+    conn: &rusqlite::Connection,
+    comparison_id: &str,
+    which: ComparisonSide,
+) -> Result<GenerationRequest> {
+    let comparison = db::comparisons::get_comparison(conn, comparison_id)?
+        .with_context(|| format!("Comparison {} not found", comparison_id))?;
+    let image_id = match which {
+        ComparisonSide::A => comparison.image_a_id,
+        ComparisonSide::B => comparison.image_b_id,
+    };
+    // A variation's comparison has no B until the job finishes
+    if image_id.is_empty() {
+        anyhow::bail!("Comparison {} has no image {:?} yet", comparison_id, which);
+    }
+    let image = db::images::get_image(conn, &image_id)?
+        .with_context(|| format!("Image {} not found", image_id))?;
+    request_for_image(&image)
+}
+
+/// Rebuild the request that made `image` from its stored settings. Uses
+/// the same settings a variation job carries, so missing values get the
+/// defaults the queue would use.
+pub fn request_for_image(image: &ImageEntry) -> Result<GenerationRequest> {
+    let settings = variation_settings(image, &PartialGenerationRequest::default())?;
+    Ok(settings.into_request(
+        image.positive_prompt.clone().unwrap_or_default(),
+        image.negative_prompt.clone().unwrap_or_default(),
+    ))
+}
+
+/// Names of the parameters an override actually changes, in a stable order.
+/// Names match the `variable_changed` values used by manual comparisons.
+pub fn changed_fields(image: &ImageEntry, o: &PartialGenerationRequest) -> Vec<&'static str> {
+    let mut changed = Vec::new();
+    if differs(&o.positive_prompt, &image.positive_prompt) {
+        changed.push("positive_prompt");
+    }
+    if differs(&o.negative_prompt, &image.negative_prompt) {
+        changed.push("negative_prompt");
+    }
+    if differs(&o.checkpoint, &image.checkpoint) {
+        changed.push("checkpoint");
+    }
+    if differs(&o.width, &image.width) {
+        changed.push("width");
+    }
+    if differs(&o.height, &image.height) {
+        changed.push("height");
+    }
+    if differs(&o.steps, &image.steps) {
+        changed.push("steps");
+    }
+    if differs(&o.cfg_scale, &image.cfg_scale) {
+        changed.push("cfg");
+    }
+    if differs(&o.sampler, &image.sampler) {
+        changed.push("sampler");
+    }
+    if differs(&o.scheduler, &image.scheduler) {
+        changed.push("scheduler");
+    }
+    if differs(&o.seed, &image.seed) {
+        changed.push("seed");
+    }
+    if differs(&o.denoise, &image.denoise) {
+        changed.push("denoise");
+    }
+    changed
+}
+
+fn differs<T: PartialEq>(override_value: &Option<T>, stored: &Option<T>) -> bool {
+    match override_value {
+        Some(v) => stored.as_ref() != Some(v),
+        None => false,
+    }
+}
+
+/// Build a pending queue job from an image's stored settings plus overrides.
+/// The stored seed is reused so that only the overridden parameters differ.
+pub fn build_variation_job(
+    image: &ImageEntry,
+    overrides: &PartialGenerationRequest,
+) -> Result<QueueJob> {
+    let settings = variation_settings(image, overrides)?;
+
+    Ok(QueueJob {
+        id: uuid::Uuid::new_v4().to_string(),
+        priority: QueuePriority::Normal,
+        status: QueueJobStatus::Pending,
+        positive_prompt: overrides
+            .positive_prompt
+            .clone()
+            .or_else(|| image.positive_prompt.clone())
+            .unwrap_or_default(),
+        negative_prompt: overrides
+            .negative_prompt
+            .clone()
+            .or_else(|| image.negative_prompt.clone())
+            .unwrap_or_default(),
+        settings_json: settings.to_json()?,
+        pipeline_log: image.pipeline_log.clone(),
+        original_idea: image.original_idea.clone(),
+        selected_concept: image.selected_concept,
+        auto_approved: image.auto_approved,
+        linked_comparison_id: None,
+        group_id: None,
+        pipeline_run_id: None,
+        parent_image_id: Some(image.id.clone()),
+        retry_count: 0,
+        created_at: None,
+        started_at: None,
+        completed_at: None,
+        result_image_id: None,
+    })
+}
+
+/// The image's stored settings with `overrides` applied on top. Values the
+/// image doesn't record get the queue's defaults.
+fn variation_settings(
+    image: &ImageEntry,
+    overrides: &PartialGenerationRequest,
+) -> Result<GenerationSettings> {
+    let checkpoint = overrides
+        .checkpoint
+        .clone()
+        .or_else(|| image.checkpoint.clone())
+        .with_context(|| format!("Image {} has no stored checkpoint", image.id))?;
+
+    let mut settings = GenerationSettings::for_checkpoint(checkpoint);
+    settings.apply(&stored_settings(image));
+    settings.apply(overrides);
+    Ok(settings)
+}
+
+/// The generation parameters recorded on `image`, as overrides.
+pub fn stored_settings(image: &ImageEntry) -> PartialGenerationRequest {
+    PartialGenerationRequest {
+        positive_prompt: image.positive_prompt.clone(),
+        negative_prompt: image.negative_prompt.clone(),
+        checkpoint: image.checkpoint.clone(),
+        width: image.width,
+        height: image.height,
+        steps: image.steps,
+        cfg_scale: image.cfg_scale,
+        sampler: image.sampler.clone(),
+        scheduler: image.scheduler.clone(),
+        seed: image.seed,
+        denoise: image.denoise,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::images::tests::make_test_image;
+    use crate::types::config::AppConfig;
+    use crate::types::generation::GenerationSettings;
+
+    fn make_state() -> AppState {
+        let conn = crate::db::open_memory_database().unwrap();
+        AppState::new(conn, AppConfig::default())
+    }
+
+    fn sampler_override() -> PartialGenerationRequest {
+        PartialGenerationRequest {
+            sampler: Some("euler".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_sampler_override_changes_only_sampler() {
+        let state = make_state();
+        let source = make_test_image("img-src");
+        {
+            let conn = state.db.lock().unwrap();
+            db::images::insert_image(&conn, &source).unwrap();
+        }
+
+        let job_id = create_variation(&state, "img-src", &sampler_override()).unwrap();
+
+        let conn = state.db.lock().unwrap();
+        let job = db::queue::get_job(&conn, &job_id).unwrap().unwrap();
+        assert_eq!(job.status, QueueJobStatus::Pending);
+        assert_eq!(job.positive_prompt, source.positive_prompt.unwrap());
+        assert_eq!(job.negative_prompt, source.negative_prompt.unwrap());
+        assert_eq!(job.parent_image_id.as_deref(), Some("img-src"));
+
+        let settings: GenerationSettings = serde_json::from_str(&job.settings_json).unwrap();
+        assert_eq!(settings.sampler, "euler");
+        assert_eq!(settings.checkpoint, "dreamshaper_8.safetensors");
+        assert_eq!(settings.width, 512);
+        assert_eq!(settings.height, 768);
+        assert_eq!(settings.steps, 25);
+        assert_eq!(settings.cfg_scale, 7.5);
+        assert_eq!(settings.scheduler, "karras");
+        assert_eq!(settings.seed, 12345);
+
+        let comparison_id = job.linked_comparison_id.unwrap();
+        let comparison = db::comparisons::get_comparison(&conn, &comparison_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(comparison.image_a_id, "img-src");
+        assert_eq!(comparison.variable_changed, "sampler");
+    }
+
+    #[test]
+    fn test_override_matching_stored_value_is_rejected() {
+        let state = make_state();
+        {
+            let conn = state.db.lock().unwrap();
+            db::images::insert_image(&conn, &make_test_image("img-src")).unwrap();
+        }
+        let same = PartialGenerationRequest {
+            sampler: Some("dpmpp_2m".to_string()),
+            ..Default::default()
+        };
+        assert!(create_variation(&state, "img-src", &same).is_err());
+    }
+
+    #[test]
+    fn test_continue_from_winner_returns_chosen_side() {
+        let conn = crate::db::open_memory_database().unwrap();
+        let image_a = make_test_image("img-a");
+        let mut image_b = make_test_image("img-b");
+        image_b.positive_prompt = Some("a dog on a throne".to_string());
+        image_b.sampler = Some("euler".to_string());
+        image_b.seed = Some(777);
+        db::images::insert_image(&conn, &image_a).unwrap();
+        db::images::insert_image(&conn, &image_b).unwrap();
+        db::comparisons::insert_comparison(
+            &conn,
+            &crate::types::comparison::Comparison {
+                id: "cmp-1".to_string(),
+                image_a_id: "img-a".to_string(),
+                image_b_id: "img-b".to_string(),
+                variable_changed: "sampler".to_string(),
+                note: None,
+                created_at: None,
+            },
+        )
+        .unwrap();
+
+        let a = continue_from_winner(&conn, "cmp-1", ComparisonSide::A).unwrap();
+        assert_eq!(a.positive_prompt, "a cat on a throne");
+        assert_eq!(a.sampler, "dpmpp_2m");
+        assert_eq!(a.seed, 12345);
+        assert_eq!(a.checkpoint, "dreamshaper_8.safetensors");
+        assert_eq!((a.width, a.height), (512, 768));
+        assert_eq!(a.batch_size, 1);
+
+        let b = continue_from_winner(&conn, "cmp-1", ComparisonSide::B).unwrap();
+        assert_eq!(b.positive_prompt, "a dog on a throne");
+        assert_eq!(b.sampler, "euler");
+        assert_eq!(b.seed, 777);
+    }
+
+    #[test]
+    fn test_continue_from_pending_comparison_fails() {
+        let conn = crate::db::open_memory_database().unwrap();
+        db::images::insert_image(&conn, &make_test_image("img-a")).unwrap();
+        db::comparisons::insert_pending_comparison(&conn, "cmp-1", "img-a", "seed").unwrap();
+
+        assert!(continue_from_winner(&conn, "cmp-1", ComparisonSide::A).is_ok());
+        assert!(continue_from_winner(&conn, "cmp-1", ComparisonSide::B).is_err());
+        assert!(continue_from_winner(&conn, "missing", ComparisonSide::A).is_err());
+    }
+
+    #[test]
+    fn test_changed_fields_lists_multiple() {
+        let image = make_test_image("img-src");
+        let overrides = PartialGenerationRequest {
+            cfg_scale: Some(9.0),
+            steps: Some(40),
+            ..Default::default()
+        };
+        assert_eq!(changed_fields(&image, &overrides), vec!["steps", "cfg"]);
+    }
+}
@@ -0,0 +1,234 @@
+//! Home Assistant power guard: holds the queue while the GPU's power draw, as
+//! reported by an HA sensor, is above `hardware.ha_max_watts`. The guard is
+//! advisory — if HA can't be reached or never reports a low enough reading,
+//! generation carries on rather than stalling the queue.
+
+use anyhow::{Context, Result};
+use reqwest::Client;
+use serde_json::Value;
+use std::time::Duration;
+
+use crate::types::config::HardwareSettings;
+
+/// How often the sensor is re-read while waiting for the draw to drop.
+pub const POWER_POLL_INTERVAL: Duration = Duration::from_secs(10);
+/// Longest a job is held back before it runs anyway.
+pub const POWER_WAIT_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Watts reported by an HA state object, as returned by `/api/states/<id>`.
+/// Sensors reporting in kW are converted.
+pub fn parse_power_state(state: &Value) -> Result<f64> {
+    let raw = state
+        .get("state")
+        .and_then(|v| v.as_str())
+        .context("Home Assistant state has no 'state' field")?;
+    let value: f64 = raw
+        .trim()
+        .parse()
+        .with_context(|| format!("Home Assistant sensor is not numeric: {:?}", raw))?;
+    let unit = state
+        .pointer("/attributes/unit_of_measurement")
+        .and_then(|v| v.as_str());
+    Ok(match unit {
+        Some("kW") => value * 1000.0,
+        _ => value,
+    })
+}
+
+/// Current power draw in watts of `entity_id`.
+pub async fn read_power(
+    client: &Client,
+    ha_url: &str,
+    token: Option<&str>,
+    entity_id: &str,
+) -> Result<f64> {
+    let url = format!("{}/api/states/{}", ha_url.trim_end_matches('/'), entity_id);
+    let mut request = client.get(&url).timeout(Duration::from_secs(5));
+    if let Some(token) = token.filter(|t| !t.is_empty()) {
+        request = request.bearer_auth(token);
+    }
+
+    let resp = request
+        .send()
+        .await
+        .context("Failed to reach Home Assistant")?;
+    if !resp.status().is_success() {
+        anyhow::bail!(
+            "Home Assistant returned {} for {}",
+            resp.status(),
+            entity_id
+        );
+    }
+    let state: Value = resp
+        .json()
+        .await
+        .context("Failed to parse Home Assistant state")?;
+    parse_power_state(&state)
+}
+
+/// Wait until the configured sensor reads at or below `ha_max_watts`,
+/// re-reading every `poll`. Returns straight away when monitoring is off,
+/// and gives up (logging why) when HA fails to answer or `timeout` passes.
+pub async fn wait_for_power_headroom(
+    client: &Client,
+    hardware: &HardwareSettings,
+    poll: Duration,
+    timeout: Duration,
+) {
+    if !hardware.enable_ha_power_monitoring || hardware.ha_max_watts == 0 {
+        return;
+    }
+    let max_watts = f64::from(hardware.ha_max_watts);
+    let deadline = tokio::time::Instant::now() + timeout;
+
+    loop {
+        let watts = match read_power(
+            client,
+            &hardware.ha_url,
+            hardware.ha_token.as_deref(),
+            &hardware.ha_entity_id,
+        )
+        .await
+        {
+            Ok(watts) => watts,
+            Err(e) => {
+                eprintln!(
+                    "[hardware] Could not read GPU power, starting anyway: {:#}",
+                    e
+                );
+                return;
+            }
+        };
+        if watts <= max_watts {
+            return;
+        }
+        if tokio::time::Instant::now() + poll > deadline {
+            eprintln!(
+                "[hardware] GPU still drawing {:.0} W (limit {} W) after {:?}, starting anyway",
+                watts, hardware.ha_max_watts, timeout
+            );
+            return;
+        }
+        eprintln!(
+            "[hardware] GPU drawing {:.0} W (limit {} W), waiting",
+            watts, hardware.ha_max_watts
+        );
+        tokio::time::sleep(poll).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_http::MockServer;
+    use crate::types::config::AppConfig;
+
+    const FAST: Duration = Duration::from_millis(1);
+
+    fn sensor(state: &str) -> String {
+        serde_json::json!({
+            "entity_id": "sensor.gpu_power_draw",
+            "state": state,
+            "attributes": {
+                "unit_of_measurement": "W",
+                "device_class": "power",
+                "friendly_name": "GPU Power Draw"
+            },
+            "last_changed": "2026-10-15T09:12:44.101+00:00",
+            "last_updated": "2026-10-15T09:12:44.101+00:00"
+        })
+        .to_string()
+    }
+
+    fn monitoring(endpoint: &str) -> HardwareSettings {
+        let mut hardware = AppConfig::default().hardware;
+        hardware.enable_ha_power_monitoring = true;
+        hardware.ha_url = endpoint.to_string();
+        hardware.ha_token = Some("ha-token".to_string());
+        hardware.ha_max_watts = 180;
+        hardware
+    }
+
+    #[test]
+    fn test_parse_power_state() {
+        let state: Value = serde_json::from_str(&sensor("243.7")).unwrap();
+        assert_eq!(parse_power_state(&state).unwrap(), 243.7);
+
+        let kw = serde_json::json!({"state": "0.25", "attributes": {"unit_of_measurement": "kW"}});
+        assert_eq!(parse_power_state(&kw).unwrap(), 250.0);
+
+        let unavailable: Value = serde_json::from_str(&sensor("unavailable")).unwrap();
+        assert!(parse_power_state(&unavailable).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_power_requests_entity_state() {
+        let server = MockServer::start(vec![sensor("95")]).await;
+        let watts = read_power(
+            &Client::new(),
+            &format!("{}/", server.endpoint),
+            Some("ha-token"),
+            "sensor.gpu_power_draw",
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(watts, 95.0);
+        let requests = server.requests();
+        assert_eq!(requests[0].path, "/api/states/sensor.gpu_power_draw");
+        assert_eq!(
+            requests[0].authorization.as_deref(),
+            Some("Bearer ha-token")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_waits_until_power_drops() {
+        let server = MockServer::start(vec![sensor("300"), sensor("250"), sensor("120")]).await;
+        let hardware = monitoring(&server.endpoint);
+
+        wait_for_power_headroom(&Client::new(), &hardware, FAST, Duration::from_secs(5)).await;
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_proceeds_when_ha_unreachable() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        // Returns rather than polling forever
+        wait_for_power_headroom(
+            &Client::new(),
+            &monitoring(&endpoint),
+            FAST,
+            Duration::from_secs(600),
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_timeout() {
+        let server = MockServer::start(vec![sensor("300"); 50]).await;
+        let hardware = monitoring(&server.endpoint);
+
+        wait_for_power_headroom(
+            &Client::new(),
+            &hardware,
+            Duration::from_millis(20),
+            Duration::from_millis(50),
+        )
+        .await;
+        assert!((1..=3).contains(&server.requests().len()));
+    }
+
+    #[tokio::test]
+    async fn test_disabled_guard_makes_no_requests() {
+        let server = MockServer::start(vec![sensor("300")]).await;
+        let mut hardware = monitoring(&server.endpoint);
+        hardware.enable_ha_power_monitoring = false;
+
+        wait_for_power_headroom(&Client::new(), &hardware, FAST, FAST).await;
+        assert!(server.requests().is_empty());
+    }
+}
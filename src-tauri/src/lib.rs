@@ -7,6 +7,9 @@ pub mod commands;
 pub mod config;
 pub mod db;
 pub mod gallery;
+pub mod hardware;
+#[cfg(test)]
+mod mock_http;
 pub mod pipeline;
 pub mod queue;
 pub mod state;
@@ -86,10 +89,17 @@ pub fn run() {
         eprintln!("[startup] Requeued {} interrupted jobs", requeued);
     }
 
+    match gallery::deletion::purge_expired_trash(&conn, &config) {
+        Ok(0) => {}
+        Ok(purged) => eprintln!("[startup] Purged {} expired images from the trash", purged),
+        Err(e) => eprintln!("[startup] Failed to purge expired trash: {:#}", e),
+    }
+
     // Capture the configured image directory before config is moved into AppState
     let custom_image_dir = config::manager::image_dir(&config);
 
     let app_state = state::AppState::new(conn, config);
+    let backup_db_path = db_path.clone();
 
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
@@ -123,6 +133,7 @@ pub fn run() {
 
             queue::executor::spawn(app.handle().clone());
             ai_batch::executor::spawn(app.handle().clone());
+            db::backup_schedule::spawn(app.handle().clone(), backup_db_path);
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -131,8 +142,15 @@ pub fn run() {
             commands::config_cmds::save_config,
             // Pipeline
             commands::pipeline_cmds::run_full_pipeline,
+            commands::pipeline_cmds::idea_to_image,
+            commands::pipeline_cmds::record_prompt_edits,
+            commands::pipeline_cmds::list_saved_prompts,
+            commands::pipeline_cmds::idea_from_image,
             commands::pipeline_cmds::run_pipeline_stage,
+            commands::pipeline_cmds::tune_prompt,
             commands::pipeline_cmds::cancel_pipeline,
+            commands::pipeline_cmds::get_prompt_templates,
+            commands::pipeline_cmds::set_prompt_template,
             commands::pipeline_cmds::get_available_models,
             commands::pipeline_cmds::get_thinking_models,
             commands::pipeline_cmds::check_ollama_health,
@@ -141,38 +159,82 @@ pub fn run() {
             commands::comfyui_cmds::get_comfyui_checkpoints,
             commands::comfyui_cmds::get_comfyui_samplers,
             commands::comfyui_cmds::get_comfyui_schedulers,
+            commands::comfyui_cmds::get_comfyui_loras,
+            commands::comfyui_cmds::get_comfyui_controlnets,
             commands::comfyui_cmds::queue_generation,
             commands::comfyui_cmds::get_generation_status,
             commands::comfyui_cmds::get_comfyui_queue_status,
+            commands::comfyui_cmds::get_comfyui_system_stats,
+            commands::comfyui_cmds::estimate_fit,
             commands::comfyui_cmds::free_comfyui_memory,
             commands::comfyui_cmds::interrupt_comfyui,
+            commands::comfyui_cmds::smoke_test,
+            commands::comfyui_cmds::validate_workflow,
+            commands::comfyui_cmds::preview_seeds,
+            commands::comfyui_cmds::start_comfyui_log_tail,
+            commands::comfyui_cmds::stop_comfyui_log_tail,
             // Queue
             commands::queue_cmds::add_to_queue,
             commands::queue_cmds::get_queue,
             commands::queue_cmds::reorder_queue,
             commands::queue_cmds::cancel_queue_job,
+            commands::queue_cmds::cancel_group,
+            commands::queue_cmds::enqueue_variable_sweep,
+            commands::queue_cmds::relink_result,
             commands::queue_cmds::pause_queue,
             commands::queue_cmds::resume_queue,
             commands::queue_cmds::is_queue_paused,
             commands::queue_cmds::prune_old_queue_jobs,
             // Gallery
             commands::gallery_cmds::get_gallery_images,
+            commands::gallery_cmds::get_new_images,
+            commands::search_cmds::find_similar_images,
+            commands::search_cmds::find_duplicates,
+            commands::gallery_cmds::mark_gallery_seen,
             commands::gallery_cmds::get_image,
+            commands::gallery_cmds::get_total_compute,
+            commands::gallery_cmds::list_gallery_checkpoints,
+            commands::gallery_cmds::get_recent_choices,
+            commands::gallery_cmds::get_term_frequencies,
+            commands::gallery_cmds::import_images,
             commands::gallery_cmds::delete_image,
+            commands::batch_cmds::delete_images,
             commands::gallery_cmds::restore_image,
             commands::gallery_cmds::permanently_delete_image,
             commands::gallery_cmds::update_image_rating,
+            commands::batch_cmds::rate_range,
+            commands::batch_cmds::rate_images,
+            commands::batch_cmds::favorite_images,
             commands::gallery_cmds::update_image_favorite,
+            commands::gallery_cmds::quick_triage,
+            commands::batch_cmds::reattribute_checkpoint,
             commands::gallery_cmds::update_caption,
+            commands::gallery_cmds::add_image_caption,
+            commands::gallery_cmds::list_image_captions,
+            commands::gallery_cmds::delete_image_caption,
             commands::gallery_cmds::update_image_note,
-            commands::gallery_cmds::add_tag,
-            commands::gallery_cmds::remove_tag,
+            commands::tag_cmds::add_tag,
+            commands::tag_cmds::remove_tag,
+            commands::tag_cmds::add_tag_implication,
+            commands::tag_cmds::remove_tag_implication,
+            commands::tag_cmds::rename_tag,
+            commands::tag_cmds::merge_tags,
+            commands::tag_cmds::get_tag_counts,
+            commands::tag_cmds::list_tag_implications,
             commands::gallery_cmds::get_image_lineage,
+            commands::gallery_cmds::get_image_derivation,
+            commands::gallery_cmds::variation,
             commands::gallery_cmds::get_image_file_path,
-            commands::gallery_cmds::get_thumbnail_file_path,
+            commands::gallery_cmds::get_image_dir_path,
+            commands::thumbnail_cmds::get_thumbnail_file_path,
+            commands::thumbnail_cmds::regenerate_thumbnails,
+            commands::search_cmds::index_prompt_embeddings,
+            commands::search_cmds::rebuild_search_index,
+            commands::search_cmds::semantic_search,
             // AI
             commands::ai_cmds::tag_image,
             commands::ai_cmds::caption_image,
+            commands::ai_cmds::score_aesthetic,
             // AI Batch
             commands::ai_batch_cmds::submit_batch_job,
             commands::ai_batch_cmds::get_batch_jobs,
@@ -185,6 +247,7 @@ pub fn run() {
             // Seeds
             commands::seed_cmds::create_seed,
             commands::seed_cmds::get_seed,
+            commands::seed_cmds::set_sample_image,
             commands::seed_cmds::list_seeds,
             commands::seed_cmds::delete_seed,
             commands::seed_cmds::add_seed_tag,
@@ -194,12 +257,17 @@ pub fn run() {
             // Checkpoints
             commands::checkpoint_cmds::upsert_checkpoint,
             commands::checkpoint_cmds::get_checkpoint,
+            commands::checkpoint_cmds::get_checkpoint_defaults,
+            commands::checkpoint_cmds::set_checkpoint_sample_image,
             commands::checkpoint_cmds::list_checkpoint_profiles,
             commands::checkpoint_cmds::add_prompt_term,
             commands::checkpoint_cmds::get_prompt_terms,
             commands::checkpoint_cmds::add_checkpoint_observation,
+            commands::checkpoint_cmds::promote_note_to_observation,
             commands::checkpoint_cmds::get_checkpoint_observations,
             commands::checkpoint_cmds::get_checkpoint_context,
+            commands::checkpoint_cmds::suggest_checkpoint_profile,
+            commands::checkpoint_cmds::preview_pipeline_context,
             // Comparisons
             commands::comparison_cmds::create_comparison,
             commands::comparison_cmds::get_comparison,
@@ -207,9 +275,13 @@ pub fn run() {
             commands::comparison_cmds::list_comparisons_for_checkpoint,
             commands::comparison_cmds::update_comparison_note,
             commands::comparison_cmds::delete_comparison,
+            commands::comparison_cmds::continue_from_winner,
             // Export
             commands::export_cmds::export_images,
             commands::export_cmds::export_gallery,
+            commands::export_cmds::export_folder,
+            commands::export_cmds::export_provenance,
+            commands::export_cmds::cancel_export,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
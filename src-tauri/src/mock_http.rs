@@ -0,0 +1,137 @@
+//! Minimal canned-response HTTP server for tests that exercise the Ollama and
+//! ComfyUI clients end to end. Each incoming request consumes the next queued
+//! response body, regardless of path.
+
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub path: String,
+    pub body: String,
+    /// Value of the `Authorization` header, if sent.
+    pub authorization: Option<String>,
+}
+
+pub struct MockServer {
+    pub endpoint: String,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+}
+
+impl MockServer {
+    /// Start a server that answers requests with `responses` in order.
+    /// Once the queue is exhausted every further request gets a 404.
+    pub async fn start(responses: Vec<String>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let queue = Arc::new(Mutex::new(responses.into_iter()));
+
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let next = queue.lock().unwrap().next();
+                let recorded = recorded.clone();
+                tokio::spawn(async move {
+                    handle(stream, next, recorded).await;
+                });
+            }
+        });
+
+        Self {
+            endpoint: format!("http://{}", addr),
+            requests,
+        }
+    }
+
+    /// Requests received so far, in arrival order.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+async fn handle(
+    mut stream: TcpStream,
+    response: Option<String>,
+    recorded: Arc<Mutex<Vec<RecordedRequest>>>,
+) {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let header_end = loop {
+        let n = stream.read(&mut chunk).await.unwrap_or(0);
+        if n == 0 {
+            return;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buf[..header_end]).to_string();
+    let path = head
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .unwrap_or("/")
+        .to_string();
+    let header = |wanted: &str| {
+        head.lines().find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            name.eq_ignore_ascii_case(wanted)
+                .then(|| value.trim().to_string())
+        })
+    };
+    let content_length = header("content-length")
+        .and_then(|value| value.parse::<usize>().ok())
+        .unwrap_or(0);
+    let authorization = header("authorization");
+
+    while buf.len() < header_end + content_length {
+        let n = stream.read(&mut chunk).await.unwrap_or(0);
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+    let body = String::from_utf8_lossy(&buf[header_end..]).to_string();
+    recorded.lock().unwrap().push(RecordedRequest {
+        path,
+        body,
+        authorization,
+    });
+
+    let (status, payload) = match response {
+        Some(p) => ("200 OK", p),
+        None => ("404 Not Found", String::new()),
+    };
+    let reply = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        payload.len(),
+        payload
+    );
+    let _ = stream.write_all(reply.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+/// Body of a non-streaming Ollama `/api/chat` response.
+pub fn ollama_chat(content: &str) -> String {
+    serde_json::json!({
+        "message": { "role": "assistant", "content": content },
+        "done": true,
+        "prompt_eval_count": 10,
+        "eval_count": 20,
+    })
+    .to_string()
+}
+
+/// Body of an Ollama `/api/tags` response listing `models` as installed.
+pub fn ollama_tags(models: &[&str]) -> String {
+    let models: Vec<_> = models
+        .iter()
+        .map(|name| serde_json::json!({ "name": name }))
+        .collect();
+    serde_json::json!({ "models": models }).to_string()
+}
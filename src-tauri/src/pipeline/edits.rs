@@ -0,0 +1,102 @@
+use anyhow::{Context, Result};
+
+use super::engine;
+use crate::types::pipeline::{EditDiff, PipelineResult, PromptPair, UserEdits};
+
+/// Record the user's final prompts on a pipeline result. The Prompt
+/// Engineer's output is left untouched so both versions are kept; the diff
+/// is computed per comma-separated term.
+pub fn apply_user_edits(result: &mut PipelineResult, edited: PromptPair) -> Result<()> {
+    let original = engine::get_final_prompts(result)
+        .context("Pipeline produced no prompts to edit (is the Prompt Engineer stage enabled?)")?;
+
+    let diff = EditDiff {
+        positive_added: terms_missing_from(&edited.positive, &original.positive),
+        positive_removed: terms_missing_from(&original.positive, &edited.positive),
+        negative_added: terms_missing_from(&edited.negative, &original.negative),
+        negative_removed: terms_missing_from(&original.negative, &edited.negative),
+    };
+    let prompt_edited = edited.positive.trim() != original.positive.trim()
+        || edited.negative.trim() != original.negative.trim();
+
+    result.user_edits = Some(UserEdits {
+        prompt_edited,
+        edit_diff: prompt_edited.then_some(diff),
+        edited_prompts: prompt_edited.then_some(edited),
+    });
+    Ok(())
+}
+
+/// The prompts generation should use: the user's edit if there is one,
+/// otherwise the Prompt Engineer's output.
+pub fn effective_prompts(result: &PipelineResult) -> Option<PromptPair> {
+    result
+        .user_edits
+        .as_ref()
+        .and_then(|e| e.edited_prompts.clone())
+        .or_else(|| engine::get_final_prompts(result))
+}
+
+fn terms(prompt: &str) -> impl Iterator<Item = &str> {
+    prompt.split(',').map(str::trim).filter(|t| !t.is_empty())
+}
+
+fn terms_missing_from(prompt: &str, other: &str) -> Vec<String> {
+    let other: Vec<&str> = terms(other).collect();
+    terms(prompt)
+        .filter(|t| !other.contains(t))
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::engine::tests::make_test_result;
+
+    fn edited() -> PromptPair {
+        PromptPair {
+            positive: "masterpiece, cat on throne, gold crown".to_string(),
+            negative: "lowres".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_apply_user_edits_records_edit_and_diff() {
+        let mut result = make_test_result();
+        apply_user_edits(&mut result, edited()).unwrap();
+
+        let edits = result.user_edits.as_ref().unwrap();
+        assert!(edits.prompt_edited);
+        let diff = edits.edit_diff.as_ref().unwrap();
+        assert_eq!(diff.positive_added, vec!["gold crown"]);
+        assert!(diff.positive_removed.is_empty());
+        assert_eq!(diff.negative_removed, vec!["blurry"]);
+
+        // Original output is preserved; generation uses the edit
+        let original = engine::get_final_prompts(&result).unwrap();
+        assert_eq!(original.positive, "masterpiece, cat on throne");
+        assert_eq!(
+            effective_prompts(&result).unwrap().positive,
+            "masterpiece, cat on throne, gold crown"
+        );
+    }
+
+    #[test]
+    fn test_unchanged_prompts_are_not_an_edit() {
+        let mut result = make_test_result();
+        let same = engine::get_final_prompts(&result).unwrap();
+        apply_user_edits(&mut result, same).unwrap();
+
+        let edits = result.user_edits.as_ref().unwrap();
+        assert!(!edits.prompt_edited);
+        assert!(edits.edited_prompts.is_none());
+    }
+
+    #[test]
+    fn test_apply_user_edits_without_prompt_engineer_fails() {
+        let mut result = make_test_result();
+        result.stages.prompt_engineer = None;
+        assert!(apply_user_edits(&mut result, edited()).is_err());
+    }
+}
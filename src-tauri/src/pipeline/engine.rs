@@ -3,9 +3,11 @@ use reqwest::Client;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
-use crate::pipeline::prompts::CheckpointContext;
-use crate::pipeline::stages;
-use crate::types::config::AppConfig;
+use crate::pipeline::ollama::LlmEndpoint;
+use crate::pipeline::preflight::{self, ResolvedModels};
+use crate::pipeline::prompts::{CheckpointContext, PromptTemplates};
+use crate::pipeline::{review_loop, stages};
+use crate::types::config::{AppConfig, ModelAssignments, PipelineSettings};
 use crate::types::pipeline::{
     ComposerOutput, ModelsUsed, PipelineConfig, PipelineResult, PipelineStages, PromptPair,
 };
@@ -15,40 +17,38 @@ pub struct PipelineInput {
     pub num_concepts: u32,
     pub auto_approve: bool,
     pub checkpoint_context: Option<CheckpointContext>,
+    pub prompt_templates: PromptTemplates,
 }
 
-pub async fn run_pipeline(
-    client: &Client,
-    config: &AppConfig,
-    input: PipelineInput,
-    cancelled: Option<Arc<AtomicBool>>,
-) -> Result<PipelineResult> {
-    // Validate input
-    const MAX_IDEA_LENGTH: usize = 10_000;
-    const MAX_CONCEPTS: u32 = 10;
+impl PipelineInput {
+    /// Reject an empty or oversized idea and an out-of-range concept count.
+    pub(super) fn validate(&self) -> Result<()> {
+        const MAX_IDEA_LENGTH: usize = 10_000;
+        const MAX_CONCEPTS: u32 = 10;
 
-    if input.idea.is_empty() {
-        anyhow::bail!("Idea cannot be empty");
-    }
-    if input.idea.len() > MAX_IDEA_LENGTH {
-        anyhow::bail!(
-            "Idea text too long ({} chars, max {})",
-            input.idea.len(),
-            MAX_IDEA_LENGTH
-        );
-    }
-    if input.num_concepts == 0 || input.num_concepts > MAX_CONCEPTS {
-        anyhow::bail!("Number of concepts must be between 1 and {}", MAX_CONCEPTS);
+        if self.idea.is_empty() {
+            anyhow::bail!("Idea cannot be empty");
+        }
+        if self.idea.len() > MAX_IDEA_LENGTH {
+            anyhow::bail!(
+                "Idea text too long ({} chars, max {})",
+                self.idea.len(),
+                MAX_IDEA_LENGTH
+            );
+        }
+        if self.num_concepts == 0 || self.num_concepts > MAX_CONCEPTS {
+            anyhow::bail!("Number of concepts must be between 1 and {}", MAX_CONCEPTS);
+        }
+        Ok(())
     }
+}
 
-    let pipeline = &config.pipeline;
-    let models = &config.models;
-    let endpoint = &config.ollama.endpoint;
-
-    // Resolve per-stage thinking mode from config
-    let think_for =
-        |stage_name: &str| -> Option<bool> { models.thinking_overrides.get(stage_name).copied() };
-
+/// Which stages run and the model each enabled stage uses.
+pub(super) fn pipeline_config(
+    pipeline: &PipelineSettings,
+    resolved: &ResolvedModels,
+) -> PipelineConfig {
+    let models = &resolved.models;
     let stages_enabled = [
         pipeline.enable_ideator,
         pipeline.enable_composer,
@@ -56,56 +56,92 @@ pub async fn run_pipeline(
         pipeline.enable_prompt_engineer,
         pipeline.enable_reviewer,
     ];
-
-    let models_used = ModelsUsed {
-        ideator: if stages_enabled[0] {
-            Some(models.ideator.clone())
-        } else {
-            None
-        },
-        composer: if stages_enabled[1] {
-            Some(models.composer.clone())
-        } else {
-            None
-        },
-        judge: if stages_enabled[2] {
-            Some(models.judge.clone())
-        } else {
-            None
-        },
-        prompt_engineer: if stages_enabled[3] {
-            Some(models.prompt_engineer.clone())
-        } else {
-            None
-        },
-        reviewer: if stages_enabled[4] {
-            Some(models.reviewer.clone())
-        } else {
-            None
+    let model_if = |enabled: bool, model: &String| enabled.then(|| model.clone());
+    PipelineConfig {
+        stages_enabled,
+        models_used: ModelsUsed {
+            ideator: model_if(stages_enabled[0], &models.ideator),
+            composer: model_if(stages_enabled[1], &models.composer),
+            judge: model_if(stages_enabled[2], &models.judge),
+            prompt_engineer: model_if(stages_enabled[3], &models.prompt_engineer),
+            reviewer: model_if(stages_enabled[4], &models.reviewer),
+            substitutions: resolved.substitutions.clone(),
         },
-    };
+    }
+}
 
-    let pipeline_config = PipelineConfig {
-        stages_enabled,
-        models_used,
+/// Unload the last used model to free VRAM for Stable Diffusion. The judge
+/// only ran when there was more than one composed description.
+pub(super) async fn unload_last_model(
+    client: &Client,
+    endpoint: &LlmEndpoint,
+    models: &ModelAssignments,
+    stages_enabled: [bool; 5],
+    composed_count: usize,
+) {
+    let last_model = if stages_enabled[4] {
+        Some(&models.reviewer)
+    } else if stages_enabled[3] {
+        Some(&models.prompt_engineer)
+    } else if stages_enabled[2] && composed_count > 1 {
+        Some(&models.judge)
+    } else if stages_enabled[1] {
+        Some(&models.composer)
+    } else if stages_enabled[0] {
+        Some(&models.ideator)
+    } else {
+        None
     };
+    if let Some(model) = last_model.filter(|_| endpoint.is_ollama()) {
+        let _ = super::ollama::unload_model(client, &endpoint.url, model).await;
+    }
+}
+
+fn check_cancelled(cancelled: &Option<Arc<AtomicBool>>) -> Result<()> {
+    if cancelled
+        .as_ref()
+        .is_some_and(|flag| flag.load(Ordering::Relaxed))
+    {
+        anyhow::bail!("Pipeline cancelled by user");
+    }
+    Ok(())
+}
+
+pub async fn run_pipeline(
+    client: &Client,
+    config: &AppConfig,
+    input: PipelineInput,
+    cancelled: Option<Arc<AtomicBool>>,
+) -> Result<PipelineResult> {
+    input.validate()?;
+
+    let pipeline = &config.pipeline;
+    let endpoint = &LlmEndpoint::from(&config.ollama);
+    let resolved = preflight::resolve_stage_models(client, config).await?;
+    let models = &resolved.models;
+
+    // Resolve per-stage thinking mode from config
+    let think_for =
+        |stage_name: &str| -> Option<bool> { models.thinking_overrides.get(stage_name).copied() };
+    let sampling_for = |stage_name: &str| config.stage_tuning.for_stage(stage_name);
+
+    let pipeline_config = pipeline_config(pipeline, &resolved);
+    let stages_enabled = pipeline_config.stages_enabled;
 
     let mut result_stages = PipelineStages::default();
 
     // Stage 1: Ideator
     let concepts = if stages_enabled[0] {
-        if let Some(ref flag) = cancelled {
-            if flag.load(Ordering::Relaxed) {
-                anyhow::bail!("Pipeline cancelled by user");
-            }
-        }
+        check_cancelled(&cancelled)?;
         let ideator_output = stages::run_ideator(
             client,
             endpoint,
             &models.ideator,
             &input.idea,
             input.num_concepts,
+            &input.prompt_templates,
             think_for("ideator"),
+            sampling_for("ideator"),
         )
         .await
         .context("Pipeline failed at Ideator stage")?;
@@ -128,11 +164,7 @@ pub async fn run_pipeline(
 
     // Stage 2: Composer — enrich each concept
     let (composed, all_composer_outputs) = if stages_enabled[1] {
-        if let Some(ref flag) = cancelled {
-            if flag.load(Ordering::Relaxed) {
-                anyhow::bail!("Pipeline cancelled by user");
-            }
-        }
+        check_cancelled(&cancelled)?;
         let mut composed_descs = Vec::new();
         let mut all_outputs: Vec<ComposerOutput> = Vec::new();
 
@@ -143,7 +175,9 @@ pub async fn run_pipeline(
                 &models.composer,
                 concept,
                 i,
+                &input.prompt_templates,
                 think_for("composer"),
+                sampling_for("composer"),
             )
             .await
             .with_context(|| format!("Pipeline failed at Composer stage for concept {}", i))?;
@@ -159,18 +193,17 @@ pub async fn run_pipeline(
 
     // Stage 3: Judge — rank composed descriptions (skip if only 1 concept)
     let (top_description, selected_index) = if stages_enabled[2] && composed.len() > 1 {
-        if let Some(ref flag) = cancelled {
-            if flag.load(Ordering::Relaxed) {
-                anyhow::bail!("Pipeline cancelled by user");
-            }
-        }
+        check_cancelled(&cancelled)?;
         let judge_output = stages::run_judge(
             client,
             endpoint,
             &models.judge,
             &input.idea,
             &composed,
+            &input.prompt_templates,
             think_for("judge"),
+            sampling_for("judge"),
+            pipeline.max_retries,
         )
         .await
         .context("Pipeline failed at Judge stage")?;
@@ -204,152 +237,72 @@ pub async fn run_pipeline(
         }
     }
 
-    // Stage 4: Prompt Engineer — convert to SD prompts
-    let prompt_pair = if stages_enabled[3] {
-        if let Some(ref flag) = cancelled {
-            if flag.load(Ordering::Relaxed) {
-                anyhow::bail!("Pipeline cancelled by user");
-            }
-        }
-        let pe_output = stages::run_prompt_engineer(
+    let (cancelled, idea, templates) = (&cancelled, &input.idea, &input.prompt_templates);
+    let (top_description, checkpoint_context) = (&top_description, &input.checkpoint_context);
+    let engineer = |feedback: Option<Vec<String>>| async move {
+        check_cancelled(cancelled)?;
+        stages::run_prompt_engineer(
             client,
             endpoint,
             &models.prompt_engineer,
-            &top_description,
-            input.checkpoint_context,
+            top_description,
+            checkpoint_context.clone(),
+            feedback.as_deref(),
+            templates,
             think_for("promptEngineer"),
+            sampling_for("promptEngineer"),
+            pipeline.max_retries,
         )
         .await
-        .context("Pipeline failed at Prompt Engineer stage")?;
-        let pair = pe_output.output.clone();
-        result_stages.prompt_engineer = Some(pe_output);
-        pair
-    } else {
-        // Bypass: use description as positive prompt, default negative
-        PromptPair {
-            positive: top_description.clone(),
-            negative: "lowres, bad anatomy, bad hands, text, watermark, blurry".to_string(),
-        }
+        .context("Pipeline failed at Prompt Engineer stage")
     };
-
-    // Stage 5: Reviewer — sanity check
-    if stages_enabled[4] {
-        if let Some(ref flag) = cancelled {
-            if flag.load(Ordering::Relaxed) {
-                anyhow::bail!("Pipeline cancelled by user");
-            }
-        }
-        let reviewer_output = stages::run_reviewer(
+    let review = |pair: PromptPair| async move {
+        check_cancelled(cancelled)?;
+        stages::run_reviewer(
             client,
             endpoint,
             &models.reviewer,
-            &input.idea,
-            &prompt_pair.positive,
-            &prompt_pair.negative,
+            idea,
+            &pair.positive,
+            &pair.negative,
+            templates,
             think_for("reviewer"),
+            sampling_for("reviewer"),
+            pipeline.max_retries,
+            pipeline.reviewer_fail_mode,
         )
         .await
-        .context("Pipeline failed at Reviewer stage")?;
-        result_stages.reviewer = Some(reviewer_output);
-    }
+        .context("Pipeline failed at Reviewer stage")
+    };
+    review_loop::run_review_loop(
+        pipeline,
+        top_description,
+        &mut result_stages,
+        engineer,
+        review,
+        |_, _, _| {},
+    )
+    .await?;
 
-    // If reviewer suggested changes and was not approved, update the prompt engineer output
-    if let Some(ref reviewer) = result_stages.reviewer {
-        if !reviewer.approved {
-            if let Some(ref mut pe) = result_stages.prompt_engineer {
-                if let Some(ref suggested_pos) = reviewer.suggested_positive {
-                    pe.output.positive = suggested_pos.clone();
-                }
-                if let Some(ref suggested_neg) = reviewer.suggested_negative {
-                    pe.output.negative = suggested_neg.clone();
-                }
-            }
-        }
-    }
+    unload_last_model(client, endpoint, models, stages_enabled, composed.len()).await;
 
-    // Unload the last used model to free VRAM for Stable Diffusion
-    let last_model = if stages_enabled[4] {
-        Some(&models.reviewer)
-    } else if stages_enabled[3] {
-        Some(&models.prompt_engineer)
-    } else if stages_enabled[2] && composed.len() > 1 {
-        Some(&models.judge)
-    } else if stages_enabled[1] {
-        Some(&models.composer)
-    } else if stages_enabled[0] {
-        Some(&models.ideator)
-    } else {
-        None
-    };
-    if let Some(model) = last_model {
-        let _ = super::ollama::unload_model(client, endpoint, model).await;
+    if !pipeline.capture_raw {
+        result_stages.clear_raw_responses();
     }
 
+    let summary = result_stages.summary();
     Ok(PipelineResult {
+        run_id: Some(uuid::Uuid::new_v4().to_string()),
         original_idea: input.idea,
         pipeline_config,
         stages: result_stages,
         user_edits: None,
         auto_approved: input.auto_approve,
         generation_settings: None,
+        summary: Some(summary),
     })
 }
 
-/// Run a single pipeline stage by name (for the run_pipeline_stage command)
-pub async fn run_single_stage(
-    client: &Client,
-    endpoint: &str,
-    stage: &str,
-    model: &str,
-    input: &str,
-    checkpoint_context: Option<CheckpointContext>,
-) -> Result<String> {
-    match stage {
-        "ideator" => {
-            let output = stages::run_ideator(client, endpoint, model, input, 5, None).await?;
-            serde_json::to_string(&output).context("Failed to serialize ideator output")
-        }
-        "composer" => {
-            let output = stages::run_composer(client, endpoint, model, input, 0, None).await?;
-            serde_json::to_string(&output).context("Failed to serialize composer output")
-        }
-        "judge" => {
-            let concepts: Vec<String> = serde_json::from_str(input)
-                .context("Judge input must be a JSON array of strings")?;
-            let output = stages::run_judge(client, endpoint, model, "", &concepts, None).await?;
-            serde_json::to_string(&output).context("Failed to serialize judge output")
-        }
-        "prompt_engineer" => {
-            let output = stages::run_prompt_engineer(
-                client,
-                endpoint,
-                model,
-                input,
-                checkpoint_context,
-                None,
-            )
-            .await?;
-            serde_json::to_string(&output).context("Failed to serialize prompt engineer output")
-        }
-        "reviewer" => {
-            let pair: PromptPair = serde_json::from_str(input)
-                .context("Reviewer input must be JSON with positive/negative fields")?;
-            let output = stages::run_reviewer(
-                client,
-                endpoint,
-                model,
-                "",
-                &pair.positive,
-                &pair.negative,
-                None,
-            )
-            .await?;
-            serde_json::to_string(&output).context("Failed to serialize reviewer output")
-        }
-        _ => anyhow::bail!("Unknown pipeline stage: {}", stage),
-    }
-}
-
 /// Get the final prompts from a pipeline result
 pub fn get_final_prompts(result: &PipelineResult) -> Option<PromptPair> {
     result
@@ -372,4 +325,4 @@ pub fn get_selected_concept(result: &PipelineResult) -> usize {
 
 #[cfg(test)]
 #[path = "engine_test.rs"]
-mod tests;
+pub(crate) mod tests;
@@ -4,12 +4,13 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tauri::{AppHandle, Emitter};
 
-use super::engine::PipelineInput;
-use super::stages_streaming;
+use super::engine::{self, PipelineInput};
+use super::ollama::LlmEndpoint;
+use super::{preflight, review_loop, stages_streaming};
 use crate::types::config::AppConfig;
 use crate::types::pipeline::{
-    ComposerOutput, ModelsUsed, PipelineConfig, PipelineResult, PipelineStageCompleteEvent,
-    PipelineStageStartEvent, PipelineStageTokenEvent, PipelineStages, PromptPair,
+    ComposerOutput, PipelineResult, PipelineReviewIterationEvent, PipelineStageCompleteEvent,
+    PipelineStageStartEvent, PipelineStageTokenEvent, PipelineStages, PromptPair, ReviewerOutput,
 };
 
 fn check_cancelled(cancelled: &Arc<AtomicBool>) -> Result<()> {
@@ -26,72 +27,20 @@ pub async fn run_pipeline_streaming(
     app_handle: AppHandle,
     cancelled: Arc<AtomicBool>,
 ) -> Result<PipelineResult> {
-    // Validate input
-    const MAX_IDEA_LENGTH: usize = 10_000;
-    const MAX_CONCEPTS: u32 = 10;
-
-    if input.idea.is_empty() {
-        anyhow::bail!("Idea cannot be empty");
-    }
-    if input.idea.len() > MAX_IDEA_LENGTH {
-        anyhow::bail!(
-            "Idea text too long ({} chars, max {})",
-            input.idea.len(),
-            MAX_IDEA_LENGTH
-        );
-    }
-    if input.num_concepts == 0 || input.num_concepts > MAX_CONCEPTS {
-        anyhow::bail!("Number of concepts must be between 1 and {}", MAX_CONCEPTS);
-    }
+    input.validate()?;
 
     let pipeline = &config.pipeline;
-    let models = &config.models;
-    let endpoint = &config.ollama.endpoint;
+    let endpoint = &LlmEndpoint::from(&config.ollama);
+    let resolved = preflight::resolve_stage_models(client, config).await?;
+    let models = &resolved.models;
 
     // Resolve per-stage thinking mode from config
     let think_for =
         |stage_name: &str| -> Option<bool> { models.thinking_overrides.get(stage_name).copied() };
+    let sampling_for = |stage_name: &str| config.stage_tuning.for_stage(stage_name);
 
-    let stages_enabled = [
-        pipeline.enable_ideator,
-        pipeline.enable_composer,
-        pipeline.enable_judge,
-        pipeline.enable_prompt_engineer,
-        pipeline.enable_reviewer,
-    ];
-
-    let models_used = ModelsUsed {
-        ideator: if stages_enabled[0] {
-            Some(models.ideator.clone())
-        } else {
-            None
-        },
-        composer: if stages_enabled[1] {
-            Some(models.composer.clone())
-        } else {
-            None
-        },
-        judge: if stages_enabled[2] {
-            Some(models.judge.clone())
-        } else {
-            None
-        },
-        prompt_engineer: if stages_enabled[3] {
-            Some(models.prompt_engineer.clone())
-        } else {
-            None
-        },
-        reviewer: if stages_enabled[4] {
-            Some(models.reviewer.clone())
-        } else {
-            None
-        },
-    };
-
-    let pipeline_config = PipelineConfig {
-        stages_enabled,
-        models_used,
-    };
+    let pipeline_config = engine::pipeline_config(pipeline, &resolved);
+    let stages_enabled = pipeline_config.stages_enabled;
 
     let mut result_stages = PipelineStages::default();
 
@@ -112,7 +61,9 @@ pub async fn run_pipeline_streaming(
             &models.ideator,
             &input.idea,
             input.num_concepts,
+            &input.prompt_templates,
             think_for("ideator"),
+            sampling_for("ideator"),
             Some(cancelled.clone()),
             move |token: &str| {
                 let _ = ah.emit(
@@ -171,7 +122,9 @@ pub async fn run_pipeline_streaming(
                 &models.composer,
                 concept,
                 i,
+                &input.prompt_templates,
                 think_for("composer"),
+                sampling_for("composer"),
                 Some(cancelled.clone()),
                 move |token: &str| {
                     let _ = ah.emit(
@@ -219,7 +172,10 @@ pub async fn run_pipeline_streaming(
             &models.judge,
             &input.idea,
             &composed,
+            &input.prompt_templates,
             think_for("judge"),
+            sampling_for("judge"),
+            pipeline.max_retries,
             Some(cancelled.clone()),
             move |token: &str| {
                 let _ = ah.emit(
@@ -270,9 +226,11 @@ pub async fn run_pipeline_streaming(
         }
     }
 
-    // Stage 4: Prompt Engineer — convert to SD prompts
-    let prompt_pair = if stages_enabled[3] {
-        check_cancelled(&cancelled)?;
+    let (cancelled, app_handle) = (&cancelled, &app_handle);
+    let (idea, templates) = (&input.idea, &input.prompt_templates);
+    let (top_description, checkpoint_context) = (&top_description, &input.checkpoint_context);
+    let engineer = |feedback: Option<Vec<String>>| async move {
+        check_cancelled(cancelled)?;
         let _ = app_handle.emit(
             "pipeline:stage_start",
             PipelineStageStartEvent {
@@ -285,9 +243,13 @@ pub async fn run_pipeline_streaming(
             client,
             endpoint,
             &models.prompt_engineer,
-            &top_description,
-            input.checkpoint_context,
+            top_description,
+            checkpoint_context.clone(),
+            feedback.as_deref(),
+            templates,
             think_for("promptEngineer"),
+            sampling_for("promptEngineer"),
+            pipeline.max_retries,
             Some(cancelled.clone()),
             move |token: &str| {
                 let _ = ah.emit(
@@ -309,19 +271,10 @@ pub async fn run_pipeline_streaming(
                 duration_ms: pe_output.duration_ms,
             },
         );
-        let pair = pe_output.output.clone();
-        result_stages.prompt_engineer = Some(pe_output);
-        pair
-    } else {
-        PromptPair {
-            positive: top_description.clone(),
-            negative: "lowres, bad anatomy, bad hands, text, watermark, blurry".to_string(),
-        }
+        Ok(pe_output)
     };
-
-    // Stage 5: Reviewer — sanity check
-    if stages_enabled[4] {
-        check_cancelled(&cancelled)?;
+    let review = |pair: PromptPair| async move {
+        check_cancelled(cancelled)?;
         let _ = app_handle.emit(
             "pipeline:stage_start",
             PipelineStageStartEvent {
@@ -334,10 +287,14 @@ pub async fn run_pipeline_streaming(
             client,
             endpoint,
             &models.reviewer,
-            &input.idea,
-            &prompt_pair.positive,
-            &prompt_pair.negative,
+            idea,
+            &pair.positive,
+            &pair.negative,
+            templates,
             think_for("reviewer"),
+            sampling_for("reviewer"),
+            pipeline.max_retries,
+            pipeline.reviewer_fail_mode,
             Some(cancelled.clone()),
             move |token: &str| {
                 let _ = ah.emit(
@@ -359,47 +316,44 @@ pub async fn run_pipeline_streaming(
                 duration_ms: reviewer_output.duration_ms,
             },
         );
-        result_stages.reviewer = Some(reviewer_output);
-    }
+        Ok(reviewer_output)
+    };
+    let on_review = |iteration, max_iterations, output: &ReviewerOutput| {
+        let _ = app_handle.emit(
+            "pipeline:review_iteration",
+            PipelineReviewIterationEvent {
+                iteration,
+                max_iterations,
+                approved: output.approved,
+                issues: output.issues.clone().unwrap_or_default(),
+            },
+        );
+    };
+    review_loop::run_review_loop(
+        pipeline,
+        top_description,
+        &mut result_stages,
+        engineer,
+        review,
+        on_review,
+    )
+    .await?;
 
-    // If reviewer suggested changes and was not approved, update the prompt engineer output
-    if let Some(ref reviewer) = result_stages.reviewer {
-        if !reviewer.approved {
-            if let Some(ref mut pe) = result_stages.prompt_engineer {
-                if let Some(ref suggested_pos) = reviewer.suggested_positive {
-                    pe.output.positive = suggested_pos.clone();
-                }
-                if let Some(ref suggested_neg) = reviewer.suggested_negative {
-                    pe.output.negative = suggested_neg.clone();
-                }
-            }
-        }
-    }
+    engine::unload_last_model(client, endpoint, models, stages_enabled, composed.len()).await;
 
-    // Unload the last used model to free VRAM for Stable Diffusion
-    let last_model = if stages_enabled[4] {
-        Some(&models.reviewer)
-    } else if stages_enabled[3] {
-        Some(&models.prompt_engineer)
-    } else if stages_enabled[2] && composed.len() > 1 {
-        Some(&models.judge)
-    } else if stages_enabled[1] {
-        Some(&models.composer)
-    } else if stages_enabled[0] {
-        Some(&models.ideator)
-    } else {
-        None
-    };
-    if let Some(model) = last_model {
-        let _ = super::ollama::unload_model(client, endpoint, model).await;
+    if !pipeline.capture_raw {
+        result_stages.clear_raw_responses();
     }
 
+    let summary = result_stages.summary();
     Ok(PipelineResult {
+        run_id: Some(uuid::Uuid::new_v4().to_string()),
         original_idea: input.idea,
         pipeline_config,
         stages: result_stages,
         user_edits: None,
         auto_approved: input.auto_approve,
         generation_settings: None,
+        summary: Some(summary),
     })
 }
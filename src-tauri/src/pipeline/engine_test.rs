@@ -4,8 +4,9 @@ use crate::types::pipeline::{
     PipelineResult, PipelineStages, PromptEngineerOutput, PromptPair, ReviewerOutput,
 };
 
-fn make_test_result() -> PipelineResult {
+pub fn make_test_result() -> PipelineResult {
     PipelineResult {
+        run_id: Some("run-001".to_string()),
         original_idea: "a cat on a throne".to_string(),
         pipeline_config: PipelineConfig {
             stages_enabled: [true, true, true, true, false],
@@ -15,6 +16,7 @@ fn make_test_result() -> PipelineResult {
                 judge: Some("qwen2.5:7b".to_string()),
                 prompt_engineer: Some("mistral:7b".to_string()),
                 reviewer: None,
+                substitutions: Vec::new(),
             },
         },
         stages: PipelineStages {
@@ -25,6 +27,8 @@ fn make_test_result() -> PipelineResult {
                 model: "mistral:7b".to_string(),
                 tokens_in: Some(50),
                 tokens_out: Some(200),
+                fallback: None,
+                raw_response: None,
             }),
             composer: Some(ComposerOutput {
                 input_concept_index: 1,
@@ -34,6 +38,7 @@ fn make_test_result() -> PipelineResult {
                 model: "llama3.1:8b".to_string(),
                 tokens_in: Some(80),
                 tokens_out: Some(150),
+                raw_response: None,
             }),
             judge: Some(JudgeOutput {
                 input: vec!["Desc A".to_string(), "Desc B".to_string()],
@@ -53,6 +58,8 @@ fn make_test_result() -> PipelineResult {
                 ],
                 duration_ms: 2000,
                 model: "qwen2.5:7b".to_string(),
+                parse_retries: 0,
+                raw_response: None,
             }),
             prompt_engineer: Some(PromptEngineerOutput {
                 input: "Rich description".to_string(),
@@ -65,12 +72,15 @@ fn make_test_result() -> PipelineResult {
                 model: "mistral:7b".to_string(),
                 tokens_in: Some(100),
                 tokens_out: Some(60),
+                parse_retries: 0,
+                raw_response: None,
             }),
             reviewer: None,
         },
         user_edits: None,
         auto_approved: false,
         generation_settings: None,
+        summary: None,
     }
 }
 
@@ -102,6 +112,27 @@ fn test_get_selected_concept_no_judge() {
     assert_eq!(get_selected_concept(&result), 0);
 }
 
+#[test]
+fn test_summary_totals_stage_numbers() {
+    let mut result = make_test_result();
+    let summary = result.stages.summary();
+    // Judge adds its time but has no token counts
+    assert_eq!(summary.total_duration_ms, 1000 + 1500 + 2000 + 1000);
+    assert_eq!(summary.total_tokens_in, 50 + 80 + 100);
+    assert_eq!(summary.total_tokens_out, 200 + 150 + 60);
+    assert_eq!(summary.stage_count, 4);
+
+    if let Some(pe) = result.stages.prompt_engineer.as_mut() {
+        pe.tokens_in = None;
+    }
+    result.stages.ideator = None;
+    let summary = result.stages.summary();
+    assert_eq!(summary.total_duration_ms, 1500 + 2000 + 1000);
+    assert_eq!(summary.total_tokens_in, 80);
+    assert_eq!(summary.total_tokens_out, 150 + 60);
+    assert_eq!(summary.stage_count, 3);
+}
+
 #[test]
 fn test_pipeline_result_serialization() {
     let result = make_test_result();
@@ -120,25 +151,157 @@ fn test_reviewer_overrides_prompts() {
         issues: Some(vec!["prompt drift".to_string()]),
         suggested_positive: Some("better positive".to_string()),
         suggested_negative: Some("better negative".to_string()),
+        fidelity_score: Some(55),
         duration_ms: 500,
         model: "qwen2.5:7b".to_string(),
+        parse_retries: 0,
+        raw_response: None,
     });
 
-    // Simulate the engine's reviewer override logic
-    if let Some(ref reviewer) = result.stages.reviewer {
-        if !reviewer.approved {
-            if let Some(ref mut pe) = result.stages.prompt_engineer {
-                if let Some(ref sp) = reviewer.suggested_positive {
-                    pe.output.positive = sp.clone();
-                }
-                if let Some(ref sn) = reviewer.suggested_negative {
-                    pe.output.negative = sn.clone();
-                }
-            }
-        }
-    }
+    review_loop::apply_reviewer_suggestions(&mut result.stages);
 
     let prompts = get_final_prompts(&result).unwrap();
     assert_eq!(prompts.positive, "better positive");
     assert_eq!(prompts.negative, "better negative");
 }
+
+async fn run_composer_only(capture_raw: bool) -> PipelineResult {
+    use crate::mock_http::{ollama_chat, ollama_tags, MockServer};
+
+    let mut config = AppConfig::default();
+    let server = MockServer::start(vec![
+        ollama_tags(&[&config.models.composer]),
+        ollama_chat("  A cat on a gilded throne  "),
+    ])
+    .await;
+    config.ollama.endpoint = server.endpoint.clone();
+    config.pipeline.enable_ideator = false;
+    config.pipeline.enable_judge = false;
+    config.pipeline.enable_prompt_engineer = false;
+    config.pipeline.capture_raw = capture_raw;
+    let input = PipelineInput {
+        idea: "a cat on a throne".to_string(),
+        num_concepts: 1,
+        auto_approve: false,
+        checkpoint_context: None,
+        prompt_templates: PromptTemplates::default(),
+    };
+
+    run_pipeline(&Client::new(), &config, input, None)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_capture_raw_keeps_model_response() {
+    let result = run_composer_only(true).await;
+    let composer = result.stages.composer.unwrap();
+    assert_eq!(composer.output, "A cat on a gilded throne");
+    assert_eq!(
+        composer.raw_response.as_deref(),
+        Some("  A cat on a gilded throne  ")
+    );
+}
+
+#[tokio::test]
+async fn test_raw_response_dropped_without_capture_raw() {
+    let result = run_composer_only(false).await;
+    let composer = result.stages.composer.unwrap();
+    assert_eq!(composer.output, "A cat on a gilded throne");
+    assert!(composer.raw_response.is_none());
+}
+
+#[tokio::test]
+async fn test_run_pipeline_attaches_summary() {
+    let result = run_composer_only(false).await;
+    let summary = result.summary.unwrap();
+    assert_eq!(summary.stage_count, 1);
+    // From the mocked reply's prompt_eval_count / eval_count
+    assert_eq!(summary.total_tokens_in, 10);
+    assert_eq!(summary.total_tokens_out, 20);
+}
+
+#[tokio::test]
+async fn test_missing_model_stops_before_any_stage() {
+    use crate::mock_http::{ollama_tags, MockServer};
+
+    let server = MockServer::start(vec![ollama_tags(&["some-other-model:1b"])]).await;
+    let mut config = AppConfig::default();
+    config.ollama.endpoint = server.endpoint.clone();
+    let input = PipelineInput {
+        idea: "a cat on a throne".to_string(),
+        num_concepts: 1,
+        auto_approve: false,
+        checkpoint_context: None,
+        prompt_templates: PromptTemplates::default(),
+    };
+
+    let err = run_pipeline(&Client::new(), &config, input, None)
+        .await
+        .unwrap_err();
+    let expected = format!("Run `ollama pull {}`", config.models.ideator);
+    assert!(err.to_string().contains(&expected), "{}", err);
+    // Only the model list was requested; no stage ran
+    assert_eq!(server.requests().len(), 1);
+    assert_eq!(server.requests()[0].path, "/api/tags");
+}
+
+#[tokio::test]
+async fn test_rejected_review_reruns_prompt_engineer_with_issues() {
+    use crate::mock_http::{ollama_chat, ollama_tags, MockServer};
+
+    let mut config = AppConfig::default();
+    config.models.prompt_engineer = "pe-model".to_string();
+    config.models.reviewer = "review-model".to_string();
+    let server = MockServer::start(vec![
+        ollama_tags(&["pe-model", "review-model"]),
+        ollama_chat(r#"{"positive": "cat", "negative": "lowres"}"#),
+        ollama_chat(
+            r#"{"approved": false, "fidelity_score": 40, "issues": ["dropped the throne"], "suggested_positive": "cat, throne"}"#,
+        ),
+        ollama_chat(r#"{"positive": "cat on a golden throne", "negative": "lowres"}"#),
+        ollama_chat(r#"{"approved": true, "fidelity_score": 92}"#),
+    ])
+    .await;
+    config.ollama.endpoint = server.endpoint.clone();
+    config.pipeline.enable_ideator = false;
+    config.pipeline.enable_composer = false;
+    config.pipeline.enable_judge = false;
+    config.pipeline.enable_reviewer = true;
+    config.pipeline.max_review_iterations = 3;
+    let input = PipelineInput {
+        idea: "a cat on a throne".to_string(),
+        num_concepts: 1,
+        auto_approve: false,
+        checkpoint_context: None,
+        prompt_templates: PromptTemplates::default(),
+    };
+
+    let result = run_pipeline(&Client::new(), &config, input, None)
+        .await
+        .unwrap();
+
+    let chats: Vec<_> = server
+        .requests()
+        .into_iter()
+        .filter(|r| r.path == "/api/chat")
+        .collect();
+    let models: Vec<String> = chats
+        .iter()
+        .map(|r| {
+            let body: serde_json::Value = serde_json::from_str(&r.body).unwrap();
+            body["model"].as_str().unwrap().to_string()
+        })
+        .collect();
+    // Stopped after the approval, with an iteration to spare
+    assert_eq!(
+        models,
+        ["pe-model", "review-model", "pe-model", "review-model"]
+    );
+    assert!(!chats[0].body.contains("dropped the throne"));
+    assert!(chats[2].body.contains("dropped the throne"));
+
+    assert!(result.stages.reviewer.as_ref().unwrap().approved);
+    let prompts = get_final_prompts(&result).unwrap();
+    assert_eq!(prompts.positive, "cat on a golden throne");
+}
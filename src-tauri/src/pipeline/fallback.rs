@@ -0,0 +1,310 @@
+use anyhow::{Context, Result};
+use reqwest::Client;
+use serde_json::Value;
+
+use super::ollama::{self, ChatMessage, LlmEndpoint};
+use super::options;
+use super::parsing::{extract_json_from_text, parse_reviewer_output, ParsedReviewer};
+use super::prompts;
+use crate::types::config::ReviewerFailMode;
+use crate::types::pipeline::IdeatorFallback;
+
+/// Recover Ideator concepts after the numbered-list parse came back empty.
+/// Retries once in JSON mode; if that also yields nothing, the raw idea
+/// becomes the single concept so the run can continue.
+pub(super) async fn recover_ideator_concepts(
+    client: &Client,
+    endpoint: &LlmEndpoint,
+    model: &str,
+    idea: &str,
+    num_concepts: u32,
+    think: Option<bool>,
+) -> (Vec<String>, IdeatorFallback) {
+    let (system, user) = prompts::ideator_json_prompt(idea, num_concepts);
+    let messages = vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content: system,
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: user,
+        },
+    ];
+
+    match ollama::chat_with_options(
+        client,
+        endpoint,
+        model,
+        &messages,
+        true,
+        &options::stage_options_with_thinking(1024, think),
+    )
+    .await
+    {
+        Ok(resp) => {
+            let concepts = parse_concept_array(&resp.content);
+            if !concepts.is_empty() {
+                return (concepts, IdeatorFallback::JsonRetry);
+            }
+            eprintln!("[pipeline] Ideator JSON retry returned no concepts; using raw idea");
+        }
+        Err(e) => {
+            eprintln!(
+                "[pipeline] Ideator JSON retry failed: {:#}; using raw idea",
+                e
+            );
+        }
+    }
+
+    (vec![idea.trim().to_string()], IdeatorFallback::RawIdea)
+}
+
+/// Settle the Reviewer's verdict after `reply` to `messages` couldn't be
+/// parsed. Approve and reject record the failure as an issue so the skipped
+/// review stays visible; retry asks once more for JSON and fails the stage
+/// if that reply is unparseable too.
+#[allow(clippy::too_many_arguments)]
+pub(super) async fn recover_reviewer_verdict(
+    client: &Client,
+    endpoint: &LlmEndpoint,
+    model: &str,
+    messages: &[ChatMessage],
+    reply: &str,
+    error: anyhow::Error,
+    mode: ReviewerFailMode,
+    think: Option<bool>,
+) -> Result<ParsedReviewer> {
+    eprintln!(
+        "[pipeline] Reviewer reply unparseable ({:#}); fail mode {:?}",
+        error, mode
+    );
+    let approved = match mode {
+        ReviewerFailMode::ApproveOnError => true,
+        ReviewerFailMode::RejectOnError => false,
+        ReviewerFailMode::RetryOnError => {
+            let mut retry = messages.to_vec();
+            retry.push(ChatMessage {
+                role: "assistant".to_string(),
+                content: reply.to_string(),
+            });
+            retry.push(ChatMessage {
+                role: "user".to_string(),
+                content: "That reply could not be parsed. Respond with only the JSON object \
+                          described above, including a boolean \"approved\" field."
+                    .to_string(),
+            });
+            let resp = ollama::chat_with_options(
+                client,
+                endpoint,
+                model,
+                &retry,
+                true,
+                &options::stage_options_with_thinking(1024, think),
+            )
+            .await
+            .context("Reviewer retry failed")?;
+            return parse_reviewer_output(&resp.content)
+                .context("Reviewer reply was still unparseable after a retry");
+        }
+    };
+
+    let verdict = if approved { "approved" } else { "not approved" };
+    Ok(ParsedReviewer {
+        approved,
+        issues: Some(vec![format!(
+            "Reviewer reply could not be parsed; marked {} without review",
+            verdict
+        )]),
+        suggested_positive: None,
+        suggested_negative: None,
+        fidelity_score: None,
+    })
+}
+
+/// Parse `{"concepts": [...]}`, any object wrapping a string array, or a bare array.
+pub(super) fn parse_concept_array(text: &str) -> Vec<String> {
+    let Ok(json) = extract_json_from_text(text) else {
+        return Vec::new();
+    };
+
+    let arr = match &json {
+        Value::Array(a) => Some(a),
+        Value::Object(obj) => obj
+            .get("concepts")
+            .and_then(|v| v.as_array())
+            .or_else(|| obj.values().find_map(|v| v.as_array())),
+        _ => None,
+    };
+
+    arr.map(|a| {
+        a.iter()
+            .filter_map(|v| v.as_str())
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_http::{ollama_chat, MockServer};
+    use crate::pipeline::prompts::PromptTemplates;
+    use crate::pipeline::stages::{run_ideator, run_reviewer};
+    use crate::types::config::StageSampling;
+    use crate::types::pipeline::ReviewerOutput;
+
+    #[test]
+    fn test_parse_concept_array_object() {
+        let concepts = parse_concept_array(r#"{"concepts": ["A cat", "A dog"]}"#);
+        assert_eq!(concepts, vec!["A cat", "A dog"]);
+    }
+
+    #[test]
+    fn test_parse_concept_array_bare_and_garbage() {
+        assert_eq!(parse_concept_array(r#"["only one"]"#), vec!["only one"]);
+        assert!(parse_concept_array("no json here").is_empty());
+        assert!(parse_concept_array(r#"{"concepts": []}"#).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unparseable_ideator_falls_back_to_raw_idea() {
+        let server =
+            MockServer::start(vec![ollama_chat("   \n"), ollama_chat("still not json")]).await;
+        let client = Client::new();
+
+        let out = run_ideator(
+            &client,
+            &LlmEndpoint::ollama(&server.endpoint),
+            "m",
+            "a cat on a throne",
+            3,
+            &PromptTemplates::default(),
+            None,
+            StageSampling::default(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(out.output, vec!["a cat on a throne"]);
+        assert_eq!(out.fallback, Some(IdeatorFallback::RawIdea));
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].path, "/api/chat");
+        assert!(requests[1].body.contains(r#""format":"json""#));
+    }
+
+    #[tokio::test]
+    async fn test_ideator_json_retry_recovers_concepts() {
+        let server = MockServer::start(vec![
+            ollama_chat(""),
+            ollama_chat(r#"{"concepts": ["Gothic cat", "Pixel-art cat"]}"#),
+        ])
+        .await;
+        let client = Client::new();
+
+        let out = run_ideator(
+            &client,
+            &LlmEndpoint::ollama(&server.endpoint),
+            "m",
+            "cat",
+            2,
+            &PromptTemplates::default(),
+            None,
+            StageSampling::default(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(out.output, vec!["Gothic cat", "Pixel-art cat"]);
+        assert_eq!(out.fallback, Some(IdeatorFallback::JsonRetry));
+    }
+
+    async fn review_unparseable(
+        replies: Vec<String>,
+        mode: ReviewerFailMode,
+    ) -> (Result<ReviewerOutput>, MockServer) {
+        let server = MockServer::start(replies).await;
+        let out = run_reviewer(
+            &Client::new(),
+            &LlmEndpoint::ollama(&server.endpoint),
+            "m",
+            "a cat",
+            "a cat, masterpiece",
+            "blurry",
+            &PromptTemplates::default(),
+            None,
+            StageSampling::default(),
+            0,
+            mode,
+        )
+        .await;
+        (out, server)
+    }
+
+    #[tokio::test]
+    async fn test_reviewer_approve_on_error_flags_skipped_review() {
+        let (out, server) = review_unparseable(
+            vec![ollama_chat("Looks good to me!")],
+            ReviewerFailMode::ApproveOnError,
+        )
+        .await;
+        let out = out.unwrap();
+        assert!(out.approved);
+        assert!(out.issues.unwrap()[0].contains("could not be parsed"));
+        assert_eq!(out.raw_response.as_deref(), Some("Looks good to me!"));
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_reviewer_reject_on_error_needs_attention() {
+        let (out, server) = review_unparseable(
+            vec![ollama_chat("Looks good to me!")],
+            ReviewerFailMode::RejectOnError,
+        )
+        .await;
+        let out = out.unwrap();
+        assert!(!out.approved);
+        assert!(out.issues.unwrap()[0].contains("could not be parsed"));
+        assert!(out.suggested_positive.is_none());
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_reviewer_retry_on_error_uses_json_reply() {
+        let (out, server) = review_unparseable(
+            vec![
+                ollama_chat("Looks good to me!"),
+                ollama_chat(r#"{"approved": false, "issues": ["drift"]}"#),
+            ],
+            ReviewerFailMode::RetryOnError,
+        )
+        .await;
+        let out = out.unwrap();
+        assert!(!out.approved);
+        assert_eq!(out.issues.unwrap(), vec!["drift"]);
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].body.contains(r#""format":"json""#));
+        assert!(requests[1].body.contains("Looks good to me!"));
+    }
+
+    #[tokio::test]
+    async fn test_reviewer_retry_on_error_fails_when_still_unparseable() {
+        let (out, server) = review_unparseable(
+            vec![
+                ollama_chat("Looks good to me!"),
+                ollama_chat("Yes, approved."),
+            ],
+            ReviewerFailMode::RetryOnError,
+        )
+        .await;
+        let err = format!("{:#}", out.unwrap_err());
+        assert!(err.contains("still unparseable"), "{}", err);
+        assert_eq!(server.requests().len(), 2);
+    }
+}
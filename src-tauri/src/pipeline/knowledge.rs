@@ -0,0 +1,352 @@
+use anyhow::{Context, Result};
+use reqwest::Client;
+use rusqlite::Connection;
+
+use crate::db;
+use crate::pipeline::ollama::{self, ChatMessage, LlmEndpoint};
+use crate::pipeline::options;
+use crate::pipeline::parsing::extract_json_from_text;
+use crate::pipeline::prompts::CheckpointContext;
+use crate::pipeline::retry::parse_with_retries;
+use crate::types::checkpoints::ProfileSuggestion;
+use crate::types::config::AppConfig;
+
+/// How many of the newest observations are shown to the model.
+pub const SUMMARY_OBSERVATION_LIMIT: usize = 40;
+
+const SUMMARY_SYSTEM: &str =
+    "You maintain a behavioral profile of a Stable Diffusion checkpoint. Read the \
+profile, the prompt terms tested on it and the observations recorded while using it, \
+then write an updated list of its strengths and weaknesses.\n\n\
+Rules:\n\
+- Each entry is a short phrase (2-8 words), e.g. \"soft portrait lighting\"\n\
+- Keep existing entries the observations still support; drop ones they contradict\n\
+- Only add entries backed by the observations or term data\n\
+- At most 8 strengths and 8 weaknesses\n\n\
+Respond in EXACTLY this JSON format:\n\
+{\"strengths\": [\"...\"], \"weaknesses\": [\"...\"]}";
+
+/// Everything recorded about a checkpoint, as text for the summarizer: its
+/// current profile, known terms, and the newest observations.
+pub fn summary_context(conn: &Connection, filename: &str) -> Result<String> {
+    let profile = db::checkpoints::get_checkpoint(conn, filename)?
+        .with_context(|| format!("Checkpoint {} not found", filename))?;
+    let checkpoint_id = profile.id.unwrap_or(0);
+    let terms = db::checkpoints::get_prompt_terms(conn, checkpoint_id)?;
+    let observations = db::checkpoints::get_observations(conn, checkpoint_id)?;
+    if terms.is_empty() && observations.is_empty() {
+        anyhow::bail!(
+            "No observations or terms recorded for {} yet — nothing to summarize",
+            filename
+        );
+    }
+
+    let mut context = format!("Checkpoint file: {}\n", filename);
+    context.push_str(&db::checkpoints::get_checkpoint_context(conn, filename)?);
+    if !observations.is_empty() {
+        context.push_str("Recent observations:\n");
+        for obs in observations.iter().take(SUMMARY_OBSERVATION_LIMIT) {
+            context.push_str(&format!(
+                "- [{}] {}\n",
+                obs.source.as_str(),
+                obs.observation
+            ));
+        }
+    }
+    Ok(context)
+}
+
+/// The checkpoint profile as the Prompt Engineer receives it, or `None` when
+/// nothing is stored for `filename` (the stage then uses the defaults).
+pub fn pipeline_context(conn: &Connection, filename: &str) -> Result<Option<CheckpointContext>> {
+    let context = db::checkpoints::get_checkpoint_context(conn, filename)?;
+    if context.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(parse_checkpoint_context(&context, filename)))
+    }
+}
+
+/// Parse the text from `get_checkpoint_context` into prompt fields; anything
+/// it doesn't mention keeps the default.
+pub fn parse_checkpoint_context(context_str: &str, checkpoint: &str) -> CheckpointContext {
+    // Try JSON first (new format)
+    if let Ok(ctx) = serde_json::from_str::<CheckpointContext>(context_str) {
+        return ctx;
+    }
+
+    // Fall back to line-based parsing (legacy format)
+    let mut ctx = CheckpointContext {
+        checkpoint_name: checkpoint.to_string(),
+        ..Default::default()
+    };
+
+    for line in context_str.lines() {
+        let line = line.trim();
+        if let Some(rest) = line.strip_prefix("Checkpoint: ") {
+            ctx.checkpoint_name = rest.to_string();
+        } else if let Some(rest) = line.strip_prefix("Base model: ") {
+            ctx.base_model = rest.to_string();
+        } else if let Some(rest) = line.strip_prefix("Strengths: ") {
+            ctx.strengths = rest.to_string();
+        } else if let Some(rest) = line.strip_prefix("Weaknesses: ") {
+            ctx.weaknesses = rest.to_string();
+        } else if let Some(rest) = line.strip_prefix("Notes: ") {
+            ctx.checkpoint_notes = rest.to_string();
+        } else if line.starts_with("Known terms:") {
+            ctx.term_list = String::new();
+        } else if line.starts_with("- ") {
+            if !ctx.term_list.is_empty() {
+                ctx.term_list.push('\n');
+            }
+            ctx.term_list.push_str(line);
+        }
+    }
+
+    ctx
+}
+
+/// Ask the judge model for updated strengths and weaknesses from `context`
+/// (see [`summary_context`]). Nothing is saved; the caller shows the
+/// suggestion and writes it with `upsert_checkpoint` once it's accepted.
+pub async fn suggest_profile(
+    client: &Client,
+    config: &AppConfig,
+    context: &str,
+) -> Result<ProfileSuggestion> {
+    let model = &config.models.judge;
+    let messages = vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content: SUMMARY_SYSTEM.to_string(),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: context.to_string(),
+        },
+    ];
+
+    let think = config.models.thinking_overrides.get("judge").copied();
+    let opts = options::stage_options_with_thinking(1024, think)
+        .with_sampling(config.stage_tuning.for_stage("judge"));
+    let endpoint = &LlmEndpoint::from(&config.ollama);
+    let resp = ollama::chat_with_options(client, endpoint, model, &messages, true, &opts)
+        .await
+        .context("Checkpoint summary failed")?;
+    let parsed = parse_with_retries(
+        client,
+        endpoint,
+        model,
+        &messages,
+        &opts,
+        resp,
+        config.pipeline.max_retries,
+        parse_profile_suggestion,
+    )
+    .await?;
+
+    let (strengths, weaknesses) = parsed.value.context("Failed to parse checkpoint summary")?;
+    Ok(ProfileSuggestion {
+        strengths,
+        weaknesses,
+        model: model.clone(),
+    })
+}
+
+fn parse_profile_suggestion(text: &str) -> Result<(Vec<String>, Vec<String>)> {
+    let json = extract_json_from_text(text)?;
+    let list = |key: &str| -> Result<Vec<String>> {
+        let items = json
+            .get(key)
+            .and_then(|v| v.as_array())
+            .with_context(|| format!("Summary has no \"{}\" list", key))?;
+        Ok(items
+            .iter()
+            .filter_map(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+            .collect())
+    };
+    Ok((list("strengths")?, list("weaknesses")?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_http::{ollama_chat, MockServer};
+    use crate::types::checkpoints::{
+        CheckpointObservation, CheckpointProfile, ObservationSource, PromptTerm, TermStrength,
+    };
+
+    fn seed_checkpoint(conn: &Connection) -> i64 {
+        let id = db::checkpoints::upsert_checkpoint(
+            conn,
+            &CheckpointProfile {
+                id: None,
+                filename: "dreamshaper_8.safetensors".to_string(),
+                display_name: Some("DreamShaper 8".to_string()),
+                base_model: Some("SD1.5".to_string()),
+                created_at: None,
+                strengths: Some(vec!["fantasy scenes".to_string()]),
+                weaknesses: None,
+                preferred_cfg: None,
+                cfg_range_low: None,
+                cfg_range_high: None,
+                preferred_sampler: None,
+                preferred_scheduler: None,
+                optimal_resolution: None,
+                notes: None,
+                sample_image_id: None,
+                sample_filename: None,
+            },
+        )
+        .unwrap();
+        db::checkpoints::add_prompt_term(
+            conn,
+            &PromptTerm {
+                id: None,
+                checkpoint_id: id,
+                term: "volumetric fog".to_string(),
+                effect: "thick atmospheric haze".to_string(),
+                strength: TermStrength::Strong,
+                example_image_id: None,
+                created_at: None,
+            },
+        )
+        .unwrap();
+        db::checkpoints::add_observation(
+            conn,
+            &CheckpointObservation {
+                id: None,
+                checkpoint_id: id,
+                observation: "hands come out mangled above cfg 8".to_string(),
+                source: ObservationSource::User,
+                comparison_id: None,
+                created_at: None,
+            },
+        )
+        .unwrap();
+        id
+    }
+
+    #[test]
+    fn test_summary_context_includes_terms_and_observations() {
+        let conn = db::open_memory_database().unwrap();
+        seed_checkpoint(&conn);
+
+        let context = summary_context(&conn, "dreamshaper_8.safetensors").unwrap();
+        assert!(context.contains("Checkpoint file: dreamshaper_8.safetensors"));
+        assert!(context.contains("Strengths: fantasy scenes"));
+        assert!(context.contains("- volumetric fog (strong): thick atmospheric haze"));
+        assert!(context.contains("- [user] hands come out mangled above cfg 8"));
+    }
+
+    #[test]
+    fn test_summary_context_needs_recorded_data() {
+        let conn = db::open_memory_database().unwrap();
+        assert!(summary_context(&conn, "missing.safetensors").is_err());
+
+        db::checkpoints::upsert_checkpoint(
+            &conn,
+            &CheckpointProfile {
+                id: None,
+                filename: "empty.safetensors".to_string(),
+                display_name: None,
+                base_model: None,
+                created_at: None,
+                strengths: None,
+                weaknesses: None,
+                preferred_cfg: None,
+                cfg_range_low: None,
+                cfg_range_high: None,
+                preferred_sampler: None,
+                preferred_scheduler: None,
+                optimal_resolution: None,
+                notes: None,
+                sample_image_id: None,
+                sample_filename: None,
+            },
+        )
+        .unwrap();
+        let err = summary_context(&conn, "empty.safetensors").unwrap_err();
+        assert!(err.to_string().contains("nothing to summarize"));
+    }
+
+    #[tokio::test]
+    async fn test_suggest_profile_returns_model_lists() {
+        let conn = db::open_memory_database().unwrap();
+        let checkpoint_id = seed_checkpoint(&conn);
+        let context = summary_context(&conn, "dreamshaper_8.safetensors").unwrap();
+
+        let server = MockServer::start(vec![ollama_chat(
+            r#"{"strengths": ["fantasy scenes", " atmospheric fog "], "weaknesses": ["hands at high cfg", ""]}"#,
+        )])
+        .await;
+        let mut config = AppConfig::default();
+        config.ollama.endpoint = server.endpoint.clone();
+        config.models.judge = "judge-model".to_string();
+
+        let suggestion = suggest_profile(&Client::new(), &config, &context)
+            .await
+            .unwrap();
+        assert_eq!(
+            suggestion.strengths,
+            vec!["fantasy scenes", "atmospheric fog"]
+        );
+        assert_eq!(suggestion.weaknesses, vec!["hands at high cfg"]);
+        assert_eq!(suggestion.model, "judge-model");
+
+        let body: serde_json::Value = serde_json::from_str(&server.requests()[0].body).unwrap();
+        assert_eq!(body["model"], "judge-model");
+        let messages = body["messages"].to_string();
+        assert!(messages.contains("volumetric fog"));
+        assert!(messages.contains("hands come out mangled"));
+
+        // Only a suggestion: the stored profile is untouched
+        let profile = db::checkpoints::get_checkpoint(&conn, "dreamshaper_8.safetensors")
+            .unwrap()
+            .unwrap();
+        assert_eq!(profile.id, Some(checkpoint_id));
+        assert_eq!(profile.strengths, Some(vec!["fantasy scenes".to_string()]));
+        assert!(profile.weaknesses.is_none());
+    }
+
+    #[test]
+    fn test_pipeline_context_lists_stored_terms() {
+        let conn = db::open_memory_database().unwrap();
+        seed_checkpoint(&conn);
+
+        let ctx = pipeline_context(&conn, "dreamshaper_8.safetensors")
+            .unwrap()
+            .unwrap();
+        assert_eq!(ctx.checkpoint_name, "DreamShaper 8");
+        assert_eq!(ctx.base_model, "SD1.5");
+        assert_eq!(ctx.strengths, "fantasy scenes");
+        assert_eq!(
+            ctx.term_list,
+            "- volumetric fog (strong): thick atmospheric haze"
+        );
+        // Not in the profile, so the Prompt Engineer gets the defaults
+        assert_eq!(ctx.weaknesses, CheckpointContext::default().weaknesses);
+    }
+
+    #[test]
+    fn test_pipeline_context_unknown_checkpoint_is_none() {
+        let conn = db::open_memory_database().unwrap();
+        let ctx = pipeline_context(&conn, "missing.safetensors").unwrap();
+        assert!(ctx.is_none());
+        let fallback = ctx.unwrap_or_default();
+        assert_eq!(fallback.checkpoint_name, "unknown");
+        assert_eq!(fallback.term_list, "No specific term data available.");
+    }
+
+    #[test]
+    fn test_parse_profile_suggestion_requires_both_lists() {
+        assert!(parse_profile_suggestion(r#"{"strengths": ["a"]}"#).is_err());
+        assert!(parse_profile_suggestion("no json here").is_err());
+        let (s, w) = parse_profile_suggestion(r#"{"strengths": [], "weaknesses": ["b"]}"#).unwrap();
+        assert!(s.is_empty());
+        assert_eq!(w, vec!["b"]);
+    }
+}
@@ -1,6 +1,19 @@
+pub mod edits;
 pub mod engine;
 pub mod engine_streaming;
+mod fallback;
+pub mod knowledge;
 pub mod ollama;
+pub mod ollama_streaming;
+pub mod openai;
+pub mod options;
+mod parsing;
+pub mod preflight;
+pub mod prompt_templates;
 pub mod prompts;
+mod retry;
+mod review_loop;
+pub mod single_stage;
 pub mod stages;
 pub mod stages_streaming;
+pub mod thinking;
@@ -0,0 +1,208 @@
+use anyhow::{Context, Result};
+use futures::StreamExt;
+use reqwest::Client;
+use serde_json::Value;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::pipeline::ollama::{normalize_endpoint, ChatMessage, ChatResponse, LlmEndpoint};
+use crate::pipeline::openai;
+use crate::pipeline::options::{build_options, OllamaOptions};
+use crate::pipeline::thinking::is_known_thinking_model;
+
+/// Streaming variant of chat that calls `on_token` for each token chunk.
+/// Returns the full accumulated response when done.
+pub async fn chat_streaming<F>(
+    client: &Client,
+    endpoint: &LlmEndpoint,
+    model: &str,
+    messages: &[ChatMessage],
+    format_json: bool,
+    on_token: F,
+) -> Result<ChatResponse>
+where
+    F: FnMut(&str),
+{
+    chat_streaming_with_options(
+        client,
+        endpoint,
+        model,
+        messages,
+        format_json,
+        &OllamaOptions::default(),
+        None,
+        on_token,
+    )
+    .await
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn chat_streaming_with_options<F>(
+    client: &Client,
+    endpoint: &LlmEndpoint,
+    model: &str,
+    messages: &[ChatMessage],
+    format_json: bool,
+    opts: &OllamaOptions,
+    cancelled: Option<Arc<AtomicBool>>,
+    mut on_token: F,
+) -> Result<ChatResponse>
+where
+    F: FnMut(&str),
+{
+    if !endpoint.is_ollama() {
+        return openai::chat_streaming(
+            client,
+            endpoint,
+            model,
+            messages,
+            format_json,
+            opts,
+            cancelled,
+            on_token,
+        )
+        .await;
+    }
+    let endpoint = normalize_endpoint(&endpoint.url);
+    let url = format!("{}/api/chat", endpoint);
+
+    let mut body = serde_json::json!({
+        "model": model,
+        "messages": messages,
+        "stream": true,
+        "keep_alive": "30m",
+    });
+
+    if format_json {
+        body["format"] = serde_json::json!("json");
+    }
+
+    let options = build_options(opts);
+    if !options.is_empty() {
+        body["options"] = serde_json::json!(options);
+    }
+
+    // Apply thinking mode — this is a top-level parameter, not inside "options".
+    // Only send it for models that actually support thinking; non-thinking models
+    // (e.g. llama3.1) will reject the parameter with a 400 error.
+    if let Some(think) = opts.think {
+        if is_known_thinking_model(model) {
+            body["think"] = serde_json::json!(think);
+        }
+    }
+
+    let resp = client
+        .post(&url)
+        .timeout(Duration::from_secs(300))
+        .json(&body)
+        .send()
+        .await
+        .with_context(|| {
+            format!(
+                "Cannot connect to Ollama at {} — is the service running?",
+                endpoint
+            )
+        })?;
+
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        anyhow::bail!("Ollama returned {} for chat: {}", status, body);
+    }
+
+    let mut stream = resp.bytes_stream();
+    let mut accumulated_content = String::new();
+    let mut total_duration_ns: Option<u64> = None;
+    let mut prompt_eval_count: Option<u64> = None;
+    let mut eval_count: Option<u64> = None;
+    let mut line_buffer = String::new();
+    const MAX_BUFFER_SIZE: usize = 1_048_576; // 1MB
+
+    while let Some(chunk) = stream.next().await {
+        if let Some(ref flag) = cancelled {
+            if flag.load(Ordering::Relaxed) {
+                anyhow::bail!("Pipeline cancelled by user");
+            }
+        }
+        let chunk = chunk.context("Error reading stream chunk")?;
+        let text = String::from_utf8_lossy(&chunk);
+        line_buffer.push_str(&text);
+
+        // Guard against unbounded buffer accumulation
+        if line_buffer.len() > MAX_BUFFER_SIZE {
+            anyhow::bail!(
+                "Ollama response exceeded maximum buffer size ({}MB). Response may be malformed.",
+                MAX_BUFFER_SIZE / 1_048_576
+            );
+        }
+
+        // Ollama sends newline-delimited JSON
+        while let Some(newline_pos) = line_buffer.find('\n') {
+            let line = line_buffer[..newline_pos].trim().to_string();
+            line_buffer = line_buffer[newline_pos + 1..].to_string();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Ok(json) = serde_json::from_str::<Value>(&line) {
+                if let Some(error) = json.get("error").and_then(|v| v.as_str()) {
+                    anyhow::bail!("Ollama error: {}", error);
+                }
+
+                if let Some(content) = json
+                    .get("message")
+                    .and_then(|m| m.get("content"))
+                    .and_then(|c| c.as_str())
+                {
+                    if !content.is_empty() {
+                        accumulated_content.push_str(content);
+                        if accumulated_content.len() > MAX_BUFFER_SIZE {
+                            anyhow::bail!(
+                                "Ollama accumulated response exceeded {}MB limit",
+                                MAX_BUFFER_SIZE / 1_048_576
+                            );
+                        }
+                        on_token(content);
+                    }
+                }
+
+                if json.get("done").and_then(|v| v.as_bool()).unwrap_or(false) {
+                    total_duration_ns = json.get("total_duration").and_then(|v| v.as_u64());
+                    prompt_eval_count = json.get("prompt_eval_count").and_then(|v| v.as_u64());
+                    eval_count = json.get("eval_count").and_then(|v| v.as_u64());
+                }
+            }
+        }
+    }
+
+    // Process any remaining buffer
+    let remaining = line_buffer.trim().to_string();
+    if !remaining.is_empty() {
+        if let Ok(json) = serde_json::from_str::<Value>(&remaining) {
+            if let Some(content) = json
+                .get("message")
+                .and_then(|m| m.get("content"))
+                .and_then(|c| c.as_str())
+            {
+                if !content.is_empty() {
+                    accumulated_content.push_str(content);
+                    on_token(content);
+                }
+            }
+            if json.get("done").and_then(|v| v.as_bool()).unwrap_or(false) {
+                total_duration_ns = json.get("total_duration").and_then(|v| v.as_u64());
+                prompt_eval_count = json.get("prompt_eval_count").and_then(|v| v.as_u64());
+                eval_count = json.get("eval_count").and_then(|v| v.as_u64());
+            }
+        }
+    }
+
+    Ok(ChatResponse {
+        content: accumulated_content,
+        total_duration_ns,
+        prompt_eval_count,
+        eval_count,
+    })
+}
@@ -0,0 +1,265 @@
+//! Chat over the OpenAI-compatible `/v1/chat/completions` API, for models
+//! served by vLLM, LM Studio and the like. Replies come back as the same
+//! `ChatResponse` the Ollama client returns, so stages don't care which
+//! backend answered.
+
+use anyhow::{Context, Result};
+use futures::StreamExt;
+use reqwest::Client;
+use serde_json::Value;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::pipeline::ollama::{normalize_endpoint, ChatMessage, ChatResponse, LlmEndpoint};
+use crate::pipeline::options::OllamaOptions;
+
+/// Request body for `/v1/chat/completions`. Ollama-only options
+/// (repeat penalty, top_k, thinking) have no standard equivalent and are
+/// left out.
+fn request_body(
+    model: &str,
+    messages: &[ChatMessage],
+    format_json: bool,
+    opts: &OllamaOptions,
+    stream: bool,
+) -> Value {
+    let mut body = serde_json::json!({
+        "model": model,
+        "messages": messages,
+        "stream": stream,
+    });
+    if format_json {
+        body["response_format"] = serde_json::json!({ "type": "json_object" });
+    }
+    if let Some(n) = opts.num_predict {
+        body["max_tokens"] = serde_json::json!(n);
+    }
+    for (key, value) in [("temperature", opts.temperature), ("top_p", opts.top_p)] {
+        if let Some(number) = value.and_then(serde_json::Number::from_f64) {
+            body[key] = Value::Number(number);
+        }
+    }
+    if stream {
+        // Without this the token counts never arrive on a stream
+        body["stream_options"] = serde_json::json!({ "include_usage": true });
+    }
+    body
+}
+
+async fn post_chat(
+    client: &Client,
+    endpoint: &LlmEndpoint,
+    body: &Value,
+) -> Result<reqwest::Response> {
+    let base = normalize_endpoint(&endpoint.url);
+    let url = format!("{}/v1/chat/completions", base);
+    let mut request = client
+        .post(&url)
+        .timeout(Duration::from_secs(300))
+        .json(body);
+    if let Some(key) = endpoint.api_key.as_deref().filter(|k| !k.is_empty()) {
+        request = request.bearer_auth(key);
+    }
+    let resp = request.send().await.with_context(|| {
+        format!(
+            "Cannot connect to OpenAI-compatible server at {} — is it running?",
+            base
+        )
+    })?;
+
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        anyhow::bail!("Server returned {} for chat: {}", status, body);
+    }
+    Ok(resp)
+}
+
+pub async fn chat(
+    client: &Client,
+    endpoint: &LlmEndpoint,
+    model: &str,
+    messages: &[ChatMessage],
+    format_json: bool,
+    opts: &OllamaOptions,
+) -> Result<ChatResponse> {
+    let body = request_body(model, messages, format_json, opts, false);
+    let resp = post_chat(client, endpoint, &body).await?;
+    let json: Value = resp
+        .json()
+        .await
+        .context("Failed to parse chat completion response")?;
+    parse_chat_response(&json)
+}
+
+/// Map a non-streaming chat completion onto a `ChatResponse`.
+pub fn parse_chat_response(json: &Value) -> Result<ChatResponse> {
+    check_error(json)?;
+    let choice = json
+        .get("choices")
+        .and_then(|c| c.get(0))
+        .context("Chat completion response has no choices")?;
+    let content = choice
+        .get("message")
+        .and_then(|m| m.get("content"))
+        .and_then(|c| c.as_str())
+        .unwrap_or("")
+        .to_string();
+    let (prompt_eval_count, eval_count) = usage(json);
+
+    Ok(ChatResponse {
+        content,
+        total_duration_ns: None,
+        prompt_eval_count,
+        eval_count,
+    })
+}
+
+fn check_error(json: &Value) -> Result<()> {
+    if let Some(error) = json.get("error").filter(|e| !e.is_null()) {
+        let message = error
+            .get("message")
+            .and_then(|m| m.as_str())
+            .map(str::to_string)
+            .unwrap_or_else(|| error.to_string());
+        anyhow::bail!("Server error: {}", message);
+    }
+    Ok(())
+}
+
+/// `(prompt_tokens, completion_tokens)` from a response's `usage`, if any.
+fn usage(json: &Value) -> (Option<u64>, Option<u64>) {
+    let usage = json.get("usage");
+    let count = |key: &str| usage.and_then(|u| u.get(key)).and_then(|v| v.as_u64());
+    (count("prompt_tokens"), count("completion_tokens"))
+}
+
+/// One `data:` line of a streamed completion.
+#[derive(Debug, Default, PartialEq)]
+pub struct StreamFrame {
+    pub content: Option<String>,
+    pub prompt_tokens: Option<u64>,
+    pub completion_tokens: Option<u64>,
+    /// The `[DONE]` sentinel that ends the stream.
+    pub done: bool,
+}
+
+/// Parse one line of a server-sent event stream. Lines that aren't `data:`
+/// frames (blank keep-alives, `event:` and comment lines) yield `None`.
+pub fn parse_stream_line(line: &str) -> Result<Option<StreamFrame>> {
+    let Some(data) = line.trim().strip_prefix("data:") else {
+        return Ok(None);
+    };
+    let data = data.trim();
+    if data == "[DONE]" {
+        return Ok(Some(StreamFrame {
+            done: true,
+            ..StreamFrame::default()
+        }));
+    }
+    let json: Value = serde_json::from_str(data).context("Malformed stream frame")?;
+    check_error(&json)?;
+    let content = json
+        .get("choices")
+        .and_then(|c| c.get(0))
+        .and_then(|c| c.get("delta"))
+        .and_then(|d| d.get("content"))
+        .and_then(|c| c.as_str())
+        .filter(|c| !c.is_empty())
+        .map(str::to_string);
+    let (prompt_tokens, completion_tokens) = usage(&json);
+    Ok(Some(StreamFrame {
+        content,
+        prompt_tokens,
+        completion_tokens,
+        done: false,
+    }))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn chat_streaming<F>(
+    client: &Client,
+    endpoint: &LlmEndpoint,
+    model: &str,
+    messages: &[ChatMessage],
+    format_json: bool,
+    opts: &OllamaOptions,
+    cancelled: Option<Arc<AtomicBool>>,
+    mut on_token: F,
+) -> Result<ChatResponse>
+where
+    F: FnMut(&str),
+{
+    const MAX_BUFFER_SIZE: usize = 1_048_576; // 1MB
+
+    let body = request_body(model, messages, format_json, opts, true);
+    let resp = post_chat(client, endpoint, &body).await?;
+
+    let mut stream = resp.bytes_stream();
+    let mut line_buffer = String::new();
+    let mut response = ChatResponse {
+        content: String::new(),
+        total_duration_ns: None,
+        prompt_eval_count: None,
+        eval_count: None,
+    };
+
+    'stream: while let Some(chunk) = stream.next().await {
+        if let Some(ref flag) = cancelled {
+            if flag.load(Ordering::Relaxed) {
+                anyhow::bail!("Pipeline cancelled by user");
+            }
+        }
+        let chunk = chunk.context("Error reading stream chunk")?;
+        line_buffer.push_str(&String::from_utf8_lossy(&chunk));
+        if line_buffer.len() > MAX_BUFFER_SIZE {
+            anyhow::bail!(
+                "Stream exceeded maximum buffer size ({}MB). Response may be malformed.",
+                MAX_BUFFER_SIZE / 1_048_576
+            );
+        }
+
+        while let Some(newline_pos) = line_buffer.find('\n') {
+            let line: String = line_buffer.drain(..=newline_pos).collect();
+            let Some(frame) = parse_stream_line(&line)? else {
+                continue;
+            };
+            if frame.done {
+                break 'stream;
+            }
+            apply_frame(&mut response, frame, &mut on_token);
+            if response.content.len() > MAX_BUFFER_SIZE {
+                anyhow::bail!(
+                    "Accumulated response exceeded {}MB limit",
+                    MAX_BUFFER_SIZE / 1_048_576
+                );
+            }
+        }
+    }
+
+    // A final frame without a trailing newline
+    if let Some(frame) = parse_stream_line(&line_buffer)? {
+        if !frame.done {
+            apply_frame(&mut response, frame, &mut on_token);
+        }
+    }
+    Ok(response)
+}
+
+fn apply_frame(response: &mut ChatResponse, frame: StreamFrame, on_token: &mut impl FnMut(&str)) {
+    if let Some(content) = frame.content {
+        response.content.push_str(&content);
+        on_token(&content);
+    }
+    if frame.prompt_tokens.is_some() {
+        response.prompt_eval_count = frame.prompt_tokens;
+    }
+    if frame.completion_tokens.is_some() {
+        response.eval_count = frame.completion_tokens;
+    }
+}
+
+#[cfg(test)]
+#[path = "openai_test.rs"]
+mod tests;
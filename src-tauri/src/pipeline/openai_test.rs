@@ -0,0 +1,159 @@
+use super::*;
+use crate::mock_http::MockServer;
+use crate::types::config::LlmBackend;
+
+fn openai_endpoint(url: &str, api_key: Option<&str>) -> LlmEndpoint {
+    LlmEndpoint {
+        url: url.to_string(),
+        backend: LlmBackend::OpenAiCompatible,
+        api_key: api_key.map(str::to_string),
+    }
+}
+
+#[test]
+fn test_parse_chat_response_maps_content_and_usage() {
+    let json = serde_json::json!({
+        "id": "chatcmpl-1",
+        "object": "chat.completion",
+        "choices": [{
+            "index": 0,
+            "message": { "role": "assistant", "content": "{\"positive\": \"a cat\"}" },
+            "finish_reason": "stop"
+        }],
+        "usage": { "prompt_tokens": 42, "completion_tokens": 7, "total_tokens": 49 }
+    });
+    let resp = parse_chat_response(&json).unwrap();
+    assert_eq!(resp.content, "{\"positive\": \"a cat\"}");
+    assert_eq!(resp.prompt_eval_count, Some(42));
+    assert_eq!(resp.eval_count, Some(7));
+    assert_eq!(resp.total_duration_ns, None);
+}
+
+#[test]
+fn test_parse_chat_response_without_usage() {
+    let json = serde_json::json!({
+        "choices": [{ "message": { "role": "assistant", "content": "hi" } }]
+    });
+    let resp = parse_chat_response(&json).unwrap();
+    assert_eq!(resp.content, "hi");
+    assert_eq!(resp.prompt_eval_count, None);
+    assert_eq!(resp.eval_count, None);
+}
+
+#[test]
+fn test_parse_chat_response_errors() {
+    let error = serde_json::json!({ "error": { "message": "model not found" } });
+    let err = parse_chat_response(&error).unwrap_err();
+    assert!(err.to_string().contains("model not found"));
+
+    let empty = serde_json::json!({ "choices": [] });
+    assert!(parse_chat_response(&empty).is_err());
+}
+
+#[test]
+fn test_parse_stream_line() {
+    let frame = parse_stream_line(r#"data: {"choices":[{"delta":{"content":"cat"}}]}"#)
+        .unwrap()
+        .unwrap();
+    assert_eq!(frame.content.as_deref(), Some("cat"));
+    assert!(!frame.done);
+
+    let usage = parse_stream_line(
+        r#"data: {"choices":[],"usage":{"prompt_tokens":5,"completion_tokens":3}}"#,
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(usage.content, None);
+    assert_eq!(usage.prompt_tokens, Some(5));
+    assert_eq!(usage.completion_tokens, Some(3));
+
+    assert!(parse_stream_line("data: [DONE]").unwrap().unwrap().done);
+    assert!(parse_stream_line("").unwrap().is_none());
+    assert!(parse_stream_line(": keep-alive").unwrap().is_none());
+    assert!(parse_stream_line("data: {not json").is_err());
+}
+
+#[test]
+fn test_request_body_maps_options() {
+    let opts = OllamaOptions {
+        num_predict: Some(256),
+        repeat_penalty: Some(1.2),
+        temperature: Some(0.4),
+        top_k: Some(40),
+        ..OllamaOptions::default()
+    };
+    let body = request_body("m", &[], true, &opts, false);
+    assert_eq!(body["max_tokens"], 256);
+    assert_eq!(body["temperature"], 0.4);
+    assert_eq!(body["response_format"]["type"], "json_object");
+    assert!(body.get("repeat_penalty").is_none());
+    assert!(body.get("top_k").is_none());
+    assert!(body.get("stream_options").is_none());
+}
+
+#[tokio::test]
+async fn test_chat_posts_completions_with_bearer_auth() {
+    let reply = serde_json::json!({
+        "choices": [{ "message": { "role": "assistant", "content": "hello" } }],
+        "usage": { "prompt_tokens": 3, "completion_tokens": 1 }
+    });
+    let server = MockServer::start(vec![reply.to_string()]).await;
+    let endpoint = openai_endpoint(&format!("{}/", server.endpoint), Some("sk-test"));
+    let messages = [ChatMessage {
+        role: "user".to_string(),
+        content: "hi".to_string(),
+    }];
+
+    let resp = chat(
+        &Client::new(),
+        &endpoint,
+        "local-model",
+        &messages,
+        false,
+        &OllamaOptions::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(resp.content, "hello");
+    assert_eq!(resp.eval_count, Some(1));
+
+    let request = &server.requests()[0];
+    assert_eq!(request.path, "/v1/chat/completions");
+    assert_eq!(request.authorization.as_deref(), Some("Bearer sk-test"));
+    let body: Value = serde_json::from_str(&request.body).unwrap();
+    assert_eq!(body["model"], "local-model");
+    assert_eq!(body["stream"], false);
+}
+
+#[tokio::test]
+async fn test_chat_streaming_collects_sse_frames() {
+    let sse = [
+        r#"data: {"choices":[{"delta":{"role":"assistant"}}]}"#,
+        r#"data: {"choices":[{"delta":{"content":"a "}}]}"#,
+        r#"data: {"choices":[{"delta":{"content":"cat"}}]}"#,
+        r#"data: {"choices":[],"usage":{"prompt_tokens":9,"completion_tokens":2}}"#,
+        "data: [DONE]",
+    ]
+    .join("\n\n");
+    let server = MockServer::start(vec![sse]).await;
+    let endpoint = openai_endpoint(&server.endpoint, None);
+
+    let mut tokens = Vec::new();
+    let resp = chat_streaming(
+        &Client::new(),
+        &endpoint,
+        "m",
+        &[],
+        false,
+        &OllamaOptions::default(),
+        None,
+        |t: &str| tokens.push(t.to_string()),
+    )
+    .await
+    .unwrap();
+    assert_eq!(resp.content, "a cat");
+    assert_eq!(tokens, ["a ", "cat"]);
+    assert_eq!(resp.prompt_eval_count, Some(9));
+    assert_eq!(resp.eval_count, Some(2));
+    assert!(server.requests()[0].authorization.is_none());
+}
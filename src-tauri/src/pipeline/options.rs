@@ -0,0 +1,145 @@
+use serde_json::Value;
+
+use crate::types::config::StageSampling;
+
+#[derive(Debug, Clone, Default)]
+pub struct OllamaOptions {
+    pub num_predict: Option<u32>,
+    pub repeat_penalty: Option<f64>,
+    pub repeat_last_n: Option<u32>,
+    pub temperature: Option<f64>,
+    pub top_p: Option<f64>,
+    pub top_k: Option<u32>,
+    /// Control thinking/reasoning mode for supported models.
+    /// Some(true) = force thinking on, Some(false) = force thinking off,
+    /// None = omit parameter (model uses its default behavior).
+    pub think: Option<bool>,
+}
+
+impl OllamaOptions {
+    /// Apply a stage's configured temperature, top_p and top_k.
+    pub fn with_sampling(mut self, sampling: StageSampling) -> Self {
+        self.temperature = sampling.temperature;
+        self.top_p = sampling.top_p;
+        self.top_k = sampling.top_k;
+        self
+    }
+}
+
+/// Default options for pipeline stages: repeat_penalty=1.2, repeat_last_n=128, with
+/// a per-stage num_predict cap to prevent runaway generation.
+pub fn stage_options(num_predict: u32) -> OllamaOptions {
+    OllamaOptions {
+        num_predict: Some(num_predict),
+        repeat_penalty: Some(1.2),
+        repeat_last_n: Some(128),
+        think: None,
+        ..OllamaOptions::default()
+    }
+}
+
+/// Create stage options with an explicit thinking mode.
+pub fn stage_options_with_thinking(num_predict: u32, think: Option<bool>) -> OllamaOptions {
+    OllamaOptions {
+        num_predict: Some(num_predict),
+        repeat_penalty: Some(1.2),
+        repeat_last_n: Some(128),
+        think,
+        ..OllamaOptions::default()
+    }
+}
+
+pub(super) fn build_options(opts: &OllamaOptions) -> serde_json::Map<String, Value> {
+    let mut map = serde_json::Map::new();
+    if let Some(n) = opts.num_predict {
+        map.insert("num_predict".into(), Value::Number(n.into()));
+    }
+    if let Some(rp) = opts.repeat_penalty {
+        map.insert(
+            "repeat_penalty".into(),
+            serde_json::Number::from_f64(rp)
+                .map(Value::Number)
+                .unwrap_or(Value::Null),
+        );
+    }
+    if let Some(rn) = opts.repeat_last_n {
+        map.insert("repeat_last_n".into(), Value::Number(rn.into()));
+    }
+    for (key, value) in [("temperature", opts.temperature), ("top_p", opts.top_p)] {
+        if let Some(number) = value.and_then(serde_json::Number::from_f64) {
+            map.insert(key.into(), Value::Number(number));
+        }
+    }
+    if let Some(k) = opts.top_k {
+        map.insert("top_k".into(), Value::Number(k.into()));
+    }
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::config::PipelineStageTuning;
+
+    #[test]
+    fn test_stage_options_with_thinking() {
+        let opts = stage_options_with_thinking(1024, Some(false));
+        assert_eq!(opts.think, Some(false));
+        assert_eq!(opts.num_predict, Some(1024));
+
+        let opts_default = stage_options_with_thinking(512, None);
+        assert_eq!(opts_default.think, None);
+
+        let opts_on = stage_options_with_thinking(2048, Some(true));
+        assert_eq!(opts_on.think, Some(true));
+        assert_eq!(opts_on.num_predict, Some(2048));
+    }
+
+    #[test]
+    fn test_think_param_not_in_build_options() {
+        // think is a top-level param, not in "options" sub-object
+        let opts = OllamaOptions {
+            think: Some(false),
+            ..Default::default()
+        };
+        let options = build_options(&opts);
+        assert!(!options.contains_key("think"));
+    }
+
+    #[test]
+    fn test_build_options_emits_sampling_keys() {
+        let sampling = StageSampling {
+            temperature: Some(0.9),
+            top_p: Some(0.95),
+            top_k: Some(40),
+        };
+        let options = build_options(&stage_options(1024).with_sampling(sampling));
+        assert_eq!(options["temperature"], serde_json::json!(0.9));
+        assert_eq!(options["top_p"], serde_json::json!(0.95));
+        assert_eq!(options["top_k"], serde_json::json!(40));
+
+        // Unset values are left to the model
+        let options = build_options(&stage_options(1024));
+        assert!(!options.contains_key("temperature"));
+        assert!(!options.contains_key("top_k"));
+    }
+
+    #[test]
+    fn test_stage_sampling_defaults_differ() {
+        let tuning = PipelineStageTuning::default();
+        let ideator = tuning.for_stage("ideator").temperature.unwrap();
+        let judge = tuning.for_stage("judge").temperature.unwrap();
+        assert!(ideator > judge);
+        assert_eq!(
+            tuning.for_stage("prompt_engineer"),
+            tuning.for_stage("promptEngineer")
+        );
+        assert_eq!(tuning.for_stage("unknown"), StageSampling::default());
+    }
+
+    #[test]
+    fn test_stage_options_default_has_no_think() {
+        let opts = stage_options(1024);
+        assert_eq!(opts.think, None);
+    }
+}
@@ -0,0 +1,170 @@
+use anyhow::Result;
+use reqwest::Client;
+
+use crate::pipeline::ollama::{self, OllamaModel};
+use crate::types::config::{AppConfig, LlmBackend, ModelAssignments};
+use crate::types::pipeline::ModelSubstitution;
+
+/// The models a run will actually use, after the installed-model check.
+pub struct ResolvedModels {
+    pub models: ModelAssignments,
+    pub substitutions: Vec<ModelSubstitution>,
+}
+
+/// Check, with one `/api/tags` call, that every enabled stage's model is
+/// installed in Ollama. A missing model is replaced by `models.fallback_model`
+/// when that is set and installed; otherwise the run stops before any stage
+/// with the `ollama pull` command that fixes it. Disabled stages aren't
+/// checked. OpenAI-compatible servers serve whatever they were started
+/// with, so nothing is checked for them.
+pub async fn resolve_stage_models(client: &Client, config: &AppConfig) -> Result<ResolvedModels> {
+    if config.ollama.backend != LlmBackend::Ollama {
+        return Ok(ResolvedModels {
+            models: config.models.clone(),
+            substitutions: Vec::new(),
+        });
+    }
+    let installed = ollama::list_models(client, &config.ollama.endpoint).await?;
+    resolve_against(config, &installed)
+}
+
+fn resolve_against(config: &AppConfig, installed: &[OllamaModel]) -> Result<ResolvedModels> {
+    let pipeline = &config.pipeline;
+    let mut models = config.models.clone();
+    let fallback = models
+        .fallback_model
+        .clone()
+        .filter(|f| !f.trim().is_empty());
+    let mut substitutions = Vec::new();
+
+    let stages = [
+        ("ideator", pipeline.enable_ideator, &mut models.ideator),
+        ("composer", pipeline.enable_composer, &mut models.composer),
+        ("judge", pipeline.enable_judge, &mut models.judge),
+        (
+            "promptEngineer",
+            pipeline.enable_prompt_engineer,
+            &mut models.prompt_engineer,
+        ),
+        ("reviewer", pipeline.enable_reviewer, &mut models.reviewer),
+    ];
+    for (stage, enabled, model) in stages {
+        if !enabled || is_installed(installed, model) {
+            continue;
+        }
+        match &fallback {
+            Some(f) if is_installed(installed, f) => {
+                eprintln!(
+                    "[pipeline] {} model {} is not installed, using fallback {}",
+                    stage, model, f
+                );
+                substitutions.push(ModelSubstitution {
+                    stage: stage.to_string(),
+                    configured: std::mem::replace(model, f.clone()),
+                    used: f.clone(),
+                });
+            }
+            Some(f) => anyhow::bail!(
+                "Model {} for the {} stage is not installed, and neither is the fallback {}. \
+                 Run `ollama pull {}`.",
+                model,
+                stage,
+                f,
+                model
+            ),
+            None => anyhow::bail!(
+                "Model {} for the {} stage is not installed. Run `ollama pull {}`.",
+                model,
+                stage,
+                model
+            ),
+        }
+    }
+
+    Ok(ResolvedModels {
+        models,
+        substitutions,
+    })
+}
+
+/// Ollama lists untagged pulls as `name:latest`, so `llama3` matches that too.
+fn is_installed(installed: &[OllamaModel], name: &str) -> bool {
+    installed
+        .iter()
+        .any(|m| m.name == name || (!name.contains(':') && m.name == format!("{}:latest", name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn installed(names: &[&str]) -> Vec<OllamaModel> {
+        names
+            .iter()
+            .map(|name| OllamaModel {
+                name: name.to_string(),
+                size: None,
+                digest: None,
+            })
+            .collect()
+    }
+
+    fn config() -> AppConfig {
+        let mut config = AppConfig::default();
+        config.models.ideator = "mistral:7b".to_string();
+        config.models.composer = "llama3".to_string();
+        config.models.judge = "qwen2.5:7b".to_string();
+        config.models.prompt_engineer = "mistral:7b".to_string();
+        config.models.reviewer = "not-pulled:1b".to_string();
+        config.pipeline.enable_reviewer = false;
+        config
+    }
+
+    #[test]
+    fn test_missing_model_names_pull_command() {
+        let config = config();
+        let err = resolve_against(&config, &installed(&["llama3:latest", "qwen2.5:7b"]))
+            .err()
+            .unwrap();
+        let message = err.to_string();
+        assert!(message.contains("mistral:7b"));
+        assert!(message.contains("ideator"));
+        assert!(message.contains("Run `ollama pull mistral:7b`"));
+    }
+
+    #[test]
+    fn test_disabled_stage_model_is_not_checked() {
+        let config = config();
+        let resolved = resolve_against(
+            &config,
+            &installed(&["mistral:7b", "llama3:latest", "qwen2.5:7b"]),
+        )
+        .unwrap();
+        assert!(resolved.substitutions.is_empty());
+        assert_eq!(resolved.models.reviewer, "not-pulled:1b");
+    }
+
+    #[test]
+    fn test_fallback_model_substitutes_missing() {
+        let mut config = config();
+        config.models.fallback_model = Some("llama3".to_string());
+
+        let resolved =
+            resolve_against(&config, &installed(&["llama3:latest", "qwen2.5:7b"])).unwrap();
+        assert_eq!(resolved.models.ideator, "llama3");
+        assert_eq!(resolved.models.prompt_engineer, "llama3");
+        assert_eq!(resolved.models.judge, "qwen2.5:7b");
+        assert_eq!(resolved.substitutions.len(), 2);
+        assert_eq!(resolved.substitutions[0].stage, "ideator");
+        assert_eq!(resolved.substitutions[0].configured, "mistral:7b");
+        assert_eq!(resolved.substitutions[0].used, "llama3");
+
+        config.models.fallback_model = Some("also-missing".to_string());
+        let err = resolve_against(&config, &installed(&["llama3:latest"]))
+            .err()
+            .unwrap();
+        assert!(err
+            .to_string()
+            .contains("neither is the fallback also-missing"));
+    }
+}
@@ -0,0 +1,123 @@
+/// Stages whose system prompt can be overridden, in pipeline order.
+pub const TEMPLATE_STAGES: [&str; 5] = [
+    "ideator",
+    "composer",
+    "judge",
+    "prompt_engineer",
+    "reviewer",
+];
+
+// Built-in system prompts. `{name}` placeholders are filled in by the
+// builders below; any other braces are literal.
+
+pub(super) const IDEATOR_TEMPLATE: &str =
+    "You are a creative director brainstorming visual concepts. Given a simple idea, \
+generate {num_concepts} distinctly different creative interpretations. Each should be a \
+unique visual direction — vary the style, mood, setting, or perspective.\n\n\
+Output as a numbered list. Each concept should be 2-3 sentences describing the \
+visual scene. Be specific and vivid. Think like a cinematographer.";
+
+pub(super) const COMPOSER_TEMPLATE: &str =
+    "You are a visual scene designer. Take this concept and enrich it with specific \
+visual details that would make it a stunning image.\n\n\
+Add: specific materials and textures, lighting direction and quality, color \
+palette (name specific colors), camera angle and lens characteristics, \
+atmospheric effects, small details that add realism or charm.\n\n\
+Do NOT write in prompt syntax. Write a rich paragraph of natural description.";
+
+pub(super) const JUDGE_TEMPLATE: &str = "You are an art director evaluating visual concepts for image generation with \
+Stable Diffusion 1.5. You MUST evaluate and rank ALL {count} concepts from best to worst.\n\n\
+Evaluate each concept on:\n\
+1. Visual clarity — can this be rendered as a single coherent image?\n\
+2. SD-friendliness — does it avoid things SD1.5 struggles with (hands, text, \
+   multiple specific characters, complex spatial relationships)?\n\
+3. Composition — is there a clear focal point and visual hierarchy?\n\
+4. Faithfulness — does it honor the user's original idea?\n\
+5. Appeal — would this make someone go \"wow\"?\n\n\
+IMPORTANT: Your response MUST contain exactly {count} entries — one for EVERY concept. \
+Do not skip any. Provide a detailed reasoning for each.\n\n\
+Return a JSON array with {count} objects ranked best-to-worst:\n\
+[{\"rank\": 1, \"concept_index\": <0-based index>, \"score\": <0-100>, \"reasoning\": \"2-3 sentences explaining strengths and weaknesses\"}, \
+{\"rank\": 2, \"concept_index\": <0-based index>, \"score\": <0-100>, \"reasoning\": \"...\"}, \
+... one entry per concept ...]";
+
+pub(super) const PROMPT_ENGINEER_TEMPLATE: &str =
+    "You are an expert Stable Diffusion prompt engineer. Convert this scene \
+description into optimized positive and negative prompts.\n\n\
+TARGET CHECKPOINT: {checkpoint_name}\n\
+Base model: {base_model}\n\n\
+CHECKPOINT BEHAVIORAL PROFILE:\n\
+Strengths: {strengths}\n\
+Weaknesses: {weaknesses}\n\
+Preferred CFG: {cfg_range_low}–{cfg_range_high}\n\
+Preferred sampler: {preferred_sampler}\n\
+Notes: {checkpoint_notes}\n\n\
+KNOWN EFFECTIVE TERMS FOR THIS CHECKPOINT:\n\
+{term_list}\n\n\
+Rules:\n\
+- Use comma-separated tags, not sentences\n\
+- Put the most important elements first\n\
+- Use (parentheses:weight) for emphasis, range 0.5-1.5\n\
+- Include quality boosters: masterpiece, best quality, highly detailed\n\
+- Negative prompt should cover common SD artifacts\n\
+- Keep total positive prompt under 75 tokens (CLIP limit for SD1.5)\n\
+- Match the style to the scene (photorealistic → photo terms, illustration → art terms)\n\
+- Prefer terms known to be effective on the target checkpoint\n\
+- Avoid terms known to be weak or broken on the target checkpoint\n\n\
+Respond in EXACTLY this JSON format:\n\
+{\"positive\": \"the positive prompt here\", \"negative\": \"the negative prompt here\"}";
+
+pub(super) const REVIEWER_TEMPLATE: &str =
+    "Compare this SD prompt against the user's original idea. Check for:\n\
+1. Prompt drift — did we lose the core of what they asked for?\n\
+2. Conflicting terms — anything contradictory?\n\
+3. Token bloat — is the prompt over-stuffed?\n\
+4. Missing elements — anything from the original idea that got dropped?\n\n\
+Also score fidelity_score from 0 to 100: how faithfully the positive prompt captures the idea.\n\n\
+If the prompts are good, respond: {\"approved\": true, \"fidelity_score\": 90}\n\
+If changes needed, respond: {\"approved\": false, \"fidelity_score\": 60, \"issues\": [...], \
+\"suggested_positive\": \"...\", \"suggested_negative\": \"...\"}";
+
+/// The built-in system prompt for a stage in `TEMPLATE_STAGES`.
+pub fn default_template(stage: &str) -> Option<&'static str> {
+    match stage {
+        "ideator" => Some(IDEATOR_TEMPLATE),
+        "composer" => Some(COMPOSER_TEMPLATE),
+        "judge" => Some(JUDGE_TEMPLATE),
+        "prompt_engineer" => Some(PROMPT_ENGINEER_TEMPLATE),
+        "reviewer" => Some(REVIEWER_TEMPLATE),
+        _ => None,
+    }
+}
+
+/// Substitute `{name}` placeholders; unknown braces are left untouched.
+pub(super) fn render(template: &str, vars: &[(&str, &str)]) -> String {
+    vars.iter()
+        .fold(template.to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{}}}", name), value)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_template_stage_has_a_default() {
+        for stage in TEMPLATE_STAGES {
+            assert!(default_template(stage).is_some(), "{}", stage);
+        }
+        assert!(default_template("upscaler").is_none());
+    }
+
+    #[test]
+    fn test_render_fills_known_placeholders_only() {
+        assert_eq!(
+            render(
+                "{count} ideas for {idea} {unknown}",
+                &[("count", "3"), ("idea", "a fox")]
+            ),
+            "3 ideas for a fox {unknown}"
+        );
+    }
+}
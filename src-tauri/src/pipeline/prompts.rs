@@ -1,10 +1,52 @@
-pub fn ideator_prompt(idea: &str, num_concepts: u32) -> (String, String) {
+use std::collections::HashMap;
+
+use super::prompt_templates::{
+    render, COMPOSER_TEMPLATE, IDEATOR_TEMPLATE, JUDGE_TEMPLATE, PROMPT_ENGINEER_TEMPLATE,
+    REVIEWER_TEMPLATE,
+};
+
+/// User overrides of stage system prompts, keyed by stage. Stages without
+/// an override use the built-in template.
+#[derive(Debug, Clone, Default)]
+pub struct PromptTemplates {
+    overrides: HashMap<String, String>,
+}
+
+impl PromptTemplates {
+    pub fn new(overrides: HashMap<String, String>) -> Self {
+        Self { overrides }
+    }
+
+    fn get<'a>(&'a self, stage: &str, default: &'a str) -> &'a str {
+        self.overrides
+            .get(stage)
+            .map(String::as_str)
+            .unwrap_or(default)
+    }
+}
+
+pub fn ideator_prompt(
+    idea: &str,
+    num_concepts: u32,
+    templates: &PromptTemplates,
+) -> (String, String) {
+    let system = render(
+        templates.get("ideator", IDEATOR_TEMPLATE),
+        &[("num_concepts", &num_concepts.to_string())],
+    );
+
+    let user = format!("User's idea: {}", idea);
+    (system, user)
+}
+
+/// Stricter Ideator prompt used when the numbered-list response can't be parsed.
+pub fn ideator_json_prompt(idea: &str, num_concepts: u32) -> (String, String) {
     let system = format!(
         "You are a creative director brainstorming visual concepts. Given a simple idea, \
-generate {} distinctly different creative interpretations. Each should be a \
-unique visual direction — vary the style, mood, setting, or perspective.\n\n\
-Output as a numbered list. Each concept should be 2-3 sentences describing the \
-visual scene. Be specific and vivid. Think like a cinematographer.",
+generate {} distinctly different creative interpretations, each 2-3 sentences \
+describing the visual scene.\n\n\
+Respond with ONLY a JSON object of exactly this shape:\n\
+{{\"concepts\": [\"first concept\", \"second concept\", ...]}}",
         num_concepts
     );
 
@@ -12,21 +54,15 @@ visual scene. Be specific and vivid. Think like a cinematographer.",
     (system, user)
 }
 
-pub fn composer_prompt(concept: &str) -> (String, String) {
-    let system = "You are a visual scene designer. Take this concept and enrich it with specific \
-visual details that would make it a stunning image.\n\n\
-Add: specific materials and textures, lighting direction and quality, color \
-palette (name specific colors), camera angle and lens characteristics, \
-atmospheric effects, small details that add realism or charm.\n\n\
-Do NOT write in prompt syntax. Write a rich paragraph of natural description."
-        .to_string();
+pub fn composer_prompt(concept: &str, templates: &PromptTemplates) -> (String, String) {
+    let system = templates.get("composer", COMPOSER_TEMPLATE).to_string();
 
     let user = format!("Concept: {}", concept);
     (system, user)
 }
 
-#[derive(serde::Deserialize)]
-#[serde(default)]
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default, rename_all(serialize = "camelCase"))]
 pub struct CheckpointContext {
     pub checkpoint_name: String,
     pub base_model: String,
@@ -55,24 +91,15 @@ impl Default for CheckpointContext {
     }
 }
 
-pub fn judge_prompt(original_idea: &str, concepts: &[String]) -> (String, String) {
+pub fn judge_prompt(
+    original_idea: &str,
+    concepts: &[String],
+    templates: &PromptTemplates,
+) -> (String, String) {
     let count = concepts.len();
-    let system = format!(
-        "You are an art director evaluating visual concepts for image generation with \
-Stable Diffusion 1.5. You MUST evaluate and rank ALL {count} concepts from best to worst.\n\n\
-Evaluate each concept on:\n\
-1. Visual clarity — can this be rendered as a single coherent image?\n\
-2. SD-friendliness — does it avoid things SD1.5 struggles with (hands, text, \
-   multiple specific characters, complex spatial relationships)?\n\
-3. Composition — is there a clear focal point and visual hierarchy?\n\
-4. Faithfulness — does it honor the user's original idea?\n\
-5. Appeal — would this make someone go \"wow\"?\n\n\
-IMPORTANT: Your response MUST contain exactly {count} entries — one for EVERY concept. \
-Do not skip any. Provide a detailed reasoning for each.\n\n\
-Return a JSON array with {count} objects ranked best-to-worst:\n\
-[{{\"rank\": 1, \"concept_index\": <0-based index>, \"score\": <0-100>, \"reasoning\": \"2-3 sentences explaining strengths and weaknesses\"}}, \
-{{\"rank\": 2, \"concept_index\": <0-based index>, \"score\": <0-100>, \"reasoning\": \"...\"}}, \
-... one entry per concept ...]"
+    let system = render(
+        templates.get("judge", JUDGE_TEMPLATE),
+        &[("count", &count.to_string())],
     );
 
     let numbered: Vec<String> = concepts
@@ -91,57 +118,55 @@ Return a JSON array with {count} objects ranked best-to-worst:\n\
     (system, user)
 }
 
-pub fn prompt_engineer_prompt(description: &str, ctx: &CheckpointContext) -> (String, String) {
-    let system = format!(
-        "You are an expert Stable Diffusion prompt engineer. Convert this scene \
-description into optimized positive and negative prompts.\n\n\
-TARGET CHECKPOINT: {checkpoint_name}\n\
-Base model: {base_model}\n\n\
-CHECKPOINT BEHAVIORAL PROFILE:\n\
-Strengths: {strengths}\n\
-Weaknesses: {weaknesses}\n\
-Preferred CFG: {cfg_range_low}–{cfg_range_high}\n\
-Preferred sampler: {preferred_sampler}\n\
-Notes: {checkpoint_notes}\n\n\
-KNOWN EFFECTIVE TERMS FOR THIS CHECKPOINT:\n\
-{term_list}\n\n\
-Rules:\n\
-- Use comma-separated tags, not sentences\n\
-- Put the most important elements first\n\
-- Use (parentheses:weight) for emphasis, range 0.5-1.5\n\
-- Include quality boosters: masterpiece, best quality, highly detailed\n\
-- Negative prompt should cover common SD artifacts\n\
-- Keep total positive prompt under 75 tokens (CLIP limit for SD1.5)\n\
-- Match the style to the scene (photorealistic → photo terms, illustration → art terms)\n\
-- Prefer terms known to be effective on the target checkpoint\n\
-- Avoid terms known to be weak or broken on the target checkpoint\n\n\
-Respond in EXACTLY this JSON format:\n\
-{{\"positive\": \"the positive prompt here\", \"negative\": \"the negative prompt here\"}}",
-        checkpoint_name = ctx.checkpoint_name,
-        base_model = ctx.base_model,
-        strengths = ctx.strengths,
-        weaknesses = ctx.weaknesses,
-        cfg_range_low = ctx.cfg_range_low,
-        cfg_range_high = ctx.cfg_range_high,
-        preferred_sampler = ctx.preferred_sampler,
-        checkpoint_notes = ctx.checkpoint_notes,
-        term_list = ctx.term_list,
+/// With `feedback`, the Reviewer's issues with the previous attempt are
+/// appended so the model can address them.
+pub fn prompt_engineer_prompt(
+    description: &str,
+    ctx: &CheckpointContext,
+    templates: &PromptTemplates,
+    feedback: Option<&[String]>,
+) -> (String, String) {
+    let system = render(
+        templates.get("prompt_engineer", PROMPT_ENGINEER_TEMPLATE),
+        &[
+            ("checkpoint_name", &ctx.checkpoint_name),
+            ("base_model", &ctx.base_model),
+            ("strengths", &ctx.strengths),
+            ("weaknesses", &ctx.weaknesses),
+            ("cfg_range_low", &ctx.cfg_range_low),
+            ("cfg_range_high", &ctx.cfg_range_high),
+            ("preferred_sampler", &ctx.preferred_sampler),
+            ("checkpoint_notes", &ctx.checkpoint_notes),
+            ("term_list", &ctx.term_list),
+        ],
     );
 
-    let user = format!("Scene description:\n{}", description);
+    let mut user = format!("Scene description:\n{}", description);
+    if let Some(issues) = feedback {
+        user.push_str("\n\nA reviewer rejected your previous prompts.");
+        if !issues.is_empty() {
+            user.push_str(" Fix these issues:\n");
+            user.push_str(&bullet_list(issues));
+        }
+    }
     (system, user)
 }
 
-pub fn reviewer_prompt(original_idea: &str, positive: &str, negative: &str) -> (String, String) {
-    let system = "Compare this SD prompt against the user's original idea. Check for:\n\
-1. Prompt drift — did we lose the core of what they asked for?\n\
-2. Conflicting terms — anything contradictory?\n\
-3. Token bloat — is the prompt over-stuffed?\n\
-4. Missing elements — anything from the original idea that got dropped?\n\n\
-If the prompts are good, respond: {\"approved\": true}\n\
-If changes needed, respond: {\"approved\": false, \"issues\": [...], \
-\"suggested_positive\": \"...\", \"suggested_negative\": \"...\"}"
-        .to_string();
+fn bullet_list(items: &[String]) -> String {
+    items
+        .iter()
+        .map(|item| format!("- {}", item))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn reviewer_prompt(
+    original_idea: &str,
+    positive: &str,
+    negative: &str,
+    templates: &PromptTemplates,
+) -> (String, String) {
+    let system = templates.get("reviewer", REVIEWER_TEMPLATE).to_string();
 
     let user = format!(
         "Original idea: {}\nPositive prompt: {}\nNegative prompt: {}",
@@ -157,14 +182,25 @@ mod tests {
 
     #[test]
     fn test_ideator_prompt_contains_count_and_idea() {
-        let (system, user) = ideator_prompt("a cat on a throne", 5);
+        let (system, user) = ideator_prompt("a cat on a throne", 5, &PromptTemplates::default());
         assert!(system.contains("5 distinctly different"));
         assert!(user.contains("a cat on a throne"));
     }
 
+    #[test]
+    fn test_ideator_json_prompt_requests_array() {
+        let (system, user) = ideator_json_prompt("a cat on a throne", 3);
+        assert!(system.contains("3 distinctly different"));
+        assert!(system.contains("\"concepts\""));
+        assert!(user.contains("a cat on a throne"));
+    }
+
     #[test]
     fn test_composer_prompt_contains_concept() {
-        let (system, user) = composer_prompt("Gothic black cat on iron throne");
+        let (system, user) = composer_prompt(
+            "Gothic black cat on iron throne",
+            &PromptTemplates::default(),
+        );
         assert!(system.contains("visual scene designer"));
         assert!(user.contains("Gothic black cat"));
     }
@@ -176,7 +212,7 @@ mod tests {
             "Concept B".to_string(),
             "Concept C".to_string(),
         ];
-        let (system, user) = judge_prompt("cat throne", &concepts);
+        let (system, user) = judge_prompt("cat throne", &concepts, &PromptTemplates::default());
         assert!(system.contains("art director"));
         assert!(system.contains("ALL 3 concepts"));
         assert!(system.contains("exactly 3 entries"));
@@ -200,7 +236,8 @@ mod tests {
             checkpoint_notes: "Good all-around".to_string(),
             term_list: "cinematic lighting (strong): volumetric rays".to_string(),
         };
-        let (system, user) = prompt_engineer_prompt("A cat on a throne", &ctx);
+        let (system, user) =
+            prompt_engineer_prompt("A cat on a throne", &ctx, &PromptTemplates::default(), None);
         assert!(system.contains("dreamshaper_8.safetensors"));
         assert!(system.contains("SD 1.5"));
         assert!(system.contains("photorealism"));
@@ -215,6 +252,7 @@ mod tests {
             "cat on throne",
             "masterpiece, best quality, cat on throne",
             "lowres, bad anatomy",
+            &PromptTemplates::default(),
         );
         assert!(system.contains("Prompt drift"));
         assert!(user.contains("cat on throne"));
@@ -228,4 +266,43 @@ mod tests {
         assert_eq!(ctx.base_model, "SD 1.5");
         assert!(ctx.checkpoint_notes.contains("No specific notes"));
     }
+
+    #[test]
+    fn test_overridden_ideator_template_is_emitted() {
+        let templates = PromptTemplates::new(HashMap::from([(
+            "ideator".to_string(),
+            "Give me {num_concepts} weird takes. Literal {braces} stay.".to_string(),
+        )]));
+        let (system, user) = ideator_prompt("a cat on a throne", 4, &templates);
+        assert_eq!(system, "Give me 4 weird takes. Literal {braces} stay.");
+        assert_eq!(user, "User's idea: a cat on a throne");
+
+        // Other stages keep their built-in prompt
+        let (system, _) = composer_prompt("cat", &templates);
+        assert_eq!(system, COMPOSER_TEMPLATE);
+    }
+
+    #[test]
+    fn test_default_templates_render_without_placeholders() {
+        let t = PromptTemplates::default();
+        let (system, _) = judge_prompt("x", &["a".to_string()], &t);
+        assert!(system.contains("[{\"rank\": 1"));
+        assert!(!system.contains("{count}"));
+        let (system, _) = prompt_engineer_prompt("x", &CheckpointContext::default(), &t, None);
+        assert!(system.contains("Preferred CFG: 6.0–9.0"));
+        assert!(!system.contains("{term_list}"));
+    }
+
+    #[test]
+    fn test_prompt_engineer_prompt_lists_review_feedback() {
+        let ctx = CheckpointContext::default();
+        let t = PromptTemplates::default();
+        let issues = vec!["dropped the crown".to_string(), "too many tags".to_string()];
+        let (_, user) = prompt_engineer_prompt("A cat", &ctx, &t, Some(&issues));
+        assert!(user.starts_with("Scene description:\nA cat"));
+        assert!(user.contains("Fix these issues:\n- dropped the crown\n- too many tags"));
+
+        let (_, user) = prompt_engineer_prompt("A cat", &ctx, &t, None);
+        assert!(!user.contains("reviewer"));
+    }
 }
@@ -0,0 +1,70 @@
+use anyhow::{Context, Result};
+use reqwest::Client;
+
+use crate::pipeline::ollama::{self, ChatMessage, ChatResponse, LlmEndpoint};
+use crate::pipeline::options::OllamaOptions;
+
+/// Sent after a reply that didn't parse, before asking again.
+const JSON_REMINDER: &str = "Your reply was not valid JSON. Return only valid JSON in the format \
+                             described above, with no other text.";
+
+/// A stage reply after any retries: the parse of the last reply, that
+/// reply, and how many extra requests were made.
+pub(super) struct RetriedReply<T> {
+    pub value: Result<T>,
+    pub response: ChatResponse,
+    pub retries: u32,
+}
+
+/// Parse a stage's `first` reply to `messages`, re-asking the model up to
+/// `max_retries` times while it doesn't parse. Each retry shows the model
+/// its bad reply followed by a reminder to answer with JSON only. When every
+/// attempt fails, `value` holds the last parse error.
+#[allow(clippy::too_many_arguments)]
+pub(super) async fn parse_with_retries<T>(
+    client: &Client,
+    endpoint: &LlmEndpoint,
+    model: &str,
+    messages: &[ChatMessage],
+    opts: &OllamaOptions,
+    first: ChatResponse,
+    max_retries: u32,
+    parse: impl Fn(&str) -> Result<T>,
+) -> Result<RetriedReply<T>> {
+    let mut conversation = messages.to_vec();
+    let mut response = first;
+    let mut retries = 0;
+    loop {
+        let value = parse(&response.content);
+        let Err(e) = &value else {
+            return Ok(RetriedReply {
+                value,
+                response,
+                retries,
+            });
+        };
+        if retries >= max_retries {
+            return Ok(RetriedReply {
+                value,
+                response,
+                retries,
+            });
+        }
+        retries += 1;
+        eprintln!(
+            "[pipeline] Unparseable reply from {} ({:#}), retry {}/{}",
+            model, e, retries, max_retries
+        );
+        conversation.push(ChatMessage {
+            role: "assistant".to_string(),
+            content: std::mem::take(&mut response.content),
+        });
+        conversation.push(ChatMessage {
+            role: "user".to_string(),
+            content: JSON_REMINDER.to_string(),
+        });
+        response = ollama::chat_with_options(client, endpoint, model, &conversation, true, opts)
+            .await
+            .context("Retry after an unparseable reply failed")?;
+    }
+}
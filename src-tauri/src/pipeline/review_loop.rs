@@ -0,0 +1,79 @@
+use anyhow::Result;
+use std::future::Future;
+
+use crate::types::config::PipelineSettings;
+use crate::types::pipeline::{PipelineStages, PromptEngineerOutput, PromptPair, ReviewerOutput};
+
+/// Negative prompt used when the Prompt Engineer is bypassed.
+const BYPASS_NEGATIVE: &str = "lowres, bad anatomy, bad hands, text, watermark, blurry";
+
+/// Stages 4 and 5: Prompt Engineer, then Reviewer. While the Reviewer
+/// rejects and iterations remain, the Prompt Engineer runs again with the
+/// Reviewer's issues as feedback. `engineer` and `review` run one stage
+/// call each; `on_review` sees every verdict with its iteration and the
+/// iteration cap. A prompt still rejected at the end is replaced by the
+/// Reviewer's suggestions.
+pub(super) async fn run_review_loop<E, EFut, R, RFut>(
+    pipeline: &PipelineSettings,
+    description: &str,
+    result_stages: &mut PipelineStages,
+    mut engineer: E,
+    mut review: R,
+    mut on_review: impl FnMut(u32, u32, &ReviewerOutput),
+) -> Result<()>
+where
+    E: FnMut(Option<Vec<String>>) -> EFut,
+    EFut: Future<Output = Result<PromptEngineerOutput>>,
+    R: FnMut(PromptPair) -> RFut,
+    RFut: Future<Output = Result<ReviewerOutput>>,
+{
+    let max_iterations = pipeline.max_review_iterations.max(1);
+    let mut feedback: Option<Vec<String>> = None;
+    let mut iteration = 0;
+    loop {
+        iteration += 1;
+        let prompt_pair = if pipeline.enable_prompt_engineer {
+            let pe_output = engineer(feedback.take()).await?;
+            let pair = pe_output.output.clone();
+            result_stages.prompt_engineer = Some(pe_output);
+            pair
+        } else {
+            // Bypass: use description as positive prompt, default negative
+            PromptPair {
+                positive: description.to_string(),
+                negative: BYPASS_NEGATIVE.to_string(),
+            }
+        };
+
+        if !pipeline.enable_reviewer {
+            break;
+        }
+        let reviewer_output = review(prompt_pair).await?;
+        on_review(iteration, max_iterations, &reviewer_output);
+        let approved = reviewer_output.approved;
+        let issues = reviewer_output.issues.clone().unwrap_or_default();
+        result_stages.reviewer = Some(reviewer_output);
+        if approved || iteration >= max_iterations || !pipeline.enable_prompt_engineer {
+            break;
+        }
+        feedback = Some(issues);
+    }
+
+    apply_reviewer_suggestions(result_stages);
+    Ok(())
+}
+
+/// If the Reviewer rejected the final prompt, swap in its suggestions.
+pub(super) fn apply_reviewer_suggestions(stages: &mut PipelineStages) {
+    let Some(reviewer) = stages.reviewer.as_ref().filter(|r| !r.approved) else {
+        return;
+    };
+    if let Some(pe) = stages.prompt_engineer.as_mut() {
+        if let Some(ref suggested_pos) = reviewer.suggested_positive {
+            pe.output.positive = suggested_pos.clone();
+        }
+        if let Some(ref suggested_neg) = reviewer.suggested_negative {
+            pe.output.negative = suggested_neg.clone();
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use anyhow::{Context, Result};
+use reqwest::Client;
+
+use crate::pipeline::ollama::LlmEndpoint;
+use crate::pipeline::prompts::{CheckpointContext, PromptTemplates};
+use crate::pipeline::stages;
+use crate::types::config::{AppConfig, ReviewerFailMode};
+use crate::types::pipeline::PromptPair;
+
+/// Run only the Prompt Engineer over a prompt the user wrote themselves,
+/// so checkpoint context (boosters, known terms, weaknesses) still gets
+/// applied without going through ideation. Uses the configured Prompt
+/// Engineer model and its thinking override.
+pub async fn tune_prompt(
+    client: &Client,
+    config: &AppConfig,
+    prompt: &str,
+    checkpoint_context: Option<CheckpointContext>,
+    templates: &PromptTemplates,
+) -> Result<PromptPair> {
+    const MAX_PROMPT_LENGTH: usize = 10_000;
+
+    let prompt = prompt.trim();
+    if prompt.is_empty() {
+        anyhow::bail!("Prompt cannot be empty");
+    }
+    if prompt.len() > MAX_PROMPT_LENGTH {
+        anyhow::bail!(
+            "Prompt too long ({} chars, max {})",
+            prompt.len(),
+            MAX_PROMPT_LENGTH
+        );
+    }
+
+    let models = &config.models;
+    let output = stages::run_prompt_engineer(
+        client,
+        &LlmEndpoint::from(&config.ollama),
+        &models.prompt_engineer,
+        prompt,
+        checkpoint_context,
+        None,
+        templates,
+        models.thinking_overrides.get("promptEngineer").copied(),
+        config.stage_tuning.for_stage("promptEngineer"),
+        config.pipeline.max_retries,
+    )
+    .await
+    .context("Failed to tune prompt")?;
+    Ok(output.output)
+}
+
+/// Run a single pipeline stage by name (for the run_pipeline_stage command),
+/// with the stage's configured sampling and retry budget.
+pub async fn run_single_stage(
+    client: &Client,
+    config: &AppConfig,
+    stage: &str,
+    model: &str,
+    input: &str,
+    checkpoint_context: Option<CheckpointContext>,
+    templates: &PromptTemplates,
+) -> Result<String> {
+    let endpoint = &LlmEndpoint::from(&config.ollama);
+    let sampling = config.stage_tuning.for_stage(stage);
+    let max_retries = config.pipeline.max_retries;
+    match stage {
+        "ideator" => {
+            let output =
+                stages::run_ideator(client, endpoint, model, input, 5, templates, None, sampling)
+                    .await?;
+            serde_json::to_string(&output).context("Failed to serialize ideator output")
+        }
+        "composer" => {
+            let output =
+                stages::run_composer(client, endpoint, model, input, 0, templates, None, sampling)
+                    .await?;
+            serde_json::to_string(&output).context("Failed to serialize composer output")
+        }
+        "judge" => {
+            let concepts: Vec<String> = serde_json::from_str(input)
+                .context("Judge input must be a JSON array of strings")?;
+            let output = stages::run_judge(
+                client,
+                endpoint,
+                model,
+                "",
+                &concepts,
+                templates,
+                None,
+                sampling,
+                max_retries,
+            )
+            .await?;
+            serde_json::to_string(&output).context("Failed to serialize judge output")
+        }
+        "prompt_engineer" => {
+            let output = stages::run_prompt_engineer(
+                client,
+                endpoint,
+                model,
+                input,
+                checkpoint_context,
+                None,
+                templates,
+                None,
+                sampling,
+                max_retries,
+            )
+            .await?;
+            serde_json::to_string(&output).context("Failed to serialize prompt engineer output")
+        }
+        "reviewer" => {
+            let pair: PromptPair = serde_json::from_str(input)
+                .context("Reviewer input must be JSON with positive/negative fields")?;
+            let output = stages::run_reviewer(
+                client,
+                endpoint,
+                model,
+                "",
+                &pair.positive,
+                &pair.negative,
+                templates,
+                None,
+                sampling,
+                max_retries,
+                ReviewerFailMode::default(),
+            )
+            .await?;
+            serde_json::to_string(&output).context("Failed to serialize reviewer output")
+        }
+        _ => anyhow::bail!("Unknown pipeline stage: {}", stage),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_tune_prompt_applies_checkpoint_terms() {
+        use crate::mock_http::{ollama_chat, MockServer};
+
+        let server = MockServer::start(vec![ollama_chat(
+            r#"{"positive": "a fox in snow, cinematic lighting", "negative": "lowres"}"#,
+        )])
+        .await;
+        let mut config = AppConfig::default();
+        config.ollama.endpoint = server.endpoint.clone();
+        config.models.prompt_engineer = "pe-model".to_string();
+        let ctx = CheckpointContext {
+            checkpoint_name: "dreamshaper_8".to_string(),
+            term_list: "- cinematic lighting (strong): volumetric rays".to_string(),
+            ..Default::default()
+        };
+
+        let pair = tune_prompt(
+            &Client::new(),
+            &config,
+            "  a fox in snow  ",
+            Some(ctx),
+            &PromptTemplates::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(pair.positive, "a fox in snow, cinematic lighting");
+        assert_eq!(pair.negative, "lowres");
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1, "only the Prompt Engineer runs");
+        let body: serde_json::Value = serde_json::from_str(&requests[0].body).unwrap();
+        assert_eq!(body["model"], "pe-model");
+        let messages = body["messages"].to_string();
+        assert!(messages.contains("cinematic lighting (strong): volumetric rays"));
+        assert!(messages.contains("dreamshaper_8"));
+        assert!(messages.contains("a fox in snow"));
+    }
+
+    #[tokio::test]
+    async fn test_tune_prompt_rejects_blank_prompt() {
+        let config = AppConfig::default();
+        let result = tune_prompt(
+            &Client::new(),
+            &config,
+            "   ",
+            None,
+            &PromptTemplates::default(),
+        )
+        .await;
+        assert!(result.is_err());
+    }
+}
@@ -4,12 +4,16 @@ use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 use std::time::Instant;
 
-use super::ollama::{self, ChatMessage};
-use super::prompts::{self, CheckpointContext};
-use super::stages::{
-    backfill_rankings, parse_judge_rankings, parse_numbered_list, parse_prompt_pair,
+use super::fallback;
+use super::ollama::{ChatMessage, LlmEndpoint};
+use super::parsing::{
+    backfill_rankings, parse_judge_reply, parse_numbered_list, parse_prompt_pair,
     parse_reviewer_output,
 };
+use super::prompts::{self, CheckpointContext, PromptTemplates};
+use super::retry::parse_with_retries;
+use super::{ollama_streaming, options};
+use crate::types::config::{ReviewerFailMode, StageSampling};
 use crate::types::pipeline::{
     ComposerOutput, IdeatorOutput, JudgeOutput, PromptEngineerOutput, ReviewerOutput,
 };
@@ -17,16 +21,18 @@ use crate::types::pipeline::{
 #[allow(clippy::too_many_arguments)]
 pub async fn run_ideator_streaming<F: FnMut(&str)>(
     client: &Client,
-    endpoint: &str,
+    endpoint: &LlmEndpoint,
     model: &str,
     idea: &str,
     num_concepts: u32,
+    templates: &PromptTemplates,
     think: Option<bool>,
+    sampling: StageSampling,
     cancelled: Option<Arc<AtomicBool>>,
     on_token: F,
 ) -> Result<IdeatorOutput> {
     let start = Instant::now();
-    let (system, user) = prompts::ideator_prompt(idea, num_concepts);
+    let (system, user) = prompts::ideator_prompt(idea, num_concepts, templates);
     let messages = vec![
         ChatMessage {
             role: "system".to_string(),
@@ -37,24 +43,30 @@ pub async fn run_ideator_streaming<F: FnMut(&str)>(
             content: user,
         },
     ];
-    let resp = ollama::chat_streaming_with_options(
+    let resp = ollama_streaming::chat_streaming_with_options(
         client,
         endpoint,
         model,
         &messages,
         false,
-        &ollama::stage_options_with_thinking(1024, think),
+        &options::stage_options_with_thinking(1024, think).with_sampling(sampling),
         cancelled,
         on_token,
     )
     .await
     .context("Ideator stage failed")?;
-    let concepts = parse_numbered_list(&resp.content);
+    let mut concepts = parse_numbered_list(&resp.content);
+    let mut fallback = None;
     if concepts.is_empty() {
-        anyhow::bail!(
-            "Ideator returned no concepts. Raw response: {}",
+        eprintln!(
+            "[pipeline] Ideator returned no parseable concepts. Raw response: {}",
             &resp.content[..resp.content.len().min(200)]
         );
+        let (recovered, kind) =
+            fallback::recover_ideator_concepts(client, endpoint, model, idea, num_concepts, think)
+                .await;
+        concepts = recovered;
+        fallback = Some(kind);
     }
     Ok(IdeatorOutput {
         input: idea.to_string(),
@@ -63,22 +75,26 @@ pub async fn run_ideator_streaming<F: FnMut(&str)>(
         model: model.to_string(),
         tokens_in: resp.prompt_eval_count,
         tokens_out: resp.eval_count,
+        fallback,
+        raw_response: Some(resp.content),
     })
 }
 
 #[allow(clippy::too_many_arguments)]
 pub async fn run_composer_streaming<F: FnMut(&str)>(
     client: &Client,
-    endpoint: &str,
+    endpoint: &LlmEndpoint,
     model: &str,
     concept: &str,
     concept_index: usize,
+    templates: &PromptTemplates,
     think: Option<bool>,
+    sampling: StageSampling,
     cancelled: Option<Arc<AtomicBool>>,
     on_token: F,
 ) -> Result<ComposerOutput> {
     let start = Instant::now();
-    let (system, user) = prompts::composer_prompt(concept);
+    let (system, user) = prompts::composer_prompt(concept, templates);
     let messages = vec![
         ChatMessage {
             role: "system".to_string(),
@@ -89,13 +105,13 @@ pub async fn run_composer_streaming<F: FnMut(&str)>(
             content: user,
         },
     ];
-    let resp = ollama::chat_streaming_with_options(
+    let resp = ollama_streaming::chat_streaming_with_options(
         client,
         endpoint,
         model,
         &messages,
         false,
-        &ollama::stage_options_with_thinking(2048, think),
+        &options::stage_options_with_thinking(2048, think).with_sampling(sampling),
         cancelled,
         on_token,
     )
@@ -113,22 +129,26 @@ pub async fn run_composer_streaming<F: FnMut(&str)>(
         model: model.to_string(),
         tokens_in: resp.prompt_eval_count,
         tokens_out: resp.eval_count,
+        raw_response: Some(resp.content),
     })
 }
 
 #[allow(clippy::too_many_arguments)]
 pub async fn run_judge_streaming<F: FnMut(&str)>(
     client: &Client,
-    endpoint: &str,
+    endpoint: &LlmEndpoint,
     model: &str,
     original_idea: &str,
     concepts: &[String],
+    templates: &PromptTemplates,
     think: Option<bool>,
+    sampling: StageSampling,
+    max_retries: u32,
     cancelled: Option<Arc<AtomicBool>>,
     on_token: F,
 ) -> Result<JudgeOutput> {
     let start = Instant::now();
-    let (system, user) = prompts::judge_prompt(original_idea, concepts);
+    let (system, user) = prompts::judge_prompt(original_idea, concepts, templates);
     let messages = vec![
         ChatMessage {
             role: "system".to_string(),
@@ -139,43 +159,50 @@ pub async fn run_judge_streaming<F: FnMut(&str)>(
             content: user,
         },
     ];
-    let resp = ollama::chat_streaming_with_options(
+    let opts = options::stage_options_with_thinking(1024, think).with_sampling(sampling);
+    let resp = ollama_streaming::chat_streaming_with_options(
+        client, endpoint, model, &messages, true, &opts, cancelled, on_token,
+    )
+    .await
+    .context("Judge stage failed")?;
+    let parsed = parse_with_retries(
         client,
         endpoint,
         model,
         &messages,
-        true,
-        &ollama::stage_options_with_thinking(1024, think),
-        cancelled,
-        on_token,
+        &opts,
+        resp,
+        max_retries,
+        parse_judge_reply,
     )
-    .await
-    .context("Judge stage failed")?;
-    let rankings =
-        parse_judge_rankings(&resp.content).context("Failed to parse Judge output as rankings")?;
-    if rankings.is_empty() {
-        anyhow::bail!(
-            "Judge returned no rankings. Raw response: {}",
-            &resp.content[..resp.content.len().min(200)]
-        );
-    }
+    .await?;
+    let rankings = parsed
+        .value
+        .context("Failed to parse Judge output as rankings")?;
+    let resp = parsed.response;
     let rankings = backfill_rankings(rankings, concepts.len());
     Ok(JudgeOutput {
         input: concepts.to_vec(),
         output: rankings,
         duration_ms: start.elapsed().as_millis() as u64,
         model: model.to_string(),
+        parse_retries: parsed.retries,
+        raw_response: Some(resp.content),
     })
 }
 
 #[allow(clippy::too_many_arguments)]
 pub async fn run_prompt_engineer_streaming<F: FnMut(&str)>(
     client: &Client,
-    endpoint: &str,
+    endpoint: &LlmEndpoint,
     model: &str,
     description: &str,
     checkpoint_ctx: Option<CheckpointContext>,
+    feedback: Option<&[String]>,
+    templates: &PromptTemplates,
     think: Option<bool>,
+    sampling: StageSampling,
+    max_retries: u32,
     cancelled: Option<Arc<AtomicBool>>,
     on_token: F,
 ) -> Result<PromptEngineerOutput> {
@@ -185,7 +212,7 @@ pub async fn run_prompt_engineer_streaming<F: FnMut(&str)>(
         "Checkpoint: {}, Base: {}, Strengths: {}, Weaknesses: {}",
         ctx.checkpoint_name, ctx.base_model, ctx.strengths, ctx.weaknesses
     );
-    let (system, user) = prompts::prompt_engineer_prompt(description, &ctx);
+    let (system, user) = prompts::prompt_engineer_prompt(description, &ctx, templates, feedback);
     let messages = vec![
         ChatMessage {
             role: "system".to_string(),
@@ -196,20 +223,27 @@ pub async fn run_prompt_engineer_streaming<F: FnMut(&str)>(
             content: user,
         },
     ];
-    let resp = ollama::chat_streaming_with_options(
+    let opts = options::stage_options_with_thinking(1024, think).with_sampling(sampling);
+    let resp = ollama_streaming::chat_streaming_with_options(
+        client, endpoint, model, &messages, true, &opts, cancelled, on_token,
+    )
+    .await
+    .context("Prompt Engineer stage failed")?;
+    let parsed = parse_with_retries(
         client,
         endpoint,
         model,
         &messages,
-        true,
-        &ollama::stage_options_with_thinking(1024, think),
-        cancelled,
-        on_token,
+        &opts,
+        resp,
+        max_retries,
+        parse_prompt_pair,
     )
-    .await
-    .context("Prompt Engineer stage failed")?;
-    let pair = parse_prompt_pair(&resp.content)
+    .await?;
+    let pair = parsed
+        .value
         .context("Failed to parse Prompt Engineer output as positive/negative pair")?;
+    let resp = parsed.response;
     Ok(PromptEngineerOutput {
         input: description.to_string(),
         checkpoint_context: Some(checkpoint_context_str),
@@ -218,23 +252,29 @@ pub async fn run_prompt_engineer_streaming<F: FnMut(&str)>(
         model: model.to_string(),
         tokens_in: resp.prompt_eval_count,
         tokens_out: resp.eval_count,
+        parse_retries: parsed.retries,
+        raw_response: Some(resp.content),
     })
 }
 
 #[allow(clippy::too_many_arguments)]
 pub async fn run_reviewer_streaming<F: FnMut(&str)>(
     client: &Client,
-    endpoint: &str,
+    endpoint: &LlmEndpoint,
     model: &str,
     original_idea: &str,
     positive: &str,
     negative: &str,
+    templates: &PromptTemplates,
     think: Option<bool>,
+    sampling: StageSampling,
+    max_retries: u32,
+    fail_mode: ReviewerFailMode,
     cancelled: Option<Arc<AtomicBool>>,
     on_token: F,
 ) -> Result<ReviewerOutput> {
     let start = Instant::now();
-    let (system, user) = prompts::reviewer_prompt(original_idea, positive, negative);
+    let (system, user) = prompts::reviewer_prompt(original_idea, positive, negative, templates);
     let messages = vec![
         ChatMessage {
             role: "system".to_string(),
@@ -245,25 +285,49 @@ pub async fn run_reviewer_streaming<F: FnMut(&str)>(
             content: user,
         },
     ];
-    let resp = ollama::chat_streaming_with_options(
+    let opts = options::stage_options_with_thinking(1024, think).with_sampling(sampling);
+    let resp = ollama_streaming::chat_streaming_with_options(
+        client, endpoint, model, &messages, true, &opts, cancelled, on_token,
+    )
+    .await
+    .context("Reviewer stage failed")?;
+    let parsed = parse_with_retries(
         client,
         endpoint,
         model,
         &messages,
-        true,
-        &ollama::stage_options_with_thinking(1024, think),
-        cancelled,
-        on_token,
+        &opts,
+        resp,
+        max_retries,
+        parse_reviewer_output,
     )
-    .await
-    .context("Reviewer stage failed")?;
-    let output = parse_reviewer_output(&resp.content).context("Failed to parse Reviewer output")?;
+    .await?;
+    let resp = parsed.response;
+    let output = match parsed.value {
+        Ok(output) => output,
+        Err(e) => {
+            fallback::recover_reviewer_verdict(
+                client,
+                endpoint,
+                model,
+                &messages,
+                &resp.content,
+                e,
+                fail_mode,
+                think,
+            )
+            .await?
+        }
+    };
     Ok(ReviewerOutput {
         approved: output.approved,
         issues: output.issues,
         suggested_positive: output.suggested_positive,
         suggested_negative: output.suggested_negative,
+        fidelity_score: output.fidelity_score,
         duration_ms: start.elapsed().as_millis() as u64,
         model: model.to_string(),
+        parse_retries: parsed.retries,
+        raw_response: Some(resp.content),
     })
 }
@@ -0,0 +1,129 @@
+use reqwest::Client;
+use serde_json::Value;
+use std::time::Duration;
+
+use crate::pipeline::ollama::normalize_endpoint;
+
+/// Built-in list of model family name patterns known to support thinking mode.
+/// Matched case-insensitively against the model name before the ":" tag separator.
+const KNOWN_THINKING_MODEL_PATTERNS: &[&str] = &[
+    "qwen3",
+    "qwq",
+    "deepseek-r1",
+    "phi4-reasoning",
+    "phi-4-reasoning",
+    "marco-o1",
+    "gpt-oss",
+    "skywork-or1",
+    "smallthinker",
+    "granite3-moe",
+];
+
+/// Check if a model name matches a known thinking model pattern.
+pub fn is_known_thinking_model(model_name: &str) -> bool {
+    let base = model_name.split(':').next().unwrap_or(model_name);
+    let base_lower = base.to_lowercase();
+    KNOWN_THINKING_MODEL_PATTERNS
+        .iter()
+        .any(|pattern| base_lower.contains(&pattern.to_lowercase()))
+}
+
+/// Probe a specific model via `/api/show` to check if it supports thinking.
+/// Falls back to the known-models list if the probe fails.
+pub async fn probe_model_thinking(client: &Client, endpoint: &str, model_name: &str) -> bool {
+    if is_known_thinking_model(model_name) {
+        return true;
+    }
+
+    let endpoint = normalize_endpoint(endpoint);
+    let url = format!("{}/api/show", endpoint);
+    let body = serde_json::json!({ "name": model_name });
+
+    let resp = match client
+        .post(&url)
+        .timeout(Duration::from_secs(5))
+        .json(&body)
+        .send()
+        .await
+    {
+        Ok(r) if r.status().is_success() => r,
+        _ => return false,
+    };
+
+    let json: Value = match resp.json().await {
+        Ok(j) => j,
+        Err(_) => return false,
+    };
+
+    if let Some(template) = json.get("template").and_then(|t| t.as_str()) {
+        let tpl_lower = template.to_lowercase();
+        if tpl_lower.contains("<think>")
+            || tpl_lower.contains("thinking")
+            || tpl_lower.contains(".thinking")
+        {
+            return true;
+        }
+    }
+
+    if let Some(caps) = json.get("capabilities").and_then(|c| c.as_array()) {
+        for cap in caps {
+            if let Some(s) = cap.as_str() {
+                if s.eq_ignore_ascii_case("thinking") {
+                    return true;
+                }
+            }
+        }
+    }
+
+    false
+}
+
+/// Batch-detect thinking capability for all provided models.
+pub async fn detect_thinking_models(
+    client: &Client,
+    endpoint: &str,
+    model_names: &[String],
+) -> Vec<String> {
+    let mut thinking_models = Vec::new();
+    for name in model_names {
+        if probe_model_thinking(client, endpoint, name).await {
+            thinking_models.push(name.clone());
+        }
+    }
+    thinking_models
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_thinking_models() {
+        assert!(is_known_thinking_model("qwen3:8b"));
+        assert!(is_known_thinking_model("qwen3:32b-q4_K_M"));
+        assert!(is_known_thinking_model("deepseek-r1:7b"));
+        assert!(is_known_thinking_model("deepseek-r1:1.5b"));
+        assert!(is_known_thinking_model("qwq:latest"));
+        assert!(is_known_thinking_model("phi4-reasoning:14b"));
+        assert!(is_known_thinking_model("phi-4-reasoning:14b"));
+        assert!(is_known_thinking_model("gpt-oss:latest"));
+        assert!(is_known_thinking_model("marco-o1:7b"));
+    }
+
+    #[test]
+    fn test_non_thinking_models() {
+        assert!(!is_known_thinking_model("mistral:7b"));
+        assert!(!is_known_thinking_model("llama3.1:8b"));
+        assert!(!is_known_thinking_model("qwen2.5:7b"));
+        assert!(!is_known_thinking_model("llava:7b"));
+        assert!(!is_known_thinking_model("codellama:13b"));
+        assert!(!is_known_thinking_model("gemma2:9b"));
+    }
+
+    #[test]
+    fn test_thinking_model_case_insensitive() {
+        assert!(is_known_thinking_model("Qwen3:8b"));
+        assert!(is_known_thinking_model("DEEPSEEK-R1:7b"));
+        assert!(is_known_thinking_model("QwQ:latest"));
+    }
+}
@@ -0,0 +1,115 @@
+use crate::ai::tagger;
+use crate::db;
+use crate::gallery::storage;
+use crate::state::AppState;
+use crate::types::config::AppConfig;
+use crate::types::gallery::ImageEntry;
+
+/// Tag freshly generated images with the configured tagger model and store
+/// the result as AI tags, when `pipeline.auto_tag_on_complete` is on.
+/// Best-effort: a failed image is logged and the rest are still tagged.
+/// Returns how many images were tagged.
+pub async fn tag_generated_images(
+    state: &AppState,
+    config: &AppConfig,
+    images: &[ImageEntry],
+) -> usize {
+    if !config.pipeline.auto_tag_on_complete {
+        return 0;
+    }
+
+    let mut tagged = 0;
+    for image in images {
+        let path = storage::get_image_path_for(config, &image.filename);
+        let tags = {
+            let _gpu = state.exclusive_gpu().await;
+            tagger::tag_image(
+                &state.http_client,
+                &config.ollama.endpoint,
+                &config.models.tagger,
+                &path,
+                config.hardware.vision_max_dimension(),
+            )
+            .await
+        };
+        let tags = match tags {
+            Ok(tags) => tags,
+            Err(e) => {
+                eprintln!("[queue] Auto-tagging {} failed: {:#}", image.id, e);
+                continue;
+            }
+        };
+
+        let Ok(conn) = state.db.lock() else {
+            eprintln!("[queue] Auto-tagging stopped: database lock poisoned");
+            break;
+        };
+        for tag_name in &tags {
+            let _ = db::tags::add_image_tag(&conn, &image.id, tag_name, "ai", None);
+        }
+        tagged += 1;
+    }
+    tagged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::images::tests::make_test_image;
+    use crate::mock_http::MockServer;
+
+    async fn run(auto_tag: bool) -> (usize, MockServer, AppState) {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = AppConfig::default();
+        config.storage.image_directory = tmp.path().to_string_lossy().to_string();
+        config.pipeline.auto_tag_on_complete = auto_tag;
+        storage::save_image_from_bytes_with_config(&config, b"png", "gen.png").unwrap();
+
+        let reply = serde_json::json!({"response": r#"["cat", "throne"]"#});
+        let server = MockServer::start(vec![reply.to_string()]).await;
+        config.ollama.endpoint = server.endpoint.clone();
+
+        let conn = db::open_memory_database().unwrap();
+        let mut image = make_test_image("img-1");
+        image.filename = "gen.png".to_string();
+        db::images::insert_image(&conn, &image).unwrap();
+        let state = AppState::new(conn, config.clone());
+
+        let tagged = tag_generated_images(&state, &config, &[image]).await;
+        (tagged, server, state)
+    }
+
+    #[tokio::test]
+    async fn test_completed_job_is_tagged_when_enabled() {
+        let (tagged, server, state) = run(true).await;
+        assert_eq!(tagged, 1);
+        assert_eq!(server.requests().len(), 1);
+        assert_eq!(server.requests()[0].path, "/api/generate");
+
+        let conn = state.db.lock().unwrap();
+        let names: Vec<String> = db::tags::get_image_tags(&conn, "img-1")
+            .unwrap()
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        assert_eq!(names.len(), 2);
+        assert!(names.contains(&"cat".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_no_tag_call_when_disabled() {
+        let (tagged, server, _state) = run(false).await;
+        assert_eq!(tagged, 0);
+        assert!(server.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_tagging_failure_is_not_fatal() {
+        let mut config = AppConfig::default();
+        config.pipeline.auto_tag_on_complete = true;
+        let state = AppState::new(db::open_memory_database().unwrap(), config.clone());
+        // No file on disk: the image is skipped, not an error
+        let tagged = tag_generated_images(&state, &config, &[make_test_image("gone")]).await;
+        assert_eq!(tagged, 0);
+    }
+}
@@ -0,0 +1,120 @@
+use std::time::Duration;
+
+use crate::comfyui::system_stats;
+use crate::types::config::AppConfig;
+
+/// Cooldown between generations: `cooldown_secs` plus a uniformly random
+/// 0..=`jitter_secs` (millisecond resolution). Zero jitter is exactly the base.
+pub(super) fn cooldown_duration(
+    cooldown_secs: u32,
+    jitter_secs: u32,
+    rng: &mut impl rand::Rng,
+) -> Duration {
+    let base = Duration::from_secs(cooldown_secs as u64);
+    if jitter_secs == 0 {
+        return base;
+    }
+    base + Duration::from_millis(rng.random_range(0..=jitter_secs as u64 * 1000))
+}
+
+/// Free VRAM share (percent of total) at or above which the cooldown is
+/// skipped, and below which it is doubled.
+const VRAM_HEADROOM_HIGH_PERCENT: u64 = 50;
+const VRAM_HEADROOM_LOW_PERCENT: u64 = 20;
+
+/// Cooldown for the VRAM ComfyUI has free: none with at least half the card
+/// free, half of `cooldown_secs` in between, and double below a fifth.
+fn adaptive_cooldown_secs(cooldown_secs: u32, vram_free: u64, vram_total: u64) -> u32 {
+    if vram_total == 0 {
+        return cooldown_secs;
+    }
+    let free_percent = vram_free.min(vram_total) * 100 / vram_total;
+    if free_percent >= VRAM_HEADROOM_HIGH_PERCENT {
+        0
+    } else if free_percent >= VRAM_HEADROOM_LOW_PERCENT {
+        cooldown_secs / 2
+    } else {
+        cooldown_secs.saturating_mul(2)
+    }
+}
+
+/// Base cooldown after a finished job: the fixed `cooldown_seconds`, or with
+/// `adaptive_cooldown` on, scaled to the VRAM ComfyUI reports free. Falls
+/// back to the fixed value when the stats can't be read.
+pub(super) async fn cooldown_secs_after_job(http: &reqwest::Client, config: &AppConfig) -> u32 {
+    let fixed = config.hardware.cooldown_seconds;
+    if !config.hardware.adaptive_cooldown {
+        return fixed;
+    }
+    match system_stats::get_system_stats(http, &config.comfyui.endpoint).await {
+        Ok(system_stats::SystemStats {
+            vram_free: Some(free),
+            vram_total: Some(total),
+            ..
+        }) => adaptive_cooldown_secs(fixed, free, total),
+        Ok(_) => fixed,
+        Err(e) => {
+            eprintln!(
+                "[queue] Could not read VRAM for adaptive cooldown, using {}s: {:#}",
+                fixed, e
+            );
+            fixed
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cooldown_jitter_stays_within_bounds() {
+        use rand::SeedableRng;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+
+        for _ in 0..200 {
+            let d = cooldown_duration(30, 10, &mut rng);
+            assert!(d >= Duration::from_secs(30), "{:?} below cooldown", d);
+            assert!(
+                d <= Duration::from_secs(40),
+                "{:?} above cooldown + jitter",
+                d
+            );
+        }
+    }
+
+    #[test]
+    fn test_zero_jitter_is_exact_cooldown() {
+        let mut rng = rand::rng();
+        assert_eq!(cooldown_duration(30, 0, &mut rng), Duration::from_secs(30));
+        assert_eq!(cooldown_duration(0, 0, &mut rng), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_adaptive_cooldown_follows_vram_headroom() {
+        const GIB: u64 = 1024 * 1024 * 1024;
+        // High headroom: skipped
+        assert_eq!(adaptive_cooldown_secs(30, 16 * GIB, 24 * GIB), 0);
+        assert_eq!(adaptive_cooldown_secs(30, 12 * GIB, 24 * GIB), 0);
+        // Medium: halved
+        assert_eq!(adaptive_cooldown_secs(30, 8 * GIB, 24 * GIB), 15);
+        // Low: doubled
+        assert_eq!(adaptive_cooldown_secs(30, 2 * GIB, 24 * GIB), 60);
+        assert_eq!(adaptive_cooldown_secs(30, 0, 24 * GIB), 60);
+        // No total reported: fixed
+        assert_eq!(adaptive_cooldown_secs(30, 0, 0), 30);
+    }
+
+    #[tokio::test]
+    async fn test_adaptive_cooldown_falls_back_without_stats() {
+        let mut config = AppConfig::default();
+        config.hardware.cooldown_seconds = 30;
+        config.hardware.adaptive_cooldown = true;
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        config.comfyui.endpoint = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        let http = reqwest::Client::new();
+        assert_eq!(cooldown_secs_after_job(&http, &config).await, 30);
+    }
+}
@@ -0,0 +1,168 @@
+//! Event payloads the queue executor emits to the frontend.
+
+use crate::comfyui::preview;
+use crate::types::gallery::ImageEntry;
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobStartedEvent {
+    pub job_id: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobCompletedEvent {
+    pub job_id: String,
+    /// First image of the batch (the job's result image).
+    pub image_id: String,
+    /// Every image the job produced, in batch order.
+    pub image_ids: Vec<String>,
+    /// The seed the result image was generated with, already resolved when
+    /// the job asked for a random one (-1).
+    pub seed: Option<i64>,
+    pub checkpoint: Option<String>,
+}
+
+impl JobCompletedEvent {
+    /// The event for a finished job whose result image is `first`.
+    pub(super) fn new(job_id: &str, first: &ImageEntry, image_ids: Vec<String>) -> Self {
+        Self {
+            job_id: job_id.to_string(),
+            image_id: first.id.clone(),
+            image_ids,
+            seed: first.seed,
+            checkpoint: first.checkpoint.clone(),
+        }
+    }
+}
+
+/// A sampler preview of a running job, as a `data:` URL the UI can show
+/// directly in an `<img>`.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobPreviewEvent {
+    pub job_id: String,
+    pub data_url: String,
+}
+
+impl JobPreviewEvent {
+    pub(super) fn new(job_id: &str, preview: &preview::PreviewImage) -> Self {
+        let encoded =
+            base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &preview.bytes);
+        Self {
+            job_id: job_id.to_string(),
+            data_url: format!("data:{};base64,{}", preview.mime, encoded),
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobFailedEvent {
+    pub job_id: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobProgressEvent {
+    pub job_id: String,
+    pub current_step: u32,
+    pub total_steps: u32,
+    pub progress: f64,
+}
+
+/// A failed job was put back in the queue for another attempt.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobRetryingEvent {
+    pub job_id: String,
+    /// Retries used so far, including this one.
+    pub retry_count: u32,
+    pub max_retries: u32,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobCancelledEvent {
+    pub job_id: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::queue::output::build_image_entry;
+    use crate::queue::prepare::tests::make_job_with_settings;
+    use crate::queue::prepare::{build_generation_request, prepare_generation};
+    use crate::types::config::AppConfig;
+    use crate::types::generation::MAX_DIMENSION;
+
+    #[test]
+    fn test_event_structs_serialize() {
+        let started = JobStartedEvent {
+            job_id: "j1".to_string(),
+        };
+        let json = serde_json::to_string(&started).unwrap();
+        assert!(json.contains("jobId"));
+
+        let completed = JobCompletedEvent {
+            job_id: "j1".to_string(),
+            image_id: "img1".to_string(),
+            image_ids: vec!["img1".to_string(), "img2".to_string()],
+            seed: Some(42),
+            checkpoint: Some("sd.safetensors".to_string()),
+        };
+        let json = serde_json::to_string(&completed).unwrap();
+        assert!(json.contains("jobId"));
+        assert!(json.contains("imageId"));
+        assert!(json.contains(r#""imageIds":["img1","img2"]"#));
+        assert!(json.contains(r#""seed":42"#));
+
+        let preview = JobPreviewEvent::new(
+            "j1",
+            &preview::PreviewImage {
+                mime: "image/png",
+                bytes: b"png".to_vec(),
+            },
+        );
+        assert_eq!(preview.data_url, "data:image/png;base64,cG5n");
+        let json = serde_json::to_string(&preview).unwrap();
+        assert!(json.contains("dataUrl"));
+
+        let failed = JobFailedEvent {
+            job_id: "j1".to_string(),
+            error: "something broke".to_string(),
+        };
+        let json = serde_json::to_string(&failed).unwrap();
+        assert!(json.contains("jobId"));
+        assert!(json.contains("something broke"));
+    }
+
+    #[tokio::test]
+    async fn test_completed_event_carries_resolved_seed() {
+        let job = make_job_with_settings(r#"{"checkpoint":"sd.safetensors","seed":-1}"#);
+        assert_eq!(
+            build_generation_request(&job, MAX_DIMENSION).unwrap().seed,
+            -1
+        );
+
+        let config = AppConfig::default();
+        let (request, workflow) = prepare_generation(&reqwest::Client::new(), &config, &job)
+            .await
+            .unwrap();
+        let seed = request.seed;
+        let entry = build_image_entry(&job, &request, "out.png".to_string());
+        let event = JobCompletedEvent::new(&job.id, &entry, vec![entry.id.clone()]);
+
+        assert!(seed >= 0);
+        assert_eq!(event.seed, Some(seed));
+        assert_eq!(workflow["5"]["inputs"]["seed"], seed);
+        assert_eq!(event.checkpoint.as_deref(), Some("sd.safetensors"));
+        assert_eq!(event.image_id, entry.id);
+
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["seed"], seed);
+        assert_eq!(json["checkpoint"], "sd.safetensors");
+    }
+}
@@ -3,52 +3,20 @@ use std::sync::atomic::Ordering;
 use std::time::Duration;
 use tauri::{AppHandle, Emitter, Manager};
 
-use crate::comfyui::{client, workflow};
+use super::cooldown::{cooldown_duration, cooldown_secs_after_job};
+use super::events::{
+    JobCancelledEvent, JobCompletedEvent, JobFailedEvent, JobRetryingEvent, JobStartedEvent,
+};
+use super::output::{self, build_image_entry, record_images, source_image_id};
+use super::prepare::prepare_generation;
+use crate::comfyui::retry;
 use crate::db;
-use crate::gallery::storage;
-use crate::queue::manager;
+use crate::hardware::ha;
+use crate::queue::{auto_tag, manager, monitor, reconcile};
 use crate::state::AppState;
 use crate::types::gallery::ImageEntry;
-use crate::types::generation::GenerationRequest;
 
 const POLL_INTERVAL: Duration = Duration::from_secs(3);
-const COMFYUI_TIMEOUT: Duration = Duration::from_secs(600); // 10 minutes
-
-/// Event payloads emitted to the frontend
-#[derive(Debug, Clone, serde::Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct JobStartedEvent {
-    pub job_id: String,
-}
-
-#[derive(Debug, Clone, serde::Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct JobCompletedEvent {
-    pub job_id: String,
-    pub image_id: String,
-}
-
-#[derive(Debug, Clone, serde::Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct JobFailedEvent {
-    pub job_id: String,
-    pub error: String,
-}
-
-#[derive(Debug, Clone, serde::Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct JobProgressEvent {
-    pub job_id: String,
-    pub current_step: u32,
-    pub total_steps: u32,
-    pub progress: f64,
-}
-
-#[derive(Debug, Clone, serde::Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct JobCancelledEvent {
-    pub job_id: String,
-}
 
 /// Spawn the background queue executor. Call this once during app setup.
 pub fn spawn(app_handle: AppHandle) {
@@ -92,18 +60,19 @@ async fn run_loop(app_handle: AppHandle) {
         }
 
         // Read hardware config
-        let (cooldown_secs, max_consecutive) = {
-            match state.config_snapshot() {
-                Ok(c) => (
-                    c.hardware.cooldown_seconds,
-                    c.hardware.max_consecutive_generations,
-                ),
-                Err(e) => {
-                    eprintln!("[queue] Config mutex poisoned: {}", e);
-                    continue;
-                }
+        let config = match state.config_snapshot() {
+            Ok(c) => c,
+            Err(e) => {
+                eprintln!("[queue] Config mutex poisoned: {}", e);
+                continue;
             }
         };
+        let hardware = &config.hardware;
+        let (cooldown_secs, cooldown_jitter, max_consecutive) = (
+            hardware.cooldown_seconds,
+            hardware.cooldown_jitter_secs,
+            hardware.max_consecutive_generations,
+        );
 
         // Check consecutive limit
         if max_consecutive > 0 && consecutive_count >= max_consecutive {
@@ -111,7 +80,8 @@ async fn run_loop(app_handle: AppHandle) {
                 "[queue] Consecutive generation limit ({}) reached, cooling down",
                 max_consecutive
             );
-            tokio::time::sleep(Duration::from_secs(cooldown_secs as u64)).await;
+            let cooldown = cooldown_duration(cooldown_secs, cooldown_jitter, &mut rand::rng());
+            tokio::time::sleep(cooldown).await;
             consecutive_count = 0;
             continue;
         }
@@ -138,16 +108,59 @@ async fn run_loop(app_handle: AppHandle) {
             }
         };
 
-        // Process the job
-        let result = process_job(&app_handle, &state, &job).await;
+        // Hold the job while the GPU is still drawing too much power
+        tokio::select! {
+            _ = shutdown_rx.recv() => {
+                eprintln!("[queue] Shutdown signal received, stopping executor");
+                return;
+            }
+            _ = ha::wait_for_power_headroom(
+                &state.http_client,
+                hardware,
+                ha::POWER_POLL_INTERVAL,
+                ha::POWER_WAIT_TIMEOUT,
+            ) => {}
+        }
+
+        // The hold can be long: the queue may have been paused, and the job
+        // cancelled or deleted, in the meantime
+        if state.queue_paused.load(Ordering::Relaxed) {
+            continue;
+        }
+
+        // Process the job, holding the GPU against exclusive vision-model work
+        let result = {
+            let _gpu = state.gpu_lock.lock().await;
+            let claimed = match state.db.lock() {
+                Ok(conn) => manager::claim_job(&conn, &job.id),
+                Err(e) => {
+                    eprintln!("[queue] DB mutex poisoned: {}", e);
+                    continue;
+                }
+            };
+            match claimed {
+                Ok(true) => process_job(&app_handle, &state, &job).await,
+                Ok(false) => {
+                    eprintln!("[queue] Job {} left the queue before it started", job.id);
+                    continue;
+                }
+                Err(e) => {
+                    eprintln!("[queue] Failed to start job {}: {:#}", job.id, e);
+                    continue;
+                }
+            }
+        };
 
         match result {
             Ok(_) => {
                 consecutive_count += 1;
 
                 // Cooldown between generations
-                if cooldown_secs > 0 {
-                    tokio::time::sleep(Duration::from_secs(cooldown_secs as u64)).await;
+                let cooldown_secs = cooldown_secs_after_job(&state.http_client, &config).await;
+                if cooldown_secs > 0 || cooldown_jitter > 0 {
+                    let cooldown =
+                        cooldown_duration(cooldown_secs, cooldown_jitter, &mut rand::rng());
+                    tokio::time::sleep(cooldown).await;
                 }
             }
             Err(e) => {
@@ -175,35 +188,53 @@ async fn run_loop(app_handle: AppHandle) {
                         },
                     );
                 } else {
-                    eprintln!("[queue] Job {} failed: {}", job.id, err_msg);
-                    if let Ok(conn) = state.db.lock() {
-                        let _ = manager::mark_failed(&conn, &job.id);
+                    let max_retries = config.generation.max_job_retries;
+                    let retry_count = match state.db.lock() {
+                        Ok(conn) => manager::retry_or_fail(&conn, &job.id, max_retries)
+                            .unwrap_or_else(|e| {
+                                eprintln!("[queue] Failed to requeue job {}: {:#}", job.id, e);
+                                None
+                            }),
+                        Err(_) => None,
+                    };
+                    if let Some(retry_count) = retry_count {
+                        eprintln!(
+                            "[queue] Job {} failed, retrying ({}/{}): {}",
+                            job.id, retry_count, max_retries, err_msg
+                        );
+                        let _ = app_handle.emit(
+                            "queue:job_retrying",
+                            JobRetryingEvent {
+                                job_id: job.id.clone(),
+                                retry_count,
+                                max_retries,
+                                error: err_msg,
+                            },
+                        );
+                    } else {
+                        eprintln!("[queue] Job {} failed: {}", job.id, err_msg);
+                        let _ = app_handle.emit(
+                            "queue:job_failed",
+                            JobFailedEvent {
+                                job_id: job.id.clone(),
+                                error: err_msg,
+                            },
+                        );
                     }
-                    let _ = app_handle.emit(
-                        "queue:job_failed",
-                        JobFailedEvent {
-                            job_id: job.id.clone(),
-                            error: err_msg,
-                        },
-                    );
                 }
             }
         }
     }
 }
 
+/// Run a job already claimed with [`manager::claim_job`].
 async fn process_job(
     app_handle: &AppHandle,
     state: &AppState,
     job: &crate::types::queue::QueueJob,
 ) -> Result<()> {
-    let endpoint = state.config_snapshot()?.comfyui.endpoint;
-
-    // Mark as generating
-    {
-        let conn = state.db.lock().map_err(|e| anyhow::anyhow!("{}", e))?;
-        manager::mark_generating(&conn, &job.id)?;
-    }
+    let config = state.config_snapshot()?;
+    let endpoint = config.comfyui.endpoint.clone();
 
     let _ = app_handle.emit(
         "queue:job_started",
@@ -213,205 +244,123 @@ async fn process_job(
     );
 
     // Build generation request from job data
-    let gen_request = build_generation_request(job)?;
-    let (workflow_json, actual_seed) = workflow::build_txt2img(&gen_request);
+    let (mut gen_request, workflow_json) =
+        prepare_generation(&state.http_client, &config, job).await?;
     let client_id = uuid::Uuid::new_v4().to_string();
 
     // Queue prompt to ComfyUI
-    let prompt_id = client::queue_prompt(&state.http_client, &endpoint, &workflow_json, &client_id)
-        .await
-        .context("Failed to queue prompt to ComfyUI")?;
-
-    // Wait for completion with real-time progress via WebSocket,
-    // racing against a cancellation poll loop that checks the DB every 2s.
-    let job_id_for_progress = job.id.clone();
-    let ah_progress = app_handle.clone();
-    let ws_future = client::wait_for_completion_ws(
+    let prompt_id = retry::queue_prompt(
         &state.http_client,
         &endpoint,
-        &prompt_id,
+        &workflow_json,
         &client_id,
-        COMFYUI_TIMEOUT,
-        move |update| {
-            let progress = if update.total_steps > 0 {
-                update.current_step as f64 / update.total_steps as f64
-            } else {
-                0.0
-            };
-            let _ = ah_progress.emit(
-                "queue:job_progress",
-                JobProgressEvent {
-                    job_id: job_id_for_progress.clone(),
-                    current_step: update.current_step,
-                    total_steps: update.total_steps,
-                    progress,
-                },
-            );
-        },
-    );
-
-    let job_id_cancel = job.id.clone();
-    let cancel_poll = async {
-        loop {
-            tokio::time::sleep(Duration::from_secs(2)).await;
-            let is_cancelled = {
-                if let Ok(conn) = state.db.lock() {
-                    db::queue::is_job_cancelled(&conn, &job_id_cancel).unwrap_or(false)
-                } else {
-                    false
-                }
-            };
-            if is_cancelled {
-                return;
-            }
-        }
-    };
+        config.comfyui.max_retries,
+    )
+    .await
+    .context("Failed to queue prompt to ComfyUI")?;
 
-    let gen_status = tokio::select! {
-        result = ws_future => result.context("Error waiting for ComfyUI completion")?,
-        _ = cancel_poll => {
-            // Job was cancelled — interrupt ComfyUI best-effort
-            let _ = client::interrupt(&state.http_client, &endpoint).await;
-            anyhow::bail!("Job cancelled by user");
-        }
-    };
+    let gen_status = monitor::wait_for_prompt(
+        app_handle, state, &endpoint, &prompt_id, &client_id, &job.id,
+    )
+    .await?;
 
     if let Some(ref error) = gen_status.error {
         anyhow::bail!("Generation failed: {}", error);
     }
 
     // Fetch full history to get ImageRef data (subfolder, type)
-    let history = client::get_history(&state.http_client, &endpoint, &prompt_id)
-        .await
-        .context("Failed to fetch ComfyUI history after completion")?
-        .with_context(|| "Completed prompt has no history entry")?;
+    let history = retry::get_history(
+        &state.http_client,
+        &endpoint,
+        &prompt_id,
+        config.comfyui.max_retries,
+    )
+    .await
+    .context("Failed to fetch ComfyUI history after completion")?
+    .with_context(|| "Completed prompt has no history entry")?;
 
     if history.image_filenames.is_empty() {
         anyhow::bail!("ComfyUI returned no image filenames");
     }
 
-    // Prefer the last image (most likely to be the final output, not a preview)
-    let img_ref = history
-        .image_filenames
-        .last()
-        .context("ComfyUI returned no image filenames")?;
-    let image_bytes = client::get_image(
-        &state.http_client,
-        &endpoint,
-        &img_ref.filename,
-        &img_ref.subfolder,
-        &img_ref.img_type,
-    )
-    .await
-    .context("Failed to download image from ComfyUI")?;
+    // Store what actually ran if the executed graph differs from the request
+    let settings_mismatch = history
+        .prompt_graph
+        .as_ref()
+        .and_then(|graph| reconcile::reconcile_with_graph(&mut gen_request, graph));
+    if let Some(mismatch) = &settings_mismatch {
+        eprintln!(
+            "[queue] Job {} ran with different settings: {}",
+            job.id, mismatch
+        );
+    }
 
-    let local_filename = storage::generate_filename();
     let config_clone = state.config_snapshot()?;
-    {
-        let filename_clone = local_filename.clone();
-        let bytes_clone = image_bytes.clone();
-        let config_for_save = config_clone.clone();
-        tokio::task::spawn_blocking(move || {
-            storage::save_image_from_bytes_with_config(
-                &config_for_save,
-                &bytes_clone,
-                &filename_clone,
-            )
-        })
-        .await
-        .context("Image save task panicked")?
-        .context("Failed to save image to gallery")?;
-    }
+    let (saved, batch_error) =
+        output::save_outputs(state, &config_clone, &history.image_filenames, &gen_request).await?;
 
-    // === POST-GENERATION CANCELLATION CHECK ===
-    // If the job was cancelled while we were downloading, don't persist to gallery.
+    // If the job was cancelled while we were downloading, don't persist to gallery
     {
         let conn = state.db.lock().map_err(|e| anyhow::anyhow!("{}", e))?;
         let was_cancelled = db::queue::is_job_cancelled(&conn, &job.id).unwrap_or(false);
         drop(conn);
         if was_cancelled {
-            // Clean up the file we just saved
-            if let Err(cleanup_err) =
-                storage::delete_image_files_for(&config_clone, &local_filename)
-            {
-                eprintln!(
-                    "[queue] ERROR: Failed to clean up cancelled job image {}: {}",
-                    local_filename, cleanup_err
-                );
-            }
+            output::discard_saved(&config_clone, &saved);
             anyhow::bail!("Job cancelled by user");
         }
     }
 
-    // Insert into gallery DB
-    let image_id = uuid::Uuid::new_v4().to_string();
-    let now = chrono::Utc::now().to_rfc3339();
-    let image_entry = ImageEntry {
-        id: image_id.clone(),
-        filename: local_filename,
-        created_at: now,
-        positive_prompt: Some(job.positive_prompt.clone()),
-        negative_prompt: Some(job.negative_prompt.clone()),
-        original_idea: job.original_idea.clone(),
-        checkpoint: Some(gen_request.checkpoint.clone()),
-        width: Some(gen_request.width),
-        height: Some(gen_request.height),
-        steps: Some(gen_request.steps),
-        cfg_scale: Some(gen_request.cfg_scale),
-        sampler: Some(gen_request.sampler.clone()),
-        scheduler: Some(gen_request.scheduler.clone()),
-        seed: Some(actual_seed),
-        pipeline_log: job.pipeline_log.clone(),
-        selected_concept: job.selected_concept,
-        auto_approved: job.auto_approved,
-        caption: None,
-        caption_edited: false,
-        rating: None,
-        favorite: false,
-        deleted: false,
-        user_note: None,
-        tags: None,
+    let parent_image_id = {
+        let conn = state.db.lock().map_err(|e| anyhow::anyhow!("{}", e))?;
+        source_image_id(&conn, job, &gen_request)?
+    };
+
+    // Insert into gallery DB, one row per image
+    let phashes: Vec<Option<u64>> = saved.iter().map(|image| image.phash).collect();
+    let entries: Vec<ImageEntry> = saved
+        .into_iter()
+        .map(|image| {
+            let mut entry = build_image_entry(job, &gen_request, image.filename);
+            entry.settings_mismatch = settings_mismatch.clone();
+            entry.parent_image_id = parent_image_id.clone();
+            entry
+        })
+        .collect();
+    let image_ids: Vec<String> = entries.iter().map(|e| e.id.clone()).collect();
+    let Some(first) = entries.first() else {
+        anyhow::bail!("ComfyUI returned no output images");
     };
 
     {
         let conn = state.db.lock().map_err(|e| anyhow::anyhow!("{}", e))?;
-        db::images::insert_image(&conn, &image_entry)?;
-        manager::mark_completed(&conn, &job.id, &image_id)?;
+        record_images(
+            &conn,
+            job,
+            &entries,
+            &phashes,
+            batch_error.is_none(),
+            config_clone.gallery.auto_rate_from_fidelity,
+        )?;
     }
 
-    let _ = app_handle.emit(
-        "queue:job_completed",
-        JobCompletedEvent {
-            job_id: job.id.clone(),
-            image_id,
-        },
-    );
+    let completed = JobCompletedEvent::new(&job.id, first, image_ids);
 
-    Ok(())
-}
+    // Tagging runs in the background so the next job can start right away
+    if config_clone.pipeline.auto_tag_on_complete {
+        let app = app_handle.clone();
+        tauri::async_runtime::spawn(async move {
+            let state = app.state::<AppState>();
+            auto_tag::tag_generated_images(&state, &config_clone, &entries).await;
+        });
+    }
+
+    if let Some(e) = batch_error {
+        return Err(e);
+    }
 
-/// Parse the settings_json stored in a QueueJob into a GenerationRequest.
-fn build_generation_request(job: &crate::types::queue::QueueJob) -> Result<GenerationRequest> {
-    use crate::types::generation::GenerationSettings;
-
-    let settings: GenerationSettings =
-        serde_json::from_str(&job.settings_json).context("Failed to parse job settings_json")?;
-
-    settings.validate().context("Invalid generation settings")?;
-
-    Ok(GenerationRequest {
-        positive_prompt: job.positive_prompt.clone(),
-        negative_prompt: job.negative_prompt.clone(),
-        checkpoint: settings.checkpoint,
-        width: settings.width,
-        height: settings.height,
-        steps: settings.steps,
-        cfg_scale: settings.cfg_scale,
-        sampler: settings.sampler,
-        scheduler: settings.scheduler,
-        seed: settings.seed,
-        batch_size: settings.batch_size,
-    })
+    let _ = app_handle.emit("queue:job_completed", completed);
+
+    Ok(())
 }
 
 #[cfg(test)]
@@ -3,21 +3,80 @@ use rusqlite::Connection;
 use std::sync::atomic::Ordering;
 
 use crate::db;
+use crate::pipeline::{edits, engine};
 use crate::state::AppState;
+use crate::types::gallery::ImageSource;
+use crate::types::generation::{GenerationSettings, PartialGenerationRequest};
+use crate::types::pipeline::PipelineResult;
 use crate::types::queue::{QueueJob, QueueJobStatus, QueuePriority};
 
 /// Add a new job to the queue with a generated ID and pending status.
-pub fn add_job(state: &AppState, mut job: QueueJob) -> Result<String> {
+pub fn add_job(state: &AppState, job: QueueJob) -> Result<String> {
+    let priority = job.priority.clone();
+    add_job_at(state, job, priority, None)
+}
+
+/// Add a job with `priority`, optionally at `position` among the pending
+/// jobs of that priority (0 = next). `None` appends, like [`add_job`].
+pub fn add_job_at(
+    state: &AppState,
+    mut job: QueueJob,
+    priority: QueuePriority,
+    position: Option<usize>,
+) -> Result<String> {
     if job.id.is_empty() {
         job.id = uuid::Uuid::new_v4().to_string();
     }
     job.status = QueueJobStatus::Pending;
+    job.priority = priority;
 
     let conn = state.db.lock().map_err(|e| anyhow::anyhow!("{}", e))?;
-    db::queue::insert_job(&conn, &job)?;
+    match position {
+        Some(position) => db::queue::insert_job_at(&conn, &job, position)?,
+        None => db::queue::insert_job(&conn, &job)?,
+    }
     Ok(job.id)
 }
 
+/// Build a pending job from a finished pipeline run. Prompts come from the
+/// user's edit if one was recorded, else the pipeline's final output; unset
+/// generation settings fall back to defaults.
+pub fn job_from_pipeline(
+    result: &PipelineResult,
+    checkpoint: &str,
+    settings: &PartialGenerationRequest,
+) -> Result<QueueJob> {
+    let prompts = edits::effective_prompts(result)
+        .context("Pipeline produced no prompts (is the Prompt Engineer stage enabled?)")?;
+
+    let mut job_settings = GenerationSettings::for_checkpoint(checkpoint);
+    job_settings.apply(settings);
+
+    Ok(QueueJob {
+        id: String::new(),
+        priority: QueuePriority::Normal,
+        status: QueueJobStatus::Pending,
+        positive_prompt: prompts.positive,
+        negative_prompt: prompts.negative,
+        settings_json: job_settings.to_json()?,
+        pipeline_log: Some(
+            serde_json::to_string(result).context("Failed to serialize pipeline result")?,
+        ),
+        original_idea: Some(result.original_idea.clone()),
+        selected_concept: Some(engine::get_selected_concept(result) as u32),
+        auto_approved: result.auto_approved,
+        linked_comparison_id: None,
+        group_id: None,
+        pipeline_run_id: result.run_id.clone(),
+        parent_image_id: None,
+        retry_count: 0,
+        created_at: None,
+        started_at: None,
+        completed_at: None,
+        result_image_id: None,
+    })
+}
+
 /// Get all jobs sorted by status then priority then creation time.
 pub fn get_all_jobs(state: &AppState) -> Result<Vec<QueueJob>> {
     let conn = state.db.lock().map_err(|e| anyhow::anyhow!("{}", e))?;
@@ -42,29 +101,35 @@ pub fn reorder_job(state: &AppState, job_id: &str, new_priority: QueuePriority)
     db::queue::update_job_priority(&conn, job_id, &new_priority)
 }
 
-/// Cancel a pending or generating job. If generating, also interrupt ComfyUI.
-pub async fn cancel_job(state: &AppState, job_id: &str) -> Result<()> {
-    let endpoint = state
-        .config
-        .read()
-        .map_err(|e| anyhow::anyhow!("{}", e))?
-        .comfyui
-        .endpoint
-        .clone();
+/// Cancel a pending or generating job. A generating job's prompt is stopped
+/// by the executor, which notices the cancellation while waiting on ComfyUI
+/// and cancels only that prompt (see `prompt_queue::cancel_prompt`).
+pub fn cancel_job(state: &AppState, job_id: &str) -> Result<()> {
+    let conn = state.db.lock().map_err(|e| anyhow::anyhow!("{}", e))?;
+    db::queue::cancel_job(&conn, job_id)?;
+    Ok(())
+}
 
-    let prev_status = {
+/// Cancel all pending jobs in a group. A member that is already generating
+/// finishes normally unless `abort_generating` is set, in which case it is
+/// cancelled and its ComfyUI prompt stopped. Returns the number of jobs
+/// cancelled.
+pub fn cancel_group(state: &AppState, group_id: &str, abort_generating: bool) -> Result<u32> {
+    let (mut cancelled, generating) = {
         let conn = state.db.lock().map_err(|e| anyhow::anyhow!("{}", e))?;
-        db::queue::cancel_job(&conn, job_id)?
+        (
+            db::queue::cancel_pending_in_group(&conn, group_id)?,
+            db::queue::generating_jobs_in_group(&conn, group_id)?,
+        )
     };
 
-    // If job was actively generating, interrupt ComfyUI (best-effort)
-    if prev_status == "generating" {
-        crate::comfyui::client::interrupt(&state.http_client, &endpoint)
-            .await
-            .context("Job was cancelled, but ComfyUI interrupt failed")?;
+    if abort_generating {
+        for job_id in generating {
+            cancel_job(state, &job_id)?;
+            cancelled += 1;
+        }
     }
-
-    Ok(())
+    Ok(cancelled)
 }
 
 /// Pause the queue — executor will finish the current job but won't start new ones.
@@ -89,9 +154,11 @@ pub fn next_pending_job(conn: &Connection) -> Result<Option<QueueJob>> {
     Ok(jobs.into_iter().next())
 }
 
-/// Mark a job as generating (sets started_at).
-pub fn mark_generating(conn: &Connection, job_id: &str) -> Result<()> {
-    db::queue::update_job_status(conn, job_id, &QueueJobStatus::Generating)
+/// Mark a job as generating (sets started_at), but only if it is still
+/// pending. Returns false when it was cancelled or deleted since it was
+/// picked, in which case it must not run.
+pub fn claim_job(conn: &Connection, job_id: &str) -> Result<bool> {
+    db::queue::claim_pending_job(conn, job_id)
 }
 
 /// Mark a job as completed and link the result image.
@@ -100,135 +167,49 @@ pub fn mark_completed(conn: &Connection, job_id: &str, image_id: &str) -> Result
     db::queue::set_job_result_image(conn, job_id, image_id)
 }
 
+/// Link an existing image to a job as its result and mark the job completed.
+/// Used to recover jobs whose image was saved but never recorded (e.g. after a crash).
+pub fn relink_result(state: &AppState, job_id: &str, image_id: &str) -> Result<()> {
+    let conn = state.db.lock().map_err(|e| anyhow::anyhow!("{}", e))?;
+
+    db::queue::get_job(&conn, job_id)?
+        .with_context(|| format!("Queue job {} not found", job_id))?;
+    db::images::get_image(&conn, image_id)?
+        .with_context(|| format!("Image {} not found", image_id))?;
+
+    db::queue::set_job_result_image(&conn, job_id, image_id)?;
+    db::queue::update_job_status(&conn, job_id, &QueueJobStatus::Completed)
+}
+
+/// Classify the gallery image a completed job produces. Variations carry a
+/// linked comparison; pipeline jobs carry the pipeline log or original idea
+/// (variations inherit those, hence the order).
+pub fn image_source_for_job(job: &QueueJob) -> ImageSource {
+    if job.linked_comparison_id.is_some() {
+        ImageSource::Variation
+    } else if job.pipeline_log.is_some() || job.original_idea.is_some() {
+        ImageSource::Pipeline
+    } else {
+        ImageSource::Manual
+    }
+}
+
 /// Mark a job as failed.
 pub fn mark_failed(conn: &Connection, job_id: &str) -> Result<()> {
     db::queue::update_job_status(conn, job_id, &QueueJobStatus::Failed)
 }
 
+/// Requeue a failed job while it has retries left, otherwise mark it
+/// failed. Returns the job's retry count when it was requeued.
+pub fn retry_or_fail(conn: &Connection, job_id: &str, max_retries: u32) -> Result<Option<u32>> {
+    db::queue::retry_or_fail_job(conn, job_id, max_retries)
+}
+
 /// On app startup, requeue any jobs that were mid-generation when the app closed.
 pub fn requeue_interrupted(conn: &Connection) -> Result<u32> {
     db::queue::requeue_interrupted_jobs(conn)
 }
 
 #[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::types::config::AppConfig;
-
-    fn make_state() -> AppState {
-        let conn = crate::db::open_memory_database().unwrap();
-        AppState::new(conn, AppConfig::default())
-    }
-
-    fn make_job(positive: &str) -> QueueJob {
-        QueueJob {
-            id: String::new(),
-            priority: QueuePriority::Normal,
-            status: QueueJobStatus::Pending,
-            positive_prompt: positive.to_string(),
-            negative_prompt: "lowres".to_string(),
-            settings_json: r#"{"steps":20}"#.to_string(),
-            pipeline_log: None,
-            original_idea: None,
-            selected_concept: None,
-            auto_approved: false,
-            linked_comparison_id: None,
-            created_at: None,
-            started_at: None,
-            completed_at: None,
-            result_image_id: None,
-        }
-    }
-
-    #[test]
-    fn test_add_job_generates_id() {
-        let state = make_state();
-        let job = make_job("a cat");
-        let id = add_job(&state, job).unwrap();
-        assert!(!id.is_empty());
-
-        let jobs = get_all_jobs(&state).unwrap();
-        assert_eq!(jobs.len(), 1);
-        assert_eq!(jobs[0].id, id);
-    }
-
-    #[tokio::test]
-    async fn test_cancel_job() {
-        let state = make_state();
-        let id = add_job(&state, make_job("a cat")).unwrap();
-        cancel_job(&state, &id).await.unwrap();
-
-        let jobs = get_all_jobs(&state).unwrap();
-        assert_eq!(jobs[0].status, QueueJobStatus::Cancelled);
-    }
-
-    #[test]
-    fn test_reorder_job() {
-        let state = make_state();
-        let id = add_job(&state, make_job("a cat")).unwrap();
-        reorder_job(&state, &id, QueuePriority::High).unwrap();
-
-        let jobs = get_all_jobs(&state).unwrap();
-        assert_eq!(jobs[0].priority, QueuePriority::High);
-    }
-
-    #[test]
-    fn test_reorder_non_pending_fails() {
-        let state = make_state();
-        let id = add_job(&state, make_job("a cat")).unwrap();
-
-        // Mark generating
-        {
-            let conn = state.db.lock().unwrap();
-            mark_generating(&conn, &id).unwrap();
-        }
-
-        let err = reorder_job(&state, &id, QueuePriority::High);
-        assert!(err.is_err());
-    }
-
-    #[test]
-    fn test_pause_resume() {
-        let state = make_state();
-        assert!(!is_paused(&state));
-
-        pause_queue(&state);
-        assert!(is_paused(&state));
-
-        resume_queue(&state);
-        assert!(!is_paused(&state));
-    }
-
-    #[test]
-    fn test_next_pending_job() {
-        let state = make_state();
-        add_job(&state, make_job("first")).unwrap();
-        add_job(&state, make_job("second")).unwrap();
-
-        let conn = state.db.lock().unwrap();
-        let next = next_pending_job(&conn).unwrap();
-        assert!(next.is_some());
-        assert_eq!(next.unwrap().positive_prompt, "first");
-    }
-
-    #[test]
-    fn test_mark_completed_with_image() {
-        let state = make_state();
-        let job_id = add_job(&state, make_job("a cat")).unwrap();
-
-        let conn = state.db.lock().unwrap();
-        // Insert a test image to satisfy FK
-        conn.execute(
-            "INSERT INTO images (id, filename) VALUES ('img-1', 'test.png')",
-            [],
-        )
-        .unwrap();
-
-        mark_generating(&conn, &job_id).unwrap();
-        mark_completed(&conn, &job_id, "img-1").unwrap();
-
-        let job = db::queue::get_job(&conn, &job_id).unwrap().unwrap();
-        assert_eq!(job.status, QueueJobStatus::Completed);
-        assert_eq!(job.result_image_id.unwrap(), "img-1");
-    }
-}
+#[path = "manager_test.rs"]
+mod tests;
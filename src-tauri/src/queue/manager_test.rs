@@ -0,0 +1,277 @@
+use super::*;
+use crate::types::config::AppConfig;
+
+fn make_state() -> AppState {
+    let conn = crate::db::open_memory_database().unwrap();
+    AppState::new(conn, AppConfig::default())
+}
+
+fn make_job(positive: &str) -> QueueJob {
+    QueueJob {
+        positive_prompt: positive.to_string(),
+        original_idea: None,
+        selected_concept: None,
+        ..db::queue::tests::make_job("", QueuePriority::Normal)
+    }
+}
+
+#[test]
+fn test_add_job_generates_id() {
+    let state = make_state();
+    let job = make_job("a cat");
+    let id = add_job(&state, job).unwrap();
+    assert!(!id.is_empty());
+
+    let jobs = get_all_jobs(&state).unwrap();
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0].id, id);
+}
+
+#[test]
+fn test_cancel_job() {
+    let state = make_state();
+    let id = add_job(&state, make_job("a cat")).unwrap();
+    cancel_job(&state, &id).unwrap();
+
+    let jobs = get_all_jobs(&state).unwrap();
+    assert_eq!(jobs[0].status, QueueJobStatus::Cancelled);
+}
+
+#[tokio::test]
+async fn test_cancel_generating_job_sends_no_global_interrupt() {
+    let server = crate::mock_http::MockServer::start(vec!["{}".to_string()]).await;
+    let mut config = AppConfig::default();
+    config.comfyui.endpoint = server.endpoint.clone();
+    let state = AppState::new(crate::db::open_memory_database().unwrap(), config);
+    let id = add_job(&state, make_job("a cat")).unwrap();
+    {
+        let conn = state.db.lock().unwrap();
+        assert!(claim_job(&conn, &id).unwrap());
+    }
+
+    cancel_job(&state, &id).unwrap();
+
+    let jobs = get_all_jobs(&state).unwrap();
+    assert_eq!(jobs[0].status, QueueJobStatus::Cancelled);
+    // Whatever ComfyUI is running may not be ours; only the executor's
+    // prompt-scoped cancel may stop it
+    assert!(server.requests().is_empty());
+}
+
+#[test]
+fn test_add_urgent_job_next() {
+    let state = make_state();
+    let mut high = make_job("already high");
+    high.priority = QueuePriority::High;
+    add_job(&state, high).unwrap();
+    add_job(&state, make_job("normal")).unwrap();
+
+    let id = add_job_at(&state, make_job("urgent"), QueuePriority::High, Some(0)).unwrap();
+
+    let conn = state.db.lock().unwrap();
+    let next = next_pending_job(&conn).unwrap().unwrap();
+    assert_eq!(next.id, id);
+    assert_eq!(next.priority, QueuePriority::High);
+}
+
+#[test]
+fn test_reorder_job() {
+    let state = make_state();
+    let id = add_job(&state, make_job("a cat")).unwrap();
+    reorder_job(&state, &id, QueuePriority::High).unwrap();
+
+    let jobs = get_all_jobs(&state).unwrap();
+    assert_eq!(jobs[0].priority, QueuePriority::High);
+}
+
+#[test]
+fn test_reorder_non_pending_fails() {
+    let state = make_state();
+    let id = add_job(&state, make_job("a cat")).unwrap();
+
+    // Mark generating
+    {
+        let conn = state.db.lock().unwrap();
+        assert!(claim_job(&conn, &id).unwrap());
+    }
+
+    let err = reorder_job(&state, &id, QueuePriority::High);
+    assert!(err.is_err());
+}
+
+#[test]
+fn test_pause_resume() {
+    let state = make_state();
+    assert!(!is_paused(&state));
+
+    pause_queue(&state);
+    assert!(is_paused(&state));
+
+    resume_queue(&state);
+    assert!(!is_paused(&state));
+}
+
+#[test]
+fn test_next_pending_job() {
+    let state = make_state();
+    add_job(&state, make_job("first")).unwrap();
+    add_job(&state, make_job("second")).unwrap();
+
+    let conn = state.db.lock().unwrap();
+    let next = next_pending_job(&conn).unwrap();
+    assert!(next.is_some());
+    assert_eq!(next.unwrap().positive_prompt, "first");
+}
+
+#[test]
+fn test_mark_completed_with_image() {
+    let state = make_state();
+    let job_id = add_job(&state, make_job("a cat")).unwrap();
+
+    let conn = state.db.lock().unwrap();
+    // Insert a test image to satisfy FK
+    conn.execute(
+        "INSERT INTO images (id, filename) VALUES ('img-1', 'test.png')",
+        [],
+    )
+    .unwrap();
+
+    assert!(claim_job(&conn, &job_id).unwrap());
+    mark_completed(&conn, &job_id, "img-1").unwrap();
+
+    let job = db::queue::get_job(&conn, &job_id).unwrap().unwrap();
+    assert_eq!(job.status, QueueJobStatus::Completed);
+    assert_eq!(job.result_image_id.unwrap(), "img-1");
+}
+
+#[test]
+fn test_relink_result_sets_image_and_status() {
+    let state = make_state();
+    let job_id = add_job(&state, make_job("a cat")).unwrap();
+    {
+        let conn = state.db.lock().unwrap();
+        conn.execute(
+            "INSERT INTO images (id, filename) VALUES ('img-1', 'test.png')",
+            [],
+        )
+        .unwrap();
+        mark_failed(&conn, &job_id).unwrap();
+    }
+
+    relink_result(&state, &job_id, "img-1").unwrap();
+
+    let conn = state.db.lock().unwrap();
+    let job = db::queue::get_job(&conn, &job_id).unwrap().unwrap();
+    assert_eq!(job.status, QueueJobStatus::Completed);
+    assert_eq!(job.result_image_id.as_deref(), Some("img-1"));
+}
+
+#[test]
+fn test_relink_result_rejects_missing_image() {
+    let state = make_state();
+    let job_id = add_job(&state, make_job("a cat")).unwrap();
+    {
+        let conn = state.db.lock().unwrap();
+        mark_failed(&conn, &job_id).unwrap();
+    }
+
+    let err = relink_result(&state, &job_id, "no-such-image").unwrap_err();
+    assert!(err.to_string().contains("not found"));
+
+    let conn = state.db.lock().unwrap();
+    let job = db::queue::get_job(&conn, &job_id).unwrap().unwrap();
+    assert_eq!(job.status, QueueJobStatus::Failed);
+    assert!(job.result_image_id.is_none());
+}
+
+#[test]
+fn test_job_from_pipeline_uses_final_prompts() {
+    let state = make_state();
+    let result = crate::pipeline::engine::tests::make_test_result();
+    let settings = PartialGenerationRequest {
+        steps: Some(30),
+        ..Default::default()
+    };
+
+    let job = job_from_pipeline(&result, "dreamshaper_8.safetensors", &settings).unwrap();
+    let id = add_job(&state, job).unwrap();
+
+    let conn = state.db.lock().unwrap();
+    let stored = db::queue::get_job(&conn, &id).unwrap().unwrap();
+    assert_eq!(stored.status, QueueJobStatus::Pending);
+    assert_eq!(stored.positive_prompt, "masterpiece, cat on throne");
+    assert_eq!(stored.negative_prompt, "lowres, blurry");
+    assert_eq!(stored.original_idea.as_deref(), Some("a cat on a throne"));
+    assert_eq!(stored.selected_concept, Some(1));
+    assert!(stored.pipeline_log.unwrap().contains("masterpiece"));
+
+    let parsed: crate::types::generation::GenerationSettings =
+        serde_json::from_str(&stored.settings_json).unwrap();
+    assert_eq!(parsed.checkpoint, "dreamshaper_8.safetensors");
+    assert_eq!(parsed.steps, 30);
+    assert_eq!(parsed.sampler, "dpmpp_2m");
+}
+
+#[test]
+fn test_job_from_pipeline_uses_user_edits() {
+    let mut result = crate::pipeline::engine::tests::make_test_result();
+    let edited = crate::types::pipeline::PromptPair {
+        positive: "masterpiece, cat on throne, gold crown".to_string(),
+        negative: "lowres, blurry".to_string(),
+    };
+    crate::pipeline::edits::apply_user_edits(&mut result, edited).unwrap();
+
+    let job = job_from_pipeline(&result, "ckpt", &PartialGenerationRequest::default()).unwrap();
+    assert_eq!(
+        job.positive_prompt,
+        "masterpiece, cat on throne, gold crown"
+    );
+
+    // The stored log keeps both the model's output and the edit
+    let log: crate::types::pipeline::PipelineResult =
+        serde_json::from_str(job.pipeline_log.as_deref().unwrap()).unwrap();
+    let pe = log.stages.prompt_engineer.unwrap();
+    assert_eq!(pe.output.positive, "masterpiece, cat on throne");
+    assert!(log.user_edits.unwrap().prompt_edited);
+}
+
+#[test]
+fn test_job_from_pipeline_without_prompts_fails() {
+    let mut result = crate::pipeline::engine::tests::make_test_result();
+    result.stages.prompt_engineer = None;
+    let err = job_from_pipeline(&result, "ckpt", &PartialGenerationRequest::default());
+    assert!(err.is_err());
+}
+
+#[test]
+fn test_image_source_for_job() {
+    let manual = make_job("a cat");
+    assert_eq!(image_source_for_job(&manual), ImageSource::Manual);
+
+    let mut pipeline = make_job("a cat");
+    pipeline.original_idea = Some("cat".to_string());
+    assert_eq!(image_source_for_job(&pipeline), ImageSource::Pipeline);
+
+    let mut variation = pipeline.clone();
+    variation.linked_comparison_id = Some("cmp-1".to_string());
+    assert_eq!(image_source_for_job(&variation), ImageSource::Variation);
+}
+
+#[test]
+fn test_cancel_group_only_touches_that_group() {
+    let state = make_state();
+    let mut ids = Vec::new();
+    for group in ["sweep-a", "sweep-a", "sweep-b"] {
+        let mut job = make_job("a cat");
+        job.group_id = Some(group.to_string());
+        ids.push(add_job(&state, job).unwrap());
+    }
+
+    assert_eq!(cancel_group(&state, "sweep-a", false).unwrap(), 2);
+
+    let jobs = get_all_jobs(&state).unwrap();
+    let status = |id: &str| jobs.iter().find(|j| j.id == id).unwrap().status.clone();
+    assert_eq!(status(&ids[0]), QueueJobStatus::Cancelled);
+    assert_eq!(status(&ids[1]), QueueJobStatus::Cancelled);
+    assert_eq!(status(&ids[2]), QueueJobStatus::Pending);
+}
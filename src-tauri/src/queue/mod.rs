@@ -1,2 +1,10 @@
+pub mod auto_tag;
+mod cooldown;
+pub mod events;
 pub mod executor;
 pub mod manager;
+mod monitor;
+mod output;
+pub mod prepare;
+pub mod reconcile;
+pub mod sweep;
@@ -0,0 +1,83 @@
+use anyhow::{Context, Result};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use super::events::{JobPreviewEvent, JobProgressEvent};
+use crate::comfyui::{completion, prompt_queue};
+use crate::db;
+use crate::state::AppState;
+use crate::types::generation::GenerationStatus;
+
+const COMFYUI_TIMEOUT: Duration = Duration::from_secs(600); // 10 minutes
+
+/// Wait for ComfyUI to finish `prompt_id`, forwarding its progress and
+/// sampler previews to the frontend. Races the WebSocket against a poll of
+/// the job's status every 2s; when the job is cancelled the prompt is
+/// dropped from ComfyUI and an error returned.
+pub(super) async fn wait_for_prompt(
+    app_handle: &AppHandle,
+    state: &AppState,
+    endpoint: &str,
+    prompt_id: &str,
+    client_id: &str,
+    job_id: &str,
+) -> Result<GenerationStatus> {
+    let job_id_for_progress = job_id.to_string();
+    let ah_progress = app_handle.clone();
+    let job_id_for_preview = job_id.to_string();
+    let ah_preview = app_handle.clone();
+    let ws_future = completion::wait_for_completion_ws(
+        &state.http_client,
+        endpoint,
+        prompt_id,
+        client_id,
+        COMFYUI_TIMEOUT,
+        move |update| {
+            let progress = if update.total_steps > 0 {
+                update.current_step as f64 / update.total_steps as f64
+            } else {
+                0.0
+            };
+            let _ = ah_progress.emit(
+                "queue:job_progress",
+                JobProgressEvent {
+                    job_id: job_id_for_progress.clone(),
+                    current_step: update.current_step,
+                    total_steps: update.total_steps,
+                    progress,
+                },
+            );
+        },
+        move |preview| {
+            let _ = ah_preview.emit(
+                "queue:job_preview",
+                JobPreviewEvent::new(&job_id_for_preview, &preview),
+            );
+        },
+    );
+
+    let cancel_poll = async {
+        loop {
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            let is_cancelled = {
+                if let Ok(conn) = state.db.lock() {
+                    db::queue::is_job_cancelled(&conn, job_id).unwrap_or(false)
+                } else {
+                    false
+                }
+            };
+            if is_cancelled {
+                return;
+            }
+        }
+    };
+
+    tokio::select! {
+        result = ws_future => result.context("Error waiting for ComfyUI completion"),
+        _ = cancel_poll => {
+            // Job was cancelled: drop our prompt from ComfyUI, best-effort
+            let _ = prompt_queue::cancel_prompt(&state.http_client, endpoint, prompt_id).await;
+            anyhow::bail!("Job cancelled by user");
+        }
+    }
+}
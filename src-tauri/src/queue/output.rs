@@ -0,0 +1,372 @@
+use anyhow::{Context, Result};
+
+use crate::comfyui::{client, retry};
+use crate::db;
+use crate::gallery::{auto_rating, storage};
+use crate::queue::{manager, sweep};
+use crate::state::AppState;
+use crate::types::config::AppConfig;
+use crate::types::gallery::ImageEntry;
+use crate::types::generation::GenerationRequest;
+
+/// Insert a job's gallery rows and point the job at the first of them. A
+/// batch that only partly saved (`complete` false) keeps its rows and result
+/// image but is left for the caller to fail rather than marked completed.
+pub(super) fn record_images(
+    conn: &rusqlite::Connection,
+    job: &crate::types::queue::QueueJob,
+    entries: &[ImageEntry],
+    phashes: &[Option<u64>],
+    complete: bool,
+    auto_rate: bool,
+) -> Result<()> {
+    let Some(first) = entries.first() else {
+        anyhow::bail!("ComfyUI returned no output images");
+    };
+    for (entry, phash) in entries.iter().zip(phashes) {
+        db::images::insert_image(conn, entry)?;
+        if let Some(hash) = phash {
+            db::phash::set_phash(conn, &entry.id, *hash)?;
+        }
+    }
+    if complete {
+        manager::mark_completed(conn, &job.id, &first.id)?;
+    } else {
+        db::queue::set_job_result_image(conn, &job.id, &first.id)?;
+    }
+    if let Some(comparison_id) = &job.linked_comparison_id {
+        db::comparisons::set_comparison_image_b(conn, comparison_id, &first.id)?;
+    }
+    // Best-effort, like auto-rating below
+    if let Err(e) = sweep::link_group_comparison(conn, job, first) {
+        eprintln!("[queue] Failed to link group comparison: {:#}", e);
+    }
+    if auto_rate {
+        if let Some(log) = &job.pipeline_log {
+            // Best-effort: a failed auto-rating shouldn't fail the job
+            for entry in entries {
+                if let Err(e) = auto_rating::apply_fidelity_rating(conn, &entry.id, log) {
+                    eprintln!("[queue] Failed to auto-rate image {}: {:#}", entry.id, e);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Save every image of the batch, each under a fresh local name (ComfyUI
+/// reuses its output filenames). Fails if the first image can't be saved;
+/// after that a failure stops the batch and is returned alongside the
+/// images already saved, so they can still be recorded before the job fails.
+pub(super) async fn save_outputs(
+    state: &AppState,
+    config: &AppConfig,
+    refs: &[client::ImageRef],
+    request: &GenerationRequest,
+) -> Result<(Vec<storage::SavedImage>, Option<anyhow::Error>)> {
+    let outputs = output_images(refs);
+    let mut saved = Vec::new();
+    for img_ref in &outputs {
+        match download_and_save(state, config, img_ref, request).await {
+            Ok(image) => saved.push(image),
+            Err(e) if saved.is_empty() => return Err(e),
+            Err(e) => {
+                let e = e.context(format!(
+                    "Saved {} of {} batch images before failing",
+                    saved.len(),
+                    outputs.len()
+                ));
+                return Ok((saved, Some(e)));
+            }
+        }
+    }
+    Ok((saved, None))
+}
+
+/// Remove the files of images saved for a job that was cancelled meanwhile.
+pub(super) fn discard_saved(config: &AppConfig, saved: &[storage::SavedImage]) {
+    for image in saved {
+        if let Err(cleanup_err) = storage::delete_image_files_for(config, &image.filename) {
+            eprintln!(
+                "[queue] ERROR: Failed to clean up cancelled job image {}: {}",
+                image.filename, cleanup_err
+            );
+        }
+    }
+}
+
+/// The batch's final images. Previews (`temp`) are skipped; if ComfyUI
+/// reported no `output` images the last image is used, as before batches
+/// were saved in full.
+fn output_images(refs: &[client::ImageRef]) -> Vec<&client::ImageRef> {
+    let outputs: Vec<&client::ImageRef> = refs.iter().filter(|r| r.img_type == "output").collect();
+    if outputs.is_empty() {
+        refs.last().into_iter().collect()
+    } else {
+        outputs
+    }
+}
+
+/// Download one ComfyUI image and save it to the gallery directory under a
+/// fresh name. Returns the local filename and perceptual hash.
+async fn download_and_save(
+    state: &AppState,
+    config: &AppConfig,
+    img_ref: &client::ImageRef,
+    request: &GenerationRequest,
+) -> Result<storage::SavedImage> {
+    let image_bytes = retry::get_image(
+        &state.http_client,
+        &config.comfyui.endpoint,
+        &img_ref.filename,
+        &img_ref.subfolder,
+        &img_ref.img_type,
+        config.comfyui.max_retries,
+    )
+    .await
+    .with_context(|| format!("Failed to download {} from ComfyUI", img_ref.filename))?;
+
+    let config = config.clone();
+    let request = request.clone();
+    tokio::task::spawn_blocking(move || {
+        storage::save_image_with_metadata(&config, &image_bytes, &request)
+    })
+    .await
+    .context("Image save task panicked")?
+    .context("Failed to save image to gallery")
+}
+
+/// The gallery image a job's output derives from: the image a variation
+/// was made from, or the init image of an img2img pass. `None` for plain
+/// txt2img and for init images that aren't in the gallery.
+pub(super) fn source_image_id(
+    conn: &rusqlite::Connection,
+    job: &crate::types::queue::QueueJob,
+    request: &GenerationRequest,
+) -> Result<Option<String>> {
+    if let Some(id) = &job.parent_image_id {
+        return Ok(Some(id.clone()));
+    }
+    match &request.init_image {
+        Some(filename) if request.denoise < 1.0 => {
+            db::images::find_image_id_by_filename(conn, filename)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Gallery row for a finished job, carrying the job's provenance.
+pub(super) fn build_image_entry(
+    job: &crate::types::queue::QueueJob,
+    gen_request: &GenerationRequest,
+    filename: String,
+) -> ImageEntry {
+    let (width, height) = gen_request.output_size();
+    ImageEntry {
+        id: uuid::Uuid::new_v4().to_string(),
+        filename,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        positive_prompt: Some(job.positive_prompt.clone()),
+        negative_prompt: Some(job.negative_prompt.clone()),
+        original_idea: job.original_idea.clone(),
+        checkpoint: Some(gen_request.checkpoint.clone()),
+        width: Some(width),
+        height: Some(height),
+        steps: Some(gen_request.steps),
+        cfg_scale: Some(gen_request.cfg_scale),
+        sampler: Some(gen_request.sampler.clone()),
+        scheduler: Some(gen_request.scheduler.clone()),
+        seed: Some(gen_request.seed),
+        denoise: Some(gen_request.denoise),
+        pipeline_log: job.pipeline_log.clone(),
+        selected_concept: job.selected_concept,
+        auto_approved: job.auto_approved,
+        caption: None,
+        caption_edited: false,
+        rating: None,
+        favorite: false,
+        deleted: false,
+        user_note: None,
+        // The request's cost covers the whole batch; each row carries its share
+        compute_cost: Some(gen_request.compute_cost() / i64::from(gen_request.batch_size.max(1))),
+        aesthetic_score: None,
+        pipeline_run_id: job.pipeline_run_id.clone(),
+        parent_image_id: job.parent_image_id.clone(),
+        deleted_at: None,
+        settings_mismatch: None,
+        source: Some(manager::image_source_for_job(job)),
+        tags: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::queue::prepare::build_generation_request;
+    use crate::queue::prepare::tests::make_job_with_settings;
+    use crate::types::generation::MAX_DIMENSION;
+    use crate::types::queue::QueueJobStatus;
+
+    #[test]
+    fn test_pipeline_run_id_flows_from_run_to_job_to_image() {
+        use crate::pipeline::engine::tests::make_test_result;
+        use crate::types::generation::PartialGenerationRequest;
+
+        let conn = db::open_memory_database().unwrap();
+        let result = make_test_result();
+        let mut job = manager::job_from_pipeline(
+            &result,
+            "dreamshaper_8.safetensors",
+            &PartialGenerationRequest::default(),
+        )
+        .unwrap();
+        job.id = "job-run".to_string();
+        assert_eq!(job.pipeline_run_id.as_deref(), Some("run-001"));
+
+        db::queue::insert_job(&conn, &job).unwrap();
+        let stored = db::queue::get_job(&conn, "job-run").unwrap().unwrap();
+        assert_eq!(stored.pipeline_run_id.as_deref(), Some("run-001"));
+
+        let mut request = build_generation_request(&stored, MAX_DIMENSION).unwrap();
+        request.seed = 42;
+        let image = build_image_entry(&stored, &request, "run.png".to_string());
+        db::images::insert_image(&conn, &image).unwrap();
+
+        let saved = db::images::get_image(&conn, &image.id).unwrap().unwrap();
+        assert_eq!(saved.pipeline_run_id.as_deref(), Some("run-001"));
+        assert_eq!(saved.seed, Some(42));
+    }
+
+    #[test]
+    fn test_source_image_id_links_img2img_and_variations() {
+        let conn = db::open_memory_database().unwrap();
+        db::images::insert_image(&conn, &crate::db::images::tests::make_test_image("img-src"))
+            .unwrap();
+
+        let img2img = make_job_with_settings(
+            r#"{"checkpoint":"a.safetensors","denoise":0.6,"initImage":"img-src.png"}"#,
+        );
+        let request = build_generation_request(&img2img, MAX_DIMENSION).unwrap();
+        assert_eq!(
+            source_image_id(&conn, &img2img, &request)
+                .unwrap()
+                .as_deref(),
+            Some("img-src")
+        );
+
+        // At full denoise the init image isn't used, so nothing derives from it
+        let txt2img = make_job_with_settings(
+            r#"{"checkpoint":"a.safetensors","denoise":1.0,"initImage":"img-src.png"}"#,
+        );
+        let request = build_generation_request(&txt2img, MAX_DIMENSION).unwrap();
+        assert_eq!(source_image_id(&conn, &txt2img, &request).unwrap(), None);
+
+        let outside = make_job_with_settings(
+            r#"{"checkpoint":"a.safetensors","denoise":0.5,"initImage":"elsewhere.png"}"#,
+        );
+        let request = build_generation_request(&outside, MAX_DIMENSION).unwrap();
+        assert_eq!(source_image_id(&conn, &outside, &request).unwrap(), None);
+
+        let mut variation = make_job_with_settings(r#"{"checkpoint":"a.safetensors"}"#);
+        variation.parent_image_id = Some("img-src".to_string());
+        let request = build_generation_request(&variation, MAX_DIMENSION).unwrap();
+        assert_eq!(
+            source_image_id(&conn, &variation, &request)
+                .unwrap()
+                .as_deref(),
+            Some("img-src")
+        );
+    }
+
+    fn image_ref(filename: &str, img_type: &str) -> client::ImageRef {
+        client::ImageRef {
+            filename: filename.to_string(),
+            subfolder: String::new(),
+            img_type: img_type.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_output_images_keeps_whole_batch() {
+        let refs = vec![
+            image_ref("preview_00001_.png", "temp"),
+            image_ref("vf_00001_.png", "output"),
+            image_ref("vf_00002_.png", "output"),
+            image_ref("vf_00003_.png", "output"),
+        ];
+        let names: Vec<&str> = output_images(&refs)
+            .iter()
+            .map(|r| r.filename.as_str())
+            .collect();
+        assert_eq!(names, ["vf_00001_.png", "vf_00002_.png", "vf_00003_.png"]);
+    }
+
+    #[test]
+    fn test_output_images_falls_back_to_last_image() {
+        let refs = vec![image_ref("a.png", "temp"), image_ref("b.png", "temp")];
+        let picked = output_images(&refs);
+        assert_eq!(picked.len(), 1);
+        assert_eq!(picked[0].filename, "b.png");
+        assert!(output_images(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_batch_rows_each_carry_their_share_of_compute() {
+        let job = make_job_with_settings(r#"{"checkpoint":"sd.safetensors","batchSize":4}"#);
+        let request = build_generation_request(&job, MAX_DIMENSION).unwrap();
+        assert_eq!(request.batch_size, 4);
+
+        let entry = build_image_entry(&job, &request, "b.png".to_string());
+        assert_eq!(entry.compute_cost, Some(request.compute_cost() / 4));
+    }
+
+    #[test]
+    fn test_partial_batch_keeps_rows_without_completing_job() {
+        let conn = db::open_memory_database().unwrap();
+        let job = make_job_with_settings(r#"{"checkpoint":"sd.safetensors","batchSize":3}"#);
+        db::queue::insert_job(&conn, &job).unwrap();
+        assert!(manager::claim_job(&conn, &job.id).unwrap());
+        let request = build_generation_request(&job, MAX_DIMENSION).unwrap();
+        let entries: Vec<ImageEntry> = ["one.png", "two.png"]
+            .iter()
+            .map(|name| build_image_entry(&job, &request, name.to_string()))
+            .collect();
+
+        record_images(&conn, &job, &entries, &[None, Some(7)], false, false).unwrap();
+
+        let stored = db::queue::get_job(&conn, &job.id).unwrap().unwrap();
+        assert_eq!(stored.status, QueueJobStatus::Generating);
+        assert_eq!(
+            stored.result_image_id.as_deref(),
+            Some(entries[0].id.as_str())
+        );
+        assert!(stored.completed_at.is_none());
+        for entry in &entries {
+            assert!(db::images::get_image(&conn, &entry.id).unwrap().is_some());
+        }
+    }
+
+    #[test]
+    fn test_complete_batch_marks_job_completed() {
+        let conn = db::open_memory_database().unwrap();
+        let job = make_job_with_settings(r#"{"checkpoint":"sd.safetensors"}"#);
+        db::queue::insert_job(&conn, &job).unwrap();
+        assert!(manager::claim_job(&conn, &job.id).unwrap());
+        let request = build_generation_request(&job, MAX_DIMENSION).unwrap();
+        let entry = build_image_entry(&job, &request, "done.png".to_string());
+
+        record_images(
+            &conn,
+            &job,
+            std::slice::from_ref(&entry),
+            &[None],
+            true,
+            false,
+        )
+        .unwrap();
+
+        let stored = db::queue::get_job(&conn, &job.id).unwrap().unwrap();
+        assert_eq!(stored.status, QueueJobStatus::Completed);
+        assert_eq!(stored.result_image_id.as_deref(), Some(entry.id.as_str()));
+    }
+}
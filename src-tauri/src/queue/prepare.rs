@@ -0,0 +1,101 @@
+use anyhow::{Context, Result};
+
+use crate::comfyui::{upload, workflow};
+use crate::gallery::storage;
+use crate::types::config::AppConfig;
+use crate::types::generation::GenerationRequest;
+
+/// The request and workflow for `job`, with a random seed (-1) replaced by
+/// the one the workflow uses so saved metadata and the gallery row agree.
+pub(super) async fn prepare_generation(
+    http: &reqwest::Client,
+    config: &AppConfig,
+    job: &crate::types::queue::QueueJob,
+) -> Result<(GenerationRequest, serde_json::Value)> {
+    let mut request = build_generation_request(job, config.generation.max_dimension)?;
+    let (workflow, seed) = build_workflow(http, config, &request).await?;
+    request.seed = seed;
+    Ok((request, workflow))
+}
+
+/// Build the ComfyUI workflow for `request`: img2img when it names an init
+/// image and denoise is below 1.0 (the image is uploaded to ComfyUI first),
+/// txt2img otherwise, with a ControlNet on top when one is set (its control
+/// image is uploaded too). A custom workflow replaces all of that and only
+/// receives the prompts and seed. Returns the workflow and the seed it will use.
+pub(crate) async fn build_workflow(
+    http: &reqwest::Client,
+    config: &AppConfig,
+    request: &GenerationRequest,
+) -> Result<(serde_json::Value, i64)> {
+    if let Some(custom) = &request.custom_workflow {
+        return workflow::build_custom(request, custom);
+    }
+    let (mut graph, seed) = match (&request.init_image, &request.hires) {
+        (Some(init_image), _) if request.denoise < 1.0 => {
+            let uploaded = upload_gallery_image(http, config, init_image, "init image").await?;
+            let (mut graph, seed) = workflow::build_img2img(request, &uploaded, request.denoise);
+            if let Some(hires) = &request.hires {
+                workflow::apply_hires(&mut graph, hires.upscale_by, hires.steps, hires.denoise)?;
+            }
+            (graph, seed)
+        }
+        (_, Some(hires)) => {
+            workflow::build_txt2img_hires(request, hires.upscale_by, hires.steps, hires.denoise)?
+        }
+        _ => workflow::build_txt2img(request),
+    };
+
+    if let Some(controlnet) = &request.controlnet {
+        let uploaded =
+            upload_gallery_image(http, config, &controlnet.image, "control image").await?;
+        workflow::apply_controlnet(
+            &mut graph,
+            &uploaded,
+            &controlnet.model,
+            controlnet.strength,
+        );
+    }
+    Ok((graph, seed))
+}
+
+/// Upload a gallery original to ComfyUI's input folder and return the name
+/// ComfyUI stored it under. `what` names the image in error messages.
+async fn upload_gallery_image(
+    http: &reqwest::Client,
+    config: &AppConfig,
+    filename: &str,
+    what: &str,
+) -> Result<String> {
+    storage::validate_filename(filename)?;
+    let path = storage::locate_original(config, filename)
+        .with_context(|| format!("The {} {} was not found in the gallery", what, filename))?;
+    let bytes = tokio::fs::read(&path)
+        .await
+        .with_context(|| format!("Failed to read {} {}", what, path.display()))?;
+    upload::upload_image(http, &config.comfyui.endpoint, filename, &bytes)
+        .await
+        .with_context(|| format!("Failed to upload {} to ComfyUI", what))
+}
+
+/// Parse the settings_json stored in a QueueJob into a validated
+/// GenerationRequest, rejecting sizes above `max_dimension`.
+pub(super) fn build_generation_request(
+    job: &crate::types::queue::QueueJob,
+    max_dimension: u32,
+) -> Result<GenerationRequest> {
+    use crate::types::generation::GenerationSettings;
+
+    let settings: GenerationSettings =
+        serde_json::from_str(&job.settings_json).context("Failed to parse job settings_json")?;
+
+    let request = settings.into_request(job.positive_prompt.clone(), job.negative_prompt.clone());
+    request
+        .validate(max_dimension)
+        .context("Invalid generation settings")?;
+    Ok(request)
+}
+
+#[cfg(test)]
+#[path = "prepare_test.rs"]
+pub(crate) mod tests;
@@ -0,0 +1,304 @@
+use super::*;
+use crate::db;
+use crate::queue::output::build_image_entry;
+use crate::types::generation::MAX_DIMENSION;
+use crate::types::queue::{QueueJob, QueuePriority};
+
+pub(crate) fn make_job_with_settings(settings_json: &str) -> QueueJob {
+    QueueJob {
+        settings_json: settings_json.to_string(),
+        selected_concept: Some(0),
+        ..db::queue::tests::make_job("test-job", QueuePriority::Normal)
+    }
+}
+
+#[test]
+fn test_build_generation_request_full() {
+    let job = make_job_with_settings(
+        r#"{"checkpoint":"sd_xl_base.safetensors","width":1024,"height":1024,"steps":30,"cfgScale":8.0,"sampler":"euler","scheduler":"normal","seed":42,"batchSize":2}"#,
+    );
+    let req = build_generation_request(&job, MAX_DIMENSION).unwrap();
+    assert_eq!(req.checkpoint, "sd_xl_base.safetensors");
+    assert_eq!(req.width, 1024);
+    assert_eq!(req.height, 1024);
+    assert_eq!(req.steps, 30);
+    assert_eq!(req.cfg_scale, 8.0);
+    assert_eq!(req.sampler, "euler");
+    assert_eq!(req.scheduler, "normal");
+    assert_eq!(req.seed, 42);
+    assert_eq!(req.batch_size, 2);
+    assert_eq!(req.positive_prompt, "a cat");
+    assert_eq!(req.negative_prompt, "lowres");
+}
+
+#[test]
+fn test_build_generation_request_missing_checkpoint_errors() {
+    let job = make_job_with_settings(r#"{}"#);
+    let result = build_generation_request(&job, MAX_DIMENSION);
+    assert!(result.is_err());
+    let err_msg = format!("{:#}", result.unwrap_err());
+    assert!(
+        err_msg.contains("checkpoint") || err_msg.contains("settings_json"),
+        "Error should mention checkpoint or settings_json, got: {}",
+        err_msg
+    );
+}
+
+#[test]
+fn test_build_generation_request_defaults_with_checkpoint() {
+    let job = make_job_with_settings(r#"{"checkpoint":"test.safetensors"}"#);
+    let req = build_generation_request(&job, MAX_DIMENSION).unwrap();
+    assert_eq!(req.checkpoint, "test.safetensors");
+    assert_eq!(req.width, 512);
+    assert_eq!(req.height, 768);
+    assert_eq!(req.steps, 25);
+    assert_eq!(req.cfg_scale, 7.5);
+    assert_eq!(req.sampler, "dpmpp_2m");
+    assert_eq!(req.scheduler, "karras");
+    assert_eq!(req.seed, -1);
+    assert_eq!(req.batch_size, 1);
+}
+
+#[test]
+fn test_build_generation_request_snake_case_keys() {
+    let job = make_job_with_settings(
+        r#"{"checkpoint":"test.safetensors","cfg_scale":6.0,"batch_size":3}"#,
+    );
+    let req = build_generation_request(&job, MAX_DIMENSION).unwrap();
+    assert_eq!(req.cfg_scale, 6.0);
+    assert_eq!(req.batch_size, 3);
+}
+
+#[test]
+fn test_build_generation_request_parses_seed_forms() {
+    let seed_of = |seed: &str| {
+        let job = make_job_with_settings(&format!(
+            r#"{{"checkpoint":"test.safetensors","seed":{}}}"#,
+            seed
+        ));
+        build_generation_request(&job, MAX_DIMENSION).unwrap().seed
+    };
+
+    assert_eq!(seed_of("12345"), 12345);
+    assert_eq!(seed_of(r#""12345""#), 12345);
+    assert_eq!(seed_of(r#""0x3039""#), 12345);
+    assert_eq!(seed_of(r#"" 0XFF ""#), 255);
+    assert_eq!(seed_of("-1"), -1);
+    assert_eq!(seed_of(r#""-1""#), -1);
+    assert_eq!(seed_of(r#""not a seed""#), -1);
+}
+
+#[test]
+fn test_parse_seed_normalizes_out_of_range_values() {
+    use crate::types::generation::parse_seed;
+    use serde_json::json;
+
+    // Same bit pattern via hex, unsigned and signed decimal
+    let from_hex = parse_seed(&json!("0xFFFFFFFFFFFFFFFE"));
+    assert_eq!(from_hex, i64::MAX - 1);
+    assert_eq!(parse_seed(&json!(u64::MAX - 1)), from_hex);
+    assert_eq!(parse_seed(&json!(-2)), from_hex);
+    assert_eq!(parse_seed(&json!(null)), -1);
+}
+
+#[test]
+fn test_build_generation_request_invalid_json() {
+    let job = make_job_with_settings("not json");
+    let result = build_generation_request(&job, MAX_DIMENSION);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_compute_cost_for_known_request() {
+    let job = make_job_with_settings(
+        r#"{"checkpoint":"sd_xl_base.safetensors","width":1024,"height":768,"steps":30,"batchSize":2}"#,
+    );
+    let req = build_generation_request(&job, MAX_DIMENSION).unwrap();
+    assert_eq!(req.compute_cost(), 30 * 1024 * 768 * 2);
+}
+
+#[test]
+fn test_hires_settings_upscale_saved_dimensions() {
+    let job = make_job_with_settings(
+        r#"{"checkpoint":"a.safetensors","width":512,"height":768,"steps":20,"hires":{"upscaleBy":1.5,"steps":10,"denoise":0.4}}"#,
+    );
+    let req = build_generation_request(&job, MAX_DIMENSION).unwrap();
+    assert_eq!(req.output_size(), (768, 1152));
+    assert_eq!(
+        req.compute_cost(),
+        20 * 512 * 768 + 10 * 768 * 1152,
+        "the hires pass counts at its upscaled size"
+    );
+
+    let entry = build_image_entry(&job, &req, "out.png".to_string());
+    assert_eq!(entry.width, Some(768));
+    assert_eq!(entry.height, Some(1152));
+}
+
+#[test]
+fn test_hires_settings_are_validated() {
+    for hires in [
+        r#"{"upscaleBy":1.0,"steps":10,"denoise":0.4}"#,
+        r#"{"upscaleBy":2.0,"steps":0,"denoise":0.4}"#,
+        r#"{"upscaleBy":2.0,"steps":10,"denoise":1.5}"#,
+    ] {
+        let job = make_job_with_settings(&format!(
+            r#"{{"checkpoint":"a.safetensors","width":512,"height":512,"hires":{}}}"#,
+            hires
+        ));
+        assert!(
+            build_generation_request(&job, MAX_DIMENSION).is_err(),
+            "{}",
+            hires
+        );
+    }
+
+    // The upscaled size has to fit the dimension cap too
+    let job = make_job_with_settings(
+        r#"{"checkpoint":"a.safetensors","width":1024,"height":1024,"hires":{"upscaleBy":2.0,"steps":10,"denoise":0.4}}"#,
+    );
+    assert!(build_generation_request(&job, 1536).is_err());
+    assert!(build_generation_request(&job, 2048).is_ok());
+}
+
+#[test]
+fn test_denoise_flows_into_ksampler_and_image_row() {
+    let conn = db::open_memory_database().unwrap();
+    let job = make_job_with_settings(r#"{"checkpoint":"dreamshaper_8.safetensors","denoise":0.6}"#);
+    let request = build_generation_request(&job, MAX_DIMENSION).unwrap();
+    assert_eq!(request.denoise, 0.6);
+
+    let (workflow_json, _) = workflow::build_txt2img(&request);
+    assert_eq!(workflow_json["5"]["inputs"]["denoise"], 0.6);
+
+    let image = build_image_entry(&job, &request, "denoise.png".to_string());
+    db::images::insert_image(&conn, &image).unwrap();
+    let saved = db::images::get_image(&conn, &image.id).unwrap().unwrap();
+    assert_eq!(saved.denoise, Some(0.6));
+}
+
+#[test]
+fn test_dimension_over_configured_cap_is_rejected() {
+    let job =
+        make_job_with_settings(r#"{"checkpoint":"x.safetensors","width":2048,"height":2049}"#);
+    let err = format!("{:#}", build_generation_request(&job, 2048).unwrap_err());
+    assert!(
+        err.contains("Height of 2049 px exceeds the maximum image dimension of 2048"),
+        "{}",
+        err
+    );
+
+    let job =
+        make_job_with_settings(r#"{"checkpoint":"x.safetensors","width":2048,"height":2048}"#);
+    assert!(build_generation_request(&job, 2048).is_ok());
+
+    // The hard limit still applies when the config asks for more
+    let job = make_job_with_settings(r#"{"checkpoint":"x.safetensors","width":8192}"#);
+    assert!(build_generation_request(&job, 10_000).is_err());
+}
+
+#[test]
+fn test_denoise_defaults_to_one_and_rejects_out_of_range() {
+    let job = make_job_with_settings(r#"{"checkpoint":"x.safetensors"}"#);
+    assert_eq!(
+        build_generation_request(&job, MAX_DIMENSION)
+            .unwrap()
+            .denoise,
+        1.0
+    );
+
+    let job = make_job_with_settings(r#"{"checkpoint":"x.safetensors","denoise":1.5}"#);
+    assert!(build_generation_request(&job, MAX_DIMENSION).is_err());
+}
+
+#[tokio::test]
+async fn test_build_workflow_uploads_init_image_for_img2img() {
+    let tmp = tempfile::tempdir().unwrap();
+    let mut config = AppConfig::default();
+    config.storage.image_directory = tmp.path().to_string_lossy().to_string();
+    storage::save_image_from_bytes_with_config(&config, b"init bytes", "base.png").unwrap();
+    let server = crate::mock_http::MockServer::start(vec![
+        r#"{"name": "base (1).png", "subfolder": "", "type": "input"}"#.to_string(),
+    ])
+    .await;
+    config.comfyui.endpoint = server.endpoint.clone();
+
+    let job = make_job_with_settings(
+        r#"{"checkpoint":"sd.safetensors","initImage":"base.png","denoise":0.5}"#,
+    );
+    let mut request = build_generation_request(&job, MAX_DIMENSION).unwrap();
+    assert_eq!(request.init_image.as_deref(), Some("base.png"));
+
+    let client = reqwest::Client::new();
+    let (workflow, _) = build_workflow(&client, &config, &request).await.unwrap();
+    assert_eq!(workflow["8"]["inputs"]["image"], "base (1).png");
+    assert_eq!(workflow["5"]["inputs"]["denoise"], 0.5);
+    assert!(server.requests()[0].body.contains("init bytes"));
+
+    // Full denoise never touches the init image
+    request.denoise = 1.0;
+    let (workflow, _) = build_workflow(&client, &config, &request).await.unwrap();
+    assert_eq!(workflow["2"]["class_type"], "EmptyLatentImage");
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[tokio::test]
+async fn test_build_workflow_uploads_control_image() {
+    let tmp = tempfile::tempdir().unwrap();
+    let mut config = AppConfig::default();
+    config.storage.image_directory = tmp.path().to_string_lossy().to_string();
+    storage::save_image_from_bytes_with_config(&config, b"pose bytes", "pose.png").unwrap();
+    let server = crate::mock_http::MockServer::start(vec![
+        r#"{"name": "pose.png", "subfolder": "", "type": "input"}"#.to_string(),
+    ])
+    .await;
+    config.comfyui.endpoint = server.endpoint.clone();
+
+    let job = make_job_with_settings(
+        r#"{"checkpoint":"sd.safetensors","controlnet":{"image":"pose.png","model":"openpose.pth","strength":0.7}}"#,
+    );
+    let request = build_generation_request(&job, MAX_DIMENSION).unwrap();
+    let client = reqwest::Client::new();
+    let (workflow, _) = build_workflow(&client, &config, &request).await.unwrap();
+
+    assert_eq!(workflow["8"]["inputs"]["image"], "pose.png");
+    assert_eq!(workflow["9"]["inputs"]["control_net_name"], "openpose.pth");
+    assert_eq!(
+        workflow["5"]["inputs"]["positive"],
+        serde_json::json!(["10", 0])
+    );
+    assert!(server.requests()[0].body.contains("pose bytes"));
+
+    // A control image missing from the gallery fails before anything is queued
+    let mut request = request;
+    if let Some(controlnet) = request.controlnet.as_mut() {
+        controlnet.image = "missing.png".to_string();
+    }
+    assert!(build_workflow(&client, &config, &request).await.is_err());
+}
+
+#[tokio::test]
+async fn test_saved_png_embeds_resolved_random_seed() {
+    let tmp = tempfile::tempdir().unwrap();
+    let mut config = AppConfig::default();
+    config.storage.image_directory = tmp.path().to_string_lossy().to_string();
+    let job = make_job_with_settings(r#"{"checkpoint":"sd.safetensors","seed":-1}"#);
+
+    let (request, workflow) = prepare_generation(&reqwest::Client::new(), &config, &job)
+        .await
+        .unwrap();
+    assert_ne!(request.seed, -1);
+    assert_eq!(workflow["5"]["inputs"]["seed"], request.seed);
+
+    let mut png = std::io::Cursor::new(Vec::new());
+    image::RgbImage::new(8, 8)
+        .write_to(&mut png, image::ImageFormat::Png)
+        .unwrap();
+    let saved = storage::save_image_with_metadata(&config, &png.into_inner(), &request).unwrap();
+    let text = crate::gallery::png_chunks::read_png_text(
+        &storage::originals_dir_for(&config).join(saved.filename),
+    )
+    .unwrap();
+    let meta = crate::gallery::png_metadata::parse_a1111_parameters(&text["parameters"]);
+    assert_eq!(meta.seed, Some(request.seed));
+}
@@ -0,0 +1,95 @@
+use serde_json::Value;
+
+use crate::gallery::png_metadata;
+use crate::types::generation::GenerationRequest;
+
+/// Compare the KSampler settings in the graph ComfyUI actually executed with
+/// what we requested. The request is updated to what ran, so the image row
+/// records reality; the differences come back as a short description such
+/// as `sampler: requested euler, ran dpmpp_2m`. `None` means they matched or
+/// the graph had no KSampler to compare against.
+pub fn reconcile_with_graph(request: &mut GenerationRequest, graph: &Value) -> Option<String> {
+    let actual = png_metadata::parse_comfyui_graph(graph)?;
+    let mut mismatches = Vec::new();
+
+    if let Some(sampler) = actual.sampler {
+        if sampler != request.sampler {
+            mismatches.push(format!(
+                "sampler: requested {}, ran {}",
+                request.sampler, sampler
+            ));
+            request.sampler = sampler;
+        }
+    }
+    if let Some(scheduler) = actual.scheduler {
+        if scheduler != request.scheduler {
+            mismatches.push(format!(
+                "scheduler: requested {}, ran {}",
+                request.scheduler, scheduler
+            ));
+            request.scheduler = scheduler;
+        }
+    }
+    if let Some(steps) = actual.steps {
+        if steps != request.steps {
+            mismatches.push(format!("steps: requested {}, ran {}", request.steps, steps));
+            request.steps = steps;
+        }
+    }
+
+    (!mismatches.is_empty()).then(|| mismatches.join("; "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::comfyui::workflow;
+
+    fn request() -> GenerationRequest {
+        GenerationRequest {
+            positive_prompt: "a cat".to_string(),
+            negative_prompt: String::new(),
+            checkpoint: "dreamshaper_8.safetensors".to_string(),
+            width: 512,
+            height: 512,
+            steps: 20,
+            cfg_scale: 7.0,
+            sampler: "euler".to_string(),
+            scheduler: "normal".to_string(),
+            seed: 42,
+            batch_size: 1,
+            denoise: 1.0,
+            init_image: None,
+            loras: Vec::new(),
+            hires: None,
+            controlnet: None,
+            custom_workflow: None,
+        }
+    }
+
+    #[test]
+    fn test_history_with_different_sampler_wins() {
+        let mut req = request();
+        let (mut graph, _) = workflow::build_txt2img(&req);
+        graph["5"]["inputs"]["sampler_name"] = "dpmpp_2m".into();
+
+        let mismatch = reconcile_with_graph(&mut req, &graph);
+
+        assert_eq!(
+            mismatch.as_deref(),
+            Some("sampler: requested euler, ran dpmpp_2m")
+        );
+        assert_eq!(req.sampler, "dpmpp_2m");
+        assert_eq!(req.scheduler, "normal");
+        assert_eq!(req.steps, 20);
+    }
+
+    #[test]
+    fn test_matching_history_reports_nothing() {
+        let mut req = request();
+        let (graph, _) = workflow::build_txt2img(&req);
+        assert!(reconcile_with_graph(&mut req, &graph).is_none());
+        assert!(reconcile_with_graph(&mut req, &serde_json::json!({})).is_none());
+        assert_eq!(req.sampler, "euler");
+    }
+}
@@ -0,0 +1,297 @@
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+use serde_json::Value;
+
+use crate::comfyui::workflow;
+use crate::db;
+use crate::gallery::variation;
+use crate::state::AppState;
+use crate::types::comparison::Comparison;
+use crate::types::gallery::ImageEntry;
+use crate::types::generation::{GenerationRequest, GenerationSettings, PartialGenerationRequest};
+use crate::types::queue::{QueueJob, QueueJobStatus, QueuePriority, VariableSweep};
+
+/// Upper bound on jobs a single sweep may enqueue.
+pub const MAX_SWEEP_VALUES: usize = 50;
+
+/// Enqueue one job per value of `variable`, everything else fixed, sharing a
+/// group. As the jobs finish, each result is paired with the group's first
+/// result as a comparison (see `link_group_comparison`).
+pub fn enqueue_variable_sweep(
+    state: &AppState,
+    base: &GenerationRequest,
+    variable: &str,
+    values: &[Value],
+) -> Result<VariableSweep> {
+    let jobs = build_sweep_jobs(base, variable, values, &mut rand::rng())?;
+    let sweep = VariableSweep {
+        group_id: jobs[0].group_id.clone().unwrap_or_default(),
+        job_ids: jobs.iter().map(|j| j.id.clone()).collect(),
+    };
+
+    let conn = state.db.lock().map_err(|e| anyhow::anyhow!("{}", e))?;
+    let tx = conn
+        .unchecked_transaction()
+        .context("Failed to start sweep transaction")?;
+    for job in &jobs {
+        db::queue::insert_job(&tx, job)?;
+    }
+    tx.commit().context("Failed to commit sweep jobs")?;
+    Ok(sweep)
+}
+
+/// Build the sweep's jobs. A random base seed is resolved once so the jobs
+/// really differ only in the swept field.
+pub fn build_sweep_jobs(
+    base: &GenerationRequest,
+    variable: &str,
+    values: &[Value],
+    rng: &mut impl rand::Rng,
+) -> Result<Vec<QueueJob>> {
+    let key = settings_key(variable)?;
+    if values.len() < 2 {
+        anyhow::bail!("A sweep needs at least two values");
+    }
+    if values.len() > MAX_SWEEP_VALUES {
+        anyhow::bail!(
+            "A sweep is limited to {} values, got {}",
+            MAX_SWEEP_VALUES,
+            values.len()
+        );
+    }
+
+    let mut base_settings = GenerationSettings::from(base);
+    base_settings.seed = workflow::resolve_seed(base.seed, rng);
+    let group_id = uuid::Uuid::new_v4().to_string();
+
+    values
+        .iter()
+        .map(|value| {
+            check_value(key, value)?;
+            let swept: PartialGenerationRequest =
+                serde_json::from_value(serde_json::json!({ key: value }))
+                    .with_context(|| format!("Invalid value {} for {}", value, key))?;
+            let mut settings = base_settings.clone();
+            settings.apply(&swept);
+            Ok(QueueJob {
+                id: uuid::Uuid::new_v4().to_string(),
+                priority: QueuePriority::Normal,
+                status: QueueJobStatus::Pending,
+                positive_prompt: base.positive_prompt.clone(),
+                negative_prompt: base.negative_prompt.clone(),
+                settings_json: settings.to_json()?,
+                pipeline_log: None,
+                original_idea: None,
+                selected_concept: None,
+                auto_approved: false,
+                linked_comparison_id: None,
+                group_id: Some(group_id.clone()),
+                pipeline_run_id: None,
+                parent_image_id: None,
+                retry_count: 0,
+                created_at: None,
+                started_at: None,
+                completed_at: None,
+                result_image_id: None,
+            })
+        })
+        .collect()
+}
+
+/// Map a sweepable field name to its `settings_json` key.
+fn settings_key(variable: &str) -> Result<&'static str> {
+    match variable {
+        "sampler" => Ok("sampler"),
+        "scheduler" => Ok("scheduler"),
+        "cfg" | "cfgScale" | "cfg_scale" => Ok("cfgScale"),
+        "steps" => Ok("steps"),
+        other => anyhow::bail!(
+            "Cannot sweep '{}'; expected sampler, scheduler, cfg or steps",
+            other
+        ),
+    }
+}
+
+fn check_value(key: &str, value: &Value) -> Result<()> {
+    let ok = match key {
+        "sampler" | "scheduler" => value.as_str().is_some_and(|s| !s.trim().is_empty()),
+        "cfgScale" => value.is_number(),
+        "steps" => value.as_u64().is_some(),
+        _ => false,
+    };
+    if !ok {
+        anyhow::bail!("Invalid value {} for {}", value, key);
+    }
+    Ok(())
+}
+
+/// Pair a finished group job's image with the group's first result as a
+/// comparison, labelled with whatever differs between the two. Returns the
+/// comparison ID, or `None` for the first result or if nothing differs.
+pub fn link_group_comparison(
+    conn: &Connection,
+    job: &QueueJob,
+    image: &ImageEntry,
+) -> Result<Option<String>> {
+    let Some(group_id) = &job.group_id else {
+        return Ok(None);
+    };
+    let Some(first_id) = db::queue::first_group_result(conn, group_id, &job.id)? else {
+        return Ok(None);
+    };
+    let Some(first) = db::images::get_image(conn, &first_id)? else {
+        return Ok(None);
+    };
+
+    let changed = variation::changed_fields(&first, &variation::stored_settings(image));
+    if changed.is_empty() {
+        return Ok(None);
+    }
+
+    let comparison = Comparison {
+        id: uuid::Uuid::new_v4().to_string(),
+        image_a_id: first.id,
+        image_b_id: image.id.clone(),
+        variable_changed: changed.join(", "),
+        note: None,
+        created_at: None,
+    };
+    db::comparisons::insert_comparison(conn, &comparison)?;
+    Ok(Some(comparison.id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::images::tests::make_test_image;
+    use crate::queue::manager;
+    use crate::types::config::AppConfig;
+    use crate::types::generation::{ControlNetSpec, HiresFix, LoraSpec};
+    use rand::SeedableRng;
+    use serde_json::json;
+
+    fn base_request() -> GenerationRequest {
+        GenerationRequest {
+            positive_prompt: "a cat on a throne".to_string(),
+            negative_prompt: "lowres".to_string(),
+            checkpoint: "dreamshaper_8.safetensors".to_string(),
+            width: 512,
+            height: 768,
+            steps: 25,
+            cfg_scale: 7.5,
+            sampler: "dpmpp_2m".to_string(),
+            scheduler: "karras".to_string(),
+            seed: -1,
+            batch_size: 1,
+            denoise: 1.0,
+            init_image: None,
+            loras: Vec::new(),
+            hires: None,
+            controlnet: None,
+            custom_workflow: None,
+        }
+    }
+
+    #[test]
+    fn test_sampler_sweep_jobs_differ_only_in_sampler() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let jobs = build_sweep_jobs(
+            &base_request(),
+            "sampler",
+            &[json!("euler"), json!("dpmpp_2m_sde")],
+            &mut rng,
+        )
+        .unwrap();
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(jobs[0].group_id, jobs[1].group_id);
+        assert!(jobs[0].group_id.is_some());
+
+        let mut a: Value = serde_json::from_str(&jobs[0].settings_json).unwrap();
+        let mut b: Value = serde_json::from_str(&jobs[1].settings_json).unwrap();
+        assert_eq!(a["sampler"], "euler");
+        assert_eq!(b["sampler"], "dpmpp_2m_sde");
+        assert!(a["seed"].as_i64().unwrap() >= 0);
+
+        a.as_object_mut().unwrap().remove("sampler");
+        b.as_object_mut().unwrap().remove("sampler");
+        assert_eq!(a, b);
+        assert_eq!(jobs[0].positive_prompt, jobs[1].positive_prompt);
+    }
+
+    #[test]
+    fn test_sweep_jobs_keep_loras_hires_and_controlnet() {
+        let mut base = base_request();
+        base.loras = vec![LoraSpec {
+            filename: "detail.safetensors".to_string(),
+            model_weight: 0.8,
+            clip_weight: 0.6,
+        }];
+        base.hires = Some(HiresFix {
+            upscale_by: 1.5,
+            steps: 10,
+            denoise: 0.4,
+        });
+        base.controlnet = Some(ControlNetSpec {
+            image: "pose.png".to_string(),
+            model: "openpose.safetensors".to_string(),
+            strength: 1.0,
+        });
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let jobs = build_sweep_jobs(&base, "steps", &[json!(20), json!(30)], &mut rng).unwrap();
+
+        for (job, steps) in jobs.iter().zip([20, 30]) {
+            let settings: GenerationSettings = serde_json::from_str(&job.settings_json).unwrap();
+            assert_eq!(settings.steps, steps);
+            assert_eq!(settings.loras, base.loras);
+            assert_eq!(settings.hires, base.hires);
+            assert_eq!(settings.controlnet, base.controlnet);
+        }
+    }
+
+    #[test]
+    fn test_sweep_rejects_bad_input() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let base = base_request();
+        assert!(
+            build_sweep_jobs(&base, "checkpoint", &[json!("a"), json!("b")], &mut rng).is_err()
+        );
+        assert!(build_sweep_jobs(&base, "steps", &[json!(20)], &mut rng).is_err());
+        assert!(build_sweep_jobs(&base, "steps", &[json!(20), json!("thirty")], &mut rng).is_err());
+        assert!(build_sweep_jobs(&base, "cfg", &[json!(5.0), json!(9)], &mut rng).is_ok());
+    }
+
+    #[test]
+    fn test_finished_sweep_jobs_form_comparisons() {
+        let state = AppState::new(
+            crate::db::open_memory_database().unwrap(),
+            AppConfig::default(),
+        );
+        let sweep = enqueue_variable_sweep(
+            &state,
+            &base_request(),
+            "sampler",
+            &[json!("euler"), json!("dpmpp_2m_sde")],
+        )
+        .unwrap();
+
+        let conn = state.db.lock().unwrap();
+        let mut linked = Vec::new();
+        for (i, job_id) in sweep.job_ids.iter().enumerate() {
+            let job = db::queue::get_job(&conn, job_id).unwrap().unwrap();
+            let settings: Value = serde_json::from_str(&job.settings_json).unwrap();
+            let mut image = make_test_image(&format!("img-{}", i));
+            image.sampler = settings["sampler"].as_str().map(String::from);
+            db::images::insert_image(&conn, &image).unwrap();
+            manager::mark_completed(&conn, job_id, &image.id).unwrap();
+            linked.push(link_group_comparison(&conn, &job, &image).unwrap());
+        }
+
+        assert_eq!(linked[0], None);
+        let comparison = db::comparisons::get_comparison(&conn, linked[1].as_ref().unwrap())
+            .unwrap()
+            .unwrap();
+        assert_eq!(comparison.image_a_id, "img-0");
+        assert_eq!(comparison.image_b_id, "img-1");
+        assert_eq!(comparison.variable_changed, "sampler");
+    }
+}
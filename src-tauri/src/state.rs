@@ -1,6 +1,7 @@
 use crate::types::config::AppConfig;
 use reqwest::Client;
 use rusqlite::Connection;
+use serde_json::Value;
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 use std::sync::Mutex;
@@ -17,7 +18,16 @@ pub struct AppState {
     pub http_client: Client,
     pub queue_paused: AtomicBool,
     pub pipeline_cancelled: Arc<AtomicBool>,
+    /// Set by `cancel_export` to stop the running export between images.
+    pub export_cancelled: Arc<AtomicBool>,
     pub shutdown_tx: broadcast::Sender<()>,
+    /// ComfyUI `/object_info`, keyed by the endpoint it was fetched from.
+    pub object_info_cache: Mutex<Option<(String, Arc<Value>)>>,
+    /// Stop flag of the running ComfyUI log tail, if any.
+    pub comfyui_log_tail: Mutex<Option<Arc<AtomicBool>>>,
+    /// Held by the queue executor while a job generates. With
+    /// `hardware.exclusive_gpu` on, vision-model work holds it too.
+    pub gpu_lock: tokio::sync::Mutex<()>,
 }
 
 impl AppState {
@@ -37,7 +47,11 @@ impl AppState {
             http_client,
             queue_paused: AtomicBool::new(false),
             pipeline_cancelled: Arc::new(AtomicBool::new(false)),
+            export_cancelled: Arc::new(AtomicBool::new(false)),
             shutdown_tx,
+            object_info_cache: Mutex::new(None),
+            comfyui_log_tail: Mutex::new(None),
+            gpu_lock: tokio::sync::Mutex::new(()),
         }
     }
 
@@ -47,4 +61,20 @@ impl AppState {
             .map_err(|e| anyhow::anyhow!("{}", e))
             .map(|config| config.clone())
     }
+
+    /// Wait until no generation is running and hold the GPU until the guard
+    /// is dropped, when `hardware.exclusive_gpu` is on. Returns `None`
+    /// straight away when it's off.
+    pub async fn exclusive_gpu(&self) -> Option<tokio::sync::MutexGuard<'_, ()>> {
+        let exclusive = self
+            .config
+            .read()
+            .map(|config| config.hardware.exclusive_gpu)
+            .unwrap_or(false);
+        if exclusive {
+            Some(self.gpu_lock.lock().await)
+        } else {
+            None
+        }
+    }
 }
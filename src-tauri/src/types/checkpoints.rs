@@ -17,6 +17,22 @@ pub struct CheckpointProfile {
     pub preferred_scheduler: Option<String>,
     pub optimal_resolution: Option<String>,
     pub notes: Option<String>,
+    /// Gallery image chosen to represent this checkpoint.
+    #[serde(default)]
+    pub sample_image_id: Option<String>,
+    /// Filename of the sample image, joined in on read (ignored on upsert).
+    #[serde(default)]
+    pub sample_filename: Option<String>,
+}
+
+/// Strengths and weaknesses proposed by the summarizer, for the user to
+/// review before they're written to the profile.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileSuggestion {
+    pub strengths: Vec<String>,
+    pub weaknesses: Vec<String>,
+    pub model: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -10,3 +10,10 @@ pub struct Comparison {
     pub note: Option<String>,
     pub created_at: Option<String>,
 }
+
+/// One side of an A/B comparison.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ComparisonSide {
+    A,
+    B,
+}
@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+
+use crate::types::gallery::GallerySortField;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GallerySettings {
+    /// Ratings at or above this value also mark the image as a favorite. 0 disables.
+    #[serde(default)]
+    pub auto_favorite_rating: u32,
+    /// Give newly generated, unrated images a star rating derived from the
+    /// reviewer's fidelity score.
+    #[serde(default)]
+    pub auto_rate_from_fidelity: bool,
+    /// Refuse permanent deletes, for shared or demo installs. Moving images
+    /// to the trash still works.
+    #[serde(default)]
+    pub safe_mode: bool,
+    /// When the gallery was last marked as seen (RFC 3339), for the
+    /// "new since last visit" filter. None until the first visit.
+    #[serde(default)]
+    pub last_seen_at: Option<String>,
+    /// Images per gallery page when the filter doesn't set a limit.
+    #[serde(default = "default_page_size")]
+    pub default_page_size: u32,
+    /// Sort used when the filter doesn't pick one. None keeps the built-in
+    /// order: best match first when searching, newest first otherwise.
+    #[serde(default)]
+    pub default_sort: Option<GallerySortField>,
+    /// Days an image stays in the trash before it's purged for good at
+    /// startup. 0 keeps trashed images forever.
+    #[serde(default)]
+    pub trash_retention_days: u32,
+}
+
+impl Default for GallerySettings {
+    fn default() -> Self {
+        Self {
+            auto_favorite_rating: 0,
+            auto_rate_from_fidelity: false,
+            safe_mode: false,
+            last_seen_at: None,
+            default_page_size: default_page_size(),
+            default_sort: None,
+            trash_retention_days: 0,
+        }
+    }
+}
+
+fn default_page_size() -> u32 {
+    crate::types::gallery::DEFAULT_PAGE_SIZE
+}
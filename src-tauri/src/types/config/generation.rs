@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationLimits {
+    /// Largest width or height accepted for a generation, in pixels.
+    #[serde(default = "default_max_dimension")]
+    pub max_dimension: u32,
+    /// Times a failed job goes back in the queue before it is marked failed.
+    /// 0 fails jobs on their first error.
+    #[serde(default = "default_max_job_retries")]
+    pub max_job_retries: u32,
+}
+
+impl Default for GenerationLimits {
+    fn default() -> Self {
+        Self {
+            max_dimension: default_max_dimension(),
+            max_job_retries: default_max_job_retries(),
+        }
+    }
+}
+
+fn default_max_dimension() -> u32 {
+    crate::types::generation::MAX_DIMENSION
+}
+
+fn default_max_job_retries() -> u32 {
+    1
+}
@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HardwareSettings {
+    pub cooldown_seconds: u32,
+    /// Extra random delay (0..=jitter seconds) added to each cooldown so the
+    /// GPU duty cycle isn't perfectly periodic. 0 disables jitter.
+    #[serde(default)]
+    pub cooldown_jitter_secs: u32,
+    /// Scale the cooldown by the VRAM ComfyUI reports free after each job:
+    /// skipped or shortened with plenty of headroom, extended when tight.
+    #[serde(default)]
+    pub adaptive_cooldown: bool,
+    pub max_consecutive_generations: u32,
+    pub enable_ha_power_monitoring: bool,
+    pub ha_entity_id: String,
+    pub ha_max_watts: u32,
+    /// Base URL of the Home Assistant instance reporting `ha_entity_id`.
+    #[serde(default = "default_ha_url")]
+    pub ha_url: String,
+    /// Long-lived access token, sent as a bearer token.
+    #[serde(default)]
+    pub ha_token: Option<String>,
+    /// Enable auto-downscaling of images before sending to vision models.
+    #[serde(default = "default_true")]
+    pub ai_batch_downscale: Option<bool>,
+    /// Maximum dimension (width or height) for downscaled images.
+    #[serde(default = "default_max_dim")]
+    pub ai_batch_max_dimension: Option<u32>,
+    /// Worker threads used when regenerating thumbnails in bulk.
+    #[serde(default = "default_thumbnail_concurrency")]
+    pub thumbnail_concurrency: u32,
+    /// Make vision-model tagging and captioning wait for the queue to finish
+    /// its current generation, and vice versa, so the two never share one
+    /// GPU's memory.
+    #[serde(default)]
+    pub exclusive_gpu: bool,
+}
+
+impl HardwareSettings {
+    /// Longest side images are downscaled to before going to a vision
+    /// model, or None to send originals.
+    pub fn vision_max_dimension(&self) -> Option<u32> {
+        if self.ai_batch_downscale.unwrap_or(true) {
+            self.ai_batch_max_dimension
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for HardwareSettings {
+    fn default() -> Self {
+        Self {
+            cooldown_seconds: 30,
+            cooldown_jitter_secs: 0,
+            adaptive_cooldown: false,
+            max_consecutive_generations: 5,
+            enable_ha_power_monitoring: false,
+            ha_entity_id: "sensor.gpu_power_draw".to_string(),
+            ha_max_watts: 180,
+            ha_url: default_ha_url(),
+            ha_token: None,
+            ai_batch_downscale: Some(true),
+            ai_batch_max_dimension: Some(1024),
+            thumbnail_concurrency: default_thumbnail_concurrency(),
+            exclusive_gpu: false,
+        }
+    }
+}
+
+fn default_true() -> Option<bool> {
+    Some(true)
+}
+
+fn default_ha_url() -> String {
+    "http://homeassistant.local:8123".to_string()
+}
+
+fn default_max_dim() -> Option<u32> {
+    Some(1024)
+}
+
+fn default_thumbnail_concurrency() -> u32 {
+    4
+}
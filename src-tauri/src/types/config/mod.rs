@@ -1,6 +1,20 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+mod gallery;
+mod generation;
+mod hardware;
+mod pipeline;
+mod storage;
+
+pub use gallery::GallerySettings;
+pub use generation::GenerationLimits;
+pub use hardware::HardwareSettings;
+pub use pipeline::{
+    PipelineSettings, PipelineStageTuning, ReviewerFailMode, StageSampling, DEFAULT_MAX_RETRIES,
+};
+pub use storage::{StorageSettings, ThumbnailFormat};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AppConfig {
@@ -12,18 +26,49 @@ pub struct AppConfig {
     pub presets: HashMap<String, QualityPreset>,
     #[serde(default)]
     pub storage: StorageSettings,
+    #[serde(default)]
+    pub gallery: GallerySettings,
+    #[serde(default)]
+    pub generation: GenerationLimits,
+    #[serde(default)]
+    pub stage_tuning: PipelineStageTuning,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ComfyUiConfig {
     pub endpoint: String,
+    /// Extra attempts for queueing a prompt and fetching its results when
+    /// ComfyUI can't be reached or times out, with exponential backoff.
+    #[serde(default = "default_comfyui_max_retries")]
+    pub max_retries: u32,
+}
+
+fn default_comfyui_max_retries() -> u32 {
+    3
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct OllamaConfig {
     pub endpoint: String,
+    /// Protocol spoken by the server at `endpoint` for pipeline chat.
+    #[serde(default)]
+    pub backend: LlmBackend,
+    /// Sent as a bearer token to OpenAI-compatible servers.
+    #[serde(default)]
+    pub api_key: Option<String>,
+}
+
+/// Which chat API the pipeline's LLM server speaks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LlmBackend {
+    /// Ollama's `/api/chat`.
+    #[default]
+    Ollama,
+    /// `/v1/chat/completions`, as served by vLLM, LM Studio and others.
+    OpenAiCompatible,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -36,6 +81,9 @@ pub struct ModelAssignments {
     pub reviewer: String,
     pub tagger: String,
     pub captioner: String,
+    /// Embedding model used for semantic gallery search.
+    #[serde(default = "default_embedder")]
+    pub embedder: String,
 
     /// Per-stage thinking mode override.
     /// Key = stage name (e.g., "ideator", "judge"), Value = thinking enabled.
@@ -45,49 +93,15 @@ pub struct ModelAssignments {
     /// Model names the user has manually marked as thinking-capable.
     #[serde(default)]
     pub custom_thinking_models: Vec<String>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct PipelineSettings {
-    pub enable_ideator: bool,
-    pub enable_composer: bool,
-    pub enable_judge: bool,
-    pub enable_prompt_engineer: bool,
-    pub enable_reviewer: bool,
-    pub auto_approve: bool,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct HardwareSettings {
-    pub cooldown_seconds: u32,
-    pub max_consecutive_generations: u32,
-    pub enable_ha_power_monitoring: bool,
-    pub ha_entity_id: String,
-    pub ha_max_watts: u32,
-    /// Enable auto-downscaling of images before sending to vision models.
-    #[serde(default = "default_true")]
-    pub ai_batch_downscale: Option<bool>,
-    /// Maximum dimension (width or height) for downscaled images.
-    #[serde(default = "default_max_dim")]
-    pub ai_batch_max_dimension: Option<u32>,
-}
 
-fn default_true() -> Option<bool> {
-    Some(true)
+    /// Installed model to run a stage on when its own model isn't pulled.
+    /// Unset means such a run stops before starting.
+    #[serde(default)]
+    pub fallback_model: Option<String>,
 }
 
-fn default_max_dim() -> Option<u32> {
-    Some(1024)
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-#[serde(rename_all = "camelCase")]
-pub struct StorageSettings {
-    /// Custom image directory. Empty string means use default (~/.visionforge/images).
-    #[serde(default)]
-    pub image_directory: String,
+fn default_embedder() -> String {
+    "nomic-embed-text".to_string()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -141,9 +155,12 @@ impl Default for AppConfig {
         Self {
             comfyui: ComfyUiConfig {
                 endpoint: "http://localhost:8188".to_string(),
+                max_retries: default_comfyui_max_retries(),
             },
             ollama: OllamaConfig {
                 endpoint: "http://localhost:11434".to_string(),
+                backend: LlmBackend::default(),
+                api_key: None,
             },
             models: ModelAssignments {
                 ideator: "mistral:7b".to_string(),
@@ -153,28 +170,18 @@ impl Default for AppConfig {
                 reviewer: "qwen2.5:7b".to_string(),
                 tagger: "llava:7b".to_string(),
                 captioner: "llava:7b".to_string(),
+                embedder: default_embedder(),
                 thinking_overrides: HashMap::new(),
                 custom_thinking_models: Vec::new(),
+                fallback_model: None,
             },
-            pipeline: PipelineSettings {
-                enable_ideator: true,
-                enable_composer: true,
-                enable_judge: true,
-                enable_prompt_engineer: true,
-                enable_reviewer: false,
-                auto_approve: false,
-            },
-            hardware: HardwareSettings {
-                cooldown_seconds: 30,
-                max_consecutive_generations: 5,
-                enable_ha_power_monitoring: false,
-                ha_entity_id: "sensor.gpu_power_draw".to_string(),
-                ha_max_watts: 180,
-                ai_batch_downscale: Some(true),
-                ai_batch_max_dimension: Some(1024),
-            },
+            pipeline: PipelineSettings::default(),
+            hardware: HardwareSettings::default(),
             presets,
             storage: StorageSettings::default(),
+            gallery: GallerySettings::default(),
+            generation: GenerationLimits::default(),
+            stage_tuning: PipelineStageTuning::default(),
         }
     }
 }
@@ -0,0 +1,133 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PipelineSettings {
+    pub enable_ideator: bool,
+    pub enable_composer: bool,
+    pub enable_judge: bool,
+    pub enable_prompt_engineer: bool,
+    pub enable_reviewer: bool,
+    pub auto_approve: bool,
+    /// Keep each stage's unparsed model response in the pipeline log.
+    /// Off by default since the raw text roughly doubles the log size.
+    #[serde(default)]
+    pub capture_raw: bool,
+    /// What to do when the Reviewer's reply has no parseable verdict.
+    #[serde(default)]
+    pub reviewer_fail_mode: ReviewerFailMode,
+    /// How many times the Judge, Prompt Engineer and Reviewer are re-asked
+    /// when their reply isn't valid JSON.
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// How many Reviewer passes a run may take. After a rejection the Prompt
+    /// Engineer rewrites the prompts with the Reviewer's issues and they're
+    /// reviewed again; 1 reviews once and applies its suggestions.
+    #[serde(default = "default_max_review_iterations")]
+    pub max_review_iterations: u32,
+    /// Tag every generated image with the tagger model once its job
+    /// completes, in the background.
+    #[serde(default)]
+    pub auto_tag_on_complete: bool,
+}
+
+impl Default for PipelineSettings {
+    fn default() -> Self {
+        Self {
+            enable_ideator: true,
+            enable_composer: true,
+            enable_judge: true,
+            enable_prompt_engineer: true,
+            enable_reviewer: false,
+            auto_approve: false,
+            capture_raw: false,
+            reviewer_fail_mode: ReviewerFailMode::default(),
+            max_retries: default_max_retries(),
+            max_review_iterations: default_max_review_iterations(),
+            auto_tag_on_complete: false,
+        }
+    }
+}
+
+/// Default for `PipelineSettings::max_retries`.
+pub const DEFAULT_MAX_RETRIES: u32 = 2;
+
+fn default_max_retries() -> u32 {
+    DEFAULT_MAX_RETRIES
+}
+
+fn default_max_review_iterations() -> u32 {
+    1
+}
+
+/// How the Reviewer stage settles a reply it can't parse.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ReviewerFailMode {
+    /// Treat the prompts as approved, noting the failure in the issues.
+    #[default]
+    ApproveOnError,
+    /// Mark the prompts as not approved so they get a human look.
+    RejectOnError,
+    /// Ask the model once more for JSON; fail the stage if that reply is
+    /// unparseable too.
+    RetryOnError,
+}
+
+/// Sampling options sent to Ollama for one pipeline stage. `None` leaves
+/// the model's own default in place.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StageSampling {
+    pub temperature: Option<f64>,
+    pub top_p: Option<f64>,
+    pub top_k: Option<u32>,
+}
+
+impl StageSampling {
+    fn with_temperature(temperature: f64) -> Self {
+        Self {
+            temperature: Some(temperature),
+            ..Self::default()
+        }
+    }
+}
+
+/// Per-stage sampling. The stages that invent run hotter than the ones
+/// that rank or check.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PipelineStageTuning {
+    pub ideator: StageSampling,
+    pub composer: StageSampling,
+    pub judge: StageSampling,
+    pub prompt_engineer: StageSampling,
+    pub reviewer: StageSampling,
+}
+
+impl Default for PipelineStageTuning {
+    fn default() -> Self {
+        Self {
+            ideator: StageSampling::with_temperature(0.9),
+            composer: StageSampling::with_temperature(0.8),
+            judge: StageSampling::with_temperature(0.2),
+            prompt_engineer: StageSampling::with_temperature(0.5),
+            reviewer: StageSampling::with_temperature(0.2),
+        }
+    }
+}
+
+impl PipelineStageTuning {
+    /// Sampling for a stage, by the names used for thinking overrides.
+    /// Unknown stages get the model defaults.
+    pub fn for_stage(&self, stage: &str) -> StageSampling {
+        match stage {
+            "ideator" => self.ideator,
+            "composer" => self.composer,
+            "judge" => self.judge,
+            "promptEngineer" | "prompt_engineer" => self.prompt_engineer,
+            "reviewer" => self.reviewer,
+            _ => StageSampling::default(),
+        }
+    }
+}
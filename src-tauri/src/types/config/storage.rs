@@ -0,0 +1,84 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageSettings {
+    /// Custom image directory. Empty string means use default (~/.visionforge/images).
+    #[serde(default)]
+    pub image_directory: String,
+    /// Write prompts and settings into saved PNGs. When off, every text
+    /// chunk (including ComfyUI's own) is stripped so shared files carry no
+    /// prompt.
+    #[serde(default = "default_embed_metadata")]
+    pub embed_metadata: bool,
+    /// Hours between automatic database backups. 0 disables them.
+    #[serde(default = "default_backup_interval_hours")]
+    pub backup_interval_hours: u32,
+    /// Number of database backups to keep.
+    #[serde(default = "default_backup_keep")]
+    pub backup_keep: u32,
+    /// Longest side of gallery thumbnails, in px.
+    #[serde(default = "default_thumbnail_size")]
+    pub thumbnail_size: u32,
+    #[serde(default)]
+    pub thumbnail_format: ThumbnailFormat,
+}
+
+fn default_embed_metadata() -> bool {
+    true
+}
+
+fn default_backup_interval_hours() -> u32 {
+    24
+}
+
+fn default_backup_keep() -> u32 {
+    7
+}
+
+/// Image format thumbnails are written in. Changing it only affects new
+/// thumbnails until they're regenerated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThumbnailFormat {
+    #[default]
+    Jpeg,
+    /// Lossless, so line art keeps its edges.
+    WebP,
+    Png,
+}
+
+impl ThumbnailFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Jpeg => "jpg",
+            Self::WebP => "webp",
+            Self::Png => "png",
+        }
+    }
+
+    pub fn image_format(self) -> image::ImageFormat {
+        match self {
+            Self::Jpeg => image::ImageFormat::Jpeg,
+            Self::WebP => image::ImageFormat::WebP,
+            Self::Png => image::ImageFormat::Png,
+        }
+    }
+}
+
+fn default_thumbnail_size() -> u32 {
+    256
+}
+
+impl Default for StorageSettings {
+    fn default() -> Self {
+        Self {
+            image_directory: String::new(),
+            embed_metadata: true,
+            backup_interval_hours: default_backup_interval_hours(),
+            backup_keep: default_backup_keep(),
+            thumbnail_size: default_thumbnail_size(),
+            thumbnail_format: ThumbnailFormat::default(),
+        }
+    }
+}
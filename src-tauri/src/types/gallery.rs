@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::types::pipeline::PipelineResult;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ImageEntry {
@@ -17,6 +19,9 @@ pub struct ImageEntry {
     pub sampler: Option<String>,
     pub scheduler: Option<String>,
     pub seed: Option<i64>,
+    /// KSampler denoise; 1.0 for every txt2img generation.
+    #[serde(default)]
+    pub denoise: Option<f64>,
     pub pipeline_log: Option<String>,
     pub selected_concept: Option<u32>,
     pub auto_approved: bool,
@@ -26,9 +31,68 @@ pub struct ImageEntry {
     pub favorite: bool,
     pub deleted: bool,
     pub user_note: Option<String>,
+    /// steps × width × height × batch — a rough "compute units" figure.
+    #[serde(default)]
+    pub compute_cost: Option<i64>,
+    /// How the image entered the gallery. `None` only for rows that predate
+    /// source tracking and could not be backfilled.
+    #[serde(default)]
+    pub source: Option<ImageSource>,
+    /// Vision-model aesthetic rating, 1–10. Unscored images are `None`.
+    #[serde(default)]
+    pub aesthetic_score: Option<f64>,
+    /// Pipeline run the prompts came from, via the queue job.
+    #[serde(default)]
+    pub pipeline_run_id: Option<String>,
+    /// Set when ComfyUI ran different KSampler settings than were requested,
+    /// e.g. `sampler: requested euler, ran dpmpp_2m`. The stored settings
+    /// are what actually ran.
+    #[serde(default)]
+    pub settings_mismatch: Option<String>,
+    /// Image this one is a variation of, if any.
+    #[serde(default)]
+    pub parent_image_id: Option<String>,
+    /// When the image was moved to the trash; `None` unless `deleted`.
+    #[serde(default)]
+    pub deleted_at: Option<String>,
     pub tags: Option<Vec<TagEntry>>,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ImageSource {
+    /// Generated from an idea through the LLM pipeline
+    Pipeline,
+    /// Generated from hand-written prompts
+    Manual,
+    /// Brought in from outside VisionForge
+    Imported,
+    /// Requeued from an existing image with parameter overrides
+    Variation,
+}
+
+impl ImageSource {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Pipeline => "pipeline",
+            Self::Manual => "manual",
+            Self::Imported => "imported",
+            Self::Variation => "variation",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "pipeline" => Some(Self::Pipeline),
+            "manual" => Some(Self::Manual),
+            "imported" => Some(Self::Imported),
+            "variation" => Some(Self::Variation),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TagEntry {
@@ -38,11 +102,143 @@ pub struct TagEntry {
     pub confidence: Option<f64>,
 }
 
+/// A tag that automatically brings another tag along (e.g. "corgi" → "dog").
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagImplication {
+    pub tag_id: i64,
+    pub tag_name: String,
+    pub implied_tag_id: i64,
+    pub implied_tag_name: String,
+}
+
+/// What renaming or merging a tag touches: the images and seeds currently
+/// carrying it.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TagChangeSummary {
+    pub images: u32,
+    pub seeds: u32,
+}
+
+/// A caption for an image. The primary comes from `images.caption` and has
+/// no `id`; alternates live in `image_captions`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageCaption {
+    pub id: Option<i64>,
+    pub image_id: String,
+    pub caption: String,
+    pub source: Option<String>,
+    pub created_at: Option<String>,
+    pub is_primary: bool,
+}
+
+/// The images an image was derived from and those derived from it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageLineage {
+    /// Parent first, then the parent's parent, and so on.
+    pub ancestors: Vec<ImageEntry>,
+    /// Immediate children, oldest first.
+    pub children: Vec<ImageEntry>,
+}
+
+/// Everything recorded about how one image was made, for sharing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProvenanceBundle {
+    /// The image's stored metadata. Its pipeline log is moved to `pipeline`.
+    pub image: ImageEntry,
+    /// The pipeline run that wrote the prompts, when there was one.
+    pub pipeline: Option<PipelineResult>,
+    /// The ComfyUI API graph embedded in the original PNG, when the file
+    /// still carries it.
+    pub workflow: Option<serde_json::Value>,
+    pub tags: Vec<TagEntry>,
+    /// Images this one was derived from, nearest first.
+    pub ancestors: Vec<ImageEntry>,
+}
+
+/// Recently used generation settings, newest first, for the generate form.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentChoices {
+    pub checkpoints: Vec<String>,
+    pub samplers: Vec<String>,
+    pub schedulers: Vec<String>,
+    pub dimensions: Vec<Dimensions>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Dimensions {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// How many times a prompt term appears across the gallery.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TermCount {
+    pub term: String,
+    pub count: u32,
+}
+
+/// Outcome of importing images made by other tools.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    /// IDs of the new gallery images, in the order the paths were given.
+    pub imported: Vec<String>,
+    pub failed: Vec<ImportFailure>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportFailure {
+    pub path: String,
+    pub error: String,
+}
+
+/// Outcome of a change applied to many images at once. Unknown ids are
+/// reported rather than failing the batch.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchUpdate {
+    pub updated: Vec<String>,
+    pub not_found: Vec<String>,
+}
+
+/// Outcome of re-rendering every thumbnail at a new size.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ThumbnailRegenSummary {
+    pub total: usize,
+    pub regenerated: usize,
+    pub skipped_missing: usize,
+    pub failed: usize,
+    /// One entry per skipped or failed image, in completion order.
+    pub errors: Vec<ThumbnailFailure>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ThumbnailFailure {
+    pub filename: String,
+    pub error: String,
+}
+
+/// Gallery page size when neither the filter nor the config sets one.
+pub const DEFAULT_PAGE_SIZE: u32 = 50;
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase", default)]
 pub struct GalleryFilter {
     pub search: Option<String>,
     pub tags: Option<Vec<String>>,
+    /// With several `tags`, require every one (the default) rather than any.
+    pub tag_match_all: Option<bool>,
     pub checkpoint: Option<String>,
     pub min_rating: Option<u32>,
     pub favorite_only: Option<bool>,
@@ -58,6 +254,45 @@ pub struct GalleryFilter {
     /// Filter to show only images without a caption.
     #[serde(default)]
     pub uncaptioned_only: Option<bool>,
+    #[serde(default)]
+    pub source: Option<ImageSource>,
+    /// Only images created strictly after this RFC 3339 timestamp.
+    #[serde(default)]
+    pub since: Option<String>,
+}
+
+impl GalleryFilter {
+    /// Fill in the configured page size and sort where the filter leaves
+    /// them unset. A search without an explicit sort keeps relevance order.
+    pub fn with_defaults(mut self, page_size: u32, sort: Option<&GallerySortField>) -> Self {
+        self.limit.get_or_insert(page_size);
+        if self.sort_by.is_none() && self.search.is_none() {
+            self.sort_by = sort.cloned();
+        }
+        self
+    }
+}
+
+/// Images created since a timestamp (usually the last gallery visit), with
+/// how many there are in total; `images` is limited like any gallery page.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewImages {
+    pub since: Option<String>,
+    pub count: u32,
+    pub images: Vec<ImageEntry>,
+}
+
+/// Per-image file written next to each original in a folder export.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SidecarFormat {
+    #[default]
+    None,
+    /// `<stem>.txt` holding the Automatic1111 `parameters` text.
+    A1111Txt,
+    /// `<stem>.json` holding the image's manifest entry.
+    Json,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -65,7 +300,12 @@ pub struct GalleryFilter {
 pub enum GallerySortField {
     CreatedAt,
     Rating,
+    AestheticScore,
     Random,
+    /// Best full-text match first; newest first when there is no search.
+    Relevance,
+    /// When the image was moved to the trash, for the trash view.
+    DeletedAt,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,250 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+mod settings;
+
+pub use settings::{parse_seed, GenerationSettings};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationRequest {
+    pub positive_prompt: String,
+    pub negative_prompt: String,
+    pub checkpoint: String,
+    pub width: u32,
+    pub height: u32,
+    pub steps: u32,
+    pub cfg_scale: f64,
+    pub sampler: String,
+    pub scheduler: String,
+    pub seed: i64,
+    pub batch_size: u32,
+    /// KSampler denoise strength. 1.0 for txt2img; lower values keep more of
+    /// the init image.
+    #[serde(default = "default_denoise")]
+    pub denoise: f64,
+    /// Gallery filename to refine (img2img). Ignored when denoise is 1.0.
+    #[serde(default)]
+    pub init_image: Option<String>,
+    /// LoRAs applied on top of the checkpoint, in order.
+    #[serde(default)]
+    pub loras: Vec<LoraSpec>,
+    /// Optional second pass at a higher resolution (hires fix).
+    #[serde(default)]
+    pub hires: Option<HiresFix>,
+    /// Optional ControlNet guiding both conditionings from a gallery image.
+    #[serde(default)]
+    pub controlnet: Option<ControlNetSpec>,
+    /// Queue this user-supplied workflow instead of the built-in graph. The
+    /// fields above still provide the prompts, seed and stored metadata.
+    #[serde(default)]
+    pub custom_workflow: Option<CustomWorkflow>,
+}
+
+/// An uploaded ComfyUI workflow in API format. It is queued unchanged
+/// except for the prompts and seed, which are written into the named nodes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomWorkflow {
+    pub prompt_graph: Value,
+    /// Node whose `text` input receives the positive prompt.
+    pub prompt_node_id: String,
+    /// Node whose `text` input receives the negative prompt, if the
+    /// workflow has one.
+    #[serde(default)]
+    pub negative_node_id: Option<String>,
+    /// Node whose `seed` (or `noise_seed`) input receives the seed.
+    pub seed_node_id: String,
+}
+
+/// One LoRA to apply: the file ComfyUI knows it by and its strength on the
+/// diffusion model and the text encoder.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoraSpec {
+    pub filename: String,
+    pub model_weight: f64,
+    pub clip_weight: f64,
+}
+
+/// A hires-fix pass, as in A1111: the first pass's latent is upscaled by
+/// `upscale_by` and sampled again for `steps` at `denoise`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HiresFix {
+    pub upscale_by: f64,
+    pub steps: u32,
+    pub denoise: f64,
+}
+
+/// A ControlNet pass: `model` (as ComfyUI lists it) applied to the positive
+/// and negative conditioning with the gallery file `image` as the hint.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ControlNetSpec {
+    pub image: String,
+    pub model: String,
+    pub strength: f64,
+}
+
+/// Hard upper bound on width and height, whatever `generation.maxDimension` says.
+pub const MAX_DIMENSION: u32 = 4096;
+const MIN_DIMENSION: u32 = 64;
+
+impl GenerationRequest {
+    /// Check every parameter is in range before anything reaches ComfyUI.
+    /// `max_dimension` is the configured cap on width and height.
+    pub fn validate(&self, max_dimension: u32) -> anyhow::Result<()> {
+        if self.checkpoint.is_empty() {
+            anyhow::bail!("Checkpoint is required. Please select a checkpoint before queueing.");
+        }
+        let max_dimension = max_dimension.clamp(MIN_DIMENSION, MAX_DIMENSION);
+        check_dimension("Width", self.width, max_dimension)?;
+        check_dimension("Height", self.height, max_dimension)?;
+        if self.steps < 1 || self.steps > 150 {
+            anyhow::bail!("Steps must be between 1 and 150, got {}", self.steps);
+        }
+        if self.cfg_scale < 0.0 || self.cfg_scale > 30.0 {
+            anyhow::bail!("CFG scale must be between 0 and 30, got {}", self.cfg_scale);
+        }
+        if self.batch_size < 1 || self.batch_size > 16 {
+            anyhow::bail!(
+                "Batch size must be between 1 and 16, got {}",
+                self.batch_size
+            );
+        }
+        if !(0.0..=1.0).contains(&self.denoise) {
+            anyhow::bail!("Denoise must be between 0 and 1, got {}", self.denoise);
+        }
+        if self.loras.iter().any(|l| l.filename.trim().is_empty()) {
+            anyhow::bail!("Every LoRA needs a filename");
+        }
+        if let Some(hires) = &self.hires {
+            if hires.upscale_by.is_nan() || hires.upscale_by <= 1.0 || hires.upscale_by > 4.0 {
+                anyhow::bail!(
+                    "Hires upscale must be above 1 and at most 4, got {}",
+                    hires.upscale_by
+                );
+            }
+            if hires.steps < 1 || hires.steps > 150 {
+                anyhow::bail!("Hires steps must be between 1 and 150, got {}", hires.steps);
+            }
+            if !(0.0..=1.0).contains(&hires.denoise) {
+                anyhow::bail!(
+                    "Hires denoise must be between 0 and 1, got {}",
+                    hires.denoise
+                );
+            }
+            let (width, height) = self.output_size();
+            check_dimension("Upscaled width", width, max_dimension)?;
+            check_dimension("Upscaled height", height, max_dimension)?;
+        }
+        if let Some(controlnet) = &self.controlnet {
+            if controlnet.image.trim().is_empty() || controlnet.model.trim().is_empty() {
+                anyhow::bail!("ControlNet needs both a control image and a model");
+            }
+            if !(0.0..=10.0).contains(&controlnet.strength) {
+                anyhow::bail!(
+                    "ControlNet strength must be between 0 and 10, got {}",
+                    controlnet.strength
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Pixel size of the saved image: width × height, or the upscaled size
+    /// when a hires pass runs. ComfyUI scales the 1/8-size latent and rounds,
+    /// so the result is always a multiple of 8.
+    pub fn output_size(&self) -> (u32, u32) {
+        match &self.hires {
+            Some(hires) => (
+                upscaled_dimension(self.width, hires.upscale_by),
+                upscaled_dimension(self.height, hires.upscale_by),
+            ),
+            None => (self.width, self.height),
+        }
+    }
+
+    /// Rough compute units for capacity planning: steps × width × height ×
+    /// batch, plus the same for a hires pass at its upscaled size.
+    pub fn compute_cost(&self) -> i64 {
+        let mut cost = self.steps as i64 * self.width as i64 * self.height as i64;
+        if let Some(hires) = &self.hires {
+            let (width, height) = self.output_size();
+            cost += hires.steps as i64 * width as i64 * height as i64;
+        }
+        cost * self.batch_size as i64
+    }
+}
+
+fn upscaled_dimension(px: u32, upscale_by: f64) -> u32 {
+    ((px / 8) as f64 * upscale_by).round() as u32 * 8
+}
+
+fn check_dimension(name: &str, value: u32, max_dimension: u32) -> anyhow::Result<()> {
+    if value < MIN_DIMENSION {
+        anyhow::bail!(
+            "{} must be at least {} px, got {}",
+            name,
+            MIN_DIMENSION,
+            value
+        );
+    }
+    if value > max_dimension {
+        anyhow::bail!(
+            "{} of {} px exceeds the maximum image dimension of {} px (generation.maxDimension)",
+            name,
+            value,
+            max_dimension
+        );
+    }
+    Ok(())
+}
+
+/// A sparse set of generation parameters. Only the fields that are `Some`
+/// are applied on top of an existing image's settings.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PartialGenerationRequest {
+    pub positive_prompt: Option<String>,
+    pub negative_prompt: Option<String>,
+    pub checkpoint: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub steps: Option<u32>,
+    pub cfg_scale: Option<f64>,
+    pub sampler: Option<String>,
+    pub scheduler: Option<String>,
+    pub seed: Option<i64>,
+    pub denoise: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum GenerationStatusKind {
+    Queued,
+    Generating,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationStatus {
+    pub prompt_id: String,
+    pub status: GenerationStatusKind,
+    pub progress: Option<f64>,
+    pub current_step: Option<u32>,
+    pub total_steps: Option<u32>,
+    pub image_filenames: Option<Vec<String>>,
+    pub error: Option<String>,
+    /// Seed the job was submitted with (a `-1` request resolved to the
+    /// random value). Only set when queuing; status polls leave it empty.
+    #[serde(default)]
+    pub seed: Option<i64>,
+}
+
+fn default_denoise() -> f64 {
+    1.0
+}
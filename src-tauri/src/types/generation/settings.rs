@@ -0,0 +1,212 @@
+use anyhow::Context;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+
+use super::{
+    default_denoise, ControlNetSpec, CustomWorkflow, GenerationRequest, HiresFix, LoraSpec,
+    PartialGenerationRequest,
+};
+
+/// Typed representation of the settings_json stored in QueueJob.
+/// Written in camelCase; snake_case field names are accepted via serde aliases.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationSettings {
+    pub checkpoint: String,
+
+    #[serde(default = "default_width")]
+    pub width: u32,
+
+    #[serde(default = "default_height")]
+    pub height: u32,
+
+    #[serde(default = "default_steps")]
+    pub steps: u32,
+
+    #[serde(alias = "cfg_scale", default = "default_cfg")]
+    pub cfg_scale: f64,
+
+    #[serde(default = "default_sampler")]
+    pub sampler: String,
+
+    #[serde(default = "default_scheduler")]
+    pub scheduler: String,
+
+    #[serde(default = "default_seed", deserialize_with = "deserialize_seed")]
+    pub seed: i64,
+
+    #[serde(alias = "batch_size", default = "default_batch_size")]
+    pub batch_size: u32,
+
+    #[serde(default = "default_denoise")]
+    pub denoise: f64,
+
+    #[serde(alias = "init_image", default, skip_serializing_if = "Option::is_none")]
+    pub init_image: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub loras: Vec<LoraSpec>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hires: Option<HiresFix>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub controlnet: Option<ControlNetSpec>,
+
+    #[serde(
+        alias = "custom_workflow",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub custom_workflow: Option<CustomWorkflow>,
+}
+
+impl GenerationSettings {
+    /// The queue's defaults for everything but the checkpoint.
+    pub fn for_checkpoint(checkpoint: impl Into<String>) -> Self {
+        Self {
+            checkpoint: checkpoint.into(),
+            width: default_width(),
+            height: default_height(),
+            steps: default_steps(),
+            cfg_scale: default_cfg(),
+            sampler: default_sampler(),
+            scheduler: default_scheduler(),
+            seed: default_seed(),
+            batch_size: default_batch_size(),
+            denoise: default_denoise(),
+            init_image: None,
+            loras: Vec::new(),
+            hires: None,
+            controlnet: None,
+            custom_workflow: None,
+        }
+    }
+
+    /// Overwrite the sampling parameters `overrides` sets. Prompts and the
+    /// checkpoint are left to the caller.
+    pub fn apply(&mut self, overrides: &PartialGenerationRequest) {
+        self.width = overrides.width.unwrap_or(self.width);
+        self.height = overrides.height.unwrap_or(self.height);
+        self.steps = overrides.steps.unwrap_or(self.steps);
+        self.cfg_scale = overrides.cfg_scale.unwrap_or(self.cfg_scale);
+        if let Some(sampler) = &overrides.sampler {
+            self.sampler = sampler.clone();
+        }
+        if let Some(scheduler) = &overrides.scheduler {
+            self.scheduler = scheduler.clone();
+        }
+        self.seed = overrides.seed.unwrap_or(self.seed);
+        self.denoise = overrides.denoise.unwrap_or(self.denoise);
+    }
+
+    /// Serialize for a queue job's `settings_json`.
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        serde_json::to_string(self).context("Failed to serialize generation settings")
+    }
+
+    /// The full request these settings describe, with the given prompts.
+    pub fn into_request(
+        self,
+        positive_prompt: String,
+        negative_prompt: String,
+    ) -> GenerationRequest {
+        GenerationRequest {
+            positive_prompt,
+            negative_prompt,
+            checkpoint: self.checkpoint,
+            width: self.width,
+            height: self.height,
+            steps: self.steps,
+            cfg_scale: self.cfg_scale,
+            sampler: self.sampler,
+            scheduler: self.scheduler,
+            seed: self.seed,
+            batch_size: self.batch_size,
+            denoise: self.denoise,
+            init_image: self.init_image,
+            loras: self.loras,
+            hires: self.hires,
+            controlnet: self.controlnet,
+            custom_workflow: self.custom_workflow,
+        }
+    }
+}
+
+impl From<&GenerationRequest> for GenerationSettings {
+    fn from(request: &GenerationRequest) -> Self {
+        Self {
+            checkpoint: request.checkpoint.clone(),
+            width: request.width,
+            height: request.height,
+            steps: request.steps,
+            cfg_scale: request.cfg_scale,
+            sampler: request.sampler.clone(),
+            scheduler: request.scheduler.clone(),
+            seed: request.seed,
+            batch_size: request.batch_size,
+            denoise: request.denoise,
+            init_image: request.init_image.clone(),
+            loras: request.loras.clone(),
+            hires: request.hires,
+            controlnet: request.controlnet.clone(),
+            custom_workflow: request.custom_workflow.clone(),
+        }
+    }
+}
+
+fn default_width() -> u32 {
+    512
+}
+fn default_height() -> u32 {
+    768
+}
+fn default_steps() -> u32 {
+    25
+}
+fn default_cfg() -> f64 {
+    7.5
+}
+fn default_sampler() -> String {
+    "dpmpp_2m".to_string()
+}
+fn default_scheduler() -> String {
+    "karras".to_string()
+}
+fn default_seed() -> i64 {
+    -1
+}
+fn default_batch_size() -> u32 {
+    1
+}
+
+fn deserialize_seed<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+    Ok(parse_seed(&Value::deserialize(deserializer)?))
+}
+
+/// Parse a seed given as a JSON integer or as a string holding a decimal or
+/// `0x`-prefixed hex number. -1 stays -1 (random); unparseable input also
+/// falls back to random. Any other value is masked to 63 bits, so a seed
+/// pasted as hex, unsigned or negative decimal always maps to the same
+/// non-negative seed that ComfyUI accepts.
+pub fn parse_seed(value: &Value) -> i64 {
+    let raw = match value {
+        Value::Number(n) => n.as_i64().or_else(|| n.as_u64().map(|u| u as i64)),
+        Value::String(s) => {
+            let s = s.trim();
+            match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+                Some(hex) => u64::from_str_radix(hex, 16).ok().map(|u| u as i64),
+                None => s
+                    .parse::<i64>()
+                    .ok()
+                    .or_else(|| s.parse::<u64>().ok().map(|u| u as i64)),
+            }
+        }
+        _ => None,
+    };
+
+    match raw {
+        Some(-1) | None => -1,
+        Some(seed) => seed & i64::MAX,
+    }
+}
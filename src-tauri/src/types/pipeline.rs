@@ -23,15 +23,44 @@ pub struct PipelineStageCompleteEvent {
     pub duration_ms: u64,
 }
 
+/// Emitted after each Reviewer pass as `pipeline:review_iteration`. When
+/// the verdict is a rejection with iterations left, the Prompt Engineer
+/// runs again next.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PipelineReviewIterationEvent {
+    pub iteration: u32,
+    pub max_iterations: u32,
+    pub approved: bool,
+    pub issues: Vec<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PipelineResult {
+    /// Unique per run; copied onto queue jobs and images built from it.
+    #[serde(default)]
+    pub run_id: Option<String>,
     pub original_idea: String,
     pub pipeline_config: PipelineConfig,
     pub stages: PipelineStages,
     pub user_edits: Option<UserEdits>,
     pub auto_approved: bool,
     pub generation_settings: Option<GenerationSettings>,
+    /// Totals over the recorded stages; absent in logs from older versions.
+    #[serde(default)]
+    pub summary: Option<PipelineSummary>,
+}
+
+/// Time and tokens summed over a run's recorded stage outputs. Judge and
+/// Reviewer don't report token counts, so they only add to the duration.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PipelineSummary {
+    pub total_duration_ms: u64,
+    pub total_tokens_in: u64,
+    pub total_tokens_out: u64,
+    pub stage_count: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -49,6 +78,18 @@ pub struct ModelsUsed {
     pub judge: Option<String>,
     pub prompt_engineer: Option<String>,
     pub reviewer: Option<String>,
+    /// Stages whose configured model wasn't installed and ran on the
+    /// fallback model instead.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub substitutions: Vec<ModelSubstitution>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelSubstitution {
+    pub stage: String,
+    pub configured: String,
+    pub used: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -61,6 +102,52 @@ pub struct PipelineStages {
     pub reviewer: Option<ReviewerOutput>,
 }
 
+impl PipelineStages {
+    pub fn summary(&self) -> PipelineSummary {
+        let stages = [
+            self.ideator
+                .as_ref()
+                .map(|s| (s.duration_ms, s.tokens_in, s.tokens_out)),
+            self.composer
+                .as_ref()
+                .map(|s| (s.duration_ms, s.tokens_in, s.tokens_out)),
+            self.judge.as_ref().map(|s| (s.duration_ms, None, None)),
+            self.prompt_engineer
+                .as_ref()
+                .map(|s| (s.duration_ms, s.tokens_in, s.tokens_out)),
+            self.reviewer.as_ref().map(|s| (s.duration_ms, None, None)),
+        ];
+        stages.into_iter().flatten().fold(
+            PipelineSummary::default(),
+            |sum, (ms, tokens_in, tokens_out)| PipelineSummary {
+                total_duration_ms: sum.total_duration_ms + ms,
+                total_tokens_in: sum.total_tokens_in + tokens_in.unwrap_or(0),
+                total_tokens_out: sum.total_tokens_out + tokens_out.unwrap_or(0),
+                stage_count: sum.stage_count + 1,
+            },
+        )
+    }
+
+    /// Drop the raw model responses, keeping only the parsed outputs.
+    pub fn clear_raw_responses(&mut self) {
+        if let Some(stage) = self.ideator.as_mut() {
+            stage.raw_response = None;
+        }
+        if let Some(stage) = self.composer.as_mut() {
+            stage.raw_response = None;
+        }
+        if let Some(stage) = self.judge.as_mut() {
+            stage.raw_response = None;
+        }
+        if let Some(stage) = self.prompt_engineer.as_mut() {
+            stage.raw_response = None;
+        }
+        if let Some(stage) = self.reviewer.as_mut() {
+            stage.raw_response = None;
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct IdeatorOutput {
@@ -70,6 +157,23 @@ pub struct IdeatorOutput {
     pub model: String,
     pub tokens_in: Option<u64>,
     pub tokens_out: Option<u64>,
+    /// Set when the numbered-list response could not be parsed and a fallback
+    /// produced the concepts instead.
+    #[serde(default)]
+    pub fallback: Option<IdeatorFallback>,
+    /// Unparsed model response, kept when `pipeline.capture_raw` is on.
+    #[serde(default)]
+    pub raw_response: Option<String>,
+}
+
+/// How the Ideator recovered from an unparseable response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IdeatorFallback {
+    /// A second request in JSON mode returned usable concepts.
+    JsonRetry,
+    /// Both attempts failed; the raw idea was used as the only concept.
+    RawIdea,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -82,6 +186,8 @@ pub struct ComposerOutput {
     pub model: String,
     pub tokens_in: Option<u64>,
     pub tokens_out: Option<u64>,
+    #[serde(default)]
+    pub raw_response: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -100,6 +206,11 @@ pub struct JudgeOutput {
     pub output: Vec<JudgeRanking>,
     pub duration_ms: u64,
     pub model: String,
+    /// Extra requests needed before the reply parsed as JSON.
+    #[serde(default)]
+    pub parse_retries: u32,
+    #[serde(default)]
+    pub raw_response: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -119,6 +230,11 @@ pub struct PromptEngineerOutput {
     pub model: String,
     pub tokens_in: Option<u64>,
     pub tokens_out: Option<u64>,
+    /// Extra requests needed before the reply parsed as JSON.
+    #[serde(default)]
+    pub parse_retries: u32,
+    #[serde(default)]
+    pub raw_response: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -128,8 +244,16 @@ pub struct ReviewerOutput {
     pub issues: Option<Vec<String>>,
     pub suggested_positive: Option<String>,
     pub suggested_negative: Option<String>,
+    /// 0–100: how faithfully the prompt captures the original idea.
+    #[serde(default)]
+    pub fidelity_score: Option<u32>,
     pub duration_ms: u64,
     pub model: String,
+    /// Extra requests needed before the reply parsed as JSON.
+    #[serde(default)]
+    pub parse_retries: u32,
+    #[serde(default)]
+    pub raw_response: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -137,6 +261,10 @@ pub struct ReviewerOutput {
 pub struct UserEdits {
     pub prompt_edited: bool,
     pub edit_diff: Option<EditDiff>,
+    /// The prompts actually sent to generation when the user changed the
+    /// Prompt Engineer's output; the original stays in `stages`.
+    #[serde(default)]
+    pub edited_prompts: Option<PromptPair>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -160,3 +288,23 @@ pub struct GenerationSettings {
     pub width: u32,
     pub height: u32,
 }
+
+/// A hand-edited final prompt next to the Prompt Engineer output it replaced.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedPrompt {
+    pub id: i64,
+    pub pipeline_run_id: Option<String>,
+    pub original: PromptPair,
+    pub edited: PromptPair,
+    pub created_at: Option<String>,
+}
+
+/// A stage's system prompt as shown in the template editor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptTemplateInfo {
+    pub stage: String,
+    pub default_template: String,
+    pub override_template: Option<String>,
+}
@@ -75,8 +75,29 @@ pub struct QueueJob {
     #[serde(default)]
     pub auto_approved: bool,
     pub linked_comparison_id: Option<String>,
+    /// Jobs queued together (e.g. a seed sweep) share a group ID so they can
+    /// be managed as one.
+    #[serde(default)]
+    pub group_id: Option<String>,
+    /// `PipelineResult::run_id` of the run that produced the prompts.
+    #[serde(default)]
+    pub pipeline_run_id: Option<String>,
+    /// Image this job is a variation of.
+    #[serde(default)]
+    pub parent_image_id: Option<String>,
+    /// Times the job was put back in the queue after failing.
+    #[serde(default)]
+    pub retry_count: u32,
     pub created_at: Option<String>,
     pub started_at: Option<String>,
     pub completed_at: Option<String>,
     pub result_image_id: Option<String>,
 }
+
+/// Jobs enqueued by a variable sweep, sharing one group.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VariableSweep {
+    pub group_id: String,
+    pub job_ids: Vec<String>,
+}
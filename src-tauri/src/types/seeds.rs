@@ -10,6 +10,9 @@ pub struct SeedEntry {
     pub sample_image_id: Option<String>,
     pub created_at: Option<String>,
     pub tags: Option<Vec<String>>,
+    /// Filename of the sample image, joined in on read (ignored on insert).
+    #[serde(default)]
+    pub sample_filename: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -3,6 +3,9 @@ import type {
   CheckpointProfile,
   PromptTerm,
   CheckpointObservation,
+  ProfileSuggestion,
+  GenerationRequest,
+  CheckpointContext,
 } from "../types";
 
 export async function upsertCheckpoint(
@@ -17,6 +20,13 @@ export async function getCheckpoint(
   return invoke("get_checkpoint", { filename });
 }
 
+/** Generation settings pre-filled from the checkpoint's preferences. */
+export async function getCheckpointDefaults(
+  filename: string,
+): Promise<GenerationRequest | null> {
+  return invoke("get_checkpoint_defaults", { filename });
+}
+
 export async function listCheckpointProfiles(): Promise<CheckpointProfile[]> {
   return invoke("list_checkpoint_profiles");
 }
@@ -37,6 +47,13 @@ export async function addCheckpointObservation(
   return invoke("add_checkpoint_observation", { observation });
 }
 
+/** Copy an image's note onto its checkpoint as a user observation. */
+export async function promoteNoteToObservation(
+  imageId: string,
+): Promise<CheckpointObservation> {
+  return invoke("promote_note_to_observation", { imageId });
+}
+
 export async function getCheckpointObservations(
   checkpointId: number,
 ): Promise<CheckpointObservation[]> {
@@ -46,3 +63,17 @@ export async function getCheckpointObservations(
 export async function getCheckpointContext(filename: string): Promise<string> {
   return invoke("get_checkpoint_context", { filename });
 }
+
+/** Proposed strengths/weaknesses from the judge model; not saved until upserted. */
+export async function suggestCheckpointProfile(
+  filename: string,
+): Promise<ProfileSuggestion> {
+  return invoke("suggest_checkpoint_profile", { filename });
+}
+
+/** The checkpoint context exactly as the Prompt Engineer receives it. */
+export async function previewPipelineContext(
+  filename: string,
+): Promise<CheckpointContext> {
+  return invoke("preview_pipeline_context", { filename });
+}
@@ -17,6 +17,14 @@ export async function getComfyuiSchedulers(): Promise<string[]> {
   return invoke("get_comfyui_schedulers");
 }
 
+export async function getComfyuiLoras(): Promise<string[]> {
+  return invoke("get_comfyui_loras");
+}
+
+export async function getComfyuiControlnets(): Promise<string[]> {
+  return invoke("get_comfyui_controlnets");
+}
+
 export async function queueGeneration(
   request: GenerationRequest,
 ): Promise<GenerationStatus> {
@@ -38,6 +46,40 @@ export async function getComfyuiQueueStatus(): Promise<QueueStatus> {
   return invoke("get_comfyui_queue_status");
 }
 
+/** GPU and runtime details; device fields describe the first device. */
+export interface SystemStats {
+  deviceName?: string | null;
+  deviceType?: string | null;
+  /** Bytes. */
+  vramTotal?: number | null;
+  vramFree?: number | null;
+  comfyuiVersion?: string | null;
+  pytorchVersion?: string | null;
+  cudaVersion?: string | null;
+}
+
+export async function getComfyuiSystemStats(): Promise<SystemStats> {
+  return invoke("get_comfyui_system_stats");
+}
+
+export type FitVerdict = "likelyFits" | "risky" | "wontFit" | "unknown";
+
+export interface FitEstimate {
+  verdict: FitVerdict;
+  family: "sd15" | "sdxl";
+  /** Bytes. */
+  estimatedBytes: number;
+  vramFree?: number | null;
+  vramTotal?: number | null;
+}
+
+/** Rough check of whether a request fits in ComfyUI's free VRAM. */
+export async function estimateFit(
+  request: GenerationRequest,
+): Promise<FitEstimate> {
+  return invoke("estimate_fit", { request });
+}
+
 export async function freeComfyuiMemory(
   unloadModels: boolean,
 ): Promise<void> {
@@ -47,3 +89,14 @@ export async function freeComfyuiMemory(
 export async function interruptComfyui(): Promise<void> {
   return invoke("interrupt_comfyui");
 }
+
+export interface SmokeTestResult {
+  passed: boolean;
+  elapsedMs: number;
+  checkpoint?: string;
+  error?: string;
+}
+
+export async function runComfyuiSmokeTest(): Promise<SmokeTestResult> {
+  return invoke("smoke_test");
+}
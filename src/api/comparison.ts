@@ -1,5 +1,9 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { Comparison } from "../types";
+import type {
+  Comparison,
+  ComparisonSide,
+  GenerationRequest,
+} from "../types";
 
 export async function createComparison(comparison: Comparison): Promise<void> {
   return invoke("create_comparison", { comparison });
@@ -29,3 +33,11 @@ export async function updateComparisonNote(
 export async function deleteComparison(id: string): Promise<void> {
   return invoke("delete_comparison", { id });
 }
+
+/** The chosen side's full generation settings, to continue from. */
+export async function continueFromWinner(
+  comparisonId: string,
+  which: ComparisonSide,
+): Promise<GenerationRequest> {
+  return invoke("continue_from_winner", { comparisonId, which });
+}
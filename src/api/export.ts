@@ -1,16 +1,42 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { GalleryFilter } from "../types";
+import type { GalleryFilter, ProvenanceBundle, SidecarFormat } from "../types";
 
 export async function exportImages(
   imageIds: string[],
   outputPath: string,
+  includeImages = true,
 ): Promise<void> {
-  return invoke("export_images", { imageIds, outputPath });
+  return invoke("export_images", { imageIds, outputPath, includeImages });
 }
 
 export async function exportGallery(
   filter: GalleryFilter,
   outputPath: string,
+  includeImages = true,
 ): Promise<number> {
-  return invoke("export_gallery", { filter, outputPath });
+  return invoke("export_gallery", { filter, outputPath, includeImages });
+}
+
+/**
+ * Copy originals into an existing folder, with an optional `.txt` or `.json`
+ * sidecar per image and a `manifest.csv`. Returns how many were copied.
+ */
+export async function exportFolder(
+  imageIds: string[],
+  outputDir: string,
+  sidecarFormat: SidecarFormat = "none",
+): Promise<number> {
+  return invoke("export_folder", { imageIds, outputDir, sidecarFormat });
+}
+
+/** Everything recorded about how one image was made, for sharing. */
+export async function exportProvenance(
+  imageId: string,
+): Promise<ProvenanceBundle> {
+  return invoke("export_provenance", { imageId });
+}
+
+/** Stop the running export; listen for `export:progress` to track it. */
+export async function cancelExport(): Promise<void> {
+  return invoke("cancel_export");
 }
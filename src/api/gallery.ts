@@ -1,5 +1,17 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { ImageEntry, GalleryFilter } from "../types";
+import type {
+  BatchUpdate,
+  ImageEntry,
+  GalleryFilter,
+  ImageLineage,
+  ImportSummary,
+  NewImages,
+  RecentChoices,
+  TagChangeSummary,
+  TagEntry,
+  TermCount,
+  ThumbnailRegenSummary,
+} from "../types";
 
 export async function getGalleryImages(
   filter: GalleryFilter,
@@ -7,6 +19,42 @@ export async function getGalleryImages(
   return invoke("get_gallery_images", { filter });
 }
 
+/** Images created since `since`, or since the last marked visit. */
+export async function getNewImages(
+  since?: string,
+  limit?: number,
+): Promise<NewImages> {
+  return invoke("get_new_images", { since, limit });
+}
+
+/** Mark the gallery as seen now; returns the stored timestamp. */
+export async function markGallerySeen(): Promise<string> {
+  return invoke("mark_gallery_seen");
+}
+
+/** Checkpoints used by gallery images as [filename, imageCount], most used first. */
+export async function listGalleryCheckpoints(): Promise<[string, number][]> {
+  return invoke("list_gallery_checkpoints");
+}
+
+/** Recently used checkpoints, samplers, schedulers and sizes, newest first. */
+export async function getRecentChoices(): Promise<RecentChoices> {
+  return invoke("get_recent_choices");
+}
+
+/** Most used prompt terms among images matching the filter. */
+export async function getTermFrequencies(
+  filter: GalleryFilter,
+  limit?: number,
+): Promise<TermCount[]> {
+  return invoke("get_term_frequencies", { filter, limit });
+}
+
+/** Copy PNGs made by other tools into the gallery with their metadata. */
+export async function importImages(paths: string[]): Promise<ImportSummary> {
+  return invoke("import_images", { paths });
+}
+
 export async function getImage(id: string): Promise<ImageEntry | null> {
   return invoke("get_image", { id });
 }
@@ -15,6 +63,11 @@ export async function deleteImage(id: string): Promise<void> {
   return invoke("delete_image", { id });
 }
 
+/** Trash many images; unknown ids come back in `notFound`. */
+export async function deleteImages(ids: string[]): Promise<BatchUpdate> {
+  return invoke("delete_images", { ids });
+}
+
 export async function restoreImage(id: string): Promise<void> {
   return invoke("restore_image", { id });
 }
@@ -30,6 +83,29 @@ export async function updateImageRating(
   return invoke("update_image_rating", { id, rating });
 }
 
+/** Give several images the same rating; returns how many were rated. */
+export async function rateRange(
+  ids: string[],
+  rating: number | null,
+): Promise<number> {
+  return invoke("rate_range", { ids, rating });
+}
+
+/** Rate many images; unknown ids come back in `notFound`. */
+export async function rateImages(
+  ids: string[],
+  rating: number | null,
+): Promise<BatchUpdate> {
+  return invoke("rate_images", { ids, rating });
+}
+
+export async function favoriteImages(
+  ids: string[],
+  favorite: boolean,
+): Promise<BatchUpdate> {
+  return invoke("favorite_images", { ids, favorite });
+}
+
 export async function updateImageFavorite(
   id: string,
   favorite: boolean,
@@ -37,6 +113,27 @@ export async function updateImageFavorite(
   return invoke("update_image_favorite", { id, favorite });
 }
 
+export interface QuickTriage {
+  rating?: number;
+  favorite?: boolean;
+  addTags?: string[];
+}
+
+export async function quickTriage(
+  id: string,
+  triage: QuickTriage,
+): Promise<ImageEntry> {
+  return invoke("quick_triage", { id, ...triage });
+}
+
+/** Re-label images with a different checkpoint. Returns the number updated. */
+export async function reattributeCheckpoint(
+  ids: string[],
+  newCheckpoint: string,
+): Promise<number> {
+  return invoke("reattribute_checkpoint", { ids, newCheckpoint });
+}
+
 export async function updateCaption(
   id: string,
   caption: string,
@@ -66,16 +163,84 @@ export async function removeTag(
   return invoke("remove_tag", { imageId, tagId });
 }
 
+/** Rename a tag; with dryRun only reports how many images and seeds it would touch. */
+export async function renameTag(
+  tagId: number,
+  newName: string,
+  dryRun = false,
+): Promise<TagChangeSummary> {
+  return invoke("rename_tag", { tagId, newName, dryRun });
+}
+
+/** Fold one tag into another; with dryRun only reports what would move. */
+export async function mergeTags(
+  sourceTagId: number,
+  targetTagId: number,
+  dryRun = false,
+): Promise<TagChangeSummary> {
+  return invoke("merge_tags", { sourceTagId, targetTagId, dryRun });
+}
+
+/** Images that look like `id`, closest first, with their hash distance. */
+export async function findSimilarImages(
+  id: string,
+  maxDistance?: number,
+): Promise<[ImageEntry, number][]> {
+  return invoke("find_similar_images", { id, maxDistance });
+}
+
+/** Groups of near-identical images across the whole gallery. */
+export async function findDuplicates(
+  maxDistance?: number,
+): Promise<ImageEntry[][]> {
+  return invoke("find_duplicates", { maxDistance });
+}
+
+/** Each tag with its image count, most used first. */
+export async function getTagCounts(
+  includeDeleted = false,
+): Promise<[TagEntry, number][]> {
+  return invoke("get_tag_counts", { includeDeleted });
+}
+
 export async function getImageLineage(
   imageId: string,
 ): Promise<string | null> {
   return invoke("get_image_lineage", { imageId });
 }
 
+/** Variation/img2img ancestry of an image, plus its direct children. */
+export async function getImageDerivation(
+  imageId: string,
+): Promise<ImageLineage> {
+  return invoke("get_image_derivation", { imageId });
+}
+
 export async function getImageFilePath(filename: string): Promise<string> {
   return invoke("get_image_file_path", { filename });
 }
 
+/** Folder containing the image's original, for revealing it in the file manager. */
+export async function getImageDirPath(id: string): Promise<string> {
+  return invoke("get_image_dir_path", { id });
+}
+
 export async function getThumbnailFilePath(filename: string): Promise<string> {
   return invoke("get_thumbnail_file_path", { filename });
 }
+
+/**
+ * Re-render every thumbnail at `size`, or the configured size when omitted,
+ * in the configured format. Progress is emitted as `thumbnails:progress`;
+ * missing originals are skipped.
+ */
+export async function regenerateThumbnails(
+  size?: number,
+): Promise<ThumbnailRegenSummary> {
+  return invoke("regenerate_thumbnails", { size });
+}
+
+/** Reindex gallery search from scratch; returns how many images are indexed. */
+export async function rebuildSearchIndex(): Promise<number> {
+  return invoke("rebuild_search_index");
+}
@@ -1,5 +1,5 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { PipelineResult } from "../types";
+import type { PipelineResult, PromptPair, SavedPrompt } from "../types";
 
 export interface RunPipelineInput {
   idea: string;
@@ -28,6 +28,14 @@ export async function runPipelineStage(
   return invoke("run_pipeline_stage", { stage, input, model, checkpointContext });
 }
 
+/** Run only the Prompt Engineer over a hand-written prompt, with the checkpoint's profile as context. */
+export async function tunePrompt(
+  prompt: string,
+  checkpointFilename: string,
+): Promise<PromptPair> {
+  return invoke("tune_prompt", { prompt, checkpointFilename });
+}
+
 export async function getAvailableModels(): Promise<string[]> {
   return invoke("get_available_models");
 }
@@ -47,3 +55,22 @@ export async function cancelPipeline(): Promise<void> {
 export async function checkOllamaHealth(): Promise<boolean> {
   return invoke("check_ollama_health");
 }
+
+export async function recordPromptEdits(
+  result: PipelineResult,
+  positivePrompt: string,
+  negativePrompt: string,
+): Promise<PipelineResult> {
+  return invoke("record_prompt_edits", { result, positivePrompt, negativePrompt });
+}
+
+export async function listSavedPrompts(): Promise<SavedPrompt[]> {
+  return invoke("list_saved_prompts");
+}
+
+export async function ideaFromImage(
+  imagePath: string,
+  model?: string,
+): Promise<string> {
+  return invoke("idea_from_image", { imagePath, model });
+}
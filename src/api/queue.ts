@@ -1,8 +1,16 @@
 import { invoke } from "@tauri-apps/api/core";
 import type { QueueJob, QueuePriority } from "../types";
 
-export async function addToQueue(job: QueueJob): Promise<string> {
-  return invoke("add_to_queue", { job });
+/**
+ * Queue a job. `priority` overrides the job's own; `position` places it among
+ * pending jobs of that priority (0 = next) instead of appending.
+ */
+export async function addToQueue(
+  job: QueueJob,
+  priority?: QueuePriority,
+  position?: number,
+): Promise<string> {
+  return invoke("add_to_queue", { job, priority, position });
 }
 
 export async function getQueue(): Promise<QueueJob[]> {
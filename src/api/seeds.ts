@@ -9,6 +9,13 @@ export async function getSeed(id: number): Promise<SeedEntry | null> {
   return invoke("get_seed", { id });
 }
 
+export async function setSeedSampleImage(
+  seedId: number,
+  imageId: string,
+): Promise<void> {
+  return invoke("set_sample_image", { seedId, imageId });
+}
+
 export async function listSeeds(filter: SeedFilter): Promise<SeedEntry[]> {
   return invoke("list_seeds", { filter });
 }
@@ -71,6 +71,7 @@ export function FilterBar({ filter, onFilterChange }: FilterBarProps) {
         <option value="createdAt">Date</option>
         <option value="rating">Rating</option>
         <option value="random">Random</option>
+        <option value="relevance">Relevance</option>
       </select>
 
       <button
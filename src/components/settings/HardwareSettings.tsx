@@ -35,6 +35,19 @@ export function HardwareSettings({ config, onChange }: HardwareSettingsProps) {
             className="mt-1 block w-32 bg-zinc-700 border border-zinc-600 rounded px-3 py-2 text-sm text-zinc-100 focus:border-blue-500 focus:outline-none"
           />
         </label>
+        <label className="flex items-center gap-3 cursor-pointer">
+          <input
+            type="checkbox"
+            checked={hw.adaptiveCooldown ?? false}
+            onChange={() =>
+              updateHw({ adaptiveCooldown: !(hw.adaptiveCooldown ?? false) })
+            }
+            className="w-4 h-4 rounded bg-zinc-700 border-zinc-600 text-blue-500 focus:ring-blue-500 focus:ring-offset-zinc-800"
+          />
+          <span className="text-sm text-zinc-300">
+            Adapt cooldown to free VRAM
+          </span>
+        </label>
         <label className="block">
           <span className="text-sm text-zinc-400">
             Max consecutive generations before forced cooldown
@@ -70,6 +83,27 @@ export function HardwareSettings({ config, onChange }: HardwareSettingsProps) {
           </label>
           {hw.enableHaPowerMonitoring && (
             <div className="space-y-3 pl-7">
+              <label className="block">
+                <span className="text-sm text-zinc-400">HA URL</span>
+                <input
+                  type="text"
+                  value={hw.haUrl ?? ""}
+                  placeholder="http://homeassistant.local:8123"
+                  onChange={(e) => updateHw({ haUrl: e.target.value })}
+                  className="mt-1 block w-full bg-zinc-700 border border-zinc-600 rounded px-3 py-2 text-sm text-zinc-100 focus:border-blue-500 focus:outline-none"
+                />
+              </label>
+              <label className="block">
+                <span className="text-sm text-zinc-400">Access Token</span>
+                <input
+                  type="password"
+                  value={hw.haToken ?? ""}
+                  onChange={(e) =>
+                    updateHw({ haToken: e.target.value || undefined })
+                  }
+                  className="mt-1 block w-full bg-zinc-700 border border-zinc-600 rounded px-3 py-2 text-sm text-zinc-100 focus:border-blue-500 focus:outline-none"
+                />
+              </label>
               <label className="block">
                 <span className="text-sm text-zinc-400">HA Entity ID</span>
                 <input
@@ -126,6 +160,19 @@ export function HardwareSettings({ config, onChange }: HardwareSettingsProps) {
               />
             </label>
           )}
+          <label className="flex items-center gap-3 cursor-pointer mt-3">
+            <input
+              type="checkbox"
+              checked={hw.exclusiveGpu ?? false}
+              onChange={() =>
+                updateHw({ exclusiveGpu: !(hw.exclusiveGpu ?? false) })
+              }
+              className="w-4 h-4 rounded bg-zinc-700 border-zinc-600 text-blue-500 focus:ring-blue-500 focus:ring-offset-zinc-800"
+            />
+            <span className="text-sm text-zinc-300">
+              Don't run AI tagging/captioning during a generation
+            </span>
+          </label>
         </div>
       </div>
     </section>
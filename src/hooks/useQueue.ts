@@ -14,6 +14,21 @@ interface JobEvent {
   jobId: string;
 }
 
+/** Payload of `queue:job_completed`. */
+export interface JobCompletedEvent extends JobEvent {
+  imageId: string;
+  imageIds: string[];
+  /** Resolved seed of the result image, never -1. */
+  seed?: number | null;
+  checkpoint?: string | null;
+}
+
+interface JobPreviewEvent {
+  jobId: string;
+  /** `data:` URL of the latest sampler preview. */
+  dataUrl: string;
+}
+
 interface JobProgressEvent {
   jobId: string;
   currentStep: number;
@@ -26,6 +41,7 @@ export interface JobProgress {
   totalSteps: number;
   progress: number;
   lastUpdate?: number;
+  previewUrl?: string;
 }
 
 export function useQueue() {
@@ -87,7 +103,7 @@ export function useQueue() {
 
     const setup = async () => {
       const u1 = await listen<JobEvent>("queue:job_started", () => refresh());
-      const u2 = await listen<JobEvent>("queue:job_completed", (e) => {
+      const u2 = await listen<JobCompletedEvent>("queue:job_completed", (e) => {
         setProgressMap((prev) => {
           const next = { ...prev };
           delete next[e.payload.jobId];
@@ -103,6 +119,15 @@ export function useQueue() {
         });
         refresh();
       });
+      // A retried job goes back to pending, so its progress starts over
+      const u7 = await listen<JobEvent>("queue:job_retrying", (e) => {
+        setProgressMap((prev) => {
+          const next = { ...prev };
+          delete next[e.payload.jobId];
+          return next;
+        });
+        refresh();
+      });
       const u5 = await listen<JobEvent>("queue:job_cancelled", (e) => {
         setProgressMap((prev) => {
           const next = { ...prev };
@@ -119,15 +144,26 @@ export function useQueue() {
             totalSteps: e.payload.totalSteps,
             progress: e.payload.progress,
             lastUpdate: Date.now(),
+            previewUrl: prev[e.payload.jobId]?.previewUrl,
           },
         }));
       });
+      const u6 = await listen<JobPreviewEvent>("queue:job_preview", (e) => {
+        setProgressMap((prev) => {
+          const current = prev[e.payload.jobId];
+          if (!current) return prev;
+          return {
+            ...prev,
+            [e.payload.jobId]: { ...current, previewUrl: e.payload.dataUrl },
+          };
+        });
+      });
 
       if (cancelled) {
         // Effect was cleaned up before setup finished — tear down immediately
-        [u1, u2, u3, u4, u5].forEach((u) => u());
+        [u1, u2, u3, u4, u5, u6, u7].forEach((u) => u());
       } else {
-        unlisteners.push(u1, u2, u3, u4, u5);
+        unlisteners.push(u1, u2, u3, u4, u5, u6, u7);
       }
     };
 
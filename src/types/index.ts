@@ -2,13 +2,29 @@
 // Pipeline Types
 // ============================================
 
+export interface PromptTemplateInfo {
+  stage: "ideator" | "composer" | "judge" | "prompt_engineer" | "reviewer";
+  defaultTemplate: string;
+  overrideTemplate?: string | null;
+}
+
 export interface PipelineResult {
+  runId?: string;
   originalIdea: string;
   pipelineConfig: PipelineConfig;
   stages: PipelineStages;
   userEdits?: UserEdits;
   autoApproved: boolean;
   generationSettings?: GenerationSettings;
+  summary?: PipelineSummary;
+}
+
+/** Totals over the recorded stages. Judge and Reviewer add time only. */
+export interface PipelineSummary {
+  totalDurationMs: number;
+  totalTokensIn: number;
+  totalTokensOut: number;
+  stageCount: number;
 }
 
 export interface PipelineConfig {
@@ -22,6 +38,14 @@ export interface ModelsUsed {
   judge?: string;
   promptEngineer?: string;
   reviewer?: string;
+  /** Stages that ran on the fallback model because theirs wasn't installed. */
+  substitutions?: ModelSubstitution[];
+}
+
+export interface ModelSubstitution {
+  stage: string;
+  configured: string;
+  used: string;
 }
 
 export interface PipelineStages {
@@ -39,6 +63,8 @@ export interface IdeatorOutput {
   model: string;
   tokensIn?: number;
   tokensOut?: number;
+  fallback?: "jsonRetry" | "rawIdea" | null;
+  rawResponse?: string;
 }
 
 export interface ComposerOutput {
@@ -49,6 +75,7 @@ export interface ComposerOutput {
   model: string;
   tokensIn?: number;
   tokensOut?: number;
+  rawResponse?: string;
 }
 
 export interface JudgeRanking {
@@ -63,6 +90,8 @@ export interface JudgeOutput {
   output: JudgeRanking[];
   durationMs: number;
   model: string;
+  parseRetries?: number;
+  rawResponse?: string;
 }
 
 export interface PromptPair {
@@ -78,6 +107,8 @@ export interface PromptEngineerOutput {
   model: string;
   tokensIn?: number;
   tokensOut?: number;
+  parseRetries?: number;
+  rawResponse?: string;
 }
 
 export interface ReviewerOutput {
@@ -85,13 +116,25 @@ export interface ReviewerOutput {
   issues?: string[];
   suggestedPositive?: string;
   suggestedNegative?: string;
+  fidelityScore?: number;
   durationMs: number;
   model: string;
+  parseRetries?: number;
+  rawResponse?: string;
 }
 
 export interface UserEdits {
   promptEdited: boolean;
   editDiff?: EditDiff;
+  editedPrompts?: PromptPair;
+}
+
+export interface SavedPrompt {
+  id: number;
+  pipelineRunId?: string;
+  original: PromptPair;
+  edited: PromptPair;
+  createdAt?: string;
 }
 
 export interface EditDiff {
@@ -144,6 +187,44 @@ export interface GenerationRequest {
   scheduler: string;
   seed: number;
   batchSize: number;
+  denoise?: number;
+  /** Gallery filename to refine (img2img); ignored when denoise is 1. */
+  initImage?: string;
+  loras?: LoraSpec[];
+  /** Second, upscaled sampling pass; the saved image is the upscaled size. */
+  hires?: HiresFix;
+  /** ControlNet applied to both prompts, hinted by a gallery image. */
+  controlnet?: ControlNetSpec;
+  /** Queue this API-format workflow instead of the built-in graph. */
+  customWorkflow?: CustomWorkflow;
+}
+
+export interface CustomWorkflow {
+  promptGraph: Record<string, unknown>;
+  /** Node whose `text` input receives the positive prompt. */
+  promptNodeId: string;
+  negativeNodeId?: string;
+  /** Node whose `seed` or `noise_seed` input receives the seed. */
+  seedNodeId: string;
+}
+
+export interface ControlNetSpec {
+  /** Gallery filename used as the control image. */
+  image: string;
+  model: string;
+  strength: number;
+}
+
+export interface HiresFix {
+  upscaleBy: number;
+  steps: number;
+  denoise: number;
+}
+
+export interface LoraSpec {
+  filename: string;
+  modelWeight: number;
+  clipWeight: number;
 }
 
 export type GenerationStatusKind =
@@ -160,6 +241,8 @@ export interface GenerationStatus {
   totalSteps?: number;
   imageFilenames?: string[];
   error?: string;
+  /** Seed the job was queued with; a -1 request reports the random seed picked. */
+  seed?: number;
 }
 
 // ============================================
@@ -181,6 +264,7 @@ export interface ImageEntry {
   sampler?: string;
   scheduler?: string;
   seed?: number;
+  denoise?: number;
   pipelineLog?: string;
   selectedConcept?: number;
   autoApproved: boolean;
@@ -190,9 +274,40 @@ export interface ImageEntry {
   favorite: boolean;
   deleted: boolean;
   userNote?: string;
+  computeCost?: number;
+  source?: ImageSource;
+  aestheticScore?: number;
+  pipelineRunId?: string;
+  settingsMismatch?: string;
+  parentImageId?: string;
+  /** When the image was moved to the trash. */
+  deletedAt?: string | null;
   tags?: TagEntry[];
 }
 
+/** Outcome of a change applied to many images; unknown ids are listed, not fatal. */
+export interface BatchUpdate {
+  updated: string[];
+  notFound: string[];
+}
+
+/** Images an image was derived from (nearest first) and derived from it. */
+export interface ImageLineage {
+  ancestors: ImageEntry[];
+  children: ImageEntry[];
+}
+
+/** Everything recorded about how one image was made. */
+export interface ProvenanceBundle {
+  image: ImageEntry;
+  pipeline: PipelineResult | null;
+  workflow: Record<string, unknown> | null;
+  tags: TagEntry[];
+  ancestors: ImageEntry[];
+}
+
+export type ImageSource = "pipeline" | "manual" | "imported" | "variation";
+
 export interface TagEntry {
   id: number;
   name: string;
@@ -200,12 +315,91 @@ export interface TagEntry {
   confidence?: number;
 }
 
-export type GallerySortField = "createdAt" | "rating" | "random";
+export interface TagImplication {
+  tagId: number;
+  tagName: string;
+  impliedTagId: number;
+  impliedTagName: string;
+}
+
+export interface TagChangeSummary {
+  images: number;
+  seeds: number;
+}
+
+export interface ImportSummary {
+  imported: string[];
+  failed: { path: string; error: string }[];
+}
+
+export interface RecentChoices {
+  checkpoints: string[];
+  samplers: string[];
+  schedulers: string[];
+  dimensions: { width: number; height: number }[];
+}
+
+export interface TermCount {
+  term: string;
+  count: number;
+}
+
+export interface ImageCaption {
+  id: number | null;
+  imageId: string;
+  caption: string;
+  source: "ai" | "user" | null;
+  createdAt: string | null;
+  isPrimary: boolean;
+}
+
+export interface ThumbnailRegenSummary {
+  total: number;
+  regenerated: number;
+  skippedMissing: number;
+  failed: number;
+  errors?: ThumbnailFailure[];
+}
+
+export interface ThumbnailFailure {
+  filename: string;
+  error: string;
+}
+
+export interface ComfyUiLogEvent {
+  line: string;
+}
+
+/** Per-image file written next to each original in a folder export. */
+export type SidecarFormat = "none" | "a1111Txt" | "json";
+
+/** Emitted as `export:progress` after each image is written. */
+export interface ExportProgressEvent {
+  done: number;
+  total: number;
+}
+
+export interface ThumbnailProgressEvent {
+  filename: string;
+  status: "regenerated" | "missing" | "failed";
+  completed: number;
+  total: number;
+}
+
+export type GallerySortField =
+  | "createdAt"
+  | "rating"
+  | "aestheticScore"
+  | "random"
+  | "relevance"
+  | "deletedAt";
 export type SortOrder = "asc" | "desc";
 
 export interface GalleryFilter {
   search?: string;
   tags?: string[];
+  /** With several tags, require all of them (default) rather than any. */
+  tagMatchAll?: boolean;
   checkpoint?: string;
   minRating?: number;
   favoriteOnly?: boolean;
@@ -217,6 +411,15 @@ export interface GalleryFilter {
   offset?: number;
   untaggedOnly?: boolean;
   uncaptionedOnly?: boolean;
+  source?: ImageSource;
+  /** Only images created after this RFC 3339 timestamp. */
+  since?: string;
+}
+
+export interface NewImages {
+  since?: string | null;
+  count: number;
+  images: ImageEntry[];
 }
 
 // ============================================
@@ -231,6 +434,8 @@ export interface SeedEntry {
   sampleImageId?: string;
   createdAt?: string;
   tags?: string[];
+  /** Filename of the sample image, filled in on read. */
+  sampleFilename?: string;
 }
 
 export interface SeedCheckpointNote {
@@ -265,6 +470,8 @@ export interface CheckpointProfile {
   preferredScheduler?: string;
   optimalResolution?: string;
   notes?: string;
+  sampleImageId?: string;
+  sampleFilename?: string;
 }
 
 export type TermStrength = "strong" | "moderate" | "weak" | "broken";
@@ -285,6 +492,25 @@ export type ObservationSource =
   | "pipelineNote"
   | "autoRating";
 
+export interface ProfileSuggestion {
+  strengths: string[];
+  weaknesses: string[];
+  model: string;
+}
+
+/** Checkpoint profile fields as rendered into the Prompt Engineer prompt. */
+export interface CheckpointContext {
+  checkpointName: string;
+  baseModel: string;
+  strengths: string;
+  weaknesses: string;
+  cfgRangeLow: string;
+  cfgRangeHigh: string;
+  preferredSampler: string;
+  checkpointNotes: string;
+  termList: string;
+}
+
 export interface CheckpointObservation {
   id?: number;
   checkpointId: number;
@@ -307,6 +533,8 @@ export interface Comparison {
   createdAt?: string;
 }
 
+export type ComparisonSide = "A" | "B";
+
 // ============================================
 // Queue Types
 // ============================================
@@ -332,12 +560,22 @@ export interface QueueJob {
   selectedConcept?: number;
   autoApproved?: boolean;
   linkedComparisonId?: string;
+  groupId?: string;
+  pipelineRunId?: string;
+  parentImageId?: string;
+  /** Times the job was put back in the queue after failing. */
+  retryCount?: number;
   createdAt?: string;
   startedAt?: string;
   completedAt?: string;
   resultImageId?: string;
 }
 
+export interface VariableSweep {
+  groupId: string;
+  jobIds: string[];
+}
+
 // ============================================
 // Config Types
 // ============================================
@@ -350,20 +588,68 @@ export interface AppConfig {
   hardware: HardwareSettings;
   presets: Record<string, QualityPreset>;
   storage: StorageSettings;
+  gallery?: GallerySettings;
+  generation?: GenerationLimits;
+  stageTuning?: PipelineStageTuning;
+}
+
+/** Ollama sampling for one pipeline stage; unset keeps the model default. */
+export interface StageSampling {
+  temperature?: number | null;
+  topP?: number | null;
+  topK?: number | null;
+}
+
+export interface PipelineStageTuning {
+  ideator: StageSampling;
+  composer: StageSampling;
+  judge: StageSampling;
+  promptEngineer: StageSampling;
+  reviewer: StageSampling;
 }
 
 export interface StorageSettings {
   imageDirectory: string;
+  embedMetadata?: boolean;
+  backupIntervalHours?: number;
+  backupKeep?: number;
+  /** Longest thumbnail side in px (default 256). */
+  thumbnailSize?: number;
+  thumbnailFormat?: ThumbnailFormat;
+}
+
+export type ThumbnailFormat = "jpeg" | "webp" | "png";
+
+export interface GallerySettings {
+  autoFavoriteRating: number;
+  autoRateFromFidelity?: boolean;
+  safeMode?: boolean;
+  /** Last time the gallery was marked as seen (RFC 3339). */
+  lastSeenAt?: string | null;
+  /** Images per page when a filter sets no limit (default 50). */
+  defaultPageSize?: number;
+  /** Sort when a filter picks none; unset keeps newest/best match first. */
+  defaultSort?: GallerySortField | null;
+  /** Days before trashed images are purged at startup; 0 keeps them. */
+  trashRetentionDays?: number;
 }
 
 export interface ComfyUiConfig {
   endpoint: string;
+  /** Extra attempts when ComfyUI can't be reached; defaults to 3. */
+  maxRetries?: number;
 }
 
 export interface OllamaConfig {
   endpoint: string;
+  /** Chat protocol of the server at `endpoint`; defaults to "ollama". */
+  backend?: LlmBackend;
+  /** Bearer token for OpenAI-compatible servers. */
+  apiKey?: string;
 }
 
+export type LlmBackend = "ollama" | "openAiCompatible";
+
 export interface ModelAssignments {
   ideator: string;
   composer: string;
@@ -372,6 +658,9 @@ export interface ModelAssignments {
   reviewer: string;
   tagger: string;
   captioner: string;
+  embedder?: string;
+  /** Used for a stage whose model isn't installed, instead of failing the run. */
+  fallbackModel?: string;
 
   /** Per-stage thinking mode override. Key = stage name, value = thinking enabled. */
   thinkingOverrides?: Record<string, boolean>;
@@ -387,16 +676,44 @@ export interface PipelineSettings {
   enablePromptEngineer: boolean;
   enableReviewer: boolean;
   autoApprove: boolean;
+  captureRaw?: boolean;
+  reviewerFailMode?: ReviewerFailMode;
+  maxRetries?: number;
+  /** Reviewer passes per run; rejected prompts go back to the Prompt Engineer. */
+  maxReviewIterations?: number;
+  /** Tag each generated image with the tagger model when its job completes. */
+  autoTagOnComplete?: boolean;
+}
+
+/** Payload of `pipeline:review_iteration`, sent after each Reviewer pass. */
+export interface PipelineReviewIterationEvent {
+  iteration: number;
+  maxIterations: number;
+  approved: boolean;
+  issues: string[];
 }
 
+export type ReviewerFailMode = "approveOnError" | "rejectOnError" | "retryOnError";
+
 export interface HardwareSettings {
   cooldownSeconds: number;
+  cooldownJitterSecs?: number;
+  adaptiveCooldown?: boolean;
   maxConsecutiveGenerations: number;
   enableHaPowerMonitoring: boolean;
   haEntityId: string;
   haMaxWatts: number;
+  haUrl?: string;
+  haToken?: string;
   aiBatchDownscale?: boolean;
   aiBatchMaxDimension?: number;
+  thumbnailConcurrency?: number;
+  exclusiveGpu?: boolean;
+}
+
+export interface GenerationLimits {
+  maxDimension: number;
+  maxJobRetries?: number;
 }
 
 export interface QualityPreset {